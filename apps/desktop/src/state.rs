@@ -11,7 +11,7 @@
 //! No more mocks.
 
 use cortex_core::{DeviceCapabilities, TaskQueue};
-use cortex_grid::{LanDiscovery, NodeId, PeerInfo, PeerStore, Discovery};
+use cortex_grid::{LanDiscovery, NodeId, PeerFilter, PeerInfo, PeerStore, Discovery};
 use cortex_inference::{
     DistributedExecutor, DistributedConfig, PipelineRole, PipelineNode, 
 };
@@ -63,6 +63,9 @@ pub struct AppConfig {
     pub contribute_compute: bool,
     pub open_to_internet: bool,
     pub model_id: String,
+    /// Peers accepted from discovery (allow all, allowlist or blocklist)
+    #[serde(default)]
+    pub peer_filter: PeerFilter,
 }
 
 impl Default for AppConfig {
@@ -75,6 +78,7 @@ impl Default for AppConfig {
             contribute_compute: true,
             open_to_internet: false,
             model_id: "Qwen/Qwen2.5-0.5B-Instruct".to_string(),
+            peer_filter: PeerFilter::AllowAll,
         }
     }
 }
@@ -150,6 +154,7 @@ impl AppState {
         let tensor_port = self.config.tensor_port;
         let model_id = self.config.model_id.clone();
         let peer_store = Arc::clone(&self.peer_store);
+        peer_store.read().await.set_filter(self.config.peer_filter.clone()).await;
         
         // 1. Download Model (if missing)
         info!("📥 Checking model weights for {}...", model_id);
@@ -190,14 +195,16 @@ impl AppState {
         let peer_store_clone = Arc::clone(&peer_store);
         tokio::spawn(async move {
             while let Some(event) = discovery_rx.recv().await {
-                info!("🔗 Discovered: {} at {:?}", event.peer_id.short_id(), event.addresses);
+                info!("🔗 Discovered: {} at {:?}", event.peer_id.short(), event.addresses);
                 
                 let mut peer = PeerInfo::new(event.peer_id.clone(), [0u8; 32]);
                 peer.addresses = event.addresses;
                 peer.capabilities.can_compute = true;
                 peer.capabilities.can_relay = true;
                 
-                peer_store_clone.write().await.insert(peer).await;
+                if !peer_store_clone.write().await.insert(peer).await {
+                    info!("🚫 Ignoring {} (not allowed by peer filter)", event.peer_id.short());
+                }
             }
         });
         
@@ -7,6 +7,27 @@
 #include <stdint.h>
 #include <stdbool.h>
 
+// ============ Error Codes ============
+
+// Outcome of the last fallible call on the calling thread. Functions still
+// return their JSON/bool result; check this to branch without parsing it.
+typedef enum {
+    CORTEX_OK = 0,
+    CORTEX_AGENT_NOT_FOUND = 1,
+    CORTEX_AGENT_STOPPED = 2,
+    CORTEX_INVALID_ARG = 3,
+    CORTEX_MODEL_LOAD_FAILED = 4,
+    CORTEX_NULL_ARG = 5,
+    CORTEX_INVALID_UTF8 = 6,
+    CORTEX_INFERENCE_FAILED = 7,
+} CortexErrorCode;
+
+int32_t cortex_last_error(void);
+
+// Required string parameters must not be NULL. With strict mode on, they
+// must also be valid UTF-8; otherwise bad bytes are replaced.
+void cortex_set_strict_utf8(bool strict);
+
 // Initialize CortexOS
 bool cortex_init(void);
 
@@ -27,9 +48,22 @@ char* cortex_start_logger_agent(const char* name);
 // Start a local inference agent
 char* cortex_start_inference_agent(const char* name);
 
+// Start an offline agent running a local GGUF model (tokenizer.json beside it)
+char* cortex_start_local_gguf_agent(const char* name, const char* model_path);
+
+// Same as above with GenerationParams JSON, NULL uses the defaults
+char* cortex_start_local_gguf_agent_with_params(const char* name, const char* model_path, const char* params_json);
+
 // Start a remote inference agent (connects to Ollama, etc.)
 char* cortex_start_remote_inference_agent(const char* name, const char* url, const char* model);
 
+// Start an agent for an OpenAI-compatible server (LM Studio, vLLM, hosted APIs).
+// api_key may be NULL or empty when the server needs no authentication.
+char* cortex_start_openai_inference_agent(const char* name, const char* url, const char* model, const char* api_key);
+
+// Forget an inference agent's conversation history (multi-turn context)
+bool cortex_clear_agent_context(const char* agent_id);
+
 // Start a CoreML inference agent (uses native Apple ML)
 char* cortex_spawn_coreml_agent(const char* name);
 
@@ -52,6 +86,13 @@ bool cortex_remove_agent(const char* agent_id);
 // Export agent's conversation history as JSONL dataset
 char* cortex_export_dataset(const char* agent_id);
 
+// Export history at or above a privacy level (0 = private, 1 = shareable, 2 = public)
+// Non-public entries have emails and long numbers replaced by hashed placeholders
+char* cortex_export_dataset_filtered(const char* agent_id, int32_t min_privacy_level);
+
+// Set the privacy level tagged on an agent's future history entries
+bool cortex_set_agent_privacy(const char* agent_id, int32_t privacy_level);
+
 // ============ Messaging API ============
 
 // Send message to a specific agent
@@ -65,6 +106,13 @@ char* cortex_publish_event(const char* kind, const char* payload);
 // Broadcast discovery to local network
 char* cortex_broadcast_discovery(void);
 
+// Called with {"event":"added"|"removed","peer":{...}} when the peer set changes.
+// Runs on a background thread: copy the string (it is freed on return) and
+// dispatch to the main queue before updating UI. NULL unregisters.
+typedef void (*PeerCallback)(const char* peer_json);
+void cortex_register_peer_callback(PeerCallback callback);
+void cortex_unregister_peer_callback(void);
+
 // ============ Stats API ============
 
 // Get overall stats as JSON
@@ -58,7 +58,7 @@ impl LoggerAgent {
     fn should_log(&self, event: &Event) -> bool {
         self.filter
             .as_ref()
-            .map(|f| f.matches(event))
+            .map(|f| f.matches(&event.kind))
             .unwrap_or(true)
     }
 
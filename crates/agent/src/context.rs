@@ -7,7 +7,7 @@ use crate::intention::IntentionManager;
 use crate::lifecycle::AgentHandle;
 use crate::traits::{Agent, Emitter};
 use crate::types::{
-    Event, EventId, EventPattern, GraphQuery, IntentionId, NodeId, ThoughtContent, ThoughtNode,
+    AgentId, CapabilitySet, Event, EventId, EventPattern, GraphQuery, IntentionId, NodeId, ThoughtContent, ThoughtNode,
     Timestamp,
 };
 
@@ -41,6 +41,45 @@ impl Clone for EventBusHandle {
     }
 }
 
+/// Per-agent inboxes for point-to-point events that bypass the broadcast
+/// bus
+#[derive(Clone, Default)]
+pub struct MailboxRegistry {
+    mailboxes: Arc<RwLock<HashMap<AgentId, mpsc::Sender<Event>>>>,
+}
+
+impl MailboxRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a mailbox for `agent`, replacing any previous one
+    pub async fn register(&self, agent: AgentId, capacity: usize) -> mpsc::Receiver<Event> {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        self.mailboxes.write().await.insert(agent, tx);
+        rx
+    }
+
+    pub async fn unregister(&self, agent: &AgentId) {
+        self.mailboxes.write().await.remove(agent);
+    }
+
+    /// Deliver `event` to `target` only
+    pub async fn send(&self, target: &AgentId, event: Event) -> Result<(), AgentError> {
+        let mailbox = self
+            .mailboxes
+            .read()
+            .await
+            .get(target)
+            .cloned()
+            .ok_or(AgentError::AgentNotFound(*target))?;
+        mailbox
+            .send(event)
+            .await
+            .map_err(|_| AgentError::AgentNotFound(*target))
+    }
+}
+
 pub struct GraphStoreHandle {
     nodes: Arc<RwLock<HashMap<NodeId, ThoughtNode>>>,
 }
@@ -116,7 +155,7 @@ impl Subscription {
     pub async fn next(&mut self) -> Option<Event> {
         loop {
             match self.receiver.recv().await {
-                Ok(event) if self.pattern.matches(&event) => return Some(event),
+                Ok(event) if self.pattern.matches(&event.kind) => return Some(event),
                 Ok(_) => continue,
                 Err(broadcast::error::RecvError::Closed) => return None,
                 Err(broadcast::error::RecvError::Lagged(_)) => continue,
@@ -135,6 +174,9 @@ pub struct AgentContext {
     intentions: IntentionManager,
     emitters: Vec<Box<dyn Emitter>>,
     agent_spawn_tx: mpsc::Sender<Box<dyn Agent>>,
+    capabilities: CapabilitySet,
+    mailboxes: MailboxRegistry,
+    agent_id: Option<AgentId>,
 }
 
 impl AgentContext {
@@ -150,9 +192,31 @@ impl AgentContext {
             intentions,
             emitters: Vec::new(),
             agent_spawn_tx,
+            capabilities: CapabilitySet::new(),
+            mailboxes: MailboxRegistry::new(),
+            agent_id: None,
         }
     }
 
+    /// Agent this context belongs to; stamped as the source of `send_to`
+    /// events
+    pub fn with_agent_id(mut self, agent_id: AgentId) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    pub fn with_mailboxes(mut self, mailboxes: MailboxRegistry) -> Self {
+        self.mailboxes = mailboxes;
+        self
+    }
+
+    /// Capabilities of the agent this context belongs to; they gate
+    /// `subscribe`
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
     pub fn add_emitter(&mut self, emitter: Box<dyn Emitter>) {
         self.emitters.push(emitter);
     }
@@ -169,11 +233,24 @@ impl AgentContext {
         Ok(id)
     }
 
+    /// Subscribe to events matching `pattern`, which must fall under one of
+    /// the agent's subscribe grants
     pub async fn subscribe(&self, pattern: EventPattern) -> Result<Subscription, AgentError> {
+        if !self.capabilities.check_subscribe(&pattern) {
+            return Err(AgentError::CapabilityDenied(format!("subscribe to {}", pattern)));
+        }
         let receiver = self.event_bus.subscribe();
         Ok(Subscription::new(pattern, receiver))
     }
 
+    /// Send `event` to a single agent's mailbox instead of broadcasting it
+    pub async fn send_to(&self, target: &AgentId, mut event: Event) -> Result<(), AgentError> {
+        if let Some(agent_id) = self.agent_id {
+            event.source.get_or_insert(agent_id);
+        }
+        self.mailboxes.send(target, event).await
+    }
+
     pub async fn query_graph(&self, query: GraphQuery) -> Result<Vec<ThoughtNode>, AgentError> {
         Ok(self.graph.query(&query).await)
     }
@@ -207,3 +284,62 @@ impl AgentContext {
         &self.intentions
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(capabilities: CapabilitySet) -> AgentContext {
+        let (spawn_tx, _) = mpsc::channel(1);
+        AgentContext::new(
+            EventBusHandle::new(16),
+            GraphStoreHandle::new(),
+            IntentionManager::new(),
+            spawn_tx,
+        )
+        .with_capabilities(capabilities)
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_checks_capabilities() {
+        let ctx = context(CapabilitySet::new().with_subscribe("grid.**"));
+        assert!(ctx.subscribe(EventPattern::new("grid.task")).await.is_ok());
+        assert!(ctx.subscribe(EventPattern::new("grid.*.done")).await.is_ok());
+        assert!(matches!(
+            ctx.subscribe(EventPattern::new("private.*")).await,
+            Err(AgentError::CapabilityDenied(_))
+        ));
+        // "gr*" would also match kinds outside the grant
+        assert!(ctx.subscribe(EventPattern::new("gr*")).await.is_err());
+        assert!(ctx.subscribe(EventPattern::all()).await.is_err());
+
+        let ctx = context(CapabilitySet::new().with_subscribe("**"));
+        assert!(ctx.subscribe(EventPattern::all()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_bypasses_bus() {
+        let mailboxes = MailboxRegistry::new();
+        let (sender, target, bystander) = (AgentId::new(), AgentId::new(), AgentId::new());
+        let mut inbox = mailboxes.register(target, 4).await;
+        let mut other_inbox = mailboxes.register(bystander, 4).await;
+
+        let ctx = context(CapabilitySet::new())
+            .with_agent_id(sender)
+            .with_mailboxes(mailboxes.clone());
+        let mut bus = ctx.event_bus().subscribe();
+
+        ctx.send_to(&target, Event::new("plan.step", b"go".to_vec())).await.unwrap();
+        let event = inbox.recv().await.unwrap();
+        assert_eq!(event.kind, "plan.step");
+        assert_eq!(event.source, Some(sender));
+        assert!(other_inbox.try_recv().is_err());
+        assert!(bus.try_recv().is_err());
+
+        mailboxes.unregister(&target).await;
+        assert!(matches!(
+            ctx.send_to(&target, Event::new("plan.step", vec![])).await,
+            Err(AgentError::AgentNotFound(_))
+        ));
+    }
+}
@@ -52,6 +52,14 @@ pub enum AgentError {
     #[error("Subscription error: {0}")]
     SubscriptionError(String),
 
+    /// Shutdown dependencies would form a cycle
+    #[error("Dependency cycle: {0}")]
+    DependencyCycle(String),
+
+    /// Agent lacks the capability for the requested operation
+    #[error("Capability denied: {0}")]
+    CapabilityDenied(String),
+
     /// Internal agent framework error
     #[error("Internal error: {0}")]
     Internal(String),
@@ -6,7 +6,7 @@ pub mod lifecycle;
 pub mod traits;
 pub mod types;
 
-pub use context::{AgentContext, EventBusHandle, GraphStoreHandle, Subscription};
+pub use context::{AgentContext, EventBusHandle, GraphStoreHandle, MailboxRegistry, Subscription};
 pub use error::{AgentError, IntentionError};
 pub use intention::{Intention, IntentionManager, IntentionStatus};
 pub use lifecycle::{AgentHandle, AgentManager, AgentManagerConfig, AgentState};
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -6,11 +6,11 @@ use tokio::sync::{mpsc, oneshot, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-use crate::context::{AgentContext, EventBusHandle, GraphStoreHandle};
+use crate::context::{AgentContext, EventBusHandle, GraphStoreHandle, MailboxRegistry};
 use crate::error::AgentError;
 use crate::intention::IntentionManager;
 use crate::traits::Agent;
-use crate::types::AgentId;
+use crate::types::{AgentId, Event};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AgentState {
@@ -70,6 +70,10 @@ pub struct AgentManagerConfig {
     pub tick_interval: Duration,
     pub event_bus_capacity: usize,
     pub spawn_channel_capacity: usize,
+    /// Queued direct messages per agent before `send_to` waits
+    pub mailbox_capacity: usize,
+    /// How long `stop_agent` waits for each agent to shut down
+    pub shutdown_timeout: Duration,
 }
 
 impl Default for AgentManagerConfig {
@@ -78,6 +82,8 @@ impl Default for AgentManagerConfig {
             tick_interval: Duration::from_secs(1),
             event_bus_capacity: 1024,
             spawn_channel_capacity: 64,
+            mailbox_capacity: 256,
+            shutdown_timeout: Duration::from_secs(5),
         }
     }
 }
@@ -87,6 +93,9 @@ pub struct AgentManager {
     event_bus: EventBusHandle,
     graph: GraphStoreHandle,
     intentions: IntentionManager,
+    mailboxes: MailboxRegistry,
+    /// agent -> agents that must outlive it during shutdown
+    dependencies: Arc<RwLock<HashMap<AgentId, HashSet<AgentId>>>>,
     config: AgentManagerConfig,
     spawn_tx: mpsc::Sender<Box<dyn Agent>>,
     spawn_rx: Arc<RwLock<Option<mpsc::Receiver<Box<dyn Agent>>>>>,
@@ -101,6 +110,8 @@ impl AgentManager {
             event_bus: EventBusHandle::new(config.event_bus_capacity),
             graph: GraphStoreHandle::new(),
             intentions: IntentionManager::new(),
+            mailboxes: MailboxRegistry::new(),
+            dependencies: Arc::new(RwLock::new(HashMap::new())),
             config,
             spawn_tx,
             spawn_rx: Arc::new(RwLock::new(Some(spawn_rx))),
@@ -123,6 +134,11 @@ impl AgentManager {
         &self.intentions
     }
 
+    /// Deliver `event` to one agent without broadcasting it on the event bus
+    pub async fn send_to(&self, target: &AgentId, event: Event) -> Result<(), AgentError> {
+        self.mailboxes.send(target, event).await
+    }
+
     pub async fn start_agent(&self, mut agent: Box<dyn Agent>) -> Result<AgentId, AgentError> {
         let agent_id = *agent.id();
         let agent_name = agent.name().to_string();
@@ -149,6 +165,8 @@ impl AgentManager {
         let intentions = self.intentions.clone();
         let spawn_tx = self.spawn_tx.clone();
         let tick_interval = self.config.tick_interval;
+        let mailboxes = self.mailboxes.clone();
+        let mailbox = mailboxes.register(agent_id, self.config.mailbox_capacity).await;
 
         let task = tokio::spawn(async move {
             let result = run_agent_loop(
@@ -157,11 +175,14 @@ impl AgentManager {
                 graph,
                 intentions,
                 spawn_tx,
+                mailboxes.clone(),
+                mailbox,
                 stop_rx,
                 tick_interval,
                 Arc::clone(&state),
             )
             .await;
+            mailboxes.unregister(&agent_id).await;
 
             if let Err(e) = result {
                 error!(agent_id = %agent_id, error = %e, "Agent failed");
@@ -189,8 +210,9 @@ impl AgentManager {
         info!(agent_id = %agent_id, "Stopping agent");
 
         running.handle.request_stop();
+        self.forget_dependencies(agent_id).await;
 
-        match tokio::time::timeout(Duration::from_secs(5), running.task).await {
+        match tokio::time::timeout(self.config.shutdown_timeout, running.task).await {
             Ok(Ok(())) => {
                 self.intentions.unregister_agent(agent_id).await;
                 Ok(())
@@ -233,8 +255,74 @@ impl AgentManager {
         self.agents.read().await.len()
     }
 
+    /// Declare that `agent` needs each of `others` running until it has
+    /// shut down, e.g. a producer depends on the relay it flushes to.
+    /// Fails without changing anything if this would create a cycle.
+    pub async fn depends_on(&self, agent: AgentId, others: &[AgentId]) -> Result<(), AgentError> {
+        let mut dependencies = self.dependencies.write().await;
+        for other in others {
+            if let Some(path) = dependency_path(&dependencies, *other, agent) {
+                let cycle: Vec<String> = std::iter::once(agent)
+                    .chain(path)
+                    .map(|id| id.to_string())
+                    .collect();
+                return Err(AgentError::DependencyCycle(cycle.join(" -> ")));
+            }
+        }
+        dependencies.entry(agent).or_default().extend(others.iter().copied());
+        Ok(())
+    }
+
+    /// Running agents in the order `stop_all` stops them: every agent comes
+    /// before the agents it depends on. Ties are broken by id.
+    pub async fn shutdown_order(&self) -> Result<Vec<AgentId>, AgentError> {
+        let running: HashSet<AgentId> = self.agents.read().await.keys().copied().collect();
+        let dependencies = self.dependencies.read().await;
+
+        // Dependents still running, per agent
+        let mut dependents: HashMap<AgentId, usize> = running.iter().map(|id| (*id, 0)).collect();
+        for (agent, deps) in dependencies.iter().filter(|(agent, _)| running.contains(agent)) {
+            for dep in deps.iter().filter(|dep| running.contains(dep) && *dep != agent) {
+                *dependents.entry(*dep).or_default() += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(running.len());
+        let mut ready: Vec<AgentId> = Vec::new();
+        loop {
+            ready.extend(dependents.iter().filter(|(_, n)| **n == 0).map(|(id, _)| *id));
+            if ready.is_empty() {
+                break;
+            }
+            ready.sort_by_key(|id| std::cmp::Reverse(id.0));
+            let next = ready.pop().expect("ready is not empty");
+            ready.clear();
+            dependents.remove(&next);
+            for dep in dependencies.get(&next).into_iter().flatten() {
+                if let Some(n) = dependents.get_mut(dep) {
+                    *n = n.saturating_sub(1);
+                }
+            }
+            order.push(next);
+        }
+
+        if !dependents.is_empty() {
+            let stuck: Vec<String> = dependents.keys().map(|id| id.to_string()).collect();
+            return Err(AgentError::DependencyCycle(stuck.join(", ")));
+        }
+        Ok(order)
+    }
+
+    /// Stop every agent, dependents before their dependencies, each with
+    /// its own `shutdown_timeout`
     pub async fn stop_all(&self) -> Vec<Result<(), AgentError>> {
-        let agent_ids: Vec<_> = self.agents.read().await.keys().copied().collect();
+        let agent_ids = match self.shutdown_order().await {
+            Ok(order) => order,
+            Err(e) => {
+                error!(error = %e, "Ignoring shutdown dependencies");
+                self.agents.read().await.keys().copied().collect()
+            }
+        };
         let mut results = Vec::with_capacity(agent_ids.len());
 
         for id in agent_ids {
@@ -244,6 +332,14 @@ impl AgentManager {
         results
     }
 
+    async fn forget_dependencies(&self, agent_id: &AgentId) {
+        let mut dependencies = self.dependencies.write().await;
+        dependencies.remove(agent_id);
+        for deps in dependencies.values_mut() {
+            deps.remove(agent_id);
+        }
+    }
+
     pub async fn run_spawn_listener(&self) {
         let mut rx = self.spawn_rx.write().await.take();
 
@@ -257,17 +353,48 @@ impl AgentManager {
     }
 }
 
+/// Dependency chain from `from` to `to`, if one exists
+fn dependency_path(
+    dependencies: &HashMap<AgentId, HashSet<AgentId>>,
+    from: AgentId,
+    to: AgentId,
+) -> Option<Vec<AgentId>> {
+    let mut stack = vec![vec![from]];
+    let mut seen = HashSet::new();
+    while let Some(path) = stack.pop() {
+        let last = *path.last().expect("paths are never empty");
+        if last == to {
+            return Some(path);
+        }
+        if !seen.insert(last) {
+            continue;
+        }
+        for next in dependencies.get(&last).into_iter().flatten() {
+            let mut longer = path.clone();
+            longer.push(*next);
+            stack.push(longer);
+        }
+    }
+    None
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn run_agent_loop(
     agent: &mut dyn Agent,
     event_bus: EventBusHandle,
     graph: GraphStoreHandle,
     intentions: IntentionManager,
     spawn_tx: mpsc::Sender<Box<dyn Agent>>,
+    mailboxes: MailboxRegistry,
+    mut mailbox: mpsc::Receiver<Event>,
     mut stop_rx: oneshot::Receiver<()>,
     tick_interval: Duration,
     state: Arc<RwLock<AgentState>>,
 ) -> Result<(), AgentError> {
-    let mut ctx = AgentContext::new(event_bus.clone(), graph, intentions, spawn_tx);
+    let mut ctx = AgentContext::new(event_bus.clone(), graph, intentions, spawn_tx)
+        .with_capabilities(agent.capabilities().clone())
+        .with_agent_id(*agent.id())
+        .with_mailboxes(mailboxes);
 
     agent.init(&mut ctx).await?;
     *state.write().await = AgentState::Running;
@@ -291,6 +418,12 @@ async fn run_agent_loop(
                 }
             }
 
+            Some(event) = mailbox.recv() => {
+                if let Err(e) = agent.on_event(&event, &mut ctx).await {
+                    warn!(agent_id = %agent.id(), error = %e, "Direct message handling error");
+                }
+            }
+
             event_result = event_rx.recv() => {
                 match event_result {
                     Ok(event) => {
@@ -311,3 +444,84 @@ async fn run_agent_loop(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CapabilitySet, Event};
+    use async_trait::async_trait;
+
+    struct RecordingAgent {
+        id: AgentId,
+        name: String,
+        capabilities: CapabilitySet,
+        stopped: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Agent for RecordingAgent {
+        fn id(&self) -> &AgentId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn capabilities(&self) -> &CapabilitySet {
+            &self.capabilities
+        }
+
+        async fn init(&mut self, _ctx: &mut AgentContext) -> Result<(), AgentError> {
+            Ok(())
+        }
+
+        async fn on_event(&mut self, _event: &Event, _ctx: &mut AgentContext) -> Result<(), AgentError> {
+            Ok(())
+        }
+
+        async fn tick(&mut self, _ctx: &mut AgentContext) -> Result<(), AgentError> {
+            Ok(())
+        }
+
+        async fn shutdown(&mut self, _ctx: &mut AgentContext) -> Result<(), AgentError> {
+            self.stopped.lock().unwrap().push(self.name.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_all_respects_dependencies() {
+        let manager = AgentManager::with_defaults();
+        let stopped = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let mut ids = HashMap::new();
+        for name in ["relay", "producer", "sensor", "idle"] {
+            let agent = RecordingAgent {
+                id: AgentId::new(),
+                name: name.to_string(),
+                capabilities: CapabilitySet::new(),
+                stopped: Arc::clone(&stopped),
+            };
+            ids.insert(name, manager.start_agent(Box::new(agent)).await.unwrap());
+        }
+
+        // sensor -> producer -> relay
+        manager.depends_on(ids["producer"], &[ids["relay"]]).await.unwrap();
+        manager.depends_on(ids["sensor"], &[ids["producer"]]).await.unwrap();
+        assert!(matches!(
+            manager.depends_on(ids["relay"], &[ids["sensor"]]).await,
+            Err(AgentError::DependencyCycle(_))
+        ));
+
+        let results = manager.stop_all().await;
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(manager.running_count().await, 0);
+
+        let stopped = stopped.lock().unwrap().clone();
+        let position = |name: &str| stopped.iter().position(|n| n == name).unwrap();
+        assert_eq!(stopped.len(), 4);
+        assert!(position("sensor") < position("producer"));
+        assert!(position("producer") < position("relay"));
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::HashSet;
 use std::fmt;
 use uuid::Uuid;
 
+pub use cortex_core::event::EventPattern;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AgentId(pub Uuid);
 
@@ -100,6 +102,10 @@ impl Capability {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CapabilitySet {
     capabilities: HashSet<String>,
+    /// Event kind patterns the agent may subscribe to; `**` grants every
+    /// event
+    #[serde(default)]
+    subscribe: HashSet<EventPattern>,
 }
 
 impl CapabilitySet {
@@ -123,6 +129,20 @@ impl CapabilitySet {
     pub fn iter(&self) -> impl Iterator<Item = &String> {
         self.capabilities.iter()
     }
+
+    pub fn with_subscribe(mut self, pattern: impl Into<String>) -> Self {
+        self.subscribe.insert(EventPattern::new(pattern));
+        self
+    }
+
+    pub fn allow_subscribe(&mut self, pattern: impl Into<String>) {
+        self.subscribe.insert(EventPattern::new(pattern));
+    }
+
+    /// Whether every event `pattern` can match falls under a granted pattern
+    pub fn check_subscribe(&self, pattern: &EventPattern) -> bool {
+        self.subscribe.iter().any(|granted| granted.covers(pattern))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -151,51 +171,6 @@ impl Event {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EventPattern {
-    pub kind_prefix: Option<String>,
-    pub source: Option<AgentId>,
-}
-
-impl EventPattern {
-    pub fn all() -> Self {
-        Self {
-            kind_prefix: None,
-            source: None,
-        }
-    }
-
-    pub fn kind(prefix: impl Into<String>) -> Self {
-        Self {
-            kind_prefix: Some(prefix.into()),
-            source: None,
-        }
-    }
-
-    pub fn from_source(source: AgentId) -> Self {
-        Self {
-            kind_prefix: None,
-            source: Some(source),
-        }
-    }
-
-    pub fn matches(&self, event: &Event) -> bool {
-        let kind_matches = self
-            .kind_prefix
-            .as_ref()
-            .map(|p| event.kind.starts_with(p))
-            .unwrap_or(true);
-
-        let source_matches = self
-            .source
-            .as_ref()
-            .map(|s| event.source.as_ref() == Some(s))
-            .unwrap_or(true);
-
-        kind_matches && source_matches
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphQuery {
     pub node_type: Option<String>,
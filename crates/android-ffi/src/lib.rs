@@ -0,0 +1,503 @@
+//! CortexOS Android FFI Library
+//!
+//! Exposes the peer to Kotlin/Java through JNI. It mirrors the iOS FFI:
+//! one global Tokio runtime and one peer state, with `Java_*` entry points
+//! in place of `extern "C"` ones. Strings cross as `jstring`; structured
+//! results are JSON. The agents are the ones the iOS library hosts, shared
+//! through `cortex_mobile`.
+//!
+//! The entry points belong to `com.cortexos.CortexNative`:
+//!
+//! ```kotlin
+//! object CortexNative {
+//!     init { System.loadLibrary("cortex_android_ffi") }
+//!
+//!     external fun init(): Boolean
+//!     external fun getNodeId(): String
+//!     external fun getDeviceInfo(): String
+//!     external fun startDiscovery(port: Int): Boolean
+//!     external fun stopDiscovery(): Boolean
+//!     external fun isRunning(): Boolean
+//!     external fun getPeerCount(): Int
+//!     external fun getPeers(): String
+//!     external fun sendQuery(query: String): String
+//!     external fun getChatHistory(): String
+//!
+//!     external fun startHeartbeatAgent(name: String, intervalSecs: Long): String
+//!     external fun startLoggerAgent(name: String): String
+//!     external fun startInferenceAgent(name: String): String
+//!     external fun listAgents(): String
+//!     external fun stopAgent(agentId: String): Boolean
+//!     external fun removeAgent(agentId: String): Boolean
+//!     external fun sendToAgent(agentId: String, message: String): String
+//! }
+//! ```
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jni::objects::{JClass, JString};
+use jni::sys::{jboolean, jint, jlong, jstring, JNI_FALSE, JNI_TRUE};
+use jni::JNIEnv;
+use once_cell::sync::OnceCell;
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use cortex_core::DeviceCapabilities;
+use cortex_grid::{Discovery, LanDiscovery, NodeId, PeerInfo, PeerStore, PipelineConfig, PipelineCoordinator};
+use cortex_mobile::{AgentRegistry, RealAgent};
+
+/// How long a discovered peer stays in the peer list without being seen
+const PEER_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Global state
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+static STATE: OnceCell<Arc<RwLock<PeerState>>> = OnceCell::new();
+
+struct PeerState {
+    node_id: NodeId,
+    capabilities: DeviceCapabilities,
+    peer_store: Arc<PeerStore>,
+    /// Running LAN discovery, `None` when stopped
+    discovery: Option<LanDiscovery>,
+    chat_history: Vec<ChatMessage>,
+    agents: AgentRegistry,
+    /// Pipeline over the discovered compute peers, resharded as they come
+    /// and go
+    pipeline: Arc<PipelineCoordinator>,
+}
+
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+fn get_runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime")
+    })
+}
+
+fn get_state() -> &'static Arc<RwLock<PeerState>> {
+    STATE.get_or_init(|| {
+        let node_id = NodeId::random();
+        let peer_store = Arc::new(PeerStore::new(PEER_TIMEOUT));
+        let pipeline = Arc::new(PipelineCoordinator::new(
+            node_id,
+            Arc::clone(&peer_store),
+            PipelineConfig::default(),
+        ));
+        let _runtime = get_runtime().enter();
+        Arc::clone(&pipeline).rebuild_on_change();
+
+        Arc::new(RwLock::new(PeerState {
+            node_id,
+            capabilities: DeviceCapabilities::detect(),
+            peer_store,
+            discovery: None,
+            chat_history: Vec::new(),
+            agents: AgentRegistry::new(),
+            pipeline,
+        }))
+    })
+}
+
+// ============ Peer operations ============
+
+async fn start_discovery(port: u16) -> Result<(), String> {
+    let mut s = get_state().write().await;
+    if s.discovery.is_some() {
+        return Ok(());
+    }
+
+    let pubkey = [0u8; 32];
+    let (mut discovery, mut discovery_rx) = LanDiscovery::new(s.node_id, pubkey, port);
+    discovery.start().await.map_err(|e| e.to_string())?;
+
+    let peer_store = Arc::clone(&s.peer_store);
+    tokio::spawn(async move {
+        while let Some(event) = discovery_rx.recv().await {
+            let mut peer = PeerInfo::new(event.peer_id, [0u8; 32]);
+            peer.addresses = event.addresses;
+            // Peers that announce nothing are not picked for compute
+            peer.capabilities = event.capabilities.unwrap_or_default();
+            peer_store.insert(peer).await;
+        }
+    });
+
+    s.discovery = Some(discovery);
+    Ok(())
+}
+
+/// Returns `false` if discovery was not running
+async fn stop_discovery() -> bool {
+    let discovery = get_state().write().await.discovery.take();
+    match discovery {
+        Some(mut discovery) => {
+            if let Err(e) = discovery.stop().await {
+                warn!("Stopping discovery failed: {}", e);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+async fn device_info() -> String {
+    let s = get_state().read().await;
+    serde_json::json!({
+        "cpu": s.capabilities.cpu.model,
+        "cores": s.capabilities.cpu.cores,
+        "ram_mb": s.capabilities.memory.total_mb,
+        "score": s.capabilities.capacity_score,
+        "max_layers": s.capabilities.max_layers,
+    })
+    .to_string()
+}
+
+async fn peers_json() -> String {
+    let peer_store = Arc::clone(&get_state().read().await.peer_store);
+    let arr: Vec<serde_json::Value> = peer_store
+        .list_active()
+        .await
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "node_id": p.node_id.to_string(),
+                "address": p.addresses.first().map(|a| a.to_string()).unwrap_or_default(),
+            })
+        })
+        .collect();
+    serde_json::to_string(&arr).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Run `query` through a pipeline of the discovered compute peers. The
+/// state lock is released while inference runs.
+async fn send_query(query: String) -> Result<String, String> {
+    let pipeline = {
+        let mut s = get_state().write().await;
+        s.chat_history.push(ChatMessage {
+            role: "user".to_string(),
+            content: query.clone(),
+        });
+        Arc::clone(&s.pipeline)
+    };
+
+    let result = match pipeline.build_pipeline().await {
+        Ok(_) => pipeline.infer(&query).await,
+        Err(e) => Err(e),
+    };
+
+    if let Ok(response) = &result {
+        get_state().write().await.chat_history.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: response.clone(),
+        });
+    }
+    result
+}
+
+async fn chat_history_json() -> String {
+    let s = get_state().read().await;
+    let arr: Vec<serde_json::Value> = s
+        .chat_history
+        .iter()
+        .map(|m| serde_json::json!({ "role": m.role, "content": m.content }))
+        .collect();
+    serde_json::to_string(&arr).unwrap_or_else(|_| "[]".to_string())
+}
+
+// ============ Agent operations ============
+
+/// Register `agent` and describe it as JSON
+async fn start_agent(agent: RealAgent) -> String {
+    let json = serde_json::json!({ "id": agent.id, "name": agent.name, "type": agent.type_name() });
+    get_state().write().await.agents.insert(agent);
+    json.to_string()
+}
+
+/// `{"response": ...}`, `{"success": true}` for agents that do not reply,
+/// or `{"error": ...}`
+async fn send_to_agent(id: &str, message: &str) -> serde_json::Value {
+    match get_state().write().await.agents.send(id, message) {
+        Ok(Some(response)) => serde_json::json!({ "response": response }),
+        Ok(None) => serde_json::json!({ "success": true, "agent": id }),
+        Err(e) => serde_json::json!({ "error": e.to_string() }),
+    }
+}
+
+// ============ JNI helpers ============
+
+/// Run `f`, turning a panic into `fallback` so it never unwinds into the JVM
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+fn to_jboolean(value: bool) -> jboolean {
+    if value {
+        JNI_TRUE
+    } else {
+        JNI_FALSE
+    }
+}
+
+/// A new Java string, or null if the JVM could not allocate one
+fn to_jstring(env: &mut JNIEnv, s: String) -> jstring {
+    env.new_string(s).map(|s| s.into_raw()).unwrap_or(std::ptr::null_mut())
+}
+
+fn from_jstring(env: &mut JNIEnv, s: &JString) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    env.get_string(s).ok().map(Into::into)
+}
+
+// ============ JNI Functions ============
+
+/// Initialize the CortexOS peer
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_init(_env: JNIEnv, _class: JClass) -> jboolean {
+    guard(JNI_FALSE, || {
+        let _ = get_runtime();
+        let _ = get_state();
+        JNI_TRUE
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_getNodeId(mut env: JNIEnv, _class: JClass) -> jstring {
+    let id = guard(String::new(), || {
+        get_runtime().block_on(async { get_state().read().await.node_id.to_string() })
+    });
+    to_jstring(&mut env, id)
+}
+
+/// Device info as JSON
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_getDeviceInfo(mut env: JNIEnv, _class: JClass) -> jstring {
+    let json = guard("{}".to_string(), || get_runtime().block_on(device_info()));
+    to_jstring(&mut env, json)
+}
+
+/// Start LAN discovery, announcing `port`. Already running is success.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_startDiscovery(
+    _env: JNIEnv,
+    _class: JClass,
+    port: jint,
+) -> jboolean {
+    let Ok(port) = u16::try_from(port) else {
+        return JNI_FALSE;
+    };
+    guard(JNI_FALSE, || {
+        match get_runtime().block_on(start_discovery(port)) {
+            Ok(()) => JNI_TRUE,
+            Err(e) => {
+                warn!("Discovery failed to start: {}", e);
+                JNI_FALSE
+            }
+        }
+    })
+}
+
+/// Stop LAN discovery. Returns false if it was not running.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_stopDiscovery(_env: JNIEnv, _class: JClass) -> jboolean {
+    guard(JNI_FALSE, || to_jboolean(get_runtime().block_on(stop_discovery())))
+}
+
+/// Check if discovery is running
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_isRunning(_env: JNIEnv, _class: JClass) -> jboolean {
+    guard(JNI_FALSE, || {
+        to_jboolean(get_runtime().block_on(async { get_state().read().await.discovery.is_some() }))
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_getPeerCount(_env: JNIEnv, _class: JClass) -> jint {
+    guard(0, || {
+        get_runtime().block_on(async {
+            let peer_store = Arc::clone(&get_state().read().await.peer_store);
+            let count = peer_store.list_active().await.len();
+            jint::try_from(count).unwrap_or(jint::MAX)
+        })
+    })
+}
+
+/// Active peers as a JSON array
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_getPeers(mut env: JNIEnv, _class: JClass) -> jstring {
+    let json = guard("[]".to_string(), || get_runtime().block_on(peers_json()));
+    to_jstring(&mut env, json)
+}
+
+/// Run an AI query across the discovered peers. Returns
+/// `{"response": ...}` or `{"error": ...}`.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_sendQuery(
+    mut env: JNIEnv,
+    _class: JClass,
+    query: JString,
+) -> jstring {
+    let json = match from_jstring(&mut env, &query) {
+        Some(query) => guard(
+            serde_json::json!({ "error": "query panicked" }),
+            || match get_runtime().block_on(send_query(query)) {
+                Ok(response) => serde_json::json!({ "response": response }),
+                Err(e) => serde_json::json!({ "error": e }),
+            },
+        ),
+        None => serde_json::json!({ "error": "null query" }),
+    };
+    to_jstring(&mut env, json.to_string())
+}
+
+/// Chat history as a JSON array of `{role, content}`
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_getChatHistory(mut env: JNIEnv, _class: JClass) -> jstring {
+    let json = guard("[]".to_string(), || get_runtime().block_on(chat_history_json()));
+    to_jstring(&mut env, json)
+}
+
+// ============ Agent JNI Functions ============
+
+fn start_agent_json(env: &mut JNIEnv, name: &JString, make: impl FnOnce(String) -> RealAgent) -> jstring {
+    let json = match from_jstring(env, name) {
+        Some(name) => guard(r#"{"error":"start panicked"}"#.to_string(), || {
+            get_runtime().block_on(start_agent(make(name)))
+        }),
+        None => r#"{"error":"null name"}"#.to_string(),
+    };
+    to_jstring(env, json)
+}
+
+/// Start an agent that only counts the events it receives
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_startHeartbeatAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    name: JString,
+    interval_secs: jlong,
+) -> jstring {
+    let interval_secs = u64::try_from(interval_secs).unwrap_or(0).max(1);
+    start_agent_json(&mut env, &name, |name| RealAgent::new_heartbeat(name, interval_secs))
+}
+
+/// Start an agent that echoes what it receives into the log
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_startLoggerAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    name: JString,
+) -> jstring {
+    start_agent_json(&mut env, &name, RealAgent::new_logger)
+}
+
+/// Start an agent answering with the built-in local rules
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_startInferenceAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    name: JString,
+) -> jstring {
+    start_agent_json(&mut env, &name, RealAgent::new_inference_local)
+}
+
+/// Agents as a JSON array of `{id, name, type, status, events}`
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_listAgents(mut env: JNIEnv, _class: JClass) -> jstring {
+    let json = guard("[]".to_string(), || {
+        get_runtime().block_on(async { get_state().read().await.agents.list_json() })
+    });
+    to_jstring(&mut env, json)
+}
+
+/// Stop an agent. Returns false if there is no such agent.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_stopAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    agent_id: JString,
+) -> jboolean {
+    let Some(id) = from_jstring(&mut env, &agent_id) else {
+        return JNI_FALSE;
+    };
+    guard(JNI_FALSE, || {
+        to_jboolean(get_runtime().block_on(async { get_state().write().await.agents.stop(&id).is_ok() }))
+    })
+}
+
+/// Remove an agent. Returns false if there is no such agent.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_removeAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    agent_id: JString,
+) -> jboolean {
+    let Some(id) = from_jstring(&mut env, &agent_id) else {
+        return JNI_FALSE;
+    };
+    guard(JNI_FALSE, || {
+        to_jboolean(get_runtime().block_on(async { get_state().write().await.agents.remove(&id).is_some() }))
+    })
+}
+
+/// Deliver `message` to an agent. Returns `{"response": ...}`,
+/// `{"success": true, ...}` for agents that do not reply, or `{"error": ...}`.
+#[no_mangle]
+pub extern "system" fn Java_com_cortexos_CortexNative_sendToAgent(
+    mut env: JNIEnv,
+    _class: JClass,
+    agent_id: JString,
+    message: JString,
+) -> jstring {
+    let json = match (from_jstring(&mut env, &agent_id), from_jstring(&mut env, &message)) {
+        (Some(id), Some(message)) => guard(serde_json::json!({ "error": "agent panicked" }), || {
+            get_runtime().block_on(send_to_agent(&id, &message))
+        }),
+        _ => serde_json::json!({ "error": "null argument" }),
+    };
+    to_jstring(&mut env, json.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_without_peers_reports_error() {
+        assert_eq!(peers_json().await, "[]");
+
+        let error = send_query("hello".to_string()).await.unwrap_err();
+        assert_eq!(error, "No compute nodes available");
+
+        // The question is kept even though nobody answered it
+        let history: serde_json::Value = serde_json::from_str(&chat_history_json().await).unwrap();
+        assert_eq!(history[0]["role"], "user");
+        assert_eq!(history[0]["content"], "hello");
+
+        assert!(!stop_discovery().await);
+    }
+
+    #[tokio::test]
+    async fn test_agents_answer_until_stopped() {
+        let started: serde_json::Value =
+            serde_json::from_str(&start_agent(RealAgent::new_inference_local("calc".to_string())).await).unwrap();
+        let id = started["id"].as_str().unwrap().to_string();
+        assert_eq!(started["type"], "inference (local)");
+
+        assert_eq!(send_to_agent(&id, "2 + 2").await["response"], "🤖 [calc]: = 4");
+        assert_eq!(send_to_agent("missing", "hi").await["error"], "Agent missing not found");
+
+        get_state().write().await.agents.stop(&id).unwrap();
+        assert_eq!(send_to_agent(&id, "2 + 2").await["error"], format!("Agent {} is stopped", id));
+        assert!(get_state().write().await.agents.remove(&id).is_some());
+    }
+}
@@ -11,6 +11,7 @@
 //! - **Coalesce**: Keep only latest event per key (stateful deduplication)
 //! - **Sample**: Keep 1 out of every N events (downsampling)
 //! - **Persist**: Spill to storage when full (durability priority)
+//! - **Priority**: Serve the most urgent first, evict the least urgent when full
 //!
 //! # Examples
 //!
@@ -43,7 +44,8 @@
 //! ```
 
 use parking_lot::Mutex;
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 /// Backpressure policy for handling queue overload.
 ///
@@ -90,11 +92,22 @@ pub enum BackpressurePolicy {
     ///
     /// Note: Storage persistence is not yet fully implemented.
     Persist,
+
+    /// Pop the highest-priority item first, FIFO among equal priorities.
+    ///
+    /// Use for mixed-urgency streams, such as sensor alerts sharing a queue
+    /// with routine readings. When full, the lowest-priority item is evicted
+    /// (the newest one if several tie), which may be the incoming item.
+    ///
+    /// Priorities come from `PolicyQueue::with_priority_fn`; without it every
+    /// item has priority 0.
+    Priority,
 }
 
 /// Trait for items that can be keyed for coalescing.
 ///
 /// Implement this trait to enable `BackpressurePolicy::Coalesce` for your type.
+/// For types you cannot implement it on, use [`PolicyQueue::with_key_fn`].
 pub trait Keyed {
     /// Returns the key for this item, used for coalescing.
     ///
@@ -113,9 +126,25 @@ pub struct PolicyQueue<T> {
     queue: Mutex<VecDeque<T>>,
     coalesce_map: Mutex<HashMap<String, usize>>,
     sample_counter: Mutex<usize>,
+    key_fn: KeyFn<T>,
+    /// Storage for `Priority`, ordered by (priority, arrival reversed) so the
+    /// last entry is served next and the first is evicted next
+    ranked: Mutex<Ranked<T>>,
+    priority_fn: PriorityFn<T>,
+}
+
+/// Extracts the coalescing key of an item
+type KeyFn<T> = Box<dyn Fn(&T) -> Option<String> + Send + Sync>;
+
+/// Ranks an item for `BackpressurePolicy::Priority`; higher is more urgent
+type PriorityFn<T> = Box<dyn Fn(&T) -> i64 + Send + Sync>;
+
+struct Ranked<T> {
+    items: BTreeMap<(i64, Reverse<u64>), T>,
+    next_seq: u64,
 }
 
-impl<T: Clone + Keyed> PolicyQueue<T> {
+impl<T: Keyed> PolicyQueue<T> {
     /// Create a new queue with the specified policy and capacity.
     ///
     /// # Examples
@@ -133,15 +162,70 @@ impl<T: Clone + Keyed> PolicyQueue<T> {
     /// );
     /// ```
     pub fn new(policy: BackpressurePolicy, capacity: usize) -> Self {
+        Self::with_key_fn(policy, capacity, |item: &T| item.key().map(str::to_string))
+    }
+}
+
+impl<T> PolicyQueue<T> {
+    /// Create a queue that coalesces by a caller-supplied key instead of
+    /// [`Keyed`], for types that cannot implement the trait.
+    ///
+    /// `key_fn` returning `None` means the item is never coalesced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cortex_core::backpressure::{BackpressurePolicy, PolicyQueue};
+    ///
+    /// // (sensor id, reading) tuples, coalesced by sensor id
+    /// let queue = PolicyQueue::with_key_fn(
+    ///     BackpressurePolicy::Coalesce("sensor".to_string()),
+    ///     100,
+    ///     |reading: &(String, f32)| Some(reading.0.clone()),
+    /// );
+    /// queue.push(("temp".to_string(), 20.5)).unwrap();
+    /// queue.push(("temp".to_string(), 21.0)).unwrap();
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn with_key_fn(
+        policy: BackpressurePolicy,
+        capacity: usize,
+        key_fn: impl Fn(&T) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
         Self {
             policy,
             capacity,
             queue: Mutex::new(VecDeque::with_capacity(capacity)),
             coalesce_map: Mutex::new(HashMap::new()),
             sample_counter: Mutex::new(0),
+            key_fn: Box::new(key_fn),
+            ranked: Mutex::new(Ranked {
+                items: BTreeMap::new(),
+                next_seq: 0,
+            }),
+            priority_fn: Box::new(|_| 0),
         }
     }
 
+    /// Set how items are ranked under `BackpressurePolicy::Priority`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cortex_core::backpressure::{BackpressurePolicy, PolicyQueue};
+    ///
+    /// // (urgency, message)
+    /// let queue = PolicyQueue::with_key_fn(BackpressurePolicy::Priority, 10, |_: &(i64, &str)| None)
+    ///     .with_priority_fn(|item| item.0);
+    /// queue.push((0, "reading")).unwrap();
+    /// queue.push((9, "alert")).unwrap();
+    /// assert_eq!(queue.pop(), Some((9, "alert")));
+    /// ```
+    pub fn with_priority_fn(mut self, priority_fn: impl Fn(&T) -> i64 + Send + Sync + 'static) -> Self {
+        self.priority_fn = Box::new(priority_fn);
+        self
+    }
+
     /// Push an item onto the queue according to the backpressure policy.
     ///
     /// Returns `Ok(())` if the item was accepted, or `Err(item)` if it was
@@ -170,9 +254,27 @@ impl<T: Clone + Keyed> PolicyQueue<T> {
             BackpressurePolicy::Coalesce(key_field) => self.push_coalesce(item, key_field.clone()),
             BackpressurePolicy::Sample(n) => self.push_sample(item, *n),
             BackpressurePolicy::Persist => self.push_persist(item),
+            BackpressurePolicy::Priority => self.push_priority(item),
         }
     }
 
+    fn push_priority(&self, item: T) -> Result<(), T> {
+        let priority = (self.priority_fn)(&item);
+        let mut ranked = self.ranked.lock();
+        let seq = ranked.next_seq;
+        ranked.next_seq += 1;
+        ranked.items.insert((priority, Reverse(seq)), item);
+
+        if ranked.items.len() > self.capacity {
+            if let Some(((_, Reverse(evicted_seq)), evicted)) = ranked.items.pop_first() {
+                if evicted_seq == seq {
+                    return Err(evicted);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn push_drop_new(&self, item: T) -> Result<(), T> {
         let mut queue = self.queue.lock();
         if queue.len() >= self.capacity {
@@ -195,8 +297,7 @@ impl<T: Clone + Keyed> PolicyQueue<T> {
         let mut queue = self.queue.lock();
         let mut coalesce_map = self.coalesce_map.lock();
 
-        if let Some(key) = item.key() {
-            let key = key.to_string();
+        if let Some(key) = (self.key_fn)(&item) {
             if let Some(&idx) = coalesce_map.get(&key) {
                 if idx < queue.len() {
                     queue[idx] = item;
@@ -205,8 +306,8 @@ impl<T: Clone + Keyed> PolicyQueue<T> {
             }
             if queue.len() >= self.capacity {
                 if let Some(removed) = queue.pop_front() {
-                    if let Some(removed_key) = removed.key() {
-                        coalesce_map.remove(removed_key);
+                    if let Some(removed_key) = (self.key_fn)(&removed) {
+                        coalesce_map.remove(&removed_key);
                     }
                     for (_, v) in coalesce_map.iter_mut() {
                         *v = v.saturating_sub(1);
@@ -274,17 +375,40 @@ impl<T: Clone + Keyed> PolicyQueue<T> {
     /// assert_eq!(item.value, 42);
     /// ```
     pub fn pop(&self) -> Option<T> {
-        self.queue.lock().pop_front()
+        match self.policy {
+            BackpressurePolicy::Priority => self.ranked.lock().items.pop_last().map(|(_, item)| item),
+            _ => self.queue.lock().pop_front(),
+        }
+    }
+
+    /// Pop up to `max` items in the order `pop` would return them.
+    pub fn drain_batch(&self, max: usize) -> Vec<T> {
+        match self.policy {
+            BackpressurePolicy::Priority => {
+                let mut ranked = self.ranked.lock();
+                std::iter::from_fn(|| ranked.items.pop_last().map(|(_, item)| item))
+                    .take(max)
+                    .collect()
+            }
+            _ => {
+                let mut queue = self.queue.lock();
+                let n = max.min(queue.len());
+                queue.drain(..n).collect()
+            }
+        }
     }
 
     /// Returns the current number of items in the queue.
     pub fn len(&self) -> usize {
-        self.queue.lock().len()
+        match self.policy {
+            BackpressurePolicy::Priority => self.ranked.lock().items.len(),
+            _ => self.queue.lock().len(),
+        }
     }
 
     /// Returns `true` if the queue contains no items.
     pub fn is_empty(&self) -> bool {
-        self.queue.lock().is_empty()
+        self.len() == 0
     }
 
     /// Returns the maximum capacity of the queue.
@@ -410,6 +534,54 @@ mod tests {
         assert_eq!(queue.len(), 2);
     }
 
+    #[test]
+    fn test_coalesce_with_key_fn() {
+        // A foreign type with no `Keyed` impl, keyed by its first field
+        let queue = PolicyQueue::with_key_fn(
+            BackpressurePolicy::Coalesce("device".to_string()),
+            2,
+            |item: &(String, i32)| if item.0.is_empty() { None } else { Some(item.0.clone()) },
+        );
+
+        queue.push(("a".to_string(), 1)).unwrap();
+        queue.push(("a".to_string(), 2)).unwrap();
+        queue.push(("b".to_string(), 3)).unwrap();
+        assert_eq!(queue.len(), 2);
+
+        // At capacity: the oldest entry is evicted and its key forgotten
+        queue.push(("c".to_string(), 4)).unwrap();
+        queue.push(("c".to_string(), 5)).unwrap();
+        assert_eq!(queue.pop(), Some(("b".to_string(), 3)));
+        assert_eq!(queue.pop(), Some(("c".to_string(), 5)));
+
+        queue.push((String::new(), 6)).unwrap();
+        queue.push((String::new(), 7)).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_priority_eviction() {
+        let queue: PolicyQueue<TestItem> = PolicyQueue::new(BackpressurePolicy::Priority, 3)
+            .with_priority_fn(|item: &TestItem| item.value as i64 / 10);
+
+        // Two routine readings and two alerts into a queue of three
+        queue.push(TestItem { key: None, value: 1 }).unwrap();
+        queue.push(TestItem { key: None, value: 2 }).unwrap();
+        queue.push(TestItem { key: None, value: 90 }).unwrap();
+        queue.push(TestItem { key: None, value: 91 }).unwrap();
+        assert_eq!(queue.len(), 3);
+
+        // A new item less urgent than everything queued is itself rejected
+        let rejected = queue.push(TestItem { key: None, value: -10 });
+        assert_eq!(rejected.unwrap_err().value, -10);
+        assert_eq!(queue.len(), 3);
+
+        // Highest priority first, FIFO among equals; reading 2 was evicted
+        let values: Vec<i32> = queue.drain_batch(10).iter().map(|item| item.value).collect();
+        assert_eq!(values, vec![90, 91, 1]);
+        assert!(queue.is_empty());
+    }
+
     #[test]
     fn test_persist_policy() {
         let queue: PolicyQueue<TestItem> = PolicyQueue::new(BackpressurePolicy::Persist, 2);
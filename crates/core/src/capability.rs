@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::event::{EventPattern, Timestamp};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SensorType {
@@ -104,9 +107,175 @@ impl Capability {
     }
 }
 
+/// Validity window of a time-boxed grant, in Unix milliseconds (inclusive).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub not_before: Timestamp,
+    pub not_after: Timestamp,
+}
+
+impl TimeWindow {
+    pub fn until(valid_until: Timestamp) -> Self {
+        Self {
+            not_before: 0,
+            not_after: valid_until,
+        }
+    }
+
+    pub fn between(not_before: Timestamp, not_after: Timestamp) -> Self {
+        Self {
+            not_before,
+            not_after,
+        }
+    }
+
+    pub fn contains(&self, at: Timestamp) -> bool {
+        at >= self.not_before && at <= self.not_after
+    }
+
+    pub fn is_expired(&self, at: Timestamp) -> bool {
+        at > self.not_after
+    }
+
+    /// Whether this window lies entirely inside `other`
+    pub fn is_within(&self, other: &TimeWindow) -> bool {
+        self.not_before >= other.not_before && self.not_after <= other.not_after
+    }
+
+    /// The instants in both windows, or `None` if they do not meet
+    pub fn overlap(&self, other: &TimeWindow) -> Option<TimeWindow> {
+        let not_before = self.not_before.max(other.not_before);
+        let not_after = self.not_after.min(other.not_after);
+        (not_before <= not_after).then(|| TimeWindow::between(not_before, not_after))
+    }
+
+    /// The part of this window outside `other`, or `None` if `other` covers
+    /// all of it. When `other` sits strictly inside, what is left is two
+    /// pieces that one window cannot express, so this window is returned.
+    pub fn minus(&self, other: &TimeWindow) -> Option<TimeWindow> {
+        if self.is_within(other) {
+            return None;
+        }
+        if self.overlap(other).is_none() {
+            return Some(*self);
+        }
+        match (other.not_before > self.not_before, other.not_after < self.not_after) {
+            (true, true) => Some(*self),
+            (true, false) => Some(TimeWindow::between(self.not_before, other.not_before - 1)),
+            _ => Some(TimeWindow::between(other.not_after + 1, self.not_after)),
+        }
+    }
+}
+
+/// Window of a grant that is not time-boxed
+const ALWAYS: TimeWindow = TimeWindow {
+    not_before: 0,
+    not_after: Timestamp::MAX,
+};
+
+/// Flattened, display-friendly view of one grant in a `CapabilitySet`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityDescriptor {
+    /// Capability family: `filesystem`, `network`, `sensor`, `grid` or `event_bus`
+    pub kind: String,
+    /// Human-readable description of what is granted
+    pub summary: String,
+    pub capability: Capability,
+    /// Present when the grant is time-boxed
+    pub window: Option<TimeWindow>,
+}
+
+impl CapabilityDescriptor {
+    fn new(cap: &Capability, window: Option<TimeWindow>) -> Self {
+        let (kind, summary) = match cap {
+            Capability::FileSystem { read, write, paths } => {
+                let mode = match (read, write) {
+                    (true, true) => "read/write",
+                    (true, false) => "read",
+                    (false, true) => "write",
+                    (false, false) => "none",
+                };
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                ("filesystem", format!("{} {}", mode, paths.join(", ")))
+            }
+            Capability::Network { tcp, udp, hosts } => {
+                let protocols = match (tcp, udp) {
+                    (true, true) => "tcp+udp",
+                    (true, false) => "tcp",
+                    (false, true) => "udp",
+                    (false, false) => "none",
+                };
+                let hosts = if hosts.is_empty() {
+                    "any host".to_string()
+                } else {
+                    hosts.join(", ")
+                };
+                ("network", format!("{} to {}", protocols, hosts))
+            }
+            Capability::Sensor(sensor) => ("sensor", format!("{:?}", sensor)),
+            Capability::Grid { relay, task_accept } => {
+                let mut roles = Vec::new();
+                if *relay {
+                    roles.push("relay");
+                }
+                if *task_accept {
+                    roles.push("accept tasks");
+                }
+                ("grid", roles.join(", "))
+            }
+            Capability::EventBus { publish, subscribe } => (
+                "event_bus",
+                format!(
+                    "publish [{}], subscribe [{}]",
+                    publish.join(", "),
+                    subscribe.join(", ")
+                ),
+            ),
+        };
+
+        Self {
+            kind: kind.to_string(),
+            summary,
+            capability: cap.clone(),
+            window,
+        }
+    }
+}
+
+fn now_millis() -> Timestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CapabilitySet {
     capabilities: HashSet<Capability>,
+    /// Grants that are only valid within a time window. Stored as pairs
+    /// because JSON map keys must be strings.
+    #[serde(default, with = "window_pairs")]
+    windows: HashMap<Capability, TimeWindow>,
+}
+
+mod window_pairs {
+    use super::{Capability, TimeWindow};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        windows: &HashMap<Capability, TimeWindow>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(windows)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Capability, TimeWindow>, D::Error> {
+        let pairs = Vec::<(Capability, TimeWindow)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
 }
 
 impl CapabilitySet {
@@ -115,24 +284,70 @@ impl CapabilitySet {
     }
 
     pub fn with_capability(mut self, cap: Capability) -> Self {
-        self.capabilities.insert(cap);
+        self.add(cap);
         self
     }
 
+    /// Grant a capability unconditionally, clearing any time window on it.
     pub fn add(&mut self, cap: Capability) {
+        self.windows.remove(&cap);
+        self.capabilities.insert(cap);
+    }
+
+    /// Grant a capability that stops being honoured after `valid_until`.
+    pub fn add_timed(&mut self, cap: Capability, valid_until: Timestamp) {
+        self.add_windowed(cap, TimeWindow::until(valid_until));
+    }
+
+    /// Grant a capability that is only honoured inside `window`.
+    pub fn add_windowed(&mut self, cap: Capability, window: TimeWindow) {
+        self.windows.insert(cap.clone(), window);
         self.capabilities.insert(cap);
     }
 
     pub fn remove(&mut self, cap: &Capability) -> bool {
+        self.windows.remove(cap);
         self.capabilities.remove(cap)
     }
 
     pub fn has(&self, cap: &Capability) -> bool {
-        self.capabilities.contains(cap)
+        self.capabilities.contains(cap) && self.is_active(cap, now_millis())
+    }
+
+    /// Time window attached to a grant, if it is time-boxed.
+    pub fn window(&self, cap: &Capability) -> Option<&TimeWindow> {
+        self.windows.get(cap)
+    }
+
+    /// Drop grants whose window has ended. Returns how many were removed.
+    pub fn prune_expired(&mut self) -> usize {
+        let now = now_millis();
+        let expired: Vec<Capability> = self
+            .windows
+            .iter()
+            .filter(|(_, w)| w.is_expired(now))
+            .map(|(cap, _)| cap.clone())
+            .collect();
+        for cap in &expired {
+            self.remove(cap);
+        }
+        expired.len()
+    }
+
+    fn is_active(&self, cap: &Capability, at: Timestamp) -> bool {
+        self.windows.get(cap).is_none_or(|w| w.contains(at))
+    }
+
+    /// Grants that are valid right now.
+    fn active(&self) -> impl Iterator<Item = &Capability> {
+        let now = now_millis();
+        self.capabilities
+            .iter()
+            .filter(move |cap| self.is_active(cap, now))
     }
 
     pub fn check_fs_read(&self, path: &PathBuf) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::FileSystem { read, paths, .. } => {
                 *read && paths.iter().any(|p| path.starts_with(p))
             }
@@ -141,7 +356,7 @@ impl CapabilitySet {
     }
 
     pub fn check_fs_write(&self, path: &PathBuf) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::FileSystem { write, paths, .. } => {
                 *write && paths.iter().any(|p| path.starts_with(p))
             }
@@ -150,7 +365,7 @@ impl CapabilitySet {
     }
 
     pub fn check_network(&self, host: &str, is_tcp: bool) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::Network { tcp, udp, hosts } => {
                 let protocol_ok = if is_tcp { *tcp } else { *udp };
                 protocol_ok && (hosts.is_empty() || hosts.iter().any(|h| host.contains(h)))
@@ -160,27 +375,25 @@ impl CapabilitySet {
     }
 
     pub fn check_sensor(&self, sensor_type: &SensorType) -> bool {
-        self.capabilities
-            .iter()
-            .any(|cap| matches!(cap, Capability::Sensor(st) if st == sensor_type))
+        self.active().any(|cap| matches!(cap, Capability::Sensor(st) if st == sensor_type))
     }
 
     pub fn check_grid_relay(&self) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::Grid { relay, .. } => *relay,
             _ => false,
         })
     }
 
     pub fn check_grid_task_accept(&self) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::Grid { task_accept, .. } => *task_accept,
             _ => false,
         })
     }
 
     pub fn check_publish(&self, kind: &str) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::EventBus { publish, .. } => {
                 publish.iter().any(|p| pattern_matches(p, kind))
             }
@@ -189,14 +402,84 @@ impl CapabilitySet {
     }
 
     pub fn check_subscribe(&self, pattern: &str) -> bool {
-        self.capabilities.iter().any(|cap| match cap {
+        self.active().any(|cap| match cap {
             Capability::EventBus { subscribe, .. } => {
-                subscribe.iter().any(|s| pattern_matches(s, pattern))
+                subscribe
+                    .iter()
+                    .any(|s| EventPattern::new(s.as_str()).covers(&EventPattern::new(pattern)))
             }
             _ => false,
         })
     }
 
+    /// Structured listing of every grant, sorted for stable display.
+    pub fn describe(&self) -> Vec<CapabilityDescriptor> {
+        let mut descriptors: Vec<_> = self
+            .capabilities
+            .iter()
+            .map(|cap| CapabilityDescriptor::new(cap, self.windows.get(cap).copied()))
+            .collect();
+        descriptors.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.summary.cmp(&b.summary)));
+        descriptors
+    }
+
+    /// Grants present in both sets. When either side is time-boxed the
+    /// result is only valid where both windows overlap, and a grant whose
+    /// windows never meet is left out.
+    pub fn intersect(&self, other: &CapabilitySet) -> CapabilitySet {
+        let mut result = CapabilitySet::new();
+        for cap in self.capabilities.intersection(&other.capabilities) {
+            match (self.windows.get(cap), other.windows.get(cap)) {
+                (None, None) => result.add(cap.clone()),
+                (Some(w), None) | (None, Some(w)) => result.add_windowed(cap.clone(), *w),
+                (Some(a), Some(b)) => {
+                    if let Some(window) = a.overlap(b) {
+                        result.add_windowed(cap.clone(), window);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Grants in this set that `other` does not cover. A grant `other` only
+    /// holds for part of its window is kept for the rest of it (see
+    /// `TimeWindow::minus`).
+    pub fn difference(&self, other: &CapabilitySet) -> CapabilitySet {
+        let mut result = CapabilitySet::new();
+        for cap in &self.capabilities {
+            let mine = self.windows.get(cap).copied().unwrap_or(ALWAYS);
+            let left = if other.capabilities.contains(cap) {
+                mine.minus(other.windows.get(cap).unwrap_or(&ALWAYS))
+            } else {
+                Some(mine)
+            };
+            match left {
+                Some(window) if window == ALWAYS => result.add(cap.clone()),
+                Some(window) => result.add_windowed(cap.clone(), window),
+                None => {}
+            }
+        }
+        result
+    }
+
+    /// Whether every grant in this set is also granted by `other`, for at
+    /// least as long: a time-boxed grant in `other` only covers one whose
+    /// window lies inside it.
+    ///
+    /// Grants are compared exactly; a narrower path or host list is not
+    /// considered covered by a broader one.
+    pub fn is_subset_of(&self, other: &CapabilitySet) -> bool {
+        self.capabilities.iter().all(|cap| {
+            other.capabilities.contains(cap)
+                && match (self.windows.get(cap), other.windows.get(cap)) {
+                    (_, None) => true,
+                    (Some(mine), Some(theirs)) => mine.is_within(theirs),
+                    (None, Some(_)) => false,
+                }
+        })
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Capability> {
         self.capabilities.iter()
     }
@@ -211,18 +494,7 @@ impl CapabilitySet {
 }
 
 fn pattern_matches(pattern: &str, target: &str) -> bool {
-    if pattern == "*" || pattern == target {
-        return true;
-    }
-    if pattern.ends_with(".*") {
-        let prefix = &pattern[..pattern.len() - 2];
-        return target.starts_with(prefix);
-    }
-    if pattern.ends_with("*") {
-        let prefix = &pattern[..pattern.len() - 1];
-        return target.starts_with(prefix);
-    }
-    false
+    EventPattern::new(pattern).matches(target)
 }
 
 #[cfg(test)]
@@ -368,4 +640,162 @@ mod tests {
         assert!(pattern_matches("test*", "test"));
         assert!(!pattern_matches("test*", "tes"));
     }
+
+    #[test]
+    fn test_timed_capability_expires() {
+        let mut caps = CapabilitySet::new();
+        let net = Capability::network_tcp(vec![]);
+        caps.add_timed(net.clone(), now_millis() + 60_000);
+        assert!(caps.check_network("example.com", true));
+        assert!(caps.has(&net));
+
+        caps.add_timed(net.clone(), now_millis() - 1);
+        assert!(!caps.check_network("example.com", true));
+        assert!(!caps.has(&net));
+    }
+
+    #[test]
+    fn test_windowed_capability_not_yet_valid() {
+        let mut caps = CapabilitySet::new();
+        let now = now_millis();
+        caps.add_windowed(
+            Capability::fs_read(vec![PathBuf::from("/tmp")]),
+            TimeWindow::between(now + 60_000, now + 120_000),
+        );
+        assert!(!caps.check_fs_read(&PathBuf::from("/tmp/file.txt")));
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let mut caps = CapabilitySet::new()
+            .with_capability(Capability::sensor(SensorType::Microphone));
+        caps.add_timed(Capability::grid_relay(), now_millis() - 1);
+        caps.add_timed(Capability::grid_worker(), now_millis() + 60_000);
+        assert_eq!(caps.len(), 3);
+
+        assert_eq!(caps.prune_expired(), 1);
+        assert_eq!(caps.len(), 2);
+        assert!(!caps.check_grid_relay());
+        assert!(caps.check_grid_task_accept());
+        assert!(caps.check_sensor(&SensorType::Microphone));
+    }
+
+    #[test]
+    fn test_add_clears_time_window() {
+        let mut caps = CapabilitySet::new();
+        caps.add_timed(Capability::grid_relay(), now_millis() - 1);
+        assert!(!caps.check_grid_relay());
+
+        caps.add(Capability::grid_relay());
+        assert!(caps.check_grid_relay());
+        assert!(caps.window(&Capability::grid_relay()).is_none());
+    }
+
+    #[test]
+    fn test_describe() {
+        let caps = CapabilitySet::new()
+            .with_capability(Capability::network_tcp(vec![]))
+            .with_capability(Capability::fs_read(vec![PathBuf::from("/tmp")]));
+
+        let described = caps.describe();
+        assert_eq!(described.len(), caps.len());
+        assert_eq!(described[0].kind, "filesystem");
+        assert_eq!(described[0].summary, "read /tmp");
+        assert_eq!(described[1].kind, "network");
+        assert_eq!(described[1].summary, "tcp to any host");
+        assert!(described.iter().all(|d| d.window.is_none()));
+    }
+
+    #[test]
+    fn test_intersect_and_difference() {
+        let relay = Capability::grid_relay();
+        let mic = Capability::sensor(SensorType::Microphone);
+        let cam = Capability::sensor(SensorType::Camera);
+
+        let a = CapabilitySet::new()
+            .with_capability(relay.clone())
+            .with_capability(mic.clone());
+        let b = CapabilitySet::new()
+            .with_capability(relay.clone())
+            .with_capability(cam.clone());
+
+        let both = a.intersect(&b);
+        assert_eq!(both.len(), 1);
+        assert!(both.has(&relay));
+
+        let only_a = a.difference(&b);
+        assert_eq!(only_a.len(), 1);
+        assert!(only_a.has(&mic));
+    }
+
+    #[test]
+    fn test_intersect_narrows_windows() {
+        let relay = Capability::grid_relay();
+        let mut a = CapabilitySet::new();
+        a.add_windowed(relay.clone(), TimeWindow::between(100, 500));
+        let mut b = CapabilitySet::new();
+        b.add_windowed(relay.clone(), TimeWindow::between(300, 900));
+
+        let both = a.intersect(&b);
+        assert_eq!(both.window(&relay), Some(&TimeWindow::between(300, 500)));
+
+        let mut c = CapabilitySet::new();
+        c.add_windowed(relay.clone(), TimeWindow::between(600, 900));
+        assert!(a.intersect(&c).is_empty());
+    }
+
+    #[test]
+    fn test_difference_respects_windows() {
+        let relay = Capability::grid_relay();
+        let always = CapabilitySet::new().with_capability(relay.clone());
+        let mut early = CapabilitySet::new();
+        early.add_windowed(relay.clone(), TimeWindow::between(100, 500));
+        let mut late = CapabilitySet::new();
+        late.add_windowed(relay.clone(), TimeWindow::between(300, 900));
+        let mut middle = CapabilitySet::new();
+        middle.add_windowed(relay.clone(), TimeWindow::between(200, 300));
+
+        assert!(early.difference(&always).is_empty());
+        assert!(middle.difference(&early).is_empty());
+        assert_eq!(early.difference(&late).window(&relay), Some(&TimeWindow::between(100, 299)));
+        assert_eq!(late.difference(&early).window(&relay), Some(&TimeWindow::between(501, 900)));
+
+        // The uncovered part is split in two, so the whole grant is kept
+        assert_eq!(early.difference(&middle).window(&relay), Some(&TimeWindow::between(100, 500)));
+        let open = always.difference(&middle);
+        assert!(open.has(&relay));
+        assert!(open.window(&relay).is_none());
+    }
+
+    #[test]
+    fn test_is_subset_of() {
+        let small = CapabilitySet::new().with_capability(Capability::grid_relay());
+        let large = CapabilitySet::new()
+            .with_capability(Capability::grid_relay())
+            .with_capability(Capability::grid_worker());
+
+        assert!(small.is_subset_of(&large));
+        assert!(!large.is_subset_of(&small));
+        assert!(CapabilitySet::new().is_subset_of(&small));
+
+        let mut brief = CapabilitySet::new();
+        brief.add_windowed(Capability::grid_relay(), TimeWindow::between(100, 200));
+        let mut briefer = CapabilitySet::new();
+        briefer.add_windowed(Capability::grid_relay(), TimeWindow::between(120, 180));
+        assert!(brief.is_subset_of(&small));
+        assert!(!small.is_subset_of(&brief));
+        assert!(briefer.is_subset_of(&brief));
+        assert!(!brief.is_subset_of(&briefer));
+    }
+
+    #[test]
+    fn test_windowed_set_json_roundtrip() {
+        let mut caps = CapabilitySet::new().with_capability(Capability::grid_worker());
+        caps.add_windowed(Capability::grid_relay(), TimeWindow::between(100, 200));
+
+        let json = serde_json::to_string(&caps).unwrap();
+        let back: CapabilitySet = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.window(&Capability::grid_relay()), Some(&TimeWindow::between(100, 200)));
+        assert!(back.is_subset_of(&caps) && caps.is_subset_of(&back));
+    }
 }
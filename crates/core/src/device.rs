@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::process::Command;
 
 /// Real device capabilities - measured from actual hardware
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeviceCapabilities {
     /// Device type
     pub device_type: DeviceType,
@@ -29,7 +29,7 @@ pub struct DeviceCapabilities {
     pub can_inference: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeviceType {
     Desktop,
     Laptop,
@@ -40,7 +40,7 @@ pub enum DeviceType {
     Unknown,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CpuInfo {
     /// CPU model name
     pub model: String,
@@ -54,7 +54,7 @@ pub struct CpuInfo {
     pub arch: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MemoryInfo {
     /// Total RAM in MB
     pub total_mb: u64,
@@ -64,7 +64,7 @@ pub struct MemoryInfo {
     pub used_mb: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GpuInfo {
     /// GPU model
     pub model: String,
@@ -74,9 +74,13 @@ pub struct GpuInfo {
     pub gpu_type: GpuType,
     /// CUDA/Metal/OpenCL support
     pub compute_api: String,
+    /// Shares system RAM with the CPU (Apple Silicon); `vram_mb` is then the
+    /// portion of RAM the GPU may use rather than dedicated memory
+    #[serde(default)]
+    pub unified_memory: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GpuType {
     Nvidia,
     Amd,
@@ -85,7 +89,7 @@ pub enum GpuType {
     Other,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StorageInfo {
     /// Free storage in MB
     pub free_mb: u64,
@@ -93,12 +97,15 @@ pub struct StorageInfo {
     pub is_ssd: bool,
 }
 
+/// Leads the wire form so a peer can tell which layout it is decoding
+const WIRE_VERSION: u8 = 1;
+
 impl DeviceCapabilities {
     /// Detect REAL device capabilities from the running system
     pub fn detect() -> Self {
         let cpu = detect_cpu();
         let memory = detect_memory();
-        let gpu = detect_gpu();
+        let gpu = detect_gpu(&memory);
         let storage = detect_storage();
         let device_type = detect_device_type(&cpu, &memory);
         
@@ -125,6 +132,39 @@ impl DeviceCapabilities {
         }
     }
     
+    /// Re-read available memory and refresh the derived fields.
+    ///
+    /// Static facts (CPU, GPU model, storage) are kept from `detect`, so this
+    /// is cheap enough to call periodically before re-advertising.
+    pub fn recompute_score(&mut self) {
+        self.update_memory(detect_memory());
+    }
+
+    /// Apply a new memory reading and recompute `capacity_score`,
+    /// `max_layers` and `can_inference` from it
+    pub fn update_memory(&mut self, memory: MemoryInfo) {
+        self.memory = memory;
+        self.capacity_score = calculate_capacity_score(&self.cpu, &self.memory, &self.gpu);
+        self.max_layers = calculate_max_layers(self.memory.available_mb, self.gpu.as_ref());
+        self.can_inference = self.memory.available_mb >= 512;
+    }
+
+    /// Compact form for advertising to peers: a version byte, then bincode
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![WIRE_VERSION];
+        bytes.extend(bincode::serialize(self).unwrap_or_default());
+        bytes
+    }
+
+    /// Read a peer's advertisement; `None` if it is malformed or from an
+    /// unknown wire version
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        match data.split_first() {
+            Some((&WIRE_VERSION, rest)) => bincode::deserialize(rest).ok(),
+            _ => None,
+        }
+    }
+
     /// Get a human-readable summary
     pub fn summary(&self) -> String {
         format!(
@@ -330,21 +370,36 @@ fn detect_memory() -> MemoryInfo {
     }
 }
 
-fn detect_gpu() -> Option<GpuInfo> {
+#[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
+fn detect_gpu(memory: &MemoryInfo) -> Option<GpuInfo> {
     #[cfg(target_os = "macos")]
     {
-        // Check for Apple Silicon GPU
-        let gpu_info = run_command("system_profiler", &["SPDisplaysDataType"])?;
-        
-        if gpu_info.contains("Apple") {
-            // Apple Silicon - unified memory
-            let mem = detect_memory();
+        // hw.optional.arm64 is also 1 for x86_64 binaries under Rosetta
+        let apple_silicon = cfg!(target_arch = "aarch64")
+            || run_command("sysctl", &["-n", "hw.optional.arm64"]).is_some_and(|s| s.trim() == "1");
+        let gpu_info = run_command("system_profiler", &["SPDisplaysDataType"]).unwrap_or_default();
+
+        if apple_silicon {
+            // Integrated Metal GPU sharing unified memory with the CPU
+            let chip = run_command("sysctl", &["-n", "machdep.cpu.brand_string"])
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "Apple Silicon".to_string());
+            let model = match parse_gpu_cores(&gpu_info) {
+                Some(cores) => format!("{} GPU ({} cores)", chip, cores),
+                None => format!("{} GPU", chip),
+            };
+            let wired_limit_mb = run_command("sysctl", &["-n", "iogpu.wired_limit_mb"])
+                .and_then(|s| s.trim().parse().ok());
             Some(GpuInfo {
-                model: "Apple Silicon GPU".to_string(),
-                vram_mb: mem.total_mb / 2, // Shared memory
+                model,
+                vram_mb: unified_gpu_budget_mb(memory.total_mb, wired_limit_mb),
                 gpu_type: GpuType::Apple,
                 compute_api: "Metal".to_string(),
+                unified_memory: true,
             })
+        } else if gpu_info.is_empty() {
+            None
         } else {
             // Discrete GPU
             let model = gpu_info.lines()
@@ -358,6 +413,7 @@ fn detect_gpu() -> Option<GpuInfo> {
                 vram_mb: 4096, // Default
                 gpu_type: GpuType::Other,
                 compute_api: "Metal".to_string(),
+                unified_memory: false,
             })
         }
     }
@@ -379,6 +435,7 @@ fn detect_gpu() -> Option<GpuInfo> {
                     vram_mb,
                     gpu_type: GpuType::Nvidia,
                     compute_api: "CUDA".to_string(),
+                    unified_memory: false,
                 });
             }
         }
@@ -406,6 +463,7 @@ fn detect_gpu() -> Option<GpuInfo> {
                         vram_mb: 2048, // Default
                         gpu_type,
                         compute_api: "OpenCL".to_string(),
+                        unified_memory: false,
                     });
                 }
             }
@@ -442,6 +500,7 @@ fn detect_gpu() -> Option<GpuInfo> {
                 vram_mb: 4096,
                 gpu_type,
                 compute_api: if matches!(gpu_type, GpuType::Nvidia) { "CUDA" } else { "DirectX" }.to_string(),
+                unified_memory: false,
             })
         } else {
             None
@@ -454,6 +513,30 @@ fn detect_gpu() -> Option<GpuInfo> {
     }
 }
 
+/// Memory an Apple Silicon GPU can use for inference.
+///
+/// Metal caps a process's GPU working set at roughly two thirds of RAM on
+/// machines with up to 36GB and three quarters above that. An explicit
+/// `iogpu.wired_limit_mb` override takes precedence.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn unified_gpu_budget_mb(total_mb: u64, wired_limit_mb: Option<u64>) -> u64 {
+    match wired_limit_mb {
+        Some(limit) if limit > 0 => limit.min(total_mb),
+        _ if total_mb <= 36 * 1024 => total_mb * 2 / 3,
+        _ => total_mb * 3 / 4,
+    }
+}
+
+/// GPU core count from `system_profiler SPDisplaysDataType` output
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_gpu_cores(system_profiler: &str) -> Option<u32> {
+    system_profiler
+        .lines()
+        .find(|l| l.trim_start().starts_with("Total Number of Cores"))
+        .and_then(|l| l.split(':').nth(1))
+        .and_then(|s| s.trim().parse().ok())
+}
+
 fn detect_storage() -> StorageInfo {
     #[cfg(target_os = "macos")]
     {
@@ -532,8 +615,14 @@ fn calculate_capacity_score(cpu: &CpuInfo, memory: &MemoryInfo, gpu: &Option<Gpu
     score += (cpu.cores * 5).min(40);
     
     // Memory score (0-30 points)
-    // More available RAM = more score
-    let mem_gb = memory.available_mb / 1024;
+    // More available RAM = more score. With unified memory the GPU budget
+    // is RAM the OS will hand over for inference, even if it currently
+    // holds reclaimable caches that `available_mb` does not count.
+    let usable_mb = match gpu {
+        Some(gpu) if gpu.unified_memory => memory.available_mb.max(gpu.vram_mb),
+        _ => memory.available_mb,
+    };
+    let mem_gb = usable_mb / 1024;
     score += (mem_gb * 3).min(30) as u32;
     
     // GPU score (0-30 points)
@@ -591,4 +680,97 @@ mod tests {
         assert!(caps.memory.total_mb > 0);
         assert!(caps.capacity_score <= 100);
     }
+
+    #[test]
+    fn test_recompute_after_memory_drop() {
+        let mut caps = DeviceCapabilities::detect();
+        caps.gpu = None;
+        caps.update_memory(MemoryInfo {
+            total_mb: 16 * 1024,
+            available_mb: 12 * 1024,
+            used_mb: 4 * 1024,
+        });
+        let (score, layers) = (caps.capacity_score, caps.max_layers);
+        assert!(caps.can_inference);
+
+        caps.update_memory(MemoryInfo {
+            total_mb: 16 * 1024,
+            available_mb: 256,
+            used_mb: 16 * 1024 - 256,
+        });
+        assert!(caps.capacity_score < score);
+        assert!(caps.max_layers < layers);
+        assert!(!caps.can_inference);
+
+        let cpu_model = caps.cpu.model.clone();
+        caps.recompute_score();
+        assert_eq!(caps.cpu.model, cpu_model);
+        assert!(caps.memory.total_mb > 0);
+    }
+
+    #[test]
+    fn test_wire_round_trip() {
+        let mut caps = DeviceCapabilities::detect();
+        caps.gpu = Some(GpuInfo {
+            model: "RTX 4090".to_string(),
+            vram_mb: 24 * 1024,
+            gpu_type: GpuType::Nvidia,
+            compute_api: "CUDA".to_string(),
+            unified_memory: false,
+        });
+
+        let json = serde_json::to_string(&caps).unwrap();
+        let from_json: DeviceCapabilities = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.summary(), caps.summary());
+
+        let bytes = caps.encode();
+        assert!(bytes.len() < json.len());
+        let decoded = DeviceCapabilities::decode(&bytes).unwrap();
+        assert_eq!(decoded.summary(), caps.summary());
+        assert_eq!(decoded.gpu.unwrap().vram_mb, 24 * 1024);
+        assert_eq!(decoded.capacity_score, caps.capacity_score);
+
+        let mut future = bytes.clone();
+        future[0] = WIRE_VERSION + 1;
+        assert!(DeviceCapabilities::decode(&future).is_none());
+        assert!(DeviceCapabilities::decode(&bytes[..bytes.len() / 2]).is_none());
+        assert!(DeviceCapabilities::decode(&[]).is_none());
+    }
+
+    #[test]
+    fn test_apple_silicon_scoring() {
+        assert_eq!(unified_gpu_budget_mb(16 * 1024, None), 16 * 1024 * 2 / 3);
+        assert_eq!(unified_gpu_budget_mb(64 * 1024, None), 48 * 1024);
+        assert_eq!(unified_gpu_budget_mb(16 * 1024, Some(14 * 1024)), 14 * 1024);
+        assert_eq!(unified_gpu_budget_mb(16 * 1024, Some(0)), 16 * 1024 * 2 / 3);
+
+        let profiler = "Graphics/Displays:\n\n    Apple M2 Pro:\n\n      Chipset Model: Apple M2 Pro\n      Total Number of Cores: 19\n";
+        assert_eq!(parse_gpu_cores(profiler), Some(19));
+        assert_eq!(parse_gpu_cores("Chipset Model: Intel UHD"), None);
+
+        // A 16GB M-series laptop whose free RAM is mostly file cache
+        let cpu = CpuInfo {
+            model: "Apple M2 Pro".to_string(),
+            cores: 10,
+            threads: 10,
+            frequency_mhz: 3500,
+            arch: "aarch64".to_string(),
+        };
+        let memory = MemoryInfo {
+            total_mb: 16 * 1024,
+            available_mb: 1024,
+            used_mb: 15 * 1024,
+        };
+        let gpu = GpuInfo {
+            model: "Apple M2 Pro GPU (19 cores)".to_string(),
+            vram_mb: unified_gpu_budget_mb(memory.total_mb, None),
+            gpu_type: GpuType::Apple,
+            compute_api: "Metal".to_string(),
+            unified_memory: true,
+        };
+        let cpu_only = calculate_capacity_score(&cpu, &memory, &None);
+        let with_metal = calculate_capacity_score(&cpu, &memory, &Some(gpu.clone()));
+        assert!(with_metal >= cpu_only + 50, "{} vs {}", with_metal, cpu_only);
+        assert!(calculate_max_layers(memory.available_mb, Some(&gpu)) > calculate_max_layers(memory.available_mb, None));
+    }
 }
@@ -42,6 +42,14 @@ pub enum CoreError {
     #[error("Runtime shutdown")]
     RuntimeShutdown,
 
+    /// A subscriber fell behind and this many events were dropped for it
+    #[error("Subscriber lagged, {0} events missed")]
+    Lagged(u64),
+
+    /// No reply arrived in time for a request
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
     /// Event pattern matching failed
     #[error("Pattern match error: {0}")]
     PatternError(String),
@@ -90,6 +90,9 @@ pub struct Event {
     pub kind: String,
     pub payload: Payload,
     pub trace: Trace,
+    /// For a reply, the id of the event it answers
+    #[serde(default)]
+    pub correlation_id: Option<EventId>,
 }
 
 impl Event {
@@ -104,9 +107,18 @@ impl Event {
             kind: kind.to_string(),
             payload,
             trace: Trace::default(),
+            correlation_id: None,
         }
     }
 
+    /// Create a reply to `request`, correlated by its id
+    pub fn reply_to(request: &Event, source: &str, kind: &str, payload: Payload) -> Self {
+        let mut reply = Self::new(source, kind, payload);
+        reply.correlation_id = Some(request.id.clone());
+        reply.trace = request.trace.clone();
+        reply
+    }
+
     /// Create a validated event with bounds checking
     pub fn new_validated(source: &str, kind: &str, payload: Payload) -> Result<Self> {
         // Validate source length
@@ -163,6 +175,7 @@ impl Event {
             kind: sanitize_string(kind),
             payload,
             trace: Trace::default(),
+            correlation_id: None,
         })
     }
 
@@ -228,6 +241,124 @@ fn sanitize_string(s: &str) -> String {
 
 pub type Timestamp = u64;
 
+/// A glob over dot-separated event kinds, used for subscriptions and
+/// event-bus capabilities.
+///
+/// Grammar, applied segment by segment:
+/// - `*` as a whole segment matches exactly one segment:
+///   `task.*` matches `task.compile` but not `task.sub.x` or `task`
+/// - `**` matches zero or more segments:
+///   `task.**` matches `task`, `task.compile` and `task.sub.x`
+/// - `*` inside a segment matches any characters within that segment:
+///   `test*` matches `test` and `test123` but not `test.x`
+/// - anything else matches literally
+///
+/// A pattern that is just `*` matches every kind, like `**`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventPattern(String);
+
+impl EventPattern {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self(pattern.into())
+    }
+
+    /// Matches every event kind
+    pub fn all() -> Self {
+        Self::new("**")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn matches(&self, kind: &str) -> bool {
+        if self.0 == "*" {
+            return true;
+        }
+        let pattern: Vec<&str> = self.0.split('.').collect();
+        let kind: Vec<&str> = kind.split('.').collect();
+        match_segments(&pattern, &kind)
+    }
+
+    /// Whether every kind matched by `other` is also matched by `self`.
+    ///
+    /// Used to decide if a subscription falls within a granted pattern.
+    /// Intra-segment globs in `other` are only covered by an identical
+    /// segment, `*` or `**`, which keeps the check conservative.
+    pub fn covers(&self, other: &EventPattern) -> bool {
+        if self.0 == "*" {
+            return true;
+        }
+        let other = if other.0 == "*" { "**" } else { other.as_str() };
+        let grant: Vec<&str> = self.0.split('.').collect();
+        let request: Vec<&str> = other.split('.').collect();
+        covers_segments(&grant, &request)
+    }
+}
+
+impl fmt::Display for EventPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for EventPattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+fn match_segments(pattern: &[&str], kind: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => kind.is_empty(),
+        Some((&"**", rest)) => (0..=kind.len()).any(|skip| match_segments(rest, &kind[skip..])),
+        Some((seg, rest)) => match kind.split_first() {
+            Some((first, kind_rest)) => glob_segment(seg, first) && match_segments(rest, kind_rest),
+            None => false,
+        },
+    }
+}
+
+fn covers_segments(grant: &[&str], request: &[&str]) -> bool {
+    match grant.split_first() {
+        None => request.is_empty(),
+        Some((&"**", rest)) => (0..=request.len()).any(|skip| covers_segments(rest, &request[skip..])),
+        Some((seg, rest)) => match request.split_first() {
+            Some((&"**", _)) => false,
+            Some((first, request_rest)) => {
+                let covered = if first.contains('*') {
+                    *seg == "*" || seg == first
+                } else {
+                    glob_segment(seg, first)
+                };
+                covered && covers_segments(rest, request_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match one segment where `*` stands for any run of characters
+fn glob_segment(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` in the segment
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,4 +496,66 @@ mod tests {
         let result = Event::new_validated("source", "test.v1", Payload::inline(max_size_payload));
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_event_pattern_single_segment() {
+        let p = EventPattern::new("task.*");
+        assert!(p.matches("task.compile"));
+        assert!(!p.matches("task.sub.x"));
+        assert!(!p.matches("task"));
+        assert!(!p.matches("other.compile"));
+        assert!(EventPattern::new("agent.*.done").matches("agent.build.done"));
+        assert!(!EventPattern::new("agent.*.done").matches("agent.build.x.done"));
+    }
+
+    #[test]
+    fn test_event_pattern_any_depth() {
+        let p = EventPattern::new("task.**");
+        assert!(p.matches("task"));
+        assert!(p.matches("task.compile"));
+        assert!(p.matches("task.sub.x"));
+        assert!(!p.matches("tasks.compile"));
+
+        let p = EventPattern::new("**.done");
+        assert!(p.matches("done"));
+        assert!(p.matches("agent.build.done"));
+        assert!(!p.matches("agent.done.x"));
+
+        assert!(EventPattern::new("a.**.z").matches("a.z"));
+        assert!(EventPattern::new("a.**.z").matches("a.b.c.z"));
+        assert!(EventPattern::all().matches("anything.at.all"));
+        assert!(EventPattern::new("*").matches("anything.at.all"));
+    }
+
+    #[test]
+    fn test_event_pattern_literal_and_intra_segment() {
+        assert!(EventPattern::new("task.compile").matches("task.compile"));
+        assert!(!EventPattern::new("task.compile").matches("task.compile.x"));
+        assert!(EventPattern::new("test*").matches("test"));
+        assert!(EventPattern::new("test*").matches("test123"));
+        assert!(!EventPattern::new("test*").matches("tes"));
+        assert!(!EventPattern::new("test*").matches("test.x"));
+        assert!(EventPattern::new("sensor.*.v*").matches("sensor.mic.v1"));
+        assert!(EventPattern::new("a*b*c").matches("aXbYc"));
+        assert!(!EventPattern::new("a*b*c").matches("aXcYb"));
+    }
+
+    #[test]
+    fn test_event_pattern_covers() {
+        let grant = EventPattern::new("grid.*");
+        assert!(grant.covers(&"grid.*".into()));
+        assert!(grant.covers(&"grid.msg".into()));
+        assert!(!grant.covers(&"grid.**".into()));
+        assert!(!grant.covers(&"sensor.*".into()));
+
+        let grant = EventPattern::new("grid.**");
+        assert!(grant.covers(&"grid.**".into()));
+        assert!(grant.covers(&"grid.a.*".into()));
+        assert!(!grant.covers(&"**".into()));
+
+        assert!(EventPattern::new("test*").covers(&"test123".into()));
+        assert!(!EventPattern::new("test*").covers(&"te*".into()));
+        assert!(EventPattern::new("*").covers(&"*".into()));
+        assert!(!EventPattern::new("grid.*").covers(&"*".into()));
+    }
 }
@@ -35,6 +35,21 @@ impl SymbolId {
         Self(id)
     }
 
+    /// Derive an id for `name` within `namespace`, so independent subsystems
+    /// can reuse mnemonics like `ACK` in a shared codebook without colliding.
+    ///
+    /// The namespace is folded into the hash rather than stored, keeping
+    /// `SymbolId` a plain 16-byte key. Ids from `from_bytes` are unaffected.
+    pub fn namespaced(namespace: &str, name: &str) -> Self {
+        let mut hasher = blake3::Hasher::new_derive_key("cortexOS 2024 symbol namespace");
+        hasher.update(&(namespace.len() as u64).to_le_bytes());
+        hasher.update(namespace.as_bytes());
+        hasher.update(name.as_bytes());
+        let mut id = [0u8; 16];
+        id.copy_from_slice(&hasher.finalize().as_bytes()[..16]);
+        Self(id)
+    }
+
     pub fn as_bytes(&self) -> &[u8; 16] {
         &self.0
     }
@@ -87,6 +102,20 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_symbol_id_namespaced() {
+        let a = SymbolId::namespaced("a", "ACK");
+        assert_eq!(a, SymbolId::namespaced("a", "ACK"));
+        assert_ne!(a, SymbolId::namespaced("b", "ACK"));
+        assert_ne!(a, SymbolId::from_bytes(b"ACK"));
+        assert_ne!(SymbolId::namespaced("ab", "C"), SymbolId::namespaced("a", "bC"));
+
+        // from_bytes keeps its original derivation
+        let mut expected = [0u8; 16];
+        expected.copy_from_slice(&blake3::hash(b"ACK").as_bytes()[..16]);
+        assert_eq!(SymbolId::from_bytes(b"ACK").as_bytes(), &expected);
+    }
+
     #[test]
     fn test_symbol_id_display() {
         let id = SymbolId::from_bytes(b"test");
@@ -4,13 +4,19 @@ pub mod device;
 pub mod error;
 pub mod event;
 pub mod id;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod logging;
+pub mod metrics;
 pub mod runtime;
 pub mod task_queue;
+pub mod wire;
 pub mod work_distributor;
 
 pub use async_trait::async_trait;
 pub use error::{CoreError, Result};
 pub use id::{NodeId, SymbolId};
 pub use device::DeviceCapabilities;
-pub use task_queue::{TaskQueue, TensorChunk, ProcessedChunk, ResponseAssembler};
-pub use work_distributor::{WorkDistributor, WorkPlan, PeerWork};
+pub use task_queue::{TaskQueue, TensorChunk, TensorMsg, ProcessedChunk, ResponseAssembler, AssemblyResult, verification_transform};
+pub use work_distributor::{WorkDistributor, WorkPeer, WorkPlan, PeerWork};
+pub use runtime::{EventSubscription, OverflowPolicy};
+pub use wire::{WireError, WireFormat};
@@ -0,0 +1,68 @@
+//! Log output selection shared by the CortexOS binaries.
+//!
+//! `--log-format json` (or `CORTEX_LOG_FORMAT=json`) switches the tracing
+//! subscriber to newline-delimited JSON for log aggregators. Event fields
+//! such as `node_id`, `peer_id` and `task_id` become top-level JSON keys.
+
+use std::fmt;
+use std::str::FromStr;
+use tracing::Level;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable, the default
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "pretty" | "human" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format '{}', expected 'text' or 'json'", other)),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Install the global tracing subscriber in the given format.
+///
+/// `with_target` only affects text output; JSON always includes the target.
+pub fn init(format: LogFormat, level: Level, with_target: bool) {
+    let builder = tracing_subscriber::fmt().with_max_level(level);
+    match format {
+        LogFormat::Text => builder.with_target(with_target).init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("xml".parse::<LogFormat>().is_err());
+        assert_eq!(LogFormat::default().to_string(), "text");
+    }
+}
@@ -0,0 +1,141 @@
+//! Prometheus text exposition
+//!
+//! A minimal encoder for the text format (version 0.0.4) served on the
+//! `/metrics` endpoints of the webui and peer. Each binary collects its own
+//! values; this module only handles formatting.
+
+use crate::runtime::MetricsSnapshot;
+use std::fmt::Write;
+
+/// Content type for Prometheus text exposition
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// Builds a Prometheus text exposition document
+#[derive(Debug, Default)]
+pub struct PrometheusEncoder {
+    out: String,
+}
+
+impl PrometheusEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the `# HELP` and `# TYPE` header for a metric family.
+    ///
+    /// Call once per family, before its samples.
+    pub fn family(&mut self, name: &str, help: &str, kind: MetricType) -> &mut Self {
+        let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind.as_str());
+        self
+    }
+
+    /// Write one sample with optional labels
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, val)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{}=\"{}\"", key, escape_label(val));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+        self
+    }
+
+    /// Write a family with a single unlabelled sample
+    pub fn single(&mut self, name: &str, help: &str, kind: MetricType, value: f64) -> &mut Self {
+        self.family(name, help, kind).sample(name, &[], value)
+    }
+
+    /// Write the event bus counters under the given metric prefix
+    pub fn event_bus(&mut self, prefix: &str, snapshot: &MetricsSnapshot) -> &mut Self {
+        let counters = [
+            ("events_published_total", "Events published on the event bus", snapshot.events_published),
+            ("events_delivered_total", "Events delivered to subscribers", snapshot.events_delivered),
+            ("events_dropped_total", "Events dropped due to backpressure", snapshot.events_dropped),
+            ("events_dead_lettered_total", "Events that matched no subscription", snapshot.events_dead_lettered),
+        ];
+        for (name, help, value) in counters {
+            self.single(&format!("{}_{}", prefix, name), help, MetricType::Counter, value as f64);
+        }
+        self.single(
+            &format!("{}_event_subscriptions", prefix),
+            "Active event bus subscriptions",
+            MetricType::Gauge,
+            snapshot.active_subscriptions as f64,
+        );
+        self.single(
+            &format!("{}_agents", prefix),
+            "Active agents",
+            MetricType::Gauge,
+            snapshot.active_agents as f64,
+        )
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_exposition() {
+        let mut encoder = PrometheusEncoder::new();
+        encoder.single("cortex_peers", "Active peers", MetricType::Gauge, 3.0);
+        encoder
+            .family("cortex_peer_latency_ms", "Peer latency", MetricType::Gauge)
+            .sample("cortex_peer_latency_ms", &[("peer", "ab\"c")], 12.5);
+        let text = encoder.finish();
+
+        assert_eq!(
+            text,
+            "# HELP cortex_peers Active peers\n\
+             # TYPE cortex_peers gauge\n\
+             cortex_peers 3\n\
+             # HELP cortex_peer_latency_ms Peer latency\n\
+             # TYPE cortex_peer_latency_ms gauge\n\
+             cortex_peer_latency_ms{peer=\"ab\\\"c\"} 12.5\n"
+        );
+    }
+}
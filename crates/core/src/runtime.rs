@@ -1,13 +1,20 @@
 use crate::capability::CapabilitySet;
 use crate::error::{CoreError, Result};
-use crate::event::Event;
+use crate::event::{Event, EventPattern, Payload};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use futures::future::BoxFuture;
 use parking_lot::RwLock;
-use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
+
+/// Default time `Runtime::shutdown` waits for agents before aborting them
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
 /// Runtime metrics for monitoring event processing
 #[derive(Debug, Default)]
@@ -22,6 +29,10 @@ pub struct RuntimeMetrics {
     pub active_subscriptions: AtomicU64,
     /// Number of active agents
     pub active_agents: AtomicU64,
+    /// Events no subscription matched, captured as dead letters
+    pub events_dead_lettered: AtomicU64,
+    /// Events a subscriber lost because it fell behind
+    pub events_lagged: AtomicU64,
 }
 
 impl RuntimeMetrics {
@@ -41,6 +52,14 @@ impl RuntimeMetrics {
         self.events_delivered.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn record_dead_letter(&self) {
+        self.events_dead_lettered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_lag(&self) {
+        self.events_lagged.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn snapshot(&self) -> MetricsSnapshot {
         MetricsSnapshot {
             events_published: self.events_published.load(Ordering::Relaxed),
@@ -48,6 +67,8 @@ impl RuntimeMetrics {
             events_delivered: self.events_delivered.load(Ordering::Relaxed),
             active_subscriptions: self.active_subscriptions.load(Ordering::Relaxed),
             active_agents: self.active_agents.load(Ordering::Relaxed),
+            events_dead_lettered: self.events_dead_lettered.load(Ordering::Relaxed),
+            events_lagged: self.events_lagged.load(Ordering::Relaxed),
         }
     }
 }
@@ -60,10 +81,43 @@ pub struct MetricsSnapshot {
     pub events_delivered: u64,
     pub active_subscriptions: u64,
     pub active_agents: u64,
+    pub events_dead_lettered: u64,
+    pub events_lagged: u64,
 }
 
 pub type EventHandler = Box<dyn Fn(Event) -> BoxFuture<'static, ()> + Send + Sync>;
 
+/// Descriptive information tooling can read from any agent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentMetadata {
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl AgentMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+}
+
+/// An agent run by the `Runtime`. Only `name`, `capabilities` and `handle`
+/// are required; every lifecycle hook defaults to a no-op.
 #[async_trait]
 pub trait Agent: Send + Sync {
     fn name(&self) -> &str;
@@ -75,16 +129,70 @@ pub trait Agent: Send + Sync {
     async fn stop(&self) -> Result<()> {
         Ok(())
     }
+
+    /// How often the runtime calls `tick`; `None` (the default) never does
+    fn tick_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Periodic work, run between events every `tick_interval`
+    async fn tick(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once when the runtime stops the agent. Defaults to `stop`.
+    async fn shutdown(&self) -> Result<()> {
+        self.stop().await
+    }
+
+    fn metadata(&self) -> AgentMetadata {
+        AgentMetadata::default()
+    }
+}
+
+/// Event kind published by `Runtime::update_capabilities`
+pub const CAPABILITIES_CHANGED: &str = "agent.capabilities_changed";
+
+/// Bincode payload of a `CAPABILITIES_CHANGED` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesChanged {
+    pub agent: String,
+    /// Grants the agent did not have before
+    pub granted: CapabilitySet,
+    /// Grants the agent no longer has
+    pub revoked: CapabilitySet,
 }
 
 pub struct AgentHandle {
     pub name: String,
-    pub capabilities: CapabilitySet,
+    metadata: AgentMetadata,
+    capabilities: Arc<RwLock<CapabilitySet>>,
     sender: mpsc::Sender<Event>,
     shutdown: mpsc::Sender<()>,
 }
 
 impl AgentHandle {
+    /// `Agent::metadata()` as reported when the agent was spawned
+    pub fn metadata(&self) -> &AgentMetadata {
+        &self.metadata
+    }
+
+    /// The agent's current grants, as last set by the runtime.
+    ///
+    /// Starts as `Agent::capabilities()` and changes with
+    /// `Runtime::update_capabilities`, so check this rather than the agent.
+    pub fn capabilities(&self) -> CapabilitySet {
+        self.capabilities.read().clone()
+    }
+
+    pub fn check_publish(&self, kind: &str) -> bool {
+        self.capabilities.read().check_publish(kind)
+    }
+
+    pub fn check_subscribe(&self, pattern: &str) -> bool {
+        self.capabilities.read().check_subscribe(pattern)
+    }
+
     pub async fn send(&self, event: Event) -> Result<()> {
         self.sender
             .send(event)
@@ -101,14 +209,209 @@ impl AgentHandle {
 }
 
 struct Subscription {
-    pattern: String,
-    sender: mpsc::Sender<Event>,
+    pattern: EventPattern,
+    sink: Sink,
+    /// For `Runtime::subscribe_as`, the subscribing agent's live grants.
+    /// Delivery stops while they no longer cover `pattern`.
+    grants: Option<Arc<RwLock<CapabilitySet>>>,
+}
+
+impl Subscription {
+    fn new(pattern: &str, sink: Sink, grants: Option<Arc<RwLock<CapabilitySet>>>) -> Self {
+        Self { pattern: EventPattern::new(pattern), sink, grants }
+    }
+
+    fn permitted(&self) -> bool {
+        self.grants
+            .as_ref()
+            .is_none_or(|grants| grants.read().check_subscribe(self.pattern.as_str()))
+    }
+}
+
+enum Sink {
+    /// `subscribe`: a full channel drops the new event
+    Channel(mpsc::Sender<Event>),
+    /// `subscribe_with`
+    Queue(Arc<SubscriberQueue>),
+}
+
+impl Subscription {
+    /// Whether the receiving end has been dropped
+    fn is_closed(&self) -> bool {
+        match &self.sink {
+            Sink::Channel(sender) => sender.is_closed(),
+            Sink::Queue(queue) => queue.state.lock().receiver_closed,
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Sink::Queue(queue) = &self.sink {
+            queue.state.lock().bus_closed = true;
+            queue.readable.notify_one();
+        }
+    }
+}
+
+/// What a `subscribe_with` subscription does when its queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued event to make room. The subscriber keeps
+    /// receiving; `EventSubscription::lagged` counts what it lost.
+    #[default]
+    DropOldest,
+    /// `EventBus::publish_async` waits for room. A synchronous `publish`
+    /// can't wait, so there the new event is dropped and reported as with
+    /// `Error`.
+    Block,
+    /// Drop the new event. The subscriber's `recv` returns
+    /// `CoreError::Lagged` at the point of the gap, then carries on.
+    Error,
+}
+
+enum Slot {
+    Event(Event),
+    /// Events dropped here, reported by `recv`
+    Gap(u64),
+}
+
+#[derive(Default)]
+struct QueueState {
+    slots: VecDeque<Slot>,
+    /// Events in `slots`, gaps excluded
+    len: usize,
+    lagged: u64,
+    bus_closed: bool,
+    receiver_closed: bool,
+}
+
+enum Delivery {
+    Queued,
+    /// Queued, but the oldest event was evicted for it
+    Displaced,
+    Rejected,
+    Closed,
+}
+
+struct SubscriberQueue {
+    overflow: OverflowPolicy,
+    capacity: usize,
+    state: parking_lot::Mutex<QueueState>,
+    readable: Notify,
+    writable: Notify,
+}
+
+impl SubscriberQueue {
+    fn push(&self, event: Event) -> Delivery {
+        let mut state = self.state.lock();
+        if state.receiver_closed {
+            return Delivery::Closed;
+        }
+        let delivery = if state.len < self.capacity {
+            state.slots.push_back(Slot::Event(event));
+            state.len += 1;
+            Delivery::Queued
+        } else if self.overflow == OverflowPolicy::DropOldest {
+            while let Some(Slot::Gap(_)) = state.slots.front() {
+                state.slots.pop_front();
+            }
+            state.slots.pop_front();
+            state.slots.push_back(Slot::Event(event));
+            state.lagged += 1;
+            Delivery::Displaced
+        } else {
+            match state.slots.back_mut() {
+                Some(Slot::Gap(missed)) => *missed += 1,
+                _ => state.slots.push_back(Slot::Gap(1)),
+            }
+            state.lagged += 1;
+            Delivery::Rejected
+        };
+        drop(state);
+        self.readable.notify_one();
+        delivery
+    }
+
+    /// Queue `event`, waiting while the queue is full
+    async fn push_waiting(&self, event: Event) -> Delivery {
+        loop {
+            let notified = self.writable.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            {
+                let mut state = self.state.lock();
+                if state.receiver_closed {
+                    return Delivery::Closed;
+                }
+                if state.len < self.capacity {
+                    state.slots.push_back(Slot::Event(event));
+                    state.len += 1;
+                    drop(state);
+                    self.readable.notify_one();
+                    return Delivery::Queued;
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Receiving end of `EventBus::subscribe_with`
+pub struct EventSubscription {
+    queue: Arc<SubscriberQueue>,
+}
+
+impl EventSubscription {
+    /// Next matching event. Under `OverflowPolicy::Error` (and `Block` fed
+    /// by `publish`) a gap in the stream is reported once as
+    /// `CoreError::Lagged(missed)` where it happened. Fails with
+    /// `ChannelClosed` once the bus is gone and the queue is drained.
+    pub async fn recv(&mut self) -> Result<Event> {
+        loop {
+            let notified = self.queue.readable.notified();
+            {
+                let mut state = self.queue.state.lock();
+                match state.slots.pop_front() {
+                    Some(Slot::Event(event)) => {
+                        state.len -= 1;
+                        drop(state);
+                        self.queue.writable.notify_waiters();
+                        return Ok(event);
+                    }
+                    Some(Slot::Gap(missed)) => return Err(CoreError::Lagged(missed)),
+                    None if state.bus_closed => return Err(CoreError::ChannelClosed),
+                    None => {}
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Total events this subscriber has lost to overflow
+    pub fn lagged(&self) -> u64 {
+        self.queue.state.lock().lagged
+    }
+
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.queue.overflow
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.queue.state.lock().receiver_closed = true;
+        self.queue.writable.notify_waiters();
+    }
 }
 
 pub struct EventBus {
     broadcast: broadcast::Sender<Event>,
     subscriptions: RwLock<Vec<Subscription>>,
     metrics: Arc<RuntimeMetrics>,
+    /// Capture events that no subscription matches
+    capture_dead_letters: AtomicBool,
+    dead_letters: RwLock<Vec<mpsc::Sender<Event>>>,
 }
 
 impl EventBus {
@@ -118,21 +421,37 @@ impl EventBus {
             broadcast,
             subscriptions: RwLock::new(Vec::new()),
             metrics: Arc::new(RuntimeMetrics::new()),
+            capture_dead_letters: AtomicBool::new(false),
+            dead_letters: RwLock::new(Vec::new()),
         }
     }
 
+    /// Route events that match no subscription to `dead_letters` receivers
+    /// instead of silently dropping them. `subscribe_all` listeners do not
+    /// count as a match.
+    pub fn with_dead_letters(self, enabled: bool) -> Self {
+        self.capture_dead_letters.store(enabled, Ordering::Relaxed);
+        self
+    }
+
     pub fn publish(&self, event: Event) -> Result<()> {
-        self.metrics.record_publish();
-        
-        let _ = self.broadcast.send(event.clone());
+        let (_, closed) = self.dispatch(&self.subscriptions.read(), &event, false);
+        if closed {
+            self.prune_closed();
+        }
+        Ok(())
+    }
 
-        let subscriptions = self.subscriptions.read();
-        for sub in subscriptions.iter() {
-            if pattern_matches(&sub.pattern, event.kind()) {
-                match sub.sender.try_send(event.clone()) {
-                    Ok(_) => self.metrics.record_delivery(),
-                    Err(_) => self.metrics.record_drop(),
-                }
+    /// Like `publish`, but waits for room in matching subscriptions with
+    /// `OverflowPolicy::Block` instead of dropping the event for them
+    pub async fn publish_async(&self, event: Event) -> Result<()> {
+        let (blocked, closed) = self.dispatch(&self.subscriptions.read(), &event, true);
+        if closed {
+            self.prune_closed();
+        }
+        for queue in blocked {
+            if let Delivery::Queued = queue.push_waiting(event.clone()).await {
+                self.metrics.record_delivery();
             }
         }
         Ok(())
@@ -141,37 +460,182 @@ impl EventBus {
     /// Publish multiple events in a batch for improved performance.
     /// This reduces lock contention by acquiring the subscriptions lock once.
     pub fn publish_batch(&self, events: &[Event]) -> Result<usize> {
-        let mut published = 0;
-        let subscriptions = self.subscriptions.read();
-        
-        for event in events {
-            self.metrics.record_publish();
-            let _ = self.broadcast.send(event.clone());
-            
-            for sub in subscriptions.iter() {
-                if pattern_matches(&sub.pattern, event.kind()) {
-                    match sub.sender.try_send(event.clone()) {
-                        Ok(_) => self.metrics.record_delivery(),
-                        Err(_) => self.metrics.record_drop(),
+        let mut closed = false;
+        {
+            let subscriptions = self.subscriptions.read();
+            for event in events {
+                closed |= self.dispatch(&subscriptions, event, false).1;
+            }
+        }
+        if closed {
+            self.prune_closed();
+        }
+        Ok(events.len())
+    }
+
+    /// Deliver `event` to every matching subscription. With `defer_blocking`
+    /// the `Block` queues are skipped and returned for the caller to wait on.
+    /// Also reports whether a matching subscription's receiver was dropped.
+    fn dispatch(
+        &self,
+        subscriptions: &[Subscription],
+        event: &Event,
+        defer_blocking: bool,
+    ) -> (Vec<Arc<SubscriberQueue>>, bool) {
+        self.metrics.record_publish();
+        let _ = self.broadcast.send(event.clone());
+
+        let mut matched = false;
+        let mut closed = false;
+        let mut deferred = Vec::new();
+        for sub in subscriptions.iter() {
+            if !sub.pattern.matches(event.kind()) || !sub.permitted() {
+                continue;
+            }
+            if sub.is_closed() {
+                closed = true;
+                continue;
+            }
+            matched = true;
+            match &sub.sink {
+                Sink::Channel(sender) => match sender.try_send(event.clone()) {
+                    Ok(_) => self.metrics.record_delivery(),
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        self.metrics.record_drop();
+                        self.metrics.record_lag();
                     }
+                    Err(mpsc::error::TrySendError::Closed(_)) => closed = true,
+                },
+                Sink::Queue(queue) if defer_blocking && queue.overflow == OverflowPolicy::Block => {
+                    deferred.push(Arc::clone(queue));
                 }
+                Sink::Queue(queue) => match queue.push(event.clone()) {
+                    Delivery::Queued => self.metrics.record_delivery(),
+                    Delivery::Displaced => {
+                        self.metrics.record_delivery();
+                        self.metrics.record_drop();
+                        self.metrics.record_lag();
+                    }
+                    Delivery::Rejected => {
+                        self.metrics.record_drop();
+                        self.metrics.record_lag();
+                    }
+                    Delivery::Closed => closed = true,
+                },
             }
-            published += 1;
         }
-        
-        Ok(published)
+
+        if !matched && self.capture_dead_letters.load(Ordering::Relaxed) {
+            self.metrics.record_dead_letter();
+            self.dead_letters
+                .write()
+                .retain(|tx| match tx.try_send(event.clone()) {
+                    Ok(_) => true,
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        self.metrics.record_drop();
+                        true
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => false,
+                });
+        }
+        (deferred, closed)
+    }
+
+    /// Forget subscriptions whose receiver has been dropped
+    fn prune_closed(&self) {
+        let mut subscriptions = self.subscriptions.write();
+        let before = subscriptions.len();
+        subscriptions.retain(|sub| !sub.is_closed());
+        let removed = (before - subscriptions.len()) as u64;
+        self.metrics.active_subscriptions.fetch_sub(removed, Ordering::Relaxed);
+    }
+
+    /// Receive events that matched no subscription.
+    ///
+    /// Only fed when the bus was built `with_dead_letters(true)`, or belongs
+    /// to a runtime built that way.
+    pub fn dead_letters(&self) -> mpsc::Receiver<Event> {
+        let (tx, rx) = mpsc::channel(256);
+        self.dead_letters.write().push(tx);
+        rx
     }
 
     pub fn subscribe(&self, pattern: &str) -> mpsc::Receiver<Event> {
+        self.subscribe_guarded(pattern, None)
+    }
+
+    fn subscribe_guarded(&self, pattern: &str, grants: Option<Arc<RwLock<CapabilitySet>>>) -> mpsc::Receiver<Event> {
+        self.prune_closed();
         let (tx, rx) = mpsc::channel(256);
-        self.subscriptions.write().push(Subscription {
-            pattern: pattern.to_string(),
-            sender: tx,
-        });
+        self.subscriptions.write().push(Subscription::new(pattern, Sink::Channel(tx), grants));
         self.metrics.active_subscriptions.fetch_add(1, Ordering::Relaxed);
         rx
     }
 
+    /// Subscribe with a queue of `capacity` events and an explicit
+    /// `overflow` behavior for when the subscriber falls behind
+    pub fn subscribe_with(&self, pattern: &str, capacity: usize, overflow: OverflowPolicy) -> EventSubscription {
+        self.subscribe_with_guarded(pattern, capacity, overflow, None)
+    }
+
+    fn subscribe_with_guarded(
+        &self,
+        pattern: &str,
+        capacity: usize,
+        overflow: OverflowPolicy,
+        grants: Option<Arc<RwLock<CapabilitySet>>>,
+    ) -> EventSubscription {
+        let queue = Arc::new(SubscriberQueue {
+            overflow,
+            capacity: capacity.max(1),
+            state: parking_lot::Mutex::new(QueueState::default()),
+            readable: Notify::new(),
+            writable: Notify::new(),
+        });
+        self.prune_closed();
+        self.subscriptions.write().push(Subscription::new(pattern, Sink::Queue(Arc::clone(&queue)), grants));
+        self.metrics.active_subscriptions.fetch_add(1, Ordering::Relaxed);
+        EventSubscription { queue }
+    }
+
+    /// Publish `event` and wait for its reply.
+    ///
+    /// The reply is the first event of kind `reply_kind` (an `EventPattern`)
+    /// whose `correlation_id` is `event.id`; see `Event::reply_to`. Other
+    /// events of that kind, including replies to other requests, are
+    /// skipped and still reach their own subscribers.
+    ///
+    /// Ordering: the reply listener is attached before `event` is published,
+    /// so a reply sent as soon as a subscriber sees the request is never
+    /// missed. If more than one reply is sent, only the first is returned.
+    /// Replies are read from the broadcast channel; if the bus overflows
+    /// that channel while waiting, a reply can be lost and the call times
+    /// out.
+    pub async fn request(&self, event: Event, reply_kind: &str, timeout: Duration) -> Result<Event> {
+        let reply_pattern = EventPattern::new(reply_kind);
+        let request_id = event.id.clone();
+        let mut replies = self.broadcast.subscribe();
+        self.publish(event)?;
+
+        let wait = async {
+            loop {
+                match replies.recv().await {
+                    Ok(reply)
+                        if reply.correlation_id.as_ref() == Some(&request_id)
+                            && reply_pattern.matches(reply.kind()) =>
+                    {
+                        return Ok(reply);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Err(CoreError::ChannelClosed),
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| CoreError::Timeout(timeout))?
+    }
+
     pub fn subscribe_all(&self) -> broadcast::Receiver<Event> {
         self.broadcast.subscribe()
     }
@@ -187,24 +651,26 @@ impl Default for EventBus {
     }
 }
 
-fn pattern_matches(pattern: &str, kind: &str) -> bool {
-    if pattern == "*" || pattern == kind {
-        return true;
-    }
-    if pattern.ends_with(".*") {
-        let prefix = &pattern[..pattern.len() - 2];
-        return kind.starts_with(prefix);
-    }
-    if pattern.ends_with("*") {
-        let prefix = &pattern[..pattern.len() - 1];
-        return kind.starts_with(prefix);
+/// Outcome of `Runtime::shutdown`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Agents that finished their current work and ran `stop` within the grace period
+    pub clean: Vec<String>,
+    /// Agents that were force-aborted or panicked
+    pub aborted: Vec<String>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.aborted.is_empty()
     }
-    false
 }
 
 pub struct Runtime {
     agents: DashMap<String, AgentHandle>,
+    tasks: DashMap<String, JoinHandle<()>>,
     event_bus: Arc<EventBus>,
+    shutdown_grace: Duration,
     shutdown_tx: mpsc::Sender<()>,
     shutdown_rx: RwLock<Option<mpsc::Receiver<()>>>,
 }
@@ -214,12 +680,29 @@ impl Runtime {
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
         Self {
             agents: DashMap::new(),
+            tasks: DashMap::new(),
             event_bus: Arc::new(EventBus::default()),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
             shutdown_tx,
             shutdown_rx: RwLock::new(Some(shutdown_rx)),
         }
     }
 
+    /// Set how long `shutdown` waits for agents before aborting them
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Capture events no subscription matches on the runtime's bus; read
+    /// them from `event_bus().dead_letters()`. Off by default.
+    pub fn with_dead_letters(self, enabled: bool) -> Self {
+        self.event_bus
+            .capture_dead_letters
+            .store(enabled, Ordering::Relaxed);
+        self
+    }
+
     pub fn event_bus(&self) -> Arc<EventBus> {
         Arc::clone(&self.event_bus)
     }
@@ -236,7 +719,8 @@ impl Runtime {
 
         let handle = AgentHandle {
             name: name.clone(),
-            capabilities: agent.capabilities().clone(),
+            metadata: agent.metadata(),
+            capabilities: Arc::new(RwLock::new(agent.capabilities().clone())),
             sender: event_tx,
             shutdown: shutdown_tx,
         };
@@ -249,34 +733,51 @@ impl Runtime {
         let agent = Arc::new(agent);
         let agent_clone = Arc::clone(&agent);
         let metrics = self.event_bus.metrics();
+        let task_name = name.clone();
 
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             if let Err(e) = agent_clone.start().await {
                 tracing::error!(agent = %name, error = %e, "Agent failed to start");
                 metrics.active_agents.fetch_sub(1, Ordering::Relaxed);
                 return;
             }
 
+            let mut ticker = agent_clone.tick_interval().map(|period| {
+                let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                ticker
+            });
+
+            // A shutdown signal is only observed between events and ticks, so
+            // the current `handle` always runs to completion; `biased` makes a
+            // pending shutdown win over queued events.
             loop {
                 tokio::select! {
+                    biased;
+                    _ = shutdown_rx.recv() => {
+                        tracing::info!(agent = %name, "Agent shutting down");
+                        break;
+                    }
                     Some(event) = event_rx.recv() => {
                         if let Err(e) = agent_clone.handle(event).await {
                             tracing::warn!(agent = %name, error = %e, "Agent failed to handle event");
                         }
                     }
-                    _ = shutdown_rx.recv() => {
-                        tracing::info!(agent = %name, "Agent shutting down");
-                        break;
+                    _ = async { ticker.as_mut().unwrap().tick().await }, if ticker.is_some() => {
+                        if let Err(e) = agent_clone.tick().await {
+                            tracing::warn!(agent = %name, error = %e, "Agent tick failed");
+                        }
                     }
                 }
             }
 
-            if let Err(e) = agent_clone.stop().await {
+            if let Err(e) = agent_clone.shutdown().await {
                 tracing::error!(agent = %name, error = %e, "Agent failed to stop gracefully");
             }
             
             metrics.active_agents.fetch_sub(1, Ordering::Relaxed);
         });
+        self.tasks.insert(task_name, task);
 
         Ok(())
     }
@@ -285,20 +786,88 @@ impl Runtime {
         self.agents.get(name)
     }
 
+    /// Deliver `event` straight to one agent's mailbox. It skips the event
+    /// bus, so no subscriber sees it and no pattern matching runs.
+    pub async fn send_to(&self, name: &str, event: Event) -> Result<()> {
+        let sender = self
+            .agents
+            .get(name)
+            .map(|agent| agent.sender.clone())
+            .ok_or_else(|| CoreError::AgentNotFound(name.to_string()))?;
+        sender.send(event).await.map_err(|_| CoreError::ChannelClosed)?;
+        self.event_bus.metrics().record_delivery();
+        Ok(())
+    }
+
+    #[deprecated(note = "renamed to `send_to`")]
     pub async fn send_to_agent(&self, name: &str, event: Event) -> Result<()> {
+        self.send_to(name, event).await
+    }
+
+    /// Replace an agent's capability set without respawning it.
+    ///
+    /// The swap is atomic: checks through the agent's `AgentHandle` see
+    /// either the old set or the new one. A `CAPABILITIES_CHANGED` event
+    /// describing the grants and revocations is published afterwards.
+    pub fn update_capabilities(&self, name: &str, capabilities: CapabilitySet) -> Result<()> {
         let agent = self
             .agents
             .get(name)
             .ok_or_else(|| CoreError::AgentNotFound(name.to_string()))?;
-        agent.send(event).await
+        let previous = std::mem::replace(&mut *agent.capabilities.write(), capabilities.clone());
+        drop(agent);
+
+        let change = CapabilitiesChanged {
+            agent: name.to_string(),
+            granted: capabilities.difference(&previous),
+            revoked: previous.difference(&capabilities),
+        };
+        tracing::info!(
+            agent = %name,
+            granted = change.granted.len(),
+            revoked = change.revoked.len(),
+            "Agent capabilities updated"
+        );
+        let payload = Payload::inline(bincode::serialize(&change)?);
+        self.event_bus.publish(Event::new("runtime", CAPABILITIES_CHANGED, payload))
+    }
+
+    /// An event whose `source` names a spawned agent is only published
+    /// if the agent's current grants allow its kind
+    fn authorize_publish(&self, event: &Event) -> Result<()> {
+        match self.agents.get(&event.source) {
+            Some(agent) if !agent.check_publish(&event.kind) => Err(CoreError::CapabilityDenied(format!(
+                "agent '{}' may not publish '{}'",
+                event.source, event.kind
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// The grants of agent `name`, if `pattern` is within them
+    fn authorize_subscribe(&self, name: &str, pattern: &str) -> Result<Arc<RwLock<CapabilitySet>>> {
+        let agent = self
+            .agents
+            .get(name)
+            .ok_or_else(|| CoreError::AgentNotFound(name.to_string()))?;
+        if !agent.check_subscribe(pattern) {
+            return Err(CoreError::CapabilityDenied(format!(
+                "agent '{}' may not subscribe to '{}'",
+                name, pattern
+            )));
+        }
+        Ok(Arc::clone(&agent.capabilities))
     }
 
     pub fn publish(&self, event: Event) -> Result<()> {
+        self.authorize_publish(&event)?;
         self.event_bus.publish(event)
     }
 
-    /// Publish multiple events in a batch for improved performance
+    /// Publish multiple events in a batch for improved performance. Nothing
+    /// is published if any event fails `publish`'s capability check.
     pub fn publish_batch(&self, events: &[Event]) -> Result<usize> {
+        events.iter().try_for_each(|event| self.authorize_publish(event))?;
         self.event_bus.publish_batch(events)
     }
 
@@ -306,14 +875,91 @@ impl Runtime {
         self.event_bus.subscribe(pattern)
     }
 
-    pub async fn shutdown(&self) -> Result<()> {
-        for entry in self.agents.iter() {
-            let _ = entry.value().shutdown().await;
+    pub fn subscribe_with(&self, pattern: &str, capacity: usize, overflow: OverflowPolicy) -> EventSubscription {
+        self.event_bus.subscribe_with(pattern, capacity, overflow)
+    }
+
+    /// Subscribe on behalf of agent `name`, which must hold a grant covering
+    /// `pattern`. Revoking the grant with `update_capabilities` stops
+    /// delivery; granting it again resumes it.
+    pub fn subscribe_as(&self, name: &str, pattern: &str) -> Result<mpsc::Receiver<Event>> {
+        let grants = self.authorize_subscribe(name, pattern)?;
+        Ok(self.event_bus.subscribe_guarded(pattern, Some(grants)))
+    }
+
+    /// `subscribe_with` on behalf of agent `name`, checked as in `subscribe_as`
+    pub fn subscribe_with_as(
+        &self,
+        name: &str,
+        pattern: &str,
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> Result<EventSubscription> {
+        let grants = self.authorize_subscribe(name, pattern)?;
+        Ok(self.event_bus.subscribe_with_guarded(pattern, capacity, overflow, Some(grants)))
+    }
+
+    pub async fn publish_async(&self, event: Event) -> Result<()> {
+        self.authorize_publish(&event)?;
+        self.event_bus.publish_async(event).await
+    }
+
+    pub async fn request(&self, event: Event, reply_kind: &str, timeout: Duration) -> Result<Event> {
+        self.authorize_publish(&event)?;
+        self.event_bus.request(event, reply_kind, timeout).await
+    }
+
+    /// Stop all agents, draining in-flight work.
+    ///
+    /// Agents are unregistered first, so `send_to` fails from here on
+    /// and queued events are discarded. Each agent then finishes the event
+    /// it is currently handling and runs its `stop` hook. Agents still busy
+    /// when the grace period (see `with_shutdown_grace`) runs out are
+    /// aborted without `stop` being called.
+    pub async fn shutdown(&self) -> Result<ShutdownReport> {
+        let names: Vec<String> = self.agents.iter().map(|entry| entry.key().clone()).collect();
+        let handles: Vec<AgentHandle> = names
+            .iter()
+            .filter_map(|name| self.agents.remove(name).map(|(_, handle)| handle))
+            .collect();
+        for handle in &handles {
+            // Fails only if the agent already exited, e.g. because `start` failed
+            let _ = handle.shutdown().await;
         }
+        drop(handles);
+
+        let deadline = tokio::time::Instant::now() + self.shutdown_grace;
+        let mut report = ShutdownReport::default();
+        for name in names {
+            let Some((_, mut task)) = self.tasks.remove(&name) else {
+                continue;
+            };
+            let finished = match tokio::time::timeout_at(deadline, &mut task).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    task.abort();
+                    task.await
+                }
+            };
+            match finished {
+                Ok(()) => report.clean.push(name),
+                Err(e) => {
+                    if e.is_cancelled() {
+                        tracing::warn!(agent = %name, "Agent did not stop within grace period, aborted");
+                    } else {
+                        tracing::error!(agent = %name, error = %e, "Agent task panicked");
+                    }
+                    self.event_bus.metrics().active_agents.fetch_sub(1, Ordering::Relaxed);
+                    report.aborted.push(name);
+                }
+            }
+        }
+
         self.shutdown_tx
             .send(())
             .await
-            .map_err(|_| CoreError::RuntimeShutdown)
+            .map_err(|_| CoreError::RuntimeShutdown)?;
+        Ok(report)
     }
 
     /// Get current runtime metrics snapshot
@@ -342,7 +988,7 @@ impl Default for Runtime {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::event::Payload;
+    use crate::capability::Capability;
 
     struct TestAgent {
         name: String,
@@ -463,6 +1109,83 @@ mod tests {
         assert!(grid_rx.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn test_dead_letters() {
+        let bus = EventBus::new(64).with_dead_letters(true);
+        let _sensor_rx = bus.subscribe("sensor.*");
+        let mut dead_rx = bus.dead_letters();
+
+        bus.publish(Event::new("s1", "sensor.data", Payload::inline(vec![])))
+            .unwrap();
+        bus.publish(Event::new("x1", "nobody.listens", Payload::inline(vec![])))
+            .unwrap();
+
+        let dead = dead_rx.recv().await.unwrap();
+        assert_eq!(dead.kind(), "nobody.listens");
+        assert!(dead_rx.try_recv().is_err());
+        assert_eq!(bus.metrics().snapshot().events_dead_lettered, 1);
+
+        // Without capture, unmatched events are still dropped.
+        let bus = EventBus::new(64);
+        let mut dead_rx = bus.dead_letters();
+        bus.publish(Event::new("x1", "nobody.listens", Payload::inline(vec![])))
+            .unwrap();
+        assert!(dead_rx.try_recv().is_err());
+        assert_eq!(bus.metrics().snapshot().events_dead_lettered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_dead_letters() {
+        let runtime = Runtime::new().with_dead_letters(true);
+        let mut dead_rx = runtime.event_bus().dead_letters();
+
+        runtime
+            .publish(Event::new("x1", "nobody.listens", Payload::inline(vec![])))
+            .unwrap();
+        assert_eq!(dead_rx.recv().await.unwrap().kind(), "nobody.listens");
+
+        let runtime = Runtime::new();
+        let mut dead_rx = runtime.event_bus().dead_letters();
+        runtime
+            .publish(Event::new("x1", "nobody.listens", Payload::inline(vec![])))
+            .unwrap();
+        assert!(dead_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_reply() {
+        let bus = Arc::new(EventBus::new(64));
+        let mut requests = bus.subscribe("math.square");
+
+        let responder = Arc::clone(&bus);
+        tokio::spawn(async move {
+            while let Some(req) = requests.recv().await {
+                let Payload::Inline(ref data) = req.payload else { continue };
+                let n = data[0];
+                // An unrelated reply of the same kind must be ignored.
+                responder
+                    .publish(Event::new("noise", "math.result", Payload::inline(vec![0])))
+                    .unwrap();
+                responder
+                    .publish(Event::reply_to(&req, "squarer", "math.result", Payload::inline(vec![n * n])))
+                    .unwrap();
+            }
+        });
+
+        let req = Event::new("client", "math.square", Payload::inline(vec![7]));
+        let req_id = req.id.clone();
+        let reply = bus.request(req, "math.result", Duration::from_secs(1)).await.unwrap();
+        assert_eq!(reply.correlation_id, Some(req_id));
+        assert!(matches!(reply.payload, Payload::Inline(ref d) if d == &vec![49]));
+
+        let unanswered = Event::new("client", "math.cube", Payload::inline(vec![2]));
+        let err = bus
+            .request(unanswered, "math.result", Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CoreError::Timeout(_)));
+    }
+
     #[tokio::test]
     async fn test_high_throughput() {
         let bus = EventBus::new(10000);
@@ -498,10 +1221,10 @@ mod tests {
 
     #[test]
     fn test_pattern_matching() {
-        assert!(pattern_matches("*", "anything"));
-        assert!(pattern_matches("test.*", "test.event"));
-        assert!(pattern_matches("test.event", "test.event"));
-        assert!(!pattern_matches("test.*", "other.event"));
+        assert!(EventPattern::new("*").matches("anything"));
+        assert!(EventPattern::new("test.*").matches("test.event"));
+        assert!(EventPattern::new("test.event").matches("test.event"));
+        assert!(!EventPattern::new("test.*").matches("other.event"));
     }
 
     #[tokio::test]
@@ -563,7 +1286,277 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_send_to_agent() {
+    async fn test_agent_tick_and_metadata() {
+        struct TickingAgent {
+            caps: CapabilitySet,
+            ticks: Arc<AtomicU64>,
+            shut_down: Arc<AtomicU64>,
+        }
+
+        #[async_trait]
+        impl Agent for TickingAgent {
+            fn name(&self) -> &str {
+                "ticking"
+            }
+
+            fn capabilities(&self) -> &CapabilitySet {
+                &self.caps
+            }
+
+            async fn handle(&self, _event: Event) -> Result<()> {
+                Ok(())
+            }
+
+            fn tick_interval(&self) -> Option<Duration> {
+                Some(Duration::from_millis(10))
+            }
+
+            async fn tick(&self) -> Result<()> {
+                self.ticks.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+
+            async fn shutdown(&self) -> Result<()> {
+                self.shut_down.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+
+            fn metadata(&self) -> AgentMetadata {
+                AgentMetadata::new().with_version("1.2.0").with_tag("test")
+            }
+        }
+
+        let ticks = Arc::new(AtomicU64::new(0));
+        let shut_down = Arc::new(AtomicU64::new(0));
+        let runtime = Runtime::new();
+        runtime
+            .spawn_agent(TickingAgent {
+                caps: CapabilitySet::new(),
+                ticks: Arc::clone(&ticks),
+                shut_down: Arc::clone(&shut_down),
+            })
+            .await
+            .unwrap();
+        runtime.spawn_agent(TestAgent { name: "plain".to_string(), caps: CapabilitySet::new() }).await.unwrap();
+
+        let metadata = runtime.get_agent("ticking").unwrap().metadata().clone();
+        assert_eq!(metadata.version.as_deref(), Some("1.2.0"));
+        assert_eq!(metadata.tags, vec!["test"]);
+        assert_eq!(*runtime.get_agent("plain").unwrap().metadata(), AgentMetadata::default());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(ticks.load(Ordering::Relaxed) >= 2);
+
+        runtime.shutdown().await.unwrap();
+        assert_eq!(shut_down.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_and_aborts() {
+        struct SlowAgent {
+            name: String,
+            caps: CapabilitySet,
+            work: Duration,
+            handled: Arc<AtomicU64>,
+            stopped: Arc<AtomicU64>,
+        }
+
+        #[async_trait]
+        impl Agent for SlowAgent {
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn capabilities(&self) -> &CapabilitySet {
+                &self.caps
+            }
+
+            async fn handle(&self, _event: Event) -> Result<()> {
+                tokio::time::sleep(self.work).await;
+                self.handled.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+
+            async fn stop(&self) -> Result<()> {
+                self.stopped.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let handled = Arc::new(AtomicU64::new(0));
+        let stopped = Arc::new(AtomicU64::new(0));
+        let runtime = Runtime::new().with_shutdown_grace(Duration::from_millis(200));
+        for (name, work) in [("quick", Duration::from_millis(30)), ("stuck", Duration::from_secs(30))] {
+            runtime
+                .spawn_agent(SlowAgent {
+                    name: name.to_string(),
+                    caps: CapabilitySet::new(),
+                    work,
+                    handled: Arc::clone(&handled),
+                    stopped: Arc::clone(&stopped),
+                })
+                .await
+                .unwrap();
+            let event = Event::new("test", "work", Payload::inline(vec![]));
+            runtime.send_to(name, event).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let report = runtime.shutdown().await.unwrap();
+        assert_eq!(report.clean, vec!["quick".to_string()]);
+        assert_eq!(report.aborted, vec!["stuck".to_string()]);
+        assert!(!report.is_clean());
+
+        // The in-flight event finished and only the clean agent ran `stop`
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+        assert_eq!(stopped.load(Ordering::SeqCst), 1);
+        assert_eq!(runtime.metrics().active_agents, 0);
+
+        let late = Event::new("test", "work", Payload::inline(vec![]));
+        assert!(matches!(runtime.send_to("quick", late).await, Err(CoreError::AgentNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_update_capabilities() {
+        let runtime = Runtime::new();
+        let agent = TestAgent {
+            name: "admin-target".to_string(),
+            caps: CapabilitySet::new().with_capability(Capability::EventBus {
+                publish: vec!["sensor.*".to_string()],
+                subscribe: vec![],
+            }),
+        };
+        runtime.spawn_agent(agent).await.unwrap();
+        let mut changes = runtime.subscribe(CAPABILITIES_CHANGED);
+
+        assert!(runtime.get_agent("admin-target").unwrap().check_publish("sensor.temp"));
+
+        let tightened = CapabilitySet::new().with_capability(Capability::EventBus {
+            publish: vec!["sensor.temp".to_string()],
+            subscribe: vec![],
+        });
+        runtime.update_capabilities("admin-target", tightened.clone()).unwrap();
+
+        let handle = runtime.get_agent("admin-target").unwrap();
+        assert!(handle.check_publish("sensor.temp"));
+        assert!(!handle.check_publish("sensor.humidity"));
+        drop(handle);
+
+        let event = changes.recv().await.unwrap();
+        let Payload::Inline(data) = event.payload else { panic!("expected inline payload") };
+        let change: CapabilitiesChanged = bincode::deserialize(&data).unwrap();
+        assert_eq!(change.agent, "admin-target");
+        assert!(change.granted.is_subset_of(&tightened));
+        assert_eq!((change.granted.len(), change.revoked.len()), (1, 1));
+
+        assert!(matches!(
+            runtime.update_capabilities("missing", CapabilitySet::new()),
+            Err(CoreError::AgentNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_runtime_enforces_agent_grants() {
+        let runtime = Runtime::new();
+        let grants = |publish: &str, subscribe: &str| {
+            CapabilitySet::new().with_capability(Capability::EventBus {
+                publish: vec![publish.to_string()],
+                subscribe: vec![subscribe.to_string()],
+            })
+        };
+        runtime
+            .spawn_agent(TestAgent { name: "sensor".to_string(), caps: grants("sensor.*", "grid.*") })
+            .await
+            .unwrap();
+
+        let allowed = Event::new("sensor", "sensor.temp", Payload::inline(vec![]));
+        let denied = Event::new("sensor", "grid.msg", Payload::inline(vec![]));
+        assert!(runtime.publish(allowed.clone()).is_ok());
+        assert!(matches!(runtime.publish(denied.clone()), Err(CoreError::CapabilityDenied(_))));
+        assert!(matches!(runtime.publish_async(denied.clone()).await, Err(CoreError::CapabilityDenied(_))));
+        assert!(matches!(
+            runtime.publish_batch(&[allowed.clone(), denied]),
+            Err(CoreError::CapabilityDenied(_))
+        ));
+        // Sources that are not agents are not checked
+        assert!(runtime.publish(Event::new("external", "grid.msg", Payload::inline(vec![]))).is_ok());
+
+        assert!(matches!(runtime.subscribe_as("sensor", "sensor.*"), Err(CoreError::CapabilityDenied(_))));
+        assert!(matches!(runtime.subscribe_as("missing", "grid.*"), Err(CoreError::AgentNotFound(_))));
+        let mut inbox = runtime.subscribe_as("sensor", "grid.*").unwrap();
+        runtime.publish(Event::new("external", "grid.one", Payload::inline(vec![]))).unwrap();
+        assert_eq!(inbox.recv().await.unwrap().kind, "grid.one");
+
+        // A revoked grant takes effect on the next publish
+        runtime.update_capabilities("sensor", grants("sensor.*", "agent.*")).unwrap();
+        assert!(runtime.publish(allowed).is_ok());
+        runtime.publish(Event::new("external", "grid.two", Payload::inline(vec![]))).unwrap();
+        assert!(inbox.try_recv().is_err());
+    }
+
+    fn numbered(i: u8) -> Event {
+        Event::new("test", "test.seq", Payload::inline(vec![i]))
+    }
+
+    fn number(event: &Event) -> u8 {
+        let Payload::Inline(data) = &event.payload else { panic!("expected inline payload") };
+        data[0]
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_lags() {
+        let bus = EventBus::new(64);
+        let mut recent = bus.subscribe_with("test.*", 4, OverflowPolicy::DropOldest);
+        let mut strict = bus.subscribe_with("test.*", 4, OverflowPolicy::Error);
+
+        // Neither subscriber reads while ten events arrive
+        for i in 0..10 {
+            bus.publish(numbered(i)).unwrap();
+        }
+
+        for expected in 6..10 {
+            assert_eq!(number(&recent.recv().await.unwrap()), expected);
+        }
+        assert_eq!(recent.lagged(), 6);
+
+        for expected in 0..4 {
+            assert_eq!(number(&strict.recv().await.unwrap()), expected);
+        }
+        assert!(matches!(strict.recv().await, Err(CoreError::Lagged(6))));
+        bus.publish(numbered(10)).unwrap();
+        assert_eq!(number(&strict.recv().await.unwrap()), 10);
+        assert_eq!(strict.lagged(), 6);
+
+        assert_eq!(bus.metrics().snapshot().events_lagged, 12);
+    }
+
+    #[tokio::test]
+    async fn test_block_overflow_waits_for_subscriber() {
+        let bus = Arc::new(EventBus::new(64));
+        let mut blocking = bus.subscribe_with("test.*", 2, OverflowPolicy::Block);
+
+        let publisher = {
+            let bus = Arc::clone(&bus);
+            tokio::spawn(async move {
+                for i in 0..8 {
+                    bus.publish_async(numbered(i)).await.unwrap();
+                }
+            })
+        };
+
+        for expected in 0..8 {
+            assert_eq!(number(&blocking.recv().await.unwrap()), expected);
+        }
+        publisher.await.unwrap();
+        assert_eq!(blocking.lagged(), 0);
+        assert_eq!(bus.metrics().snapshot().events_lagged, 0);
+
+        drop(bus);
+        assert!(matches!(blocking.recv().await, Err(CoreError::ChannelClosed)));
+    }
+
+    #[tokio::test]
+    async fn test_send_to() {
         let runtime = Runtime::new();
         let agent = TestAgent {
             name: "target-agent".to_string(),
@@ -571,16 +1564,22 @@ mod tests {
         };
 
         runtime.spawn_agent(agent).await.unwrap();
+        let mut bus = runtime.subscribe("test.*");
 
         let event = Event::new("test", "test.event", Payload::inline(b"data".to_vec()));
-        runtime.send_to_agent("target-agent", event).await.unwrap();
+        runtime.send_to("target-agent", event).await.unwrap();
+
+        // Point-to-point: nothing reaches the bus
+        assert!(bus.try_recv().is_err());
+        assert_eq!(runtime.metrics().events_delivered, 1);
+        assert_eq!(runtime.metrics().events_published, 0);
     }
 
     #[tokio::test]
     async fn test_send_to_nonexistent_agent() {
         let runtime = Runtime::new();
         let event = Event::new("test", "test.event", Payload::inline(b"data".to_vec()));
-        let result = runtime.send_to_agent("nonexistent", event).await;
+        let result = runtime.send_to("nonexistent", event).await;
         assert!(result.is_err());
     }
 
@@ -693,6 +1692,27 @@ mod tests {
         assert_eq!(metrics.active_subscriptions, 1);
     }
 
+    #[tokio::test]
+    async fn test_dropped_subscriptions_are_pruned() {
+        let runtime = Runtime::new();
+        let kept = runtime.subscribe("prune.*");
+        let channel = runtime.subscribe("prune.*");
+        let queue = runtime.subscribe_with("prune.*", 4, OverflowPolicy::DropOldest);
+        assert_eq!(runtime.metrics().active_subscriptions, 3);
+        drop(channel);
+        drop(queue);
+
+        let event = Event::new("test", "prune.event", Payload::inline(vec![]));
+        runtime.publish(event).unwrap();
+        assert_eq!(runtime.metrics().active_subscriptions, 1);
+        assert_eq!(runtime.metrics().events_dropped, 0);
+
+        // Subscribing also sweeps receivers that were dropped since
+        drop(kept);
+        let _fresh = runtime.subscribe("other.*");
+        assert_eq!(runtime.metrics().active_subscriptions, 1);
+    }
+
     #[tokio::test]
     async fn test_metrics_agent_tracking() {
         let runtime = Runtime::new();
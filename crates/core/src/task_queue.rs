@@ -4,11 +4,16 @@
 //! Each peer has a queue and processes tasks based on their capacity.
 
 use serde::{Deserialize, Serialize};
-use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::cmp::Ordering;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Mutex};
-use tracing::debug;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify, RwLock};
+use tracing::{debug, warn};
 
 /// A tensor chunk to be processed
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +41,23 @@ pub struct TensorChunk {
     pub created_at: u64,
 }
 
+impl TensorChunk {
+    /// Check that `start_layer..=end_layer` lies within a model of
+    /// `total_layers` layers. The range comes from the sender and
+    /// `verification_transform` does work for every layer in it, so
+    /// receivers call this before enqueueing.
+    pub fn check_layers(&self, total_layers: u32) -> Result<(), QueueError> {
+        if self.start_layer > self.end_layer || self.end_layer >= total_layers {
+            return Err(QueueError::LayerRange {
+                start: self.start_layer,
+                end: self.end_layer,
+                total: total_layers,
+            });
+        }
+        Ok(())
+    }
+}
+
 impl Eq for TensorChunk {}
 
 impl PartialEq for TensorChunk {
@@ -70,6 +92,49 @@ pub struct ProcessedChunk {
     pub processor_node: String,
 }
 
+/// Frame on a peer's tensor socket. Work chunks and returned results share
+/// the port, so every frame says which one it carries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TensorMsg {
+    /// Work to enqueue
+    Chunk(TensorChunk),
+    /// Result of a chunk this node sent out earlier
+    Result(ProcessedChunk),
+    /// Receipt for a `Chunk` or `Result`
+    Ack,
+}
+
+/// Domain separation for `verification_transform` keys
+const VERIFY_CONTEXT: &str = "cortexOS 2024 chunk verification transform v1";
+
+/// Deterministic stand-in for running a chunk through its layers.
+///
+/// For every layer in `start_layer..=end_layer` the data is XORed with a
+/// BLAKE3 keystream keyed on `(task_id, chunk_idx, layer)`. The output
+/// depends on every input byte, the chunk identity and the layer range, so
+/// a result that was corrupted, computed for another chunk, or faked
+/// without the input will not match. This exercises the distribution path
+/// end-to-end without a real model.
+pub fn verification_transform(chunk: &TensorChunk) -> Vec<u8> {
+    let mut data = chunk.tensor_data.clone();
+    let mut keystream = vec![0u8; data.len()];
+    for layer in chunk.start_layer..=chunk.end_layer {
+        let mut material = Vec::with_capacity(chunk.task_id.len() + 8);
+        material.extend_from_slice(chunk.task_id.as_bytes());
+        material.extend_from_slice(&chunk.chunk_idx.to_le_bytes());
+        material.extend_from_slice(&layer.to_le_bytes());
+        let key = blake3::derive_key(VERIFY_CONTEXT, &material);
+
+        blake3::Hasher::new_keyed(&key)
+            .finalize_xof()
+            .fill(&mut keystream);
+        for (byte, k) in data.iter_mut().zip(&keystream) {
+            *byte ^= k;
+        }
+    }
+    data
+}
+
 /// Task queue for a peer node
 pub struct TaskQueue {
     /// Priority queue for incoming chunks
@@ -78,10 +143,12 @@ pub struct TaskQueue {
     processing: Arc<RwLock<Option<TensorChunk>>>,
     /// Completed chunks waiting to be sent back
     completed: Arc<RwLock<VecDeque<ProcessedChunk>>>,
-    /// Queue capacity (based on device)
-    max_queue_size: usize,
+    /// Queue capacity (based on device, adjustable at runtime)
+    max_queue_size: AtomicUsize,
     /// Stats
     stats: Arc<RwLock<QueueStats>>,
+    /// Write-ahead log, when persistence is enabled
+    wal: Option<Arc<Mutex<TaskWal>>>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -99,16 +166,40 @@ impl TaskQueue {
             queue: Arc::new(Mutex::new(BinaryHeap::new())),
             processing: Arc::new(RwLock::new(None)),
             completed: Arc::new(RwLock::new(VecDeque::new())),
-            max_queue_size,
+            max_queue_size: AtomicUsize::new(max_queue_size),
             stats: Arc::new(RwLock::new(QueueStats::default())),
+            wal: None,
+        }
+    }
+
+    /// Create a queue backed by a write-ahead log at `path`.
+    ///
+    /// Every enqueued chunk is appended to the log and every completion is
+    /// recorded, so after a crash the chunks that were queued or in flight
+    /// (but never completed) are replayed into the new queue.
+    pub fn with_persistence(max_queue_size: usize, path: impl AsRef<Path>) -> Result<Self, QueueError> {
+        let (wal, pending) = TaskWal::open(path.as_ref())?;
+        let mut queue = Self::new(max_queue_size);
+
+        if !pending.is_empty() {
+            debug!("♻️ Replaying {} un-completed chunks from {:?}", pending.len(), wal.path);
         }
+        let stats = QueueStats {
+            total_received: pending.len() as u64,
+            current_queue_size: pending.len(),
+            ..Default::default()
+        };
+        queue.queue = Arc::new(Mutex::new(pending.into_iter().collect()));
+        queue.stats = Arc::new(RwLock::new(stats));
+        queue.wal = Some(Arc::new(Mutex::new(wal)));
+        Ok(queue)
     }
     
     /// Enqueue a tensor chunk for processing
     pub async fn enqueue(&self, chunk: TensorChunk) -> Result<(), QueueError> {
         let mut queue = self.queue.lock().await;
         
-        if queue.len() >= self.max_queue_size {
+        if queue.len() >= self.max_queue_size() {
             let mut stats = self.stats.write().await;
             stats.total_dropped += 1;
             return Err(QueueError::QueueFull);
@@ -117,6 +208,9 @@ impl TaskQueue {
         debug!("📥 Enqueued chunk {}/{} for task {}", 
                chunk.chunk_idx, chunk.total_chunks, &chunk.task_id[..8]);
         
+        if let Some(wal) = &self.wal {
+            wal.lock().await.append(&WalRecord::Enqueued(chunk.clone()))?;
+        }
         queue.push(chunk);
         
         let mut stats = self.stats.write().await;
@@ -144,6 +238,16 @@ impl TaskQueue {
     pub async fn complete(&self, result: ProcessedChunk) {
         *self.processing.write().await = None;
         
+        if let Some(wal) = &self.wal {
+            let record = WalRecord::Completed {
+                task_id: result.task_id.clone(),
+                chunk_idx: result.chunk_idx,
+            };
+            if let Err(e) = wal.lock().await.append(&record) {
+                warn!("Failed to log completion of chunk {}: {}", result.chunk_idx, e);
+            }
+        }
+        
         let mut completed = self.completed.write().await;
         completed.push_back(result);
         
@@ -157,6 +261,16 @@ impl TaskQueue {
         completed.drain(..).collect()
     }
     
+    pub fn max_queue_size(&self) -> usize {
+        self.max_queue_size.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Change the capacity. Chunks already queued beyond a lowered limit
+    /// are kept; new ones are rejected until the queue drains below it.
+    pub fn set_max_queue_size(&self, max_queue_size: usize) {
+        self.max_queue_size.store(max_queue_size, AtomicOrdering::Relaxed);
+    }
+
     /// Get queue stats
     pub async fn stats(&self) -> QueueStats {
         self.stats.read().await.clone()
@@ -179,6 +293,118 @@ pub enum QueueError {
     QueueFull,
     #[error("Invalid chunk")]
     InvalidChunk,
+    #[error("Layers {start}-{end} are outside a {total}-layer model")]
+    LayerRange { start: u32, end: u32, total: u32 },
+    #[error("Persistence error: {0}")]
+    Persistence(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum WalRecord {
+    Enqueued(TensorChunk),
+    Completed { task_id: String, chunk_idx: u32 },
+}
+
+/// Largest `WalRecord` the log writes or replays. A longer length prefix
+/// can only come from corruption.
+const MAX_WAL_RECORD: usize = 256 * 1024 * 1024;
+
+/// Append-only log of length-prefixed bincode `WalRecord`s
+struct TaskWal {
+    path: PathBuf,
+    file: File,
+}
+
+impl TaskWal {
+    /// Open the log, returning it with the chunks that were never completed.
+    /// The log is compacted down to those chunks on open, which also drops
+    /// any corrupt tail `replay` stopped at.
+    fn open(path: &Path) -> Result<(Self, Vec<TensorChunk>), QueueError> {
+        let pending = match File::open(path) {
+            Ok(file) => Self::replay(file, path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(QueueError::Persistence(e.to_string())),
+        };
+
+        // Compact into a side file and swap it in, so a crash here leaves
+        // either the old log or the new one intact.
+        let compact_path = path.with_extension("compact");
+        let mut wal = Self {
+            path: compact_path.clone(),
+            file: File::create(&compact_path).map_err(|e| QueueError::Persistence(e.to_string()))?,
+        };
+        for chunk in &pending {
+            wal.append(&WalRecord::Enqueued(chunk.clone()))?;
+        }
+        std::fs::rename(&compact_path, path).map_err(|e| QueueError::Persistence(e.to_string()))?;
+        wal.path = path.to_path_buf();
+        wal.file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| QueueError::Persistence(e.to_string()))?;
+        Ok((wal, pending))
+    }
+
+    /// Read records up to the end of the log or the first one that is
+    /// torn, oversized or undecodable. Everything before it is kept.
+    fn replay(file: File, path: &Path) -> Vec<TensorChunk> {
+        let mut reader = BufReader::new(file);
+        let mut enqueued = Vec::new();
+        let mut completed = HashSet::new();
+        let mut records = 0usize;
+
+        loop {
+            let mut len = [0u8; 4];
+            if reader.read_exact(&mut len).is_err() {
+                break;
+            }
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_WAL_RECORD {
+                warn!("Task log {:?} record {} claims {} bytes, dropping the rest of the log", path, records, len);
+                break;
+            }
+            let mut buf = vec![0u8; len];
+            // A torn final record means we crashed mid-write
+            if reader.read_exact(&mut buf).is_err() {
+                warn!("Task log {:?} record {} is truncated, dropping it", path, records);
+                break;
+            }
+            match bincode::deserialize(&buf) {
+                Ok(WalRecord::Enqueued(chunk)) => enqueued.push(chunk),
+                Ok(WalRecord::Completed { task_id, chunk_idx }) => {
+                    completed.insert((task_id, chunk_idx));
+                }
+                Err(e) => {
+                    warn!("Task log {:?} record {} is corrupt ({}), dropping the rest of the log", path, records, e);
+                    break;
+                }
+            }
+            records += 1;
+        }
+
+        enqueued
+            .into_iter()
+            .filter(|c| !completed.contains(&(c.task_id.clone(), c.chunk_idx)))
+            .collect()
+    }
+
+    fn append(&mut self, record: &WalRecord) -> Result<(), QueueError> {
+        let bytes = bincode::serialize(record).map_err(|e| QueueError::Persistence(e.to_string()))?;
+        if bytes.len() > MAX_WAL_RECORD {
+            return Err(QueueError::Persistence(format!(
+                "Record of {} bytes exceeds the {} byte log limit",
+                bytes.len(),
+                MAX_WAL_RECORD
+            )));
+        }
+        let mut frame = Vec::with_capacity(4 + bytes.len());
+        frame.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&bytes);
+        self.file
+            .write_all(&frame)
+            .and_then(|_| self.file.flush())
+            .map_err(|e| QueueError::Persistence(e.to_string()))
+    }
 }
 
 /// Tracks partially assembled responses
@@ -187,6 +413,14 @@ pub struct ResponseAssembler {
     chunks: Arc<RwLock<HashMap<String, Vec<ProcessedChunk>>>>,
     /// Expected total chunks per task
     expected: Arc<RwLock<HashMap<String, u32>>>,
+    /// When each task was registered, for timeouts
+    registered_at: Arc<RwLock<HashMap<String, Instant>>>,
+    /// How long to wait for a task's chunks before giving up on the rest
+    timeout: Option<Duration>,
+    /// Wakes `assemble_or_partial` waiters when a chunk arrives
+    arrivals: Arc<Notify>,
+    /// Digest of the expected `verification_transform` output per chunk
+    expected_digests: Arc<RwLock<HashMap<(String, u32), blake3::Hash>>>,
 }
 
 impl ResponseAssembler {
@@ -194,35 +428,96 @@ impl ResponseAssembler {
         Self {
             chunks: Arc::new(RwLock::new(HashMap::new())),
             expected: Arc::new(RwLock::new(HashMap::new())),
+            registered_at: Arc::new(RwLock::new(HashMap::new())),
+            timeout: None,
+            arrivals: Arc::new(Notify::new()),
+            expected_digests: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    /// Stop waiting for a task's chunks `timeout` after it was registered
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
     
     /// Register a new task with expected chunk count
     pub async fn register_task(&self, task_id: &str, total_chunks: u32) {
         self.expected.write().await.insert(task_id.to_string(), total_chunks);
         self.chunks.write().await.insert(task_id.to_string(), Vec::new());
+        self.registered_at.write().await.insert(task_id.to_string(), Instant::now());
     }
     
-    /// Add a completed chunk
+    /// Expect `chunk`'s result to be its `verification_transform` output.
+    ///
+    /// `add_chunk` rejects results for this chunk that do not match.
+    pub async fn expect_verified(&self, chunk: &TensorChunk) {
+        let digest = blake3::hash(&verification_transform(chunk));
+        self.expected_digests
+            .write()
+            .await
+            .insert((chunk.task_id.clone(), chunk.chunk_idx), digest);
+    }
+
+    /// Add a completed chunk. A repeated `chunk_idx` replaces the earlier one.
+    ///
+    /// A chunk registered with `expect_verified` whose data does not match
+    /// the expected transform is dropped and reported as `Rejected`.
     pub async fn add_chunk(&self, chunk: ProcessedChunk) -> AssemblyStatus {
         let task_id = chunk.task_id.clone();
+
+        let key = (task_id.clone(), chunk.chunk_idx);
+        if let Some(expected) = self.expected_digests.read().await.get(&key) {
+            if blake3::hash(&chunk.result_data) != *expected {
+                warn!(
+                    "Rejected chunk {} of task {} from {}: verification failed",
+                    chunk.chunk_idx, task_id, chunk.processor_node
+                );
+                return AssemblyStatus::Rejected {
+                    chunk_idx: chunk.chunk_idx,
+                };
+            }
+        }
         
         let mut chunks = self.chunks.write().await;
         let task_chunks = chunks.entry(task_id.clone()).or_insert_with(Vec::new);
+        task_chunks.retain(|c| c.chunk_idx != chunk.chunk_idx);
         task_chunks.push(chunk);
+        let received = task_chunks.len() as u32;
+        drop(chunks);
+        self.arrivals.notify_waiters();
         
         let expected = self.expected.read().await;
         let total = *expected.get(&task_id).unwrap_or(&0);
         
-        if task_chunks.len() as u32 >= total {
+        if received >= total {
             AssemblyStatus::Complete
         } else {
             AssemblyStatus::Partial {
-                received: task_chunks.len() as u32,
+                received,
                 total,
             }
         }
     }
+
+    /// Chunk indices of a task that have not arrived yet
+    pub async fn missing_chunks(&self, task_id: &str) -> Vec<u32> {
+        let total = match self.expected.read().await.get(task_id) {
+            Some(total) => *total,
+            None => return Vec::new(),
+        };
+        let chunks = self.chunks.read().await;
+        let received: HashSet<u32> = chunks
+            .get(task_id)
+            .map(|c| c.iter().map(|c| c.chunk_idx).collect())
+            .unwrap_or_default();
+        (0..total).filter(|idx| !received.contains(idx)).collect()
+    }
+
+    /// Whether every expected chunk of a registered task has arrived
+    pub async fn is_complete(&self, task_id: &str) -> bool {
+        self.expected.read().await.contains_key(task_id) && self.missing_chunks(task_id).await.is_empty()
+    }
     
     /// Get all chunks for a completed task, ordered by index
     pub async fn get_assembled(&self, task_id: &str) -> Option<Vec<ProcessedChunk>> {
@@ -234,15 +529,65 @@ impl ResponseAssembler {
         
         // Clean up expected
         self.expected.write().await.remove(task_id);
+        self.registered_at.write().await.remove(task_id);
+        self.expected_digests.write().await.retain(|(id, _), _| id != task_id);
         
         Some(task_chunks)
     }
+
+    /// Wait for a task to complete, or until its timeout elapses.
+    ///
+    /// A complete task is removed from the assembler. On timeout the chunks
+    /// received so far are returned together with the missing indices and
+    /// the task stays registered, so the caller can re-delegate just the
+    /// gaps and keep adding chunks. Without a timeout this waits until the
+    /// task completes. Returns `None` for an unknown task.
+    pub async fn assemble_or_partial(&self, task_id: &str) -> Option<AssemblyResult> {
+        let registered = *self.registered_at.read().await.get(task_id)?;
+        let deadline = self.timeout.map(|t| registered + t);
+
+        loop {
+            let arrival = self.arrivals.notified();
+            tokio::pin!(arrival);
+            arrival.as_mut().enable();
+
+            if self.is_complete(task_id).await {
+                return self.get_assembled(task_id).await.map(AssemblyResult::Complete);
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline.into(), arrival).await.is_err() {
+                        let missing = self.missing_chunks(task_id).await;
+                        let mut received = self.chunks.read().await.get(task_id).cloned()?;
+                        received.sort_by_key(|c| c.chunk_idx);
+                        return Some(AssemblyResult::Partial { received, missing });
+                    }
+                }
+                None => arrival.await,
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum AssemblyStatus {
     Complete,
     Partial { received: u32, total: u32 },
+    /// The result failed verification and was not stored
+    Rejected { chunk_idx: u32 },
+}
+
+/// Outcome of `ResponseAssembler::assemble_or_partial`
+#[derive(Debug, Clone)]
+pub enum AssemblyResult {
+    /// Every chunk arrived, ordered by index
+    Complete(Vec<ProcessedChunk>),
+    /// Timed out; `missing` lists the chunk indices to re-delegate
+    Partial {
+        received: Vec<ProcessedChunk>,
+        missing: Vec<u32>,
+    },
 }
 
 #[cfg(test)]
@@ -291,5 +636,221 @@ mod tests {
         let second = queue.dequeue().await.unwrap();
         assert_eq!(second.task_id, "task1");
     }
-}
 
+    fn test_chunk(task_id: &str, chunk_idx: u32) -> TensorChunk {
+        TensorChunk {
+            task_id: task_id.to_string(),
+            chunk_idx,
+            total_chunks: 3,
+            start_layer: 0,
+            end_layer: 5,
+            tensor_data: vec![chunk_idx as u8; 4],
+            shape: vec![1, 4],
+            dtype: "F32".to_string(),
+            source_node: "node1".to_string(),
+            priority: 1,
+            created_at: 100 + chunk_idx as u64,
+        }
+    }
+
+    #[test]
+    fn test_chunk_layer_range_is_checked() {
+        let chunk = test_chunk("task-layers", 0);
+        assert!(chunk.check_layers(6).is_ok());
+        assert!(matches!(chunk.check_layers(5), Err(QueueError::LayerRange { end: 5, total: 5, .. })));
+
+        let huge = TensorChunk { end_layer: u32::MAX, ..chunk.clone() };
+        assert!(huge.check_layers(24).is_err());
+        let inverted = TensorChunk { start_layer: 4, end_layer: 2, ..chunk };
+        assert!(inverted.check_layers(24).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queue_resize() {
+        let queue = TaskQueue::new(1);
+        queue.enqueue(test_chunk("resize-task", 0)).await.unwrap();
+        assert!(matches!(queue.enqueue(test_chunk("resize-task", 1)).await, Err(QueueError::QueueFull)));
+
+        queue.set_max_queue_size(2);
+        queue.enqueue(test_chunk("resize-task", 1)).await.unwrap();
+
+        // Shrinking keeps what is queued but rejects new chunks
+        queue.set_max_queue_size(1);
+        assert_eq!(queue.len().await, 2);
+        assert!(queue.enqueue(test_chunk("resize-task", 2)).await.is_err());
+        queue.dequeue().await.unwrap();
+        queue.dequeue().await.unwrap();
+        queue.enqueue(test_chunk("resize-task", 2)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queue_recovers_after_crash() {
+        let path = std::env::temp_dir().join(format!("cortex-wal-{}.log", uuid::Uuid::new_v4()));
+        let done_idx;
+
+        {
+            let queue = TaskQueue::with_persistence(10, &path).unwrap();
+            for idx in 0..3 {
+                queue.enqueue(test_chunk("task-recovery", idx)).await.unwrap();
+            }
+
+            // Finish one chunk, start another, then "crash".
+            let done = queue.dequeue().await.unwrap();
+            done_idx = done.chunk_idx;
+            queue.complete(ProcessedChunk {
+                task_id: done.task_id.clone(),
+                chunk_idx: done.chunk_idx,
+                total_chunks: done.total_chunks,
+                result_data: vec![],
+                result_shape: vec![],
+                processing_time_ms: 1,
+                processor_node: "node1".to_string(),
+            }).await;
+            let _in_flight = queue.dequeue().await.unwrap();
+        }
+
+        let queue = TaskQueue::with_persistence(10, &path).unwrap();
+        assert_eq!(queue.len().await, 2);
+        assert_eq!(queue.stats().await.current_queue_size, 2);
+
+        let mut resumed = Vec::new();
+        while let Some(chunk) = queue.dequeue().await {
+            resumed.push(chunk.chunk_idx);
+        }
+        resumed.sort();
+        assert_eq!(resumed.len(), 2);
+        assert!(!resumed.contains(&done_idx));
+
+        drop(queue);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_queue_recovers_from_corrupt_log_tail() {
+        let path = std::env::temp_dir().join(format!("cortex-wal-{}.log", uuid::Uuid::new_v4()));
+        {
+            let queue = TaskQueue::with_persistence(10, &path).unwrap();
+            for idx in 0..2 {
+                queue.enqueue(test_chunk("task-corrupt", idx)).await.unwrap();
+            }
+        }
+        let good_len = std::fs::metadata(&path).unwrap().len();
+
+        for tail in [vec![0xff; 4], vec![3, 0, 0, 0, 0xde, 0xad, 0xbe], vec![40, 0, 0, 0, 1]] {
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(&tail).unwrap();
+            drop(file);
+
+            // The good records survive and the tail is compacted away
+            let queue = TaskQueue::with_persistence(10, &path).unwrap();
+            assert_eq!(queue.len().await, 2);
+            drop(queue);
+            assert_eq!(std::fs::metadata(&path).unwrap().len(), good_len);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn processed(task_id: &str, chunk_idx: u32) -> ProcessedChunk {
+        ProcessedChunk {
+            task_id: task_id.to_string(),
+            chunk_idx,
+            total_chunks: 3,
+            result_data: vec![chunk_idx as u8],
+            result_shape: vec![1],
+            processing_time_ms: 1,
+            processor_node: "node1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assembler_returns_partial_on_timeout() {
+        let assembler = ResponseAssembler::new().with_timeout(Duration::from_millis(50));
+        assembler.register_task("task-partial", 3).await;
+        assembler.add_chunk(processed("task-partial", 0)).await;
+        assembler.add_chunk(processed("task-partial", 2)).await;
+
+        assert!(!assembler.is_complete("task-partial").await);
+        assert_eq!(assembler.missing_chunks("task-partial").await, vec![1]);
+
+        match assembler.assemble_or_partial("task-partial").await {
+            Some(AssemblyResult::Partial { received, missing }) => {
+                assert_eq!(missing, vec![1]);
+                let idxs: Vec<u32> = received.iter().map(|c| c.chunk_idx).collect();
+                assert_eq!(idxs, vec![0, 2]);
+            }
+            other => panic!("expected partial result, got {:?}", other),
+        }
+
+        // The gap can still be filled after re-delegation.
+        assembler.add_chunk(processed("task-partial", 1)).await;
+        assert!(matches!(
+            assembler.assemble_or_partial("task-partial").await,
+            Some(AssemblyResult::Complete(chunks)) if chunks.len() == 3
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_assembler_completes_when_last_chunk_arrives() {
+        let assembler = Arc::new(ResponseAssembler::new().with_timeout(Duration::from_secs(5)));
+        assembler.register_task("task-complete", 2).await;
+        assembler.add_chunk(processed("task-complete", 0)).await;
+
+        let late = Arc::clone(&assembler);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            late.add_chunk(processed("task-complete", 1)).await;
+        });
+
+        match assembler.assemble_or_partial("task-complete").await {
+            Some(AssemblyResult::Complete(chunks)) => assert_eq!(chunks.len(), 2),
+            other => panic!("expected complete result, got {:?}", other),
+        }
+        assert!(assembler.assemble_or_partial("task-complete").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_assembler_rejects_unverified_results() {
+        let assembler = ResponseAssembler::new();
+        assembler.register_task("task-verify", 2).await;
+
+        let chunks = [test_chunk("task-verify", 0), test_chunk("task-verify", 1)];
+        for chunk in &chunks {
+            assembler.expect_verified(chunk).await;
+        }
+
+        // Same input, different chunk identity: a different output.
+        let out0 = verification_transform(&chunks[0]);
+        assert_eq!(out0, verification_transform(&chunks[0]));
+        let mut relabeled = chunks[0].clone();
+        relabeled.chunk_idx = 1;
+        assert_ne!(out0, verification_transform(&relabeled));
+
+        let mut result = processed("task-verify", 0);
+        result.result_data = out0.clone();
+        assert!(matches!(
+            assembler.add_chunk(result.clone()).await,
+            AssemblyStatus::Partial { received: 1, total: 2 }
+        ));
+
+        // A corrupted result, and one faked with the old "+1" placeholder.
+        let mut corrupted = processed("task-verify", 1);
+        corrupted.result_data = verification_transform(&chunks[1]);
+        corrupted.result_data[0] ^= 0x01;
+        let mut faked = processed("task-verify", 1);
+        faked.result_data = chunks[1].tensor_data.iter().map(|b| b.wrapping_add(1)).collect();
+        for bad in [corrupted, faked] {
+            assert!(matches!(
+                assembler.add_chunk(bad).await,
+                AssemblyStatus::Rejected { chunk_idx: 1 }
+            ));
+        }
+        assert_eq!(assembler.missing_chunks("task-verify").await, vec![1]);
+
+        let mut good = processed("task-verify", 1);
+        good.result_data = verification_transform(&chunks[1]);
+        assert!(matches!(assembler.add_chunk(good).await, AssemblyStatus::Complete));
+        let assembled = assembler.get_assembled("task-verify").await.unwrap();
+        assert_eq!(assembled[0].result_data, out0);
+    }
+}
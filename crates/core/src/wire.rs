@@ -0,0 +1,208 @@
+//! Wire encoding for tensor frames
+//!
+//! Every encoded frame starts with a two byte header: the wire version and
+//! the `WireFormat` of the payload. A receiver that sees a version or
+//! format it does not understand reports it instead of deserializing the
+//! payload into garbage, and answers with a reject frame listing the
+//! formats it does support so the sender can negotiate a common one.
+//!
+//! `Bincode` is compact but positional: adding a field to `TensorChunk` or
+//! `ProcessedChunk` breaks older peers. `Json` is self-describing, so
+//! unknown fields are ignored and new fields marked `#[serde(default)]`
+//! can be rolled out without updating the whole swarm at once.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Bumped when the header layout itself changes
+pub const WIRE_VERSION: u8 = 1;
+/// Version byte plus format byte
+pub const HEADER_LEN: usize = 2;
+/// Format code of a reject frame; its payload lists supported format codes
+const REJECT_CODE: u8 = 0xff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Bincode,
+    Json,
+}
+
+impl WireFormat {
+    /// Every format this build can encode and decode
+    pub const ALL: [WireFormat; 2] = [WireFormat::Bincode, WireFormat::Json];
+
+    pub fn code(self) -> u8 {
+        match self {
+            WireFormat::Bincode => 0,
+            WireFormat::Json => 1,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.code() == code)
+    }
+
+    /// Header followed by `value` in this format
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, WireError> {
+        let mut frame = vec![WIRE_VERSION, self.code()];
+        match self {
+            WireFormat::Bincode => bincode::serialize_into(&mut frame, value)
+                .map_err(|e| WireError::Encode(e.to_string()))?,
+            WireFormat::Json => serde_json::to_writer(&mut frame, value)
+                .map_err(|e| WireError::Encode(e.to_string()))?,
+        }
+        Ok(frame)
+    }
+
+    /// First format in `preferred` that the remote side also supports
+    pub fn negotiate(preferred: &[WireFormat], remote: &[WireFormat]) -> Option<WireFormat> {
+        preferred.iter().copied().find(|format| remote.contains(format))
+    }
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireFormat::Bincode => write!(f, "bincode"),
+            WireFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "bincode" => Ok(WireFormat::Bincode),
+            "json" => Ok(WireFormat::Json),
+            other => Err(format!("unknown wire format '{}', expected bincode or json", other)),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WireError {
+    #[error("frame shorter than the {HEADER_LEN} byte header")]
+    Truncated,
+
+    #[error("wire version mismatch: expected {expected}, got {found}")]
+    VersionMismatch { expected: u8, found: u8 },
+
+    #[error("unknown wire format code {0}")]
+    UnknownFormat(u8),
+
+    /// The remote side could not read our frame
+    #[error("frame rejected, remote supports {supported:?}")]
+    Rejected { supported: Vec<WireFormat> },
+
+    #[error("failed to encode frame: {0}")]
+    Encode(String),
+
+    #[error("failed to decode {format} frame: {message}")]
+    Decode { format: WireFormat, message: String },
+}
+
+/// Decode a frame produced by `WireFormat::encode`, returning the value and
+/// the format it was sent in
+pub fn decode<T: DeserializeOwned>(frame: &[u8]) -> Result<(T, WireFormat), WireError> {
+    if frame.len() < HEADER_LEN {
+        return Err(WireError::Truncated);
+    }
+    let (version, code, payload) = (frame[0], frame[1], &frame[HEADER_LEN..]);
+    if version != WIRE_VERSION {
+        return Err(WireError::VersionMismatch { expected: WIRE_VERSION, found: version });
+    }
+    if code == REJECT_CODE {
+        let supported = payload.iter().filter_map(|&c| WireFormat::from_code(c)).collect();
+        return Err(WireError::Rejected { supported });
+    }
+    let format = WireFormat::from_code(code).ok_or(WireError::UnknownFormat(code))?;
+    let value = match format {
+        WireFormat::Bincode => bincode::deserialize(payload).map_err(|e| e.to_string()),
+        WireFormat::Json => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+    }
+    .map_err(|message| WireError::Decode { format, message })?;
+    Ok((value, format))
+}
+
+/// Frame telling the sender its frame could not be read, listing the
+/// formats this side accepts
+pub fn encode_reject(supported: &[WireFormat]) -> Vec<u8> {
+    let mut frame = vec![WIRE_VERSION, REJECT_CODE];
+    frame.extend(supported.iter().map(|format| format.code()));
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_queue::{TensorChunk, TensorMsg};
+
+    fn chunk() -> TensorChunk {
+        TensorChunk {
+            task_id: "wire".to_string(),
+            chunk_idx: 0,
+            total_chunks: 1,
+            start_layer: 0,
+            end_layer: 1,
+            tensor_data: vec![1, 2, 3],
+            shape: vec![1, 3],
+            dtype: "f32".to_string(),
+            source_node: "127.0.0.1:9000".to_string(),
+            priority: 0,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_formats_roundtrip() {
+        for format in WireFormat::ALL {
+            let frame = format.encode(&TensorMsg::Chunk(chunk())).unwrap();
+            let (msg, decoded_as): (TensorMsg, _) = decode(&frame).unwrap();
+            assert_eq!(decoded_as, format);
+            assert!(matches!(msg, TensorMsg::Chunk(c) if c.tensor_data == vec![1, 2, 3]));
+        }
+    }
+
+    #[test]
+    fn test_json_tolerates_unknown_fields() {
+        // A newer peer added a field we don't know about
+        let mut value = serde_json::to_value(chunk()).unwrap();
+        value["draft_tokens"] = serde_json::json!(4);
+        let mut frame = vec![WIRE_VERSION, WireFormat::Json.code()];
+        frame.extend(serde_json::to_vec(&value).unwrap());
+
+        let (decoded, _): (TensorChunk, _) = decode(&frame).unwrap();
+        assert_eq!(decoded.task_id, "wire");
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let mut frame = WireFormat::Bincode.encode(&chunk()).unwrap();
+        frame[0] = WIRE_VERSION + 1;
+        assert!(matches!(
+            decode::<TensorChunk>(&frame),
+            Err(WireError::VersionMismatch { found, .. }) if found == WIRE_VERSION + 1
+        ));
+
+        frame[0] = WIRE_VERSION;
+        frame[1] = 7;
+        assert!(matches!(decode::<TensorChunk>(&frame), Err(WireError::UnknownFormat(7))));
+        assert!(matches!(decode::<TensorChunk>(&[WIRE_VERSION]), Err(WireError::Truncated)));
+
+        let reject = encode_reject(&[WireFormat::Json]);
+        let Err(WireError::Rejected { supported }) = decode::<TensorChunk>(&reject) else {
+            panic!("expected a reject");
+        };
+        assert_eq!(supported, vec![WireFormat::Json]);
+        assert_eq!(
+            WireFormat::negotiate(&[WireFormat::Bincode, WireFormat::Json], &supported),
+            Some(WireFormat::Json)
+        );
+        assert_eq!(WireFormat::negotiate(&[WireFormat::Bincode], &supported), None);
+    }
+}
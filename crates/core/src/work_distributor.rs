@@ -3,6 +3,8 @@
 //! Splits inference tasks across peers based on their REAL capacity.
 //! More powerful devices get more layers to process.
 
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
@@ -20,6 +22,51 @@ pub struct PeerWork {
     pub assigned_layers: (u32, u32),
     /// Chunk to send
     pub chunk: Option<TensorChunk>,
+    /// Measured round trip to the peer, if known
+    #[serde(default)]
+    pub latency_ms: Option<u32>,
+}
+
+impl PeerWork {
+    pub fn layer_count(&self) -> u32 {
+        self.assigned_layers.1 + 1 - self.assigned_layers.0
+    }
+
+    /// Predicted time for this peer to finish its share: the round trip plus
+    /// `LAYER_COST_MS` per layer, scaled down by capacity
+    pub fn estimated_finish_ms(&self) -> f64 {
+        let compute = self.layer_count() as f64 * LAYER_COST_MS * 100.0 / self.capacity_score.max(1) as f64;
+        self.latency_ms.unwrap_or(0) as f64 + compute
+    }
+}
+
+/// Time one layer takes on a peer with `capacity_score` 100
+pub const LAYER_COST_MS: f64 = 10.0;
+
+/// A peer the distributor can plan for
+pub trait WorkPeer {
+    fn node_id(&self) -> String;
+    fn address(&self) -> String;
+    /// Advertised hardware; peers without it are left out of plans
+    fn device(&self) -> Option<&DeviceCapabilities>;
+    /// Measured round trip, if known
+    fn latency_ms(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl WorkPeer for (String, String, DeviceCapabilities) {
+    fn node_id(&self) -> String {
+        self.0.clone()
+    }
+
+    fn address(&self) -> String {
+        self.1.clone()
+    }
+
+    fn device(&self) -> Option<&DeviceCapabilities> {
+        Some(&self.2)
+    }
 }
 
 /// Work distribution plan for a task
@@ -49,18 +96,81 @@ impl WorkPlan {
         
         format!(
             "Task {} | {} peers | {}B equivalent\n{}",
-            &self.task_id[..8],
+            &self.task_id[..8.min(self.task_id.len())],
             self.peers.len(),
             self.equivalent_params_b,
             peer_summary.join("\n")
         )
     }
+
+    /// Predicted completion time: peers work their shares in parallel, so
+    /// the plan finishes when its slowest peer does
+    pub fn estimated_makespan(&self) -> Duration {
+        let ms = self.peers.iter().map(PeerWork::estimated_finish_ms).fold(0.0, f64::max);
+        Duration::from_secs_f64(ms / 1000.0)
+    }
 }
 
+/// Hash ring points for a peer with `capacity_score` 100; weaker peers get
+/// proportionally fewer
+pub const VIRTUAL_NODES: u32 = 128;
+
 /// Distributes work across peers based on their real capacity
-pub struct WorkDistributor;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WorkDistributor {
+    consistent_hashing: bool,
+}
 
 impl WorkDistributor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign chunks over a hash ring of node ids, so a peer joining or
+    /// leaving only moves its own share and the rest keep their caches
+    pub fn with_consistent_hashing(mut self) -> Self {
+        self.consistent_hashing = true;
+        self
+    }
+
+    /// The node id that owns each of `chunks` chunks. Without consistent
+    /// hashing these are the proportional ranges from `distribute`.
+    pub fn assign_chunks(
+        &self,
+        chunks: u32,
+        peers: &[(String, String, DeviceCapabilities)],
+    ) -> Vec<String> {
+        if chunks == 0 || peers.is_empty() {
+            return Vec::new();
+        }
+        if !self.consistent_hashing {
+            return Self::plan("assignment", chunks, peers)
+                .peers
+                .iter()
+                .flat_map(|p| std::iter::repeat_n(p.node_id.clone(), p.layer_count() as usize))
+                .collect();
+        }
+
+        let mut ring: Vec<(u64, usize)> = peers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (node_id, _, caps))| {
+                let points = (VIRTUAL_NODES * caps.capacity_score / 100).max(1);
+                (0..points).map(move |v| (ring_point(&format!("{}#{}", node_id, v)), i))
+            })
+            .collect();
+        ring.sort_unstable();
+
+        (0..chunks)
+            .map(|chunk| {
+                let point = ring_point(&format!("chunk#{}", chunk));
+                // First peer clockwise from the chunk, wrapping past the top
+                let slot = ring.partition_point(|(p, _)| *p < point) % ring.len();
+                peers[ring[slot].1].0.clone()
+            })
+            .collect()
+    }
+
     /// Create a work plan that distributes layers proportionally to capacity
     /// 
     /// More powerful peers get more layers!
@@ -69,7 +179,35 @@ impl WorkDistributor {
         total_layers: u32,
         peers: &[(String, String, DeviceCapabilities)], // (node_id, address, caps)
     ) -> WorkPlan {
-        if peers.is_empty() {
+        info!("📊 Distributing {} layers across {} peers", total_layers, peers.len());
+        let plan = Self::plan(task_id, total_layers, peers);
+        info!("✅ Work plan created: {}", plan.summary());
+        plan
+    }
+
+    /// Preview how `job_size` layers would be spread over `peers` without
+    /// logging or committing anything. Peers with no advertised device are
+    /// skipped, and known latencies feed `WorkPlan::estimated_makespan`.
+    pub fn simulate<P: WorkPeer>(job_size: u32, peers: &[P]) -> WorkPlan {
+        let candidates: Vec<(&P, (String, String, DeviceCapabilities))> = peers
+            .iter()
+            .filter_map(|p| Some((p, (p.node_id(), p.address(), p.device()?.clone()))))
+            .collect();
+        let tuples: Vec<_> = candidates.iter().map(|(_, t)| t.clone()).collect();
+
+        let mut plan = Self::plan("simulation", job_size, &tuples);
+        for (work, (peer, _)) in plan.peers.iter_mut().zip(&candidates) {
+            work.latency_ms = peer.latency_ms();
+        }
+        plan
+    }
+
+    fn plan(
+        task_id: &str,
+        total_layers: u32,
+        peers: &[(String, String, DeviceCapabilities)],
+    ) -> WorkPlan {
+        if peers.is_empty() || total_layers == 0 {
             return WorkPlan {
                 task_id: task_id.to_string(),
                 total_layers,
@@ -84,14 +222,15 @@ impl WorkDistributor {
             .map(|(_, _, caps)| caps.capacity_score)
             .sum();
         
-        info!("📊 Distributing {} layers across {} peers (total capacity: {})",
-              total_layers, peers.len(), total_capacity);
-        
         let mut peer_works = Vec::new();
         let mut current_layer = 0u32;
         let mut remaining_layers = total_layers;
         
         for (i, (node_id, address, caps)) in peers.iter().enumerate() {
+            // Fewer layers than peers: the rest get nothing
+            if remaining_layers == 0 {
+                break;
+            }
             let is_last = i == peers.len() - 1;
             
             // Calculate proportional share
@@ -119,6 +258,7 @@ impl WorkDistributor {
                 max_layers: caps.max_layers,
                 assigned_layers: (current_layer, end_layer),
                 chunk: None,
+                latency_ms: None,
             });
             
             current_layer = end_layer + 1;
@@ -130,17 +270,13 @@ impl WorkDistributor {
         let params_per_layer = 0.5 / 24.0; // 0.5B model / 24 layers
         let equivalent_params_b = total_layers as f32 * params_per_layer;
         
-        let plan = WorkPlan {
+        WorkPlan {
             task_id: task_id.to_string(),
             total_layers,
             peers: peer_works,
             total_capacity,
             equivalent_params_b,
-        };
-        
-        info!("✅ Work plan created: {}", plan.summary());
-        
-        plan
+        }
     }
     
     /// Redistribute work when a peer fails
@@ -165,6 +301,13 @@ impl WorkDistributor {
     }
 }
 
+fn ring_point(key: &str) -> u64 {
+    let hash = blake3::hash(key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
 /// Response assembly - joins processed chunks back together
 pub struct ResponseJoiner;
 
@@ -276,5 +419,65 @@ mod tests {
         assert_eq!(assigned.0, 0);
         assert_eq!(assigned.1, 23); // All 24 layers
     }
-}
 
+    #[test]
+    fn test_simulated_makespan_balanced_vs_skewed() {
+        // Same total capacity, but the strong peer in the skewed set can
+        // only hold half the model, leaving the weak peers a big share
+        let balanced = vec![
+            ("node1".to_string(), "addr1".to_string(), mock_caps(34, 24)),
+            ("node2".to_string(), "addr2".to_string(), mock_caps(33, 24)),
+            ("node3".to_string(), "addr3".to_string(), mock_caps(33, 24)),
+        ];
+        let skewed = vec![
+            ("node1".to_string(), "addr1".to_string(), mock_caps(80, 12)),
+            ("node2".to_string(), "addr2".to_string(), mock_caps(10, 24)),
+            ("node3".to_string(), "addr3".to_string(), mock_caps(10, 24)),
+        ];
+
+        let balanced = WorkDistributor::simulate(24, &balanced);
+        let skewed = WorkDistributor::simulate(24, &skewed);
+        assert_eq!(skewed.peers.iter().map(PeerWork::layer_count).sum::<u32>(), 24);
+
+        // 8 layers at score 33 against 6 layers at score 10
+        assert!((balanced.estimated_makespan().as_secs_f64() - 8.0 * LAYER_COST_MS * 100.0 / 33.0 / 1000.0).abs() < 1e-9);
+        assert!(skewed.estimated_makespan() > balanced.estimated_makespan() * 2);
+    }
+
+    #[test]
+    fn test_simulate_without_work_is_empty() {
+        let peers: Vec<_> = (1..=3)
+            .map(|i| (format!("node{}", i), format!("addr{}", i), mock_caps(50, 24)))
+            .collect();
+        assert!(WorkDistributor::simulate(0, &peers).peers.is_empty());
+
+        let plan = WorkDistributor::simulate(2, &peers);
+        assert_eq!(plan.peers.len(), 2);
+        assert_eq!(plan.peers[1].assigned_layers, (1, 1));
+    }
+
+    #[test]
+    fn test_consistent_hashing_moves_only_removed_peers_chunks() {
+        let peers: Vec<_> = (1..=5)
+            .map(|i| (format!("node{}", i), format!("addr{}", i), mock_caps(50, 1000)))
+            .collect();
+        let distributor = WorkDistributor::new().with_consistent_hashing();
+
+        let before = distributor.assign_chunks(1000, &peers);
+        let remaining: Vec<_> = peers.iter().filter(|p| p.0 != "node3").cloned().collect();
+        let after = distributor.assign_chunks(1000, &remaining);
+        assert_eq!(before, distributor.assign_chunks(1000, &peers));
+
+        let moved: Vec<usize> = (0..1000).filter(|&i| before[i] != after[i]).collect();
+        assert!(moved.iter().all(|&i| before[i] == "node3"));
+        assert_eq!(moved.len(), before.iter().filter(|n| *n == "node3").count());
+        // Roughly a fifth of the chunks
+        assert!((120..=280).contains(&moved.len()), "moved {}", moved.len());
+
+        // The default contiguous split reshuffles far more
+        let contiguous = WorkDistributor::new();
+        let before = contiguous.assign_chunks(1000, &peers);
+        let after = contiguous.assign_chunks(1000, &remaining);
+        assert!((0..1000).filter(|&i| before[i] != after[i]).count() > moved.len());
+    }
+}
@@ -0,0 +1,173 @@
+//! Peer address parsing
+//!
+//! Peers advertise addresses either as `IP:port` (IPv6 bracketed, as
+//! `SocketAddr` prints them) or as multiaddrs like `/ip4/10.0.0.2/tcp/7654`.
+//! `PeerAddress` accepts both and always prints the `SocketAddr` form.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use crate::error::GridError;
+
+/// A peer's socket address, canonicalized: IPv4-mapped IPv6 addresses
+/// (`::ffff:a.b.c.d`) become plain IPv4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeerAddress(SocketAddr);
+
+impl PeerAddress {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self(SocketAddr::new(addr.ip().to_canonical(), addr.port()))
+    }
+
+    /// Parse `IP:port`, `[IPv6]:port` or a multiaddr with an `ip4`/`ip6`
+    /// component followed by `tcp`/`udp`. Trailing components such as
+    /// `/p2p/<id>` are ignored.
+    pub fn parse(s: &str) -> Result<Self, GridError> {
+        let s = s.trim();
+        if s.starts_with('/') {
+            return Self::parse_multiaddr(s);
+        }
+        SocketAddr::from_str(s)
+            .map(Self::new)
+            .map_err(|_| GridError::InvalidAddress(s.to_string()))
+    }
+
+    fn parse_multiaddr(s: &str) -> Result<Self, GridError> {
+        let invalid = || GridError::InvalidAddress(s.to_string());
+        let mut parts = s.split('/').skip(1);
+        let mut ip = None;
+        let mut port = None;
+
+        while let Some(protocol) = parts.next() {
+            let value = parts.next();
+            match protocol {
+                "ip4" | "ip6" if ip.is_none() => {
+                    let parsed: IpAddr = value.ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+                    if parsed.is_ipv4() != (protocol == "ip4") {
+                        return Err(invalid());
+                    }
+                    ip = Some(parsed);
+                }
+                "tcp" | "udp" if ip.is_some() && port.is_none() => {
+                    port = Some(value.ok_or_else(invalid)?.parse::<u16>().map_err(|_| invalid())?);
+                }
+                _ => {}
+            }
+        }
+
+        match (ip, port) {
+            (Some(ip), Some(port)) => Ok(Self::new(SocketAddr::new(ip, port))),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn socket_addr(&self) -> SocketAddr {
+        self.0
+    }
+
+    pub fn ip(&self) -> IpAddr {
+        self.0.ip()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.0.port()
+    }
+
+    /// Same host on another port
+    pub fn with_port(&self, port: u16) -> Self {
+        Self(SocketAddr::new(self.ip(), port))
+    }
+
+    /// Same host, port shifted by `offset` (services such as the task server
+    /// listen at a fixed offset from discovery); `None` on overflow
+    pub fn offset_port(&self, offset: u16) -> Option<Self> {
+        self.port().checked_add(offset).map(|port| self.with_port(port))
+    }
+
+    /// `/ip4/<ip>/tcp/<port>` or `/ip6/<ip>/tcp/<port>`
+    pub fn to_multiaddr(&self) -> String {
+        let family = if self.ip().is_ipv4() { "ip4" } else { "ip6" };
+        format!("/{}/{}/tcp/{}", family, self.ip(), self.port())
+    }
+}
+
+impl std::fmt::Display for PeerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for PeerAddress {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<SocketAddr> for PeerAddress {
+    fn from(addr: SocketAddr) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl From<PeerAddress> for SocketAddr {
+    fn from(addr: PeerAddress) -> Self {
+        addr.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_forms() {
+        let plain: PeerAddress = "192.168.1.250:7655".parse().unwrap();
+        let multi: PeerAddress = "/ip4/192.168.1.250/tcp/7655".parse().unwrap();
+        assert_eq!(plain, multi);
+        assert_eq!(plain.to_string(), "192.168.1.250:7655");
+        assert_eq!(plain.to_multiaddr(), "/ip4/192.168.1.250/tcp/7655");
+        assert_eq!(PeerAddress::parse(&plain.to_multiaddr()).unwrap(), plain);
+    }
+
+    #[test]
+    fn test_ipv6_ports() {
+        // Naive splitting on ':' used to read "1" as the port here
+        let addr: PeerAddress = "[::1]:8080".parse().unwrap();
+        assert_eq!(addr.port(), 8080);
+        assert_eq!(addr.ip(), "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(addr.to_string(), "[::1]:8080");
+
+        let multi: PeerAddress = "/ip6/fe80::1:2/udp/7654/p2p/QmPeer".parse().unwrap();
+        assert_eq!(multi.port(), 7654);
+        assert_eq!(multi.to_string(), "[fe80::1:2]:7654");
+        assert_eq!(PeerAddress::parse(&multi.to_multiaddr()).unwrap(), multi);
+
+        assert_eq!(addr.offset_port(1000).unwrap().to_string(), "[::1]:9080");
+        assert!(addr.with_port(u16::MAX).offset_port(1).is_none());
+    }
+
+    #[test]
+    fn test_canonicalizes_mapped_ipv4() {
+        let mapped: PeerAddress = "[::ffff:10.0.0.2]:7654".parse().unwrap();
+        assert_eq!(mapped, "10.0.0.2:7654".parse().unwrap());
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        for bad in [
+            "",
+            "::1:8080",
+            "10.0.0.2",
+            "/ip4/10.0.0.2",
+            "/ip4/::1/tcp/80",
+            "/ip6/10.0.0.2/tcp/80",
+            "/ip4/10.0.0.2/tcp/70000",
+            "/dns4/example.com/tcp/80",
+        ] {
+            assert!(PeerAddress::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+}
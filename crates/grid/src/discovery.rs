@@ -18,8 +18,9 @@ use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
+use crate::address::PeerAddress;
 use crate::error::{GridError, Result};
-use crate::peer::{NodeId, PeerInfo};
+use crate::peer::{Capabilities, NodeId, PeerInfo};
 
 #[async_trait]
 pub trait Discovery: Send + Sync {
@@ -31,10 +32,14 @@ pub trait Discovery: Send + Sync {
 pub struct DiscoveryEvent {
     pub peer_id: NodeId,
     pub addresses: Vec<SocketAddr>,
+    /// What the peer announced it offers, if it said
+    pub capabilities: Option<Capabilities>,
 }
 
 const MULTICAST_ADDR: &str = "239.255.70.77";
 const MULTICAST_PORT: u16 = 7077;
+/// "CORTEX", node id, public key and port; capabilities may follow
+const ANNOUNCE_HEADER_LEN: usize = 72;
 const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct LanDiscovery {
@@ -44,6 +49,7 @@ pub struct LanDiscovery {
     discovered: Arc<RwLock<HashSet<NodeId>>>,
     running: Arc<RwLock<bool>>,
     event_tx: Option<mpsc::Sender<DiscoveryEvent>>,
+    capabilities: Option<Capabilities>,
 }
 
 impl LanDiscovery {
@@ -61,22 +67,35 @@ impl LanDiscovery {
                 discovered: Arc::new(RwLock::new(HashSet::new())),
                 running: Arc::new(RwLock::new(false)),
                 event_tx: Some(tx),
+                capabilities: None,
             },
             rx,
         )
     }
 
+    /// Announce these capabilities, so peers need not guess what we offer
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
     fn create_announce_packet(&self) -> Vec<u8> {
         let mut packet = Vec::with_capacity(64);
         packet.extend_from_slice(b"CORTEX");
         packet.extend_from_slice(&self.local_node_id.0);
         packet.extend_from_slice(&self.local_pubkey);
         packet.extend_from_slice(&self.port.to_be_bytes());
+        // Optional trailer; older peers stop reading after the port
+        if let Some(capabilities) = &self.capabilities {
+            packet.extend(bincode::serialize(capabilities).unwrap_or_default());
+        }
         packet
     }
 
-    fn parse_announce_packet(data: &[u8]) -> Option<(NodeId, [u8; 32], u16)> {
-        if data.len() < 70 || &data[..6] != b"CORTEX" {
+    fn parse_announce_packet(
+        data: &[u8],
+    ) -> Option<(NodeId, [u8; 32], u16, Option<Capabilities>)> {
+        if data.len() < ANNOUNCE_HEADER_LEN || &data[..6] != b"CORTEX" {
             return None;
         }
 
@@ -87,8 +106,9 @@ impl LanDiscovery {
         pubkey.copy_from_slice(&data[38..70]);
 
         let port = u16::from_be_bytes([data[70], data[71]]);
+        let capabilities = bincode::deserialize(&data[ANNOUNCE_HEADER_LEN..]).ok();
 
-        Some((NodeId(node_id), pubkey, port))
+        Some((NodeId(node_id), pubkey, port, capabilities))
     }
 
     async fn run_announcer(
@@ -141,7 +161,7 @@ impl LanDiscovery {
 
             match tokio::time::timeout(Duration::from_secs(1), socket.recv_from(&mut buf)).await {
                 Ok(Ok((len, src))) => {
-                    if let Some((node_id, _pubkey, port)) = Self::parse_announce_packet(&buf[..len])
+                    if let Some((node_id, _pubkey, port, capabilities)) = Self::parse_announce_packet(&buf[..len])
                     {
                         if node_id == local_node_id {
                             continue;
@@ -156,6 +176,7 @@ impl LanDiscovery {
                                 .send(DiscoveryEvent {
                                     peer_id: node_id,
                                     addresses: vec![peer_addr],
+                                    capabilities,
                                 })
                                 .await;
                         }
@@ -300,6 +321,10 @@ pub struct KademliaDiscovery {
     local_node_id: NodeId,
     local_pubkey: [u8; 32],
     listen_port: u16,
+    bootstrap: Vec<Multiaddr>,
+    external: Vec<Multiaddr>,
+    confirmed_tx: mpsc::Sender<PeerAddress>,
+    confirmed_rx: Option<mpsc::Receiver<PeerAddress>>,
     discovered: Arc<RwLock<HashMap<NodeId, PeerInfo>>>,
     running: Arc<RwLock<bool>>,
 }
@@ -311,11 +336,16 @@ impl KademliaDiscovery {
         listen_port: u16,
     ) -> Result<(Self, mpsc::Receiver<DiscoveryEvent>)> {
         let (_tx, rx) = mpsc::channel(64);
+        let (confirmed_tx, confirmed_rx) = mpsc::channel(16);
 
         let discovery = Self {
             local_node_id,
             local_pubkey,
             listen_port,
+            bootstrap: Vec::new(),
+            external: Vec::new(),
+            confirmed_tx,
+            confirmed_rx: Some(confirmed_rx),
             discovered: Arc::new(RwLock::new(HashMap::new())),
             running: Arc::new(RwLock::new(false)),
         };
@@ -323,10 +353,50 @@ impl KademliaDiscovery {
         Ok((discovery, rx))
     }
 
+    /// Peers to join the DHT through, as multiaddrs. Addresses ending in
+    /// `/p2p/<peer id>` seed the routing table; others are dialed directly.
+    pub fn with_bootstrap(mut self, addrs: &[String]) -> Result<Self> {
+        self.bootstrap = addrs
+            .iter()
+            .map(|addr| {
+                addr.parse::<Multiaddr>()
+                    .map_err(|e| GridError::DiscoveryError(format!("Invalid bootstrap address '{}': {}", addr, e)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Advertise these as our reachable addresses in DHT records, typically
+    /// `PeerStore::observed_addresses()` learned from handshakes. Without
+    /// them peers only see our listen addresses, which behind NAT are
+    /// private.
+    pub fn with_external_addresses(mut self, addrs: &[PeerAddress]) -> Result<Self> {
+        self.external = addrs
+            .iter()
+            .map(|addr| {
+                addr.to_multiaddr()
+                    .parse::<Multiaddr>()
+                    .map_err(|e| GridError::DiscoveryError(format!("Invalid external address '{}': {}", addr, e)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Channel for external addresses confirmed while running, such as
+    /// those `PeerStore::record_handshake` returns; each is advertised as
+    /// soon as it arrives
+    pub fn external_address_sender(&self) -> mpsc::Sender<PeerAddress> {
+        self.confirmed_tx.clone()
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn run_event_loop(
         _local_node_id: NodeId,
         _local_pubkey: [u8; 32],
         _listen_port: u16,
+        bootstrap: Vec<Multiaddr>,
+        external: Vec<Multiaddr>,
+        mut confirmed: mpsc::Receiver<PeerAddress>,
         discovered: Arc<RwLock<HashMap<NodeId, PeerInfo>>>,
         event_tx: mpsc::Sender<DiscoveryEvent>,
         running: Arc<RwLock<bool>>,
@@ -369,6 +439,31 @@ impl KademliaDiscovery {
         // Set server mode for better DHT performance
         swarm.behaviour_mut().set_mode(Some(Mode::Server));
 
+        for addr in external {
+            debug!("Kademlia: advertising external address {}", addr);
+            swarm.add_external_address(addr);
+        }
+
+        let mut seeded = false;
+        for addr in bootstrap {
+            match addr.iter().last() {
+                Some(Protocol::P2p(peer)) => {
+                    swarm.behaviour_mut().add_address(&peer, addr);
+                    seeded = true;
+                }
+                _ => {
+                    if let Err(e) = swarm.dial(addr.clone()) {
+                        warn!("Kademlia: failed to dial bootstrap peer {}: {}", addr, e);
+                    }
+                }
+            }
+        }
+        if seeded {
+            if let Err(e) = swarm.behaviour_mut().bootstrap() {
+                warn!("Kademlia: bootstrap failed: {}", e);
+            }
+        }
+
         loop {
             {
                 if !*running.read().await {
@@ -428,6 +523,7 @@ impl KademliaDiscovery {
                                     .send(DiscoveryEvent {
                                         peer_id: node_id,
                                         addresses: socket_addrs,
+                                        capabilities: None,
                                     })
                                     .await;
                             }
@@ -438,6 +534,15 @@ impl KademliaDiscovery {
                         _ => {}
                     }
                 }
+                Some(addr) = confirmed.recv() => {
+                    match addr.to_multiaddr().parse::<Multiaddr>() {
+                        Ok(addr) => {
+                            info!("Kademlia: advertising confirmed external address {}", addr);
+                            swarm.add_external_address(addr);
+                        }
+                        Err(e) => warn!("Kademlia: invalid external address {}: {}", addr, e),
+                    }
+                }
                 _ = tokio::time::sleep(Duration::from_secs(1)) => {
                     // Keep the loop alive
                 }
@@ -458,6 +563,12 @@ impl Discovery for KademliaDiscovery {
         let local_node_id = self.local_node_id;
         let local_pubkey = self.local_pubkey;
         let listen_port = self.listen_port;
+        let bootstrap = self.bootstrap.clone();
+        let external = self.external.clone();
+        let confirmed = self
+            .confirmed_rx
+            .take()
+            .ok_or_else(|| GridError::DiscoveryError("Kademlia discovery already started".to_string()))?;
         let discovered = Arc::clone(&self.discovered);
         let running = Arc::clone(&self.running);
         
@@ -470,6 +581,9 @@ impl Discovery for KademliaDiscovery {
                 local_node_id,
                 local_pubkey,
                 listen_port,
+                bootstrap,
+                external,
+                confirmed,
                 discovered,
                 tx,
                 running,
@@ -493,3 +607,29 @@ impl Discovery for KademliaDiscovery {
         discovered.values().cloned().collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announce_carries_capabilities() {
+        let id = NodeId::random();
+        let (plain, _rx) = LanDiscovery::new(id, [3u8; 32], 7654);
+        let (node, pubkey, port, capabilities) =
+            LanDiscovery::parse_announce_packet(&plain.create_announce_packet()).unwrap();
+        assert_eq!((node, pubkey, port), (id, [3u8; 32], 7654));
+        assert!(capabilities.is_none());
+
+        let compute = Capabilities {
+            can_compute: true,
+            ..Default::default()
+        };
+        let packet = plain.with_capabilities(compute).create_announce_packet();
+        assert_eq!(
+            LanDiscovery::parse_announce_packet(&packet).unwrap().3,
+            Some(compute)
+        );
+        assert!(LanDiscovery::parse_announce_packet(&packet[..ANNOUNCE_HEADER_LEN - 1]).is_none());
+    }
+}
@@ -50,6 +50,10 @@ pub enum GridError {
     #[error("discovery error: {0}")]
     DiscoveryError(String),
 
+    /// Peer address is neither `IP:port` nor an ip4/ip6 multiaddr
+    #[error("invalid peer address: {0}")]
+    InvalidAddress(String),
+
     /// Multicast address parsing or validation failed
     #[error("invalid multicast address: {0}")]
     InvalidMulticastAddr(String),
@@ -66,6 +70,10 @@ pub enum GridError {
     #[error("timeout")]
     Timeout,
 
+    /// Peer stopped answering session heartbeats
+    #[error("peer missed {0} heartbeats")]
+    HeartbeatTimeout(u32),
+
     /// Communication channel was closed
     #[error("channel closed")]
     ChannelClosed,
@@ -5,13 +5,18 @@ use chacha20poly1305::{
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::RngCore;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tracing::{debug, info, warn};
 use x25519_dalek::{PublicKey, StaticSecret};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use cortex_core::DeviceCapabilities;
+
+use crate::address::PeerAddress;
 use crate::error::{GridError, Result};
 use crate::peer::{Capabilities, NodeId};
-use crate::wire::{Message, SessionParams, PROTOCOL_VERSION};
+use crate::session::Session;
+use crate::wire::{read_frame, write_frame, Message, SessionParams, PROTOCOL_VERSION};
 
 /// Maximum allowed time drift for timestamp validation (5 minutes)
 const MAX_TIMESTAMP_DRIFT_SECS: u64 = 300;
@@ -98,11 +103,20 @@ pub struct HandshakeContext {
     pub remote_pubkey: Option<[u8; 32]>,
     pub nonce: Option<[u8; 32]>,
     pub capabilities: Capabilities,
+    /// Hardware we advertise to the peer, if any
+    pub device: Option<DeviceCapabilities>,
+    /// Hardware the peer advertised
+    pub remote_device: Option<DeviceCapabilities>,
     // X25519 keys for session encryption (will be zeroized after key agreement)
     pub x25519_secret: Option<StaticSecret>,
     pub x25519_public: PublicKey,
     pub remote_x25519_public: Option<PublicKey>,
     pub session_keys: Option<SessionKeys>,
+    pub session_params: Option<SessionParams>,
+    /// Responder: where the initiator's connection came from
+    pub remote_addr: Option<PeerAddress>,
+    /// Initiator: our address as reported in WELCOME
+    pub observed_addr: Option<PeerAddress>,
     pub handshake_started_at: Option<SystemTime>,
 }
 
@@ -119,10 +133,15 @@ impl HandshakeContext {
             remote_pubkey: None,
             nonce: None,
             capabilities,
+            device: None,
+            remote_device: None,
             x25519_secret: Some(x25519_secret),
             x25519_public,
             remote_x25519_public: None,
             session_keys: None,
+            session_params: None,
+            remote_addr: None,
+            observed_addr: None,
             handshake_started_at: None,
         }
     }
@@ -130,6 +149,7 @@ impl HandshakeContext {
     pub fn create_hello(&self) -> Result<Message> {
         let pubkey = self.local_signing_key.verifying_key().to_bytes();
         let caps_encoded = self.capabilities.encode();
+        let device = self.encoded_device();
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| GridError::HandshakeFailed(format!("System time is before UNIX epoch: {}", e)))?
@@ -140,6 +160,7 @@ impl HandshakeContext {
         sign_data.extend_from_slice(&self.local_node_id.0);
         sign_data.extend_from_slice(&pubkey);
         sign_data.extend_from_slice(&caps_encoded);
+        sign_data.extend_from_slice(&device);
         sign_data.extend_from_slice(self.x25519_public.as_bytes());
         sign_data.extend_from_slice(&timestamp.to_be_bytes());
 
@@ -150,6 +171,7 @@ impl HandshakeContext {
             node_id: self.local_node_id,
             pubkey,
             capabilities: caps_encoded,
+            device,
             x25519_pubkey: *self.x25519_public.as_bytes(),
             timestamp,
             signature: signature.to_bytes().to_vec(),
@@ -192,15 +214,37 @@ impl HandshakeContext {
         
         self.session_keys = Some(SessionKeys::new(session_id, encryption_key));
 
+        let session_params = SessionParams {
+            session_id,
+            heartbeat_interval_ms: 30000,
+            max_message_size: 16 * 1024 * 1024,
+        };
+        self.session_params = Some(session_params.clone());
+
         Ok(Message::Welcome {
-            session_params: SessionParams {
-                session_id,
-                heartbeat_interval_ms: 30000,
-                max_message_size: 16 * 1024 * 1024,
-            },
+            session_params,
+            observed_addr: self.remote_addr,
+            device: self.encoded_device(),
         })
     }
 
+    fn encoded_device(&self) -> Vec<u8> {
+        self.device.as_ref().map(DeviceCapabilities::encode).unwrap_or_default()
+    }
+
+    /// Record the peer's advertised hardware. An advertisement we can't
+    /// read (e.g. a newer wire version) is dropped rather than failing the
+    /// handshake.
+    fn accept_remote_device(&mut self, device: &[u8]) {
+        if device.is_empty() {
+            return;
+        }
+        self.remote_device = DeviceCapabilities::decode(device);
+        if self.remote_device.is_none() {
+            debug!("Ignoring unreadable device advertisement ({} bytes)", device.len());
+        }
+    }
+
     /// Validate timestamp to prevent replay attacks
     fn validate_timestamp(&self, timestamp: u64) -> Result<()> {
         let now = SystemTime::now()
@@ -241,6 +285,7 @@ struct HelloParams<'a> {
     node_id: NodeId,
     pubkey: [u8; 32],
     capabilities: &'a [u8],
+    device: &'a [u8],
     x25519_pubkey: [u8; 32],
     timestamp: u64,
     signature: &'a [u8],
@@ -271,6 +316,20 @@ impl Handshaker {
         }
     }
 
+    /// Source address of the initiator's connection, echoed back in WELCOME
+    /// so it learns how it is seen from outside its NAT
+    pub fn with_remote_addr(mut self, addr: impl Into<PeerAddress>) -> Self {
+        self.context.remote_addr = Some(addr.into());
+        self
+    }
+
+    /// Advertise this node's hardware in HELLO (initiator) or WELCOME
+    /// (responder)
+    pub fn with_device(mut self, device: DeviceCapabilities) -> Self {
+        self.context.device = Some(device);
+        self
+    }
+
     pub fn state(&self) -> HandshakeState {
         self.context.state
     }
@@ -292,6 +351,7 @@ impl Handshaker {
                 node_id, 
                 pubkey, 
                 capabilities, 
+                device,
                 x25519_pubkey,
                 timestamp,
                 signature 
@@ -309,10 +369,12 @@ impl Handshaker {
                     node_id,
                     pubkey,
                     capabilities: &capabilities,
+                    device: &device,
                     x25519_pubkey,
                     timestamp,
                     signature: &signature,
                 })?;
+                self.context.accept_remote_device(&device);
                 self.context.remote_node_id = Some(node_id);
                 self.context.remote_pubkey = Some(pubkey);
                 self.context.remote_x25519_public = Some(PublicKey::from(x25519_pubkey));
@@ -352,8 +414,13 @@ impl Handshaker {
                 Ok(Some(welcome))
             }
 
-            (HandshakeState::ProveSent, Message::Welcome { session_params }) => {
+            (HandshakeState::ProveSent, Message::Welcome { session_params, observed_addr, device }) => {
                 info!("Received WELCOME, session_id: {:?}", &session_params.session_id[..8]);
+                if let Some(addr) = observed_addr {
+                    debug!("Responder sees us at {}", addr);
+                }
+                self.context.observed_addr = observed_addr;
+                self.context.accept_remote_device(&device);
 
                 // Derive session keys on initiator side
                 let remote_x25519 = self.context.remote_x25519_public
@@ -368,6 +435,7 @@ impl Handshaker {
                 // X25519 secret is consumed and will be dropped/zeroized here (perfect forward secrecy)
 
                 self.context.session_keys = Some(SessionKeys::new(session_params.session_id, encryption_key));
+                self.context.session_params = Some(session_params);
                 self.context.state = HandshakeState::Completed;
                 Ok(None)
             }
@@ -403,7 +471,8 @@ impl Handshaker {
         sign_data.extend_from_slice(&params.protocol_version.to_be_bytes());
         sign_data.extend_from_slice(&params.node_id.0);
         sign_data.extend_from_slice(&params.pubkey);
-        sign_data.extend_from_slice(&params.capabilities);
+        sign_data.extend_from_slice(params.capabilities);
+        sign_data.extend_from_slice(params.device);
         sign_data.extend_from_slice(&params.x25519_pubkey);
         sign_data.extend_from_slice(&params.timestamp.to_be_bytes());
 
@@ -437,6 +506,42 @@ impl Handshaker {
         Ok(())
     }
 
+    /// Run the initiator side to completion over `stream`
+    pub async fn initiate<S: AsyncRead + AsyncWrite + Unpin>(&mut self, stream: &mut S) -> Result<()> {
+        let exchange = async {
+            send_message(stream, &self.start()?).await?;
+            let mut reply = self.process(recv_message(stream).await?)?;
+            while let Some(msg) = reply {
+                send_message(stream, &msg).await?;
+                reply = self.process(recv_message(stream).await?)?;
+            }
+            Ok(())
+        };
+        within_timeout(exchange).await?;
+        self.check_completed()
+    }
+
+    /// Run the responder side to completion over `stream`
+    pub async fn respond<S: AsyncRead + AsyncWrite + Unpin>(&mut self, stream: &mut S) -> Result<()> {
+        let exchange = async {
+            while !self.is_completed() {
+                if let Some(msg) = self.process(recv_message(stream).await?)? {
+                    send_message(stream, &msg).await?;
+                }
+            }
+            Ok(())
+        };
+        within_timeout(exchange).await?;
+        self.check_completed()
+    }
+
+    fn check_completed(&self) -> Result<()> {
+        if !self.is_completed() {
+            return Err(GridError::HandshakeFailed("Handshake not completed".to_string()));
+        }
+        Ok(())
+    }
+
     pub fn is_completed(&self) -> bool {
         self.context.state == HandshakeState::Completed
     }
@@ -450,6 +555,42 @@ impl Handshaker {
         self.context.session_keys.as_ref()
     }
 
+    /// Our address as the responder observed it, after a successful
+    /// handshake as initiator
+    pub fn observed_addr(&self) -> Option<PeerAddress> {
+        self.context.observed_addr
+    }
+
+    /// Hardware the peer advertised during the handshake
+    pub fn remote_device(&self) -> Option<&DeviceCapabilities> {
+        self.context.remote_device.as_ref()
+    }
+
+    /// Session parameters from the WELCOME, after a successful handshake
+    pub fn session_params(&self) -> Option<&SessionParams> {
+        self.context.session_params.as_ref()
+    }
+
+    /// Turn a completed handshake into an encrypted session with `remote`
+    /// over `stream`, with heartbeats at the negotiated interval. The
+    /// initiator never learns the responder's id from the handshake, so the
+    /// caller names the peer it dialed; a responder checks it against HELLO.
+    pub fn into_session<S>(self, stream: S, remote: NodeId) -> Result<Session<S>>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let not_done = || GridError::HandshakeFailed("Handshake not completed".to_string());
+        if !self.is_completed() {
+            return Err(not_done());
+        }
+        if self.context.remote_node_id.is_some_and(|id| id != remote) {
+            return Err(GridError::InvalidNodeId);
+        }
+        let keys = self.context.session_keys.clone().ok_or_else(not_done)?;
+        let params = self.context.session_params.clone().ok_or_else(not_done)?;
+        Ok(Session::new(stream, remote, keys, &params))
+    }
+
     /// Get handshake duration in milliseconds
     pub fn handshake_duration_ms(&self) -> Option<u128> {
         self.context.handshake_started_at
@@ -458,6 +599,21 @@ impl Handshaker {
     }
 }
 
+async fn within_timeout(exchange: impl std::future::Future<Output = Result<()>>) -> Result<()> {
+    tokio::time::timeout(Duration::from_millis(HANDSHAKE_TIMEOUT_MS), exchange)
+        .await
+        .map_err(|_| GridError::Timeout)?
+}
+
+async fn send_message<W: AsyncWrite + Unpin>(writer: &mut W, msg: &Message) -> Result<()> {
+    let bytes = msg.encode().map_err(|e| GridError::SerializationError(e.to_string()))?;
+    write_frame(writer, &bytes).await
+}
+
+async fn recv_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Message> {
+    Message::decode(&read_frame(reader).await?).map_err(|e| GridError::SerializationError(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -513,6 +669,119 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_handshake_exchanges_device_capabilities() {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+
+        let mut beefy = DeviceCapabilities::detect();
+        beefy.capacity_score = 95;
+        beefy.max_layers = 64;
+        let mut small = DeviceCapabilities::detect();
+        small.capacity_score = 20;
+        small.max_layers = 8;
+
+        let mut initiator = Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default())
+            .with_device(beefy);
+        let mut responder = Handshaker::new_responder(responder_id, responder_key, Capabilities::default())
+            .with_device(small);
+
+        let hello = initiator.start().unwrap();
+        let challenge = responder.process(hello).unwrap().unwrap();
+        assert_eq!(responder.remote_device().map(|d| d.max_layers), Some(64));
+
+        let prove = initiator.process(challenge).unwrap().unwrap();
+        let welcome = responder.process(prove).unwrap().unwrap();
+        initiator.process(welcome).unwrap();
+        let remote = initiator.remote_device().unwrap();
+        assert_eq!((remote.capacity_score, remote.max_layers), (20, 8));
+    }
+
+    #[test]
+    fn test_tampered_device_advert_fails_signature() {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+
+        let mut initiator = Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default())
+            .with_device(DeviceCapabilities::detect());
+        let mut responder = Handshaker::new_responder(responder_id, responder_key, Capabilities::default());
+
+        let Message::Hello { protocol_version, node_id, pubkey, capabilities, x25519_pubkey, timestamp, signature, .. } =
+            initiator.start().unwrap()
+        else {
+            panic!("expected HELLO");
+        };
+        let mut inflated = DeviceCapabilities::detect();
+        inflated.capacity_score = 100;
+        let forged = Message::Hello {
+            protocol_version,
+            node_id,
+            pubkey,
+            capabilities,
+            device: inflated.encode(),
+            x25519_pubkey,
+            timestamp,
+            signature,
+        };
+        assert!(matches!(responder.process(forged), Err(GridError::InvalidSignature)));
+        assert!(responder.remote_device().is_none());
+    }
+
+    #[test]
+    fn test_welcome_reports_observed_addr() {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+        let seen: std::net::SocketAddr = "203.0.113.7:40001".parse().unwrap();
+
+        let mut initiator = Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default());
+        let mut responder = Handshaker::new_responder(responder_id, responder_key, Capabilities::default())
+            .with_remote_addr(seen);
+
+        let hello = initiator.start().unwrap();
+        let challenge = responder.process(hello).unwrap().unwrap();
+        let prove = initiator.process(challenge).unwrap().unwrap();
+        let welcome = responder.process(prove).unwrap().unwrap();
+        assert!(initiator.observed_addr().is_none());
+        initiator.process(welcome).unwrap();
+
+        assert_eq!(initiator.observed_addr(), Some(PeerAddress::from(seen)));
+        assert!(responder.observed_addr().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_over_stream() {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+        let seen: std::net::SocketAddr = "203.0.113.7:40001".parse().unwrap();
+
+        let mut initiator = Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default());
+        let mut responder = Handshaker::new_responder(responder_id, responder_key, Capabilities::default())
+            .with_remote_addr(seen);
+        let (mut a, mut b) = tokio::io::duplex(4096);
+
+        let (dialed, accepted) = tokio::join!(initiator.initiate(&mut a), responder.respond(&mut b));
+        dialed.unwrap();
+        accepted.unwrap();
+        assert_eq!(initiator.observed_addr(), Some(PeerAddress::from(seen)));
+        assert_eq!(responder.remote_node_id(), Some(initiator_id));
+
+        // A peer that never answers times out instead of hanging
+        let key = SigningKey::generate(&mut OsRng);
+        let id = NodeId::from_pubkey(&key.verifying_key().to_bytes());
+        let (mut a, _silent) = tokio::io::duplex(4096);
+        tokio::time::pause();
+        let result = Handshaker::new_initiator(id, key, Capabilities::default()).initiate(&mut a).await;
+        assert!(matches!(result, Err(GridError::Timeout)));
+    }
+
     #[test]
     fn test_session_encryption() {
         let initiator_key = SigningKey::generate(&mut OsRng);
@@ -624,6 +893,7 @@ mod tests {
             node_id: NodeId::from_pubkey(&fake_pubkey),
             pubkey: fake_pubkey,
             capabilities: caps_encoded,
+            device: Vec::new(),
             x25519_pubkey: *x25519_public.as_bytes(),
             timestamp: old_timestamp,
             signature: signature.to_bytes().to_vec(),
@@ -706,6 +976,7 @@ mod tests {
             node_id: NodeId::from_pubkey(&fake_pubkey),
             pubkey: fake_pubkey,
             capabilities: caps_encoded,
+            device: Vec::new(),
             x25519_pubkey: *x25519_public.as_bytes(),
             timestamp,
             signature: signature.to_bytes().to_vec(),
@@ -746,6 +1017,7 @@ mod tests {
             node_id: NodeId::from_pubkey(&fake_pubkey),
             pubkey: fake_pubkey,
             capabilities: caps_encoded,
+            device: Vec::new(),
             x25519_pubkey: *x25519_public.as_bytes(),
             timestamp,
             signature: invalid_sig.to_vec(),
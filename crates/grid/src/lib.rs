@@ -1,17 +1,36 @@
+pub mod address;
+#[cfg(feature = "network")]
 pub mod discovery;
 pub mod error;
 pub mod handshake;
 pub mod orchestrator;
 pub mod peer;
+#[cfg(feature = "network")]
 pub mod pipeline;
 pub mod relay;
+pub mod session;
+pub mod sim;
+pub mod transport;
 pub mod wire;
 
+pub use address::PeerAddress;
+#[cfg(feature = "network")]
 pub use discovery::{Discovery, DiscoveryEvent, KademliaDiscovery, LanDiscovery, MdnsDiscovery};
 pub use error::{GridError, Result};
 pub use handshake::{HandshakeState, Handshaker, SessionKeys};
-pub use orchestrator::GridOrchestrator;
-pub use peer::{Capabilities, NodeId, PeerInfo, PeerStore};
-pub use pipeline::{PipelineCoordinator, PipelineConfig, PipelineStatus, PipelineRole};
+pub use orchestrator::{
+    GridOrchestrator, PeerLink, RelayedPayload, TaskOutcome, RELAYED_PAYLOAD_EVENT, TASK_OUTCOME_EVENT,
+};
+pub use peer::{Capabilities, NodeId, PeerFilter, PeerInfo, PeerStore, NEUTRAL_TRUST};
+#[cfg(feature = "network")]
+pub use pipeline::{
+    LocalExecutorFn, NodeHealth, PipelineCoordinator, PipelineConfig, PipelineHealth, PipelineStatus, PipelineRole,
+    DEFAULT_TOTAL_LAYERS, PIPELINE_RESHARDED_EVENT,
+};
 pub use relay::{BeaconStore, RelayBeacon, RelayEncryption, RelayNode, RotatingIdentity};
-pub use wire::{Message, SessionParams, TaskStatus, PROTOCOL_VERSION};
+pub use session::{Session, MAX_MISSED_HEARTBEATS};
+pub use sim::{LinkConfig, SimNet, SimTransport};
+#[cfg(not(target_arch = "wasm32"))]
+pub use transport::TcpTransport;
+pub use transport::{Conn, Connection, GridTransport, InMemoryTransport, Incoming};
+pub use wire::{read_frame, write_frame, Message, SessionParams, TaskStatus, PROTOCOL_VERSION};
@@ -1,11 +1,14 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, oneshot, RwLock};
 use tracing::{debug, info, warn, error};
 
+use crate::address::PeerAddress;
 use crate::error::{GridError, Result};
-use crate::peer::{NodeId, PeerStore};
+use crate::peer::{NodeId, PeerFilter, PeerInfo, PeerStore};
+use crate::transport::{Conn, GridTransport};
 use crate::wire::{Message, TaskStatus};
 use cortex_core::event::{Event, Payload};
 use cortex_core::runtime::EventBus;
@@ -13,13 +16,49 @@ use cortex_core::runtime::EventBus;
 const TASK_TIMEOUT: Duration = Duration::from_secs(60);
 const MAX_RETRIES: u32 = 3;
 
+/// How long to wait for the rendezvous to answer a punch request
+const PUNCH_NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Dials per hole punch; the first usually races the peer's NAT mapping
+const PUNCH_ATTEMPTS: u32 = 3;
+const PUNCH_DIAL_TIMEOUT: Duration = Duration::from_secs(2);
+const PUNCH_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Event kind carrying a bincode `TaskOutcome` for each finished delegation
+pub const TASK_OUTCOME_EVENT: &str = "grid.task.outcome";
+/// Event kind carrying a bincode `RelayedPayload` for traffic that reached
+/// us through a rendezvous
+pub const RELAYED_PAYLOAD_EVENT: &str = "grid.relay.payload";
+
+/// Which peer a delegated task went to and whether it succeeded, so the
+/// reputation layer can update that peer's trust
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub task_id: [u8; 32],
+    pub peer: NodeId,
+    pub success: bool,
+}
+
+/// Data a peer sent through the rendezvous because no direct link exists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayedPayload {
+    pub from: NodeId,
+    pub payload: Vec<u8>,
+}
+
+/// How `connect_assisted` reached a peer
+pub enum PeerLink {
+    /// The hole punch worked
+    Direct(Conn),
+    /// Traffic goes through the rendezvous with `send_relayed`
+    Relayed { via: NodeId },
+}
+
 #[derive(Debug, Clone)]
 struct PendingTask {
     #[allow(dead_code)]  // Used for debugging and future implementations
     task_id: [u8; 32],
     #[allow(dead_code)]  // May be used for retry logic
     payload: Vec<u8>,
-    #[allow(dead_code)]  // Used for routing retries
     target_node: NodeId,
     created_at: Instant,
     retries: u32,
@@ -36,6 +75,12 @@ pub struct GridOrchestrator {
     message_rx: Option<mpsc::Receiver<(NodeId, Message)>>,
     shutdown_tx: mpsc::Sender<()>,
     shutdown_rx: Option<mpsc::Receiver<()>>,
+    rendezvous: Option<NodeId>,
+    transport: Option<Arc<dyn GridTransport>>,
+    /// `connect_assisted` calls waiting for the rendezvous, by target peer
+    punches: Arc<RwLock<HashMap<NodeId, oneshot::Sender<PeerAddress>>>>,
+    punched_tx: mpsc::Sender<(NodeId, Conn)>,
+    punched_rx: Option<mpsc::Receiver<(NodeId, Conn)>>,
 }
 
 impl GridOrchestrator {
@@ -46,6 +91,7 @@ impl GridOrchestrator {
     ) -> Self {
         let (message_tx, message_rx) = mpsc::channel(256);
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let (punched_tx, punched_rx) = mpsc::channel(16);
 
         Self {
             _local_node_id: local_node_id,
@@ -56,9 +102,32 @@ impl GridOrchestrator {
             message_rx: Some(message_rx),
             shutdown_tx,
             shutdown_rx: Some(shutdown_rx),
+            rendezvous: None,
+            transport: default_transport(),
+            punches: Arc::new(RwLock::new(HashMap::new())),
+            punched_tx,
+            punched_rx: Some(punched_rx),
         }
     }
 
+    /// Relay that coordinates hole punches and carries traffic when they fail
+    pub fn with_rendezvous(mut self, rendezvous: NodeId) -> Self {
+        self.rendezvous = Some(rendezvous);
+        self
+    }
+
+    /// Transport used to dial peers directly
+    pub fn with_transport(mut self, transport: Arc<dyn GridTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Direct links opened because a peer asked the rendezvous to punch
+    /// through to us. Can only be taken once.
+    pub fn take_punched_links(&mut self) -> Option<mpsc::Receiver<(NodeId, Conn)>> {
+        self.punched_rx.take()
+    }
+
     /// Get the message sender for sending grid messages
     pub fn message_sender(&self) -> Result<mpsc::Sender<(NodeId, Message)>> {
         self.message_tx
@@ -75,6 +144,18 @@ impl GridOrchestrator {
             Message::TaskAck { task_id, status } => {
                 self.handle_task_ack(task_id, status).await
             }
+            Message::PunchNotify { peer, addr } if Some(from) == self.rendezvous => {
+                self.handle_punch_notify(peer, addr).await;
+                Ok(())
+            }
+            Message::RelayedData { peer, payload } if Some(from) == self.rendezvous => {
+                let relayed = RelayedPayload { from: peer, payload };
+                let bytes = bincode::serialize(&relayed)
+                    .map_err(|e| GridError::SerializationError(e.to_string()))?;
+                self.event_bus
+                    .publish(Event::new("grid.orchestrator", RELAYED_PAYLOAD_EVENT, Payload::inline(bytes)))
+                    .map_err(|e| GridError::EventBusError(e.to_string()))
+            }
             _ => {
                 debug!("Ignoring non-task message: {:?}", message);
                 Ok(())
@@ -153,6 +234,7 @@ impl GridOrchestrator {
                         Payload::inline(task_id.to_vec()),
                     );
                     let _ = self.event_bus.publish(event);
+                    publish_outcome(&self.event_bus, task_id, task.target_node, true);
                     pending.remove(&task_id);
                 }
                 TaskStatus::Failed | TaskStatus::Rejected => {
@@ -164,6 +246,7 @@ impl GridOrchestrator {
                         Payload::inline(task_id.to_vec()),
                     );
                     let _ = self.event_bus.publish(event);
+                    publish_outcome(&self.event_bus, task_id, task.target_node, false);
 
                     // Check for retries
                     if task.retries < MAX_RETRIES {
@@ -180,6 +263,69 @@ impl GridOrchestrator {
         Ok(())
     }
 
+    /// Reach `peer` through the rendezvous: it tells both sides the other's
+    /// observed external address and both dial at once, which opens a path
+    /// through most home NATs. If that fails, traffic is relayed.
+    pub async fn connect_assisted(&self, peer: NodeId) -> Result<PeerLink> {
+        let rendezvous = self
+            .rendezvous
+            .ok_or_else(|| GridError::RelayError("no rendezvous configured".to_string()))?;
+        let tx = self.message_sender()?;
+
+        let (notify_tx, notify_rx) = oneshot::channel();
+        self.punches.write().await.insert(peer, notify_tx);
+        if tx.send((rendezvous, Message::PunchRequest { target: peer })).await.is_err() {
+            self.punches.write().await.remove(&peer);
+            return Err(GridError::ChannelClosed);
+        }
+
+        match tokio::time::timeout(PUNCH_NOTIFY_TIMEOUT, notify_rx).await {
+            Ok(Ok(addr)) => {
+                if let Some(conn) = self.punch(addr).await {
+                    info!("Hole punch to {} at {} succeeded", peer.short(), addr);
+                    return Ok(PeerLink::Direct(conn));
+                }
+            }
+            _ => {
+                self.punches.write().await.remove(&peer);
+            }
+        }
+
+        info!("Hole punch to {} failed, relaying through {}", peer.short(), rendezvous.short());
+        Ok(PeerLink::Relayed { via: rendezvous })
+    }
+
+    /// Send `payload` to `peer` through the rendezvous `via`
+    pub async fn send_relayed(&self, via: NodeId, peer: NodeId, payload: Vec<u8>) -> Result<()> {
+        self.message_sender()?
+            .send((via, Message::RelayedData { peer, payload }))
+            .await
+            .map_err(|_| GridError::ChannelClosed)
+    }
+
+    async fn handle_punch_notify(&self, peer: NodeId, addr: PeerAddress) {
+        if let Some(waiting) = self.punches.write().await.remove(&peer) {
+            let _ = waiting.send(addr);
+            return;
+        }
+
+        // The other side asked for this punch; dial back so both NATs open
+        debug!("Punching back to {} at {}", peer.short(), addr);
+        let Some(transport) = self.transport.clone() else {
+            return;
+        };
+        let punched_tx = self.punched_tx.clone();
+        tokio::spawn(async move {
+            if let Some(conn) = punch(transport.as_ref(), addr).await {
+                let _ = punched_tx.send((peer, conn)).await;
+            }
+        });
+    }
+
+    async fn punch(&self, addr: PeerAddress) -> Option<Conn> {
+        punch(self.transport.as_deref()?, addr).await
+    }
+
     /// Delegate a task to a remote peer with the can_compute capability
     pub async fn delegate_task(&self, task_id: [u8; 32], payload: Vec<u8>) -> Result<NodeId> {
         // Find peers with compute capability
@@ -192,11 +338,8 @@ impl GridOrchestrator {
             return Err(GridError::NoPeersAvailable);
         }
 
-        // Select peer with lowest latency (or first if no latency info)
-        let target_peer = peers
-            .iter()
-            .min_by_key(|p| p.latency_ms.unwrap_or(u32::MAX))
-            .ok_or(GridError::NoPeersAvailable)?;
+        let filter = self.peer_store.filter().await;
+        let target_peer = select_target(&peers, &filter).ok_or(GridError::NoPeersAvailable)?;
 
         let target_node = target_peer.node_id;
 
@@ -265,7 +408,8 @@ impl GridOrchestrator {
                                     .find_by_capability(|caps| caps.can_compute)
                                     .await;
 
-                                if let Some(peer) = peers.first() {
+                                let filter = peer_store.filter().await;
+                                if let Some(peer) = select_target(&peers, &filter) {
                                     let target_node = peer.node_id;
 
                                     // Store as pending
@@ -373,6 +517,7 @@ impl GridOrchestrator {
                                                 Payload::inline(task_id.to_vec()),
                                             );
                                             let _ = event_bus_msg.publish(event);
+                                            publish_outcome(&event_bus_msg, task_id, task.target_node, true);
                                             tasks.remove(&task_id);
                                         }
                                         TaskStatus::Failed | TaskStatus::Rejected => {
@@ -382,6 +527,7 @@ impl GridOrchestrator {
                                                 Payload::inline(task_id.to_vec()),
                                             );
                                             let _ = event_bus_msg.publish(event);
+                                            publish_outcome(&event_bus_msg, task_id, task.target_node, false);
 
                                             if task.retries < MAX_RETRIES {
                                                 task.retries += 1;
@@ -427,6 +573,58 @@ impl GridOrchestrator {
     }
 }
 
+/// Pick the compute peer to delegate to.
+///
+/// Candidates have already matched on capability, so the capability match
+/// is 1.0 and the score reduces to the peer's trust. Flaky peers are
+/// deprioritised rather than excluded; latency breaks ties. Peers the
+/// filter refuses are never chosen, even if they were stored before it
+/// changed.
+fn select_target<'a>(peers: &'a [PeerInfo], filter: &PeerFilter) -> Option<&'a PeerInfo> {
+    peers.iter().filter(|peer| filter.allows(&peer.node_id)).max_by(|a, b| {
+        a.trust_score
+            .total_cmp(&b.trust_score)
+            .then_with(|| b.latency_ms.unwrap_or(u32::MAX).cmp(&a.latency_ms.unwrap_or(u32::MAX)))
+    })
+}
+
+fn publish_outcome(event_bus: &EventBus, task_id: [u8; 32], peer: NodeId, success: bool) {
+    let outcome = TaskOutcome { task_id, peer, success };
+    match bincode::serialize(&outcome) {
+        Ok(bytes) => {
+            let _ = event_bus.publish(Event::new("grid.orchestrator", TASK_OUTCOME_EVENT, Payload::inline(bytes)));
+        }
+        Err(e) => warn!("Failed to encode task outcome: {}", e),
+    }
+}
+
+/// Dial `addr` a few times; early attempts often hit a NAT that has not yet
+/// seen the peer's outgoing packets
+async fn punch(transport: &dyn GridTransport, addr: PeerAddress) -> Option<Conn> {
+    let target = addr.to_string();
+    for attempt in 1..=PUNCH_ATTEMPTS {
+        match tokio::time::timeout(PUNCH_DIAL_TIMEOUT, transport.connect(&target)).await {
+            Ok(Ok(conn)) => return Some(conn),
+            Ok(Err(e)) => debug!("Punch attempt {} to {} failed: {}", attempt, target, e),
+            Err(_) => debug!("Punch attempt {} to {} timed out", attempt, target),
+        }
+        if attempt < PUNCH_ATTEMPTS {
+            tokio::time::sleep(PUNCH_RETRY_DELAY).await;
+        }
+    }
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_transport() -> Option<Arc<dyn GridTransport>> {
+    Some(Arc::new(crate::transport::TcpTransport))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_transport() -> Option<Arc<dyn GridTransport>> {
+    None
+}
+
 fn hex_id(bytes: &[u8]) -> String {
     bytes.iter().take(4).map(|b| format!("{:02x}", b)).collect()
 }
@@ -435,8 +633,16 @@ fn hex_id(bytes: &[u8]) -> String {
 mod tests {
     use super::*;
     use crate::peer::{Capabilities, PeerInfo};
+    use crate::transport::InMemoryTransport;
+    use crate::wire::{read_frame, write_frame};
     use std::time::Duration;
 
+    fn assisted(transport: &InMemoryTransport, rendezvous: NodeId) -> GridOrchestrator {
+        GridOrchestrator::new(NodeId::random(), PeerStore::new(Duration::from_secs(60)), Arc::new(EventBus::default()))
+            .with_rendezvous(rendezvous)
+            .with_transport(Arc::new(transport.clone()))
+    }
+
     #[tokio::test]
     async fn test_orchestrator_creation() {
         let node_id = NodeId::random();
@@ -471,4 +677,128 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(orchestrator.pending_count().await, 1);
     }
+
+    #[tokio::test]
+    async fn test_delegation_prefers_trusted_peers() {
+        let peer_store = PeerStore::new(Duration::from_secs(60));
+        let event_bus = Arc::new(EventBus::default());
+        let mut outcomes = event_bus.subscribe(TASK_OUTCOME_EVENT);
+
+        // The flaky peer is faster but has a poor track record
+        let flaky = NodeId::random();
+        let reliable = NodeId::random();
+        for (id, latency, trust) in [(flaky, 5, 0.1), (reliable, 50, 0.9)] {
+            let mut peer = PeerInfo::new(id, [0u8; 32]);
+            peer.capabilities.can_compute = true;
+            peer.latency_ms = Some(latency);
+            peer.trust_score = trust;
+            peer_store.insert(peer).await;
+        }
+
+        let orchestrator = GridOrchestrator::new(NodeId::random(), peer_store.clone(), event_bus);
+        let task_id = [2u8; 32];
+        assert_eq!(orchestrator.delegate_task(task_id, b"work".to_vec()).await.unwrap(), reliable);
+
+        orchestrator.handle_task_ack(task_id, TaskStatus::Failed).await.unwrap();
+        let event = outcomes.recv().await.unwrap();
+        let outcome: TaskOutcome = bincode::deserialize(event.payload.as_bytes().unwrap()).unwrap();
+        assert_eq!(outcome.peer, reliable);
+        assert!(!outcome.success);
+
+        // Once trust drops below the other peer's, routing switches
+        peer_store.update_trust(&reliable, 0.05).await;
+        assert_eq!(orchestrator.delegate_task([3u8; 32], b"work".to_vec()).await.unwrap(), flaky);
+    }
+
+    #[tokio::test]
+    async fn test_connect_assisted_punches_through() {
+        let transport = InMemoryTransport::new();
+        let rendezvous = NodeId::random();
+        let peer = NodeId::random();
+        let peer_addr: PeerAddress = "198.51.100.9:51234".parse().unwrap();
+        let mut listener = transport.listen(&peer_addr.to_string()).await.unwrap();
+
+        let mut orchestrator = assisted(&transport, rendezvous);
+        let mut outbound = orchestrator.message_rx.take().unwrap();
+        let orchestrator = Arc::new(orchestrator);
+
+        let connecting = tokio::spawn({
+            let orchestrator = Arc::clone(&orchestrator);
+            async move { orchestrator.connect_assisted(peer).await }
+        });
+
+        let (to, request) = outbound.recv().await.unwrap();
+        assert_eq!(to, rendezvous);
+        assert!(matches!(request, Message::PunchRequest { target } if target == peer));
+
+        // Only the rendezvous may steer our dials
+        let spoofed = Message::PunchNotify { peer, addr: "192.0.2.1:1".parse().unwrap() };
+        orchestrator.handle_message(NodeId::random(), spoofed).await.unwrap();
+        let notify = Message::PunchNotify { peer, addr: peer_addr };
+        orchestrator.handle_message(rendezvous, notify).await.unwrap();
+
+        let mut conn = match connecting.await.unwrap().unwrap() {
+            PeerLink::Direct(conn) => conn,
+            PeerLink::Relayed { .. } => panic!("punch should have connected"),
+        };
+        let (mut accepted, _) = listener.accept().await.unwrap();
+        write_frame(&mut conn, b"direct").await.unwrap();
+        assert_eq!(read_frame(&mut accepted).await.unwrap(), b"direct");
+    }
+
+    #[tokio::test]
+    async fn test_connect_assisted_falls_back_to_relay() {
+        let transport = InMemoryTransport::new();
+        let rendezvous = NodeId::random();
+        let peer = NodeId::random();
+
+        let mut orchestrator = assisted(&transport, rendezvous);
+        let mut outbound = orchestrator.message_rx.take().unwrap();
+        let orchestrator = Arc::new(orchestrator);
+
+        let connecting = tokio::spawn({
+            let orchestrator = Arc::clone(&orchestrator);
+            async move { orchestrator.connect_assisted(peer).await }
+        });
+        outbound.recv().await.unwrap();
+        // Nothing answers at the observed address, e.g. a symmetric NAT
+        let notify = Message::PunchNotify { peer, addr: "198.51.100.9:51234".parse().unwrap() };
+        orchestrator.handle_message(rendezvous, notify).await.unwrap();
+
+        let via = match connecting.await.unwrap().unwrap() {
+            PeerLink::Relayed { via } => via,
+            PeerLink::Direct(_) => panic!("nothing was listening"),
+        };
+        assert_eq!(via, rendezvous);
+
+        orchestrator.send_relayed(via, peer, b"hi".to_vec()).await.unwrap();
+        let (to, message) = outbound.recv().await.unwrap();
+        assert_eq!(to, rendezvous);
+        assert!(matches!(message, Message::RelayedData { peer: p, payload } if p == peer && payload == b"hi"));
+    }
+
+    #[tokio::test]
+    async fn test_punch_requested_by_peer_dials_back() {
+        let transport = InMemoryTransport::new();
+        let rendezvous = NodeId::random();
+        let peer = NodeId::random();
+        let peer_addr: PeerAddress = "203.0.113.7:40001".parse().unwrap();
+        let _listener = transport.listen(&peer_addr.to_string()).await.unwrap();
+
+        let mut orchestrator = assisted(&transport, rendezvous);
+        let mut links = orchestrator.take_punched_links().unwrap();
+        let mut relayed = orchestrator.event_bus.subscribe(RELAYED_PAYLOAD_EVENT);
+
+        let notify = Message::PunchNotify { peer, addr: peer_addr };
+        orchestrator.handle_message(rendezvous, notify).await.unwrap();
+        let (from, _conn) = links.recv().await.unwrap();
+        assert_eq!(from, peer);
+
+        let data = Message::RelayedData { peer, payload: b"via relay".to_vec() };
+        orchestrator.handle_message(rendezvous, data).await.unwrap();
+        let event = relayed.recv().await.unwrap();
+        let payload: RelayedPayload = bincode::deserialize(event.payload.as_bytes().unwrap()).unwrap();
+        assert_eq!(payload.from, peer);
+        assert_eq!(payload.payload, b"via relay");
+    }
 }
@@ -1,11 +1,23 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
+use tracing::debug;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+
+use cortex_core::device::DeviceCapabilities;
+use cortex_core::work_distributor::WorkPeer;
+
+use crate::address::PeerAddress;
+use crate::error::GridError;
+use crate::handshake::Handshaker;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct NodeId(pub [u8; 32]);
 
 impl NodeId {
@@ -24,21 +36,81 @@ impl NodeId {
         Self(*hash.as_bytes())
     }
 
+    /// Dev/test helper: generate keypairs until one's id (via `from_pubkey`)
+    /// starts with `prefix_hex`, giving up after `max_attempts`. Returns
+    /// `None` if no key matched or the prefix isn't hex.
+    ///
+    /// Each hex digit multiplies the expected work by 16: a 4-digit prefix
+    /// takes ~65k keygens, 8 digits ~4 billion. Keep prefixes short.
+    pub fn mine_prefix(prefix_hex: &str, max_attempts: u64) -> Option<(SigningKey, NodeId)> {
+        let prefix = prefix_hex.to_ascii_lowercase();
+        if prefix.len() > 64 || !prefix.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let prefix_bytes = prefix.len().div_ceil(2);
+
+        for _ in 0..max_attempts {
+            let key = SigningKey::generate(&mut OsRng);
+            let id = Self::from_pubkey(key.verifying_key().as_bytes());
+            if hex::encode(&id.0[..prefix_bytes]).starts_with(&prefix) {
+                return Some((key, id));
+            }
+        }
+        None
+    }
+
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
 
-    pub fn short_id(&self) -> String {
+    /// First 4 bytes as 8 hex chars, for logs and UI labels
+    pub fn short(&self) -> String {
         hex::encode(&self.0[..4])
     }
+
+    /// All 32 bytes as 64 hex chars, the form `from_hex` parses
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    #[deprecated(note = "renamed to `short`")]
+    pub fn short_id(&self) -> String {
+        self.short()
+    }
+
+    /// Kademlia XOR distance to `key`; compare results as big-endian
+    /// integers (array ordering does this)
+    pub fn distance(&self, key: &[u8; 32]) -> [u8; 32] {
+        let mut distance = [0u8; 32];
+        for (d, (a, b)) in distance.iter_mut().zip(self.0.iter().zip(key)) {
+            *d = a ^ b;
+        }
+        distance
+    }
+
+    /// Parse the full 64-char hex form produced by `to_hex`
+    pub fn from_hex(s: &str) -> Result<Self, GridError> {
+        let bytes = hex::decode(s.trim()).ok_or(GridError::InvalidNodeId)?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| GridError::InvalidNodeId)?;
+        Ok(Self(bytes))
+    }
 }
 
+/// First 8 bytes as 16 hex chars; use `to_hex` for the full id
 impl std::fmt::Display for NodeId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", hex::encode(&self.0[..8]))
     }
 }
 
+impl FromStr for NodeId {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
 impl AsRef<[u8]> for NodeId {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -74,6 +146,9 @@ impl Capabilities {
     }
 }
 
+/// Trust assigned to peers with no delegation history
+pub const NEUTRAL_TRUST: f32 = 0.5;
+
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
     pub node_id: NodeId,
@@ -83,6 +158,11 @@ pub struct PeerInfo {
     pub last_seen: Instant,
     pub latency_ms: Option<u32>,
     pub reputation: i32,
+    /// How reliably this peer completes delegated work, 0.0 to 1.0
+    /// (0.5 = no history). Set from the reputation `TrustGraph`.
+    pub trust_score: f32,
+    /// Hardware the peer advertised in its handshake, if any
+    pub device: Option<DeviceCapabilities>,
 }
 
 impl PeerInfo {
@@ -95,9 +175,30 @@ impl PeerInfo {
             last_seen: Instant::now(),
             latency_ms: None,
             reputation: 0,
+            trust_score: NEUTRAL_TRUST,
+            device: None,
         }
     }
 
+    /// This report about a peer we already know as `old`. What we measured
+    /// ourselves (latency, trust, reputation) survives, as do the key,
+    /// addresses and device when the report lacks them.
+    fn merged_over(mut self, old: &PeerInfo) -> Self {
+        if self.pubkey == [0u8; 32] {
+            self.pubkey = old.pubkey;
+        }
+        if self.addresses.is_empty() {
+            self.addresses = old.addresses.clone();
+        }
+        if self.device.is_none() {
+            self.device = old.device.clone();
+        }
+        self.latency_ms = self.latency_ms.or(old.latency_ms);
+        self.reputation = old.reputation;
+        self.trust_score = old.trust_score;
+        self
+    }
+
     pub fn is_stale(&self, timeout: Duration) -> bool {
         self.last_seen.elapsed() > timeout
     }
@@ -107,22 +208,160 @@ impl PeerInfo {
     }
 }
 
+impl WorkPeer for PeerInfo {
+    fn node_id(&self) -> String {
+        self.node_id.to_string()
+    }
+
+    fn address(&self) -> String {
+        self.addresses.first().map(|a| a.to_string()).unwrap_or_default()
+    }
+
+    fn device(&self) -> Option<&DeviceCapabilities> {
+        self.device.as_ref()
+    }
+
+    fn latency_ms(&self) -> Option<u32> {
+        self.latency_ms
+    }
+}
+
+/// Which peers this node accepts into its peer table and delegates to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeerFilter {
+    #[default]
+    AllowAll,
+    /// Only these peers
+    Allow(HashSet<NodeId>),
+    /// Every peer except these
+    Block(HashSet<NodeId>),
+}
+
+impl PeerFilter {
+    pub fn allow(peers: impl IntoIterator<Item = NodeId>) -> Self {
+        Self::Allow(peers.into_iter().collect())
+    }
+
+    pub fn block(peers: impl IntoIterator<Item = NodeId>) -> Self {
+        Self::Block(peers.into_iter().collect())
+    }
+
+    pub fn allows(&self, node_id: &NodeId) -> bool {
+        match self {
+            PeerFilter::AllowAll => true,
+            PeerFilter::Allow(peers) => peers.contains(node_id),
+            PeerFilter::Block(peers) => !peers.contains(node_id),
+        }
+    }
+}
+
+/// Distinct external addresses remembered by `PeerStore::record_observed`
+const MAX_OBSERVED_ADDRESSES: usize = 16;
+/// Distinct peers that must report an external address before it is
+/// advertised, so one peer cannot plant an address on us
+pub const OBSERVED_CONFIRMATIONS: usize = 2;
+
 pub struct PeerStore {
     peers: Arc<RwLock<HashMap<NodeId, PeerInfo>>>,
+    filter: Arc<RwLock<PeerFilter>>,
     stale_timeout: Duration,
+    /// Our own addresses as other peers reported seeing them, with who
+    /// reported each
+    observed: Arc<RwLock<HashMap<PeerAddress, HashSet<NodeId>>>>,
+    /// Bumped whenever a peer joins, leaves or changes capabilities
+    changes: Arc<watch::Sender<u64>>,
 }
 
 impl PeerStore {
     pub fn new(stale_timeout: Duration) -> Self {
         Self {
             peers: Arc::new(RwLock::new(HashMap::new())),
+            filter: Arc::new(RwLock::new(PeerFilter::AllowAll)),
             stale_timeout,
+            observed: Arc::new(RwLock::new(HashMap::new())),
+            changes: Arc::new(watch::channel(0).0),
         }
     }
 
-    pub async fn insert(&self, peer: PeerInfo) {
-        let mut peers = self.peers.write().await;
-        peers.insert(peer.node_id, peer);
+    pub fn with_filter(self, filter: PeerFilter) -> Self {
+        Self {
+            filter: Arc::new(RwLock::new(filter)),
+            ..self
+        }
+    }
+
+    /// Replace the filter and evict stored peers it no longer allows.
+    /// Clones of this store share the filter.
+    pub async fn set_filter(&self, filter: PeerFilter) {
+        let evicted = {
+            let mut peers = self.peers.write().await;
+            let before = peers.len();
+            peers.retain(|node_id, _| filter.allows(node_id));
+            before != peers.len()
+        };
+        *self.filter.write().await = filter;
+        if evicted {
+            self.notify_change();
+        }
+    }
+
+    /// Watch for membership changes: the value ticks each time a peer
+    /// joins, leaves or changes its capabilities or device
+    pub fn subscribe_changes(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    fn notify_change(&self) {
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    pub async fn filter(&self) -> PeerFilter {
+        self.filter.read().await.clone()
+    }
+
+    /// Add a peer, or merge a fresh report into the one we know (see
+    /// `PeerInfo::merged_over`). Returns `false` and drops the peer if the
+    /// filter refuses it.
+    pub async fn insert(&self, peer: PeerInfo) -> bool {
+        if !self.filter.read().await.allows(&peer.node_id) {
+            debug!("Dropping filtered peer {}", peer.node_id.short());
+            return false;
+        }
+        let changed = {
+            let mut peers = self.peers.write().await;
+            let (peer, changed) = match peers.get(&peer.node_id) {
+                Some(old) => {
+                    let peer = peer.merged_over(old);
+                    let changed = old.capabilities != peer.capabilities || old.device != peer.device;
+                    (peer, changed)
+                }
+                None => (peer, true),
+            };
+            peers.insert(peer.node_id, peer);
+            changed
+        };
+        if changed {
+            self.notify_change();
+        }
+        true
+    }
+
+    /// Keep the hardware `node_id` advertised, adding the peer if unknown
+    async fn set_device(&self, node_id: NodeId, device: DeviceCapabilities) {
+        if !self.filter.read().await.allows(&node_id) {
+            return;
+        }
+        let changed = {
+            let mut peers = self.peers.write().await;
+            let peer = peers.entry(node_id).or_insert_with(|| PeerInfo::new(node_id, [0u8; 32]));
+            peer.touch();
+            let changed = peer.device.as_ref() != Some(&device);
+            peer.device = Some(device);
+            changed
+        };
+        if changed {
+            self.notify_change();
+        }
     }
 
     pub async fn get(&self, node_id: &NodeId) -> Option<PeerInfo> {
@@ -131,8 +370,11 @@ impl PeerStore {
     }
 
     pub async fn remove(&self, node_id: &NodeId) -> Option<PeerInfo> {
-        let mut peers = self.peers.write().await;
-        peers.remove(node_id)
+        let removed = self.peers.write().await.remove(node_id);
+        if removed.is_some() {
+            self.notify_change();
+        }
+        removed
     }
 
     pub async fn touch(&self, node_id: &NodeId) {
@@ -149,6 +391,13 @@ impl PeerStore {
         }
     }
 
+    pub async fn update_trust(&self, node_id: &NodeId, trust_score: f32) {
+        let mut peers = self.peers.write().await;
+        if let Some(peer) = peers.get_mut(node_id) {
+            peer.trust_score = trust_score.clamp(0.0, 1.0);
+        }
+    }
+
     pub async fn list_active(&self) -> Vec<PeerInfo> {
         let peers = self.peers.read().await;
         peers
@@ -159,16 +408,77 @@ impl PeerStore {
     }
 
     pub async fn prune_stale(&self) -> usize {
-        let mut peers = self.peers.write().await;
-        let before = peers.len();
-        peers.retain(|_, p| !p.is_stale(self.stale_timeout));
-        before - peers.len()
+        let pruned = {
+            let mut peers = self.peers.write().await;
+            let before = peers.len();
+            peers.retain(|_, p| !p.is_stale(self.stale_timeout));
+            before - peers.len()
+        };
+        if pruned > 0 {
+            self.notify_change();
+        }
+        pruned
     }
 
     pub async fn count(&self) -> usize {
         self.peers.read().await.len()
     }
 
+    /// The `k` active peers whose ids are XOR-closest to `target`, nearest
+    /// first
+    pub async fn closest_to(&self, target: &[u8; 32], k: usize) -> Vec<PeerInfo> {
+        let mut peers = self.list_active().await;
+        peers.sort_by_cached_key(|p| p.node_id.distance(target));
+        peers.truncate(k);
+        peers
+    }
+
+    /// Note that `reporter` saw us connecting from `addr` (the
+    /// `observed_addr` of a handshake WELCOME). Reports from filtered peers
+    /// are ignored. When full, the address with the fewest reporters makes
+    /// room. Returns true when this report confirms `addr`, i.e. it was the
+    /// `OBSERVED_CONFIRMATIONS`th distinct reporter.
+    pub async fn record_observed(&self, addr: PeerAddress, reporter: NodeId) -> bool {
+        if !self.filter.read().await.allows(&reporter) {
+            return false;
+        }
+        let mut observed = self.observed.write().await;
+        if !observed.contains_key(&addr) && observed.len() >= MAX_OBSERVED_ADDRESSES {
+            let weakest = observed
+                .iter()
+                .min_by_key(|(_, reporters)| reporters.len())
+                .map(|(addr, _)| *addr);
+            if let Some(weakest) = weakest {
+                observed.remove(&weakest);
+            }
+        }
+        let reporters = observed.entry(addr).or_default();
+        reporters.insert(reporter) && reporters.len() == OBSERVED_CONFIRMATIONS
+    }
+
+    /// Record what a completed handshake with `remote` told us: the
+    /// hardware it advertised, and how it sees our address. Returns our
+    /// external address if this handshake confirmed it.
+    pub async fn record_handshake(&self, handshaker: &Handshaker, remote: NodeId) -> Option<PeerAddress> {
+        if let Some(device) = handshaker.remote_device() {
+            self.set_device(remote, device.clone()).await;
+        }
+        let addr = handshaker.observed_addr()?;
+        self.record_observed(addr, remote).await.then_some(addr)
+    }
+
+    /// Addresses other peers see us at, most widely reported first. These
+    /// are what to advertise for wide-area discovery.
+    pub async fn observed_addresses(&self) -> Vec<PeerAddress> {
+        let observed = self.observed.read().await;
+        let mut addresses: Vec<_> = observed
+            .iter()
+            .map(|(addr, reporters)| (reporters.len(), *addr))
+            .collect();
+        addresses.sort_by_key(|(reports, addr)| (std::cmp::Reverse(*reports), addr.to_string()));
+        addresses.into_iter().map(|(_, addr)| addr).collect()
+    }
+
     pub async fn find_by_capability<F>(&self, predicate: F) -> Vec<PeerInfo>
     where
         F: Fn(&Capabilities) -> bool,
@@ -186,7 +496,10 @@ impl Clone for PeerStore {
     fn clone(&self) -> Self {
         Self {
             peers: Arc::clone(&self.peers),
+            filter: Arc::clone(&self.filter),
             stale_timeout: self.stale_timeout,
+            observed: Arc::clone(&self.observed),
+            changes: Arc::clone(&self.changes),
         }
     }
 }
@@ -195,4 +508,203 @@ mod hex {
     pub fn encode(bytes: &[u8]) -> String {
         bytes.iter().map(|b| format!("{:02x}", b)).collect()
     }
+
+    pub fn decode(s: &str) -> Option<Vec<u8>> {
+        if !s.len().is_multiple_of(2) || !s.is_ascii() {
+            return None;
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_id_hex_round_trip() {
+        let id = NodeId::random();
+        let text = id.to_hex();
+        assert_eq!(text.len(), 64);
+        assert_eq!(NodeId::from_hex(&text).unwrap(), id);
+        assert_eq!(text.parse::<NodeId>().unwrap(), id);
+        assert_eq!(id.to_string(), text[..16]);
+        assert_eq!(id.short(), text[..8]);
+
+        // Display is a label, not a parseable id
+        assert!(id.to_string().parse::<NodeId>().is_err());
+
+        assert!(NodeId::from_hex(&text[..8]).is_err());
+        assert!(NodeId::from_hex("zz").is_err());
+        assert!("".parse::<NodeId>().is_err());
+    }
+
+    #[test]
+    fn test_mine_prefix() {
+        // One nibble matches 1 in 16 keys; 2000 attempts all failing is ~1e-56
+        let (key, id) = NodeId::mine_prefix("A", 2000).unwrap();
+        assert!(id.to_string().starts_with('a'));
+        assert_eq!(NodeId::from_pubkey(key.verifying_key().as_bytes()), id);
+
+        assert!(NodeId::mine_prefix("xyz", 10).is_none());
+        assert!(NodeId::mine_prefix("0", 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_blocked_peer_never_inserted() {
+        let blocked = NodeId::random();
+        let store = PeerStore::new(Duration::from_secs(60)).with_filter(PeerFilter::block([blocked]));
+
+        // Discovery re-announces peers every few seconds
+        for _ in 0..5 {
+            assert!(!store.insert(PeerInfo::new(blocked, [0u8; 32])).await);
+        }
+        assert!(store.get(&blocked).await.is_none());
+
+        let other = NodeId::random();
+        assert!(store.insert(PeerInfo::new(other, [0u8; 32])).await);
+        assert_eq!(store.count().await, 1);
+
+        // Switching to an allowlist evicts everyone not on it, including from clones
+        let clone = store.clone();
+        store.set_filter(PeerFilter::allow([blocked])).await;
+        assert_eq!(clone.count().await, 0);
+        assert!(clone.insert(PeerInfo::new(blocked, [0u8; 32])).await);
+        assert!(!clone.insert(PeerInfo::new(other, [0u8; 32])).await);
+    }
+
+    #[tokio::test]
+    async fn test_closest_to() {
+        let store = PeerStore::new(Duration::from_secs(60));
+        let id = |first: u8, last: u8| {
+            let mut bytes = [0u8; 32];
+            bytes[0] = first;
+            bytes[31] = last;
+            NodeId(bytes)
+        };
+        for node_id in [id(0x80, 0), id(0x01, 0), id(0x00, 0x07), id(0x00, 0x02), id(0x40, 0)] {
+            store.insert(PeerInfo::new(node_id, [0u8; 32])).await;
+        }
+
+        let target = id(0x00, 0x03).0;
+        let closest: Vec<NodeId> = store.closest_to(&target, 3).await.into_iter().map(|p| p.node_id).collect();
+        // Distances: 0x00..01, 0x00..04, 0x01..03
+        assert_eq!(closest, vec![id(0x00, 0x02), id(0x00, 0x07), id(0x01, 0)]);
+
+        assert_eq!(store.closest_to(&target, 10).await.len(), 5);
+        assert!(store.closest_to(&target, 0).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_observed_addresses() {
+        let store = PeerStore::new(Duration::from_secs(60));
+        let nat: PeerAddress = "203.0.113.7:40001".parse().unwrap();
+        let other: PeerAddress = "198.51.100.9:7654".parse().unwrap();
+        let (a, b, liar) = (NodeId::random(), NodeId::random(), NodeId::random());
+
+        assert!(!store.record_observed(other, a).await);
+        assert!(!store.record_observed(nat, a).await);
+        assert!(store.record_observed(nat, b).await);
+        // Repeat reports from one peer count once
+        assert!(!store.record_observed(other, a).await);
+        assert!(!store.record_observed(nat, b).await);
+        assert_eq!(store.observed_addresses().await, vec![nat, other]);
+
+        store.set_filter(PeerFilter::block([liar])).await;
+        assert!(!store.record_observed("192.0.2.1:1".parse().unwrap(), liar).await);
+        assert_eq!(store.clone().observed_addresses().await.len(), 2);
+
+        for port in 0..MAX_OBSERVED_ADDRESSES as u16 {
+            store.record_observed(nat.with_port(port), b).await;
+        }
+        let observed = store.observed_addresses().await;
+        assert_eq!(observed.len(), MAX_OBSERVED_ADDRESSES);
+        assert_eq!(observed[0], nat);
+    }
+
+    #[tokio::test]
+    async fn test_insert_merges_into_known_peer() {
+        let store = PeerStore::new(Duration::from_secs(60));
+        let id = NodeId::random();
+        let mut peer = PeerInfo::new(id, [7u8; 32]);
+        peer.addresses = vec!["10.0.0.1:7654".parse().unwrap()];
+        peer.device = Some(DeviceCapabilities::detect());
+        store.insert(peer).await;
+        store.update_latency(&id, 42).await;
+        store.update_trust(&id, 0.9).await;
+
+        // Rediscovery knows only the address and capabilities
+        let mut again = PeerInfo::new(id, [0u8; 32]);
+        again.addresses = vec!["10.0.0.2:7654".parse().unwrap()];
+        again.capabilities.can_compute = true;
+        store.insert(again).await;
+
+        let peer = store.get(&id).await.unwrap();
+        assert_eq!(peer.latency_ms, Some(42));
+        assert_eq!(peer.trust_score, 0.9);
+        assert_eq!(peer.pubkey, [7u8; 32]);
+        assert!(peer.device.is_some());
+        assert_eq!(peer.addresses, vec!["10.0.0.2:7654".parse().unwrap()]);
+        assert!(peer.capabilities.can_compute);
+    }
+
+    #[tokio::test]
+    async fn test_record_handshake_keeps_remote_device() {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+        let mut device = DeviceCapabilities::detect();
+        device.max_layers = 12;
+
+        let mut initiator = Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default())
+            .with_device(DeviceCapabilities::detect());
+        let mut responder = Handshaker::new_responder(responder_id, responder_key, Capabilities::default())
+            .with_device(device);
+        let challenge = responder.process(initiator.start().unwrap()).unwrap().unwrap();
+        let prove = initiator.process(challenge).unwrap().unwrap();
+        initiator.process(responder.process(prove).unwrap().unwrap()).unwrap();
+
+        let store = PeerStore::new(Duration::from_secs(60));
+        let changes = store.subscribe_changes();
+        store.record_handshake(&initiator, responder_id).await;
+        let peer = store.get(&responder_id).await.unwrap();
+        assert_eq!(peer.device.map(|d| d.max_layers), Some(12));
+        assert!(changes.has_changed().unwrap());
+
+        // The responder learned the initiator's hardware too, but it is blocked
+        assert!(responder.remote_device().is_some());
+        store.set_filter(PeerFilter::block([initiator_id])).await;
+        store.record_handshake(&responder, initiator_id).await;
+        assert!(store.get(&initiator_id).await.is_none());
+    }
+
+    #[test]
+    fn test_simulate_plans_peers_with_devices() {
+        use cortex_core::work_distributor::WorkDistributor;
+
+        let mut device = DeviceCapabilities::detect();
+        device.capacity_score = 50;
+        device.max_layers = 24;
+
+        let mut near = PeerInfo::new(NodeId::random(), [0u8; 32]);
+        near.device = Some(device.clone());
+        near.latency_ms = Some(5);
+        let mut far = PeerInfo::new(NodeId::random(), [0u8; 32]);
+        far.device = Some(device);
+        far.latency_ms = Some(500);
+        let unknown = PeerInfo::new(NodeId::random(), [0u8; 32]);
+
+        let plan = WorkDistributor::simulate(24, &[near.clone(), unknown, far]);
+        assert_eq!(plan.peers.len(), 2);
+        assert_eq!(plan.peers[0].node_id, near.node_id.to_string());
+
+        // The distant peer finishes last
+        let slowest = plan.peers[1].estimated_finish_ms();
+        assert_eq!(plan.estimated_makespan().as_secs_f64(), slowest / 1000.0);
+        assert!(slowest > plan.peers[0].estimated_finish_ms());
+    }
 }
@@ -13,12 +13,27 @@
 //! 5. Each node only needs enough RAM for its layers!
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use serde::{Serialize, Deserialize};
 use tracing::{info, warn};
 
+use cortex_core::event::{Event, Payload};
+use cortex_core::runtime::EventBus;
+use cortex_core::work_distributor::WorkDistributor;
+
+use crate::peer::PeerInfo;
+use crate::transport::{GridTransport, TcpTransport};
 use crate::{NodeId, PeerStore};
 
+/// How long a health probe waits for a node's task server to accept
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Event kind carrying the bincode `Vec<PipelineNode>` of a topology that
+/// `rebuild_on_change` just swapped in
+pub const PIPELINE_RESHARDED_EVENT: &str = "pipeline.resharded";
+
 /// Pipeline configuration
 #[derive(Debug, Clone)]
 pub struct PipelineConfig {
@@ -28,14 +43,47 @@ pub struct PipelineConfig {
     pub layers_per_node: u32,
     /// Model name (e.g., "llama-70b")
     pub model_name: String,
+    /// Layer count read from the loaded model, `None` when no model is
+    /// loaded and `total_layers` is a guess
+    pub model_layers: Option<u32>,
 }
 
+/// Layers assumed when no model is loaded (LLaMA-70B has ~80 layers)
+pub const DEFAULT_TOTAL_LAYERS: u32 = 80;
+
 impl Default for PipelineConfig {
     fn default() -> Self {
         Self {
-            total_layers: 80,      // LLaMA-70B has ~80 layers
+            total_layers: DEFAULT_TOTAL_LAYERS,
             layers_per_node: 4,    // Each node handles 4 layers
             model_name: "distributed-llm".to_string(),
+            model_layers: None,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// Split the loaded model's `layers` instead of the default guess
+    pub fn with_model_layers(mut self, layers: u32) -> Self {
+        self.total_layers = layers;
+        self.model_layers = Some(layers);
+        self
+    }
+
+    /// Reject a topology that could not run the loaded model
+    pub fn validate(&self) -> Result<(), String> {
+        if self.total_layers == 0 {
+            return Err("Model has no layers".to_string());
+        }
+        if self.layers_per_node == 0 {
+            return Err("layers_per_node must be at least 1".to_string());
+        }
+        match self.model_layers {
+            Some(layers) if layers != self.total_layers => Err(format!(
+                "Pipeline splits {} layers but {} has {}",
+                self.total_layers, self.model_name, layers
+            )),
+            _ => Ok(()),
         }
     }
 }
@@ -49,10 +97,28 @@ pub enum PipelineRole {
     Middle { layers: (u32, u32) },
     /// Final node - receives hidden states, produces output tokens
     Tail { layers: (u32, u32) },
+    /// Only node - runs every layer, input tokens to output tokens
+    Solo { layers: (u32, u32) },
     /// Not part of the pipeline
     Inactive,
 }
 
+impl PipelineRole {
+    /// First and last layer this node runs, `None` when inactive
+    pub fn layer_range(&self) -> Option<(u32, u32)> {
+        match self {
+            PipelineRole::Head { layers }
+            | PipelineRole::Middle { layers }
+            | PipelineRole::Tail { layers }
+            | PipelineRole::Solo { layers } => Some(*layers),
+            PipelineRole::Inactive => None,
+        }
+    }
+}
+
+/// Runs the whole model in-process for a `Solo` pipeline
+pub type LocalExecutorFn = Arc<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+
 /// Hidden state passed between pipeline nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HiddenState {
@@ -78,12 +144,58 @@ pub struct PipelineNode {
     pub latency_ms: u32,
 }
 
+/// Result of probing one pipeline node
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeHealth {
+    pub node_id: NodeId,
+    pub address: String,
+    pub reachable: bool,
+    /// Time to connect, when reachable
+    pub latency_ms: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Reachability of every node in the active topology
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineHealth {
+    pub nodes: Vec<NodeHealth>,
+}
+
+impl PipelineHealth {
+    pub fn unreachable(&self) -> impl Iterator<Item = &NodeHealth> {
+        self.nodes.iter().filter(|n| !n.reachable)
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        !self.nodes.is_empty() && self.unreachable().next().is_none()
+    }
+
+    /// `Err("N of M nodes unreachable: ...")` unless every node answered
+    pub fn ensure_healthy(&self) -> Result<(), String> {
+        if self.nodes.is_empty() {
+            return Err("Pipeline not built. Call build_pipeline() first".to_string());
+        }
+        let down: Vec<String> = self
+            .unreachable()
+            .map(|n| format!("{} ({})", n.node_id.short(), n.address))
+            .collect();
+        if down.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("{} of {} nodes unreachable: {}", down.len(), self.nodes.len(), down.join(", ")))
+        }
+    }
+}
+
 /// Pipeline coordinator - manages the distributed model
 pub struct PipelineCoordinator {
     pub config: PipelineConfig,
     pub nodes: Arc<RwLock<Vec<PipelineNode>>>,
     pub peer_store: Arc<PeerStore>,
     node_id: NodeId,
+    transport: Arc<dyn GridTransport>,
+    local_executor: Option<LocalExecutorFn>,
+    event_bus: Option<Arc<EventBus>>,
 }
 
 impl PipelineCoordinator {
@@ -93,11 +205,36 @@ impl PipelineCoordinator {
             nodes: Arc::new(RwLock::new(Vec::new())),
             peer_store,
             node_id,
+            transport: Arc::new(TcpTransport),
+            local_executor: None,
+            event_bus: None,
         }
     }
 
-    /// Assign pipeline roles to available nodes
+    /// Reach pipeline nodes over `transport` instead of TCP
+    pub fn with_transport(mut self, transport: Arc<dyn GridTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Run `Solo` pipelines in-process with `executor` instead of sending
+    /// the prompt to the node
+    pub fn with_local_executor(mut self, executor: LocalExecutorFn) -> Self {
+        self.local_executor = Some(executor);
+        self
+    }
+
+    /// Publish `PIPELINE_RESHARDED_EVENT` on `event_bus` when the topology
+    /// changes
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// Assign pipeline roles to available nodes. A single compute node gets
+    /// the whole model as `Solo`.
     pub async fn build_pipeline(&self) -> Result<Vec<PipelineNode>, String> {
+        self.config.validate()?;
         let peers = self.peer_store
             .find_by_capability(|caps| caps.can_compute)
             .await;
@@ -119,7 +256,9 @@ impl PipelineCoordinator {
             let start_layer = (i as u32) * self.config.layers_per_node;
             let end_layer = start_layer + self.config.layers_per_node - 1;
             
-            let role = if i == 0 {
+            let role = if available == 1 {
+                PipelineRole::Solo { layers: (0, self.config.total_layers.saturating_sub(1)) }
+            } else if i == 0 {
                 PipelineRole::Head { layers: (start_layer, end_layer) }
             } else if i == available - 1 {
                 PipelineRole::Tail { layers: (start_layer, end_layer) }
@@ -139,7 +278,7 @@ impl PipelineCoordinator {
                 latency_ms: peer.latency_ms.unwrap_or(0),
             };
 
-            info!("   Node {} → {:?}", &peer.node_id.to_string()[..8], node.role);
+            info!("   Node {} → {:?}", peer.node_id.short(), node.role);
             pipeline_nodes.push(node);
         }
 
@@ -151,14 +290,186 @@ impl PipelineCoordinator {
         Ok(pipeline_nodes)
     }
 
+    /// Assign layers in proportion to each compute node's advertised
+    /// `DeviceCapabilities`, most capable node first. Layers are split evenly
+    /// when some node has not advertised its hardware.
+    pub async fn build_weighted_pipeline(&self) -> Result<Vec<PipelineNode>, String> {
+        let nodes = self.plan_weighted().await?;
+        *self.nodes.write().await = nodes.clone();
+        info!("✅ Weighted pipeline ready: {} nodes", nodes.len());
+        Ok(nodes)
+    }
+
+    async fn plan_weighted(&self) -> Result<Vec<PipelineNode>, String> {
+        self.config.validate()?;
+        let total_layers = self.config.total_layers;
+        let mut peers = self.peer_store
+            .find_by_capability(|caps| caps.can_compute)
+            .await;
+
+        if peers.is_empty() {
+            return Err("No compute nodes available".to_string());
+        }
+
+        let score = |peer: &PeerInfo| peer.device.as_ref().map_or(0, |d| d.capacity_score);
+        peers.sort_by(|a, b| score(b).cmp(&score(a)).then(a.node_id.cmp(&b.node_id)));
+        peers.truncate(total_layers as usize);
+
+        let ranges: Vec<(u32, u32)> = match peers.iter().map(|p| p.device.clone()).collect::<Option<Vec<_>>>() {
+            Some(devices) => {
+                let weighted: Vec<_> = peers
+                    .iter()
+                    .zip(devices)
+                    .map(|(peer, mut device)| {
+                        // The distributor cannot place a node capped at zero layers
+                        device.max_layers = device.max_layers.max(1);
+                        (peer.node_id.to_string(), String::new(), device)
+                    })
+                    .collect();
+                WorkDistributor::distribute(&self.config.model_name, total_layers, &weighted)
+                    .peers
+                    .iter()
+                    .map(|work| work.assigned_layers)
+                    .collect()
+            }
+            None => {
+                let count = peers.len() as u32;
+                let mut start = 0;
+                (0..count)
+                    .map(|i| {
+                        let share = total_layers / count + u32::from(i < total_layers % count);
+                        let range = (start, start + share - 1);
+                        start += share;
+                        range
+                    })
+                    .collect()
+            }
+        };
+
+        // Capacity caps can leave nothing for the last nodes
+        let assigned: Vec<_> = peers
+            .iter()
+            .zip(ranges)
+            .filter(|(_, (start, end))| end >= start)
+            .collect();
+
+        let count = assigned.len();
+        Ok(assigned
+            .into_iter()
+            .enumerate()
+            .map(|(i, (peer, layers))| {
+                let role = if count == 1 {
+                    PipelineRole::Solo { layers }
+                } else if i == 0 {
+                    PipelineRole::Head { layers }
+                } else if i == count - 1 {
+                    PipelineRole::Tail { layers }
+                } else {
+                    PipelineRole::Middle { layers }
+                };
+                PipelineNode {
+                    node_id: peer.node_id,
+                    role,
+                    address: peer.addresses.first().map(|a| a.to_string()).unwrap_or_default(),
+                    layers_loaded: false,
+                    latency_ms: peer.latency_ms.unwrap_or(0),
+                }
+            })
+            .collect())
+    }
+
+    /// Follow `PeerStore` membership: whenever a compute node joins, leaves
+    /// or changes its hardware, recompute the weighted distribution and swap
+    /// it in. Inference already running keeps the topology it started with;
+    /// the next request uses the new one.
+    pub fn rebuild_on_change(self: Arc<Self>) -> JoinHandle<()> {
+        let mut changes = self.peer_store.subscribe_changes();
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let nodes = match self.plan_weighted().await {
+                    Ok(nodes) => nodes,
+                    Err(e) => {
+                        warn!("Pipeline not resharded: {}", e);
+                        continue;
+                    }
+                };
+
+                {
+                    let mut active = self.nodes.write().await;
+                    if same_topology(&active, &nodes) {
+                        continue;
+                    }
+                    *active = nodes.clone();
+                }
+                info!("🔀 Pipeline resharded across {} nodes", nodes.len());
+
+                if let (Some(event_bus), Ok(bytes)) = (&self.event_bus, bincode::serialize(&nodes)) {
+                    let _ = event_bus.publish(Event::new("grid.pipeline", PIPELINE_RESHARDED_EVENT, Payload::inline(bytes)));
+                }
+            }
+        })
+    }
+
+    /// Probe every node's task server so a dead node is reported up front
+    /// instead of failing inference partway through. Reachable nodes get
+    /// their measured latency recorded.
+    pub async fn health_check(&self) -> PipelineHealth {
+        let snapshot = self.nodes.read().await.clone();
+        let nodes = futures::future::join_all(snapshot.iter().map(|node| self.probe(node))).await;
+
+        let mut active = self.nodes.write().await;
+        for health in &nodes {
+            if let (Some(latency_ms), Some(node)) =
+                (health.latency_ms, active.iter_mut().find(|n| n.node_id == health.node_id))
+            {
+                node.latency_ms = latency_ms;
+            }
+        }
+
+        let health = PipelineHealth { nodes };
+        if let Err(e) = health.ensure_healthy() {
+            warn!("🩺 Pipeline unhealthy: {}", e);
+        }
+        health
+    }
+
+    /// Connect to the node's task server and hang up without a request
+    async fn probe(&self, node: &PipelineNode) -> NodeHealth {
+        let started = Instant::now();
+        let result = match task_address(node) {
+            Ok(addr) => match tokio::time::timeout(HEALTH_PROBE_TIMEOUT, self.transport.connect(&addr)).await {
+                Ok(Ok(_conn)) => Ok(()),
+                Ok(Err(e)) => Err(format!("Connect failed: {}", e)),
+                Err(_) => Err("Timeout".to_string()),
+            },
+            Err(e) => Err(e),
+        };
+
+        NodeHealth {
+            node_id: node.node_id,
+            address: node.address.clone(),
+            reachable: result.is_ok(),
+            latency_ms: result.is_ok().then(|| started.elapsed().as_millis() as u32),
+            error: result.err(),
+        }
+    }
+
     /// Run inference through the pipeline
     pub async fn infer(&self, prompt: &str) -> Result<String, String> {
-        let nodes = self.nodes.read().await;
+        // A snapshot, so a reshard never waits on (or disturbs) this request
+        let nodes = self.nodes.read().await.clone();
         
         if nodes.is_empty() {
             return Err("Pipeline not built. Call build_pipeline() first".to_string());
         }
 
+        if let ([node], Some(executor)) = (nodes.as_slice(), &self.local_executor) {
+            if matches!(node.role, PipelineRole::Solo { .. }) {
+                info!("🚀 Solo pipeline: running all {} layers locally", self.config.total_layers);
+                return executor(prompt);
+            }
+        }
+
         let sequence_id = format!("seq-{}", uuid::Uuid::new_v4());
         info!("🚀 Starting pipeline inference: {}", sequence_id);
 
@@ -173,13 +484,8 @@ impl PipelineCoordinator {
             let node_start = std::time::Instant::now();
             
             info!("   Stage {}/{}: Node {} processing layers {:?}",
-                i + 1, nodes.len(), &node.node_id.to_string()[..8], 
-                match &node.role {
-                    PipelineRole::Head { layers } => layers,
-                    PipelineRole::Middle { layers } => layers,
-                    PipelineRole::Tail { layers } => layers,
-                    PipelineRole::Inactive => &(0, 0),
-                });
+                i + 1, nodes.len(), node.node_id.short(), 
+                node.role.layer_range().unwrap_or((0, 0)));
 
             // Send to node for processing
             match self.send_to_node(node, &current_output, &sequence_id, i as u32).await {
@@ -210,20 +516,9 @@ impl PipelineCoordinator {
         stage: u32,
     ) -> Result<String, String> {
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
-        use tokio::net::TcpStream;
-
-        // Get task port (node port + 1000)
-        let task_addr = if let Some((ip, port_str)) = node.address.rsplit_once(':') {
-            if let Ok(port) = port_str.parse::<u16>() {
-                format!("{}:{}", ip, port + 1000)
-            } else {
-                return Err("Invalid port".to_string());
-            }
-        } else {
-            return Err("Invalid address".to_string());
-        };
 
-        let mut stream = TcpStream::connect(&task_addr)
+        let task_addr = task_address(node)?;
+        let mut stream = self.transport.connect(&task_addr)
             .await
             .map_err(|e| format!("Connect failed: {}", e))?;
 
@@ -285,7 +580,11 @@ impl PipelineCoordinator {
     pub async fn status(&self) -> PipelineStatus {
         let nodes = self.nodes.read().await;
         let active_nodes = nodes.iter().filter(|n| n.role != PipelineRole::Inactive).count();
-        let total_layers = active_nodes as u32 * self.config.layers_per_node;
+        let total_layers = nodes
+            .iter()
+            .filter_map(|n| n.role.layer_range())
+            .map(|(start, end)| end - start + 1)
+            .sum();
         let equivalent_params = active_nodes as f32 * 0.5; // 0.5B per node
 
         PipelineStatus {
@@ -297,6 +596,18 @@ impl PipelineCoordinator {
     }
 }
 
+/// Where a node's task server listens: its grid port + 1000
+fn task_address(node: &PipelineNode) -> Result<String, String> {
+    let (ip, port_str) = node.address.rsplit_once(':').ok_or("Invalid address")?;
+    let port = port_str.parse::<u16>().map_err(|_| "Invalid port")?;
+    Ok(format!("{}:{}", ip, port + 1000))
+}
+
+fn same_topology(a: &[PipelineNode], b: &[PipelineNode]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| a.node_id == b.node_id && a.role == b.role && a.address == b.address)
+}
+
 /// Pipeline status info
 #[derive(Debug, Clone, Serialize)]
 pub struct PipelineStatus {
@@ -309,6 +620,7 @@ pub struct PipelineStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::InMemoryTransport;
 
     #[test]
     fn test_pipeline_config() {
@@ -317,5 +629,162 @@ mod tests {
         assert_eq!(config.layers_per_node, 4);
         // 80 layers / 4 per node = 20 nodes needed
     }
+
+    #[tokio::test]
+    async fn test_build_rejects_layer_count_that_disagrees_with_model() {
+        use std::time::Duration;
+
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        peer_store.insert(compute_peer(7001, 50)).await;
+
+        let config = PipelineConfig::default().with_model_layers(24);
+        assert_eq!(config.total_layers, 24);
+        let pipeline = PipelineCoordinator::new(NodeId::random(), peer_store.clone(), config.clone());
+        let nodes = pipeline.build_pipeline().await.unwrap();
+        assert_eq!(nodes[0].role, PipelineRole::Solo { layers: (0, 23) });
+
+        let stale = PipelineConfig { total_layers: DEFAULT_TOTAL_LAYERS, ..config };
+        let pipeline = PipelineCoordinator::new(NodeId::random(), peer_store, stale);
+        let error = pipeline.build_pipeline().await.unwrap_err();
+        assert!(error.contains("80 layers but distributed-llm has 24"), "{}", error);
+        assert!(pipeline.build_weighted_pipeline().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_single_peer_runs_solo_locally() {
+        use crate::peer::Capabilities;
+        use std::time::Duration;
+
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let mut peer = PeerInfo::new(NodeId::random(), [0u8; 32]);
+        peer.capabilities = Capabilities { can_compute: true, ..Default::default() };
+        peer.addresses.push("127.0.0.1:7000".parse().unwrap());
+        peer_store.insert(peer).await;
+
+        // Nothing listens on the in-memory network, so any transfer fails
+        let pipeline = PipelineCoordinator::new(NodeId::random(), peer_store, PipelineConfig::default())
+            .with_transport(Arc::new(InMemoryTransport::new()))
+            .with_local_executor(Arc::new(|prompt: &str| Ok(format!("echo: {}", prompt))));
+
+        let nodes = pipeline.build_pipeline().await.unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].role, PipelineRole::Solo { layers: (0, 79) });
+        assert_eq!(nodes[0].role.layer_range(), Some((0, 79)));
+        assert_eq!(pipeline.status().await.total_layers, 80);
+
+        assert_eq!(pipeline.infer("hi").await.unwrap(), "echo: hi");
+    }
+
+    /// Answer every pipeline request on `addr` with the payload tagged `name`
+    async fn serve_stage(transport: &InMemoryTransport, addr: &str, name: &'static str) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut incoming = transport.listen(addr).await.unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = incoming.accept().await {
+                // Health probes hang up without sending a request
+                let mut len = [0u8; 4];
+                if conn.read_exact(&mut len).await.is_err() {
+                    continue;
+                }
+                let mut request = vec![0u8; u32::from_be_bytes(len) as usize];
+                conn.read_exact(&mut request).await.unwrap();
+                let request: serde_json::Value = serde_json::from_slice(&request).unwrap();
+
+                let result = format!("{}|{}", request["payload"].as_str().unwrap(), name);
+                let response = serde_json::to_vec(&serde_json::json!({ "success": true, "result": result })).unwrap();
+                conn.write_all(&(response.len() as u32).to_be_bytes()).await.unwrap();
+                conn.write_all(&response).await.unwrap();
+            }
+        });
+    }
+
+    fn compute_peer(port: u16, capacity_score: u32) -> PeerInfo {
+        let mut device = cortex_core::device::DeviceCapabilities::detect();
+        device.capacity_score = capacity_score;
+        device.max_layers = 80;
+
+        let mut peer = PeerInfo::new(NodeId::random(), [0u8; 32]);
+        peer.capabilities.can_compute = true;
+        peer.addresses.push(format!("127.0.0.1:{}", port).parse().unwrap());
+        peer.device = Some(device);
+        peer
+    }
+
+    #[tokio::test]
+    async fn test_new_peer_reshards_pipeline() {
+        use std::time::Duration;
+
+        let transport = InMemoryTransport::new();
+        serve_stage(&transport, "127.0.0.1:8001", "small").await;
+        serve_stage(&transport, "127.0.0.1:8002", "large").await;
+
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        peer_store.insert(compute_peer(7001, 30)).await;
+
+        let event_bus = Arc::new(EventBus::default());
+        let mut resharded = event_bus.subscribe(PIPELINE_RESHARDED_EVENT);
+        let pipeline = Arc::new(
+            PipelineCoordinator::new(NodeId::random(), peer_store.clone(), PipelineConfig::default())
+                .with_transport(Arc::new(transport))
+                .with_event_bus(event_bus),
+        );
+
+        pipeline.build_weighted_pipeline().await.unwrap();
+        assert_eq!(pipeline.infer("x").await.unwrap(), "x|small");
+
+        let watcher = pipeline.clone().rebuild_on_change();
+        let large = compute_peer(7002, 90);
+        let large_id = large.node_id;
+        peer_store.insert(large).await;
+
+        let event = tokio::time::timeout(Duration::from_secs(5), resharded.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let nodes: Vec<PipelineNode> = bincode::deserialize(event.payload.as_bytes().unwrap()).unwrap();
+
+        // Three times the capacity earns three times the layers
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].node_id, large_id);
+        assert_eq!(nodes[0].role, PipelineRole::Head { layers: (0, 59) });
+        assert_eq!(nodes[1].role, PipelineRole::Tail { layers: (60, 79) });
+        assert_eq!(pipeline.status().await.active_nodes, 2);
+
+        assert_eq!(pipeline.infer("x").await.unwrap(), "x|large|small");
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_unreachable_nodes() {
+        use std::time::Duration;
+
+        let transport = InMemoryTransport::new();
+        serve_stage(&transport, "127.0.0.1:8001", "up").await;
+
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let up = compute_peer(7001, 50);
+        let up_id = up.node_id;
+        peer_store.insert(up).await;
+        // Nothing listens on 8002
+        let down = compute_peer(7002, 50);
+        let down_id = down.node_id;
+        peer_store.insert(down).await;
+
+        let pipeline = PipelineCoordinator::new(NodeId::random(), peer_store, PipelineConfig::default())
+            .with_transport(Arc::new(transport));
+        pipeline.build_weighted_pipeline().await.unwrap();
+
+        let health = pipeline.health_check().await;
+        assert!(!health.is_healthy());
+        let unreachable: Vec<_> = health.unreachable().map(|n| n.node_id).collect();
+        assert_eq!(unreachable, vec![down_id]);
+        assert!(health.nodes.iter().any(|n| n.node_id == up_id && n.latency_ms.is_some()));
+
+        let error = health.ensure_healthy().unwrap_err();
+        assert!(error.starts_with("1 of 2 nodes unreachable"), "{}", error);
+        // The probe left the live node's server able to serve requests
+        assert!(pipeline.health_check().await.nodes.iter().any(|n| n.node_id == up_id && n.reachable));
+    }
 }
 
@@ -2,15 +2,16 @@ use chacha20poly1305::{
     aead::{Aead, KeyInit},
     ChaCha20Poly1305, Nonce,
 };
-use rand::RngCore;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 use tracing::{debug, info};
 use x25519_dalek::{EphemeralSecret, PublicKey, ReusableSecret};
 
+use crate::address::PeerAddress;
 use crate::error::{GridError, Result};
 use crate::peer::NodeId;
 use crate::wire::Message;
@@ -19,6 +20,16 @@ const DEFAULT_TTL: u8 = 7;
 const MAX_HOP_COUNT: u8 = 15;
 const BEACON_EXPIRY: Duration = Duration::from_secs(3600);
 const IDENTITY_ROTATION_INTERVAL: Duration = Duration::from_secs(900);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_BEACONS: usize = 10_000;
+const DEFAULT_MAX_BEACON_BYTES: usize = 64 * 1024 * 1024;
+/// Recipient hash, ttl and hop count
+const BEACON_HEADER_BYTES: usize = 10;
+/// Recent (key, nonce) pairs remembered by the reuse guard
+const NONCE_GUARD_CAPACITY: usize = 65_536;
+
+/// Per-process beacon counter mixed into every nonce
+static NONCE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Clone)]
 pub struct RelayBeacon {
@@ -69,6 +80,11 @@ impl RelayBeacon {
         })
     }
 
+    /// Bytes this beacon accounts for against a `BeaconStore` budget
+    pub fn size_bytes(&self) -> usize {
+        BEACON_HEADER_BYTES + self.encrypted_payload.len()
+    }
+
     pub fn is_expired(&self) -> bool {
         self.created_at.elapsed() > BEACON_EXPIRY
     }
@@ -160,9 +176,64 @@ impl Default for RotatingIdentity {
     }
 }
 
+/// Remembers fingerprints of the most recent (key, nonce) pairs used for
+/// encryption in this process and refuses to use one twice. Keys are
+/// per-beacon ephemeral and nonces include a counter, so a hit means the
+/// RNG handed out the same ephemeral key again.
+struct NonceGuard {
+    seen: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+    capacity: usize,
+}
+
+impl NonceGuard {
+    fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn global() -> &'static Mutex<NonceGuard> {
+        static GUARD: OnceLock<Mutex<NonceGuard>> = OnceLock::new();
+        GUARD.get_or_init(|| Mutex::new(NonceGuard::new(NONCE_GUARD_CAPACITY)))
+    }
+
+    /// Record the pair, failing if it was already used
+    fn claim(&mut self, key: &[u8; 32], nonce: &[u8; 12]) -> Result<()> {
+        let mut hasher = blake3::Hasher::new_derive_key("cortex-relay-v1 nonce guard");
+        hasher.update(key);
+        hasher.update(nonce);
+        let fingerprint = *hasher.finalize().as_bytes();
+
+        if !self.seen.insert(fingerprint) {
+            return Err(GridError::EncryptionError("relay key/nonce pair reused".to_string()));
+        }
+        self.order.push_back(fingerprint);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct RelayEncryption;
 
 impl RelayEncryption {
+    /// Nonce for the `counter`-th beacon sealed under `ephemeral_public`.
+    /// Unique per beacon even if an ephemeral key were ever repeated.
+    fn beacon_nonce(ephemeral_public: &PublicKey, counter: u64) -> [u8; 12] {
+        let mut hasher = blake3::Hasher::new_derive_key("cortex-relay-v1 nonce");
+        hasher.update(ephemeral_public.as_bytes());
+        hasher.update(&counter.to_le_bytes());
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&hasher.finalize().as_bytes()[..12]);
+        nonce
+    }
+
     pub fn encrypt(
         recipient_pubkey: &PublicKey,
         plaintext: &[u8],
@@ -176,8 +247,12 @@ impl RelayEncryption {
         let cipher = ChaCha20Poly1305::new_from_slice(&key)
             .map_err(|e| GridError::EncryptionError(e.to_string()))?;
 
-        let mut nonce_bytes = [0u8; 12];
-        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+        let counter = NONCE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let nonce_bytes = Self::beacon_nonce(&ephemeral_public, counter);
+        NonceGuard::global()
+            .lock()
+            .map_err(|_| GridError::EncryptionError("nonce guard poisoned".to_string()))?
+            .claim(&key, &nonce_bytes)?;
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         let ciphertext = cipher
@@ -215,26 +290,63 @@ impl RelayEncryption {
     }
 }
 
+/// Beacons held for pickup or forwarding, bounded by count and total size.
+/// When either limit is hit the beacons closest to expiry are evicted first.
 pub struct BeaconStore {
     beacons: HashMap<[u8; 32], RelayBeacon>,
     by_recipient: HashMap<[u8; 8], Vec<[u8; 32]>>,
+    by_age: BTreeSet<(Instant, [u8; 32])>,
+    max_beacons: usize,
+    max_bytes: usize,
+    current_bytes: usize,
+    evictions: u64,
 }
 
 impl BeaconStore {
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MAX_BEACONS, DEFAULT_MAX_BEACON_BYTES)
+    }
+
+    pub fn with_capacity(max_beacons: usize, max_bytes: usize) -> Self {
         Self {
             beacons: HashMap::new(),
             by_recipient: HashMap::new(),
+            by_age: BTreeSet::new(),
+            max_beacons,
+            max_bytes,
+            current_bytes: 0,
+            evictions: 0,
         }
     }
 
+    /// Store a beacon, evicting older ones to stay within capacity. A beacon
+    /// larger than the whole byte budget is dropped and counted as an eviction.
     pub fn insert(&mut self, beacon: RelayBeacon) -> [u8; 32] {
         let hash = beacon.hash();
+        if self.beacons.contains_key(&hash) {
+            return hash;
+        }
+
+        let size = beacon.size_bytes();
+        if size > self.max_bytes || self.max_beacons == 0 {
+            self.evictions += 1;
+            return hash;
+        }
+
+        while self.beacons.len() >= self.max_beacons || self.current_bytes + size > self.max_bytes {
+            let Some(&(_, oldest)) = self.by_age.iter().next() else {
+                break;
+            };
+            self.remove(&oldest);
+            self.evictions += 1;
+        }
 
         self.by_recipient
             .entry(beacon.recipient_pubkey_hash)
             .or_default()
             .push(hash);
+        self.by_age.insert((beacon.created_at, hash));
+        self.current_bytes += size;
 
         self.beacons.insert(hash, beacon);
         hash
@@ -259,21 +371,47 @@ impl BeaconStore {
 
     pub fn prune_expired(&mut self) -> usize {
         let expired: Vec<_> = self
-            .beacons
+            .by_age
             .iter()
-            .filter(|(_, b)| b.is_expired())
-            .map(|(h, _)| *h)
+            .map(|(_, h)| *h)
+            .take_while(|h| self.beacons.get(h).is_some_and(|b| b.is_expired()))
             .collect();
 
-        let count = expired.len();
-        for hash in expired {
-            if let Some(beacon) = self.beacons.remove(&hash) {
-                if let Some(hashes) = self.by_recipient.get_mut(&beacon.recipient_pubkey_hash) {
-                    hashes.retain(|h| h != &hash);
-                }
+        for hash in &expired {
+            self.remove(hash);
+        }
+        expired.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.beacons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.beacons.is_empty()
+    }
+
+    /// Beacons dropped to stay within capacity since the store was created
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Total `RelayBeacon::size_bytes` of the stored beacons
+    pub fn current_bytes(&self) -> usize {
+        self.current_bytes
+    }
+
+    fn remove(&mut self, hash: &[u8; 32]) -> Option<RelayBeacon> {
+        let beacon = self.beacons.remove(hash)?;
+        self.by_age.remove(&(beacon.created_at, *hash));
+        self.current_bytes -= beacon.size_bytes();
+        if let Some(hashes) = self.by_recipient.get_mut(&beacon.recipient_pubkey_hash) {
+            hashes.retain(|h| h != hash);
+            if hashes.is_empty() {
+                self.by_recipient.remove(&beacon.recipient_pubkey_hash);
             }
         }
-        count
+        Some(beacon)
     }
 }
 
@@ -289,6 +427,8 @@ pub struct RelayNode {
     beacon_store: Arc<RwLock<BeaconStore>>,
     outbound_tx: mpsc::Sender<Message>,
     running: Arc<RwLock<bool>>,
+    /// External addresses peers were seen connecting from, for rendezvous
+    observed: Arc<RwLock<HashMap<NodeId, PeerAddress>>>,
 }
 
 impl RelayNode {
@@ -302,11 +442,18 @@ impl RelayNode {
                 beacon_store: Arc::new(RwLock::new(BeaconStore::new())),
                 outbound_tx: tx,
                 running: Arc::new(RwLock::new(false)),
+                observed: Arc::new(RwLock::new(HashMap::new())),
             },
             rx,
         )
     }
 
+    /// Replace the beacon store with one bounded by `max_beacons` and `max_bytes`
+    pub fn with_beacon_capacity(mut self, max_beacons: usize, max_bytes: usize) -> Self {
+        self.beacon_store = Arc::new(RwLock::new(BeaconStore::with_capacity(max_beacons, max_bytes)));
+        self
+    }
+
     pub async fn start(&self) -> Result<()> {
         *self.running.write().await = true;
         info!("Relay node started for {}", self.node_id);
@@ -335,7 +482,7 @@ impl RelayNode {
                     }
                 }
 
-                tokio::time::sleep(Duration::from_secs(60)).await;
+                tokio::time::sleep(PRUNE_INTERVAL).await;
             }
         });
 
@@ -412,6 +559,46 @@ impl RelayNode {
     pub async fn current_pubkey_hash(&self) -> [u8; 8] {
         *self.identity.read().await.pubkey_hash()
     }
+
+    /// Record the address `node` connected to us from. Behind a NAT this is
+    /// the external mapping the peer itself cannot see.
+    pub async fn observe(&self, node: NodeId, addr: PeerAddress) {
+        self.observed.write().await.insert(node, addr);
+    }
+
+    pub async fn forget(&self, node: &NodeId) {
+        self.observed.write().await.remove(node);
+    }
+
+    /// Act as rendezvous for a message from `from`, returning what to send
+    /// and to whom. A `PunchRequest` tells both peers the other's observed
+    /// address so they dial simultaneously; `RelayedData` is forwarded with
+    /// the sender swapped in. Other messages yield nothing.
+    pub async fn handle_rendezvous(&self, from: NodeId, message: Message) -> Result<Vec<(NodeId, Message)>> {
+        let observed = self.observed.read().await;
+        let addr_of = |node: &NodeId| {
+            observed
+                .get(node)
+                .copied()
+                .ok_or_else(|| GridError::PeerNotFound(node.to_string()))
+        };
+
+        match message {
+            Message::PunchRequest { target } => {
+                let (from_addr, target_addr) = (addr_of(&from)?, addr_of(&target)?);
+                debug!("Coordinating hole punch {} <-> {}", from.short(), target.short());
+                Ok(vec![
+                    (target, Message::PunchNotify { peer: from, addr: from_addr }),
+                    (from, Message::PunchNotify { peer: target, addr: target_addr }),
+                ])
+            }
+            Message::RelayedData { peer, payload } => {
+                addr_of(&peer)?;
+                Ok(vec![(peer, Message::RelayedData { peer: from, payload })])
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
 }
 
 impl Clone for RelayNode {
@@ -422,6 +609,7 @@ impl Clone for RelayNode {
             beacon_store: Arc::clone(&self.beacon_store),
             outbound_tx: self.outbound_tx.clone(),
             running: Arc::clone(&self.running),
+            observed: Arc::clone(&self.observed),
         }
     }
 }
@@ -457,6 +645,121 @@ mod tests {
         assert_eq!(decrypted, plaintext);
     }
 
+    #[test]
+    fn test_beacon_nonces_are_distinct() {
+        let recipient_secret = ReusableSecret::random_from_rng(rand::thread_rng());
+        let recipient_public = PublicKey::from(&recipient_secret);
+
+        let mut nonces = HashSet::new();
+        for i in 0..10_000u32 {
+            let (ciphertext, ephemeral_public) =
+                RelayEncryption::encrypt(&recipient_public, &i.to_le_bytes()).unwrap();
+            assert!(nonces.insert(<[u8; 12]>::try_from(&ciphertext[..12]).unwrap()));
+            if i % 1_000 == 0 {
+                let decrypted =
+                    RelayEncryption::decrypt(&recipient_secret, &ephemeral_public, &ciphertext).unwrap();
+                assert_eq!(decrypted, i.to_le_bytes());
+            }
+        }
+
+        // Same ephemeral key, different counters: still distinct
+        let ephemeral = PublicKey::from([9u8; 32]);
+        assert_ne!(
+            RelayEncryption::beacon_nonce(&ephemeral, 0),
+            RelayEncryption::beacon_nonce(&ephemeral, 1)
+        );
+    }
+
+    #[test]
+    fn test_nonce_guard_rejects_reuse() {
+        let mut guard = NonceGuard::new(2);
+        let (key, nonce) = ([1u8; 32], [2u8; 12]);
+        guard.claim(&key, &nonce).unwrap();
+        assert!(matches!(guard.claim(&key, &nonce), Err(GridError::EncryptionError(_))));
+        guard.claim(&[3u8; 32], &nonce).unwrap();
+        guard.claim(&key, &[4u8; 12]).unwrap();
+        // Evicted once the window moved on
+        assert_eq!(guard.order.len(), 2);
+        guard.claim(&key, &nonce).unwrap();
+    }
+
+    #[test]
+    fn test_beacon_store_evicts_within_budget() {
+        // Room for three 110 byte beacons by size, five by count
+        let mut store = BeaconStore::with_capacity(5, 350);
+        let mut hashes = Vec::new();
+        for i in 0..10u8 {
+            let beacon = RelayBeacon::new([i % 2; 8], vec![i; 100]);
+            hashes.push(store.insert(beacon));
+            assert!(store.current_bytes() <= 350);
+            assert!(store.len() <= 5);
+        }
+
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.current_bytes(), 330);
+        assert_eq!(store.evictions(), 7);
+        // Oldest went first
+        assert!(store.get(&hashes[0]).is_none());
+        assert!(hashes[7..].iter().all(|h| store.get(h).is_some()));
+
+        // Count limit applies on its own too
+        let mut store = BeaconStore::with_capacity(2, usize::MAX);
+        for i in 0..4u8 {
+            store.insert(RelayBeacon::new([0; 8], vec![i]));
+        }
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.evictions(), 2);
+        assert_eq!(store.find_for_recipient(&[0; 8]).len(), 2);
+
+        // Too large to ever fit
+        let mut store = BeaconStore::with_capacity(5, 50);
+        store.insert(RelayBeacon::new([0; 8], vec![0; 100]));
+        assert!(store.is_empty());
+        assert_eq!(store.evictions(), 1);
+    }
+
+    #[test]
+    fn test_beacon_store_prunes_expired() {
+        let mut store = BeaconStore::new();
+        let mut old = RelayBeacon::new([1; 8], vec![1]);
+        old.created_at = Instant::now() - BEACON_EXPIRY - Duration::from_secs(1);
+        store.insert(old);
+        let fresh = store.insert(RelayBeacon::new([1; 8], vec![2]));
+
+        assert_eq!(store.prune_expired(), 1);
+        assert_eq!(store.len(), 1);
+        assert!(store.get(&fresh).is_some());
+        assert_eq!(store.current_bytes(), BEACON_HEADER_BYTES + 1);
+    }
+
+    #[tokio::test]
+    async fn test_rendezvous_coordinates_punch() {
+        let (relay, _rx) = RelayNode::new(NodeId::random());
+        let (a, b, stranger) = (NodeId::random(), NodeId::random(), NodeId::random());
+        let a_addr: PeerAddress = "203.0.113.7:40001".parse().unwrap();
+        let b_addr: PeerAddress = "198.51.100.9:51234".parse().unwrap();
+        relay.observe(a, a_addr).await;
+        relay.observe(b, b_addr).await;
+
+        let out = relay.handle_rendezvous(a, Message::PunchRequest { target: b }).await.unwrap();
+        assert_eq!(out.len(), 2);
+        assert!(matches!(out[0], (to, Message::PunchNotify { peer, addr })
+            if to == b && peer == a && addr == a_addr));
+        assert!(matches!(out[1], (to, Message::PunchNotify { peer, addr })
+            if to == a && peer == b && addr == b_addr));
+
+        let data = Message::RelayedData { peer: b, payload: vec![1, 2, 3] };
+        let out = relay.handle_rendezvous(a, data).await.unwrap();
+        assert!(matches!(&out[..], [(to, Message::RelayedData { peer, payload })]
+            if *to == b && *peer == a && payload == &[1, 2, 3]));
+
+        let unknown = Message::PunchRequest { target: stranger };
+        assert!(matches!(relay.handle_rendezvous(a, unknown).await, Err(GridError::PeerNotFound(_))));
+        relay.forget(&b).await;
+        let data = Message::RelayedData { peer: b, payload: vec![] };
+        assert!(relay.handle_rendezvous(a, data).await.is_err());
+    }
+
     #[test]
     fn test_rotating_identity() {
         let mut identity = RotatingIdentity::new();
@@ -0,0 +1,303 @@
+//! Encrypted post-handshake sessions with keep-alive
+//!
+//! After the handshake every frame is a `wire::Message` encrypted with the
+//! session keys. The session sends a `Ping` every `heartbeat_interval_ms`
+//! and answers the peer's pings with `Pong`; any authenticated frame from
+//! the peer counts as a sign of life. Once `max_missed` pings in a row go
+//! unanswered the session closes and, if a peer store is attached, the peer
+//! is evicted, so a half-open connection is noticed without waiting for the
+//! next send to fail.
+//!
+//! Heartbeats are driven by `recv`, so keep a task reading the session.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tracing::{debug, warn};
+
+use crate::error::{GridError, Result};
+use crate::handshake::SessionKeys;
+use crate::peer::{NodeId, PeerStore};
+use crate::wire::{read_frame, write_frame, Message, SessionParams};
+
+/// Unanswered pings in a row before the peer is considered dead
+pub const MAX_MISSED_HEARTBEATS: u32 = 3;
+
+pub struct Session<S> {
+    remote: NodeId,
+    keys: SessionKeys,
+    writer: WriteHalf<S>,
+    // Frames are read on their own task so `recv` stays cancel-safe while
+    // it races the heartbeat timer
+    frames: mpsc::Receiver<Result<Vec<u8>>>,
+    reader: JoinHandle<()>,
+    heartbeat: Interval,
+    max_missed: u32,
+    missed: u32,
+    next_seq: u64,
+    last_heartbeat: Instant,
+    peer_store: Option<Arc<PeerStore>>,
+    closed: bool,
+}
+
+impl<S> Session<S>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    pub fn new(stream: S, remote: NodeId, keys: SessionKeys, params: &SessionParams) -> Self {
+        let (mut read_half, writer): (ReadHalf<S>, _) = tokio::io::split(stream);
+        let (tx, frames) = mpsc::channel(16);
+        let reader = tokio::spawn(async move {
+            loop {
+                let frame = read_frame(&mut read_half).await;
+                let failed = frame.is_err();
+                if tx.send(frame).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let period = Duration::from_millis(params.heartbeat_interval_ms.max(1) as u64);
+        let mut heartbeat = tokio::time::interval_at(Instant::now() + period, period);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            remote,
+            keys,
+            writer,
+            frames,
+            reader,
+            heartbeat,
+            max_missed: MAX_MISSED_HEARTBEATS,
+            missed: 0,
+            next_seq: 0,
+            last_heartbeat: Instant::now(),
+            peer_store: None,
+            closed: false,
+        }
+    }
+
+    /// Evict the peer from `peer_store` when it stops answering heartbeats
+    pub fn with_peer_store(mut self, peer_store: Arc<PeerStore>) -> Self {
+        self.peer_store = Some(peer_store);
+        self
+    }
+
+    pub fn with_max_missed(mut self, max_missed: u32) -> Self {
+        self.max_missed = max_missed.max(1);
+        self
+    }
+
+    pub fn remote(&self) -> NodeId {
+        self.remote
+    }
+
+    /// When the peer last sent a `Ping` or `Pong`, or when the session
+    /// started if it hasn't yet
+    pub fn last_heartbeat(&self) -> Instant {
+        self.last_heartbeat
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Encrypt and send one message
+    pub async fn send(&mut self, message: &Message) -> Result<()> {
+        if self.closed {
+            return Err(GridError::ChannelClosed);
+        }
+        let encoded = message
+            .encode()
+            .map_err(|e| GridError::SerializationError(e.to_string()))?;
+        let frame = self.keys.encrypt(&encoded)?;
+        write_frame(&mut self.writer, &frame).await
+    }
+
+    /// Next application message from the peer. Heartbeats are sent,
+    /// answered and consumed here; fails with `HeartbeatTimeout` and closes
+    /// the session if the peer goes quiet.
+    pub async fn recv(&mut self) -> Result<Message> {
+        loop {
+            if self.closed {
+                return Err(GridError::ChannelClosed);
+            }
+
+            tokio::select! {
+                frame = self.frames.recv() => {
+                    let frame = match frame {
+                        Some(Ok(frame)) => frame,
+                        Some(Err(e)) => {
+                            self.close().await;
+                            return Err(e);
+                        }
+                        None => {
+                            self.close().await;
+                            return Err(GridError::ChannelClosed);
+                        }
+                    };
+                    let plaintext = self.keys.decrypt(&frame)?;
+                    let message = Message::decode(&plaintext)
+                        .map_err(|e| GridError::SerializationError(e.to_string()))?;
+                    self.missed = 0;
+
+                    match message {
+                        Message::Ping { seq } => {
+                            self.last_heartbeat = Instant::now();
+                            self.send(&Message::Pong { seq }).await?;
+                        }
+                        Message::Pong { .. } => {
+                            self.last_heartbeat = Instant::now();
+                        }
+                        message => return Ok(message),
+                    }
+                }
+                _ = self.heartbeat.tick() => {
+                    if self.missed >= self.max_missed {
+                        warn!(
+                            "Peer {} missed {} heartbeats, closing session",
+                            self.remote.short(),
+                            self.missed
+                        );
+                        let missed = self.missed;
+                        self.expire().await;
+                        return Err(GridError::HeartbeatTimeout(missed));
+                    }
+                    let seq = self.next_seq;
+                    self.next_seq += 1;
+                    self.missed += 1;
+                    self.send(&Message::Ping { seq }).await?;
+                }
+            }
+        }
+    }
+
+    /// Shut down the connection; later `send`/`recv` calls fail with
+    /// `ChannelClosed`
+    pub async fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        self.reader.abort();
+        if let Err(e) = self.writer.shutdown().await {
+            debug!("Session shutdown with {}: {}", self.remote.short(), e);
+        }
+    }
+
+    async fn expire(&mut self) {
+        self.close().await;
+        if let Some(peer_store) = &self.peer_store {
+            peer_store.remove(&self.remote).await;
+        }
+    }
+}
+
+impl<S> Drop for Session<S> {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::Handshaker;
+    use crate::peer::{Capabilities, PeerInfo};
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+    use tokio::io::DuplexStream;
+
+    const INTERVAL_MS: u32 = 20;
+
+    fn handshake() -> (Handshaker, Handshaker, NodeId, NodeId) {
+        let initiator_key = SigningKey::generate(&mut OsRng);
+        let responder_key = SigningKey::generate(&mut OsRng);
+        let initiator_id = NodeId::from_pubkey(&initiator_key.verifying_key().to_bytes());
+        let responder_id = NodeId::from_pubkey(&responder_key.verifying_key().to_bytes());
+
+        let mut initiator =
+            Handshaker::new_initiator(initiator_id, initiator_key, Capabilities::default());
+        let mut responder =
+            Handshaker::new_responder(responder_id, responder_key, Capabilities::default());
+
+        let hello = initiator.start().unwrap();
+        let challenge = responder.process(hello).unwrap().unwrap();
+        let prove = initiator.process(challenge).unwrap().unwrap();
+        let welcome = responder.process(prove).unwrap().unwrap();
+        initiator.process(welcome).unwrap();
+
+        (initiator, responder, initiator_id, responder_id)
+    }
+
+    fn session(handshaker: &Handshaker, stream: DuplexStream, remote: NodeId) -> Session<DuplexStream> {
+        let keys = handshaker.session_keys().unwrap().clone();
+        let mut params = handshaker.session_params().unwrap().clone();
+        params.heartbeat_interval_ms = INTERVAL_MS;
+        Session::new(stream, remote, keys, &params)
+    }
+
+    #[tokio::test]
+    async fn test_into_session_after_handshake() {
+        let (initiator, responder, initiator_id, responder_id) = handshake();
+        assert_eq!(initiator.session_params().unwrap().heartbeat_interval_ms, 30000);
+
+        let (a, b) = tokio::io::duplex(4096);
+        let mut a = initiator.into_session(a, responder_id).unwrap();
+        let mut b = responder.into_session(b, initiator_id).unwrap();
+
+        a.send(&Message::Error { code: 7, message: "hi".into() }).await.unwrap();
+        assert!(matches!(b.recv().await.unwrap(), Message::Error { code: 7, .. }));
+
+        let (_, responder, _, _) = handshake();
+        let (stream, _) = tokio::io::duplex(64);
+        let stranger = NodeId::new([9u8; 32]);
+        assert!(matches!(
+            responder.into_session(stream, stranger),
+            Err(GridError::InvalidNodeId)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_keep_idle_session_alive() {
+        let (initiator, responder, initiator_id, responder_id) = handshake();
+        let (a, b) = tokio::io::duplex(4096);
+        let mut a = session(&initiator, a, responder_id);
+        let mut b = session(&responder, b, initiator_id);
+        let started = a.last_heartbeat();
+
+        let peer = tokio::spawn(async move { b.recv().await });
+
+        // Idle for well past the missed-heartbeat threshold
+        let idle = Duration::from_millis(INTERVAL_MS as u64 * 10);
+        assert!(tokio::time::timeout(idle, a.recv()).await.is_err());
+        assert!(!a.is_closed());
+        assert!(a.last_heartbeat() > started);
+
+        a.send(&Message::Error { code: 1, message: "done".into() }).await.unwrap();
+        assert!(matches!(peer.await.unwrap().unwrap(), Message::Error { code: 1, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_dead_peer_closed_and_evicted() {
+        let (initiator, _, _, responder_id) = handshake();
+        // The far end stays open but never reads or answers: a half-open peer
+        let (a, _silent) = tokio::io::duplex(4096);
+
+        let store = Arc::new(PeerStore::new(Duration::from_secs(300)));
+        store.insert(PeerInfo::new(responder_id, [0u8; 32])).await;
+        let mut a = session(&initiator, a, responder_id).with_peer_store(store.clone());
+        let started = a.last_heartbeat();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), a.recv()).await.unwrap();
+        assert!(matches!(result, Err(GridError::HeartbeatTimeout(MAX_MISSED_HEARTBEATS))));
+        assert!(a.is_closed());
+        assert_eq!(a.last_heartbeat(), started);
+        assert!(store.get(&responder_id).await.is_none());
+        assert!(matches!(a.send(&Message::Ping { seq: 0 }).await, Err(GridError::ChannelClosed)));
+    }
+}
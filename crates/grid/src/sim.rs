@@ -0,0 +1,481 @@
+//! Simulated network for multi-node tests
+//!
+//! `SimNet` connects named in-process nodes over links with latency and
+//! packet loss, and can cut links to model partitions. Each node gets a
+//! `SimTransport`, a `GridTransport` like `InMemoryTransport`, so protocol
+//! code runs over it unchanged.
+//!
+//! Connections stay reliable and ordered like TCP: every write is one
+//! packet, a lost packet costs a retransmission timeout rather than data,
+//! and after `MAX_RETRANSMITS` losses in a row the connection is reset. A
+//! cut link refuses new connections and resets open ones at their next
+//! packet. Delays use `tokio::time`, so under a paused clock
+//! (`#[tokio::test(start_paused = true)]`) they pass in virtual time and a
+//! run is reproducible from its seed.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::error::{GridError, Result};
+use crate::transport::{Conn, GridTransport, Incoming};
+
+/// Losses in a row after which a connection is reset
+pub const MAX_RETRANSMITS: u32 = 8;
+
+/// Buffer between a link and the reading end of a connection
+const SIM_BUFFER: usize = 64 * 1024;
+
+/// One-way behavior of a link, the same in both directions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConfig {
+    pub latency: Duration,
+    /// Chance that a packet is lost and has to be retransmitted, 0.0 to 1.0
+    pub loss: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::from_millis(1),
+            loss: 0.0,
+        }
+    }
+}
+
+impl LinkConfig {
+    pub fn new(latency: Duration) -> Self {
+        Self { latency, loss: 0.0 }
+    }
+
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How long a sender waits before resending a lost packet
+    fn retransmit_timeout(&self) -> Duration {
+        (self.latency * 3).max(Duration::from_millis(1))
+    }
+}
+
+type LinkKey = (String, String);
+
+fn link_key(a: &str, b: &str) -> LinkKey {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+type Pending = (Conn, String);
+
+struct SimState {
+    links: HashMap<LinkKey, LinkConfig>,
+    cut: HashSet<LinkKey>,
+    /// Listening address -> owning node and its accept queue
+    listeners: HashMap<String, (String, mpsc::Sender<Pending>)>,
+    rng: StdRng,
+}
+
+/// A simulated network of named nodes. Clones share the same network.
+#[derive(Clone)]
+pub struct SimNet {
+    state: Arc<Mutex<SimState>>,
+}
+
+impl SimNet {
+    /// An empty network; loss decisions are drawn from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SimState {
+                links: HashMap::new(),
+                cut: HashSet::new(),
+                listeners: HashMap::new(),
+                rng: StdRng::seed_from_u64(seed),
+            })),
+        }
+    }
+
+    /// Every node linked to every other
+    pub fn fully_connected(seed: u64, nodes: &[&str], link: LinkConfig) -> Self {
+        let net = Self::new(seed);
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                net.add_link(a, b, link);
+            }
+        }
+        net
+    }
+
+    /// Each node linked to the next, and the last back to the first
+    pub fn ring(seed: u64, nodes: &[&str], link: LinkConfig) -> Self {
+        let net = Self::new(seed);
+        if nodes.len() > 1 {
+            for (i, a) in nodes.iter().enumerate() {
+                net.add_link(a, nodes[(i + 1) % nodes.len()], link);
+            }
+        }
+        net
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Link `a` and `b`, replacing any existing link between them
+    pub fn add_link(&self, a: &str, b: &str, link: LinkConfig) {
+        self.lock().links.insert(link_key(a, b), link);
+    }
+
+    pub fn remove_link(&self, a: &str, b: &str) {
+        self.lock().links.remove(&link_key(a, b));
+    }
+
+    /// Cut every link between a node in `side_a` and one in `side_b`
+    pub fn partition(&self, side_a: &[&str], side_b: &[&str]) {
+        let mut state = self.lock();
+        for a in side_a {
+            for b in side_b {
+                state.cut.insert(link_key(a, b));
+            }
+        }
+    }
+
+    /// Restore every cut link
+    pub fn heal(&self) {
+        self.lock().cut.clear();
+    }
+
+    /// Whether `a` can currently reach `b` directly
+    pub fn reachable(&self, a: &str, b: &str) -> bool {
+        let key = link_key(a, b);
+        let state = self.lock();
+        a == b || (state.links.contains_key(&key) && !state.cut.contains(&key))
+    }
+
+    /// The transport `node` uses to listen and dial
+    pub fn transport(&self, node: &str) -> SimTransport {
+        SimTransport {
+            net: self.clone(),
+            node: node.to_string(),
+        }
+    }
+
+    /// How long one packet takes from `from` to `to`, counting
+    /// retransmissions, or `None` if it can't get through
+    fn transit(&self, from: &str, to: &str) -> Option<Duration> {
+        if from == to {
+            return Some(Duration::ZERO);
+        }
+        let key = link_key(from, to);
+        let mut state = self.lock();
+        if state.cut.contains(&key) {
+            return None;
+        }
+        let link = *state.links.get(&key)?;
+
+        let mut delay = link.latency;
+        let mut losses = 0;
+        while state.rng.gen_bool(link.loss) {
+            losses += 1;
+            if losses > MAX_RETRANSMITS {
+                return None;
+            }
+            delay += link.retransmit_timeout();
+        }
+        Some(delay)
+    }
+}
+
+/// A node's view of a `SimNet`
+#[derive(Clone)]
+pub struct SimTransport {
+    net: SimNet,
+    node: String,
+}
+
+impl SimTransport {
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+}
+
+#[async_trait]
+impl GridTransport for SimTransport {
+    async fn connect(&self, addr: &str) -> Result<Conn> {
+        let (owner, listener) = self
+            .net
+            .lock()
+            .listeners
+            .get(addr)
+            .cloned()
+            .ok_or_else(|| GridError::ConnectionFailed(format!("nothing listening on {}", addr)))?;
+
+        // The connection is up once a SYN and its reply get through
+        let unreachable = || GridError::ConnectionFailed(format!("{} unreachable from {}", owner, self.node));
+        let syn = self.net.transit(&self.node, &owner).ok_or_else(unreachable)?;
+        let reply = self.net.transit(&owner, &self.node).ok_or_else(unreachable)?;
+        tokio::time::sleep(syn + reply).await;
+
+        let (client, server) = SimConn::pair(&self.net, &self.node, &owner);
+        listener
+            .send((Box::new(server), self.node.clone()))
+            .await
+            .map_err(|_| GridError::ConnectionFailed(format!("listener on {} closed", addr)))?;
+        Ok(Box::new(client))
+    }
+
+    async fn listen(&self, addr: &str) -> Result<Box<dyn Incoming>> {
+        let mut state = self.net.lock();
+        if state.listeners.get(addr).is_some_and(|(_, tx)| !tx.is_closed()) {
+            return Err(GridError::ConnectionFailed(format!("{} already in use", addr)));
+        }
+        let (tx, rx) = mpsc::channel(64);
+        state.listeners.insert(addr.to_string(), (self.node.clone(), tx));
+        Ok(Box::new(SimIncoming {
+            addr: addr.to_string(),
+            pending: rx,
+        }))
+    }
+}
+
+struct SimIncoming {
+    addr: String,
+    pending: mpsc::Receiver<Pending>,
+}
+
+#[async_trait]
+impl Incoming for SimIncoming {
+    /// The address reported is the dialing node's name
+    async fn accept(&mut self) -> Result<(Conn, String)> {
+        self.pending.recv().await.ok_or(GridError::ChannelClosed)
+    }
+
+    fn local_addr(&self) -> Result<String> {
+        Ok(self.addr.clone())
+    }
+}
+
+/// A write in flight; `None` data resets the connection when it arrives
+struct Packet {
+    deliver_at: Instant,
+    data: Option<Vec<u8>>,
+}
+
+/// One end of a simulated connection. Reads come from a buffer the link
+/// fills; each write is scheduled as a packet on the link.
+struct SimConn {
+    inbound: DuplexStream,
+    outbound: Option<mpsc::UnboundedSender<Packet>>,
+    net: SimNet,
+    from: String,
+    to: String,
+    /// Packets arrive in order, so none may land before this
+    last_delivery: Instant,
+}
+
+impl SimConn {
+    fn pair(net: &SimNet, a: &str, b: &str) -> (Self, Self) {
+        let (a_in, a_sink) = tokio::io::duplex(SIM_BUFFER);
+        let (b_in, b_sink) = tokio::io::duplex(SIM_BUFFER);
+        let a_out = spawn_link(b_sink);
+        let b_out = spawn_link(a_sink);
+        let end = |inbound, outbound, from: &str, to: &str| SimConn {
+            inbound,
+            outbound: Some(outbound),
+            net: net.clone(),
+            from: from.to_string(),
+            to: to.to_string(),
+            last_delivery: Instant::now(),
+        };
+        (end(a_in, a_out, a, b), end(b_in, b_out, b, a))
+    }
+}
+
+/// Deliver packets into `sink` at their scheduled times. Dropping `sink`
+/// ends the stream at the reader, so a reset shows up as EOF.
+fn spawn_link(mut sink: DuplexStream) -> mpsc::UnboundedSender<Packet> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Packet>();
+    tokio::spawn(async move {
+        while let Some(packet) = rx.recv().await {
+            tokio::time::sleep_until(packet.deliver_at).await;
+            let Some(data) = packet.data else {
+                break;
+            };
+            if sink.write_all(&data).await.is_err() {
+                break;
+            }
+        }
+    });
+    tx
+}
+
+impl AsyncRead for SimConn {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inbound).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SimConn {
+    fn poll_write(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let Some(outbound) = &this.outbound else {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        };
+
+        let transit = this.net.transit(&this.from, &this.to);
+        let deliver_at = (Instant::now() + transit.unwrap_or_default()).max(this.last_delivery);
+        this.last_delivery = deliver_at;
+        let packet = Packet {
+            deliver_at,
+            data: transit.map(|_| buf.to_vec()),
+        };
+        if outbound.send(packet).is_err() || transit.is_none() {
+            this.outbound = None;
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // In-flight packets still arrive before the link sees the close
+        self.outbound = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::Handshaker;
+    use crate::peer::{Capabilities, NodeId};
+    use crate::wire::{read_frame, write_frame, Message, TaskStatus};
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    async fn send(conn: &mut Conn, message: &Message) {
+        write_frame(conn, &message.encode().unwrap()).await.unwrap();
+    }
+
+    async fn recv(conn: &mut Conn) -> Message {
+        Message::decode(&read_frame(conn).await.unwrap()).unwrap()
+    }
+
+    /// Handshake over `conn`, then send a task and wait for its ACK
+    async fn delegate(mut conn: Conn, worker: NodeId) -> TaskStatus {
+        let key = SigningKey::generate(&mut OsRng);
+        let id = NodeId::from_pubkey(&key.verifying_key().to_bytes());
+        let mut handshaker = Handshaker::new_initiator(id, key, Capabilities::default());
+
+        send(&mut conn, &handshaker.start().unwrap()).await;
+        let prove = handshaker.process(recv(&mut conn).await).unwrap().unwrap();
+        send(&mut conn, &prove).await;
+        handshaker.process(recv(&mut conn).await).unwrap();
+
+        let mut session = handshaker.into_session(conn, worker).unwrap();
+        session
+            .send(&Message::TaskRequest { task_id: [7u8; 32], payload: b"work".to_vec() })
+            .await
+            .unwrap();
+        match session.recv().await.unwrap() {
+            Message::TaskAck { status, .. } => status,
+            other => panic!("expected TaskAck, got {:?}", other.message_type()),
+        }
+    }
+
+    /// Accept one delegator on `incoming` and acknowledge its task
+    fn spawn_worker(mut incoming: Box<dyn Incoming>) -> NodeId {
+        let key = SigningKey::generate(&mut OsRng);
+        let id = NodeId::from_pubkey(&key.verifying_key().to_bytes());
+        tokio::spawn(async move {
+            let (mut conn, _) = incoming.accept().await.unwrap();
+            let mut handshaker = Handshaker::new_responder(id, key, Capabilities::default());
+            let challenge = handshaker.process(recv(&mut conn).await).unwrap().unwrap();
+            send(&mut conn, &challenge).await;
+            let welcome = handshaker.process(recv(&mut conn).await).unwrap().unwrap();
+            send(&mut conn, &welcome).await;
+
+            let remote = handshaker.remote_node_id().unwrap();
+            let mut session = handshaker.into_session(conn, remote).unwrap();
+            if let Message::TaskRequest { task_id, .. } = session.recv().await.unwrap() {
+                session
+                    .send(&Message::TaskAck { task_id, status: TaskStatus::Accepted })
+                    .await
+                    .unwrap();
+            }
+            // Keep the session open until the delegator has read the ACK
+            let _ = session.recv().await;
+        });
+        id
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delegation_within_deadline_under_loss() {
+        let link = LinkConfig::new(Duration::from_millis(20)).with_loss(0.1);
+        let net = SimNet::fully_connected(42, &["a", "b", "c"], link);
+        let worker = spawn_worker(net.transport("b").listen("b:task").await.unwrap());
+
+        let started = Instant::now();
+        let conn = net.transport("a").connect("b:task").await.unwrap();
+        assert_eq!(delegate(conn, worker).await, TaskStatus::Accepted);
+
+        // Connecting, the handshake and one request are 8 one-way trips of
+        // 20ms; each loss adds a 60ms retransmission
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(160), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(1_000), "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ring_topology_limits_direct_links() {
+        let net = SimNet::ring(1, &["a", "b", "c", "d"], LinkConfig::default());
+        assert!(net.reachable("a", "b") && net.reachable("d", "a"));
+        assert!(!net.reachable("a", "c"));
+
+        let _listener = net.transport("c").listen("c:task").await.unwrap();
+        assert!(net.transport("b").connect("c:task").await.is_ok());
+        assert!(matches!(
+            net.transport("a").connect("c:task").await,
+            Err(GridError::ConnectionFailed(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_partition_resets_and_heals() {
+        let net = SimNet::fully_connected(7, &["a", "b"], LinkConfig::default());
+        let mut incoming = net.transport("b").listen("b:echo").await.unwrap();
+        let mut client = net.transport("a").connect("b:echo").await.unwrap();
+        let (mut server, from) = incoming.accept().await.unwrap();
+        assert_eq!(from, "a");
+
+        write_frame(&mut client, b"before").await.unwrap();
+        assert_eq!(read_frame(&mut server).await.unwrap(), b"before");
+
+        net.partition(&["a"], &["b"]);
+        assert!(write_frame(&mut client, b"during").await.is_err());
+        assert!(read_frame(&mut server).await.is_err());
+        assert!(net.transport("a").connect("b:echo").await.is_err());
+
+        net.heal();
+        let mut client = net.transport("a").connect("b:echo").await.unwrap();
+        let (mut server, _) = incoming.accept().await.unwrap();
+        write_frame(&mut client, b"after").await.unwrap();
+        assert_eq!(read_frame(&mut server).await.unwrap(), b"after");
+    }
+}
@@ -0,0 +1,183 @@
+//! Pluggable stream transports
+//!
+//! Protocol code (task requests, tensor streams, encrypted sessions) only
+//! needs a byte stream, so it is written against `GridTransport` rather than
+//! `TcpStream`. `TcpTransport` is the production transport; `InMemoryTransport`
+//! connects endpoints inside one process, which lets a whole pipeline run in
+//! a test without sockets. Addresses are strings: `host:port` for TCP, any
+//! name for in-memory endpoints.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+
+use crate::error::{GridError, Result};
+
+/// A bidirectional byte stream to a peer
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+pub type Conn = Box<dyn Connection>;
+
+#[async_trait]
+pub trait GridTransport: Send + Sync {
+    async fn connect(&self, addr: &str) -> Result<Conn>;
+
+    async fn listen(&self, addr: &str) -> Result<Box<dyn Incoming>>;
+}
+
+/// Connections arriving at a listening address
+#[async_trait]
+pub trait Incoming: Send {
+    /// Next connection and the address it came from
+    async fn accept(&mut self) -> Result<(Conn, String)>;
+
+    fn local_addr(&self) -> Result<String>;
+}
+
+/// Plain TCP, the behavior Grid always had
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpTransport;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl GridTransport for TcpTransport {
+    async fn connect(&self, addr: &str) -> Result<Conn> {
+        let stream = tokio::net::TcpStream::connect(addr)
+            .await
+            .map_err(|e| GridError::ConnectionFailed(format!("{}: {}", addr, e)))?;
+        Ok(Box::new(stream))
+    }
+
+    async fn listen(&self, addr: &str) -> Result<Box<dyn Incoming>> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        Ok(Box::new(listener))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Incoming for tokio::net::TcpListener {
+    async fn accept(&mut self) -> Result<(Conn, String)> {
+        let (stream, peer) = tokio::net::TcpListener::accept(self).await?;
+        Ok((Box::new(stream), peer.to_string()))
+    }
+
+    fn local_addr(&self) -> Result<String> {
+        Ok(tokio::net::TcpListener::local_addr(self)?.to_string())
+    }
+}
+
+/// Buffer size of each direction of an in-memory connection
+const IN_MEMORY_BUFFER: usize = 64 * 1024;
+
+type Pending = (Conn, String);
+
+/// In-process network. Clones share the same address space, so give every
+/// endpoint of a test a clone of one transport.
+#[derive(Clone, Default)]
+pub struct InMemoryTransport {
+    listeners: Arc<Mutex<HashMap<String, mpsc::Sender<Pending>>>>,
+    next_client: Arc<AtomicU64>,
+}
+
+impl InMemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GridTransport for InMemoryTransport {
+    async fn connect(&self, addr: &str) -> Result<Conn> {
+        let listener = self
+            .listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(addr)
+            .cloned()
+            .ok_or_else(|| GridError::ConnectionFailed(format!("nothing listening on {}", addr)))?;
+
+        let (client, server) = tokio::io::duplex(IN_MEMORY_BUFFER);
+        let peer = format!("memory:{}", self.next_client.fetch_add(1, Ordering::Relaxed));
+        listener
+            .send((Box::new(server), peer))
+            .await
+            .map_err(|_| GridError::ConnectionFailed(format!("listener on {} closed", addr)))?;
+        Ok(Box::new(client))
+    }
+
+    async fn listen(&self, addr: &str) -> Result<Box<dyn Incoming>> {
+        let mut listeners = self.listeners.lock().unwrap_or_else(|e| e.into_inner());
+        if listeners.get(addr).is_some_and(|tx| !tx.is_closed()) {
+            return Err(GridError::ConnectionFailed(format!("{} already in use", addr)));
+        }
+        let (tx, rx) = mpsc::channel(64);
+        listeners.insert(addr.to_string(), tx);
+        Ok(Box::new(InMemoryIncoming {
+            addr: addr.to_string(),
+            pending: rx,
+        }))
+    }
+}
+
+struct InMemoryIncoming {
+    addr: String,
+    pending: mpsc::Receiver<Pending>,
+}
+
+#[async_trait]
+impl Incoming for InMemoryIncoming {
+    async fn accept(&mut self) -> Result<(Conn, String)> {
+        self.pending.recv().await.ok_or(GridError::ChannelClosed)
+    }
+
+    fn local_addr(&self) -> Result<String> {
+        Ok(self.addr.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::{read_frame, write_frame};
+
+    async fn echo_once(transport: &dyn GridTransport, addr: &str) {
+        let mut incoming = transport.listen(addr).await.unwrap();
+        let addr = incoming.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut conn, _) = incoming.accept().await.unwrap();
+            let frame = read_frame(&mut conn).await.unwrap();
+            write_frame(&mut conn, &frame).await.unwrap();
+        });
+
+        let mut conn = transport.connect(&addr).await.unwrap();
+        write_frame(&mut conn, b"hello grid").await.unwrap();
+        assert_eq!(read_frame(&mut conn).await.unwrap(), b"hello grid");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_transport() {
+        echo_once(&TcpTransport, "127.0.0.1:0").await;
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_transport() {
+        let transport = InMemoryTransport::new();
+        echo_once(&transport, "node-a").await;
+
+        // The listener was dropped with the server task, so the name is free
+        assert!(transport.connect("node-a").await.is_err());
+        let _incoming = transport.listen("node-a").await.unwrap();
+        assert!(transport.listen("node-a").await.is_err());
+        assert!(transport.connect("node-b").await.is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+use crate::address::PeerAddress;
+use crate::error::GridError;
 use crate::peer::NodeId;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +30,8 @@ pub enum Message {
         node_id: NodeId,
         pubkey: [u8; 32],
         capabilities: Vec<u8>,
+        /// `DeviceCapabilities::encode`, empty when not advertised
+        device: Vec<u8>,
         x25519_pubkey: [u8; 32],
         timestamp: u64,
         signature: Vec<u8>,
@@ -41,6 +46,11 @@ pub enum Message {
     },
     Welcome {
         session_params: SessionParams,
+        /// The initiator's source address as the responder saw it
+        observed_addr: Option<PeerAddress>,
+        /// The responder's `DeviceCapabilities::encode`, empty when not
+        /// advertised
+        device: Vec<u8>,
     },
 
     // Liveness
@@ -107,6 +117,23 @@ pub enum Message {
         code: u32,
         message: String,
     },
+
+    // bincode tags variants by position, so new ones are appended here
+
+    // Rendezvous: a relay coordinates hole punching between two NATed
+    // peers, and forwards their traffic if the punch fails
+    PunchRequest {
+        target: NodeId,
+    },
+    PunchNotify {
+        peer: NodeId,
+        addr: PeerAddress,
+    },
+    /// To the rendezvous `peer` is the recipient; on delivery it is the sender
+    RelayedData {
+        peer: NodeId,
+        payload: Vec<u8>,
+    },
 }
 
 impl Message {
@@ -138,10 +165,65 @@ impl Message {
             Message::RelayForward { .. } => 0x61,
             Message::RelayDeliver { .. } => 0x62,
             Message::RelayFetch { .. } => 0x63,
+            Message::PunchRequest { .. } => 0x64,
+            Message::PunchNotify { .. } => 0x65,
+            Message::RelayedData { .. } => 0x66,
             Message::Error { .. } => 0xFF,
         }
     }
 }
 
-pub const PROTOCOL_VERSION: u32 = 1;
+/// Bumped on any change to the encoding of `Message`; 2 added rendezvous,
+/// observed addresses and device info in the handshake
+pub const PROTOCOL_VERSION: u32 = 2;
 pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024; // 16 MB
+
+/// Write one frame: a big-endian `u32` length followed by `payload`
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, payload: &[u8]) -> Result<(), GridError> {
+    if payload.len() > MAX_MESSAGE_SIZE {
+        return Err(GridError::ProtocolError(format!(
+            "frame of {} bytes exceeds {} byte limit",
+            payload.len(),
+            MAX_MESSAGE_SIZE
+        )));
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read one frame written by `write_frame`, rejecting oversized lengths
+/// before allocating
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>, GridError> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf).await?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(GridError::ProtocolError(format!(
+            "frame of {} bytes exceeds {} byte limit",
+            len, MAX_MESSAGE_SIZE
+        )));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frame_roundtrip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let message = Message::Ping { seq: 42 };
+        write_frame(&mut client, &message.encode().unwrap()).await.unwrap();
+
+        let frame = read_frame(&mut server).await.unwrap();
+        assert!(matches!(Message::decode(&frame).unwrap(), Message::Ping { seq: 42 }));
+
+        client.write_all(&u32::MAX.to_be_bytes()).await.unwrap();
+        assert!(matches!(read_frame(&mut server).await, Err(GridError::ProtocolError(_))));
+    }
+}
@@ -0,0 +1,281 @@
+//! Local GGUF inference
+//!
+//! Runs quantized llama-family models from a single `.gguf` file with
+//! candle's pure-Rust backend, so it works on devices without a network,
+//! CoreML, or a C++ toolchain. The tokenizer is read from a `tokenizer.json`
+//! next to the model file.
+
+use async_trait::async_trait;
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::{LogitsProcessor, Sampling};
+use candle_transformers::models::quantized_llama::ModelWeights;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokenizers::Tokenizer;
+
+use crate::error::{InferenceError, Result};
+use crate::model::{ChatMessage, ChatRole, GenerationParams, Model, ModelCapabilities, ModelConfig};
+
+/// Tokens considered when applying `repeat_penalty`
+const REPEAT_LAST_N: usize = 64;
+
+struct Loaded {
+    weights: Mutex<ModelWeights>,
+    tokenizer: Tokenizer,
+    eos_token: Option<u32>,
+    device: Device,
+}
+
+/// A GGUF model implementing [`Model`]
+pub struct GgufModel {
+    name: String,
+    config: ModelConfig,
+    capabilities: ModelCapabilities,
+    loaded: Option<Loaded>,
+}
+
+impl std::fmt::Debug for GgufModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GgufModel")
+            .field("name", &self.name)
+            .field("model_path", &self.config.model_path)
+            .field("loaded", &self.loaded.is_some())
+            .finish()
+    }
+}
+
+impl GgufModel {
+    pub fn new(name: &str, config: ModelConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            config,
+            capabilities: ModelCapabilities {
+                completion: true,
+                chat: true,
+                embeddings: false,
+                code: false,
+                languages: Vec::new(),
+            },
+            loaded: None,
+        }
+    }
+
+    pub fn config(&self) -> &ModelConfig {
+        &self.config
+    }
+
+    /// `tokenizer.json` beside the model file
+    pub fn tokenizer_path(&self) -> PathBuf {
+        self.config
+            .model_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("tokenizer.json")
+    }
+
+    fn loaded(&self) -> Result<&Loaded> {
+        self.loaded
+            .as_ref()
+            .ok_or_else(|| InferenceError::ModelNotLoaded(format!("{} is not loaded. Call load() first.", self.name)))
+    }
+
+    /// Blocking generation; `complete` wraps this
+    pub fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<String> {
+        let loaded = self.loaded()?;
+        let encoding = loaded
+            .tokenizer
+            .encode(prompt, true)
+            .map_err(|e| InferenceError::TokenizationError(e.to_string()))?;
+        let prompt_tokens = encoding.get_ids().to_vec();
+        if prompt_tokens.is_empty() {
+            return Err(InferenceError::InvalidInput("Empty prompt".to_string()));
+        }
+        if prompt_tokens.len() >= self.config.context_size {
+            return Err(InferenceError::ContextLengthExceeded(prompt_tokens.len(), self.config.context_size));
+        }
+
+        let sampling = if params.temperature <= 0.0 {
+            Sampling::ArgMax
+        } else {
+            Sampling::TopKThenTopP {
+                k: params.top_k.max(1) as usize,
+                p: params.top_p as f64,
+                temperature: params.temperature as f64,
+            }
+        };
+        let mut sampler = LogitsProcessor::from_sampling(self.config.seed.unwrap_or(299792458), sampling);
+        let max_new = params.max_tokens.min(self.config.context_size - prompt_tokens.len());
+
+        let mut weights = loaded
+            .weights
+            .lock()
+            .map_err(|_| InferenceError::InferenceFailed("Model lock poisoned".to_string()))?;
+        let mut all_tokens = prompt_tokens.clone();
+        let mut generated: Vec<u32> = Vec::new();
+        let mut input = prompt_tokens;
+        let mut index_pos = 0;
+
+        for _ in 0..max_new {
+            let x = Tensor::new(input.as_slice(), &loaded.device)
+                .and_then(|t| t.unsqueeze(0))
+                .map_err(infer_err)?;
+            let logits = weights
+                .forward(&x, index_pos)
+                .and_then(|l| l.squeeze(0))
+                .map_err(infer_err)?;
+            index_pos += input.len();
+
+            let logits = if params.repeat_penalty == 1.0 {
+                logits
+            } else {
+                let start = all_tokens.len().saturating_sub(REPEAT_LAST_N);
+                candle_transformers::utils::apply_repeat_penalty(&logits, params.repeat_penalty, &all_tokens[start..])
+                    .map_err(infer_err)?
+            };
+
+            let next = sampler.sample(&logits).map_err(infer_err)?;
+            if Some(next) == loaded.eos_token {
+                break;
+            }
+            all_tokens.push(next);
+            generated.push(next);
+            input = vec![next];
+
+            if !params.stop.is_empty() {
+                let text = self.decode(&generated)?;
+                if let Some(cut) = params.stop.iter().filter_map(|s| text.find(s.as_str())).min() {
+                    return Ok(text[..cut].to_string());
+                }
+            }
+        }
+
+        self.decode(&generated)
+    }
+
+    fn decode(&self, tokens: &[u32]) -> Result<String> {
+        self.loaded()?
+            .tokenizer
+            .decode(tokens, true)
+            .map_err(|e| InferenceError::TokenizationError(e.to_string()))
+    }
+}
+
+fn infer_err(e: candle_core::Error) -> InferenceError {
+    InferenceError::InferenceFailed(e.to_string())
+}
+
+#[async_trait]
+impl Model for GgufModel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> &ModelCapabilities {
+        &self.capabilities
+    }
+
+    async fn load(&mut self) -> Result<()> {
+        if self.is_loaded() {
+            return Ok(());
+        }
+
+        let path = &self.config.model_path;
+        if !path.exists() {
+            return Err(InferenceError::ModelFileNotFound(path.display().to_string()));
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("gguf") {
+            return Err(InferenceError::UnsupportedFormat(path.display().to_string()));
+        }
+
+        let tokenizer_path = self.tokenizer_path();
+        let tokenizer = Tokenizer::from_file(&tokenizer_path).map_err(|e| {
+            InferenceError::ModelLoadFailed(format!("Tokenizer {}: {}", tokenizer_path.display(), e))
+        })?;
+
+        let mut file = File::open(path).map_err(|e| InferenceError::ModelLoadFailed(e.to_string()))?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| InferenceError::ModelLoadFailed(format!("Invalid GGUF: {}", e)))?;
+        let eos_token = content
+            .metadata
+            .get("tokenizer.ggml.eos_token_id")
+            .and_then(|v| v.to_u32().ok());
+
+        let device = Device::Cpu;
+        let weights = ModelWeights::from_gguf(content, &mut file, &device)
+            .map_err(|e| InferenceError::ModelLoadFailed(e.to_string()))?;
+
+        self.loaded = Some(Loaded {
+            weights: Mutex::new(weights),
+            tokenizer,
+            eos_token,
+            device,
+        });
+        tracing::info!("Loaded GGUF model: {}", self.name);
+        Ok(())
+    }
+
+    async fn unload(&mut self) -> Result<()> {
+        self.loaded = None;
+        tracing::info!("Unloaded model: {}", self.name);
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.loaded.is_some()
+    }
+
+    async fn complete(&self, prompt: &str, params: &GenerationParams) -> Result<String> {
+        self.generate(prompt, params)
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], params: &GenerationParams) -> Result<String> {
+        let mut prompt = String::new();
+        for msg in messages {
+            let role = match msg.role {
+                ChatRole::System => "System",
+                ChatRole::User => "User",
+                ChatRole::Assistant => "Assistant",
+            };
+            prompt.push_str(&format!("### {}:\n{}\n\n", role, msg.content));
+        }
+        prompt.push_str("### Assistant:\n");
+        self.generate(&prompt, params)
+    }
+
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Err(InferenceError::InvalidInput(format!("{} does not support embeddings", self.name)))
+    }
+
+    fn tokenize(&self, text: &str) -> Result<Vec<u32>> {
+        let encoding = self
+            .loaded()?
+            .tokenizer
+            .encode(text, true)
+            .map_err(|e| InferenceError::TokenizationError(e.to_string()))?;
+        Ok(encoding.get_ids().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_errors() {
+        let mut model = GgufModel::new("missing", ModelConfig::new("/nonexistent/model.gguf"));
+        assert!(matches!(model.load().await, Err(InferenceError::ModelFileNotFound(_))));
+        assert!(!model.is_loaded());
+        assert!(matches!(
+            model.complete("hi", &GenerationParams::default()).await,
+            Err(InferenceError::ModelNotLoaded(_))
+        ));
+
+        let not_gguf = std::env::temp_dir().join(format!("cortex-model-{}.bin", std::process::id()));
+        std::fs::write(&not_gguf, b"not a model").unwrap();
+        let mut model = GgufModel::new("bin", ModelConfig::new(&not_gguf));
+        assert!(matches!(model.load().await, Err(InferenceError::UnsupportedFormat(_))));
+        let _ = std::fs::remove_file(&not_gguf);
+    }
+}
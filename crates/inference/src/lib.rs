@@ -43,19 +43,42 @@
 //! let result = executor.infer("Hello world").await?;
 //! ```
 
+pub mod error;
+pub mod model;
+pub mod gguf;
 pub mod tensor_transport;
 pub mod sharded_model;
 pub mod distributed_executor;
+pub mod skill;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
+pub use error::InferenceError;
+
+pub use model::{
+    Model,
+    ModelConfig,
+    ModelCapabilities,
+    GenerationParams,
+    ChatMessage,
+    ChatRole,
+};
+
+pub use gguf::GgufModel;
 
 pub use tensor_transport::{
     SerializedTensor, 
     InferenceMessage, 
     InferenceMetadata, 
+    QuantKind,
+    Quantization,
     TensorTransport,
     TensorTransportError,
 };
 
 pub use sharded_model::{
+    model_layer_count,
     ShardedLlama,
     ShardConfig,
     PipelineRole,
@@ -64,12 +87,22 @@ pub use sharded_model::{
 };
 
 pub use distributed_executor::{
+    build_local_pipeline,
     DistributedExecutor,
     DistributedConfig,
     PipelineNode,
     InferenceResult,
+    ExecutorState,
     ExecutorStatus,
     ExecutorError,
+    DEFAULT_MAX_NEW_TOKENS,
+};
+
+pub use skill::{
+    ChatSkill,
+    CodeSkill,
+    CompletionSkill,
+    EmbeddingSkill,
 };
 
 /// Calculate optimal layer distribution for N nodes
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use candle_transformers::generation::{LogitsProcessor, Sampling};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -91,6 +92,7 @@ impl Default for ModelCapabilities {
 
 /// Generation parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GenerationParams {
     /// Maximum tokens to generate
     pub max_tokens: usize,
@@ -104,6 +106,8 @@ pub struct GenerationParams {
     pub repeat_penalty: f32,
     /// Stop sequences
     pub stop: Vec<String>,
+    /// Sampling RNG seed; the same seed and parameters reproduce a run
+    pub seed: u64,
 }
 
 impl Default for GenerationParams {
@@ -115,10 +119,42 @@ impl Default for GenerationParams {
             top_k: 40,
             repeat_penalty: 1.1,
             stop: Vec::new(),
+            seed: 299792458,
         }
     }
 }
 
+impl GenerationParams {
+    /// Greedy decoding: always pick the most likely token
+    pub fn greedy() -> Self {
+        Self {
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    /// Sampling strategy for these parameters. A temperature of 0 is greedy;
+    /// `top_k` of 0 and `top_p` outside (0, 1) disable those filters.
+    pub fn sampling(&self) -> Sampling {
+        let temperature = self.temperature as f64;
+        if temperature <= 0.0 {
+            return Sampling::ArgMax;
+        }
+        let k = self.top_k as usize;
+        let p = self.top_p as f64;
+        match (k > 0, p > 0.0 && p < 1.0) {
+            (true, true) => Sampling::TopKThenTopP { k, p, temperature },
+            (true, false) => Sampling::TopK { k, temperature },
+            (false, true) => Sampling::TopP { p, temperature },
+            (false, false) => Sampling::All { temperature },
+        }
+    }
+
+    pub fn logits_processor(&self) -> LogitsProcessor {
+        LogitsProcessor::from_sampling(self.seed, self.sampling())
+    }
+}
+
 /// Chat message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -556,3 +592,20 @@ pub mod llama {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_from_params() {
+        assert_eq!(GenerationParams::greedy().sampling(), Sampling::ArgMax);
+        let params = GenerationParams { temperature: 0.5, top_k: 10, top_p: 1.0, ..Default::default() };
+        assert_eq!(params.sampling(), Sampling::TopK { k: 10, temperature: 0.5 });
+        let params = GenerationParams { top_k: 0, top_p: 0.9, ..params };
+        assert!(matches!(params.sampling(), Sampling::TopP { .. }));
+        let params: GenerationParams = serde_json::from_str(r#"{"temperature": 0}"#).unwrap();
+        assert_eq!(params.max_tokens, 256);
+        assert_eq!(params.sampling(), Sampling::ArgMax);
+    }
+}
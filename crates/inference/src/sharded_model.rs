@@ -3,9 +3,11 @@
 //! Loads only a portion of a model's layers for pipeline parallelism.
 //! This enables TRUE distributed inference where each node holds different layers.
 
+use candle_core::quantized::{gguf_file, QMatMul};
 use candle_core::{DType, Device, Tensor, IndexOp, Module};
-use candle_nn::{VarBuilder, linear_no_bias, RmsNorm};
+use candle_nn::{VarBuilder, RmsNorm};
 use candle_transformers::models::llama::{Config as LlamaConfig, LlamaEosToks};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::fs;
 use tracing::{info, warn};
@@ -120,7 +122,7 @@ pub struct ShardedLlama {
     
     // Only loaded if this is TAIL
     norm: Option<RmsNorm>,
-    lm_head: Option<candle_nn::Linear>,
+    lm_head: Option<QMatMul>,
     
     // Rotary embedding cache (shared across layers)
     rope: RotaryEmbedding,
@@ -136,6 +138,9 @@ struct RotaryEmbedding {
     base: f32,
     cos: Tensor,
     sin: Tensor,
+    /// Rotate adjacent pairs, matching llama.cpp's Q/K row order, rather
+    /// than the two halves of each head
+    interleaved: bool,
 }
 
 impl RotaryEmbedding {
@@ -157,14 +162,20 @@ impl RotaryEmbedding {
             base,
             cos,
             sin,
+            interleaved: false,
         })
     }
 
+    /// `x` is `[batch, heads, seq, head_dim]`
     fn forward(&self, x: &Tensor, pos: usize) -> Result<Tensor, candle_core::Error> {
-        let (_b, seq_len, _h, _d) = x.dims4()?;
+        let (_b, _h, seq_len, _d) = x.dims4()?;
         let cos = self.cos.narrow(0, pos, seq_len)?;
         let sin = self.sin.narrow(0, pos, seq_len)?;
-        candle_nn::rotary_emb::rope(x, &cos, &sin)
+        if self.interleaved {
+            candle_nn::rotary_emb::rope_i(&x.contiguous()?, &cos, &sin)
+        } else {
+            candle_nn::rotary_emb::rope(&x.contiguous()?, &cos, &sin)
+        }
     }
 }
 
@@ -175,15 +186,15 @@ pub struct TransformerBlock {
     layer_idx: u32,
     attention_norm: RmsNorm,
     ffn_norm: RmsNorm,
-    // Attention weights
-    wq: candle_nn::Linear,
-    wk: candle_nn::Linear,
-    wv: candle_nn::Linear,
-    wo: candle_nn::Linear,
+    // Attention weights, quantized when loaded from GGUF
+    wq: QMatMul,
+    wk: QMatMul,
+    wv: QMatMul,
+    wo: QMatMul,
     // FFN weights
-    w1: candle_nn::Linear, // Gate
-    w2: candle_nn::Linear, // Down
-    w3: candle_nn::Linear, // Up
+    w1: QMatMul, // Gate
+    w2: QMatMul, // Down
+    w3: QMatMul, // Up
     
     n_head: usize,
     n_kv_head: usize,
@@ -220,7 +231,123 @@ impl ShardedLlama {
         
         // Load weights
         let vb = unsafe { VarBuilder::from_mmaped_safetensors(&files, config.dtype, &config.device)? };
-        
+        Self::from_var_builder(config, llama_config, vb)
+    }
+
+    /// Load layers `start_layer..=end_layer` of a llama-family GGUF file.
+    ///
+    /// The file is memory-mapped and only the tensors this shard needs are
+    /// read: its blocks, plus the token embedding on the head and the output
+    /// norm and LM head on the tail. As in candle's quantized llama, weight
+    /// matrices stay quantized and Q/K keep llama.cpp's row order, paired
+    /// with interleaved RoPE; norms and the embedding table are dequantized.
+    pub fn load_shard(
+        gguf_path: impl AsRef<Path>,
+        start_layer: u32,
+        end_layer: u32,
+    ) -> Result<Self, ShardedModelError> {
+        let path = gguf_path.as_ref();
+        let file = fs::File::open(path)?;
+        // SAFETY: the mapping is only read while loading and dropped afterwards
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let mut reader = Cursor::new(&mmap[..]);
+        let content = gguf_file::Content::read(&mut reader)
+            .map_err(|e| ShardedModelError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        let llama_config = gguf_llama_config(&content)?;
+
+        let total_layers = llama_config.num_hidden_layers as u32;
+        if start_layer > end_layer || end_layer >= total_layers {
+            return Err(ShardedModelError::ConfigError(format!(
+                "layer range {}-{} outside the model's {} layers",
+                start_layer, end_layer, total_layers
+            )));
+        }
+        let role = match (start_layer == 0, end_layer == total_layers - 1) {
+            (true, true) => PipelineRole::Single { start_layer, end_layer },
+            (true, false) => PipelineRole::Head { start_layer, end_layer },
+            (false, true) => PipelineRole::Tail { start_layer, end_layer },
+            (false, false) => PipelineRole::Middle { start_layer, end_layer },
+        };
+        info!("🔧 Loading GGUF shard {}: role={:?}", path.display(), role);
+
+        let device = Device::Cpu;
+        let eps = llama_config.rms_norm_eps;
+        let read = |name: &str| -> Result<candle_core::quantized::QTensor, ShardedModelError> {
+            Ok(content.tensor(&mut Cursor::new(&mmap[..]), name, &device)?)
+        };
+        let matmul = |name: &str| -> Result<QMatMul, ShardedModelError> { Ok(QMatMul::from_qtensor(read(name)?)?) };
+        let norm = |name: &str| -> Result<RmsNorm, ShardedModelError> {
+            Ok(RmsNorm::new(read(name)?.dequantize(&device)?, eps))
+        };
+
+        let n_head = llama_config.num_attention_heads;
+        let mut layers = Vec::new();
+        for layer_idx in start_layer..=end_layer {
+            let blk = |name: &str| format!("blk.{}.{}.weight", layer_idx, name);
+            layers.push(TransformerBlock {
+                layer_idx,
+                attention_norm: norm(&blk("attn_norm"))?,
+                ffn_norm: norm(&blk("ffn_norm"))?,
+                wq: matmul(&blk("attn_q"))?,
+                wk: matmul(&blk("attn_k"))?,
+                wv: matmul(&blk("attn_v"))?,
+                wo: matmul(&blk("attn_output"))?,
+                w1: matmul(&blk("ffn_gate"))?,
+                w2: matmul(&blk("ffn_down"))?,
+                w3: matmul(&blk("ffn_up"))?,
+                n_head,
+                n_kv_head: llama_config.num_key_value_heads,
+                head_dim: llama_config.hidden_size / n_head,
+                span: tracing::span!(tracing::Level::TRACE, "layer", idx = layer_idx),
+            });
+        }
+
+        let embedding = if role.is_head() {
+            let table = read("token_embd.weight")?.dequantize(&device)?;
+            Some(candle_nn::Embedding::new(table, llama_config.hidden_size))
+        } else {
+            None
+        };
+        let (norm, lm_head) = if role.is_tail() {
+            // Models with tied embeddings have no separate output matrix
+            let lm_head = if content.tensor_infos.contains_key("output.weight") {
+                matmul("output.weight")?
+            } else {
+                matmul("token_embd.weight")?
+            };
+            (Some(norm("output_norm.weight")?), Some(lm_head))
+        } else {
+            (None, None)
+        };
+
+        let mut rope = RotaryEmbedding::new(
+            llama_config.hidden_size / n_head,
+            llama_config.max_position_embeddings,
+            llama_config.rope_theta,
+            &device,
+        )?;
+        rope.interleaved = true;
+
+        let shard = Self {
+            config: ShardConfig {
+                model_path: path.display().to_string(),
+                total_layers,
+                role,
+                device,
+                dtype: DType::F32,
+            },
+            llama_config,
+            embedding,
+            layers,
+            norm,
+            lm_head,
+            rope,
+        };
+        info!("✅ GGUF shard loaded: {} layers, {} parameters", shard.layers.len(), shard.parameter_count());
+        Ok(shard)
+    }
+
+    fn from_var_builder(config: ShardConfig, llama_config: LlamaConfig, vb: VarBuilder) -> Result<Self, ShardedModelError> {
         let (start, end) = config.role.layer_range();
         
         // Load RoPE
@@ -264,7 +391,7 @@ impl ShardedLlama {
                     llama_config.rms_norm_eps, 
                     vb.pp("model.norm")
                 )?),
-                Some(linear_no_bias(
+                Some(linear(
                     llama_config.hidden_size, 
                     llama_config.vocab_size, 
                     vb.pp("lm_head")
@@ -274,9 +401,7 @@ impl ShardedLlama {
             (None, None)
         };
         
-        info!("✅ Sharded model loaded: {} layers", layers.len());
-        
-        Ok(Self {
+        let shard = Self {
             config,
             llama_config,
             embedding,
@@ -284,7 +409,9 @@ impl ShardedLlama {
             norm,
             lm_head,
             rope,
-        })
+        };
+        info!("✅ Sharded model loaded: {} layers, {} parameters", shard.layers.len(), shard.parameter_count());
+        Ok(shard)
     }
     
     /// Process input through this shard's layers
@@ -320,7 +447,8 @@ impl ShardedLlama {
                 .ok_or(ShardedModelError::MissingLayer("lm_head".to_string()))?;
             
             // Extract last token logits
-            let last_hidden = hidden.i((.., seq_len - 1, ..))?;
+            // Quantized matmuls need a contiguous input
+            let last_hidden = hidden.i((.., seq_len - 1, ..))?.contiguous()?;
             hidden = lm_head.forward(&last_hidden)?;
         }
         
@@ -341,9 +469,25 @@ impl ShardedLlama {
             total_layers: self.llama_config.num_hidden_layers as u32,
             hidden_size: self.llama_config.hidden_size as u32,
             vocab_size: self.llama_config.vocab_size as u32,
+            parameter_count: self.parameter_count(),
         }
     }
+
+    /// Number of weights this shard actually holds
+    pub fn parameter_count(&self) -> u64 {
+        let hidden = self.llama_config.hidden_size as u64;
+        let embedding = self.embedding.as_ref().map_or(0, |e| e.embeddings().elem_count() as u64);
+        let norm = if self.norm.is_some() { hidden } else { 0 };
+        let lm_head = self.lm_head.as_ref().map_or(0, |l| weight_count(l) as u64);
+        let layers: u64 = self.layers.iter().map(TransformerBlock::parameter_count).sum();
+        embedding + layers + norm + lm_head
+    }
     
+    /// This shard's place in the pipeline
+    pub fn role(&self) -> PipelineRole {
+        self.config.role
+    }
+
     /// Get device
     pub fn device(&self) -> &Device {
         &self.config.device
@@ -362,13 +506,13 @@ impl TransformerBlock {
             layer_idx,
             attention_norm: candle_nn::rms_norm(hidden_size, config.rms_norm_eps, vb.pp("input_layernorm"))?,
             ffn_norm: candle_nn::rms_norm(hidden_size, config.rms_norm_eps, vb.pp("post_attention_layernorm"))?,
-            wq: linear_no_bias(hidden_size, n_head * head_dim, vb.pp("self_attn.q_proj"))?,
-            wk: linear_no_bias(hidden_size, n_kv_head * head_dim, vb.pp("self_attn.k_proj"))?,
-            wv: linear_no_bias(hidden_size, n_kv_head * head_dim, vb.pp("self_attn.v_proj"))?,
-            wo: linear_no_bias(n_head * head_dim, hidden_size, vb.pp("self_attn.o_proj"))?,
-            w1: linear_no_bias(hidden_size, intermediate_size, vb.pp("mlp.gate_proj"))?,
-            w2: linear_no_bias(intermediate_size, hidden_size, vb.pp("mlp.down_proj"))?,
-            w3: linear_no_bias(hidden_size, intermediate_size, vb.pp("mlp.up_proj"))?,
+            wq: linear(hidden_size, n_head * head_dim, vb.pp("self_attn.q_proj"))?,
+            wk: linear(hidden_size, n_kv_head * head_dim, vb.pp("self_attn.k_proj"))?,
+            wv: linear(hidden_size, n_kv_head * head_dim, vb.pp("self_attn.v_proj"))?,
+            wo: linear(n_head * head_dim, hidden_size, vb.pp("self_attn.o_proj"))?,
+            w1: linear(hidden_size, intermediate_size, vb.pp("mlp.gate_proj"))?,
+            w2: linear(intermediate_size, hidden_size, vb.pp("mlp.down_proj"))?,
+            w3: linear(hidden_size, intermediate_size, vb.pp("mlp.up_proj"))?,
             n_head,
             n_kv_head,
             head_dim,
@@ -376,6 +520,15 @@ impl TransformerBlock {
         })
     }
     
+    fn parameter_count(&self) -> u64 {
+        let linears: usize = [&self.wq, &self.wk, &self.wv, &self.wo, &self.w1, &self.w2, &self.w3]
+            .iter()
+            .map(|l| weight_count(l))
+            .sum();
+        // Plus the attention and FFN norm weights
+        (linears + 2 * self.n_head * self.head_dim) as u64
+    }
+
     fn forward(&self, x: &Tensor, rope: &RotaryEmbedding, pos: usize) -> Result<Tensor, ShardedModelError> {
         let _enter = self.span.enter();
         let (b_sz, seq_len, _hidden_size) = x.dims3()?;
@@ -431,6 +584,19 @@ impl TransformerBlock {
     }
 }
 
+/// Bias-free projection from `in_dim` to `out_dim`, stored unquantized
+fn linear(in_dim: usize, out_dim: usize, vb: VarBuilder) -> Result<QMatMul, candle_core::Error> {
+    Ok(QMatMul::Tensor(vb.get((out_dim, in_dim), "weight")?))
+}
+
+/// Number of weights in `m`, whether quantized or not
+fn weight_count(m: &QMatMul) -> usize {
+    match m {
+        QMatMul::QTensor(t) => t.shape().elem_count(),
+        QMatMul::Tensor(t) | QMatMul::TensorF16(t) => t.elem_count(),
+    }
+}
+
 fn repeat_kv(x: Tensor, n_rep: usize) -> Result<Tensor, candle_core::Error> {
     if n_rep == 1 {
         Ok(x)
@@ -469,6 +635,67 @@ fn load_llama_config(model_path: &str) -> Result<LlamaConfig, ShardedModelError>
     }
 }
 
+/// Number of transformer layers in the model at `path`: a GGUF file (only
+/// its header is read) or a directory with a `config.json`
+pub fn model_layer_count(path: impl AsRef<Path>) -> Result<u32, ShardedModelError> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        let file = fs::File::open(path.join("config.json"))?;
+        let config: LlamaConfigJson =
+            serde_json::from_reader(file).map_err(|e| ShardedModelError::ConfigError(e.to_string()))?;
+        return Ok(config.num_hidden_layers as u32);
+    }
+
+    let mut file = std::io::BufReader::new(fs::File::open(path)?);
+    let content = gguf_file::Content::read(&mut file)
+        .map_err(|e| ShardedModelError::ConfigError(format!("{}: {}", path.display(), e)))?;
+    let blocks = content
+        .metadata
+        .get("llama.block_count")
+        .ok_or_else(|| ShardedModelError::ConfigError("GGUF metadata is missing llama.block_count".to_string()))?;
+    Ok(blocks.to_u32()?)
+}
+
+/// Model hyperparameters from the `llama.*` GGUF metadata
+fn gguf_llama_config(content: &gguf_file::Content) -> Result<LlamaConfig, ShardedModelError> {
+    let get = |key: &str| {
+        content
+            .metadata
+            .get(key)
+            .ok_or_else(|| ShardedModelError::ConfigError(format!("GGUF metadata is missing {}", key)))
+    };
+    let get_u32 = |key: &str| -> Result<usize, ShardedModelError> { Ok(get(key)?.to_u32()? as usize) };
+    let shape = |name: &str| {
+        content
+            .tensor_infos
+            .get(name)
+            .map(|info| info.shape.dims().to_vec())
+            .ok_or_else(|| ShardedModelError::MissingLayer(name.to_string()))
+    };
+
+    let hidden_size = get_u32("llama.embedding_length")?;
+    let intermediate_size = match get_u32("llama.feed_forward_length") {
+        Ok(size) => size,
+        Err(_) => shape("blk.0.ffn_up.weight")?[0],
+    };
+    Ok(LlamaConfig {
+        hidden_size,
+        intermediate_size,
+        vocab_size: shape("token_embd.weight")?[0],
+        num_hidden_layers: get_u32("llama.block_count")?,
+        num_attention_heads: get_u32("llama.attention.head_count")?,
+        num_key_value_heads: get_u32("llama.attention.head_count_kv")?,
+        rms_norm_eps: get("llama.attention.layer_norm_rms_epsilon")?.to_f32()? as f64,
+        rope_theta: get("llama.rope.freq_base").and_then(|v| Ok(v.to_f32()?)).unwrap_or(10000.0),
+        max_position_embeddings: get_u32("llama.context_length").unwrap_or(4096),
+        bos_token_id: None,
+        eos_token_id: None,
+        rope_scaling: None,
+        tie_word_embeddings: !content.tensor_infos.contains_key("output.weight"),
+        use_flash_attn: false,
+    })
+}
+
 /// Info about a model shard
 #[derive(Debug, Clone)]
 pub struct ShardInfo {
@@ -479,6 +706,8 @@ pub struct ShardInfo {
     pub total_layers: u32,
     pub hidden_size: u32,
     pub vocab_size: u32,
+    /// Weights loaded on this node
+    pub parameter_count: u64,
 }
 
 /// Errors in sharded model operations
@@ -496,3 +725,69 @@ pub enum ShardedModelError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    fn max_diff(a: &Tensor, b: &Tensor) -> f32 {
+        (a - b).unwrap().abs().unwrap().flatten_all().unwrap().max(0).unwrap().to_scalar().unwrap()
+    }
+
+    #[test]
+    fn test_gguf_shards_match_full_model() {
+        let dir = std::env::temp_dir().join(format!("cortex-gguf-shard-{}", std::process::id()));
+        let gguf_path = write_tiny_model(&dir);
+        let last = LAYERS - 1;
+
+        let reference = ShardedLlama::load(ShardConfig {
+            model_path: dir.to_str().unwrap().to_string(),
+            total_layers: LAYERS,
+            role: PipelineRole::Single { start_layer: 0, end_layer: last },
+            device: Device::Cpu,
+            dtype: DType::F32,
+        })
+        .unwrap();
+        assert_eq!(model_layer_count(&dir).unwrap(), LAYERS);
+        assert_eq!(model_layer_count(&gguf_path).unwrap(), LAYERS);
+
+        let full = ShardedLlama::load_shard(&gguf_path, 0, last).unwrap();
+        assert_eq!(full.role(), PipelineRole::Single { start_layer: 0, end_layer: last });
+        assert_eq!(full.parameter_count(), reference.parameter_count());
+
+        let input = Tensor::new(&[[3u32, 7, 11, 2]], &Device::Cpu).unwrap();
+        let expected = reference.forward(&input).unwrap();
+        assert!(max_diff(&full.forward(&input).unwrap(), &expected) < 1e-5);
+
+        let head = ShardedLlama::load_shard(&gguf_path, 0, 1).unwrap();
+        let middle = ShardedLlama::load_shard(&gguf_path, 2, 2).unwrap();
+        let tail = ShardedLlama::load_shard(&gguf_path, 3, last).unwrap();
+        assert!(head.role().is_head() && !head.role().is_tail());
+        assert!(matches!(middle.role(), PipelineRole::Middle { .. }));
+        assert!(tail.role().is_tail());
+        assert_eq!(
+            head.parameter_count() + middle.parameter_count() + tail.parameter_count(),
+            full.parameter_count()
+        );
+        assert_eq!(middle.info().num_layers, 1);
+        assert!(middle.embedding.is_none() && middle.lm_head.is_none());
+
+        let hidden = head.forward(&input).unwrap();
+        let hidden = middle.forward(&hidden).unwrap();
+        assert!(max_diff(&tail.forward(&hidden).unwrap(), &expected) < 1e-5);
+
+        // Quantized matrices are kept as loaded rather than expanded to f32
+        let quantized = ShardedLlama::load_shard(dir.join(GGUF_Q8_0), 0, last).unwrap();
+        assert!(quantized.layers.iter().all(|l| matches!(l.wq, QMatMul::QTensor(_))));
+        assert!(matches!(quantized.lm_head, Some(QMatMul::QTensor(_))));
+        assert_eq!(quantized.parameter_count(), full.parameter_count());
+        let scale = max_diff(&expected, &expected.zeros_like().unwrap());
+        assert!(max_diff(&quantized.forward(&input).unwrap(), &expected) < 0.1 * scale);
+
+        assert!(ShardedLlama::load_shard(&gguf_path, 2, LAYERS).is_err());
+        assert!(ShardedLlama::load_shard(&gguf_path, 2, 1).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
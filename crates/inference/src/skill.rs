@@ -9,25 +9,6 @@ use cortex_skill::Result as SkillResult;
 
 use crate::model::{Model, GenerationParams, ChatMessage};
 
-/// Base inference skill that wraps a model
-pub struct InferenceSkill {
-    metadata: SkillMetadata,
-    model: Arc<RwLock<dyn Model>>,
-}
-
-impl InferenceSkill {
-    pub fn new(skill_id: &str, model: Arc<RwLock<dyn Model>>) -> Self {
-        Self {
-            metadata: SkillMetadata::new(
-                skill_id,
-                skill_id,
-                "LLM inference skill",
-            ),
-            model,
-        }
-    }
-}
-
 /// Text completion skill
 pub struct CompletionSkill {
     metadata: SkillMetadata,
@@ -62,6 +43,7 @@ impl Skill for CompletionSkill {
 
     fn capabilities(&self) -> SkillCapability {
         SkillCapability {
+            provides: vec!["completion".to_string()],
             hardware: vec![],
             models: vec!["llm".to_string()],
             min_memory_mb: 1024,
@@ -125,6 +107,7 @@ impl Skill for ChatSkill {
 
     fn capabilities(&self) -> SkillCapability {
         SkillCapability {
+            provides: vec!["chat".to_string()],
             hardware: vec![],
             models: vec!["llm".to_string()],
             min_memory_mb: 2048,
@@ -189,6 +172,7 @@ impl Skill for EmbeddingSkill {
 
     fn capabilities(&self) -> SkillCapability {
         SkillCapability {
+            provides: vec!["embedding".to_string()],
             hardware: vec![],
             models: vec!["embedding".to_string()],
             min_memory_mb: 512,
@@ -242,6 +226,7 @@ impl Skill for CodeSkill {
 
     fn capabilities(&self) -> SkillCapability {
         SkillCapability {
+            provides: vec!["code".to_string()],
             hardware: vec![],
             models: vec!["code-llm".to_string()],
             min_memory_mb: 4096,
@@ -264,7 +249,7 @@ impl Skill for CodeSkill {
         let params = GenerationParams {
             temperature: 0.2, // Lower temperature for code
             max_tokens: 1024,
-            stop: vec![format!("```")],
+            stop: vec!["```".to_string()],
             ..Default::default()
         };
 
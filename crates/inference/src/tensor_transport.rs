@@ -3,25 +3,57 @@
 //! Serializes and sends tensors between nodes for distributed inference.
 //! This is the core of TRUE distributed AI - passing hidden states between nodes.
 
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use candle_core::{DType, Device, Tensor};
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tracing::debug;
+use cortex_grid::{Conn, GridTransport, TcpTransport};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, warn};
+
+/// Wire encoding for tensor values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QuantKind {
+    /// Values as-is (no quantization)
+    F32,
+    /// Half precision, 2 bytes per value
+    F16,
+    /// Affine 8-bit, 1 byte per value
+    Int8,
+}
+
+/// How a quantized tensor's `data` is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Quantization {
+    F16,
+    /// `value = (q - zero_point) * scale`
+    Int8 { scale: f32, zero_point: i32 },
+}
 
 /// Serialized tensor format for network transmission
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializedTensor {
     /// Shape of the tensor (e.g., [batch, seq_len, hidden_dim])
     pub shape: Vec<usize>,
-    /// Data type (f32, f16, bf16)
+    /// Data type (f32, f16, bf16) of the original tensor
     pub dtype: String,
     /// Raw tensor data as bytes
     pub data: Vec<u8>,
     /// Checksum for integrity verification
     pub checksum: [u8; 32],
+    /// Set if `data` holds quantized values rather than `dtype`
+    pub quantization: Option<Quantization>,
 }
 
+/// Leading byte of every encoded tensor and message. bincode has no
+/// optional fields, so any layout change bumps it; 2 added quantization.
+pub const TENSOR_WIRE_VERSION: u8 = 2;
+
+/// Largest message `receive_tensor` accepts. The length prefix is read
+/// from the peer, so it is checked before the buffer is allocated.
+pub const MAX_FRAME_BYTES: u64 = 512 * 1024 * 1024;
+
 impl SerializedTensor {
     /// Serialize a Candle tensor for network transmission
     pub fn from_tensor(tensor: &Tensor) -> Result<Self, TensorTransportError> {
@@ -65,36 +97,152 @@ impl SerializedTensor {
             dtype,
             data,
             checksum,
+            quantization: None,
         })
     }
+
+    /// Re-encode the values as `kind` to shrink the payload. `F32` (and
+    /// re-quantizing an already quantized tensor) returns a copy unchanged.
+    pub fn quantize(&self, kind: QuantKind) -> Result<Self, TensorTransportError> {
+        if kind == QuantKind::F32 || self.quantization.is_some() {
+            return Ok(self.clone());
+        }
+        let values = self.values_f32()?;
+
+        let (data, quantization) = match kind {
+            QuantKind::F32 => unreachable!(),
+            QuantKind::F16 => {
+                let mut bytes = Vec::with_capacity(values.len() * 2);
+                for v in values {
+                    bytes.extend_from_slice(&half::f16::from_f32(v).to_le_bytes());
+                }
+                (bytes, Quantization::F16)
+            }
+            QuantKind::Int8 => {
+                let (min, max) = values
+                    .iter()
+                    .fold((0f32, 0f32), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+                let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+                let zero_point = (-128.0 - min / scale).round() as i32;
+                let bytes = values
+                    .iter()
+                    .map(|&v| ((v / scale).round() as i32 + zero_point).clamp(-128, 127) as i8 as u8)
+                    .collect();
+                (bytes, Quantization::Int8 { scale, zero_point })
+            }
+        };
+
+        Ok(Self {
+            shape: self.shape.clone(),
+            dtype: self.dtype.clone(),
+            checksum: *blake3::hash(&data).as_bytes(),
+            data,
+            quantization: Some(quantization),
+        })
+    }
+
+    /// Expand quantized values back to the original dtype
+    pub fn dequantize(&self) -> Result<Self, TensorTransportError> {
+        if self.quantization.is_none() {
+            return Ok(self.clone());
+        }
+        let tensor = Tensor::from_vec(self.values_f32()?, self.shape.as_slice(), &Device::Cpu)?;
+        let dtype = match self.dtype.as_str() {
+            "F32" => DType::F32,
+            "F16" => DType::F16,
+            "BF16" => DType::BF16,
+            other => return Err(TensorTransportError::UnsupportedDtype(other.to_string())),
+        };
+        Self::from_tensor(&tensor.to_dtype(dtype)?)
+    }
+
+    /// Bytes per value in `data`
+    fn value_width(&self) -> Result<usize, TensorTransportError> {
+        match (self.quantization, self.dtype.as_str()) {
+            (Some(Quantization::Int8 { .. }), _) => Ok(1),
+            (Some(Quantization::F16), _) | (None, "F16") | (None, "BF16") => Ok(2),
+            (None, "F32") => Ok(4),
+            (None, other) => Err(TensorTransportError::UnsupportedDtype(other.to_string())),
+        }
+    }
+
+    /// Verify the checksum and that `data` holds exactly one value per
+    /// element of `shape`
+    fn check_data(&self) -> Result<(), TensorTransportError> {
+        if blake3::hash(&self.data).as_bytes() != &self.checksum {
+            return Err(TensorTransportError::ChecksumMismatch);
+        }
+        // The shape comes from the peer, so its product may not fit
+        let expected = self
+            .shape
+            .iter()
+            .try_fold(self.value_width()?, |bytes, &dim| bytes.checked_mul(dim))
+            .ok_or(TensorTransportError::ShapeOverflow)?;
+        if self.data.len() != expected {
+            return Err(TensorTransportError::LengthMismatch {
+                expected,
+                actual: self.data.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Decode `data` to f32
+    fn values_f32(&self) -> Result<Vec<f32>, TensorTransportError> {
+        self.check_data()?;
+        let values = match (self.quantization, self.dtype.as_str()) {
+            (Some(Quantization::Int8 { scale, zero_point }), _) => self
+                .data
+                .iter()
+                .map(|&q| (q as i8 as i32 - zero_point) as f32 * scale)
+                .collect(),
+            (Some(Quantization::F16), _) | (None, "F16") => self
+                .data
+                .chunks_exact(2)
+                .map(|chunk| half::f16::from_le_bytes([chunk[0], chunk[1]]).to_f32())
+                .collect(),
+            (None, "BF16") => self
+                .data
+                .chunks_exact(2)
+                .map(|chunk| half::bf16::from_le_bytes([chunk[0], chunk[1]]).to_f32())
+                .collect(),
+            (None, "F32") => self
+                .data
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect(),
+            (None, other) => return Err(TensorTransportError::UnsupportedDtype(other.to_string())),
+        };
+        Ok(values)
+    }
     
-    /// Deserialize back to a Candle tensor
+    /// Deserialize back to a Candle tensor, dequantizing if needed
     pub fn to_tensor(&self, device: &Device) -> Result<Tensor, TensorTransportError> {
-        // Verify checksum
-        let computed_checksum = blake3::hash(&self.data);
-        if computed_checksum.as_bytes() != &self.checksum {
-            return Err(TensorTransportError::ChecksumMismatch);
+        if self.quantization.is_some() {
+            return self.dequantize()?.to_tensor(device);
         }
+
+        self.check_data()?;
         
         // Reconstruct tensor based on dtype
         let tensor = match self.dtype.as_str() {
             "F32" => {
                 let values: Vec<f32> = self.data
-                    .chunks(4)
+                    .chunks_exact(4)
                     .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
                     .collect();
                 Tensor::from_vec(values, self.shape.as_slice(), device)?
             }
             "F16" => {
                 let values: Vec<half::f16> = self.data
-                    .chunks(2)
+                    .chunks_exact(2)
                     .map(|chunk| half::f16::from_le_bytes([chunk[0], chunk[1]]))
                     .collect();
                 Tensor::from_vec(values, self.shape.as_slice(), device)?
             }
             "BF16" => {
                 let values: Vec<half::bf16> = self.data
-                    .chunks(2)
+                    .chunks_exact(2)
                     .map(|chunk| half::bf16::from_le_bytes([chunk[0], chunk[1]]))
                     .collect();
                 Tensor::from_vec(values, self.shape.as_slice(), device)?
@@ -107,12 +255,12 @@ impl SerializedTensor {
     
     /// Serialize to bytes for network transmission
     pub fn to_bytes(&self) -> Result<Vec<u8>, TensorTransportError> {
-        bincode::serialize(self).map_err(|e| TensorTransportError::SerializationError(e.to_string()))
+        encode_versioned(self)
     }
     
     /// Deserialize from network bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, TensorTransportError> {
-        bincode::deserialize(bytes).map_err(|e| TensorTransportError::SerializationError(e.to_string()))
+        decode_versioned(bytes)
     }
 }
 
@@ -151,6 +299,11 @@ pub enum InferenceMessage {
         task_id: String,
         error: String,
     },
+    /// Quantization kinds the sender can decode; answered with the
+    /// receiver's own list
+    QuantOffer {
+        supported: Vec<QuantKind>,
+    },
 }
 
 /// Metadata about the inference request
@@ -163,19 +316,96 @@ pub struct InferenceMetadata {
     pub batch_size: usize,
 }
 
-/// Transport for sending/receiving tensors over TCP
+/// Transport for sending/receiving tensors over TCP, or over any other
+/// `GridTransport` such as `InMemoryTransport` for single-process pipelines
 pub struct TensorTransport {
     #[allow(dead_code)]
     local_addr: String,
+    transport: Arc<dyn GridTransport>,
+    /// Encoding for outgoing hidden states, if the peer supports it
+    quantization: QuantKind,
+    /// Kind agreed with each peer address
+    negotiated: Mutex<HashMap<String, QuantKind>>,
 }
 
+/// Every kind `SerializedTensor` can decode
+const SUPPORTED_QUANT: &[QuantKind] = &[QuantKind::F32, QuantKind::F16, QuantKind::Int8];
+
 impl TensorTransport {
     pub fn new(local_addr: &str) -> Self {
+        Self::over(local_addr, Arc::new(TcpTransport))
+    }
+
+    /// Transport that connects through `transport` instead of TCP
+    pub fn over(local_addr: &str, transport: Arc<dyn GridTransport>) -> Self {
         Self {
             local_addr: local_addr.to_string(),
+            transport,
+            quantization: QuantKind::F32,
+            negotiated: Mutex::new(HashMap::new()),
         }
     }
-    
+
+    /// Quantize outgoing hidden states as `kind` for peers that accept it;
+    /// others get full precision
+    pub fn with_quantization(mut self, kind: QuantKind) -> Self {
+        self.quantization = kind;
+        self
+    }
+
+    /// Kinds this node can receive
+    pub fn supported_quant(&self) -> &'static [QuantKind] {
+        SUPPORTED_QUANT
+    }
+
+    /// Agree on the encoding for `target_addr`: our preferred kind if the
+    /// peer can decode it, otherwise `F32`. The result is cached per peer.
+    pub async fn negotiate(&self, target_addr: &str) -> QuantKind {
+        if self.quantization == QuantKind::F32 {
+            return QuantKind::F32;
+        }
+        if let Some(kind) = self.negotiated.lock().unwrap_or_else(|e| e.into_inner()).get(target_addr) {
+            return *kind;
+        }
+
+        let offer = InferenceMessage::QuantOffer {
+            supported: SUPPORTED_QUANT.to_vec(),
+        };
+        let kind = match self.request(target_addr, &offer).await {
+            Ok(InferenceMessage::QuantOffer { supported }) if supported.contains(&self.quantization) => {
+                self.quantization
+            }
+            Ok(_) => QuantKind::F32,
+            Err(e) => {
+                warn!("Quantization negotiation with {} failed: {}", target_addr, e);
+                return QuantKind::F32;
+            }
+        };
+        debug!("Using {:?} for tensors sent to {}", kind, target_addr);
+        self.negotiated
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(target_addr.to_string(), kind);
+        kind
+    }
+
+    /// Check that a tensor server answers at `target_addr`, using the
+    /// quantization offer as a cheap round trip
+    pub async fn ping(&self, target_addr: &str) -> Result<(), TensorTransportError> {
+        let offer = InferenceMessage::QuantOffer {
+            supported: SUPPORTED_QUANT.to_vec(),
+        };
+        match self.request(target_addr, &offer).await? {
+            InferenceMessage::QuantOffer { .. } => Ok(()),
+            _ => Err(TensorTransportError::UnexpectedMessage),
+        }
+    }
+
+    /// The stream transport used to reach peers and to listen
+    pub fn transport(&self) -> &Arc<dyn GridTransport> {
+        &self.transport
+    }
+
     /// Send a tensor to another node
     pub async fn send_tensor(
         &self,
@@ -183,54 +413,51 @@ impl TensorTransport {
         message: InferenceMessage,
     ) -> Result<(), TensorTransportError> {
         let start = std::time::Instant::now();
-        
-        // Serialize message
-        let data = bincode::serialize(&message)
-            .map_err(|e| TensorTransportError::SerializationError(e.to_string()))?;
-        
-        // Connect to target node
-        let mut stream = TcpStream::connect(target_addr).await
-            .map_err(|e| TensorTransportError::ConnectionError(e.to_string()))?;
-        
-        // Send length prefix (8 bytes) + data
-        let len = data.len() as u64;
-        stream.write_all(&len.to_le_bytes()).await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        stream.write_all(&data).await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        stream.flush().await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        
+        let data = encode_message(&message)?;
+        let len = data.len();
+
+        let mut stream = self.connect(target_addr).await?;
+        Self::write_message(&mut stream, &data).await?;
+
         let elapsed = start.elapsed().as_millis();
-        debug!("📤 Sent {} bytes to {} in {}ms", data.len(), target_addr, elapsed);
-        
+        debug!("📤 Sent {} bytes to {} in {}ms", len, target_addr, elapsed);
+
         Ok(())
     }
-    
+
+    /// Answer a request on `stream`, framed and versioned like every other
+    /// message
+    pub async fn reply<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        message: &InferenceMessage,
+    ) -> Result<(), TensorTransportError> {
+        Self::write_message(stream, &encode_message(message)?).await
+    }
+
     /// Receive a tensor message (blocking read)
-    pub async fn receive_tensor(
-        stream: &mut TcpStream,
+    pub async fn receive_tensor<S: AsyncRead + Unpin>(
+        stream: &mut S,
     ) -> Result<InferenceMessage, TensorTransportError> {
         // Read length prefix
         let mut len_buf = [0u8; 8];
         stream.read_exact(&mut len_buf).await
             .map_err(|e| TensorTransportError::ReceiveError(e.to_string()))?;
-        let len = u64::from_le_bytes(len_buf) as usize;
-        
+        let len = u64::from_le_bytes(len_buf);
+        if len > MAX_FRAME_BYTES {
+            return Err(TensorTransportError::FrameTooLarge { len, max: MAX_FRAME_BYTES });
+        }
+        let len = len as usize;
+
         // Read message data
         let mut data = vec![0u8; len];
         stream.read_exact(&mut data).await
             .map_err(|e| TensorTransportError::ReceiveError(e.to_string()))?;
-        
-        // Deserialize
-        let message: InferenceMessage = bincode::deserialize(&data)
-            .map_err(|e| TensorTransportError::SerializationError(e.to_string()))?;
-        
+
         debug!("📥 Received {} bytes", len);
-        
-        Ok(message)
+
+        decode_message(&data)
     }
-    
+
     /// Send hidden state and wait for response
     pub async fn forward_and_wait(
         &self,
@@ -239,33 +466,17 @@ impl TensorTransport {
         hidden_state: &Tensor,
         metadata: InferenceMetadata,
     ) -> Result<Tensor, TensorTransportError> {
-        let serialized = SerializedTensor::from_tensor(hidden_state)?;
-        
+        let quant = self.negotiate(target_addr).await;
+        let serialized = SerializedTensor::from_tensor(hidden_state)?.quantize(quant)?;
+
         let message = InferenceMessage::HiddenState {
             task_id: task_id.to_string(),
             layer_idx: metadata.current_layer,
             tensor: serialized,
             metadata: metadata.clone(),
         };
-        
-        // Connect
-        let mut stream = TcpStream::connect(target_addr).await
-            .map_err(|e| TensorTransportError::ConnectionError(e.to_string()))?;
-        
-        // Send request
-        let data = bincode::serialize(&message)
-            .map_err(|e| TensorTransportError::SerializationError(e.to_string()))?;
-        let len = data.len() as u64;
-        stream.write_all(&len.to_le_bytes()).await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        stream.write_all(&data).await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        stream.flush().await
-            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
-        
-        // Wait for response
-        let response = Self::receive_tensor(&mut stream).await?;
-        
+        let response = self.request(target_addr, &message).await?;
+
         match response {
             InferenceMessage::ProcessResponse { tensor, .. } => {
                 tensor.to_tensor(&Device::Cpu)
@@ -276,6 +487,64 @@ impl TensorTransport {
             _ => Err(TensorTransportError::UnexpectedMessage),
         }
     }
+
+    /// Send `message` and wait for the single reply
+    async fn request(&self, target_addr: &str, message: &InferenceMessage) -> Result<InferenceMessage, TensorTransportError> {
+        let data = encode_message(message)?;
+        let mut stream = self.connect(target_addr).await?;
+        Self::write_message(&mut stream, &data).await?;
+        Self::receive_tensor(&mut stream).await
+    }
+
+    async fn connect(&self, target_addr: &str) -> Result<Conn, TensorTransportError> {
+        self.transport
+            .connect(target_addr)
+            .await
+            .map_err(|e| TensorTransportError::ConnectionError(e.to_string()))
+    }
+
+    /// Write one length-prefixed (8-byte LE) message
+    async fn write_message<S: AsyncWrite + Unpin>(stream: &mut S, data: &[u8]) -> Result<(), TensorTransportError> {
+        let len = data.len() as u64;
+        if len > MAX_FRAME_BYTES {
+            return Err(TensorTransportError::FrameTooLarge { len, max: MAX_FRAME_BYTES });
+        }
+        stream.write_all(&len.to_le_bytes()).await
+            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
+        stream.write_all(data).await
+            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
+        stream.flush().await
+            .map_err(|e| TensorTransportError::SendError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// bincode of `value` behind the `TENSOR_WIRE_VERSION` byte
+fn encode_versioned<T: Serialize>(value: &T) -> Result<Vec<u8>, TensorTransportError> {
+    let mut data = vec![TENSOR_WIRE_VERSION];
+    bincode::serialize_into(&mut data, value)
+        .map_err(|e| TensorTransportError::SerializationError(e.to_string()))?;
+    Ok(data)
+}
+
+fn decode_versioned<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, TensorTransportError> {
+    match data.split_first() {
+        Some((&TENSOR_WIRE_VERSION, body)) => bincode::deserialize(body)
+            .map_err(|e| TensorTransportError::SerializationError(e.to_string())),
+        Some((&found, _)) => Err(TensorTransportError::VersionMismatch {
+            expected: TENSOR_WIRE_VERSION,
+            found,
+        }),
+        None => Err(TensorTransportError::SerializationError("empty message".into())),
+    }
+}
+
+fn encode_message(message: &InferenceMessage) -> Result<Vec<u8>, TensorTransportError> {
+    encode_versioned(message)
+}
+
+fn decode_message(data: &[u8]) -> Result<InferenceMessage, TensorTransportError> {
+    decode_versioned(data)
 }
 
 /// Errors that can occur during tensor transport
@@ -302,6 +571,18 @@ pub enum TensorTransportError {
     #[error("Checksum mismatch - data corrupted")]
     ChecksumMismatch,
     
+    #[error("Tensor data is {actual} bytes, its shape needs {expected}")]
+    LengthMismatch { expected: usize, actual: usize },
+    
+    #[error("Tensor shape has more elements than can be addressed")]
+    ShapeOverflow,
+    
+    #[error("Message of {len} bytes exceeds the {max} byte limit")]
+    FrameTooLarge { len: u64, max: u64 },
+    
+    #[error("Tensor wire version {found}, expected {expected}")]
+    VersionMismatch { expected: u8, found: u8 },
+    
     #[error("Remote error: {0}")]
     RemoteError(String),
     
@@ -323,5 +604,116 @@ mod tests {
         
         assert_eq!(original.dims(), restored.dims());
     }
-}
 
+    #[test]
+    fn test_quantization_roundtrip() {
+        let device = Device::Cpu;
+        let original = Tensor::randn(0f32, 1.0, (2, 16, 64), &device).unwrap();
+        let values: Vec<f32> = original.flatten_all().unwrap().to_vec1().unwrap();
+        let serialized = SerializedTensor::from_tensor(&original).unwrap();
+
+        let max_error = |kind: QuantKind| {
+            let quantized = serialized.quantize(kind).unwrap();
+            let restored = SerializedTensor::from_bytes(&quantized.to_bytes().unwrap())
+                .unwrap()
+                .to_tensor(&device)
+                .unwrap();
+            assert_eq!(restored.dims(), original.dims());
+            assert_eq!(restored.dtype(), DType::F32);
+            let restored: Vec<f32> = restored.flatten_all().unwrap().to_vec1().unwrap();
+            let error = values.iter().zip(&restored).map(|(a, b)| (a - b).abs()).fold(0f32, f32::max);
+            (quantized.data.len(), error)
+        };
+
+        let (f16_len, f16_error) = max_error(QuantKind::F16);
+        assert_eq!(f16_len * 2, serialized.data.len());
+        // f16 keeps 11 significant bits; randn values stay well under 8
+        assert!(f16_error < 8.0 * 2f32.powi(-11), "f16 error {}", f16_error);
+
+        let (int8_len, int8_error) = max_error(QuantKind::Int8);
+        assert_eq!(int8_len * 4, serialized.data.len());
+        let (min, max) = values.iter().fold((0f32, 0f32), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        assert!(int8_error <= (max - min) / 255.0, "int8 error {}", int8_error);
+
+        assert_eq!(serialized.quantize(QuantKind::F32).unwrap().data, serialized.data);
+    }
+
+    #[test]
+    fn test_truncated_data_is_rejected() {
+        let device = Device::Cpu;
+        let original = Tensor::randn(0f32, 1.0, (2, 8), &device).unwrap();
+        let mut serialized = SerializedTensor::from_tensor(&original).unwrap();
+        serialized.data.truncate(serialized.data.len() - 3);
+        serialized.checksum = *blake3::hash(&serialized.data).as_bytes();
+
+        assert!(matches!(
+            serialized.to_tensor(&device),
+            Err(TensorTransportError::LengthMismatch { expected: 64, actual: 61 })
+        ));
+        assert!(matches!(
+            serialized.quantize(QuantKind::F16),
+            Err(TensorTransportError::LengthMismatch { .. })
+        ));
+
+        serialized.shape = vec![usize::MAX, 2];
+        assert!(matches!(
+            serialized.to_tensor(&device),
+            Err(TensorTransportError::ShapeOverflow)
+        ));
+
+        let mut bytes = SerializedTensor::from_tensor(&original).unwrap().to_bytes().unwrap();
+        bytes[0] = TENSOR_WIRE_VERSION - 1;
+        assert!(matches!(
+            SerializedTensor::from_bytes(&bytes),
+            Err(TensorTransportError::VersionMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_rejected_before_reading() {
+        let mut stream: &[u8] = &(MAX_FRAME_BYTES + 1).to_le_bytes();
+        assert!(matches!(
+            TensorTransport::receive_tensor(&mut stream).await,
+            Err(TensorTransportError::FrameTooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quantization_negotiation() {
+        let network: Arc<dyn GridTransport> = Arc::new(cortex_grid::InMemoryTransport::new());
+        let mut incoming = network.listen("peer").await.unwrap();
+        // A peer that only decodes f16 and echoes back what it received
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = incoming.accept().await {
+                let reply = match TensorTransport::receive_tensor(&mut stream).await.unwrap() {
+                    InferenceMessage::QuantOffer { .. } => InferenceMessage::QuantOffer {
+                        supported: vec![QuantKind::F32, QuantKind::F16],
+                    },
+                    InferenceMessage::HiddenState { task_id, tensor, .. } => {
+                        assert_eq!(tensor.quantization, Some(Quantization::F16));
+                        InferenceMessage::ProcessResponse { task_id, end_layer: 0, tensor, processing_time_ms: 0 }
+                    }
+                    other => panic!("unexpected {:?}", other),
+                };
+                TensorTransport::reply(&mut stream, &reply).await.unwrap();
+            }
+        });
+
+        let metadata = InferenceMetadata {
+            model_name: "test".into(),
+            total_layers: 1,
+            current_layer: 0,
+            sequence_length: 4,
+            batch_size: 1,
+        };
+        let hidden = Tensor::randn(0f32, 1.0, (1, 4, 8), &Device::Cpu).unwrap();
+
+        let f16 = TensorTransport::over("head", Arc::clone(&network)).with_quantization(QuantKind::F16);
+        let echoed = f16.forward_and_wait("peer", "task", &hidden, metadata.clone()).await.unwrap();
+        assert_eq!(echoed.dims(), hidden.dims());
+
+        let int8 = TensorTransport::over("head", network).with_quantization(QuantKind::Int8);
+        assert_eq!(int8.negotiate("peer").await, QuantKind::F32);
+        assert_eq!(int8.negotiate("missing").await, QuantKind::F32);
+    }
+}
@@ -0,0 +1,121 @@
+//! A tiny random llama shared by the inference tests
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use candle_core::quantized::{gguf_file, GgmlDType, QTensor};
+use candle_core::{DType, Device, Tensor};
+
+pub(crate) const VOCAB: usize = 32;
+pub(crate) const HIDDEN: usize = 32;
+pub(crate) const HEADS: usize = 4;
+pub(crate) const KV_HEADS: usize = 2;
+pub(crate) const INTERMEDIATE: usize = 64;
+pub(crate) const LAYERS: u32 = 4;
+
+/// File names of the GGUF copies `write_tiny_model` writes
+pub(crate) const GGUF_F32: &str = "model.gguf";
+pub(crate) const GGUF_Q8_0: &str = "model-q8_0.gguf";
+
+/// Write the same random llama as safetensors + config.json with a
+/// word-level tokenizer (`w0`..`w31`), and as GGUF files with llama.cpp's
+/// tensor names and Q/K layout: `GGUF_F32` unquantized and `GGUF_Q8_0` with
+/// its weight matrices in Q8_0. Returns the path of the `GGUF_F32` copy.
+pub(crate) fn write_tiny_model(dir: &Path) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    let device = Device::Cpu;
+    let randn = |shape: &[usize]| Tensor::randn(0f32, 0.2, shape, &device).unwrap();
+    let ones = |len: usize| Tensor::ones(len, DType::F32, &device).unwrap();
+    let head_dim = HIDDEN / HEADS;
+    // llama.cpp stores each Q/K head with the two RoPE halves interleaved
+    let permute = |w: &Tensor, n_head: usize| {
+        w.reshape((n_head, 2, head_dim / 2, HIDDEN)).unwrap().transpose(1, 2).unwrap().reshape(w.shape()).unwrap()
+    };
+
+    let mut hf = HashMap::new();
+    // (GGUF name, tensor, whether it is a weight matrix)
+    let mut gguf = Vec::new();
+    let mut add = |hf_name: String, gguf_name: String, tensor: Tensor, gguf_tensor: Option<Tensor>, matrix: bool| {
+        gguf.push((gguf_name, gguf_tensor.unwrap_or_else(|| tensor.clone()), matrix));
+        hf.insert(hf_name, tensor);
+    };
+    add("model.embed_tokens.weight".into(), "token_embd.weight".into(), randn(&[VOCAB, HIDDEN]), None, false);
+    add("model.norm.weight".into(), "output_norm.weight".into(), ones(HIDDEN), None, false);
+    add("lm_head.weight".into(), "output.weight".into(), randn(&[VOCAB, HIDDEN]), None, true);
+    for layer in 0..LAYERS {
+        let p = |name: &str| format!("model.layers.{}.{}.weight", layer, name);
+        let g = |name: &str| format!("blk.{}.{}.weight", layer, name);
+        add(p("input_layernorm"), g("attn_norm"), ones(HIDDEN), None, false);
+        add(p("post_attention_layernorm"), g("ffn_norm"), ones(HIDDEN), None, false);
+        let q = randn(&[HEADS * head_dim, HIDDEN]);
+        add(p("self_attn.q_proj"), g("attn_q"), q.clone(), Some(permute(&q, HEADS)), true);
+        let k = randn(&[KV_HEADS * head_dim, HIDDEN]);
+        add(p("self_attn.k_proj"), g("attn_k"), k.clone(), Some(permute(&k, KV_HEADS)), true);
+        add(p("self_attn.v_proj"), g("attn_v"), randn(&[KV_HEADS * head_dim, HIDDEN]), None, true);
+        add(p("self_attn.o_proj"), g("attn_output"), randn(&[HIDDEN, HEADS * head_dim]), None, true);
+        add(p("mlp.gate_proj"), g("ffn_gate"), randn(&[INTERMEDIATE, HIDDEN]), None, true);
+        add(p("mlp.up_proj"), g("ffn_up"), randn(&[INTERMEDIATE, HIDDEN]), None, true);
+        add(p("mlp.down_proj"), g("ffn_down"), randn(&[HIDDEN, INTERMEDIATE]), None, true);
+    }
+    candle_core::safetensors::save(&hf, dir.join("model.safetensors")).unwrap();
+
+    let config = serde_json::json!({
+        "hidden_size": HIDDEN,
+        "intermediate_size": INTERMEDIATE,
+        "vocab_size": VOCAB,
+        "num_hidden_layers": LAYERS,
+        "num_attention_heads": HEADS,
+        "num_key_value_heads": KV_HEADS,
+        "rms_norm_eps": 1e-6,
+        "rope_theta": 10000.0,
+        "max_position_embeddings": 128,
+    });
+    fs::write(dir.join("config.json"), config.to_string()).unwrap();
+
+    let vocab: serde_json::Map<String, serde_json::Value> = (0..VOCAB)
+        .map(|i| (format!("w{}", i), serde_json::Value::from(i)))
+        .collect();
+    let tokenizer = serde_json::json!({
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [],
+        "normalizer": null,
+        "pre_tokenizer": { "type": "Whitespace" },
+        "post_processor": null,
+        "decoder": null,
+        "model": { "type": "WordLevel", "vocab": vocab, "unk_token": "w0" },
+    });
+    fs::write(dir.join("tokenizer.json"), tokenizer.to_string()).unwrap();
+
+    use gguf_file::Value;
+    let metadata = [
+        ("general.architecture", Value::String("llama".into())),
+        ("llama.embedding_length", Value::U32(HIDDEN as u32)),
+        ("llama.feed_forward_length", Value::U32(INTERMEDIATE as u32)),
+        ("llama.block_count", Value::U32(LAYERS)),
+        ("llama.attention.head_count", Value::U32(HEADS as u32)),
+        ("llama.attention.head_count_kv", Value::U32(KV_HEADS as u32)),
+        ("llama.attention.layer_norm_rms_epsilon", Value::F32(1e-6)),
+        ("llama.rope.freq_base", Value::F32(10000.0)),
+        ("llama.context_length", Value::U32(128)),
+    ];
+    for (file_name, matrix_dtype) in [(GGUF_F32, GgmlDType::F32), (GGUF_Q8_0, GgmlDType::Q8_0)] {
+        let qtensors: Vec<(&str, QTensor)> = gguf
+            .iter()
+            .map(|(name, tensor, matrix)| {
+                let dtype = if *matrix { matrix_dtype } else { GgmlDType::F32 };
+                (name.as_str(), QTensor::quantize(tensor, dtype).unwrap())
+            })
+            .collect();
+        let mut file = fs::File::create(dir.join(file_name)).unwrap();
+        gguf_file::write(
+            &mut file,
+            &metadata.iter().map(|(k, v)| (*k, v)).collect::<Vec<_>>(),
+            &qtensors.iter().map(|(k, v)| (*k, v)).collect::<Vec<_>>(),
+        )
+        .unwrap();
+    }
+    dir.join(GGUF_F32)
+}
@@ -1,59 +1,48 @@
 // CortexOS iOS FFI - Zero Mock Policy
 // All code uses real implementations - no fake data, no stubs
 
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::net::SocketAddr;
 use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 use tokio::runtime::Runtime;
 use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
 
 // Real discovery from cortex-grid
 use cortex_grid::discovery::{LanDiscovery, Discovery};
 use cortex_grid::peer::NodeId;
 
+// Privacy tagging for exported datasets
+use cortex_storage::{PrivacyFilter, PrivacyLevel};
+
+// Agents shared with the Android library
+use cortex_mobile::{render_transcript, AgentError, AgentRegistry, AgentStatus, InferenceEngine, RealAgent};
+
 // Real inference
-use cortex_inference::{ShardedLlama, ShardConfig, PipelineRole};
+use cortex_inference::{model_layer_count, GenerationParams, ShardedLlama, ShardConfig, PipelineRole};
 use candle_core::{Device, Tensor, DType};
-use candle_transformers::generation::LogitsProcessor;
 use tokenizers::Tokenizer;
 
 // ============================================
 // REAL AGENT SYSTEM
 // ============================================
-
-#[derive(Clone, Debug)]
-pub struct RealAgent {
-    pub id: String,
-    pub name: String,
-    pub agent_type: AgentType,
-    pub status: AgentStatus,
-    pub created_at: Instant,
-    pub events_processed: u32,
-    pub history: Vec<(String, String)>, // (Input, RawOutput)
-}
-
-#[derive(Clone, Debug)]
-pub enum AgentType {
-    Heartbeat { interval_secs: u64 },
-    Logger,
-    Inference(InferenceBackend),
-}
-
-#[derive(Clone, Debug)]
-pub enum InferenceBackend {
-    LocalRuleBased,
-    Remote { url: String, model: String },
-    CoreML,
-    LocalLlama {
-        // Wrapped in Arc/Mutex for thread safety and cloning
-        model: Arc<Mutex<Option<ShardedLlama>>>,
-        tokenizer: Arc<Tokenizer>,
-        model_path: String,
-    },
+// Agents are shared with the Android library through `cortex_mobile`; the
+// backends only iOS has plug in as inference engines.
+
+/// Wire protocol spoken by a remote inference server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiStyle {
+    /// Ollama `/api/generate`
+    Ollama,
+    /// OpenAI-compatible `/v1/chat/completions` (LM Studio, vLLM, hosted APIs)
+    OpenAiChat,
 }
 
 // Callback type for CoreML inference (implemented in Swift)
@@ -66,74 +55,117 @@ pub extern "C" fn cortex_register_coreml(callback: CoreMLCallback) {
     unsafe { COREML_CALLBACK = Some(callback); }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum AgentStatus {
-    Running,
-    Stopped,
+/// Called with `{"event":"added"|"removed","peer":{...}}` as the peer set
+/// changes. The string is only valid for the duration of the call.
+pub type PeerCallback = extern "C" fn(*const c_char);
+
+static PEER_CALLBACK: Mutex<Option<PeerCallback>> = Mutex::new(None);
+
+/// Discovered peers not heard from for this long are dropped
+const PEER_EXPIRY: Duration = Duration::from_secs(120);
+
+/// Get told about discovered and expired peers instead of polling
+/// `cortex_get_peers`. Passing NULL unregisters.
+///
+/// The callback runs on a Tokio runtime thread, never the main thread:
+/// Swift must copy the string and hop to the main queue
+/// (`DispatchQueue.main.async`) before touching UI. It must not block.
+#[no_mangle]
+pub extern "C" fn cortex_register_peer_callback(callback: Option<PeerCallback>) {
+    *PEER_CALLBACK.lock().unwrap_or_else(|e| e.into_inner()) = callback;
+}
+
+#[no_mangle]
+pub extern "C" fn cortex_unregister_peer_callback() {
+    cortex_register_peer_callback(None);
 }
 
-impl RealAgent {
-    pub fn new_heartbeat(name: String, interval_secs: u64) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Heartbeat { interval_secs },
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
+/// Hand a peer change to the registered callback. Must be called without
+/// `STATE` locked, since the callback may call back into the FFI.
+fn notify_peer_change(event: &str, peer: &DiscoveredPeer) {
+    let callback = *PEER_CALLBACK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(callback) = callback {
+        let json = format!(r#"{{"event":"{}","peer":{}}}"#, event, peer.to_json());
+        if let Ok(json) = CString::new(json) {
+            callback(json.as_ptr());
         }
     }
+}
 
-    pub fn new_logger(name: String) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Logger,
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
-        }
+/// A model server reached over HTTP
+pub struct RemoteEngine {
+    pub url: String,
+    pub model: String,
+    pub api_style: ApiStyle,
+    /// Sent as a bearer token when set
+    pub api_key: Option<String>,
+}
+
+impl InferenceEngine for RemoteEngine {
+    fn label(&self) -> String {
+        self.model.clone()
     }
 
-    pub fn new_inference_local(name: String) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Inference(InferenceBackend::LocalRuleBased),
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
-        }
+    fn tag(&self, agent_name: &str) -> String {
+        format!("🤖 [{}@{}]", agent_name, self.model)
     }
 
-    pub fn new_inference_remote(name: String, url: String, model: String) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Inference(InferenceBackend::Remote { url, model }),
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
-        }
+    fn generate(&self, context: &[(String, String)], input: &str) -> Result<String, String> {
+        remote_generate(&self.url, &self.model, self.api_style, self.api_key.as_deref(), context, input)
+            .map_err(|e| format!("Remote inference failed: {}", e))
     }
+}
 
-    pub fn new_inference_coreml(name: String) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Inference(InferenceBackend::CoreML),
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
+/// The Core ML model Swift registers with `cortex_register_coreml`
+pub struct CoreMlEngine;
+
+impl InferenceEngine for CoreMlEngine {
+    fn label(&self) -> String {
+        "coreml".to_string()
+    }
+
+    fn tag(&self, agent_name: &str) -> String {
+        format!("🧠 [{}]", agent_name)
+    }
+
+    fn generate(&self, context: &[(String, String)], input: &str) -> Result<String, String> {
+        let input = render_transcript(context, input);
+        let input = input.as_str();
+        unsafe {
+            if let Some(callback) = COREML_CALLBACK {
+                let c_input = CString::new(input).unwrap();
+                let c_result = callback(c_input.as_ptr());
+                if !c_result.is_null() {
+                    let result = CStr::from_ptr(c_result).to_string_lossy().into_owned();
+                    // Ideally we should free c_result here if Swift allocated it with malloc
+                    // For now, we assume Swift handles memory or returns a static buffer (risky but simple for MVP)
+                    // Or better: Swift returns an autoreleased string pointer? No, that's ObjC.
+                    // We will assume for this MVP that Swift returns a pointer to a buffer that we copy and don't free (leak) or Swift manages.
+                    // To be safe: We will implement the Swift side to return a pointer that Rust *should* free, 
+                    // but since we don't have a free function, we might leak small amounts of memory per inference.
+                    // Given "Zero Mock", we should do it right. But we don't have `free` exposed.
+                    // Let's assume the callback returns a static buffer or we accept the leak for now.
+                    return Ok(result);
+                }
+            }
         }
+        Err("CoreML backend not registered or failed".to_string())
     }
-    
-    pub fn new_inference_llama(name: String, model_path: String) -> Result<Self, String> {
+}
+
+/// A Llama model loaded into this process
+pub struct LlamaEngine {
+    model: Mutex<ShardedLlama>,
+    tokenizer: Tokenizer,
+    pub model_path: String,
+    /// Sampling settings and token budget for every reply
+    pub params: GenerationParams,
+}
+
+impl LlamaEngine {
+    /// Load a safetensors model directory with a `config.json` and
+    /// `tokenizer.json`
+    pub fn load(model_path: String) -> Result<Self, String> {
         // Load Tokenizer
         let tokenizer_path = std::path::Path::new(&model_path).join("tokenizer.json");
         let tokenizer = Tokenizer::from_file(&tokenizer_path)
@@ -145,141 +177,88 @@ impl RealAgent {
         #[cfg(not(feature = "metal"))]
         let device = Device::Cpu;
 
+        let total_layers = model_layer_count(&model_path).map_err(|e| format!("Failed to read model: {}", e))?;
         let config = ShardConfig {
             model_path: model_path.clone(),
-            total_layers: 24, // Assuming Qwen-0.5B or similar
-            role: PipelineRole::Single { start_layer: 0, end_layer: 23 },
+            total_layers,
+            role: PipelineRole::Single { start_layer: 0, end_layer: total_layers.saturating_sub(1) },
             device,
             dtype: DType::F32,
         };
         
         let model = ShardedLlama::load(config)
             .map_err(|e| format!("Failed to load model: {}", e))?;
-            
-        Ok(Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
-            name,
-            agent_type: AgentType::Inference(InferenceBackend::LocalLlama {
-                model: Arc::new(Mutex::new(Some(model))),
-                tokenizer: Arc::new(tokenizer),
-                model_path,
-            }),
-            status: AgentStatus::Running,
-            created_at: Instant::now(),
-            events_processed: 0,
-            history: Vec::new(),
-        })
-    }
 
-    pub fn type_name(&self) -> String {
-        match &self.agent_type {
-            AgentType::Heartbeat { .. } => "heartbeat".to_string(),
-            AgentType::Logger => "logger".to_string(),
-            AgentType::Inference(backend) => match backend {
-                InferenceBackend::LocalRuleBased => "inference (local)".to_string(),
-                InferenceBackend::Remote { model, .. } => format!("inference ({})", model),
-                InferenceBackend::CoreML => "inference (coreml)".to_string(),
-                InferenceBackend::LocalLlama { .. } => "inference (llama)".to_string(),
-            },
-        }
+        Ok(Self { model: Mutex::new(model), tokenizer, model_path, params: GenerationParams::default() })
     }
 
-    pub fn status_name(&self) -> &'static str {
-        match self.status {
-            AgentStatus::Running => "running",
-            AgentStatus::Stopped => "stopped",
-        }
-    }
+    /// Load a GGUF model for fully offline inference, as one shard holding
+    /// every layer. Expects `tokenizer.json` in the same directory.
+    pub fn load_gguf(model_path: String) -> Result<Self, String> {
+        let path = std::path::Path::new(&model_path);
+        let tokenizer_path = path.parent().unwrap_or(std::path::Path::new(".")).join("tokenizer.json");
+        let tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| format!("Failed to load tokenizer from {:?}: {}", tokenizer_path, e))?;
 
-    /// Process an incoming event - returns response if any
-    pub fn on_event(&mut self, event: &str) -> Option<String> {
-        self.events_processed += 1;
+        let layers = model_layer_count(path).map_err(|e| format!("Failed to read model: {}", e))?;
+        let model = ShardedLlama::load_shard(path, 0, layers.saturating_sub(1))
+            .map_err(|e| format!("Failed to load model: {}", e))?;
 
-        match &self.agent_type {
-            AgentType::Logger => Some(format!("📝 [{}] Logged: {}", self.name, event)),
-            AgentType::Inference(backend) => {
-                let (raw, formatted) = self.run_inference(backend, event);
-                self.history.push((event.to_string(), raw));
-                Some(formatted)
-            },
-            AgentType::Heartbeat { .. } => None,
-        }
+        Ok(Self { model: Mutex::new(model), tokenizer, model_path, params: GenerationParams::default() })
     }
 
-    /// Real inference logic - processes input and generates response
-    fn run_inference(&self, backend: &InferenceBackend, input: &str) -> (String, String) {
-        match backend {
-            InferenceBackend::LocalRuleBased => {
-                let raw = self.run_local_rules_raw(input);
-                (raw.clone(), format!("🤖 [{}]: {}", self.name, raw))
-            },
-            InferenceBackend::Remote { url, model } => {
-                let raw = self.run_remote_inference_raw(url, model, input);
-                (raw.clone(), format!("🤖 [{}@{}]: {}", self.name, model, raw))
-            },
-            InferenceBackend::CoreML => {
-                let raw = self.run_coreml_raw(input);
-                (raw.clone(), format!("🧠 [{}]: {}", self.name, raw))
-            },
-            InferenceBackend::LocalLlama { model, tokenizer, .. } => {
-                let raw = self.run_llama_raw(model, tokenizer, input);
-                (raw.clone(), format!("🦙 [{}]: {}", self.name, raw))
-            }
-        }
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
     }
-    
-    fn run_llama_raw(&self, model_arc: &Arc<Mutex<Option<ShardedLlama>>>, tokenizer: &Tokenizer, input: &str) -> String {
-        let mut model_guard = model_arc.lock().unwrap();
-        let model = if let Some(m) = model_guard.as_ref() {
-            m
-        } else {
-            return "Model not initialized".to_string();
-        };
+
+    fn generate_raw(&self, input: &str) -> Result<String, String> {
+        let model = self.model.lock().unwrap_or_else(|e| e.into_inner());
+        let tokenizer = &self.tokenizer;
         
         // 1. Tokenize
         let encoding = match tokenizer.encode(input, true) {
             Ok(e) => e,
-            Err(e) => return format!("Tokenization failed: {}", e),
+            Err(e) => return Err(format!("Tokenization failed: {}", e)),
         };
         let input_ids = encoding.get_ids();
         let mut tokens = input_ids.to_vec();
         
-        // 2. Generation Loop (Simplified Greedy)
-        let max_tokens = 50; // Short response for demo
+        // 2. Generation Loop
         let mut output_text = String::new();
         let device = model.device();
         
-        let mut logits_processor = LogitsProcessor::new(299792458, Some(0.7), Some(0.9)); // Seed, temp, top_p
+        let mut logits_processor = self.params.logits_processor();
         
-        for _ in 0..max_tokens {
+        for _ in 0..self.params.max_tokens {
             // Forward pass
             let input_tensor = match Tensor::from_vec(tokens.clone(), (1, tokens.len()), &device) {
                 Ok(t) => t,
-                Err(e) => return format!("Tensor error: {}", e),
+                Err(e) => return Err(format!("Tensor error: {}", e)),
             };
             
             let logits = match model.forward(&input_tensor) {
                 Ok(l) => l,
-                Err(e) => return format!("Inference error: {}", e),
+                Err(e) => return Err(format!("Inference error: {}", e)),
             };
             
             // Extract last token logits
             let (_b, seq_len, _vocab) = match logits.dims3() {
                 Ok(d) => d,
-                Err(_) => return "Logits shape error".to_string(),
+                Err(_) => return Err("Logits shape error".to_string()),
             };
             let last_logits = match logits.get(0) {
                 Ok(t) => match t.get(seq_len - 1) {
                     Ok(l) => l,
-                    Err(_) => return "Index error".to_string(),
+                    Err(_) => return Err("Index error".to_string()),
                 },
-                Err(_) => return "Batch error".to_string(),
+                Err(_) => return Err("Batch error".to_string()),
             };
             
             // Sample
             let next_token = match logits_processor.sample(&last_logits) {
                 Ok(t) => t,
-                Err(e) => return format!("Sampling error: {}", e),
+                Err(e) => return Err(format!("Sampling error: {}", e)),
             };
             
             tokens.push(next_token);
@@ -291,117 +270,27 @@ impl RealAgent {
                 if text.contains('\n') || next_token == 2 { // EOS for Qwen/Llama usually
                     break;
                 }
+                if self.params.stop.iter().any(|stop| output_text.contains(stop.as_str())) {
+                    break;
+                }
             }
         }
         
-        output_text
+        Ok(output_text)
     }
+}
 
-    fn run_coreml_raw(&self, input: &str) -> String {
-        unsafe {
-            if let Some(callback) = COREML_CALLBACK {
-                let c_input = CString::new(input).unwrap();
-                let c_result = callback(c_input.as_ptr());
-                if !c_result.is_null() {
-                    let result = CStr::from_ptr(c_result).to_string_lossy().into_owned();
-                    // Ideally we should free c_result here if Swift allocated it with malloc
-                    // For now, we assume Swift handles memory or returns a static buffer (risky but simple for MVP)
-                    // Or better: Swift returns an autoreleased string pointer? No, that's ObjC.
-                    // We will assume for this MVP that Swift returns a pointer to a buffer that we copy and don't free (leak) or Swift manages.
-                    // To be safe: We will implement the Swift side to return a pointer that Rust *should* free, 
-                    // but since we don't have a free function, we might leak small amounts of memory per inference.
-                    // Given "Zero Mock", we should do it right. But we don't have `free` exposed.
-                    // Let's assume the callback returns a static buffer or we accept the leak for now.
-                    return result;
-                }
-            }
-        }
-        "CoreML backend not registered or failed".to_string()
+impl InferenceEngine for LlamaEngine {
+    fn label(&self) -> String {
+        "llama".to_string()
     }
 
-    fn run_remote_inference_raw(&self, url: &str, model: &str, input: &str) -> String {
-        let client = reqwest::blocking::Client::new();
-        
-        let body = serde_json::json!({
-            "model": model,
-            "prompt": input,
-            "stream": false
-        });
-
-        match client.post(format!("{}/api/generate", url))
-            .json(&body)
-            .timeout(std::time::Duration::from_secs(10))
-            .send() 
-        {
-            Ok(resp) => {
-                if let Ok(json) = resp.json::<serde_json::Value>() {
-                    if let Some(response) = json.get("response").and_then(|v| v.as_str()) {
-                        return response.to_string();
-                    }
-                }
-                format!("Invalid response from {}", url)
-            }
-            Err(e) => {
-                format!("Connection failed: {}", e)
-            }
-        }
+    fn tag(&self, agent_name: &str) -> String {
+        format!("🦙 [{}]", agent_name)
     }
 
-    fn run_local_rules_raw(&self, input: &str) -> String {
-        let input_lower = input.to_lowercase();
-
-        if input_lower.contains("hello") || input_lower.contains("hi") || input_lower.contains("olá") {
-            return "Olá! Sou um agente de IA do CortexOS rodando localmente.".to_string();
-        }
-
-        if input_lower.contains("quem") || input_lower.contains("who are you") {
-            return "Sou um agente de inferência do CortexOS.".to_string();
-        }
-
-        if input_lower.contains("help") || input_lower.contains("ajuda") {
-            return "Posso ajudar com: saudações, matemática (2+2), echo, tempo, e análise de texto.".to_string();
-        }
-
-        if input_lower.contains("time") || input_lower.contains("tempo") {
-            let uptime = self.created_at.elapsed().as_secs();
-            return format!("Estou rodando há {}s. Processei {} eventos.", uptime, self.events_processed);
-        }
-
-        if input_lower.starts_with("echo ") {
-            return input[5..].to_string();
-        }
-
-        if let Some(result) = self.try_math(input) {
-            return format!("= {}", result);
-        }
-
-        if input_lower.contains("cortex") {
-            return "CortexOS é um sistema operacional cognitivo distribuído.".to_string();
-        }
-
-        let words = input.split_whitespace().count();
-        let chars = input.chars().count();
-        format!("Analisei sua mensagem: {} palavras, {} caracteres.", words, chars)
-    }
-
-    fn try_math(&self, input: &str) -> Option<f64> {
-        let clean = input.replace(' ', "");
-        for op in ['+', '-', '*', '/'] {
-            if let Some(pos) = clean.find(op) {
-                if pos > 0 && pos < clean.len() - 1 {
-                    let a: f64 = clean[..pos].parse().ok()?;
-                    let b: f64 = clean[pos + 1..].parse().ok()?;
-                    return match op {
-                        '+' => Some(a + b),
-                        '-' => Some(a - b),
-                        '*' => Some(a * b),
-                        '/' if b != 0.0 => Some(a / b),
-                        _ => None,
-                    };
-                }
-            }
-        }
-        None
+    fn generate(&self, context: &[(String, String)], input: &str) -> Result<String, String> {
+        self.generate_raw(&render_transcript(context, input))
     }
 }
 
@@ -417,6 +306,14 @@ pub struct DiscoveredPeer {
     pub protocol: String,
 }
 
+impl DiscoveredPeer {
+    fn to_json(&self) -> String {
+        let addrs: Vec<String> = self.addresses.iter().map(|a| format!("\"{}\"", a)).collect();
+        format!(r#"{{"node_id":"{}","addresses":[{}],"protocol":"{}","age_secs":{}}}"#,
+            self.node_id, addrs.join(","), self.protocol, self.last_seen.elapsed().as_secs())
+    }
+}
+
 // ============================================
 // GLOBAL STATE
 // ============================================
@@ -424,7 +321,7 @@ pub struct DiscoveredPeer {
 struct CortexState {
     node_id: String,
     node_id_bytes: [u8; 32],
-    agents: HashMap<String, RealAgent>,
+    agents: AgentRegistry,
     event_log: Vec<String>,
     discovery_broadcasts: u32,
     discovered_peers: HashMap<String, DiscoveredPeer>,
@@ -441,7 +338,7 @@ impl CortexState {
         Self {
             node_id,
             node_id_bytes,
-            agents: HashMap::new(),
+            agents: AgentRegistry::new(),
             event_log: Vec::new(),
             discovery_broadcasts: 0,
             discovered_peers: HashMap::new(),
@@ -456,15 +353,32 @@ impl CortexState {
         self.event_log.push(event);
     }
     
-    fn add_peer(&mut self, peer_id: String, addresses: Vec<SocketAddr>, protocol: &str) {
+    /// Record a sighting. Returns the peer if it is new.
+    fn add_peer(&mut self, peer_id: String, addresses: Vec<SocketAddr>, protocol: &str) -> Option<DiscoveredPeer> {
         let peer = DiscoveredPeer {
             node_id: peer_id.clone(),
             addresses,
             last_seen: Instant::now(),
             protocol: protocol.to_string(),
         };
-        self.discovered_peers.insert(peer_id.clone(), peer);
+        let is_new = self.discovered_peers.insert(peer_id.clone(), peer.clone()).is_none();
         self.log_event(format!("🔍 Discovered peer {} via {}", peer_id, protocol));
+        is_new.then_some(peer)
+    }
+
+    /// Drop peers not seen within `max_age` and return them
+    fn expire_peers(&mut self, max_age: Duration) -> Vec<DiscoveredPeer> {
+        let expired: Vec<String> = self.discovered_peers.iter()
+            .filter(|(_, p)| p.last_seen.elapsed() > max_age)
+            .map(|(id, _)| id.clone())
+            .collect();
+        let removed: Vec<DiscoveredPeer> = expired.iter()
+            .filter_map(|id| self.discovered_peers.remove(id))
+            .collect();
+        for peer in &removed {
+            self.log_event(format!("👋 Lost peer {}", peer.node_id));
+        }
+        removed
     }
 }
 
@@ -475,6 +389,156 @@ static RUNTIME: once_cell::sync::Lazy<Runtime> = once_cell::sync::Lazy::new(|| {
     Runtime::new().expect("Failed to create Tokio runtime")
 });
 
+// ============================================
+// REMOTE INFERENCE
+// ============================================
+
+const REMOTE_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REMOTE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+static HTTP_CLIENT: once_cell::sync::Lazy<reqwest::Client> = once_cell::sync::Lazy::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(REMOTE_CONNECT_TIMEOUT)
+        .timeout(REMOTE_REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to create HTTP client")
+});
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteInferenceError {
+    #[error("could not connect to {url}: {reason}")]
+    Connect { url: String, reason: String },
+    #[error("{url} did not answer within {secs}s")]
+    Timeout { url: String, secs: u64 },
+    #[error("{url} returned HTTP {status}")]
+    Status { url: String, status: u16 },
+    #[error("invalid response from {url}: {reason}")]
+    InvalidResponse { url: String, reason: String },
+    #[error("request to {url} panicked")]
+    Panicked { url: String },
+}
+
+impl RemoteInferenceError {
+    fn from_reqwest(url: &str, e: reqwest::Error) -> Self {
+        let url = url.to_string();
+        if e.is_timeout() {
+            Self::Timeout { url, secs: REMOTE_REQUEST_TIMEOUT.as_secs() }
+        } else if e.is_connect() {
+            Self::Connect { url, reason: e.to_string() }
+        } else if let Some(status) = e.status() {
+            Self::Status { url, status: status.as_u16() }
+        } else {
+            Self::InvalidResponse { url, reason: e.to_string() }
+        }
+    }
+}
+
+/// Run a completion request against a remote server to completion.
+fn remote_generate(
+    url: &str,
+    model: &str,
+    api_style: ApiStyle,
+    api_key: Option<&str>,
+    context: &[(String, String)],
+    input: &str,
+) -> Result<String, RemoteInferenceError> {
+    let (endpoint, body) = build_request(url.trim_end_matches('/'), model, api_style, context, input);
+
+    let request = async {
+        let mut builder = HTTP_CLIENT.post(&endpoint).json(&body);
+        if let Some(key) = api_key.filter(|k| !k.is_empty()) {
+            builder = builder.bearer_auth(key);
+        }
+        let resp = builder
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| RemoteInferenceError::from_reqwest(url, e))?;
+        let json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| RemoteInferenceError::from_reqwest(url, e))?;
+        parse_completion(api_style, &json).ok_or_else(|| RemoteInferenceError::InvalidResponse {
+            url: url.to_string(),
+            reason: match api_style {
+                ApiStyle::Ollama => "missing \"response\" field".to_string(),
+                ApiStyle::OpenAiChat => "missing \"choices[0].message.content\"".to_string(),
+            },
+        })
+    };
+
+    run_blocking(request).unwrap_or_else(|_| Err(RemoteInferenceError::Panicked { url: url.to_string() }))
+}
+
+/// Drive `future` to completion on the shared `RUNTIME`.
+///
+/// FFI calls may arrive on a thread that is already inside a Tokio runtime,
+/// where blocking on a future panics. The future is therefore driven from a
+/// dedicated thread, which is safe from any calling context. A panic on that
+/// thread is returned as `Err`; re-raising it would abort the app once it
+/// reached an `extern "C"` caller.
+fn run_blocking<F: std::future::Future + Send>(future: F) -> std::thread::Result<F::Output>
+where
+    F::Output: Send,
+{
+    std::thread::scope(|scope| scope.spawn(|| RUNTIME.block_on(future)).join())
+}
+
+/// Endpoint and JSON body for one completion request.
+///
+/// Chat APIs receive the context as `messages`; Ollama's generate endpoint
+/// takes a single prompt, so the context is rendered as a transcript.
+fn build_request(
+    base: &str,
+    model: &str,
+    api_style: ApiStyle,
+    context: &[(String, String)],
+    input: &str,
+) -> (String, serde_json::Value) {
+    match api_style {
+        ApiStyle::Ollama => (
+            format!("{}/api/generate", base),
+            serde_json::json!({
+                "model": model,
+                "prompt": render_transcript(context, input),
+                "stream": false
+            }),
+        ),
+        ApiStyle::OpenAiChat => {
+            let mut messages: Vec<_> = context
+                .iter()
+                .map(|(role, content)| serde_json::json!({ "role": role, "content": content }))
+                .collect();
+            messages.push(serde_json::json!({ "role": "user", "content": input }));
+            (
+                openai_chat_endpoint(base),
+                serde_json::json!({
+                    "model": model,
+                    "messages": messages,
+                    "stream": false
+                }),
+            )
+        }
+    }
+}
+
+/// Accept either a server root or a base URL that already ends in `/v1`
+fn openai_chat_endpoint(base: &str) -> String {
+    if base.ends_with("/v1") {
+        format!("{}/chat/completions", base)
+    } else {
+        format!("{}/v1/chat/completions", base)
+    }
+}
+
+fn parse_completion(api_style: ApiStyle, json: &serde_json::Value) -> Option<String> {
+    let text = match api_style {
+        ApiStyle::Ollama => json.get("response"),
+        ApiStyle::OpenAiChat => json.pointer("/choices/0/message/content"),
+    };
+    text.and_then(|v| v.as_str()).map(str::to_string)
+}
+
 // ============================================
 // FFI HELPERS
 // ============================================
@@ -490,6 +554,72 @@ unsafe fn c_to_string(s: *const c_char) -> String {
     CStr::from_ptr(s).to_string_lossy().into_owned()
 }
 
+/// Reject invalid UTF-8 in string parameters instead of replacing it
+static STRICT_UTF8: AtomicBool = AtomicBool::new(false);
+
+/// With `strict` set, string parameters that are not valid UTF-8 fail with
+/// `CORTEX_INVALID_UTF8`. Otherwise bad bytes become U+FFFD.
+#[no_mangle]
+pub extern "C" fn cortex_set_strict_utf8(strict: bool) {
+    STRICT_UTF8.store(strict, Ordering::Relaxed);
+}
+
+/// A required string parameter that failed validation
+struct ArgError {
+    code: CortexErrorCode,
+    param: &'static str,
+}
+
+impl ArgError {
+    /// Record the code and describe the failure as a JSON error
+    fn json(self) -> *mut c_char {
+        set_last_error(self.code);
+        let reason = match self.code {
+            CortexErrorCode::NullArg => "is null",
+            _ => "is not valid UTF-8",
+        };
+        string_to_c(format!(r#"{{"error":"{} {}"}}"#, self.param, reason))
+    }
+
+    /// Record the code for functions that return a bool
+    fn fail(self) -> bool {
+        set_last_error(self.code);
+        false
+    }
+
+    /// Record the code for functions that return an empty string on failure
+    fn empty(self) -> *mut c_char {
+        set_last_error(self.code);
+        string_to_c(String::new())
+    }
+}
+
+/// Read a required string parameter, rejecting NULL (and invalid UTF-8 in
+/// strict mode) rather than treating it as empty
+unsafe fn c_arg(s: *const c_char, param: &'static str) -> Result<String, ArgError> {
+    if s.is_null() {
+        return Err(ArgError { code: CortexErrorCode::NullArg, param });
+    }
+    let s = CStr::from_ptr(s);
+    match s.to_str() {
+        Ok(s) => Ok(s.to_owned()),
+        Err(_) if STRICT_UTF8.load(Ordering::Relaxed) => Err(ArgError { code: CortexErrorCode::InvalidUtf8, param }),
+        Err(_) => Ok(s.to_string_lossy().into_owned()),
+    }
+}
+
+/// Parse optional `GenerationParams` JSON, a NULL pointer selects the defaults
+unsafe fn params_arg(params_json: *const c_char) -> Result<GenerationParams, *mut c_char> {
+    if params_json.is_null() {
+        return Ok(GenerationParams::default());
+    }
+    let json = c_arg(params_json, "params_json").map_err(ArgError::json)?;
+    serde_json::from_str(&json).map_err(|e| {
+        set_last_error(CortexErrorCode::InvalidArg);
+        string_to_c(format!(r#"{{"error":"params_json is invalid: {}"}}"#, e.to_string().replace('"', "'")))
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn cortex_free_string(s: *mut c_char) {
     if !s.is_null() {
@@ -499,6 +629,43 @@ pub extern "C" fn cortex_free_string(s: *mut c_char) {
     }
 }
 
+// ============================================
+// ERROR CODES
+// ============================================
+
+/// Outcome of the last fallible call on the calling thread, read back with
+/// `cortex_last_error` so Swift can branch without parsing the JSON. The
+/// JSON results keep the human-readable detail.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CortexErrorCode {
+    Ok = 0,
+    AgentNotFound = 1,
+    AgentStopped = 2,
+    InvalidArg = 3,
+    ModelLoadFailed = 4,
+    /// A required string parameter was NULL
+    NullArg = 5,
+    /// A string parameter was not UTF-8 while strict mode is on
+    InvalidUtf8 = 6,
+    /// The agent's model could not produce a reply
+    InferenceFailed = 7,
+}
+
+thread_local! {
+    static LAST_ERROR: Cell<CortexErrorCode> = const { Cell::new(CortexErrorCode::Ok) };
+}
+
+fn set_last_error(code: CortexErrorCode) {
+    LAST_ERROR.with(|last| last.set(code));
+}
+
+/// `CortexErrorCode` of the most recent fallible call made on this thread
+#[no_mangle]
+pub extern "C" fn cortex_last_error() -> i32 {
+    LAST_ERROR.with(|last| last.get()) as i32
+}
+
 // ============================================
 // CORE API
 // ============================================
@@ -533,78 +700,203 @@ pub extern "C" fn cortex_get_node_id() -> *mut c_char {
 
 #[no_mangle]
 pub extern "C" fn cortex_start_heartbeat_agent(name: *const c_char, interval_secs: u64) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
     let agent = RealAgent::new_heartbeat(name.clone(), interval_secs.max(1));
     let id = agent.id.clone();
     state.log_event(format!("Started heartbeat agent '{}' ({})", name, id));
-    state.agents.insert(id.clone(), agent);
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
     string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"heartbeat","interval":{}}}"#, id, name, interval_secs))
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_start_logger_agent(name: *const c_char) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
     let agent = RealAgent::new_logger(name.clone());
     let id = agent.id.clone();
     state.log_event(format!("Started logger agent '{}' ({})", name, id));
-    state.agents.insert(id.clone(), agent);
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
     string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"logger"}}"#, id, name))
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_start_inference_agent(name: *const c_char) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
     let agent = RealAgent::new_inference_local(name.clone());
     let id = agent.id.clone();
     state.log_event(format!("Started inference agent '{}' ({})", name, id));
-    state.agents.insert(id.clone(), agent);
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
     string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference"}}"#, id, name))
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_start_llama_agent(name: *const c_char, model_path: *const c_char) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
-    let model_path = unsafe { c_to_string(model_path) };
+    cortex_start_llama_agent_with_params(name, model_path, std::ptr::null())
+}
+
+/// Start a Llama agent with sampling settings given as `GenerationParams`
+/// JSON, missing fields and a NULL `params_json` use the defaults
+#[no_mangle]
+pub extern "C" fn cortex_start_llama_agent_with_params(name: *const c_char, model_path: *const c_char, params_json: *const c_char) -> *mut c_char {
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
+    let model_path = match unsafe { c_arg(model_path, "model_path") } {
+        Ok(model_path) => model_path,
+        Err(e) => return e.json(),
+    };
+    
+    let params = match unsafe { params_arg(params_json) } {
+        Ok(params) => params,
+        Err(e) => return e,
+    };
     
-    match RealAgent::new_inference_llama(name.clone(), model_path.clone()) {
-        Ok(agent) => {
+    match LlamaEngine::load(model_path.clone()) {
+        Ok(engine) => {
+            let agent = RealAgent::new_inference(name.clone(), Arc::new(engine.with_params(params)));
             let mut state = STATE.lock().unwrap();
             let id = agent.id.clone();
             state.log_event(format!("Started Llama agent '{}' ({})", name, id));
-            state.agents.insert(id.clone(), agent);
+            state.agents.insert(agent);
+            set_last_error(CortexErrorCode::Ok);
             string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference","backend":"llama","model":"{}"}}"#, id, name, model_path))
         },
         Err(e) => {
+            set_last_error(CortexErrorCode::ModelLoadFailed);
             string_to_c(format!(r#"{{"error":"{}"}}"#, e))
         }
     }
 }
 
+/// Start an agent that runs a local GGUF model, no network required
+#[no_mangle]
+pub extern "C" fn cortex_start_local_gguf_agent(name: *const c_char, model_path: *const c_char) -> *mut c_char {
+    cortex_start_local_gguf_agent_with_params(name, model_path, std::ptr::null())
+}
+
+/// Start a local GGUF agent with sampling settings given as
+/// `GenerationParams` JSON, a NULL `params_json` uses the defaults
+#[no_mangle]
+pub extern "C" fn cortex_start_local_gguf_agent_with_params(name: *const c_char, model_path: *const c_char, params_json: *const c_char) -> *mut c_char {
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
+    let model_path = match unsafe { c_arg(model_path, "model_path") } {
+        Ok(model_path) => model_path,
+        Err(e) => return e.json(),
+    };
+
+    let params = match unsafe { params_arg(params_json) } {
+        Ok(params) => params,
+        Err(e) => return e,
+    };
+
+    match LlamaEngine::load_gguf(model_path.clone()) {
+        Ok(engine) => {
+            let agent = RealAgent::new_inference(name.clone(), Arc::new(engine.with_params(params)));
+            let mut state = STATE.lock().unwrap();
+            let id = agent.id.clone();
+            state.log_event(format!("Started GGUF agent '{}' ({})", name, id));
+            state.agents.insert(agent);
+            set_last_error(CortexErrorCode::Ok);
+            string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference","backend":"gguf","model":"{}"}}"#, id, name, model_path))
+        },
+        Err(e) => {
+            set_last_error(CortexErrorCode::ModelLoadFailed);
+            string_to_c(format!(r#"{{"error":"{}"}}"#, e.replace('"', "'")))
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn cortex_start_remote_inference_agent(name: *const c_char, url: *const c_char, model: *const c_char) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
-    let url = unsafe { c_to_string(url) };
-    let model = unsafe { c_to_string(model) };
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
+    let url = match unsafe { c_arg(url, "url") } {
+        Ok(url) => url,
+        Err(e) => return e.json(),
+    };
+    let model = match unsafe { c_arg(model, "model") } {
+        Ok(model) => model,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
-    let agent = RealAgent::new_inference_remote(name.clone(), url.clone(), model.clone());
+    let agent = RealAgent::new_inference(
+        name.clone(),
+        Arc::new(RemoteEngine { url: url.clone(), model: model.clone(), api_style: ApiStyle::Ollama, api_key: None }),
+    );
     let id = agent.id.clone();
     state.log_event(format!("Started remote inference agent '{}' ({}) -> {}", name, id, url));
-    state.agents.insert(id.clone(), agent);
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
     string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference","backend":"remote","model":"{}"}}"#, id, name, model))
 }
 
+/// Start an agent backed by an OpenAI-compatible chat completions server.
+/// `api_key` may be null or empty for servers without authentication.
+#[no_mangle]
+pub extern "C" fn cortex_start_openai_inference_agent(
+    name: *const c_char,
+    url: *const c_char,
+    model: *const c_char,
+    api_key: *const c_char,
+) -> *mut c_char {
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
+    let url = match unsafe { c_arg(url, "url") } {
+        Ok(url) => url,
+        Err(e) => return e.json(),
+    };
+    let model = match unsafe { c_arg(model, "model") } {
+        Ok(model) => model,
+        Err(e) => return e.json(),
+    };
+    let api_key = Some(unsafe { c_to_string(api_key) }).filter(|k| !k.is_empty());
+    let mut state = STATE.lock().unwrap();
+    let agent = RealAgent::new_inference(
+        name.clone(),
+        Arc::new(RemoteEngine { url: url.clone(), model: model.clone(), api_style: ApiStyle::OpenAiChat, api_key }),
+    );
+    let id = agent.id.clone();
+    state.log_event(format!("Started OpenAI-compatible inference agent '{}' ({}) -> {}", name, id, url));
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
+    string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference","backend":"openai","model":"{}"}}"#, id, name, model))
+}
+
 #[no_mangle]
 pub extern "C" fn cortex_spawn_coreml_agent(name: *const c_char) -> *mut c_char {
-    let name = unsafe { c_to_string(name) };
+    let name = match unsafe { c_arg(name, "name") } {
+        Ok(name) => name,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
     
-    let agent = RealAgent::new_inference_coreml(name.clone());
+    let agent = RealAgent::new_inference(name.clone(), Arc::new(CoreMlEngine));
     let id = agent.id.clone();
     state.log_event(format!("Started CoreML agent '{}' ({})", name, id));
-    state.agents.insert(id.clone(), agent);
+    state.agents.insert(agent);
+    set_last_error(CortexErrorCode::Ok);
     string_to_c(format!(r#"{{"id":"{}","name":"{}","type":"inference","backend":"coreml"}}"#, id, name))
 }
 
@@ -617,107 +909,245 @@ pub extern "C" fn cortex_agent_count() -> i32 {
 #[no_mangle]
 pub extern "C" fn cortex_list_agents() -> *mut c_char {
     let state = STATE.lock().unwrap();
-    let agents: Vec<String> = state.agents.values().map(|a| {
-        format!(r#"{{"id":"{}","name":"{}","type":"{}","status":"{}","events":{}}}"#,
-            a.id, a.name, a.type_name(), a.status_name(), a.events_processed)
-    }).collect();
-    string_to_c(format!("[{}]", agents.join(",")))
+    string_to_c(state.agents.list_json())
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_stop_agent(agent_id: *const c_char) -> bool {
-    let id = unsafe { c_to_string(agent_id) };
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.fail(),
+    };
     let mut state = STATE.lock().unwrap();
-    if let Some(agent) = state.agents.get_mut(&id) {
-        agent.status = AgentStatus::Stopped;
+    if state.agents.stop(&id).is_ok() {
         state.log_event(format!("Stopped agent '{}'", id));
+        set_last_error(CortexErrorCode::Ok);
         true
     } else {
+        set_last_error(CortexErrorCode::AgentNotFound);
         false
     }
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_remove_agent(agent_id: *const c_char) -> bool {
-    let id = unsafe { c_to_string(agent_id) };
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.fail(),
+    };
     let mut state = STATE.lock().unwrap();
     if state.agents.remove(&id).is_some() {
         state.log_event(format!("Removed agent '{}'", id));
+        set_last_error(CortexErrorCode::Ok);
         true
     } else {
+        set_last_error(CortexErrorCode::AgentNotFound);
         false
     }
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_export_dataset(agent_id: *const c_char) -> *mut c_char {
-    let id = unsafe { c_to_string(agent_id) };
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.empty(),
+    };
     let state = STATE.lock().unwrap();
 
     if let Some(agent) = state.agents.get(&id) {
         let mut jsonl = String::new();
-        for (input, output) in &agent.history {
-            let entry = serde_json::json!({
-                "messages": [
-                    {"role": "user", "content": input},
-                    {"role": "assistant", "content": output}
-                ]
-            });
-            jsonl.push_str(&entry.to_string());
+        for entry in &agent.history {
+            jsonl.push_str(&dataset_line(&entry.input, &entry.output));
             jsonl.push('\n');
         }
+        set_last_error(CortexErrorCode::Ok);
         return string_to_c(jsonl);
     }
+    set_last_error(CortexErrorCode::AgentNotFound);
     string_to_c(String::new())
 }
 
+/// Export only history entries at least as open as `min_privacy_level`
+/// (0 = private, 1 = shareable, 2 = public). Entries that are not public
+/// have emails, phone numbers and long digit runs replaced by hashed
+/// placeholders.
+#[no_mangle]
+pub extern "C" fn cortex_export_dataset_filtered(agent_id: *const c_char, min_privacy_level: i32) -> *mut c_char {
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.empty(),
+    };
+    let Some(min_level) = privacy_level_from_i32(min_privacy_level) else {
+        set_last_error(CortexErrorCode::InvalidArg);
+        return string_to_c(String::new());
+    };
+    let filter = PrivacyFilter::at_least(min_level);
+    let state = STATE.lock().unwrap();
+
+    if let Some(agent) = state.agents.get(&id) {
+        let mut jsonl = String::new();
+        for entry in agent.history.iter().filter(|e| filter.allows(&e.privacy)) {
+            let line = if entry.privacy.is_public() {
+                dataset_line(&entry.input, &entry.output)
+            } else {
+                dataset_line(
+                    &PrivacyFilter::redact_pii(&entry.input),
+                    &PrivacyFilter::redact_pii(&entry.output),
+                )
+            };
+            jsonl.push_str(&line);
+            jsonl.push('\n');
+        }
+        set_last_error(CortexErrorCode::Ok);
+        return string_to_c(jsonl);
+    }
+    set_last_error(CortexErrorCode::AgentNotFound);
+    string_to_c(String::new())
+}
+
+/// Set the privacy level stamped on an agent's future history entries
+#[no_mangle]
+pub extern "C" fn cortex_set_agent_privacy(agent_id: *const c_char, privacy_level: i32) -> bool {
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.fail(),
+    };
+    let Some(level) = privacy_level_from_i32(privacy_level) else {
+        set_last_error(CortexErrorCode::InvalidArg);
+        return false;
+    };
+    let mut state = STATE.lock().unwrap();
+    if let Some(agent) = state.agents.get_mut(&id) {
+        agent.privacy = level;
+        state.log_event(format!("Set privacy of agent '{}' to {:?}", id, level));
+        set_last_error(CortexErrorCode::Ok);
+        true
+    } else {
+        set_last_error(CortexErrorCode::AgentNotFound);
+        false
+    }
+}
+
+/// Forget an inference agent's conversation context
+#[no_mangle]
+pub extern "C" fn cortex_clear_agent_context(agent_id: *const c_char) -> bool {
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.fail(),
+    };
+    let mut state = STATE.lock().unwrap();
+    match state.agents.get_mut(&id) {
+        Some(agent) => {
+            agent.clear_context();
+            set_last_error(CortexErrorCode::Ok);
+            true
+        }
+        None => {
+            set_last_error(CortexErrorCode::AgentNotFound);
+            false
+        }
+    }
+}
+
+fn privacy_level_from_i32(level: i32) -> Option<PrivacyLevel> {
+    match level {
+        0 => Some(PrivacyLevel::Private),
+        1 => Some(PrivacyLevel::Shareable),
+        2 => Some(PrivacyLevel::Public),
+        _ => None,
+    }
+}
+
+fn dataset_line(input: &str, output: &str) -> String {
+    serde_json::json!({
+        "messages": [
+            {"role": "user", "content": input},
+            {"role": "assistant", "content": output}
+        ]
+    })
+    .to_string()
+}
+
 // ============================================
 // EVENT/MESSAGE API
 // ============================================
 
 #[no_mangle]
 pub extern "C" fn cortex_send_to_agent(agent_id: *const c_char, message: *const c_char) -> *mut c_char {
-    let id = unsafe { c_to_string(agent_id) };
-    let message = unsafe { c_to_string(message) };
-    let mut state = STATE.lock().unwrap();
+    let id = match unsafe { c_arg(agent_id, "agent_id") } {
+        Ok(id) => id,
+        Err(e) => return e.json(),
+    };
+    let message = match unsafe { c_arg(message, "message") } {
+        Ok(message) => message,
+        Err(e) => return e.json(),
+    };
+    let snapshot = match STATE.lock().unwrap().agents.running_mut(&id) {
+        Ok(agent) => agent.engine_snapshot(),
+        Err(e) => return send_error(e),
+    };
 
-    if let Some(agent) = state.agents.get_mut(&id) {
-        if agent.status != AgentStatus::Running {
-            return string_to_c(format!(r#"{{"error":"Agent {} is stopped"}}"#, id));
+    let mut state;
+    let result = match snapshot {
+        Some((engine, context)) => {
+            // The model may take seconds or call out over HTTP, so run it
+            // without holding STATE and apply the reply afterwards.
+            let reply = catch_unwind(AssertUnwindSafe(|| engine.generate(&context, &message)))
+                .unwrap_or_else(|_| Err("inference panicked".to_string()));
+            state = STATE.lock().unwrap();
+            match state.agents.get_mut(&id) {
+                Some(agent) => agent.apply_reply(engine.as_ref(), &message, reply),
+                None => Err(AgentError::NotFound(id.clone())),
+            }
+        }
+        None => {
+            state = STATE.lock().unwrap();
+            state.agents.send(&id, &message)
         }
+    };
 
-        if let Some(response) = agent.on_event(&message) {
+    match result {
+        Ok(Some(response)) => {
+            set_last_error(CortexErrorCode::Ok);
             state.log_event(response.clone());
             let escaped = response.replace('\\', "\\\\").replace('"', "\\\"");
-            return string_to_c(format!(r#"{{"response":"{}"}}"#, escaped));
-        } else {
-            return string_to_c(format!(r#"{{"success":true,"agent":"{}"}}"#, id));
+            string_to_c(format!(r#"{{"response":"{}"}}"#, escaped))
+        }
+        Ok(None) => {
+            set_last_error(CortexErrorCode::Ok);
+            string_to_c(format!(r#"{{"success":true,"agent":"{}"}}"#, id))
         }
+        Err(e) => send_error(e),
     }
-    string_to_c(format!(r#"{{"error":"Agent {} not found"}}"#, id))
+}
+
+/// Record `e` as the last error and describe it as JSON
+fn send_error(e: AgentError) -> *mut c_char {
+    set_last_error(match e {
+        AgentError::NotFound(_) => CortexErrorCode::AgentNotFound,
+        AgentError::Stopped(_) => CortexErrorCode::AgentStopped,
+        AgentError::Inference(..) => CortexErrorCode::InferenceFailed,
+    });
+    string_to_c(format!(r#"{{"error":"{}"}}"#, e.to_string().replace('\\', "\\\\").replace('"', "\\\"")))
 }
 
 #[no_mangle]
 pub extern "C" fn cortex_publish_event(kind: *const c_char, payload: *const c_char) -> *mut c_char {
-    let kind = unsafe { c_to_string(kind) };
-    let payload = unsafe { c_to_string(payload) };
+    let kind = match unsafe { c_arg(kind, "kind") } {
+        Ok(kind) => kind,
+        Err(e) => return e.json(),
+    };
+    let payload = match unsafe { c_arg(payload, "payload") } {
+        Ok(payload) => payload,
+        Err(e) => return e.json(),
+    };
     let mut state = STATE.lock().unwrap();
 
     state.log_event(format!("[{}] {}", kind, payload));
 
-    let mut responses = Vec::new();
-    let agent_ids: Vec<String> = state.agents.keys().cloned().collect();
-
-    for id in agent_ids {
-        if let Some(agent) = state.agents.get_mut(&id) {
-            if agent.status == AgentStatus::Running {
-                if let Some(response) = agent.on_event(&payload) {
-                    responses.push(response);
-                }
-            }
-        }
-    }
+    let responses = state.agents.publish(&payload);
 
+    set_last_error(CortexErrorCode::Ok);
     if responses.is_empty() {
         string_to_c(format!(r#"{{"success":true,"kind":"{}","delivered_to":{}}}"#, kind, state.agents.len()))
     } else {
@@ -752,20 +1182,22 @@ pub extern "C" fn cortex_start_discovery() -> *mut c_char {
         
         // Start the discovery
         if let Err(e) = lan_discovery.start().await {
-            eprintln!("❌ LAN Discovery failed to start: {}", e);
+            warn!("LAN discovery failed to start: {}", e);
             return;
         }
         
-        println!("✅ Multi-protocol discovery started for node {}", node_id_str);
+        info!("Multi-protocol discovery started for node {}", node_id_str);
         
         // Process discovery events
         while let Some(event) = event_rx.recv().await {
-            let peer_id_hex = hex::encode(&event.peer_id.0[..8]);
-            println!("🔍 Discovered peer: {} at {:?}", peer_id_hex, event.addresses);
+            let peer_id = event.peer_id.short();
+            debug!("Discovered peer {} at {:?}", peer_id, event.addresses);
             
             // Update global state
-            if let Ok(mut state) = STATE.lock() {
-                state.add_peer(peer_id_hex, event.addresses, "multicast");
+            let added = STATE.lock().ok()
+                .and_then(|mut state| state.add_peer(peer_id, event.addresses, "multicast"));
+            if let Some(peer) = added {
+                notify_peer_change("added", &peer);
             }
         }
     });
@@ -775,12 +1207,16 @@ pub extern "C" fn cortex_start_discovery() -> *mut c_char {
         start_broadcast_listener().await;
     });
     
-    // Also send periodic broadcasts
+    // Also send periodic broadcasts, expiring peers that went quiet
     let node_id_for_broadcast = state.node_id.clone();
     let agents_len = state.agents.len();
     RUNTIME.spawn(async move {
         loop {
             send_discovery_broadcast(&node_id_for_broadcast, agents_len).await;
+            let expired = STATE.lock().map(|mut state| state.expire_peers(PEER_EXPIRY)).unwrap_or_default();
+            for peer in &expired {
+                notify_peer_change("removed", peer);
+            }
             tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
         }
     });
@@ -820,11 +1256,7 @@ pub extern "C" fn cortex_broadcast_discovery() -> *mut c_char {
 pub extern "C" fn cortex_get_peers() -> *mut c_char {
     let state = STATE.lock().unwrap();
     
-    let peers: Vec<String> = state.discovered_peers.values().map(|p| {
-        let addrs: Vec<String> = p.addresses.iter().map(|a| format!("\"{}\"", a)).collect();
-        format!(r#"{{"node_id":"{}","addresses":[{}],"protocol":"{}","age_secs":{}}}"#,
-            p.node_id, addrs.join(","), p.protocol, p.last_seen.elapsed().as_secs())
-    }).collect();
+    let peers: Vec<String> = state.discovered_peers.values().map(DiscoveredPeer::to_json).collect();
     
     string_to_c(format!("[{}]", peers.join(",")))
 }
@@ -852,8 +1284,8 @@ async fn send_discovery_broadcast(node_id: &str, agents: usize) {
         
         for target in &targets {
             match socket.send_to(msg.as_bytes(), target).await {
-                Ok(_) => println!("📡 Broadcast sent to {}", target),
-                Err(e) => println!("⚠️ Broadcast to {} failed: {}", target, e),
+                Ok(_) => debug!("Broadcast sent to {}", target),
+                Err(e) => warn!("Broadcast to {} failed: {}", target, e),
             }
         }
     }
@@ -869,7 +1301,7 @@ async fn start_broadcast_listener() {
             match UdpSocket::bind("0.0.0.0:7078").await {
                 Ok(s) => s,
                 Err(_) => {
-                    eprintln!("❌ Could not bind broadcast listener: {}", e);
+                    warn!("Could not bind broadcast listener: {}", e);
                     return;
                 }
             }
@@ -882,7 +1314,7 @@ async fn start_broadcast_listener() {
     let multicast_addr: std::net::Ipv4Addr = "239.255.70.77".parse().unwrap();
     let _ = socket.join_multicast_v4(multicast_addr, std::net::Ipv4Addr::UNSPECIFIED);
     
-    println!("👂 Broadcast listener started on port 7077");
+    info!("Broadcast listener started on port 7077");
     
     let mut buf = [0u8; 1024];
     loop {
@@ -897,17 +1329,14 @@ async fn start_broadcast_listener() {
                                 let peer_id = &msg[start..start+end];
                                 
                                 // Don't add ourselves
-                                if let Ok(state) = STATE.lock() {
-                                    if peer_id != state.node_id {
-                                        drop(state);
-                                        if let Ok(mut state) = STATE.lock() {
-                                            state.add_peer(
-                                                peer_id.to_string(),
-                                                vec![src],
-                                                "broadcast"
-                                            );
-                                        }
+                                let added = STATE.lock().ok().and_then(|mut state| {
+                                    if peer_id == state.node_id {
+                                        return None;
                                     }
+                                    state.add_peer(peer_id.to_string(), vec![src], "broadcast")
+                                });
+                                if let Some(peer) = added {
+                                    notify_peer_change("added", &peer);
                                 }
                             }
                         }
@@ -915,7 +1344,7 @@ async fn start_broadcast_listener() {
                 }
             }
             Err(e) => {
-                eprintln!("Broadcast recv error: {}", e);
+                warn!("Broadcast recv error: {}", e);
             }
         }
     }
@@ -940,3 +1369,237 @@ pub extern "C" fn cortex_get_event_log() -> *mut c_char {
     let log_json: Vec<String> = state.event_log.iter().map(|e| format!(r#""{}""#, e.replace('"', "\\\""))).collect();
     string_to_c(format!("[{}]", log_json.join(",")))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_remote_inference_inside_runtime_reports_error() {
+        // Used to panic: blocking HTTP client called from within a runtime.
+        let result = remote_generate("http://127.0.0.1:9", "llama3", ApiStyle::Ollama, None, &[], "hello");
+        assert!(matches!(result, Err(RemoteInferenceError::Connect { .. })));
+
+        let engine = RemoteEngine {
+            url: "http://127.0.0.1:9".to_string(),
+            model: "llama3".to_string(),
+            api_style: ApiStyle::Ollama,
+            api_key: None,
+        };
+        assert!(engine.generate(&[], "hello").unwrap_err().starts_with("Remote inference failed: could not connect"));
+    }
+
+    #[test]
+    fn test_error_codes_distinguish_failures() {
+        let send = |id: &CString| {
+            let message = CString::new("ping").unwrap();
+            cortex_free_string(cortex_send_to_agent(id.as_ptr(), message.as_ptr()));
+            cortex_last_error()
+        };
+
+        let missing = CString::new("no-such-agent").unwrap();
+        assert!(!cortex_stop_agent(missing.as_ptr()));
+        assert_eq!(cortex_last_error(), CortexErrorCode::AgentNotFound as i32);
+        assert_eq!(send(&missing), CortexErrorCode::AgentNotFound as i32);
+
+        let name = CString::new("beat").unwrap();
+        let raw = cortex_start_heartbeat_agent(name.as_ptr(), 1);
+        let started: serde_json::Value = serde_json::from_str(&unsafe { c_to_string(raw) }).unwrap();
+        cortex_free_string(raw);
+        let id = CString::new(started["id"].as_str().unwrap()).unwrap();
+
+        assert_eq!(send(&id), CortexErrorCode::Ok as i32);
+        assert!(!cortex_set_agent_privacy(id.as_ptr(), 9));
+        assert_eq!(cortex_last_error(), CortexErrorCode::InvalidArg as i32);
+
+        assert!(cortex_stop_agent(id.as_ptr()));
+        assert_eq!(cortex_last_error(), CortexErrorCode::Ok as i32);
+        assert_eq!(send(&id), CortexErrorCode::AgentStopped as i32);
+
+        assert!(cortex_remove_agent(id.as_ptr()));
+    }
+
+    /// Fails its first reply by panicking
+    #[derive(Default)]
+    struct Flaky {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl InferenceEngine for Flaky {
+        fn label(&self) -> String {
+            "flaky".to_string()
+        }
+
+        fn generate(&self, _context: &[(String, String)], input: &str) -> Result<String, String> {
+            // Deadlocks if the caller still holds STATE
+            drop(STATE.lock().unwrap());
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("model crashed");
+            }
+            Ok(format!("echo {}", input))
+        }
+    }
+
+    #[test]
+    fn test_inference_runs_unlocked_and_panics_become_errors() {
+        let id = STATE
+            .lock()
+            .unwrap()
+            .agents
+            .insert(RealAgent::new_inference("flaky".to_string(), Arc::new(Flaky::default())));
+        let c_id = CString::new(id.clone()).unwrap();
+        let send = || {
+            let message = CString::new("ping").unwrap();
+            let raw = cortex_send_to_agent(c_id.as_ptr(), message.as_ptr());
+            let reply: serde_json::Value = serde_json::from_str(&unsafe { c_to_string(raw) }).unwrap();
+            cortex_free_string(raw);
+            reply
+        };
+
+        let reply = send();
+        assert_eq!(cortex_last_error(), CortexErrorCode::InferenceFailed as i32);
+        assert!(reply["error"].as_str().unwrap().contains("panicked"));
+
+        let reply = send();
+        assert_eq!(cortex_last_error(), CortexErrorCode::Ok as i32);
+        assert_eq!(reply["response"], "🤖 [flaky]: echo ping");
+
+        let (turns, events) = {
+            let state = STATE.lock().unwrap();
+            let agent = state.agents.get(&id).unwrap();
+            (agent.context.len(), agent.events_processed)
+        };
+        assert_eq!((turns, events), (2, 2));
+        assert!(cortex_remove_agent(c_id.as_ptr()));
+    }
+
+    #[test]
+    fn test_null_and_invalid_utf8_params_are_rejected() {
+        let code = || cortex_last_error();
+
+        let raw = cortex_start_logger_agent(std::ptr::null());
+        let reply = unsafe { c_to_string(raw) };
+        cortex_free_string(raw);
+        assert_eq!(reply, r#"{"error":"name is null"}"#);
+        assert_eq!(code(), CortexErrorCode::NullArg as i32);
+
+        assert!(!cortex_stop_agent(std::ptr::null()));
+        assert_eq!(code(), CortexErrorCode::NullArg as i32);
+
+        let name = CString::new("echo").unwrap();
+        let raw = cortex_start_logger_agent(name.as_ptr());
+        let started: serde_json::Value = serde_json::from_str(&unsafe { c_to_string(raw) }).unwrap();
+        cortex_free_string(raw);
+        let id = CString::new(started["id"].as_str().unwrap()).unwrap();
+
+        // A null message is an error, an empty one is a message
+        cortex_free_string(cortex_send_to_agent(id.as_ptr(), std::ptr::null()));
+        assert_eq!(code(), CortexErrorCode::NullArg as i32);
+        let empty = CString::new("").unwrap();
+        cortex_free_string(cortex_send_to_agent(id.as_ptr(), empty.as_ptr()));
+        assert_eq!(code(), CortexErrorCode::Ok as i32);
+
+        let corrupt = CString::new(vec![b'h', 0xff, b'i']).unwrap();
+        cortex_free_string(cortex_send_to_agent(id.as_ptr(), corrupt.as_ptr()));
+        assert_eq!(code(), CortexErrorCode::Ok as i32);
+
+        cortex_set_strict_utf8(true);
+        let raw = cortex_send_to_agent(id.as_ptr(), corrupt.as_ptr());
+        let reply = unsafe { c_to_string(raw) };
+        cortex_free_string(raw);
+        cortex_set_strict_utf8(false);
+        assert_eq!(reply, r#"{"error":"message is not valid UTF-8"}"#);
+        assert_eq!(code(), CortexErrorCode::InvalidUtf8 as i32);
+
+        assert!(cortex_remove_agent(id.as_ptr()));
+    }
+
+    #[test]
+    fn test_peer_changes_reach_callback() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        extern "C" fn record(json: *const c_char) {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            // Re-entering the FFI from the callback must not deadlock
+            assert!(cortex_peer_count() >= 0);
+            EVENTS.lock().unwrap().push(unsafe { c_to_string(json) });
+        }
+
+        let mut state = CortexState::new();
+        let addr: SocketAddr = "192.168.1.20:7077".parse().unwrap();
+
+        cortex_register_peer_callback(Some(record));
+        let peer = state.add_peer("peer-a".to_string(), vec![addr], "broadcast").unwrap();
+        notify_peer_change("added", &peer);
+        // A repeat sighting is not a new peer
+        assert!(state.add_peer("peer-a".to_string(), vec![addr], "broadcast").is_none());
+
+        std::thread::sleep(Duration::from_millis(1));
+        let expired = state.expire_peers(Duration::ZERO);
+        assert_eq!(expired.len(), 1);
+        notify_peer_change("removed", &expired[0]);
+
+        let events = EVENTS.lock().unwrap().clone();
+        assert_eq!(events.len(), 2);
+        let added: serde_json::Value = serde_json::from_str(&events[0]).unwrap();
+        assert_eq!(added["event"], "added");
+        assert_eq!(added["peer"]["node_id"], "peer-a");
+        assert_eq!(added["peer"]["addresses"][0], "192.168.1.20:7077");
+        assert!(events[1].starts_with(r#"{"event":"removed""#));
+
+        cortex_unregister_peer_callback();
+        notify_peer_change("added", &peer);
+        cortex_register_peer_callback(None);
+        notify_peer_change("added", &peer);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_openai_endpoint_and_response_parsing() {
+        assert_eq!(openai_chat_endpoint("http://localhost:1234"), "http://localhost:1234/v1/chat/completions");
+        assert_eq!(openai_chat_endpoint("https://api.example.com/v1"), "https://api.example.com/v1/chat/completions");
+
+        let chat = serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hi there" } }]
+        });
+        assert_eq!(parse_completion(ApiStyle::OpenAiChat, &chat).as_deref(), Some("hi there"));
+        assert_eq!(parse_completion(ApiStyle::Ollama, &chat), None);
+
+        let ollama = serde_json::json!({ "response": "hello" });
+        assert_eq!(parse_completion(ApiStyle::Ollama, &ollama).as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn test_conversation_context() {
+        let context = vec![
+            ("user".to_string(), "my name is Ada".to_string()),
+            ("assistant".to_string(), "Hi Ada".to_string()),
+        ];
+
+        let (_, body) = build_request("http://localhost:1234", "m", ApiStyle::OpenAiChat, &context, "what is my name?");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"], "my name is Ada");
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[2]["content"], "what is my name?");
+
+        let (_, body) = build_request("http://localhost:11434", "m", ApiStyle::Ollama, &context, "what is my name?");
+        assert_eq!(
+            body["prompt"],
+            "User: my name is Ada\nAssistant: Hi Ada\nUser: what is my name?\nAssistant:"
+        );
+        assert_eq!(render_transcript(&[], "hello"), "hello");
+
+        let mut long = Vec::new();
+        for i in 0..10 {
+            long.push(("user".to_string(), format!("question {} {}", i, "x".repeat(100))));
+            long.push(("assistant".to_string(), format!("answer {}", i)));
+        }
+        cortex_mobile::trim_context(&mut long, 300);
+        assert_eq!(long.len(), 4);
+        assert_eq!(long[0].0, "user");
+        assert!(long[0].1.starts_with("question 8"));
+    }
+}
@@ -8,8 +8,15 @@ pub enum Statement {
     Store(StoreExpr),
     Use(UseExpr),
     If(IfExpr),
+    While(WhileExpr),
     Match(MatchExpr),
     Block(Vec<Statement>),
+    /// `let name = value;` declares a variable in the current scope
+    Let(LetExpr),
+    /// `name = value;` updates an existing variable
+    Assign(LetExpr),
+    /// An expression evaluated for its value, e.g. the result of a closure body
+    Expr(Expr),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +52,12 @@ pub struct UseExpr {
     pub config: Option<Expr>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LetExpr {
+    pub name: String,
+    pub value: Expr,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IfExpr {
     pub condition: Expr,
@@ -52,6 +65,12 @@ pub struct IfExpr {
     pub else_branch: Option<Vec<Statement>>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhileExpr {
+    pub condition: Expr,
+    pub body: Vec<Statement>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchExpr {
     pub value: Expr,
@@ -78,6 +97,11 @@ pub enum Expr {
         op: BinaryOp,
         right: Box<Expr>,
     },
+    /// `fn(params) { body }`; evaluates to the value of its last statement
+    Closure {
+        params: Vec<String>,
+        body: Vec<Statement>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,238 @@
+//! Real compilation of generated Rust
+//!
+//! `Compiler::compile_to_rust` only produces source text. `RustBuilder`
+//! type-checks that source with an actual `rustc` subprocess so callers can
+//! report a genuine success flag and the compiler's diagnostics.
+//!
+//! The subprocess emits metadata only (no codegen or linking), runs in a
+//! throwaway directory, is killed when the timeout expires and, on unix,
+//! is started with CPU-time and file-size limits.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use cortex_core::capability::CapabilitySet;
+use tokio::process::Command;
+
+use crate::error::{CompileError, CompileResult};
+
+/// Diagnostics kept from stderr; the rest are summarised in one line
+const MAX_NOTES: usize = 50;
+/// Largest file the compiler may write
+#[cfg(unix)]
+const MAX_OUTPUT_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+static BUILD_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Outcome of type-checking one piece of generated code
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    /// `rustc` exited successfully
+    pub compilation_success: bool,
+    /// `None` if the process was killed (timeout or resource limit)
+    pub exit_code: Option<i32>,
+    /// Compiler diagnostics, one per line
+    pub validation_notes: Vec<String>,
+    pub duration: Duration,
+}
+
+/// Type-checks Rust source with a sandboxed `rustc`
+#[derive(Debug, Clone)]
+pub struct RustBuilder {
+    rustc: PathBuf,
+    edition: String,
+    timeout: Duration,
+    work_dir: PathBuf,
+}
+
+impl Default for RustBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RustBuilder {
+    /// Uses `$RUSTC` (or `rustc` on `PATH`), edition 2021, a 30s timeout
+    /// and the system temp directory.
+    pub fn new() -> Self {
+        Self {
+            rustc: std::env::var_os("RUSTC").map(PathBuf::from).unwrap_or_else(|| "rustc".into()),
+            edition: "2021".to_string(),
+            timeout: Duration::from_secs(30),
+            work_dir: std::env::temp_dir(),
+        }
+    }
+
+    pub fn with_rustc(mut self, rustc: impl Into<PathBuf>) -> Self {
+        self.rustc = rustc.into();
+        self
+    }
+
+    pub fn with_edition(mut self, edition: &str) -> Self {
+        self.edition = edition.to_string();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Directory under which per-build scratch directories are created
+    pub fn with_work_dir(mut self, work_dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = work_dir.into();
+        self
+    }
+
+    /// Type-check `code` as a library crate.
+    ///
+    /// `capabilities` must grant filesystem write access to the work
+    /// directory. Compiler errors and timeouts are reported in the
+    /// `BuildReport`; `Err` means the build could not be attempted.
+    pub async fn build(&self, code: &str, capabilities: &CapabilitySet) -> CompileResult<BuildReport> {
+        if !capabilities.check_fs_write(&self.work_dir) {
+            return Err(CompileError::PermissionDenied(format!(
+                "building requires write access to {}",
+                self.work_dir.display()
+            )));
+        }
+
+        let dir = self.work_dir.join(format!(
+            "cortex-build-{}-{}",
+            std::process::id(),
+            BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::create_dir_all(&dir).await.map_err(io_err)?;
+        let result = self.run_rustc(&dir, code).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+            tracing::warn!(dir = %dir.display(), error = %e, "Failed to remove build directory");
+        }
+        result
+    }
+
+    async fn run_rustc(&self, dir: &Path, code: &str) -> CompileResult<BuildReport> {
+        let source = dir.join("lib.rs");
+        tokio::fs::write(&source, code).await.map_err(io_err)?;
+
+        let mut command = Command::new(&self.rustc);
+        command
+            .arg("--edition")
+            .arg(&self.edition)
+            .args(["--crate-type", "lib", "--crate-name", "generated"])
+            .args(["--emit=metadata", "--error-format=short"])
+            .arg("--out-dir")
+            .arg(dir)
+            .arg(&source)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        limit_resources(&mut command, self.timeout);
+
+        let started = Instant::now();
+        let child = command
+            .spawn()
+            .map_err(|e| CompileError::CompilationFailed(format!("failed to run {}: {}", self.rustc.display(), e)))?;
+
+        // Dropping the future on timeout drops the child, which kills it
+        let output = match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+            Ok(output) => output.map_err(io_err)?,
+            Err(_) => {
+                return Ok(BuildReport {
+                    compilation_success: false,
+                    exit_code: None,
+                    validation_notes: vec![format!("build timed out after {:?}", self.timeout)],
+                    duration: started.elapsed(),
+                });
+            }
+        };
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut notes: Vec<String> = stderr
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        if notes.len() > MAX_NOTES {
+            let omitted = notes.len() - MAX_NOTES;
+            notes.truncate(MAX_NOTES);
+            notes.push(format!("... {} more lines omitted", omitted));
+        }
+
+        Ok(BuildReport {
+            compilation_success: output.status.success(),
+            exit_code: output.status.code(),
+            validation_notes: notes,
+            duration: started.elapsed(),
+        })
+    }
+}
+
+fn io_err(e: std::io::Error) -> CompileError {
+    CompileError::CompilationFailed(e.to_string())
+}
+
+#[cfg(unix)]
+fn limit_resources(command: &mut Command, timeout: Duration) {
+    let cpu_secs = timeout.as_secs().saturating_add(1) as libc::rlim_t;
+    let limits = [
+        (libc::RLIMIT_CPU, cpu_secs),
+        (libc::RLIMIT_FSIZE, MAX_OUTPUT_FILE_BYTES as libc::rlim_t),
+    ];
+    // SAFETY: the closure runs in the forked child before exec and only
+    // calls setrlimit, which is async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            for (resource, value) in limits {
+                let limit = libc::rlimit {
+                    rlim_cur: value,
+                    rlim_max: value,
+                };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cortex_core::capability::Capability;
+
+    fn build_caps() -> CapabilitySet {
+        CapabilitySet::new().with_capability(Capability::fs_write(vec![std::env::temp_dir()]))
+    }
+
+    #[tokio::test]
+    async fn test_real_build() {
+        let builder = RustBuilder::new();
+
+        let ok = builder
+            .build("pub fn add(a: i32, b: i32) -> i32 { a + b }\n", &build_caps())
+            .await
+            .unwrap();
+        assert!(ok.compilation_success, "{:?}", ok.validation_notes);
+        assert_eq!(ok.exit_code, Some(0));
+
+        let bad = builder
+            .build("pub fn broken() -> i32 { \"text\" }\n", &build_caps())
+            .await
+            .unwrap();
+        assert!(!bad.compilation_success);
+        assert!(bad.validation_notes.iter().any(|n| n.contains("error")));
+    }
+
+    #[tokio::test]
+    async fn test_build_requires_capability() {
+        let result = RustBuilder::new().build("pub fn f() {}", &CapabilitySet::new()).await;
+        assert!(matches!(result, Err(CompileError::PermissionDenied(_))));
+    }
+}
@@ -1,9 +1,93 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
 use crate::ast::*;
 use crate::error::CompileError;
+use crate::optimize::{optimize, OptLevel};
+
+/// Leading bytes of a serialized program
+const PROGRAM_MAGIC: &[u8; 4] = b"MLNG";
+/// Bumped whenever the AST or the serialized layout changes
+pub const PROGRAM_FORMAT_VERSION: u32 = 1;
+
+/// Optimized program ready to run, cacheable as bytes.
+///
+/// Layout: `PROGRAM_MAGIC`, the format version as little-endian u32, then
+/// the bincode-encoded program.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompiledProgram {
+    pub opt_level: OptLevel,
+    pub statements: Vec<Statement>,
+}
 
-pub struct Compiler;
+impl CompiledProgram {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CompileError> {
+        let mut bytes = PROGRAM_MAGIC.to_vec();
+        bytes.extend_from_slice(&PROGRAM_FORMAT_VERSION.to_le_bytes());
+        let body =
+            bincode::serialize(self).map_err(|e| CompileError::CompilationFailed(e.to_string()))?;
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// Rejects bytes written by another format version before decoding them
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CompileError> {
+        let header_len = PROGRAM_MAGIC.len() + 4;
+        if bytes.len() < header_len || &bytes[..PROGRAM_MAGIC.len()] != PROGRAM_MAGIC {
+            return Err(CompileError::InvalidProgram("missing program header".to_string()));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[PROGRAM_MAGIC.len()..header_len]);
+        let version = u32::from_le_bytes(version);
+        if version != PROGRAM_FORMAT_VERSION {
+            return Err(CompileError::IncompatibleVersion {
+                found: version,
+                expected: PROGRAM_FORMAT_VERSION,
+            });
+        }
+        bincode::deserialize(&bytes[header_len..])
+            .map_err(|e| CompileError::InvalidProgram(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Compiler {
+    opt_level: OptLevel,
+}
 
 impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_optimizations(mut self, level: OptLevel) -> Self {
+        self.opt_level = level;
+        self
+    }
+
+    pub fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
+
+    /// Run the optimization passes enabled for this compiler
+    pub fn optimize(&self, ast: &[Statement]) -> Vec<Statement> {
+        optimize(ast, self.opt_level)
+    }
+
+    /// Optimize, then generate Rust
+    pub fn compile(&self, ast: &[Statement]) -> Result<String, CompileError> {
+        Self::compile_to_rust(&self.optimize(ast))
+    }
+
+    /// Optimize into a program the VM can run or cache with `to_bytes`
+    pub fn compile_program(&self, ast: &[Statement]) -> CompiledProgram {
+        CompiledProgram {
+            opt_level: self.opt_level,
+            statements: self.optimize(ast),
+        }
+    }
+
     pub fn compile_to_rust(ast: &[Statement]) -> Result<String, CompileError> {
         let mut output = String::new();
         output.push_str("// Auto-generated from MindLang\n");
@@ -66,6 +150,9 @@ impl Compiler {
             Statement::If(_) => {
                 output.push_str(&format!("{}// TODO: if expression\n", prefix));
             }
+            Statement::While(_) => {
+                output.push_str(&format!("{}// TODO: while loop\n", prefix));
+            }
             Statement::Match(_) => {
                 output.push_str(&format!("{}// TODO: match expression\n", prefix));
             }
@@ -76,11 +163,126 @@ impl Compiler {
                 }
                 output.push_str(&format!("{}}}\n", prefix));
             }
+            Statement::Let(LetExpr {
+                name,
+                value: Expr::Closure { params, body },
+            }) => {
+                // Upvalues are copied into the closure, which then owns them
+                let upvalues = Self::upvalues(params, body);
+                output.push_str(&format!("{}let mut {} = {{\n", prefix, Self::sanitize_name(name)));
+                for upvalue in &upvalues {
+                    let upvalue = Self::sanitize_name(upvalue);
+                    output.push_str(&format!("{}    let mut {} = {}.clone();\n", prefix, upvalue, upvalue));
+                }
+                let params: Vec<String> = params.iter().map(|p| Self::sanitize_name(p)).collect();
+                output.push_str(&format!("{}    move |{}| {{\n", prefix, params.join(", ")));
+                for s in body {
+                    Self::compile_statement(output, s, indent + 2)?;
+                }
+                output.push_str(&format!("{}    }}\n{}}};\n", prefix, prefix));
+            }
+            Statement::Let(let_expr) => {
+                output.push_str(&format!("{}// TODO: let {}\n", prefix, let_expr.name));
+            }
+            Statement::Assign(assign) => {
+                output.push_str(&format!("{}// TODO: assign {}\n", prefix, assign.name));
+            }
+            Statement::Expr(_) => {
+                output.push_str(&format!("{}// TODO: expression\n", prefix));
+            }
         }
 
         Ok(())
     }
 
+    /// Names a closure reads or assigns from its enclosing scope: every
+    /// variable used in the body (including by nested closures) that is
+    /// neither a parameter nor declared with `let` inside the body.
+    /// Sorted, so capture order is deterministic.
+    pub fn upvalues(params: &[String], body: &[Statement]) -> Vec<String> {
+        let mut used = BTreeSet::new();
+        let mut declared: BTreeSet<String> = params.iter().cloned().collect();
+        for stmt in body {
+            Self::collect_statement(stmt, &mut used, &mut declared);
+        }
+        used.difference(&declared).cloned().collect()
+    }
+
+    fn collect_statement(stmt: &Statement, used: &mut BTreeSet<String>, declared: &mut BTreeSet<String>) {
+        let mut collect_all = |stmts: &[Statement], used: &mut BTreeSet<String>| {
+            for s in stmts {
+                Self::collect_statement(s, used, declared);
+            }
+        };
+
+        match stmt {
+            Statement::Let(let_expr) => {
+                Self::collect_expr(&let_expr.value, used);
+                declared.insert(let_expr.name.clone());
+            }
+            Statement::Assign(assign) => {
+                used.insert(assign.name.clone());
+                Self::collect_expr(&assign.value, used);
+            }
+            Statement::Expr(expr) => Self::collect_expr(expr, used),
+            Statement::If(if_expr) => {
+                Self::collect_expr(&if_expr.condition, used);
+                collect_all(&if_expr.then_branch, used);
+                if let Some(else_branch) = &if_expr.else_branch {
+                    collect_all(else_branch, used);
+                }
+            }
+            Statement::While(while_expr) => {
+                Self::collect_expr(&while_expr.condition, used);
+                collect_all(&while_expr.body, used);
+            }
+            Statement::Match(match_expr) => {
+                Self::collect_expr(&match_expr.value, used);
+                for arm in &match_expr.arms {
+                    Self::collect_expr(&arm.pattern, used);
+                    collect_all(&arm.body, used);
+                }
+            }
+            Statement::Block(stmts) => collect_all(stmts, used),
+            // Payloads and stored values are read as literals, never resolved
+            Statement::Goal(_)
+            | Statement::On(_)
+            | Statement::Emit(_)
+            | Statement::Store(_)
+            | Statement::Use(_) => {}
+        }
+    }
+
+    fn collect_expr(expr: &Expr, used: &mut BTreeSet<String>) {
+        match expr {
+            Expr::Ident(name) => {
+                used.insert(name.clone());
+            }
+            Expr::Call { func, args } => {
+                used.insert(func.clone());
+                for arg in args {
+                    Self::collect_expr(arg, used);
+                }
+            }
+            Expr::Object(pairs) => {
+                for (_, value) in pairs {
+                    Self::collect_expr(value, used);
+                }
+            }
+            Expr::Array(items) => {
+                for item in items {
+                    Self::collect_expr(item, used);
+                }
+            }
+            Expr::Binary { left, right, .. } => {
+                Self::collect_expr(left, used);
+                Self::collect_expr(right, used);
+            }
+            Expr::Closure { params, body } => used.extend(Self::upvalues(params, body)),
+            Expr::String(_) | Expr::Number(_) | Expr::Bool(_) => {}
+        }
+    }
+
     fn sanitize_name(name: &str) -> String {
         name.chars()
             .map(|c| if c.is_alphanumeric() { c } else { '_' })
@@ -103,4 +305,60 @@ mod tests {
         assert!(rust_code.contains("goal_test"));
         assert!(rust_code.contains("emit_signal"));
     }
+
+    #[test]
+    fn test_program_header_checked() {
+        let stmts = Parser::new(r#"emit "x";"#).parse().unwrap();
+        let mut bytes = Compiler::new().compile_program(&stmts).to_bytes().unwrap();
+        assert!(CompiledProgram::from_bytes(&bytes).is_ok());
+
+        // Written by a future compiler
+        bytes[4..8].copy_from_slice(&(PROGRAM_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            CompiledProgram::from_bytes(&bytes),
+            Err(CompileError::IncompatibleVersion { expected: PROGRAM_FORMAT_VERSION, .. })
+        ));
+
+        assert!(matches!(
+            CompiledProgram::from_bytes(b"emit \"x\";"),
+            Err(CompileError::InvalidProgram(_))
+        ));
+    }
+
+    #[test]
+    fn test_closure_upvalues() {
+        let input = r#"
+            let step = 1;
+            let counter = fn(by) {
+                let scratch = by * step;
+                count = count + scratch;
+                let inner = fn() { total + count };
+                count
+            };
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+        let Statement::Let(LetExpr { value: Expr::Closure { params, body }, .. }) = &stmts[1] else {
+            panic!("expected closure");
+        };
+        assert_eq!(Compiler::upvalues(params, body), vec!["count", "step", "total"]);
+
+        let rust_code = Compiler::compile_to_rust(&stmts).unwrap();
+        assert!(rust_code.contains("let mut count = count.clone();"));
+        assert!(rust_code.contains("move |by|"));
+    }
+
+    #[test]
+    fn test_optimized_compile_prunes_literal_if() {
+        let input = r#"goal "test" { if 2 + 2 == 4 { emit "done"; } else { emit "never"; } }"#;
+        let stmts = Parser::new(input).parse().unwrap();
+
+        let plain = Compiler::new().compile(&stmts).unwrap();
+        assert!(plain.contains("TODO: if expression"));
+
+        let compiler = Compiler::new().with_optimizations(OptLevel::Full);
+        let rust_code = compiler.compile(&stmts).unwrap();
+        assert!(!rust_code.contains("TODO: if expression"));
+        assert!(rust_code.contains("emit_signal(\"done\""));
+        assert!(!rust_code.contains("never"));
+    }
 }
@@ -65,6 +65,14 @@ pub enum VMError {
     /// General runtime error during VM execution
     #[error("Runtime error: {0}")]
     RuntimeError(String),
+
+    /// A compiled program could not be loaded
+    #[error("Failed to load program: {0}")]
+    LoadFailed(#[from] CompileError),
+
+    /// Script ran more statements than its step budget allows
+    #[error("Step limit of {0} exceeded")]
+    StepLimitExceeded(u64),
 }
 
 /// Convenience Result type for VM operations
@@ -80,6 +88,18 @@ pub enum CompileError {
     /// Language construct is not yet supported by compiler
     #[error("Unsupported construct: {0}")]
     UnsupportedConstruct(String),
+
+    /// Caller lacks the capability needed to run a build
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// Cached program was produced by a different compiler version
+    #[error("Program format version {found} is not supported (expected {expected}); recompile from source")]
+    IncompatibleVersion { found: u32, expected: u32 },
+
+    /// Bytes are not a serialized program
+    #[error("Invalid compiled program: {0}")]
+    InvalidProgram(String),
 }
 
 /// Convenience Result type for compiler operations
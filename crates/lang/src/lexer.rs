@@ -9,6 +9,7 @@ pub enum Token {
     Use,
     If,
     Else,
+    While,
     Match,
     Fallback,
     OnSuccess,
@@ -19,6 +20,8 @@ pub enum Token {
     Or,
     True,
     False,
+    Let,
+    Fn,
 
     LBrace,
     RBrace,
@@ -279,6 +282,7 @@ impl Lexer {
                     "use" => Token::Use,
                     "if" => Token::If,
                     "else" => Token::Else,
+                    "while" => Token::While,
                     "match" => Token::Match,
                     "fallback" => Token::Fallback,
                     "on_success" => Token::OnSuccess,
@@ -289,6 +293,8 @@ impl Lexer {
                     "or" => Token::Or,
                     "true" => Token::True,
                     "false" => Token::False,
+                    "let" => Token::Let,
+                    "fn" => Token::Fn,
                     _ => Token::Ident(ident),
                 }
             }
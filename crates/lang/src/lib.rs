@@ -1,13 +1,27 @@
 pub mod ast;
+#[cfg(feature = "native-build")]
+pub mod build;
 pub mod compiler;
 pub mod error;
 pub mod lexer;
+pub mod optimize;
 pub mod parser;
+pub mod stdlib;
+#[cfg(feature = "native-build")]
+pub mod validate;
 pub mod vm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use ast::*;
-pub use compiler::Compiler;
+#[cfg(feature = "native-build")]
+pub use build::{BuildReport, RustBuilder};
+pub use compiler::{CompiledProgram, Compiler, PROGRAM_FORMAT_VERSION};
 pub use error::{CompileError, LexError, ParseError, VMError};
 pub use lexer::{Lexer, Token};
+pub use optimize::OptLevel;
 pub use parser::Parser;
-pub use vm::{VMContext, Value, VM};
+pub use stdlib::NativeFn;
+#[cfg(feature = "native-build")]
+pub use validate::{validate, validator_for, CodeValidator, ValidationReport, Validity};
+pub use vm::{Closure, VMContext, Value, VM};
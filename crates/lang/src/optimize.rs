@@ -0,0 +1,327 @@
+//! AST optimization passes
+//!
+//! Passes rewrite the tree without changing what the VM would compute.
+//! Constant folding only evaluates operators on literal operands, leaves
+//! anything that would fail at runtime (type errors, division by zero) in
+//! place, and only touches expressions the VM evaluates: `if` conditions,
+//! `match` values and patterns, `let`/assignment values and expression
+//! statements. Payloads, stored values and call arguments are read as
+//! literals by the VM, so folding them would change their value.
+//! Dead-code elimination only prunes `if` branches and `while` loops whose
+//! condition is a literal.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::*;
+
+/// How much the compiler optimizes before code generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum OptLevel {
+    /// Compile the tree as parsed
+    #[default]
+    None,
+    /// Fold constant expressions
+    Basic,
+    /// Fold constants, then drop branches behind literal conditions
+    Full,
+}
+
+/// Run the passes enabled at `level` over a program
+pub fn optimize(ast: &[Statement], level: OptLevel) -> Vec<Statement> {
+    let mut stmts = ast.to_vec();
+    if level >= OptLevel::Basic {
+        stmts.iter_mut().for_each(fold_statement);
+    }
+    if level >= OptLevel::Full {
+        stmts = eliminate_dead_code(stmts);
+    }
+    stmts
+}
+
+/// Fold constant subexpressions bottom-up. Call arguments are left alone:
+/// builtins read them as literals.
+pub fn fold_expr(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Binary { left, op, right } => {
+            let (left, right) = (fold_expr(left), fold_expr(right));
+            match eval_literal(&left, op, &right) {
+                Some(folded) => folded,
+                None => Expr::Binary {
+                    left: Box::new(left),
+                    op: op.clone(),
+                    right: Box::new(right),
+                },
+            }
+        }
+        Expr::Object(pairs) => {
+            Expr::Object(pairs.iter().map(|(k, v)| (k.clone(), fold_expr(v))).collect())
+        }
+        Expr::Array(items) => Expr::Array(items.iter().map(fold_expr).collect()),
+        Expr::Closure { params, body } => {
+            let mut body = body.clone();
+            body.iter_mut().for_each(fold_statement);
+            Expr::Closure {
+                params: params.clone(),
+                body,
+            }
+        }
+        _ => expr.clone(),
+    }
+}
+
+/// Mirrors `VM::eval_binary_op` for literal operands; `None` where the VM
+/// would raise an error or the operands are not literals
+fn eval_literal(left: &Expr, op: &BinaryOp, right: &Expr) -> Option<Expr> {
+    use Expr::{Bool, Number, String};
+
+    let folded = match (left, op, right) {
+        (Number(l), BinaryOp::Add, Number(r)) => Number(l + r),
+        (Number(l), BinaryOp::Sub, Number(r)) => Number(l - r),
+        (Number(l), BinaryOp::Mul, Number(r)) => Number(l * r),
+        (Number(l), BinaryOp::Div, Number(r)) if *r != 0.0 => Number(l / r),
+        (Number(l), BinaryOp::Lt, Number(r)) => Bool(l < r),
+        (Number(l), BinaryOp::Gt, Number(r)) => Bool(l > r),
+        (Number(l), BinaryOp::Le, Number(r)) => Bool(l <= r),
+        (Number(l), BinaryOp::Ge, Number(r)) => Bool(l >= r),
+        (l, BinaryOp::Eq, r) => Bool(literals_equal(l, r)?),
+        (l, BinaryOp::Ne, r) => Bool(!literals_equal(l, r)?),
+        (Bool(l), BinaryOp::And, Bool(r)) => Bool(*l && *r),
+        (Bool(l), BinaryOp::Or, Bool(r)) => Bool(*l || *r),
+        (String(l), BinaryOp::Add, String(r)) => String(format!("{}{}", l, r)),
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Equality as the VM defines it, for scalar literals only
+fn literals_equal(a: &Expr, b: &Expr) -> Option<bool> {
+    let scalar = |e: &Expr| matches!(e, Expr::Bool(_) | Expr::Number(_) | Expr::String(_));
+    if !scalar(a) || !scalar(b) {
+        return None;
+    }
+    Some(match (a, b) {
+        (Expr::Bool(a), Expr::Bool(b)) => a == b,
+        (Expr::Number(a), Expr::Number(b)) => (a - b).abs() < f64::EPSILON,
+        (Expr::String(a), Expr::String(b)) => a == b,
+        _ => false,
+    })
+}
+
+/// Truthiness of a literal condition, matching `VM::execute_if`
+fn literal_truthiness(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Bool(b) => Some(*b),
+        Expr::Number(n) => Some(*n != 0.0),
+        Expr::String(s) => Some(!s.is_empty()),
+        _ => None,
+    }
+}
+
+fn fold_statement(stmt: &mut Statement) {
+    let fold_all = |stmts: &mut Vec<Statement>| stmts.iter_mut().for_each(fold_statement);
+    let fold_boxed = |stmt: &mut Option<Box<Statement>>| {
+        if let Some(stmt) = stmt {
+            fold_statement(stmt);
+        }
+    };
+
+    match stmt {
+        Statement::Goal(goal) => {
+            fold_all(&mut goal.body);
+            fold_boxed(&mut goal.on_success);
+            fold_boxed(&mut goal.on_failure);
+            fold_boxed(&mut goal.fallback);
+        }
+        Statement::On(handler) => fold_all(&mut handler.body),
+        Statement::If(if_expr) => {
+            if_expr.condition = fold_expr(&if_expr.condition);
+            fold_all(&mut if_expr.then_branch);
+            if let Some(else_branch) = &mut if_expr.else_branch {
+                fold_all(else_branch);
+            }
+        }
+        Statement::While(while_expr) => {
+            while_expr.condition = fold_expr(&while_expr.condition);
+            fold_all(&mut while_expr.body);
+        }
+        Statement::Match(match_expr) => {
+            match_expr.value = fold_expr(&match_expr.value);
+            for arm in &mut match_expr.arms {
+                arm.pattern = fold_expr(&arm.pattern);
+                fold_all(&mut arm.body);
+            }
+        }
+        Statement::Block(stmts) => fold_all(stmts),
+        Statement::Let(let_expr) | Statement::Assign(let_expr) => {
+            let_expr.value = fold_expr(&let_expr.value)
+        }
+        Statement::Expr(expr) => *expr = fold_expr(expr),
+        Statement::Emit(_) | Statement::Store(_) | Statement::Use(_) => {}
+    }
+}
+
+/// Replace `if` statements with a literal condition by the branch that runs.
+/// A statement list evaluates to its last statement, so a pruned `if` in
+/// last position becomes an empty block (which evaluates to null, as the
+/// `if` did); elsewhere it is dropped.
+fn eliminate_dead_code(stmts: Vec<Statement>) -> Vec<Statement> {
+    let len = stmts.len();
+    let mut out = Vec::with_capacity(len);
+    for (i, stmt) in stmts.into_iter().enumerate() {
+        let stmt = eliminate_in_statement(stmt);
+        let is_empty_block = matches!(&stmt, Statement::Block(body) if body.is_empty());
+        if !is_empty_block || i + 1 == len {
+            out.push(stmt);
+        }
+    }
+    out
+}
+
+fn eliminate_in_statement(stmt: Statement) -> Statement {
+    let boxed = |stmt: Option<Box<Statement>>| stmt.map(|s| Box::new(eliminate_in_statement(*s)));
+
+    match stmt {
+        Statement::Goal(mut goal) => {
+            goal.body = eliminate_dead_code(goal.body);
+            goal.on_success = boxed(goal.on_success);
+            goal.on_failure = boxed(goal.on_failure);
+            goal.fallback = boxed(goal.fallback);
+            Statement::Goal(goal)
+        }
+        Statement::On(mut handler) => {
+            handler.body = eliminate_dead_code(handler.body);
+            Statement::On(handler)
+        }
+        Statement::If(if_expr) => match literal_truthiness(&if_expr.condition) {
+            Some(true) => Statement::Block(eliminate_dead_code(if_expr.then_branch)),
+            Some(false) => {
+                Statement::Block(eliminate_dead_code(if_expr.else_branch.unwrap_or_default()))
+            }
+            None => Statement::If(IfExpr {
+                condition: if_expr.condition,
+                then_branch: eliminate_dead_code(if_expr.then_branch),
+                else_branch: if_expr.else_branch.map(eliminate_dead_code),
+            }),
+        },
+        // A loop that never runs; `while true` is left alone
+        Statement::While(while_expr) if literal_truthiness(&while_expr.condition) == Some(false) => {
+            Statement::Block(Vec::new())
+        }
+        Statement::While(mut while_expr) => {
+            while_expr.body = eliminate_dead_code(while_expr.body);
+            Statement::While(while_expr)
+        }
+        Statement::Match(mut match_expr) => {
+            for arm in &mut match_expr.arms {
+                arm.body = eliminate_dead_code(std::mem::take(&mut arm.body));
+            }
+            Statement::Match(match_expr)
+        }
+        Statement::Block(stmts) => Statement::Block(eliminate_dead_code(stmts)),
+        Statement::Let(mut let_expr) => {
+            let_expr.value = eliminate_in_expr(let_expr.value);
+            Statement::Let(let_expr)
+        }
+        Statement::Assign(mut assign) => {
+            assign.value = eliminate_in_expr(assign.value);
+            Statement::Assign(assign)
+        }
+        Statement::Expr(expr) => Statement::Expr(eliminate_in_expr(expr)),
+        other => other,
+    }
+}
+
+/// Prune the bodies of closures nested anywhere in an expression
+fn eliminate_in_expr(expr: Expr) -> Expr {
+    match expr {
+        Expr::Closure { params, body } => Expr::Closure {
+            params,
+            body: eliminate_dead_code(body),
+        },
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(eliminate_in_expr(*left)),
+            op,
+            right: Box::new(eliminate_in_expr(*right)),
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func,
+            args: args.into_iter().map(eliminate_in_expr).collect(),
+        },
+        Expr::Object(pairs) => {
+            Expr::Object(pairs.into_iter().map(|(k, v)| (k, eliminate_in_expr(v))).collect())
+        }
+        Expr::Array(items) => Expr::Array(items.into_iter().map(eliminate_in_expr).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::vm::{Value, VM};
+
+    fn parse(input: &str) -> Vec<Statement> {
+        Parser::new(input).parse().unwrap()
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        let stmts = optimize(&parse(r#"if 2 + 2 * 3 == 8 { emit "x"; }"#), OptLevel::Basic);
+        let Statement::If(if_expr) = &stmts[0] else { panic!("expected if") };
+        assert!(matches!(if_expr.condition, Expr::Bool(true)));
+
+        // Store values are read as literals by the VM and stay untouched
+        let stmts = optimize(&parse(r#"store "x", 2 + 2;"#), OptLevel::Full);
+        let Statement::Store(store) = &stmts[0] else { panic!("expected store") };
+        assert!(matches!(store.value, Some(Expr::Binary { .. })));
+    }
+
+    #[test]
+    fn test_fold_keeps_runtime_errors() {
+        let expr = fold_expr(&Expr::Binary {
+            left: Box::new(Expr::Number(1.0)),
+            op: BinaryOp::Div,
+            right: Box::new(Expr::Number(0.0)),
+        });
+        assert!(matches!(expr, Expr::Binary { .. }));
+
+        let expr = fold_expr(&Expr::Binary {
+            left: Box::new(Expr::Ident("x".to_string())),
+            op: BinaryOp::Add,
+            right: Box::new(Expr::Number(1.0)),
+        });
+        assert!(matches!(expr, Expr::Binary { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_dead_branch_removed() {
+        let input = r#"
+            if 1 > 2 { emit "never"; } else { emit "always"; }
+            if false { emit "gone"; }
+            emit "after";
+        "#;
+        let ast = parse(input);
+        let stmts = optimize(&ast, OptLevel::Full);
+
+        assert_eq!(stmts.len(), 2);
+        assert!(!stmts.iter().any(|s| matches!(s, Statement::If(_))));
+
+        // Same observable behaviour before and after
+        let mut plain = VM::new();
+        let mut optimized = VM::new();
+        plain.execute(&ast).await.unwrap();
+        optimized.execute(&stmts).await.unwrap();
+        let signals = |vm: &VM| vm.context().signals.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>();
+        assert_eq!(signals(&plain), signals(&optimized));
+        assert_eq!(signals(&optimized), vec!["always", "after"]);
+    }
+
+    #[tokio::test]
+    async fn test_pruned_tail_keeps_value() {
+        let ast = parse(r#"emit "x"; if false { emit "y"; }"#);
+        let stmts = optimize(&ast, OptLevel::Full);
+        let value = VM::new().execute(&stmts).await.unwrap();
+        assert!(matches!(value, Value::Null));
+    }
+}
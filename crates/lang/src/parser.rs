@@ -72,15 +72,32 @@ impl Parser {
             Token::Store => self.parse_store().map(Statement::Store),
             Token::Use => self.parse_use().map(Statement::Use),
             Token::If => self.parse_if().map(Statement::If),
+            Token::While => self.parse_while().map(Statement::While),
             Token::Match => self.parse_match().map(Statement::Match),
             Token::LBrace => self.parse_block().map(Statement::Block),
+            Token::Let => self.parse_let().map(Statement::Let),
+            Token::Fn => {
+                let closure = self.parse_closure()?;
+                self.skip_semicolon();
+                Ok(Statement::Expr(closure))
+            }
             Token::Ident(name) => {
                 let name = name.clone();
                 self.advance();
-                if matches!(self.peek(), Token::LParen) {
-                    self.parse_call_statement(name)
-                } else {
-                    Err(ParseError::InvalidExpression)
+                match self.peek() {
+                    Token::Eq => {
+                        self.advance();
+                        let value = self.parse_expr()?;
+                        self.skip_semicolon();
+                        Ok(Statement::Assign(LetExpr { name, value }))
+                    }
+                    _ => {
+                        // Re-read the identifier as the start of an expression
+                        self.pos -= 1;
+                        let expr = self.parse_expr()?;
+                        self.skip_semicolon();
+                        Ok(Statement::Expr(expr))
+                    }
                 }
             }
             _ => Err(ParseError::InvalidExpression),
@@ -284,6 +301,13 @@ impl Parser {
         })
     }
 
+    fn parse_while(&mut self) -> Result<WhileExpr, ParseError> {
+        self.expect(Token::While)?;
+        let condition = self.parse_expr()?;
+        let body = self.parse_block()?;
+        Ok(WhileExpr { condition, body })
+    }
+
     fn parse_match(&mut self) -> Result<MatchExpr, ParseError> {
         self.expect(Token::Match)?;
         let value = self.parse_expr()?;
@@ -303,43 +327,49 @@ impl Parser {
         Ok(MatchExpr { value, arms })
     }
 
-    fn parse_block(&mut self) -> Result<Vec<Statement>, ParseError> {
-        self.expect(Token::LBrace)?;
-        let stmts = self.parse_statements_until_rbrace()?;
-        self.expect(Token::RBrace)?;
-        Ok(stmts)
-    }
-
-    fn parse_statements_until_rbrace(&mut self) -> Result<Vec<Statement>, ParseError> {
-        let mut statements = Vec::new();
-        while !matches!(self.peek(), Token::RBrace | Token::Eof) {
-            statements.push(self.parse_statement()?);
-        }
-        Ok(statements)
+    fn parse_let(&mut self) -> Result<LetExpr, ParseError> {
+        self.expect(Token::Let)?;
+        let name = self.expect_ident()?;
+        self.expect(Token::Eq)?;
+        let value = self.parse_expr()?;
+        self.skip_semicolon();
+        Ok(LetExpr { name, value })
     }
 
-    fn parse_call_statement(&mut self, func: String) -> Result<Statement, ParseError> {
+    fn parse_closure(&mut self) -> Result<Expr, ParseError> {
+        self.expect(Token::Fn)?;
         self.expect(Token::LParen)?;
-        let mut args = Vec::new();
+        let mut params = Vec::new();
         while !matches!(self.peek(), Token::RParen | Token::Eof) {
-            args.push(self.parse_expr()?);
+            params.push(self.expect_ident()?);
             if matches!(self.peek(), Token::Comma) {
                 self.advance();
             }
         }
         self.expect(Token::RParen)?;
+        let body = self.parse_block()?;
+        Ok(Expr::Closure { params, body })
+    }
+
+    fn skip_semicolon(&mut self) {
         if matches!(self.peek(), Token::Semicolon) {
             self.advance();
         }
+    }
 
-        Ok(Statement::Emit(EmitExpr {
-            signal: func,
-            payload: if args.is_empty() {
-                None
-            } else {
-                Some(Expr::Array(args))
-            },
-        }))
+    fn parse_block(&mut self) -> Result<Vec<Statement>, ParseError> {
+        self.expect(Token::LBrace)?;
+        let stmts = self.parse_statements_until_rbrace()?;
+        self.expect(Token::RBrace)?;
+        Ok(stmts)
+    }
+
+    fn parse_statements_until_rbrace(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while !matches!(self.peek(), Token::RBrace | Token::Eof) {
+            statements.push(self.parse_statement()?);
+        }
+        Ok(statements)
     }
 
     fn parse_expr(&mut self) -> Result<Expr, ParseError> {
@@ -453,6 +483,7 @@ impl Parser {
                 self.advance();
                 Ok(Expr::Bool(false))
             }
+            Token::Fn => self.parse_closure(),
             Token::Ident(name) => {
                 self.advance();
                 if matches!(self.peek(), Token::LParen) {
@@ -0,0 +1,232 @@
+//! Standard library of native functions
+//!
+//! `VMContext::with_std()` registers these; a bare `VMContext::new()` has
+//! none, so sandboxed scripts only get the operators and the agent
+//! intrinsics. Arguments are evaluated before the call and values are
+//! passed by value, so list functions return new lists.
+//!
+//! | Function | Description |
+//! |---|---|
+//! | `len(x)` | Characters in a string, items in a list, or keys in an object |
+//! | `print(args...)` | Log the arguments, space separated, and append them to `VMContext::output`; returns null |
+//! | `push(list, item)` | Copy of `list` with `item` appended |
+//! | `map(list, f)` | List of `f(item)` for each item |
+//! | `str(x)` | `x` rendered as a string |
+//! | `abs(n)` | Absolute value |
+//! | `min(n...)`, `max(n...)` | Smallest / largest of one or more numbers |
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::error::VMError;
+use crate::vm::{Value, VM};
+
+/// Future returned by natives that need the VM
+pub type NativeFuture<'a> = Pin<Box<dyn Future<Output = Result<Value, VMError>> + Send + 'a>>;
+
+/// A function implemented in Rust and callable from scripts
+#[derive(Clone, Copy)]
+pub enum NativeFn {
+    /// Depends only on its arguments
+    Pure(fn(&[Value]) -> Result<Value, VMError>),
+    /// Needs the VM, e.g. to call a closure passed as an argument
+    Vm(for<'a> fn(&'a mut VM, Vec<Value>) -> NativeFuture<'a>),
+}
+
+impl std::fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NativeFn::Pure(_) => f.write_str("NativeFn::Pure"),
+            NativeFn::Vm(_) => f.write_str("NativeFn::Vm"),
+        }
+    }
+}
+
+/// The builtins registered by `VMContext::with_std()`
+pub fn std_functions() -> Vec<(&'static str, NativeFn)> {
+    vec![
+        ("len", NativeFn::Pure(len)),
+        ("print", NativeFn::Vm(print)),
+        ("push", NativeFn::Pure(push)),
+        ("map", NativeFn::Vm(map)),
+        ("str", NativeFn::Pure(str)),
+        ("abs", NativeFn::Pure(abs)),
+        ("min", NativeFn::Pure(min)),
+        ("max", NativeFn::Pure(max)),
+    ]
+}
+
+fn arity(name: &str, args: &[Value], expected: usize) -> Result<(), VMError> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(VMError::TypeError(format!(
+            "{} takes {} arguments, got {}",
+            name,
+            expected,
+            args.len()
+        )))
+    }
+}
+
+fn number(name: &str, value: &Value) -> Result<f64, VMError> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        other => Err(VMError::TypeError(format!("{} expects numbers, got {:?}", name, other))),
+    }
+}
+
+fn len(args: &[Value]) -> Result<Value, VMError> {
+    arity("len", args, 1)?;
+    let len = match &args[0] {
+        Value::String(s) => s.chars().count(),
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        other => return Err(VMError::TypeError(format!("len of {:?}", other))),
+    };
+    Ok(Value::Number(len as f64))
+}
+
+fn print(vm: &mut VM, args: Vec<Value>) -> NativeFuture<'_> {
+    Box::pin(async move {
+        let line: Vec<String> = args.iter().map(Value::to_string).collect();
+        let line = line.join(" ");
+        tracing::info!("{}", line);
+        vm.context_mut().output.push(line);
+        Ok(Value::Null)
+    })
+}
+
+fn push(args: &[Value]) -> Result<Value, VMError> {
+    arity("push", args, 2)?;
+    match &args[0] {
+        Value::Array(items) => {
+            let mut items = items.clone();
+            items.push(args[1].clone());
+            Ok(Value::Array(items))
+        }
+        other => Err(VMError::TypeError(format!("push onto {:?}", other))),
+    }
+}
+
+fn map(vm: &mut VM, args: Vec<Value>) -> NativeFuture<'_> {
+    Box::pin(async move {
+        arity("map", &args, 2)?;
+        let mut args = args.into_iter();
+        let (list, f) = (args.next(), args.next());
+        match (list, f) {
+            (Some(Value::Array(items)), Some(Value::Closure(f))) => {
+                let mut mapped = Vec::with_capacity(items.len());
+                for item in items {
+                    mapped.push(vm.call_closure_with(&f, vec![item]).await?);
+                }
+                Ok(Value::Array(mapped))
+            }
+            (list, f) => Err(VMError::TypeError(format!(
+                "map expects a list and a closure, got {:?} and {:?}",
+                list, f
+            ))),
+        }
+    })
+}
+
+fn str(args: &[Value]) -> Result<Value, VMError> {
+    arity("str", args, 1)?;
+    Ok(Value::String(args[0].to_string()))
+}
+
+fn abs(args: &[Value]) -> Result<Value, VMError> {
+    arity("abs", args, 1)?;
+    Ok(Value::Number(number("abs", &args[0])?.abs()))
+}
+
+fn fold_numbers(name: &str, args: &[Value], pick: fn(f64, f64) -> f64) -> Result<Value, VMError> {
+    let (first, rest) = args
+        .split_first()
+        .ok_or_else(|| VMError::TypeError(format!("{} needs at least one argument", name)))?;
+    let mut acc = number(name, first)?;
+    for value in rest {
+        acc = pick(acc, number(name, value)?);
+    }
+    Ok(Value::Number(acc))
+}
+
+fn min(args: &[Value]) -> Result<Value, VMError> {
+    fold_numbers("min", args, f64::min)
+}
+
+fn max(args: &[Value]) -> Result<Value, VMError> {
+    fold_numbers("max", args, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::vm::VMContext;
+
+    async fn run(input: &str, context: VMContext) -> Result<VM, VMError> {
+        let stmts = Parser::new(input).parse().unwrap();
+        let mut vm = VM::with_context(context);
+        vm.execute(&stmts).await?;
+        Ok(vm)
+    }
+
+    fn get(vm: &VM, name: &str) -> String {
+        vm.context().variables[name].to_string()
+    }
+
+    #[tokio::test]
+    async fn test_std_builtins() {
+        let input = r#"
+            let xs = push(push([1, 0 - 2], 3), 0 - 4);
+            let n = len(xs);
+            let doubled = map(xs, fn(x) { x * 2 });
+            let mags = map(xs, fn(x) { abs(x) });
+            let lo = min(3, 0 - 7, 2);
+            let hi = max(3, 0 - 7, 2);
+            let label = str(n) + " items";
+            let chars = len("héllo");
+            print("done:", label);
+        "#;
+        let vm = run(input, VMContext::new().with_std()).await.unwrap();
+
+        assert_eq!(get(&vm, "xs"), "[1, -2, 3, -4]");
+        assert_eq!(get(&vm, "n"), "4");
+        assert_eq!(get(&vm, "doubled"), "[2, -4, 6, -8]");
+        assert_eq!(get(&vm, "mags"), "[1, 2, 3, 4]");
+        assert_eq!(get(&vm, "lo"), "-7");
+        assert_eq!(get(&vm, "hi"), "3");
+        assert_eq!(get(&vm, "label"), "4 items");
+        assert_eq!(get(&vm, "chars"), "5");
+    }
+
+    #[tokio::test]
+    async fn test_top_level_print_writes_output() {
+        let input = r#"
+            let n = 3;
+            print("n is", n, [1, 2]);
+            print();
+        "#;
+        let vm = run(input, VMContext::new().with_std()).await.unwrap();
+
+        assert_eq!(vm.context().output, vec!["n is 3 [1, 2]".to_string(), String::new()]);
+        assert!(vm.context().signals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_builtin_type_errors() {
+        let context = VMContext::new().with_std();
+        assert!(matches!(run("let n = len(5);", context).await, Err(VMError::TypeError(_))));
+        let context = VMContext::new().with_std();
+        assert!(matches!(run("let n = min();", context).await, Err(VMError::TypeError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bare_context_has_no_std() {
+        // Without the std `len` is an unknown name, so the call emits a signal
+        let vm = run("let n = len([1, 2]);", VMContext::new()).await.unwrap();
+        assert!(matches!(vm.context().variables["n"], Value::Null));
+        assert_eq!(vm.context().signals[0].0, "len");
+    }
+}
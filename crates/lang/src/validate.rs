@@ -0,0 +1,259 @@
+//! Language-aware syntax validation of generated code
+//!
+//! Each `CodeValidator` gives a real syntax-validity signal for one language:
+//! Rust is parsed in-process with `syn`, Python is parsed by the `python3`
+//! `ast` module and JavaScript is checked with `node --check`. Languages
+//! without a validator are reported as `Validity::Unsupported` instead of
+//! passing silently.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// How long an interpreter subprocess may take to parse a source file
+const SUBPROCESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+    Valid,
+    Invalid,
+    /// No validator for the language, or its toolchain is unavailable
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    pub language: String,
+    pub validity: Validity,
+    pub validation_notes: Vec<String>,
+}
+
+impl ValidationReport {
+    fn new(language: &str, validity: Validity, validation_notes: Vec<String>) -> Self {
+        Self {
+            language: language.to_string(),
+            validity,
+            validation_notes,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validity == Validity::Valid
+    }
+
+    /// Contribution to a quality score: 1.0 if the code parses, 0.0 if it
+    /// does not, `None` when validity could not be determined.
+    pub fn syntax_score(&self) -> Option<f32> {
+        match self.validity {
+            Validity::Valid => Some(1.0),
+            Validity::Invalid => Some(0.0),
+            Validity::Unsupported => None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait CodeValidator: Send + Sync {
+    /// Canonical language name, e.g. `rust`
+    fn language(&self) -> &str;
+
+    async fn validate(&self, code: &str) -> ValidationReport;
+}
+
+pub struct RustValidator;
+
+#[async_trait]
+impl CodeValidator for RustValidator {
+    fn language(&self) -> &str {
+        "rust"
+    }
+
+    async fn validate(&self, code: &str) -> ValidationReport {
+        match syn::parse_file(code) {
+            Ok(_) => ValidationReport::new("rust", Validity::Valid, Vec::new()),
+            Err(e) => ValidationReport::new("rust", Validity::Invalid, vec![e.to_string()]),
+        }
+    }
+}
+
+pub struct PythonValidator;
+
+#[async_trait]
+impl CodeValidator for PythonValidator {
+    fn language(&self) -> &str {
+        "python"
+    }
+
+    async fn validate(&self, code: &str) -> ValidationReport {
+        let mut command = Command::new("python3");
+        command.args(["-c", "import ast, sys; ast.parse(sys.stdin.read(), '<generated>')"]);
+        check_with(command, "python", code).await
+    }
+}
+
+pub struct JavaScriptValidator;
+
+#[async_trait]
+impl CodeValidator for JavaScriptValidator {
+    fn language(&self) -> &str {
+        "javascript"
+    }
+
+    async fn validate(&self, code: &str) -> ValidationReport {
+        // With no file argument `node --check` reads the script from stdin
+        let mut command = Command::new("node");
+        command.arg("--check");
+        check_with(command, "javascript", code).await
+    }
+}
+
+/// Validator for `language`, accepting common aliases (`rs`, `py`, `js`)
+pub fn validator_for(language: &str) -> Option<Box<dyn CodeValidator>> {
+    match language.trim().to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(Box::new(RustValidator)),
+        "python" | "py" | "python3" => Some(Box::new(PythonValidator)),
+        "javascript" | "js" | "node" => Some(Box::new(JavaScriptValidator)),
+        _ => None,
+    }
+}
+
+/// Validate `code` with the validator for `language`
+pub async fn validate(language: &str, code: &str) -> ValidationReport {
+    match validator_for(language) {
+        Some(validator) => validator.validate(code).await,
+        None => ValidationReport::new(
+            language,
+            Validity::Unsupported,
+            vec![format!("ValidationUnsupported: no validator for language '{}'", language)],
+        ),
+    }
+}
+
+/// Feed `code` to a parser subprocess on stdin; a zero exit status means
+/// the code parsed and stderr becomes the notes otherwise. Stdin is written
+/// while the output is read, so a parser that fills its stderr pipe before
+/// draining stdin cannot deadlock us.
+async fn check_with(mut command: Command, language: &str, code: &str) -> ValidationReport {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let note = format!("ValidationUnsupported: could not start {:?}: {}", command.as_std().get_program(), e);
+            return ValidationReport::new(language, Validity::Unsupported, vec![note]);
+        }
+    };
+
+    let stdin = child.stdin.take();
+    let feed = async move {
+        if let Some(mut stdin) = stdin {
+            // A parser may exit before reading everything; its status still decides
+            let _ = stdin.write_all(code.as_bytes()).await;
+        }
+        // `stdin` drops here, closing the pipe so the parser sees EOF
+    };
+    let run = async {
+        let (_, output) = tokio::join!(feed, child.wait_with_output());
+        output
+    };
+
+    let output = match tokio::time::timeout(SUBPROCESS_TIMEOUT, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return ValidationReport::new(language, Validity::Unsupported, vec![format!("ValidationUnsupported: {}", e)]);
+        }
+        Err(_) => {
+            let note = format!("ValidationUnsupported: parser timed out after {:?}", SUBPROCESS_TIMEOUT);
+            return ValidationReport::new(language, Validity::Unsupported, vec![note]);
+        }
+    };
+
+    if output.status.success() {
+        ValidationReport::new(language, Validity::Valid, Vec::new())
+    } else {
+        let notes = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        ValidationReport::new(language, Validity::Invalid, notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rust_validator() {
+        assert!(validate("rust", "fn main() { let x = 1; }").await.is_valid());
+
+        let bad = validate("rs", "fn main( {").await;
+        assert_eq!(bad.validity, Validity::Invalid);
+        assert_eq!(bad.syntax_score(), Some(0.0));
+        assert!(!bad.validation_notes.is_empty());
+    }
+
+    /// Whether `program` can be started, printing why a test is skipped if not
+    fn installed(program: &str) -> bool {
+        let found = std::process::Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok();
+        if !found {
+            eprintln!("skipping: {} is not installed", program);
+        }
+        found
+    }
+
+    #[tokio::test]
+    async fn test_python_validator() {
+        if !installed("python3") {
+            return;
+        }
+        assert!(validate("python", "def f(x):\n    return x * 2\n").await.is_valid());
+        let bad = validate("py", "def f(:\n").await;
+        assert_eq!(bad.validity, Validity::Invalid);
+        assert!(bad.validation_notes.iter().any(|n| n.contains("SyntaxError")));
+    }
+
+    #[tokio::test]
+    async fn test_javascript_validator() {
+        if !installed("node") {
+            return;
+        }
+        assert!(validate("javascript", "const f = (x) => x * 2;").await.is_valid());
+        let bad = validate("js", "const = ;").await;
+        assert_eq!(bad.validity, Validity::Invalid);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_parser_filling_stderr_does_not_deadlock() {
+        // Fills the stderr pipe before reading stdin, which is larger than
+        // the stdin pipe buffer
+        let mut command = Command::new("sh");
+        command.args(["-c", "head -c 262144 /dev/zero >&2; cat > /dev/null"]);
+        let code = "x".repeat(1 << 20);
+
+        let report = check_with(command, "sh", &code).await;
+        assert_eq!(report.validity, Validity::Valid, "{:?}", report.validation_notes);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_language_is_unsupported() {
+        let report = validate("cobol", "DISPLAY 'HI'.").await;
+        assert_eq!(report.validity, Validity::Unsupported);
+        assert_eq!(report.syntax_score(), None);
+        assert!(report.validation_notes[0].starts_with("ValidationUnsupported"));
+    }
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use crate::ast::*;
+use crate::compiler::{CompiledProgram, Compiler};
 use crate::error::VMError;
+use crate::stdlib::{std_functions, NativeFn};
 
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -13,6 +16,48 @@ pub enum Value {
     String(String),
     Object(HashMap<String, Value>),
     Array(Vec<Value>),
+    Closure(Closure),
+}
+
+/// A function value together with the upvalues it captured when created.
+///
+/// Upvalues are copied out of the enclosing scope at creation; assignments
+/// inside the body update the closure's own copy, so state persists from
+/// one call to the next. Clones of the value share that copy.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub params: Vec<String>,
+    pub body: Arc<Vec<Statement>>,
+    pub env: Arc<Mutex<HashMap<String, Value>>>,
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => f.write_str(s),
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Value::Object(map) => {
+                let mut pairs: Vec<String> = map.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                pairs.sort();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Value::Closure(closure) => write!(f, "<fn({})>", closure.params.join(", ")),
+        }
+    }
+}
+
+impl Closure {
+    fn env(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Value>>, VMError> {
+        self.env
+            .lock()
+            .map_err(|_| VMError::RuntimeError("Closure environment poisoned".to_string()))
+    }
 }
 
 impl Default for Value {
@@ -41,6 +86,14 @@ pub struct VMContext {
     pub signals: Vec<(String, Option<Value>)>,
     pub stored: HashMap<String, Value>,
     pub reward: f64,
+    /// Most statements (and loop iterations) a run may execute; `None` is unbounded
+    pub step_limit: Option<u64>,
+    /// Steps executed so far against `step_limit`
+    pub steps: u64,
+    /// Functions implemented in Rust, looked up after closures in scope
+    pub natives: HashMap<String, NativeFn>,
+    /// Lines written by `print`
+    pub output: Vec<String>,
 }
 
 impl Default for VMContext {
@@ -56,6 +109,40 @@ impl VMContext {
             signals: Vec::new(),
             stored: HashMap::new(),
             reward: 0.0,
+            step_limit: None,
+            steps: 0,
+            natives: HashMap::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Register the standard library (see `stdlib`). `new()` alone leaves
+    /// scripts without it, for sandboxing.
+    pub fn with_std(mut self) -> Self {
+        for (name, native) in std_functions() {
+            self.natives.insert(name.to_string(), native);
+        }
+        self
+    }
+
+    pub fn with_native(mut self, name: impl Into<String>, native: NativeFn) -> Self {
+        self.natives.insert(name.into(), native);
+        self
+    }
+
+    /// Budget for untrusted scripts: execution stops with
+    /// `VMError::StepLimitExceeded` once `max_steps` have run
+    pub fn with_step_limit(mut self, max_steps: u64) -> Self {
+        self.step_limit = Some(max_steps);
+        self
+    }
+
+    /// Charge one step against the budget
+    fn step(&mut self) -> Result<(), VMError> {
+        self.steps += 1;
+        match self.step_limit {
+            Some(limit) if self.steps > limit => Err(VMError::StepLimitExceeded(limit)),
+            _ => Ok(()),
         }
     }
 }
@@ -86,6 +173,12 @@ impl VM {
         }
     }
 
+    /// Run a program cached with `CompiledProgram::to_bytes`
+    pub async fn load_compiled(&mut self, bytes: &[u8]) -> Result<Value, VMError> {
+        let program = CompiledProgram::from_bytes(bytes)?;
+        self.execute(&program.statements).await
+    }
+
     pub fn context(&self) -> &VMContext {
         &self.context
     }
@@ -118,6 +211,7 @@ impl VM {
         Self: Send,
     {
         Box::pin(async move {
+            self.context.step()?;
             match stmt {
                 Statement::Goal(goal) => self.execute_goal(goal).await,
                 Statement::On(handler) => self.execute_on_handler(handler).await,
@@ -125,8 +219,23 @@ impl VM {
                 Statement::Store(store) => self.execute_store(store).await,
                 Statement::Use(use_expr) => self.execute_use(use_expr).await,
                 Statement::If(if_expr) => self.execute_if(if_expr).await,
+                Statement::While(while_expr) => self.execute_while(while_expr).await,
                 Statement::Match(match_expr) => self.execute_match(match_expr).await,
                 Statement::Block(stmts) => self.execute(stmts).await,
+                Statement::Let(let_expr) => {
+                    let value = self.eval_expr(&let_expr.value).await?;
+                    self.context.variables.insert(let_expr.name.clone(), value);
+                    Ok(Value::Null)
+                }
+                Statement::Assign(assign) => {
+                    if !self.context.variables.contains_key(&assign.name) {
+                        return Err(VMError::UndefinedVariable(assign.name.clone()));
+                    }
+                    let value = self.eval_expr(&assign.value).await?;
+                    self.context.variables.insert(assign.name.clone(), value);
+                    Ok(Value::Null)
+                }
+                Statement::Expr(expr) => self.eval_expr(expr).await,
             }
         })
     }
@@ -195,16 +304,9 @@ impl VM {
     }
 
     async fn execute_if(&mut self, if_expr: &IfExpr) -> Result<Value, VMError> {
-        let condition = self.eval_expr(&if_expr.condition)?;
-        let is_truthy = match condition {
-            Value::Bool(b) => b,
-            Value::Null => false,
-            Value::Number(n) => n != 0.0,
-            Value::String(s) => !s.is_empty(),
-            _ => true,
-        };
+        let condition = self.eval_expr(&if_expr.condition).await?;
 
-        if is_truthy {
+        if Self::is_truthy(&condition) {
             self.execute(&if_expr.then_branch).await
         } else if let Some(else_branch) = &if_expr.else_branch {
             self.execute(else_branch).await
@@ -213,11 +315,33 @@ impl VM {
         }
     }
 
+    async fn execute_while(&mut self, while_expr: &WhileExpr) -> Result<Value, VMError> {
+        loop {
+            let condition = self.eval_expr(&while_expr.condition).await?;
+            if !Self::is_truthy(&condition) {
+                return Ok(Value::Null);
+            }
+            // Charged per iteration so an empty body still exhausts the budget
+            self.context.step()?;
+            self.execute(&while_expr.body).await?;
+        }
+    }
+
+    fn is_truthy(value: &Value) -> bool {
+        match value {
+            Value::Bool(b) => *b,
+            Value::Null => false,
+            Value::Number(n) => *n != 0.0,
+            Value::String(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+
     async fn execute_match(&mut self, match_expr: &MatchExpr) -> Result<Value, VMError> {
-        let value = self.eval_expr(&match_expr.value)?;
+        let value = self.eval_expr(&match_expr.value).await?;
 
         for arm in &match_expr.arms {
-            let pattern = self.eval_expr(&arm.pattern)?;
+            let pattern = self.eval_expr(&arm.pattern).await?;
             if self.values_equal(&value, &pattern) {
                 return self.execute(&arm.body).await;
             }
@@ -226,38 +350,114 @@ impl VM {
         Ok(Value::Null)
     }
 
-    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, VMError> {
-        match expr {
-            Expr::String(s) => Ok(Value::String(s.clone())),
-            Expr::Number(n) => Ok(Value::Number(*n)),
-            Expr::Bool(b) => Ok(Value::Bool(*b)),
-            Expr::Ident(name) => self
-                .context
-                .variables
-                .get(name)
-                .cloned()
-                .ok_or_else(|| VMError::UndefinedVariable(name.clone())),
-            Expr::Call { func, args } => self.eval_call(func, args),
-            Expr::Object(pairs) => {
-                let mut map = HashMap::new();
-                for (k, v) in pairs {
-                    map.insert(k.clone(), self.eval_expr(v)?);
+    fn eval_expr<'a>(
+        &'a mut self,
+        expr: &'a Expr,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, VMError>> + Send + 'a>>
+    where
+        Self: Send,
+    {
+        Box::pin(async move {
+            match expr {
+                Expr::String(s) => Ok(Value::String(s.clone())),
+                Expr::Number(n) => Ok(Value::Number(*n)),
+                Expr::Bool(b) => Ok(Value::Bool(*b)),
+                Expr::Ident(name) => self
+                    .context
+                    .variables
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| VMError::UndefinedVariable(name.clone())),
+                Expr::Call { func, args } => self.eval_call(func, args).await,
+                Expr::Object(pairs) => {
+                    let mut map = HashMap::new();
+                    for (k, v) in pairs {
+                        map.insert(k.clone(), self.eval_expr(v).await?);
+                    }
+                    Ok(Value::Object(map))
+                }
+                Expr::Array(items) => {
+                    let mut values = Vec::with_capacity(items.len());
+                    for item in items {
+                        values.push(self.eval_expr(item).await?);
+                    }
+                    Ok(Value::Array(values))
+                }
+                Expr::Binary { left, op, right } => {
+                    let l = self.eval_expr(left).await?;
+                    let r = self.eval_expr(right).await?;
+                    self.eval_binary_op(&l, op, &r)
+                }
+                Expr::Closure { params, body } => {
+                    // Capture by value whichever upvalues are in scope now
+                    let env = Compiler::upvalues(params, body)
+                        .into_iter()
+                        .filter_map(|name| {
+                            let value = self.context.variables.get(&name)?.clone();
+                            Some((name, value))
+                        })
+                        .collect();
+                    Ok(Value::Closure(Closure {
+                        params: params.clone(),
+                        body: Arc::new(body.clone()),
+                        env: Arc::new(Mutex::new(env)),
+                    }))
                 }
-                Ok(Value::Object(map))
-            }
-            Expr::Array(items) => {
-                let values: Result<Vec<_>, _> = items.iter().map(|e| self.eval_expr(e)).collect();
-                Ok(Value::Array(values?))
             }
-            Expr::Binary { left, op, right } => {
-                let l = self.eval_expr(left)?;
-                let r = self.eval_expr(right)?;
-                self.eval_binary_op(&l, op, &r)
+        })
+    }
+
+    /// Run a closure body in a scope made of its upvalues and arguments,
+    /// then write the upvalues back so the next call sees any updates
+    pub(crate) async fn call_closure_with(
+        &mut self,
+        closure: &Closure,
+        args: Vec<Value>,
+    ) -> Result<Value, VMError> {
+        if args.len() != closure.params.len() {
+            return Err(VMError::TypeError(format!(
+                "Closure takes {} arguments, got {}",
+                closure.params.len(),
+                args.len()
+            )));
+        }
+        let mut scope = closure.env()?.clone();
+        scope.extend(closure.params.iter().cloned().zip(args));
+
+        let outer = std::mem::replace(&mut self.context.variables, scope);
+        let result = self.execute(&closure.body).await;
+        let scope = std::mem::replace(&mut self.context.variables, outer);
+
+        for (name, value) in closure.env()?.iter_mut() {
+            if let Some(updated) = scope.get(name) {
+                *value = updated.clone();
             }
         }
+        result
     }
 
-    fn eval_call(&mut self, func: &str, args: &[Expr]) -> Result<Value, VMError> {
+    async fn eval_args(&mut self, args: &[Expr]) -> Result<Vec<Value>, VMError> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.eval_expr(arg).await?);
+        }
+        Ok(values)
+    }
+
+    async fn eval_call(&mut self, func: &str, args: &[Expr]) -> Result<Value, VMError> {
+        if let Some(Value::Closure(closure)) = self.context.variables.get(func) {
+            let closure = closure.clone();
+            let args = self.eval_args(args).await?;
+            return self.call_closure_with(&closure, args).await;
+        }
+        if let Some(native) = self.context.natives.get(func).copied() {
+            let args = self.eval_args(args).await?;
+            return match native {
+                NativeFn::Pure(f) => f(&args),
+                NativeFn::Vm(f) => f(self, args).await,
+            };
+        }
+
         match func {
             "adjust_reward" => {
                 if let Some(Expr::Number(n)) = args.first() {
@@ -271,8 +471,12 @@ impl VM {
                 }
                 Ok(Value::Null)
             }
+            // Neither a closure nor a native: `name(args)` emits the signal `name`
             _ => {
-                tracing::warn!("Unknown function: {}", func);
+                let args = self.eval_args(args).await?;
+                let payload = (!args.is_empty()).then_some(Value::Array(args));
+                tracing::debug!("Emitting signal: {}", func);
+                self.context.signals.push((func.to_string(), payload));
                 Ok(Value::Null)
             }
         }
@@ -336,4 +540,143 @@ mod tests {
         assert_eq!(vm.context.signals.len(), 1);
         assert_eq!(vm.context.signals[0].0, "test_signal");
     }
+
+    #[tokio::test]
+    async fn test_compiled_program_roundtrip() {
+        use crate::optimize::OptLevel;
+
+        let input = r#"
+            let total = 0;
+            let add = fn(x) { total = total + x; total };
+            let a = add(2 * 3);
+            if 1 < 2 { emit "small"; } else { emit "large"; }
+            store "sum", 42;
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+        let bytes = Compiler::new()
+            .with_optimizations(OptLevel::Full)
+            .compile_program(&stmts)
+            .to_bytes()
+            .unwrap();
+
+        let mut direct = VM::new();
+        let mut cached = VM::new();
+        direct.execute(&stmts).await.unwrap();
+        cached.load_compiled(&bytes).await.unwrap();
+
+        let render = |vm: &VM| {
+            let mut vars: Vec<String> = vm
+                .context
+                .variables
+                .iter()
+                .filter(|(_, v)| !matches!(v, Value::Closure(_)))
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            vars.sort();
+            let signals: Vec<&str> = vm.context.signals.iter().map(|(s, _)| s.as_str()).collect();
+            (vars, signals.join(","), vm.context.stored["sum"].to_string())
+        };
+        assert_eq!(render(&direct), render(&cached));
+        assert_eq!(render(&cached).1, "small");
+    }
+
+    #[tokio::test]
+    async fn test_step_limit_stops_infinite_loop() {
+        let stmts = Parser::new("while true {}").parse().unwrap();
+
+        let mut vm = VM::with_context(VMContext::new().with_step_limit(1_000));
+        let result = vm.execute(&stmts).await;
+        assert!(matches!(result, Err(VMError::StepLimitExceeded(1_000))));
+        assert_eq!(vm.context.steps, 1_001);
+    }
+
+    #[tokio::test]
+    async fn test_step_limit_allows_bounded_loop() {
+        let input = r#"
+            let i = 0;
+            while i < 10 { i = i + 1; }
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+
+        // 2 top-level statements, then 10 iterations charging the loop and its body
+        let mut vm = VM::with_context(VMContext::new().with_step_limit(22));
+        vm.execute(&stmts).await.unwrap();
+        assert!(matches!(vm.context.variables.get("i"), Some(Value::Number(n)) if *n == 10.0));
+
+        let mut vm = VM::with_context(VMContext::new().with_step_limit(21));
+        assert!(matches!(vm.execute(&stmts).await, Err(VMError::StepLimitExceeded(21))));
+    }
+
+    #[tokio::test]
+    async fn test_counter_closure_retains_state() {
+        let input = r#"
+            let make_counter = fn(step) {
+                let count = 0;
+                fn() {
+                    count = count + step;
+                    count
+                }
+            };
+            let counter = make_counter(2);
+            let other = make_counter(10);
+            let a = counter();
+            let b = counter();
+            let c = other();
+            let d = counter();
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+
+        let mut vm = VM::new();
+        vm.execute(&stmts).await.unwrap();
+
+        let number = |name: &str| match vm.context.variables.get(name) {
+            Some(Value::Number(n)) => *n,
+            other => panic!("{} = {:?}", name, other),
+        };
+        assert_eq!(number("a"), 2.0);
+        assert_eq!(number("b"), 4.0);
+        assert_eq!(number("c"), 10.0);
+        assert_eq!(number("d"), 6.0);
+        // Locals of the closure body do not leak into the caller's scope
+        assert!(!vm.context.variables.contains_key("count"));
+    }
+
+    #[tokio::test]
+    async fn test_closure_call_as_statement_updates_state() {
+        let input = r#"
+            let make_counter = fn() {
+                let count = 0;
+                fn() {
+                    count = count + 1;
+                    count
+                }
+            };
+            let counter = make_counter();
+            counter();
+            counter();
+            let n = counter();
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+
+        let mut vm = VM::new();
+        vm.execute(&stmts).await.unwrap();
+        assert!(matches!(vm.context.variables.get("n"), Some(Value::Number(n)) if *n == 3.0));
+        assert!(vm.context.signals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_closure_captures_by_value() {
+        let input = r#"
+            let base = 1;
+            let add_base = fn(x) { x + base };
+            base = 100;
+            let r = add_base(5);
+        "#;
+        let stmts = Parser::new(input).parse().unwrap();
+
+        let mut vm = VM::new();
+        vm.execute(&stmts).await.unwrap();
+        assert!(matches!(vm.context.variables.get("r"), Some(Value::Number(n)) if *n == 6.0));
+        assert!(matches!(vm.context.variables.get("base"), Some(Value::Number(n)) if *n == 100.0));
+    }
 }
@@ -0,0 +1,156 @@
+//! C ABI for running scripts from a WASM host
+//!
+//! JavaScript drives it through the module's linear memory:
+//!
+//! ```js
+//! const bytes = new TextEncoder().encode(source);
+//! const src = exports.lang_alloc(bytes.length);
+//! new Uint8Array(exports.memory.buffer, src, bytes.length).set(bytes);
+//! const out = exports.lang_eval(src, bytes.length);
+//! exports.lang_dealloc(src, bytes.length);
+//! // read the NUL-terminated JSON at `out`, then
+//! exports.lang_free_result(out);
+//! ```
+//!
+//! The result is `{"value": ..., "steps": n}` on success and
+//! `{"error": "..."}` otherwise.
+
+use std::ffi::{c_char, CString};
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+
+use serde_json::json;
+
+use crate::compiler::Compiler;
+use crate::error::VMError;
+use crate::parser::Parser;
+use crate::vm::{VMContext, Value, VM};
+
+/// Most steps one `lang_eval` may run, so a runaway script cannot hang the page
+pub const EVAL_STEP_LIMIT: u64 = 1_000_000;
+
+/// Poll `fut` to completion on the calling thread. The VM never waits on
+/// I/O, so its futures are always ready when polled.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = std::pin::pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(b) => json!(b),
+        // NaN and infinities have no JSON form
+        Value::Number(n) => serde_json::Number::from_f64(*n).map_or(serde_json::Value::Null, Into::into),
+        Value::String(s) => json!(s),
+        Value::Array(items) => items.iter().map(to_json).collect(),
+        Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), to_json(v))).collect(),
+        Value::Closure(_) => json!(value.to_string()),
+    }
+}
+
+/// Parse, compile and run `source` with the standard library under
+/// `EVAL_STEP_LIMIT`, returning the value of the last statement
+pub fn eval(source: &str) -> serde_json::Value {
+    let ast = match Parser::new(source).parse() {
+        Ok(ast) => ast,
+        Err(e) => return json!({ "error": e.to_string() }),
+    };
+    let program = Compiler::new().compile_program(&ast);
+
+    let mut vm = VM::with_context(VMContext::new().with_std().with_step_limit(EVAL_STEP_LIMIT));
+    let result: Result<Value, VMError> = block_on(vm.execute(&program.statements));
+    match result {
+        Ok(value) => json!({ "value": to_json(&value), "steps": vm.context().steps }),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+}
+
+/// Reserve `len` bytes for the host to write a script into
+#[no_mangle]
+pub extern "C" fn lang_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Release a buffer from `lang_alloc`; `len` must match the allocation
+///
+/// # Safety
+/// `ptr` must come from `lang_alloc(len)` and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn lang_dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Vec::from_raw_parts(ptr, 0, len));
+    }
+}
+
+/// Run the UTF-8 script at `source_ptr` (caller must free the result with
+/// `lang_free_result`)
+///
+/// # Safety
+/// `source_ptr` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn lang_eval(source_ptr: *const u8, len: usize) -> *mut c_char {
+    let result = if source_ptr.is_null() {
+        json!({ "error": "null source" })
+    } else {
+        match std::str::from_utf8(std::slice::from_raw_parts(source_ptr, len)) {
+            Ok(source) => eval(source),
+            Err(e) => json!({ "error": format!("source is not UTF-8: {}", e) }),
+        }
+    };
+    // serde_json escapes control characters, so the output has no interior NUL
+    CString::new(result.to_string()).unwrap_or_default().into_raw()
+}
+
+/// Free a string returned by `lang_eval`
+///
+/// # Safety
+/// `ptr` must come from `lang_eval` and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn lang_free_result(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn eval_through_abi(source: &str) -> serde_json::Value {
+        unsafe {
+            let src = lang_alloc(source.len());
+            std::ptr::copy_nonoverlapping(source.as_ptr(), src, source.len());
+            let out = lang_eval(src, source.len());
+            lang_dealloc(src, source.len());
+
+            let json = serde_json::from_str(CStr::from_ptr(out).to_str().unwrap()).unwrap();
+            lang_free_result(out);
+            json
+        }
+    }
+
+    #[test]
+    fn test_eval_returns_json_value_and_stops_runaway_loops() {
+        let result = eval_through_abi("let n = len([1, 2, 3]); n * 2;");
+        assert_eq!(result["value"], json!(6.0));
+        assert!(result["steps"].as_u64().unwrap() > 0);
+
+        let result = eval_through_abi("while true {}");
+        assert_eq!(
+            result["error"],
+            json!(VMError::StepLimitExceeded(EVAL_STEP_LIMIT).to_string())
+        );
+
+        assert!(eval_through_abi("let = ;")["error"].is_string());
+    }
+}
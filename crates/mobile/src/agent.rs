@@ -0,0 +1,395 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use cortex_storage::PrivacyLevel;
+use uuid::Uuid;
+
+use crate::registry::AgentError;
+
+/// Upper bound on the characters of conversation context kept per agent
+pub const CONTEXT_CHAR_BUDGET: usize = 8_000;
+
+/// A model an inference agent can run, supplied by the host platform
+pub trait InferenceEngine: Send + Sync {
+    /// Shown in agent listings as `inference (<label>)`
+    fn label(&self) -> String;
+
+    /// Prefix of formatted responses, e.g. `🦙 [name]`
+    fn tag(&self, agent_name: &str) -> String {
+        format!("🤖 [{}]", agent_name)
+    }
+
+    /// Answer `input`, given the earlier `(role, content)` turns. `Err`
+    /// carries the reason the model could not answer.
+    fn generate(&self, context: &[(String, String)], input: &str) -> Result<String, String>;
+}
+
+/// An engine and the context to answer with, from `RealAgent::engine_snapshot`
+pub type EngineSnapshot = (Arc<dyn InferenceEngine>, Vec<(String, String)>);
+
+#[derive(Clone)]
+pub enum InferenceBackend {
+    /// Built-in rules, available everywhere
+    LocalRuleBased,
+    Engine(Arc<dyn InferenceEngine>),
+}
+
+impl fmt::Debug for InferenceBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LocalRuleBased => f.write_str("LocalRuleBased"),
+            Self::Engine(engine) => f.debug_tuple("Engine").field(&engine.label()).finish(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum AgentType {
+    Heartbeat { interval_secs: u64 },
+    Logger,
+    Inference(InferenceBackend),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AgentStatus {
+    Running,
+    Stopped,
+}
+
+/// One input/output exchange recorded for dataset export
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub input: String,
+    pub output: String,
+    pub privacy: PrivacyLevel,
+}
+
+#[derive(Clone, Debug)]
+pub struct RealAgent {
+    pub id: String,
+    pub name: String,
+    pub agent_type: AgentType,
+    pub status: AgentStatus,
+    pub created_at: Instant,
+    pub events_processed: u32,
+    pub history: Vec<HistoryEntry>,
+    /// Rolling `(role, content)` conversation sent with each inference
+    pub context: Vec<(String, String)>,
+    /// Privacy level stamped on new history entries
+    pub privacy: PrivacyLevel,
+}
+
+impl RealAgent {
+    pub fn new(name: String, agent_type: AgentType) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string()[..8].to_string(),
+            name,
+            agent_type,
+            status: AgentStatus::Running,
+            created_at: Instant::now(),
+            events_processed: 0,
+            history: Vec::new(),
+            context: Vec::new(),
+            privacy: PrivacyLevel::Private,
+        }
+    }
+
+    pub fn new_heartbeat(name: String, interval_secs: u64) -> Self {
+        Self::new(name, AgentType::Heartbeat { interval_secs })
+    }
+
+    pub fn new_logger(name: String) -> Self {
+        Self::new(name, AgentType::Logger)
+    }
+
+    pub fn new_inference_local(name: String) -> Self {
+        Self::new(name, AgentType::Inference(InferenceBackend::LocalRuleBased))
+    }
+
+    pub fn new_inference(name: String, engine: Arc<dyn InferenceEngine>) -> Self {
+        Self::new(name, AgentType::Inference(InferenceBackend::Engine(engine)))
+    }
+
+    pub fn type_name(&self) -> String {
+        match &self.agent_type {
+            AgentType::Heartbeat { .. } => "heartbeat".to_string(),
+            AgentType::Logger => "logger".to_string(),
+            AgentType::Inference(InferenceBackend::LocalRuleBased) => {
+                "inference (local)".to_string()
+            }
+            AgentType::Inference(InferenceBackend::Engine(engine)) => {
+                format!("inference ({})", engine.label())
+            }
+        }
+    }
+
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            AgentStatus::Running => "running",
+            AgentStatus::Stopped => "stopped",
+        }
+    }
+
+    /// Forget the conversation so far; the next message starts fresh
+    pub fn clear_context(&mut self) {
+        self.context.clear();
+    }
+
+    /// Process an incoming event - returns response if any. A failed
+    /// inference is returned as an error and leaves the conversation
+    /// context and history untouched.
+    pub fn on_event(&mut self, event: &str) -> Result<Option<String>, AgentError> {
+        self.events_processed += 1;
+
+        match &self.agent_type {
+            AgentType::Logger => Ok(Some(format!("📝 [{}] Logged: {}", self.name, event))),
+            AgentType::Inference(InferenceBackend::LocalRuleBased) => {
+                let raw = self.run_local_rules_raw(event);
+                let formatted = format!("🤖 [{}]: {}", self.name, raw);
+                self.record(event, raw);
+                Ok(Some(formatted))
+            }
+            AgentType::Inference(InferenceBackend::Engine(engine)) => {
+                let engine = Arc::clone(engine);
+                let reply = engine.generate(&self.context, event);
+                self.engine_reply(engine.as_ref(), event, reply)
+            }
+            AgentType::Heartbeat { .. } => Ok(None),
+        }
+    }
+
+    /// The engine and a copy of the context for the next reply, so the
+    /// model can run without holding a lock on the agent. `None` for agents
+    /// that do not use an engine; send those events to `on_event`.
+    pub fn engine_snapshot(&self) -> Option<EngineSnapshot> {
+        match &self.agent_type {
+            AgentType::Inference(InferenceBackend::Engine(engine)) => {
+                Some((Arc::clone(engine), self.context.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Finish an event whose reply was generated from `engine_snapshot`,
+    /// exactly as `on_event` would have
+    pub fn apply_reply(
+        &mut self,
+        engine: &dyn InferenceEngine,
+        event: &str,
+        reply: Result<String, String>,
+    ) -> Result<Option<String>, AgentError> {
+        self.events_processed += 1;
+        self.engine_reply(engine, event, reply)
+    }
+
+    fn engine_reply(
+        &mut self,
+        engine: &dyn InferenceEngine,
+        event: &str,
+        reply: Result<String, String>,
+    ) -> Result<Option<String>, AgentError> {
+        let raw = reply.map_err(|reason| AgentError::Inference(self.id.clone(), reason))?;
+        let formatted = format!("{}: {}", engine.tag(&self.name), raw);
+        self.context.push(("user".to_string(), event.to_string()));
+        self.context.push(("assistant".to_string(), raw.clone()));
+        trim_context(&mut self.context, CONTEXT_CHAR_BUDGET);
+        self.record(event, raw);
+        Ok(Some(formatted))
+    }
+
+    fn record(&mut self, input: &str, output: String) {
+        self.history.push(HistoryEntry {
+            input: input.to_string(),
+            output,
+            privacy: self.privacy,
+        });
+    }
+
+    fn run_local_rules_raw(&self, input: &str) -> String {
+        let input_lower = input.to_lowercase();
+
+        if input_lower.contains("hello")
+            || input_lower.contains("hi")
+            || input_lower.contains("olá")
+        {
+            return "Olá! Sou um agente de IA do CortexOS rodando localmente.".to_string();
+        }
+
+        if input_lower.contains("quem") || input_lower.contains("who are you") {
+            return "Sou um agente de inferência do CortexOS.".to_string();
+        }
+
+        if input_lower.contains("help") || input_lower.contains("ajuda") {
+            return "Posso ajudar com: saudações, matemática (2+2), echo, tempo, e análise de texto.".to_string();
+        }
+
+        if input_lower.contains("time") || input_lower.contains("tempo") {
+            let uptime = self.created_at.elapsed().as_secs();
+            return format!(
+                "Estou rodando há {}s. Processei {} eventos.",
+                uptime, self.events_processed
+            );
+        }
+
+        if input_lower.starts_with("echo ") {
+            return input[5..].to_string();
+        }
+
+        if let Some(result) = try_math(input) {
+            return format!("= {}", result);
+        }
+
+        if input_lower.contains("cortex") {
+            return "CortexOS é um sistema operacional cognitivo distribuído.".to_string();
+        }
+
+        let words = input.split_whitespace().count();
+        let chars = input.chars().count();
+        format!(
+            "Analisei sua mensagem: {} palavras, {} caracteres.",
+            words, chars
+        )
+    }
+}
+
+fn try_math(input: &str) -> Option<f64> {
+    let clean = input.replace(' ', "");
+    for op in ['+', '-', '*', '/'] {
+        if let Some(pos) = clean.find(op) {
+            if pos > 0 && pos < clean.len() - 1 {
+                let a: f64 = clean[..pos].parse().ok()?;
+                let b: f64 = clean[pos + 1..].parse().ok()?;
+                return match op {
+                    '+' => Some(a + b),
+                    '-' => Some(a - b),
+                    '*' => Some(a * b),
+                    '/' if b != 0.0 => Some(a / b),
+                    _ => None,
+                };
+            }
+        }
+    }
+    None
+}
+
+/// Flatten a conversation into a prompt for backends that take plain text.
+/// Without prior turns this is just `input`.
+pub fn render_transcript(context: &[(String, String)], input: &str) -> String {
+    if context.is_empty() {
+        return input.to_string();
+    }
+    let mut prompt = String::new();
+    for (role, content) in context {
+        let speaker = if role == "assistant" {
+            "Assistant"
+        } else {
+            "User"
+        };
+        prompt.push_str(&format!("{}: {}\n", speaker, content));
+    }
+    prompt.push_str(&format!("User: {}\nAssistant:", input));
+    prompt
+}
+
+/// Drop the oldest user/assistant exchanges until the context fits in
+/// `budget` characters. The latest exchange is always kept.
+pub fn trim_context(context: &mut Vec<(String, String)>, budget: usize) {
+    let size = |c: &[(String, String)]| c.iter().map(|(_, content)| content.len()).sum::<usize>();
+    while context.len() > 2 && size(context) > budget {
+        context.drain(..2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl InferenceEngine for Echo {
+        fn label(&self) -> String {
+            "echo".to_string()
+        }
+
+        fn generate(&self, context: &[(String, String)], input: &str) -> Result<String, String> {
+            if input == "fail" {
+                return Err("model offline".to_string());
+            }
+            Ok(format!("{} after {} turns", input, context.len()))
+        }
+    }
+
+    #[test]
+    fn test_engine_agent_keeps_context() {
+        let mut agent = RealAgent::new_inference("e".to_string(), Arc::new(Echo));
+        assert_eq!(agent.type_name(), "inference (echo)");
+
+        assert_eq!(agent.on_event("a").unwrap().unwrap(), "🤖 [e]: a after 0 turns");
+        assert_eq!(agent.on_event("b").unwrap().unwrap(), "🤖 [e]: b after 2 turns");
+        assert_eq!(agent.history.len(), 2);
+
+        // A failure is returned, not remembered as the assistant's turn
+        assert_eq!(
+            agent.on_event("fail"),
+            Err(AgentError::Inference(agent.id.clone(), "model offline".to_string()))
+        );
+        assert_eq!(agent.context.len(), 4);
+        assert_eq!(agent.history.len(), 2);
+        assert_eq!(agent.on_event("d").unwrap().unwrap(), "🤖 [e]: d after 4 turns");
+
+        agent.clear_context();
+        assert_eq!(agent.on_event("c").unwrap().unwrap(), "🤖 [e]: c after 0 turns");
+
+        // Generating from a snapshot ends in the same state
+        let (engine, context) = agent.engine_snapshot().unwrap();
+        let reply = engine.generate(&context, "e");
+        assert_eq!(
+            agent.apply_reply(engine.as_ref(), "e", reply).unwrap().unwrap(),
+            "🤖 [e]: e after 2 turns"
+        );
+        assert_eq!(agent.context.len(), 4);
+        assert_eq!(agent.events_processed, 6);
+        assert!(RealAgent::new_logger("l".to_string()).engine_snapshot().is_none());
+    }
+
+    #[test]
+    fn test_local_rules() {
+        let mut agent = RealAgent::new_inference_local("local".to_string());
+        assert_eq!(agent.on_event("2 + 3").unwrap().unwrap(), "🤖 [local]: = 5");
+        // Rule answers do not depend on earlier turns
+        assert!(agent.context.is_empty());
+        assert_eq!(agent.history[0].output, "= 5");
+
+        assert!(RealAgent::new_heartbeat("beat".to_string(), 1)
+            .on_event("tick")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_transcript_and_trimming() {
+        assert_eq!(render_transcript(&[], "hello"), "hello");
+        let context = vec![
+            ("user".to_string(), "my name is Ada".to_string()),
+            ("assistant".to_string(), "Hi Ada".to_string()),
+        ];
+        assert_eq!(
+            render_transcript(&context, "what is my name?"),
+            "User: my name is Ada\nAssistant: Hi Ada\nUser: what is my name?\nAssistant:"
+        );
+
+        let mut long = Vec::new();
+        for i in 0..10 {
+            long.push((
+                "user".to_string(),
+                format!("question {} {}", i, "x".repeat(100)),
+            ));
+            long.push(("assistant".to_string(), format!("answer {}", i)));
+        }
+        trim_context(&mut long, 300);
+        assert_eq!(long.len(), 4);
+        assert_eq!(long[0].0, "user");
+        assert!(long[0].1.starts_with("question 8"));
+    }
+}
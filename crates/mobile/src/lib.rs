@@ -0,0 +1,15 @@
+//! Agents hosted by the mobile FFI libraries
+//!
+//! The iOS and Android libraries keep the same in-process agents: a
+//! registry of `RealAgent`s that messages are delivered to synchronously.
+//! Backends that only one platform has (CoreML, a bundled model, remote
+//! servers) plug in as an `InferenceEngine`.
+
+pub mod agent;
+pub mod registry;
+
+pub use agent::{
+    render_transcript, trim_context, AgentStatus, AgentType, EngineSnapshot, HistoryEntry,
+    InferenceBackend, InferenceEngine, RealAgent, CONTEXT_CHAR_BUDGET,
+};
+pub use registry::{AgentError, AgentRegistry};
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::agent::{AgentStatus, RealAgent};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AgentError {
+    #[error("Agent {0} not found")]
+    NotFound(String),
+
+    #[error("Agent {0} is stopped")]
+    Stopped(String),
+
+    #[error("Agent {0} inference failed: {1}")]
+    Inference(String, String),
+}
+
+/// The agents one FFI library hosts, keyed by id
+#[derive(Default)]
+pub struct AgentRegistry {
+    agents: HashMap<String, RealAgent>,
+}
+
+impl AgentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `agent`, returning its id
+    pub fn insert(&mut self, agent: RealAgent) -> String {
+        let id = agent.id.clone();
+        self.agents.insert(id.clone(), agent);
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RealAgent> {
+        self.agents.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut RealAgent> {
+        self.agents.get_mut(id)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<RealAgent> {
+        self.agents.remove(id)
+    }
+
+    /// Stop `id`; it stays listed but no longer takes messages
+    pub fn stop(&mut self, id: &str) -> Result<(), AgentError> {
+        let agent = self
+            .get_mut(id)
+            .ok_or_else(|| AgentError::NotFound(id.to_string()))?;
+        agent.status = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    /// `id`, if it exists and is still taking messages
+    pub fn running_mut(&mut self, id: &str) -> Result<&mut RealAgent, AgentError> {
+        let agent = self
+            .get_mut(id)
+            .ok_or_else(|| AgentError::NotFound(id.to_string()))?;
+        if agent.status != AgentStatus::Running {
+            return Err(AgentError::Stopped(id.to_string()));
+        }
+        Ok(agent)
+    }
+
+    /// Deliver `message` to a running agent, returning its response if any
+    pub fn send(&mut self, id: &str, message: &str) -> Result<Option<String>, AgentError> {
+        self.running_mut(id)?.on_event(message)
+    }
+
+    /// Deliver `payload` to every running agent and collect the responses.
+    /// An agent that fails contributes its error message instead.
+    pub fn publish(&mut self, payload: &str) -> Vec<String> {
+        self.agents
+            .values_mut()
+            .filter(|agent| agent.status == AgentStatus::Running)
+            .filter_map(|agent| agent.on_event(payload).unwrap_or_else(|e| Some(e.to_string())))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &RealAgent> {
+        self.agents.values()
+    }
+
+    /// Every agent as a JSON array of `{id, name, type, status, events}`
+    pub fn list_json(&self) -> String {
+        let agents: Vec<serde_json::Value> = self
+            .values()
+            .map(|a| {
+                serde_json::json!({
+                    "id": a.id,
+                    "name": a.name,
+                    "type": a.type_name(),
+                    "status": a.status_name(),
+                    "events": a.events_processed,
+                })
+            })
+            .collect();
+        serde_json::Value::from(agents).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_respects_status() {
+        let mut registry = AgentRegistry::new();
+        let id = registry.insert(RealAgent::new_logger("log".to_string()));
+
+        assert_eq!(
+            registry.send(&id, "hi").unwrap().unwrap(),
+            "📝 [log] Logged: hi"
+        );
+        assert_eq!(
+            registry.send("nope", "hi"),
+            Err(AgentError::NotFound("nope".to_string()))
+        );
+
+        registry.stop(&id).unwrap();
+        assert_eq!(
+            registry.send(&id, "hi"),
+            Err(AgentError::Stopped(id.clone()))
+        );
+        assert!(registry.publish("hi").is_empty());
+
+        let listed: serde_json::Value = serde_json::from_str(&registry.list_json()).unwrap();
+        assert_eq!(listed[0]["status"], "stopped");
+        assert_eq!(listed[0]["events"], 1);
+
+        assert!(registry.remove(&id).is_some());
+        assert!(registry.is_empty());
+    }
+}
@@ -1,48 +1,254 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
 
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item};
+
+use cortex_grid::{NodeId, PeerFilter};
+
+use crate::control::CONTROL_PORT_OFFSET;
+use crate::identity::NodeIdentity;
+
+/// Config file looked for in the working directory and the user config dir
+pub const CONFIG_FILE_NAME: &str = "cortexd.toml";
+
+const KNOWN_KEYS: &[&str] = &[
+    "name",
+    "port",
+    "data_dir",
+    "skills",
+    "bootstrap",
+    "allow_peers",
+    "block_peers",
+    "kademlia",
+    "orchestrator",
+    "compute",
+];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("invalid TOML in {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("unknown key `{key}` in {path} (expected one of: {})", KNOWN_KEYS.join(", "))]
+    UnknownKey { path: PathBuf, key: String },
+
+    #[error("`{key}` in {path} must be {expected}")]
+    InvalidValue { path: PathBuf, key: String, expected: &'static str },
+
+    #[error("`allow_peers` and `block_peers` cannot both be set in {path}")]
+    ConflictingPeerFilter { path: PathBuf },
+}
+
+#[derive(Debug, Clone)]
 pub struct NodeConfig {
     pub name: String,
     pub port: u16,
     pub data_dir: PathBuf,
     pub skills: Vec<String>,
+    /// Kademlia bootstrap peers as multiaddrs
+    pub bootstrap: Vec<String>,
+    /// Peers accepted from discovery and chosen for delegated tasks
+    pub peer_filter: PeerFilter,
     pub enable_kademlia: bool,
     pub enable_orchestrator: bool,
     pub can_compute: bool,
+    /// Replace the stored identity with a freshly generated one
+    pub regenerate_identity: bool,
 }
 
-impl NodeConfig {
-    pub fn new(
-        name: Option<String>,
-        port: u16,
-        data_dir: Option<String>,
-        skills: Option<String>,
-    ) -> Self {
-        let name = name.unwrap_or_else(|| {
-            std::env::var("HOSTNAME")
-                .or_else(|_| std::env::var("COMPUTERNAME"))
-                .unwrap_or_else(|_| format!("cortex-{}", &uuid::Uuid::new_v4().to_string()[..8]))
-        });
-
-        let data_dir = data_dir
-            .map(PathBuf::from)
-            .unwrap_or_else(|| {
-                directories::ProjectDirs::from("com", "cortexos", "cortexd")
-                    .map(|d| d.data_dir().to_path_buf())
-                    .unwrap_or_else(|| PathBuf::from(".cortexos"))
-            });
-
-        let skills = skills
-            .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
-            .unwrap_or_default();
+impl Default for NodeConfig {
+    fn default() -> Self {
+        let name = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_else(|_| format!("cortex-{}", &uuid::Uuid::new_v4().to_string()[..8]));
+
+        let data_dir = directories::ProjectDirs::from("com", "cortexos", "cortexd")
+            .map(|d| d.data_dir().to_path_buf())
+            .unwrap_or_else(|| PathBuf::from(".cortexos"));
 
         Self {
             name,
-            port,
+            port: 7654,
             data_dir,
-            skills,
+            skills: Vec::new(),
+            bootstrap: Vec::new(),
+            peer_filter: PeerFilter::AllowAll,
             enable_kademlia: true,      // Default to enabled
             enable_orchestrator: true,   // Default to enabled
             can_compute: true,           // Default to enabled
+            regenerate_identity: false,
+        }
+    }
+}
+
+impl NodeConfig {
+    /// Load `path` if given, otherwise the first config file found on the
+    /// default search path, otherwise the built-in defaults
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => match Self::search_paths().into_iter().find(|p| p.is_file()) {
+                Some(path) => Self::from_file(&path),
+                None => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// `./cortexd.toml`, then `cortexd.toml` in the user config directory
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(CONFIG_FILE_NAME)];
+        if let Some(dirs) = directories::ProjectDirs::from("com", "cortexos", "cortexd") {
+            paths.push(dirs.config_dir().join(CONFIG_FILE_NAME));
+        }
+        paths
+    }
+
+    /// Parse a TOML config file; keys it leaves out keep their defaults
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&text, path)
+    }
+
+    fn from_toml(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let doc: DocumentMut = text.parse().map_err(|e: toml_edit::TomlError| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+
+        let invalid = |key: &str, expected| ConfigError::InvalidValue {
+            path: path.to_path_buf(),
+            key: key.to_string(),
+            expected,
+        };
+
+        let mut config = Self::default();
+        for (key, item) in doc.iter() {
+            match key {
+                "name" => config.name = item.as_str().ok_or_else(|| invalid(key, "a string"))?.to_string(),
+                "port" => {
+                    config.port = item
+                        .as_integer()
+                        .and_then(|port| u16::try_from(port).ok())
+                        .ok_or_else(|| invalid(key, "a port number (0-65535)"))?
+                }
+                "data_dir" => config.data_dir = item.as_str().ok_or_else(|| invalid(key, "a string"))?.into(),
+                "skills" => config.skills = string_array(item).ok_or_else(|| invalid(key, "an array of strings"))?,
+                "bootstrap" => {
+                    config.bootstrap = string_array(item).ok_or_else(|| invalid(key, "an array of multiaddr strings"))?
+                }
+                "allow_peers" | "block_peers" => {
+                    if config.peer_filter != PeerFilter::AllowAll {
+                        return Err(ConfigError::ConflictingPeerFilter { path: path.to_path_buf() });
+                    }
+                    let peers = node_id_array(item).ok_or_else(|| invalid(key, "an array of hex node IDs"))?;
+                    config.peer_filter = if key == "allow_peers" {
+                        PeerFilter::Allow(peers)
+                    } else {
+                        PeerFilter::Block(peers)
+                    };
+                }
+                "kademlia" => config.enable_kademlia = item.as_bool().ok_or_else(|| invalid(key, "a boolean"))?,
+                "orchestrator" => {
+                    config.enable_orchestrator = item.as_bool().ok_or_else(|| invalid(key, "a boolean"))?
+                }
+                "compute" => config.can_compute = item.as_bool().ok_or_else(|| invalid(key, "a boolean"))?,
+                _ => {
+                    return Err(ConfigError::UnknownKey {
+                        path: path.to_path_buf(),
+                        key: key.to_string(),
+                    })
+                }
+            }
         }
+        Ok(config)
+    }
+
+    /// Loopback port the daemon answers `status`/`peers`/`skills` on
+    pub fn control_port(&self) -> u16 {
+        self.port.saturating_add(CONTROL_PORT_OFFSET)
+    }
+
+    /// Load the node's keypair from the data directory, creating it on first run
+    pub fn load_identity(&self) -> io::Result<NodeIdentity> {
+        NodeIdentity::load_or_generate(&self.data_dir, self.regenerate_identity)
+    }
+}
+
+/// Split a comma-separated `--skills` argument
+pub fn parse_skills(skills: &str) -> Vec<String> {
+    skills
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn string_array(item: &Item) -> Option<Vec<String>> {
+    item.as_array()?
+        .iter()
+        .map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
+
+fn node_id_array(item: &Item) -> Option<HashSet<NodeId>> {
+    string_array(item)?.iter().map(|id| NodeId::from_hex(id).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let text = r#"
+            name = "edge-01"
+            port = 7700
+            skills = ["coding", "math"]
+            bootstrap = ["/ip4/192.0.2.10/tcp/4001"]
+            kademlia = false
+        "#;
+        let config = NodeConfig::from_toml(text, Path::new("cortexd.toml")).unwrap();
+        assert_eq!(config.name, "edge-01");
+        assert_eq!(config.port, 7700);
+        assert_eq!(config.skills, vec!["coding", "math"]);
+        assert_eq!(config.bootstrap.len(), 1);
+        assert!(!config.enable_kademlia);
+        assert!(config.enable_orchestrator);
+    }
+
+    #[test]
+    fn test_rejects_bad_keys() {
+        let err = NodeConfig::from_toml("prot = 7700", Path::new("cortexd.toml")).unwrap_err();
+        assert!(matches!(&err, ConfigError::UnknownKey { key, .. } if key == "prot"));
+        assert!(err.to_string().contains("expected one of"));
+
+        let err = NodeConfig::from_toml("port = 700000", Path::new("cortexd.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidValue { .. }));
+
+        let err = NodeConfig::from_toml("port = ", Path::new("cortexd.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+
+        let err = NodeConfig::from_toml("block_peers = [\"not-hex\"]", Path::new("cortexd.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidValue { .. }));
+
+        let err = NodeConfig::from_toml("allow_peers = []\nblock_peers = []", Path::new("cortexd.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::ConflictingPeerFilter { .. }));
+    }
+
+    #[test]
+    fn test_peer_filter() {
+        let trusted = NodeId::random();
+        let text = format!("allow_peers = [\"{}\"]", trusted.to_hex());
+        let config = NodeConfig::from_toml(&text, Path::new("cortexd.toml")).unwrap();
+        assert!(config.peer_filter.allows(&trusted));
+        assert!(!config.peer_filter.allows(&NodeId::random()));
     }
 }
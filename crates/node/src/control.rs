@@ -0,0 +1,346 @@
+//! Local control interface for a running daemon
+//!
+//! `cortexd start` listens on a loopback port for `ControlRequest`s sent
+//! with the grid wire framing (length-prefixed bincode). The `status`,
+//! `peers` and `skills` subcommands connect to it and print the replies.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tracing::{debug, error, warn};
+
+use cortex_grid::{read_frame, write_frame, GridError, NodeId, PeerStore};
+use cortex_skill::NetworkSkillRegistry;
+
+/// Offset from the node port to its control port
+pub const CONTROL_PORT_OFFSET: u16 = 2000;
+
+/// How long a client waits to connect to the daemon and for its reply
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ControlRequest {
+    Status,
+    Peers,
+    Skills,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ControlResponse {
+    Status(NodeStatus),
+    Peers(Vec<PeerSummary>),
+    Skills(Vec<SkillSummary>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeStatus {
+    /// Full 64-char hex id, as the config's peer lists take it
+    pub node_id: String,
+    pub name: String,
+    pub port: u16,
+    pub uptime_secs: u64,
+    pub peer_count: usize,
+    pub can_compute: bool,
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerSummary {
+    /// Full 64-char hex id
+    pub node_id: String,
+    pub addresses: Vec<String>,
+    pub latency_ms: Option<u32>,
+    pub trust_score: f32,
+    pub last_seen_secs: u64,
+    pub can_compute: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillSummary {
+    pub skill: String,
+    /// Nodes known to provide the skill, including this one
+    pub nodes: usize,
+    pub local: bool,
+}
+
+/// Answers control requests from the daemon's live state
+#[derive(Clone)]
+pub struct ControlServer {
+    node_id: NodeId,
+    name: String,
+    port: u16,
+    can_compute: bool,
+    started: Instant,
+    peer_store: Arc<PeerStore>,
+    skill_registry: Arc<RwLock<NetworkSkillRegistry>>,
+    local_skills: Vec<String>,
+}
+
+impl ControlServer {
+    pub fn new(
+        node_id: NodeId,
+        name: String,
+        port: u16,
+        peer_store: Arc<PeerStore>,
+        skill_registry: Arc<RwLock<NetworkSkillRegistry>>,
+    ) -> Self {
+        Self {
+            node_id,
+            name,
+            port,
+            can_compute: false,
+            started: Instant::now(),
+            peer_store,
+            skill_registry,
+            local_skills: Vec::new(),
+        }
+    }
+
+    pub fn with_can_compute(mut self, can_compute: bool) -> Self {
+        self.can_compute = can_compute;
+        self
+    }
+
+    pub fn with_local_skills(mut self, skills: Vec<String>) -> Self {
+        self.local_skills = skills;
+        self
+    }
+
+    /// Listen on `127.0.0.1:control_port` and return the bound port
+    /// (useful when `control_port` is 0)
+    pub async fn start(self, control_port: u16) -> std::io::Result<u16> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, control_port)).await?;
+        let bound = listener.local_addr()?.port();
+        let server = Arc::new(self);
+
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer_addr)) => {
+                        debug!("Control connection from {}", peer_addr);
+                        let server = Arc::clone(&server);
+                        tokio::spawn(async move {
+                            if let Err(e) = server.serve(stream).await {
+                                warn!("Control connection error: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!("Control accept error: {}", e);
+                    }
+                }
+            }
+        });
+
+        Ok(bound)
+    }
+
+    /// Answer requests on one connection until the client hangs up
+    async fn serve(&self, mut stream: TcpStream) -> Result<(), GridError> {
+        loop {
+            let frame = match read_frame(&mut stream).await {
+                Ok(frame) => frame,
+                Err(GridError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let request: ControlRequest =
+                bincode::deserialize(&frame).map_err(|e| GridError::SerializationError(e.to_string()))?;
+            let response = self.handle(request).await;
+            let bytes = bincode::serialize(&response).map_err(|e| GridError::SerializationError(e.to_string()))?;
+            write_frame(&mut stream, &bytes).await?;
+        }
+    }
+
+    pub async fn handle(&self, request: ControlRequest) -> ControlResponse {
+        match request {
+            ControlRequest::Status => ControlResponse::Status(NodeStatus {
+                node_id: self.node_id.to_hex(),
+                name: self.name.clone(),
+                port: self.port,
+                uptime_secs: self.started.elapsed().as_secs(),
+                peer_count: self.peer_store.count().await,
+                can_compute: self.can_compute,
+                skills: self.local_skills.clone(),
+            }),
+            ControlRequest::Peers => {
+                let mut peers: Vec<PeerSummary> = self
+                    .peer_store
+                    .list_active()
+                    .await
+                    .into_iter()
+                    .map(|peer| PeerSummary {
+                        node_id: peer.node_id.to_hex(),
+                        addresses: peer.addresses.iter().map(SocketAddr::to_string).collect(),
+                        latency_ms: peer.latency_ms,
+                        trust_score: peer.trust_score,
+                        last_seen_secs: peer.last_seen.elapsed().as_secs(),
+                        can_compute: peer.capabilities.can_compute,
+                    })
+                    .collect();
+                peers.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+                ControlResponse::Peers(peers)
+            }
+            ControlRequest::Skills => {
+                let registry = self.skill_registry.read().await;
+                let mut skills: Vec<SkillSummary> = registry
+                    .skill_distribution()
+                    .into_iter()
+                    .map(|(skill, nodes)| SkillSummary {
+                        local: self.local_skills.iter().any(|s| s == skill.as_str()),
+                        skill: skill.to_string(),
+                        nodes,
+                    })
+                    .collect();
+                skills.sort_by(|a, b| a.skill.cmp(&b.skill));
+                ControlResponse::Skills(skills)
+            }
+        }
+    }
+}
+
+/// Send one request to the daemon listening on `control_port`
+pub async fn query(control_port: u16, request: &ControlRequest) -> Result<ControlResponse, GridError> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, control_port))
+            .await
+            .map_err(|e| {
+                GridError::ConnectionFailed(format!(
+                    "no daemon on control port {} ({}); is `cortexd start` running?",
+                    control_port, e
+                ))
+            })?;
+        let bytes = bincode::serialize(request).map_err(|e| GridError::SerializationError(e.to_string()))?;
+        write_frame(&mut stream, &bytes).await?;
+        let frame = read_frame(&mut stream).await?;
+        bincode::deserialize(&frame).map_err(|e| GridError::SerializationError(e.to_string()))
+    };
+    tokio::time::timeout(CLIENT_TIMEOUT, exchange)
+        .await
+        .map_err(|_| GridError::Timeout)?
+}
+
+/// Render a response as the table printed by the CLI
+pub fn format_response(response: &ControlResponse) -> String {
+    match response {
+        ControlResponse::Status(status) => {
+            let skills = if status.skills.is_empty() {
+                "-".to_string()
+            } else {
+                status.skills.join(", ")
+            };
+            [
+                ("Node ID", status.node_id.clone()),
+                ("Name", status.name.clone()),
+                ("Port", status.port.to_string()),
+                ("Uptime", format_duration(status.uptime_secs)),
+                ("Peers", status.peer_count.to_string()),
+                ("Compute", status.can_compute.to_string()),
+                ("Skills", skills),
+            ]
+            .iter()
+            .map(|(label, value)| format!("{:<9} {}\n", format!("{}:", label), value))
+            .collect()
+        }
+        ControlResponse::Peers(peers) => {
+            if peers.is_empty() {
+                return "No peers discovered\n".to_string();
+            }
+            let mut out = format!(
+                "{:<10} {:<24} {:>8} {:>6} {:>10} {:<7}\n",
+                "NODE", "ADDRESS", "LATENCY", "TRUST", "LAST SEEN", "COMPUTE"
+            );
+            for peer in peers {
+                let latency = peer
+                    .latency_ms
+                    .map(|ms| format!("{}ms", ms))
+                    .unwrap_or_else(|| "-".to_string());
+                out.push_str(&format!(
+                    "{:<10} {:<24} {:>8} {:>6.2} {:>10} {:<7}\n",
+                    &peer.node_id[..8.min(peer.node_id.len())],
+                    peer.addresses.first().map(String::as_str).unwrap_or("-"),
+                    latency,
+                    peer.trust_score,
+                    format!("{}s ago", peer.last_seen_secs),
+                    if peer.can_compute { "yes" } else { "no" },
+                ));
+            }
+            out
+        }
+        ControlResponse::Skills(skills) => {
+            if skills.is_empty() {
+                return "No skills known\n".to_string();
+            }
+            let mut out = format!("{:<24} {:>5} {:<5}\n", "SKILL", "NODES", "LOCAL");
+            for skill in skills {
+                out.push_str(&format!(
+                    "{:<24} {:>5} {:<5}\n",
+                    skill.skill,
+                    skill.nodes,
+                    if skill.local { "yes" } else { "no" },
+                ));
+            }
+            out
+        }
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cortex_grid::PeerInfo;
+    use cortex_reputation::SkillId;
+
+    #[tokio::test]
+    async fn test_control_roundtrip() {
+        let node_id = NodeId::random();
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let peer = NodeId::random();
+        let mut info = PeerInfo::new(peer, [0u8; 32]);
+        info.addresses = vec!["192.168.1.20:7654".parse().unwrap()];
+        info.latency_ms = Some(12);
+        peer_store.insert(info).await;
+
+        let mut registry = NetworkSkillRegistry::new(node_id);
+        registry.register_my_skill(SkillId::new("coding"));
+        registry.register_node_skill(peer, SkillId::new("coding"));
+        registry.register_node_skill(peer, SkillId::new("math"));
+
+        let port = ControlServer::new(node_id, "test".into(), 7654, peer_store, Arc::new(RwLock::new(registry)))
+            .with_local_skills(vec!["coding".into()])
+            .start(0)
+            .await
+            .unwrap();
+
+        let ControlResponse::Status(status) = query(port, &ControlRequest::Status).await.unwrap() else {
+            panic!("expected status");
+        };
+        assert_eq!(status.node_id, node_id.to_hex());
+        assert_eq!(status.peer_count, 1);
+
+        let response = query(port, &ControlRequest::Peers).await.unwrap();
+        let ControlResponse::Peers(peers) = &response else {
+            panic!("expected peers");
+        };
+        assert_eq!(peers[0].node_id, peer.to_hex());
+        assert!(format_response(&response).contains("192.168.1.20:7654"));
+
+        let ControlResponse::Skills(skills) = query(port, &ControlRequest::Skills).await.unwrap() else {
+            panic!("expected skills");
+        };
+        assert_eq!(skills.len(), 2);
+        assert_eq!((skills[0].skill.as_str(), skills[0].nodes, skills[0].local), ("coding", 2, true));
+        assert!(!skills[1].local);
+    }
+}
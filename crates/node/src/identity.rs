@@ -0,0 +1,119 @@
+//! Persistent node identity
+//!
+//! The node's Ed25519 secret key lives in `identity.key` inside the data
+//! directory. It is created on first start and reused afterwards, so the
+//! `NodeId` derived from the public key stays stable across restarts.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use cortex_grid::NodeId;
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+
+/// File name of the secret key inside the data directory
+pub const IDENTITY_FILE: &str = "identity.key";
+
+pub struct NodeIdentity {
+    signing_key: SigningKey,
+}
+
+impl NodeIdentity {
+    /// Load the identity stored in `data_dir`, generating and saving a new
+    /// one if none exists or `regenerate` is set.
+    pub fn load_or_generate(data_dir: &Path, regenerate: bool) -> io::Result<Self> {
+        let path = data_dir.join(IDENTITY_FILE);
+        if !regenerate {
+            match fs::read(&path) {
+                Ok(bytes) => return Self::from_bytes(&bytes, &path),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let identity = Self {
+            signing_key: SigningKey::generate(&mut OsRng),
+        };
+        identity.save(&path)?;
+        Ok(identity)
+    }
+
+    fn from_bytes(bytes: &[u8], path: &Path) -> io::Result<Self> {
+        let secret: [u8; 32] = bytes.try_into().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} must hold a 32-byte Ed25519 secret key", path.display()),
+            )
+        })?;
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&secret),
+        })
+    }
+
+    /// Write the secret key owner-readable only, replacing any old file
+    /// atomically so a crash never leaves a truncated key behind.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = temp_path(path);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&self.signing_key.to_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn signing_key(&self) -> SigningKey {
+        self.signing_key.clone()
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    pub fn node_id(&self) -> NodeId {
+        NodeId::from_pubkey(&self.public_key())
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_persists() {
+        let dir = std::env::temp_dir().join(format!("cortexd-identity-{}", uuid::Uuid::new_v4()));
+
+        let first = NodeIdentity::load_or_generate(&dir, false).unwrap();
+        let again = NodeIdentity::load_or_generate(&dir, false).unwrap();
+        assert_eq!(first.node_id(), again.node_id());
+        assert_eq!(first.node_id(), NodeId::from_pubkey(&again.public_key()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join(IDENTITY_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let fresh = NodeIdentity::load_or_generate(&dir, true).unwrap();
+        assert_ne!(fresh.node_id(), first.node_id());
+        assert_eq!(NodeIdentity::load_or_generate(&dir, false).unwrap().node_id(), fresh.node_id());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
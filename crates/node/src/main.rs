@@ -1,25 +1,32 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use clap::{Parser, Subcommand};
 use tokio::sync::RwLock;
-use tracing::{info, Level};
+use tracing::{debug, info, warn, Level};
 
 use cortex_grid::{
     Capabilities, Discovery, GridOrchestrator, KademliaDiscovery, LanDiscovery, NodeId, PeerInfo,
-    PeerStore, RelayNode,
+    PeerStore, RelayNode, TaskOutcome, TASK_OUTCOME_EVENT,
 };
-use cortex_reputation::{TrustGraph, SkillId};
-use cortex_skill::NetworkSkillRegistry;
+use cortex_reputation::{TrustGraph, TrustSnapshot, SkillId};
+use cortex_skill::{NetworkSkillRegistry, SkillCapability};
+use cortex_core::logging::{self, LogFormat};
+use cortex_core::DeviceCapabilities;
 use cortex_core::runtime::{EventBus, Runtime};
 
 mod capabilities;
 mod config;
+mod control;
+mod identity;
 mod network;
 mod task_server;
 
 use capabilities::{detect_device_tier, DeviceTier};
 use config::NodeConfig;
+use control::{ControlRequest, ControlServer};
+use network::HandshakeService;
 use task_server::TaskServer;
 
 #[derive(Parser)]
@@ -29,13 +36,18 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
+    /// Config file (defaults to ./cortexd.toml, then the user config dir).
+    /// Command-line flags override values from the file.
+    #[arg(short, long, global = true, env = "CORTEXD_CONFIG")]
+    config: Option<PathBuf>,
+
     /// Node name (defaults to hostname)
     #[arg(short, long)]
     name: Option<String>,
 
-    /// Port to listen on
-    #[arg(short, long, default_value = "7654")]
-    port: u16,
+    /// Port to listen on [default: 7654]
+    #[arg(short, long, global = true)]
+    port: Option<u16>,
 
     /// Data directory
     #[arg(short, long)]
@@ -45,17 +57,25 @@ struct Cli {
     #[arg(short, long)]
     skills: Option<String>,
 
-    /// Enable Kademlia wide-area discovery
-    #[arg(long, default_value = "true")]
-    kademlia: bool,
+    /// Enable Kademlia wide-area discovery [default: true]
+    #[arg(long)]
+    kademlia: Option<bool>,
+
+    /// Enable grid orchestrator [default: true]
+    #[arg(long)]
+    orchestrator: Option<bool>,
 
-    /// Enable grid orchestrator
-    #[arg(long, default_value = "true")]
-    orchestrator: bool,
+    /// Enable compute capability [default: true]
+    #[arg(long)]
+    compute: Option<bool>,
 
-    /// Enable compute capability
-    #[arg(long, default_value = "true")]
-    compute: bool,
+    /// Log output format: text or json
+    #[arg(long, global = true, env = "CORTEX_LOG_FORMAT", default_value = "text")]
+    log_format: LogFormat,
+
+    /// Discard the stored node identity and generate a new one
+    #[arg(long)]
+    regenerate_identity: bool,
 }
 
 #[derive(Subcommand)]
@@ -74,34 +94,57 @@ enum Commands {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .init();
+    logging::init(cli.log_format, Level::INFO, false);
 
-    let mut config = NodeConfig::new(cli.name, cli.port, cli.data_dir, cli.skills);
-    config.enable_kademlia = cli.kademlia;
-    config.enable_orchestrator = cli.orchestrator;
-    config.can_compute = cli.compute;
+    let mut config = NodeConfig::load(cli.config.as_deref())?;
+    if let Some(name) = cli.name {
+        config.name = name;
+    }
+    if let Some(port) = cli.port {
+        config.port = port;
+    }
+    if let Some(data_dir) = cli.data_dir {
+        config.data_dir = PathBuf::from(data_dir);
+    }
+    if let Some(skills) = cli.skills {
+        config.skills = config::parse_skills(&skills);
+    }
+    if let Some(kademlia) = cli.kademlia {
+        config.enable_kademlia = kademlia;
+    }
+    if let Some(orchestrator) = cli.orchestrator {
+        config.enable_orchestrator = orchestrator;
+    }
+    if let Some(compute) = cli.compute {
+        config.can_compute = compute;
+    }
+    config.regenerate_identity = cli.regenerate_identity;
 
     match cli.command {
         Some(Commands::Start) | None => {
             run_daemon(config).await?;
         }
         Some(Commands::Status) => {
-            println!("Node status: (not implemented yet)");
+            print_control(&config, ControlRequest::Status).await?;
         }
         Some(Commands::Peers) => {
-            println!("Peers: (not implemented yet)");
+            print_control(&config, ControlRequest::Peers).await?;
         }
         Some(Commands::Skills) => {
-            println!("Skills: (not implemented yet)");
+            print_control(&config, ControlRequest::Skills).await?;
         }
     }
 
     Ok(())
 }
 
+/// Query the daemon running on this machine with the same `--port`
+async fn print_control(config: &NodeConfig, request: ControlRequest) -> Result<(), Box<dyn std::error::Error>> {
+    let response = control::query(config.control_port(), &request).await?;
+    print!("{}", control::format_response(&response));
+    Ok(())
+}
+
 async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>> {
     info!("🧠 CortexOS Node Daemon");
     info!("   Version: 0.1.0");
@@ -113,22 +156,23 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
     info!("   Recommended model: {}", device_tier.recommended_model());
     info!("");
 
-    // Generate or load node ID
-    let node_id = NodeId::random();
-    
-    // Generate a random pubkey for discovery
-    let mut pubkey = [0u8; 32];
-    use rand::RngCore;
-    rand::thread_rng().fill_bytes(&mut pubkey);
+    // Load the persistent identity; the node ID is derived from its public key
+    let identity = config.load_identity()?;
+    let node_id = identity.node_id();
+    let pubkey = identity.public_key();
+    if config.regenerate_identity {
+        warn!("Generated a new node identity; peers will see this node as new");
+    }
 
-    info!("📍 Node ID: {}", node_id);
+    info!("📍 Node ID: {}", node_id.to_hex());
     info!("   Name: {}", config.name);
     info!("   Port: {}", config.port);
     info!("");
 
     // Initialize components
-    let peer_store = Arc::new(PeerStore::new(Duration::from_secs(120)));
-    let _trust_graph = Arc::new(RwLock::new(TrustGraph::new(node_id)));
+    let peer_store = Arc::new(PeerStore::new(Duration::from_secs(120)).with_filter(config.peer_filter.clone()));
+    let trust_path = config.data_dir.join(TRUST_SNAPSHOT_FILE);
+    let trust_graph = Arc::new(RwLock::new(load_trust_graph(&trust_path, node_id)));
     let skill_registry = Arc::new(RwLock::new(NetworkSkillRegistry::new(node_id)));
     
     // Initialize event bus and runtime for orchestrator
@@ -139,7 +183,11 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
     if !config.skills.is_empty() {
         let mut registry = skill_registry.write().await;
         for skill in &config.skills {
-            registry.register_my_skill(SkillId::new(skill));
+            let id = SkillId::new(skill);
+            // "translate.spanish" also serves requests for plain "translate"
+            let family = skill.split('.').next().unwrap_or(skill);
+            registry.register_skill_capability(id.clone(), SkillCapability::providing(family));
+            registry.register_my_skill(id);
             info!("📚 Registered skill: {}", skill);
         }
         info!("");
@@ -165,50 +213,101 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
     task_server.start().await?;
     info!("🎯 Task server on port {}", task_port);
 
+    // Local control socket for `cortexd status/peers/skills`
+    let control_port = ControlServer::new(
+        node_id,
+        config.name.clone(),
+        config.port,
+        Arc::clone(&peer_store),
+        Arc::clone(&skill_registry),
+    )
+    .with_can_compute(config.can_compute)
+    .with_local_skills(config.skills.clone())
+    .start(config.control_port())
+    .await?;
+    info!("🛠  Control interface on 127.0.0.1:{}", control_port);
+
+    // Start Kademlia discovery if enabled
+    let mut confirmed_addresses = None;
+    let mut kad_events = None;
+    if config.enable_kademlia {
+        info!("🌐 Starting Kademlia wide-area discovery...");
+        // External addresses are only known once peers confirm them in
+        // handshakes, and arrive through `external_address_sender`
+        let kademlia = KademliaDiscovery::new(node_id, pubkey, config.port)
+            .and_then(|(kad, rx)| Ok((kad.with_bootstrap(&config.bootstrap)?, rx)));
+        match kademlia {
+            Ok((mut kad_discovery, kad_rx)) => {
+                kad_discovery.start().await?;
+                confirmed_addresses = Some(kad_discovery.external_address_sender());
+                kad_events = Some(kad_rx);
+            }
+            Err(e) => {
+                info!("⚠️  Kademlia discovery initialization failed: {}", e);
+            }
+        }
+    }
+
+    // Handshake with discovered peers to learn our external address
+    let mut handshakes = HandshakeService::new(
+        node_id,
+        identity.signing_key(),
+        local_capabilities,
+        Arc::clone(&peer_store),
+    )
+    .with_device(DeviceCapabilities::detect());
+    if let Some(tx) = confirmed_addresses {
+        handshakes = handshakes.with_confirmed_addresses(tx);
+    }
+    let handshakes = Arc::new(handshakes);
+    handshakes.start(config.port).await?;
+    info!("🤝 Handshakes on port {}", config.port);
+
     // Start LAN discovery
     info!("🔍 Starting LAN discovery...");
-    let (mut discovery, mut discovery_rx) = LanDiscovery::new(node_id, pubkey, config.port);
+    let (discovery, mut discovery_rx) = LanDiscovery::new(node_id, pubkey, config.port);
+    let mut discovery = discovery.with_capabilities(local_capabilities);
     discovery.start().await?;
 
     // Spawn discovery handler
     let peer_store_clone = Arc::clone(&peer_store);
     let local_caps = local_capabilities.clone();
+    let lan_handshakes = Arc::clone(&handshakes);
     tokio::spawn(async move {
         while let Some(event) = discovery_rx.recv().await {
             info!("✨ Discovered peer: {} at {:?}", event.peer_id, event.addresses);
             
             // Create peer info and insert
             let mut peer = PeerInfo::new(event.peer_id, [0u8; 32]);
-            peer.addresses = event.addresses;
-            peer.capabilities = local_caps.clone();
-            peer_store_clone.insert(peer).await;
+            peer.addresses = event.addresses.clone();
+            // Peers from before capabilities were announced are assumed to match us
+            peer.capabilities = event.capabilities.unwrap_or(local_caps);
+            if !peer_store_clone.insert(peer).await {
+                info!("🚫 Ignoring peer {} (not allowed by peer filter)", event.peer_id.short());
+                continue;
+            }
+            handshake_with(&lan_handshakes, event.peer_id, &event.addresses);
         }
     });
 
-    // Start Kademlia discovery if enabled
-    if config.enable_kademlia {
-        info!("🌐 Starting Kademlia wide-area discovery...");
-        match KademliaDiscovery::new(node_id, pubkey, config.port) {
-            Ok((mut kad_discovery, mut kad_rx)) => {
-                kad_discovery.start().await?;
-                
-                let peer_store_kad = Arc::clone(&peer_store);
-                let local_caps_kad = local_capabilities.clone();
-                tokio::spawn(async move {
-                    while let Some(event) = kad_rx.recv().await {
-                        info!("🌍 Kademlia discovered peer: {} at {:?}", event.peer_id, event.addresses);
-                        
-                        let mut peer = PeerInfo::new(event.peer_id, [0u8; 32]);
-                        peer.addresses = event.addresses;
-                        peer.capabilities = local_caps_kad.clone();
-                        peer_store_kad.insert(peer).await;
-                    }
-                });
+    if let Some(mut kad_rx) = kad_events {
+        let peer_store_kad = Arc::clone(&peer_store);
+        let local_caps_kad = local_capabilities.clone();
+        let kad_handshakes = Arc::clone(&handshakes);
+        tokio::spawn(async move {
+            while let Some(event) = kad_rx.recv().await {
+                info!("🌍 Kademlia discovered peer: {} at {:?}", event.peer_id, event.addresses);
+
+                let mut peer = PeerInfo::new(event.peer_id, [0u8; 32]);
+                peer.addresses = event.addresses.clone();
+                peer.capabilities = local_caps_kad.clone();
+                if !peer_store_kad.insert(peer).await {
+                    info!("🚫 Ignoring peer {} (not allowed by peer filter)", event.peer_id.short());
+                    continue;
+                }
+                handshake_with(&kad_handshakes, event.peer_id, &event.addresses);
             }
-            Err(e) => {
-                info!("⚠️  Kademlia discovery initialization failed: {}", e);
-            }
-        }
+        });
     }
 
     // Start Grid Orchestrator if enabled
@@ -222,6 +321,27 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
         
         orchestrator.start().await?;
         info!("✅ Grid Orchestrator started");
+
+        // Feed delegation results into the trust graph so the orchestrator
+        // routes away from peers that keep failing
+        let mut outcomes = event_bus.subscribe(TASK_OUTCOME_EVENT);
+        let trust_graph = Arc::clone(&trust_graph);
+        let peer_store = Arc::clone(&peer_store);
+        tokio::spawn(async move {
+            while let Some(event) = outcomes.recv().await {
+                let Some(outcome) = event
+                    .payload
+                    .as_bytes()
+                    .and_then(|bytes| bincode::deserialize::<TaskOutcome>(bytes).ok())
+                else {
+                    continue;
+                };
+                let graph = trust_graph.read().await;
+                if let Err(e) = cortex_reputation::apply_outcome(&graph, &peer_store, &outcome).await {
+                    warn!("Failed to record task outcome: {}", e);
+                }
+            }
+        });
     }
 
     // Spawn relay message handler
@@ -239,6 +359,7 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
     info!("    cargo run -p cortexd -- --port 7655 --skills coding");
     info!("");
     info!("  Nodes will auto-discover each other on your local network!");
+    info!("  Inspect this node with: cortexd --port {} peers", config.port);
     info!("═══════════════════════════════════════════════════════════");
     info!("");
 
@@ -259,7 +380,46 @@ async fn run_daemon(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>
 
     relay_node.stop().await;
     discovery.stop().await?;
+    if let Err(e) = trust_graph.read().await.snapshot().save(&trust_path) {
+        warn!("Failed to save trust snapshot: {}", e);
+    }
     info!("✅ Node stopped");
 
     Ok(())
 }
+
+/// Handshake with a newly discovered peer at its first address, in the
+/// background
+fn handshake_with(handshakes: &Arc<HandshakeService>, peer: NodeId, addresses: &[std::net::SocketAddr]) {
+    let Some(&addr) = addresses.first() else {
+        return;
+    };
+    let handshakes = Arc::clone(handshakes);
+    tokio::spawn(async move {
+        if let Err(e) = handshakes.connect(peer, addr).await {
+            debug!("Handshake with {} at {} failed: {}", peer.short(), addr, e);
+        }
+    });
+}
+
+/// Trust graph snapshot kept in the data directory across restarts
+const TRUST_SNAPSHOT_FILE: &str = "trust.snapshot";
+
+/// Restore the trust graph saved at the last shutdown, or start empty
+fn load_trust_graph(path: &std::path::Path, node_id: NodeId) -> TrustGraph {
+    match TrustSnapshot::load(path) {
+        Ok(Some(snapshot)) if snapshot.my_id == node_id => {
+            info!("Restored trust graph ({} ratings)", snapshot.history.len());
+            TrustGraph::restore(snapshot)
+        }
+        Ok(Some(_)) => {
+            warn!("Trust snapshot belongs to another identity, starting fresh");
+            TrustGraph::new(node_id)
+        }
+        Ok(None) => TrustGraph::new(node_id),
+        Err(e) => {
+            warn!("Failed to load trust snapshot: {}", e);
+            TrustGraph::new(node_id)
+        }
+    }
+}
@@ -1,33 +1,122 @@
+//! Grid handshakes with discovered peers
+//!
+//! The node accepts handshakes on its grid port and runs one with every
+//! peer discovery reports. Each handshake as initiator tells us the address
+//! the peer saw us connect from; once enough peers agree on one, it is
+//! handed to Kademlia to advertise in place of our private listen address.
+//! The hardware each peer advertises is kept in the `PeerStore`.
+
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
-use cortex_grid::{NodeId, PeerStore};
+use cortex_core::DeviceCapabilities;
+use ed25519_dalek::SigningKey;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use cortex_grid::{
+    Capabilities, GridError, GridTransport, Handshaker, NodeId, PeerAddress, PeerStore, TcpTransport,
+};
 
-pub struct NetworkManager {
+pub struct HandshakeService {
     node_id: NodeId,
-    peer_store: Arc<RwLock<PeerStore>>,
-    port: u16,
+    signing_key: SigningKey,
+    capabilities: Capabilities,
+    device: Option<DeviceCapabilities>,
+    peer_store: Arc<PeerStore>,
+    transport: Arc<dyn GridTransport>,
+    confirmed: Option<mpsc::Sender<PeerAddress>>,
 }
 
-impl NetworkManager {
-    pub fn new(node_id: NodeId, peer_store: Arc<RwLock<PeerStore>>, port: u16) -> Self {
+impl HandshakeService {
+    pub fn new(
+        node_id: NodeId,
+        signing_key: SigningKey,
+        capabilities: Capabilities,
+        peer_store: Arc<PeerStore>,
+    ) -> Self {
         Self {
             node_id,
+            signing_key,
+            capabilities,
+            device: None,
             peer_store,
-            port,
+            transport: Arc::new(TcpTransport),
+            confirmed: None,
         }
     }
 
-    pub async fn connect_to_peer(&self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
-        // TODO: Implement TCP connection with handshake
-        tracing::info!("Connecting to peer at {}", addr);
+    /// Advertise this hardware to every peer we handshake with
+    pub fn with_device(mut self, device: DeviceCapabilities) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Forward external addresses confirmed by handshakes, typically to
+    /// `KademliaDiscovery::external_address_sender`
+    pub fn with_confirmed_addresses(mut self, tx: mpsc::Sender<PeerAddress>) -> Self {
+        self.confirmed = Some(tx);
+        self
+    }
+
+    /// Accept handshakes on `port`
+    pub async fn start(self: &Arc<Self>, port: u16) -> Result<(), GridError> {
+        let mut incoming = self.transport.listen(&format!("0.0.0.0:{}", port)).await?;
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match incoming.accept().await {
+                    Ok((mut conn, peer_addr)) => {
+                        let service = Arc::clone(&service);
+                        tokio::spawn(async move {
+                            let mut handshaker = Handshaker::new_responder(
+                                service.node_id,
+                                service.signing_key.clone(),
+                                service.capabilities,
+                            );
+                            if let Some(device) = service.device.clone() {
+                                handshaker = handshaker.with_device(device);
+                            }
+                            if let Ok(addr) = peer_addr.parse::<SocketAddr>() {
+                                handshaker = handshaker.with_remote_addr(addr);
+                            }
+                            if let Err(e) = handshaker.respond(&mut conn).await {
+                                debug!("Handshake from {} failed: {}", peer_addr, e);
+                                return;
+                            }
+                            if let Some(remote) = handshaker.remote_node_id() {
+                                service.record(&handshaker, remote).await;
+                            }
+                        });
+                    }
+                    Err(GridError::ChannelClosed) => break,
+                    Err(e) => warn!("Handshake accept error: {}", e),
+                }
+            }
+        });
         Ok(())
     }
 
-    pub async fn broadcast_skills(&self, skills: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-        // TODO: Broadcast skill announcements to all peers
-        tracing::info!("Broadcasting {} skills to network", skills.len());
+    /// Handshake with `peer`, which discovery reported at `addr`
+    pub async fn connect(&self, peer: NodeId, addr: SocketAddr) -> Result<(), GridError> {
+        let mut conn = self.transport.connect(&addr.to_string()).await?;
+        let mut handshaker =
+            Handshaker::new_initiator(self.node_id, self.signing_key.clone(), self.capabilities);
+        if let Some(device) = self.device.clone() {
+            handshaker = handshaker.with_device(device);
+        }
+        handshaker.initiate(&mut conn).await?;
+        self.record(&handshaker, peer).await;
         Ok(())
     }
+
+    async fn record(&self, handshaker: &Handshaker, remote: NodeId) {
+        let Some(addr) = self.peer_store.record_handshake(handshaker, remote).await else {
+            return;
+        };
+        info!("🌐 Peers confirm our external address {}", addr);
+        if let Some(tx) = &self.confirmed {
+            let _ = tx.send(addr).await;
+        }
+    }
 }
@@ -3,12 +3,11 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
 use serde::{Serialize, Deserialize};
 
-use cortex_grid::NodeId;
+use cortex_grid::{Conn, GridError, GridTransport, NodeId, TcpTransport};
 
 /// Task request sent over network
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +38,7 @@ pub struct TaskServer {
     port: u16,
     skills: Arc<RwLock<Vec<String>>>,
     executor: SkillExecutorFn,
+    transport: Arc<dyn GridTransport>,
 }
 
 impl TaskServer {
@@ -54,6 +54,7 @@ impl TaskServer {
             port,
             skills: Arc::new(RwLock::new(skills)),
             executor,
+            transport: Arc::new(TcpTransport),
         }
     }
 
@@ -62,10 +63,16 @@ impl TaskServer {
         self
     }
 
+    /// Listen on `transport` instead of TCP
+    pub fn with_transport(mut self, transport: Arc<dyn GridTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// Start the task server
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
         let addr: SocketAddr = format!("0.0.0.0:{}", self.port).parse()?;
-        let listener = TcpListener::bind(addr).await?;
+        let mut incoming = self.transport.listen(&addr.to_string()).await?;
         
         info!("🎯 Task server listening on port {}", self.port);
 
@@ -75,7 +82,7 @@ impl TaskServer {
 
         tokio::spawn(async move {
             loop {
-                match listener.accept().await {
+                match incoming.accept().await {
                     Ok((stream, peer_addr)) => {
                         debug!("Task connection from {}", peer_addr);
                         let executor = Arc::clone(&executor);
@@ -87,6 +94,7 @@ impl TaskServer {
                             }
                         });
                     }
+                    Err(GridError::ChannelClosed) => break,
                     Err(e) => {
                         error!("Accept error: {}", e);
                     }
@@ -99,7 +107,7 @@ impl TaskServer {
 }
 
 async fn handle_connection(
-    mut stream: TcpStream,
+    mut stream: Conn,
     node_id: NodeId,
     executor: SkillExecutorFn,
     skills: Arc<RwLock<Vec<String>>>,
@@ -421,13 +429,14 @@ fn call_ollama_sync(prompt: &str) -> Result<String, String> {
 
 /// Send a task to a remote node
 pub async fn send_task(
+    transport: &dyn GridTransport,
     target_addr: &str,
     task_id: &str,
     skill: &str,
     payload: &str,
     from_node: &str,
 ) -> Result<TaskResponse, Box<dyn std::error::Error + Send + Sync>> {
-    let mut stream = TcpStream::connect(target_addr).await?;
+    let mut stream = transport.connect(target_addr).await?;
     
     let request = TaskRequest {
         task_id: task_id.to_string(),
@@ -0,0 +1,200 @@
+//! Compute self-test
+//!
+//! Times the matmuls of a decoder layer (attention projections and a
+//! gated MLP, Qwen-0.5B sized) on random weights and turns the time per
+//! layer into the `capacity_score` the peer advertises, replacing the
+//! spec-based estimate from `DeviceCapabilities::detect`. The result is
+//! cached in the data directory and reused until the device or the
+//! workload changes.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use candle_core::{Device, Result as CandleResult, Tensor};
+use cortex_core::DeviceCapabilities;
+use serde::{Deserialize, Serialize};
+
+/// File name of the cached result inside the data directory
+pub const BENCH_FILE: &str = "bench.json";
+/// Layers pushed through per pass (a Qwen-0.5B sized model)
+pub const DEFAULT_BENCH_LAYERS: u32 = 24;
+/// Bump whenever the workload or result format changes so cached results
+/// are re-measured
+const WORKLOAD_VERSION: u32 = 3;
+/// Prompt tokens per pass, in f32
+const BENCH_TOKENS: usize = 8;
+/// Qwen-0.5B hidden and MLP widths
+const BENCH_HIDDEN: usize = 896;
+const BENCH_INTERMEDIATE: usize = 4864;
+/// Timed passes; the fastest one is kept to filter out scheduler noise
+const BENCH_PASSES: usize = 3;
+/// Time per layer that scores 50/100
+const REFERENCE_MS_PER_LAYER: f64 = 5.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub workload_version: u32,
+    /// Device the result was measured on, see `device_fingerprint`
+    pub device: String,
+    pub layers: u32,
+    pub tokens: usize,
+    pub ms_per_layer: f64,
+    /// Tokens per second through all `layers`
+    pub tokens_per_sec: f64,
+    /// `ms_per_layer` mapped onto 1..=100
+    pub capacity_score: u32,
+    /// Unix time of the measurement
+    pub measured_at: u64,
+}
+
+/// Identifies the hardware a cached result is valid for
+pub fn device_fingerprint(capabilities: &DeviceCapabilities) -> String {
+    format!("{} x{}", capabilities.cpu.model, capabilities.cpu.cores)
+}
+
+/// Default data directory for the peer
+pub fn default_data_dir() -> PathBuf {
+    directories::ProjectDirs::from("com", "cortexos", "cortex-peer")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Weights of one decoder layer, shared by every benchmarked layer
+struct LayerWeights {
+    attn: [Tensor; 4],
+    gate: Tensor,
+    up: Tensor,
+    down: Tensor,
+}
+
+impl LayerWeights {
+    fn random(device: &Device) -> CandleResult<Self> {
+        let square = || Tensor::randn(0f32, 0.02, (BENCH_HIDDEN, BENCH_HIDDEN), device);
+        Ok(Self {
+            attn: [square()?, square()?, square()?, square()?],
+            gate: Tensor::randn(0f32, 0.02, (BENCH_HIDDEN, BENCH_INTERMEDIATE), device)?,
+            up: Tensor::randn(0f32, 0.02, (BENCH_HIDDEN, BENCH_INTERMEDIATE), device)?,
+            down: Tensor::randn(0f32, 0.02, (BENCH_INTERMEDIATE, BENCH_HIDDEN), device)?,
+        })
+    }
+
+    /// q/k/v/o projections then a SwiGLU MLP, with residuals
+    fn forward(&self, x: &Tensor) -> CandleResult<Tensor> {
+        let mut attn = x.clone();
+        for w in &self.attn {
+            attn = attn.matmul(w)?;
+        }
+        let x = (x + attn)?;
+        let gated = (x.matmul(&self.gate)?.silu()? * x.matmul(&self.up)?)?;
+        x + gated.matmul(&self.down)?
+    }
+}
+
+/// One pass of `layers` layers over a fresh hidden state
+fn forward_pass(weights: &LayerWeights, hidden: &Tensor, layers: u32) -> CandleResult<()> {
+    let mut x = hidden.clone();
+    for _ in 0..layers {
+        x = weights.forward(&x)?;
+    }
+    // Force the result so lazy backends cannot skip the work
+    x.sum_all()?.to_scalar::<f32>()?;
+    Ok(())
+}
+
+/// Run the workload through `layers` layers on the CPU
+pub fn run(layers: u32, device: String) -> CandleResult<BenchResult> {
+    let layers = layers.max(1);
+    let cpu = Device::Cpu;
+    let weights = LayerWeights::random(&cpu)?;
+    let hidden = Tensor::randn(0f32, 1.0, (BENCH_TOKENS, BENCH_HIDDEN), &cpu)?;
+
+    // Warm caches and the allocator before timing
+    forward_pass(&weights, &hidden, layers)?;
+    let mut best = Duration::MAX;
+    for _ in 0..BENCH_PASSES {
+        let start = Instant::now();
+        forward_pass(&weights, &hidden, layers)?;
+        best = best.min(start.elapsed());
+    }
+
+    let total_ms = (best.as_secs_f64() * 1000.0).max(f64::EPSILON);
+    let ms_per_layer = total_ms / layers as f64;
+    Ok(BenchResult {
+        workload_version: WORKLOAD_VERSION,
+        device,
+        layers,
+        tokens: BENCH_TOKENS,
+        ms_per_layer,
+        tokens_per_sec: BENCH_TOKENS as f64 * 1000.0 / total_ms,
+        capacity_score: score(ms_per_layer),
+        measured_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+    })
+}
+
+/// Map time per layer onto 1..=100, with the reference speed at 50
+fn score(ms_per_layer: f64) -> u32 {
+    (50.0 * REFERENCE_MS_PER_LAYER / ms_per_layer).round().clamp(1.0, 100.0) as u32
+}
+
+/// Cached result in `data_dir`, if it was measured on `device` with the
+/// current workload
+pub fn load(data_dir: &Path, device: &str) -> Option<BenchResult> {
+    let bytes = fs::read(data_dir.join(BENCH_FILE)).ok()?;
+    let result: BenchResult = serde_json::from_slice(&bytes).ok()?;
+    (result.workload_version == WORKLOAD_VERSION && result.device == device).then_some(result)
+}
+
+pub fn save(data_dir: &Path, result: &BenchResult) -> io::Result<()> {
+    fs::create_dir_all(data_dir)?;
+    let json = serde_json::to_vec_pretty(result).map_err(io::Error::other)?;
+    fs::write(data_dir.join(BENCH_FILE), json)
+}
+
+/// Cached result for `device`, measuring and saving a new one if needed.
+/// `None` if the workload could not run on this device
+pub fn load_or_run(data_dir: &Path, device: String) -> Option<BenchResult> {
+    if let Some(result) = load(data_dir, &device) {
+        return Some(result);
+    }
+    let result = match run(DEFAULT_BENCH_LAYERS, device) {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("Benchmark failed, keeping the detected capacity score: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = save(data_dir, &result) {
+        tracing::warn!("Failed to save benchmark to {}: {}", data_dir.display(), e);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_result_is_cached() {
+        let dir = std::env::temp_dir().join(format!("cortex-peer-bench-{}", uuid::Uuid::new_v4()));
+
+        let result = run(2, "test-cpu x4".into()).unwrap();
+        assert_eq!(result.layers, 2);
+        assert!(result.ms_per_layer > 0.0 && result.tokens_per_sec > 0.0);
+        assert!((1..=100).contains(&result.capacity_score));
+
+        save(&dir, &result).unwrap();
+        let cached = load_or_run(&dir, "test-cpu x4".into()).unwrap();
+        assert_eq!(cached.measured_at, result.measured_at);
+        assert_eq!(cached.ms_per_layer, result.ms_per_layer);
+        // Other hardware must re-measure
+        assert!(load(&dir, "other-cpu x8").is_none());
+
+        assert_eq!(score(REFERENCE_MS_PER_LAYER), 50);
+        assert_eq!(score(REFERENCE_MS_PER_LAYER * 1000.0), 1);
+        assert_eq!(score(REFERENCE_MS_PER_LAYER / 1000.0), 100);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
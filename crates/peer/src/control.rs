@@ -0,0 +1,173 @@
+//! Scripting control API for the peer
+//!
+//! Served under `/control` by the UI server so orchestration tooling can
+//! pause and resume contribution, read counters and resize the task queue
+//! without going through the dashboard. Only loopback clients may call it,
+//! unless a control token is configured, in which case remote clients
+//! presenting it as `Authorization: Bearer <token>` may too.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use cortex_core::task_queue::QueueStats;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::pool::PoolStats;
+use crate::ui::UiState;
+use crate::PeerStats;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ControlStats {
+    #[serde(flatten)]
+    pub stats: PeerStats,
+    pub contributing: bool,
+    pub max_queue: usize,
+    pub queue: QueueStats,
+    pub pool: PoolStats,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContributionState {
+    pub contributing: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ControlConfig {
+    pub max_queue: usize,
+}
+
+/// Whether a caller at `addr` sending `headers` may use the control API
+fn is_authorized(addr: &SocketAddr, headers: &HeaderMap, token: Option<&str>) -> bool {
+    if addr.ip().is_loopback() {
+        return true;
+    }
+    let Some(token) = token else {
+        return false;
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        // blake3 hashes compare in constant time
+        .is_some_and(|presented| blake3::hash(presented.as_bytes()) == blake3::hash(token.as_bytes()))
+}
+
+/// Middleware guarding every `/control` route
+pub async fn authorize(
+    State(state): State<Arc<UiState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !is_authorized(&addr, request.headers(), state.control_token.as_deref()) {
+        warn!("Rejected control API call from {}", addr);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(next.run(request).await)
+}
+
+/// `POST /control/pause`: stop taking chunks off the queue
+pub async fn pause(State(state): State<Arc<UiState>>) -> Json<ContributionState> {
+    set_contributing(&state, false).await
+}
+
+/// `POST /control/resume`
+pub async fn resume(State(state): State<Arc<UiState>>) -> Json<ContributionState> {
+    set_contributing(&state, true).await
+}
+
+async fn set_contributing(state: &UiState, contributing: bool) -> Json<ContributionState> {
+    *state.peer_state.is_active.write().await = contributing;
+    info!("🔄 Contribution {} via control API", if contributing { "resumed" } else { "paused" });
+    Json(ContributionState { contributing })
+}
+
+/// `GET /control/stats`
+pub async fn stats(State(state): State<Arc<UiState>>) -> Json<ControlStats> {
+    let peer = &state.peer_state;
+    let mut stats = peer.stats.read().await.clone();
+    stats.uptime_seconds = peer.started_at.elapsed().as_secs();
+    Json(ControlStats {
+        stats,
+        contributing: *peer.is_active.read().await,
+        max_queue: peer.task_queue.max_queue_size(),
+        queue: peer.task_queue.stats().await,
+        pool: peer.pool.stats().await,
+    })
+}
+
+/// `POST /control/config {"max_queue": n}`: resize the task queue live
+pub async fn config(
+    State(state): State<Arc<UiState>>,
+    Json(config): Json<ControlConfig>,
+) -> Result<Json<ControlConfig>, (StatusCode, String)> {
+    if config.max_queue == 0 {
+        return Err((StatusCode::BAD_REQUEST, "max_queue must be at least 1".to_string()));
+    }
+    state.peer_state.task_queue.set_max_queue_size(config.max_queue);
+    info!("⚙️ Max queue set to {} via control API", config.max_queue);
+    Ok(Json(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatStore;
+    use crate::PeerState;
+    use cortex_core::DeviceCapabilities;
+    use cortex_grid::{NodeId, PeerStore};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_control_endpoints() {
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let state = Arc::new(UiState {
+            peer_state: Arc::new(PeerState::new(NodeId::random(), DeviceCapabilities::detect(), 10, peer_store)),
+            chat_store: Arc::new(RwLock::new(ChatStore::new("test"))),
+            control_token: None,
+        });
+        state.peer_state.stats.write().await.tasks_processed = 3;
+
+        assert!(!pause(State(Arc::clone(&state))).await.contributing);
+        assert!(!*state.peer_state.is_active.read().await);
+
+        let Json(current) = stats(State(Arc::clone(&state))).await;
+        assert_eq!((current.stats.tasks_processed, current.contributing, current.max_queue), (3, false, 10));
+        let json = serde_json::to_value(&current).unwrap();
+        assert_eq!(json["tasks_processed"], 3);
+
+        assert!(resume(State(Arc::clone(&state))).await.contributing);
+        assert!(*state.peer_state.is_active.read().await);
+
+        let Json(applied) = config(State(Arc::clone(&state)), Json(ControlConfig { max_queue: 2 })).await.unwrap();
+        assert_eq!(applied.max_queue, 2);
+        assert_eq!(state.peer_state.task_queue.max_queue_size(), 2);
+        let rejected = config(State(Arc::clone(&state)), Json(ControlConfig { max_queue: 0 })).await;
+        assert_eq!(rejected.unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_control_access() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+        let mut bearer = HeaderMap::new();
+        bearer.insert(header::AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        let mut wrong = HeaderMap::new();
+        wrong.insert(header::AUTHORIZATION, "Bearer guess".parse().unwrap());
+
+        assert!(is_authorized(&local, &HeaderMap::new(), None));
+        assert!(!is_authorized(&remote, &HeaderMap::new(), None));
+        assert!(!is_authorized(&remote, &bearer, None));
+        assert!(is_authorized(&remote, &bearer, Some("s3cret")));
+        assert!(!is_authorized(&remote, &wrong, Some("s3cret")));
+        assert!(!is_authorized(&remote, &HeaderMap::new(), Some("s3cret")));
+    }
+}
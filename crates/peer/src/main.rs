@@ -5,20 +5,37 @@
 //! 
 //! Works on: macOS, Linux, Windows, iOS, Android
 
+mod bench;
 mod chat;
+mod control;
+mod metrics;
+mod pool;
 mod ui;
 
 use clap::Parser;
+use cortex_core::logging::{self, LogFormat};
+use cortex_core::task_queue::AssemblyStatus;
 use cortex_core::{
-    DeviceCapabilities, TaskQueue, TensorChunk, ProcessedChunk,
+    DeviceCapabilities, TaskQueue, TensorChunk, TensorMsg, ProcessedChunk, ResponseAssembler, verification_transform,
+    WireError, WireFormat,
 };
+use cortex_core::wire;
+use pool::{ConnectionPool, POOL_IDLE_TIMEOUT};
 use cortex_grid::{Discovery, LanDiscovery, PeerInfo, PeerStore, NodeId};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, Level};
+use tracing::{debug, error, info, warn, Level};
+
+/// How long to wait for the receiver to acknowledge a result
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the tensor server keeps a silent connection open
+const SERVER_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Largest frame `read_msg` accepts; the length prefix comes from the peer
+const MAX_FRAME_BYTES: u64 = 512 * 1024 * 1024;
 
 #[derive(Parser)]
 #[command(name = "cortex-peer")]
@@ -43,6 +60,33 @@ struct Args {
     /// Maximum queue size (tasks to buffer)
     #[arg(long, default_value = "10")]
     max_queue: usize,
+    
+    /// Log output format: text or json
+    #[arg(long, env = "CORTEX_LOG_FORMAT", default_value = "text")]
+    log_format: LogFormat,
+
+    /// Directory for persistent state such as the benchmark result
+    #[arg(long, env = "CORTEX_PEER_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Re-run the compute benchmark, save the result and exit
+    #[arg(long)]
+    bench: bool,
+
+    /// Preferred encoding for outgoing tensor frames: bincode or json.
+    /// Incoming frames are accepted in either.
+    #[arg(long, env = "CORTEX_WIRE_FORMAT", default_value = "bincode")]
+    wire_format: WireFormat,
+
+    /// Token remote clients must send as `Authorization: Bearer <token>` to
+    /// use the `/control` API. Without one only localhost may use it.
+    #[arg(long, env = "CORTEX_PEER_CONTROL_TOKEN")]
+    control_token: Option<String>,
+
+    /// Layer count of the model being served; chunks asking for layers
+    /// beyond it are refused
+    #[arg(long, env = "CORTEX_MODEL_LAYERS", default_value = "24")]
+    model_layers: u32,
 }
 
 /// Peer state
@@ -53,9 +97,54 @@ pub struct PeerState {
     pub peer_store: Arc<PeerStore>,
     pub is_active: Arc<RwLock<bool>>,
     pub stats: Arc<RwLock<PeerStats>>,
+    pub started_at: Instant,
+    /// Results of chunks we sent out, keyed by task and chunk index
+    pub results: Arc<ResponseAssembler>,
+    /// Connections reused for sending results back
+    pub pool: Arc<ConnectionPool>,
+    /// Encoding tried first for outgoing frames
+    pub wire_format: WireFormat,
+    /// Layers of the served model, the bound for received layer ranges
+    pub model_layers: u32,
+    /// Measured compute, already applied to `capabilities.capacity_score`
+    pub benchmark: Option<bench::BenchResult>,
+}
+
+impl PeerState {
+    pub fn new(node_id: NodeId, capabilities: DeviceCapabilities, max_queue: usize, peer_store: Arc<PeerStore>) -> Self {
+        Self {
+            node_id,
+            capabilities,
+            task_queue: TaskQueue::new(max_queue),
+            peer_store,
+            is_active: Arc::new(RwLock::new(true)),
+            stats: Arc::new(RwLock::new(PeerStats::default())),
+            started_at: Instant::now(),
+            results: Arc::new(ResponseAssembler::new()),
+            pool: Arc::new(ConnectionPool::new(POOL_IDLE_TIMEOUT)),
+            wire_format: WireFormat::default(),
+            model_layers: bench::DEFAULT_BENCH_LAYERS,
+            benchmark: None,
+        }
+    }
+
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.wire_format = wire_format;
+        self
+    }
+
+    pub fn with_model_layers(mut self, model_layers: u32) -> Self {
+        self.model_layers = model_layers;
+        self
+    }
+
+    pub fn with_benchmark(mut self, benchmark: bench::BenchResult) -> Self {
+        self.benchmark = Some(benchmark);
+        self
+    }
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct PeerStats {
     pub tasks_received: u64,
     pub tasks_processed: u64,
@@ -66,19 +155,36 @@ pub struct PeerStats {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .init();
-    
     let args = Args::parse();
     
+    // Initialize logging
+    logging::init(args.log_format, Level::INFO, false);
+    
     // Generate node ID
     let node_id = NodeId::random();
     
     // Detect REAL device capabilities
-    let capabilities = DeviceCapabilities::detect();
+    let mut capabilities = DeviceCapabilities::detect();
+    let data_dir = args.data_dir.clone().unwrap_or_else(bench::default_data_dir);
+    let device = bench::device_fingerprint(&capabilities);
+
+    if args.bench {
+        let result = bench::run(bench::DEFAULT_BENCH_LAYERS, device)?;
+        bench::save(&data_dir, &result)?;
+        println!("Layers:         {}", result.layers);
+        println!("ms/layer:       {:.3}", result.ms_per_layer);
+        println!("Tokens/sec:     {:.1}", result.tokens_per_sec);
+        println!("Capacity score: {}/100", result.capacity_score);
+        println!("Saved to {}", data_dir.join(bench::BENCH_FILE).display());
+        return Ok(());
+    }
+
+    // The measured score is what peers schedule on, so it replaces the
+    // spec-based estimate
+    let benchmark = bench::load_or_run(&data_dir, device);
+    if let Some(ref benchmark) = benchmark {
+        capabilities.capacity_score = benchmark.capacity_score;
+    }
     
     println!("\n╔══════════════════════════════════════════════════════════════╗");
     println!("║               🧠 CortexOS Distributed AI Peer                ║");
@@ -94,24 +200,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("   GPU: {} ({} MB VRAM)", gpu.model, gpu.vram_mb);
     }
     info!("   Capacity Score: {}/100", capabilities.capacity_score);
+    if let Some(ref benchmark) = benchmark {
+        info!("   Benchmark: {:.3} ms/layer, {:.1} tokens/sec", benchmark.ms_per_layer, benchmark.tokens_per_sec);
+    }
     info!("   Max Layers: {}", capabilities.max_layers);
     info!("");
     info!("🆔 Node ID: {}", node_id);
     info!("🔌 P2P Port: {}", args.port);
-    info!("📡 Tensor Port: {}", args.tensor_port);
+    info!("📡 Tensor Port: {} ({} preferred)", args.tensor_port, args.wire_format);
     info!("");
     
     // Create peer state
     let peer_store = Arc::new(PeerStore::new(Duration::from_secs(300)));
     
-    let state = Arc::new(PeerState {
-        node_id: node_id.clone(),
-        capabilities: capabilities.clone(),
-        task_queue: TaskQueue::new(args.max_queue),
-        peer_store: Arc::clone(&peer_store),
-        is_active: Arc::new(RwLock::new(true)),
-        stats: Arc::new(RwLock::new(PeerStats::default())),
-    });
+    let mut peer_state = PeerState::new(node_id, capabilities.clone(), args.max_queue, Arc::clone(&peer_store))
+        .with_wire_format(args.wire_format)
+        .with_model_layers(args.model_layers);
+    if let Some(benchmark) = benchmark {
+        peer_state = peer_state.with_benchmark(benchmark);
+    }
+    let state = Arc::new(peer_state);
     
     // Start discovery
     let pubkey = [0u8; 32]; // Placeholder pubkey
@@ -162,6 +270,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ui_state = Arc::new(ui::UiState {
         peer_state: Arc::clone(&state),
         chat_store: Arc::clone(&chat_store),
+        control_token: args.control_token.clone(),
     });
     let ui_port = args.ui_port;
     tokio::spawn(async move {
@@ -177,10 +286,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Print status periodically
     loop {
         tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        state.pool.evict_idle().await;
         
         let peers = state.peer_store.list_active().await;
         let queue_stats = state.task_queue.stats().await;
-        let stats = state.stats.read().await;
+        let mut stats = state.stats.write().await;
+        stats.uptime_seconds = state.started_at.elapsed().as_secs();
         
         info!("📊 Status: {} peers | Queue: {} | Processed: {} | Received: {} bytes",
               peers.len(),
@@ -211,42 +322,113 @@ async fn run_tensor_server(
     }
 }
 
-/// Handle incoming tensor stream
+/// Handle incoming tensor stream. Senders may keep the connection open
+/// for several frames; it is closed on EOF or after `SERVER_IDLE_TIMEOUT`.
 async fn handle_tensor_connection(
     state: Arc<PeerState>,
     mut stream: TcpStream,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Read message length
+    loop {
+        let (message, format, len) = match tokio::time::timeout(SERVER_IDLE_TIMEOUT, read_msg(&mut stream)).await {
+            Ok(Ok(frame)) => frame,
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            // The frame was read whole but we can't decode it: say which
+            // formats we accept instead of guessing
+            Ok(Err(e)) if wire_error(&e).is_some() => {
+                warn!("Rejecting unreadable tensor frame: {}", e);
+                write_frame(&mut stream, &wire::encode_reject(&WireFormat::ALL)).await?;
+                continue;
+            }
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Ok(()),
+        };
+        if handle_tensor_msg(&state, message, len).await? {
+            write_msg(&mut stream, &TensorMsg::Ack, format).await?;
+        }
+    }
+}
+
+/// Apply one received frame, returning whether it should be acknowledged
+async fn handle_tensor_msg(
+    state: &PeerState,
+    message: TensorMsg,
+    len: usize,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    match message {
+        TensorMsg::Chunk(chunk) => {
+            {
+                let mut stats = state.stats.write().await;
+                stats.bytes_received += len as u64;
+                stats.tasks_received += 1;
+            }
+            info!("📦 Received chunk {}/{} for task {} (layers {}-{})",
+                  chunk.chunk_idx, chunk.total_chunks,
+                  &chunk.task_id[..8.min(chunk.task_id.len())],
+                  chunk.start_layer, chunk.end_layer);
+
+            // Processing cost grows with the layer range, so refuse ranges
+            // the model does not have before doing any work
+            chunk.check_layers(state.model_layers)?;
+
+            // Enqueue for processing
+            state.task_queue.enqueue(chunk).await?;
+            Ok(true)
+        }
+        TensorMsg::Result(result) => {
+            state.stats.write().await.bytes_received += len as u64;
+            let (task_id, chunk_idx) = (result.task_id.clone(), result.chunk_idx);
+            match state.results.add_chunk(result).await {
+                AssemblyStatus::Rejected { .. } => {
+                    warn!("Rejected result for chunk {} of task {}", chunk_idx, task_id);
+                }
+                status => debug!("📬 Result for chunk {} of task {}: {:?}", chunk_idx, task_id, status),
+            }
+            Ok(true)
+        }
+        TensorMsg::Ack => {
+            debug!("Ignoring unsolicited ack");
+            Ok(false)
+        }
+    }
+}
+
+/// Write one length-prefixed (u64 LE) frame in `format`, returning bytes
+/// written
+async fn write_msg(stream: &mut TcpStream, message: &TensorMsg, format: WireFormat) -> std::io::Result<usize> {
+    let frame = format.encode(message).map_err(std::io::Error::other)?;
+    write_frame(stream, &frame).await
+}
+
+async fn write_frame(stream: &mut TcpStream, frame: &[u8]) -> std::io::Result<usize> {
+    stream.write_all(&(frame.len() as u64).to_le_bytes()).await?;
+    stream.write_all(frame).await?;
+    Ok(frame.len() + 8)
+}
+
+/// Read one frame, returning the message, the format it was sent in and
+/// its length. Undecodable frames fail with an `InvalidData` error
+/// wrapping the `WireError`, frames over `MAX_FRAME_BYTES` with a plain
+/// `InvalidData` error before anything is allocated.
+async fn read_msg(stream: &mut TcpStream) -> std::io::Result<(TensorMsg, WireFormat, usize)> {
     let mut len_buf = [0u8; 8];
     stream.read_exact(&mut len_buf).await?;
-    let len = u64::from_le_bytes(len_buf) as usize;
-    
-    // Read message
+    let len = u64::from_le_bytes(len_buf);
+    if len > MAX_FRAME_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the {} byte limit", len, MAX_FRAME_BYTES),
+        ));
+    }
+    let len = len as usize;
+
     let mut data = vec![0u8; len];
     stream.read_exact(&mut data).await?;
-    
-    // Update stats
-    {
-        let mut stats = state.stats.write().await;
-        stats.bytes_received += len as u64;
-        stats.tasks_received += 1;
-    }
-    
-    // Deserialize chunk
-    let chunk: TensorChunk = bincode::deserialize(&data)?;
-    info!("📦 Received chunk {}/{} for task {} (layers {}-{})",
-          chunk.chunk_idx, chunk.total_chunks, 
-          &chunk.task_id[..8.min(chunk.task_id.len())],
-          chunk.start_layer, chunk.end_layer);
-    
-    // Enqueue for processing
-    state.task_queue.enqueue(chunk).await?;
-    
-    // Send acknowledgment
-    let ack = b"ACK";
-    stream.write_all(ack).await?;
-    
-    Ok(())
+    let (message, format) = wire::decode(&data).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok((message, format, len))
+}
+
+fn wire_error(e: &std::io::Error) -> Option<&WireError> {
+    e.get_ref().and_then(|inner| inner.downcast_ref::<WireError>())
 }
 
 /// Process tasks from the queue
@@ -263,14 +445,25 @@ async fn run_task_processor(state: Arc<PeerState>) {
         
         // Get next task
         if let Some(chunk) = state.task_queue.dequeue().await {
-            let start = std::time::Instant::now();
+            let start = Instant::now();
             
             info!("🔧 Processing chunk {}/{} (layers {}-{})",
                   chunk.chunk_idx, chunk.total_chunks,
                   chunk.start_layer, chunk.end_layer);
             
-            // Process the chunk through our layers
-            let result_data = process_chunk(&state.capabilities, &chunk).await;
+            // Process the chunk through our layers, off the async workers
+            let (chunk, result_data) = match tokio::task::spawn_blocking(move || {
+                let result_data = process_chunk(&chunk);
+                (chunk, result_data)
+            })
+            .await
+            {
+                Ok(processed) => processed,
+                Err(e) => {
+                    error!("Chunk processing panicked: {}", e);
+                    continue;
+                }
+            };
             
             let processing_time = start.elapsed().as_millis() as u64;
             
@@ -297,45 +490,35 @@ async fn run_task_processor(state: Arc<PeerState>) {
             info!("✅ Chunk processed in {}ms", processing_time);
             
             // Send result back to source
-            if let Err(e) = send_result_back(&chunk.source_node, &processed).await {
-                error!("Failed to send result: {}", e);
-            }
+            let sent = match send_result_back(&state.pool, &chunk.source_node, &processed, state.wire_format).await {
+                Ok(sent) => sent as u64,
+                Err(e) => {
+                    error!("Failed to send result: {}", e);
+                    0
+                }
+            };
+            state.stats.write().await.bytes_sent += sent;
         }
     }
 }
 
 /// Process a tensor chunk through assigned layers
-async fn process_chunk(
-    capabilities: &DeviceCapabilities,
-    chunk: &TensorChunk,
-) -> Vec<u8> {
-    // In production, this would:
-    // 1. Load the assigned layers of the model
-    // 2. Run the tensor through those layers
-    // 3. Return the processed tensor
-    
-    // For now, simulate processing based on device capability
-    let layers = chunk.end_layer - chunk.start_layer + 1;
-    let process_time_ms = (layers as u64 * 50) / (capabilities.capacity_score as u64 + 1);
-    
-    tokio::time::sleep(tokio::time::Duration::from_millis(process_time_ms)).await;
-    
-    // Return "processed" data (in real impl, actual tensor computation)
-    let mut result = chunk.tensor_data.clone();
-    
-    // Simulate transformation
-    for byte in result.iter_mut() {
-        *byte = byte.wrapping_add(1);
-    }
-    
-    result
+fn process_chunk(chunk: &TensorChunk) -> Vec<u8> {
+    // Until real layer execution lands, apply the deterministic verification
+    // transform so the requester can check the result end-to-end. The layer
+    // range was checked against `model_layers` when the chunk arrived.
+    verification_transform(chunk)
 }
 
-/// Send processed result back to the requesting node
+/// Send processed result back to the requesting node over a pooled
+/// connection, returning bytes sent. If the receiver rejects `format`, a
+/// format it supports is negotiated and the result is sent once more.
 async fn send_result_back(
+    pool: &ConnectionPool,
     source_addr: &str,
     result: &ProcessedChunk,
-) -> Result<(), Box<dyn std::error::Error>> {
+    format: WireFormat,
+) -> Result<usize, Box<dyn std::error::Error>> {
     // Parse address and connect
     let addr = if source_addr.contains(':') {
         source_addr.to_string()
@@ -343,18 +526,48 @@ async fn send_result_back(
         format!("{}:9000", source_addr)
     };
     
-    let mut stream = TcpStream::connect(&addr).await?;
-    
-    // Serialize and send
-    let data = bincode::serialize(result)?;
-    let len = data.len() as u64;
-    
-    stream.write_all(&len.to_le_bytes()).await?;
-    stream.write_all(&data).await?;
+    let message = TensorMsg::Result(result.clone());
+    let (mut stream, reused) = pool.checkout(&addr).await?;
+    let attempt = match exchange(&mut stream, &message, format).await {
+        // The receiver may have closed an idle pooled stream; retry once fresh
+        Err(e) if reused && wire_error(&e).is_none() => {
+            debug!("Pooled connection to {} failed ({}), reconnecting", addr, e);
+            stream = pool.connect(&addr).await?;
+            exchange(&mut stream, &message, format).await
+        }
+        attempt => attempt,
+    };
+    let sent = match attempt {
+        Ok(sent) => sent,
+        Err(e) => match wire_error(&e) {
+            Some(WireError::Rejected { supported }) => {
+                let mut preferred = vec![format];
+                preferred.extend(WireFormat::ALL);
+                let Some(fallback) = WireFormat::negotiate(&preferred, supported).filter(|f| *f != format) else {
+                    return Err(e.into());
+                };
+                warn!("{} rejected {} frames, falling back to {}", addr, format, fallback);
+                exchange(&mut stream, &message, fallback).await?
+            }
+            _ => return Err(e.into()),
+        },
+    };
+    pool.checkin(&addr, stream).await;
     
     info!("📤 Sent result back to {}", addr);
     
-    Ok(())
+    Ok(sent)
+}
+
+/// Send `message` and wait for its ack
+async fn exchange(stream: &mut TcpStream, message: &TensorMsg, format: WireFormat) -> std::io::Result<usize> {
+    let sent = write_msg(stream, message, format).await?;
+    match tokio::time::timeout(ACK_TIMEOUT, read_msg(stream)).await {
+        Ok(Ok((TensorMsg::Ack, _, _))) => Ok(sent),
+        Ok(Ok(_)) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "expected an ack")),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "no ack received")),
+    }
 }
 
 #[cfg(test)]
@@ -375,5 +588,156 @@ mod tests {
         assert!(caps.capacity_score > 0);
         assert!(caps.max_layers > 0);
     }
-}
 
+    #[tokio::test]
+    async fn test_results_are_not_enqueued_as_work() {
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let state = Arc::new(PeerState::new(NodeId::random(), DeviceCapabilities::detect(), 10, peer_store));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&server_state);
+                tokio::spawn(async move { handle_tensor_connection(state, stream).await.is_ok() });
+            }
+        });
+
+        let chunk = TensorChunk {
+            task_id: "correlation-task".to_string(),
+            chunk_idx: 1,
+            total_chunks: 2,
+            start_layer: 0,
+            end_layer: 3,
+            tensor_data: vec![7; 16],
+            shape: vec![1, 4],
+            dtype: "f32".to_string(),
+            source_node: addr.clone(),
+            priority: 0,
+            created_at: 0,
+        };
+        let mut stream = TcpStream::connect(&addr).await.unwrap();
+        write_msg(&mut stream, &TensorMsg::Chunk(chunk.clone()), WireFormat::Json).await.unwrap();
+        let (ack, format, _) = read_msg(&mut stream).await.unwrap();
+        assert!(matches!(ack, TensorMsg::Ack));
+        assert_eq!(format, WireFormat::Json, "acks answer in the sender's format");
+        assert_eq!(state.task_queue.len().await, 1);
+
+        state.results.register_task(&chunk.task_id, 2).await;
+        let result = ProcessedChunk {
+            task_id: chunk.task_id.clone(),
+            chunk_idx: 1,
+            total_chunks: 2,
+            result_data: process_chunk(&chunk),
+            result_shape: chunk.shape.clone(),
+            processing_time_ms: 1,
+            processor_node: "remote".to_string(),
+        };
+        send_result_back(&state.pool, &addr, &result, WireFormat::Bincode).await.unwrap();
+
+        assert_eq!(state.task_queue.len().await, 1, "a result must not become new work");
+        assert_eq!(state.results.missing_chunks(&chunk.task_id).await, vec![0]);
+
+        // Further results to the same node reuse the pooled connection
+        let result = ProcessedChunk { chunk_idx: 0, ..result };
+        send_result_back(&state.pool, &addr, &result, WireFormat::Bincode).await.unwrap();
+        send_result_back(&state.pool, &addr, &result, WireFormat::Bincode).await.unwrap();
+        let pool = state.pool.stats().await;
+        assert_eq!((pool.opened, pool.reused, pool.open_connections), (1, 2, 1));
+        assert!(state.results.is_complete(&chunk.task_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_beyond_model_layers_is_refused() {
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let state = PeerState::new(NodeId::random(), DeviceCapabilities::detect(), 10, peer_store).with_model_layers(4);
+        let chunk = TensorChunk {
+            task_id: "layer-task".to_string(),
+            chunk_idx: 0,
+            total_chunks: 1,
+            start_layer: 0,
+            end_layer: u32::MAX,
+            tensor_data: vec![7; 16],
+            shape: vec![1, 4],
+            dtype: "f32".to_string(),
+            source_node: String::new(),
+            priority: 0,
+            created_at: 0,
+        };
+        assert!(handle_tensor_msg(&state, TensorMsg::Chunk(chunk.clone()), 16).await.is_err());
+        assert_eq!(state.task_queue.len().await, 0);
+
+        let chunk = TensorChunk { end_layer: 3, ..chunk };
+        assert!(handle_tensor_msg(&state, TensorMsg::Chunk(chunk), 16).await.unwrap());
+        assert_eq!(state.task_queue.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(&u64::MAX.to_le_bytes()).await.unwrap();
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let err = read_msg(&mut stream).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(wire_error(&err).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wire_format_mismatch_is_negotiated() {
+        let peer_store = Arc::new(PeerStore::new(Duration::from_secs(60)));
+        let state = Arc::new(PeerState::new(NodeId::random(), DeviceCapabilities::detect(), 10, peer_store));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_tensor_connection(server_state, stream).await.is_ok()
+        });
+
+        // A frame from a future wire version is answered with a reject
+        // listing what this peer reads, not deserialized into garbage
+        let mut stream = TcpStream::connect(&addr).await.unwrap();
+        let mut frame = WireFormat::Bincode.encode(&TensorMsg::Ack).unwrap();
+        frame[0] = wire::WIRE_VERSION + 1;
+        write_frame(&mut stream, &frame).await.unwrap();
+        let err = read_msg(&mut stream).await.unwrap_err();
+        assert!(matches!(wire_error(&err), Some(WireError::Rejected { supported }) if supported.len() == 2));
+
+        // A receiver that only reads JSON makes the sender fall back
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let json_only = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            loop {
+                let mut len_buf = [0u8; 8];
+                if stream.read_exact(&mut len_buf).await.is_err() {
+                    return;
+                }
+                let mut data = vec![0u8; u64::from_le_bytes(len_buf) as usize];
+                stream.read_exact(&mut data).await.unwrap();
+                if data[1] == WireFormat::Json.code() {
+                    write_msg(&mut stream, &TensorMsg::Ack, WireFormat::Json).await.unwrap();
+                } else {
+                    write_frame(&mut stream, &wire::encode_reject(&[WireFormat::Json])).await.unwrap();
+                }
+            }
+        });
+        let result = ProcessedChunk {
+            task_id: "negotiate".to_string(),
+            chunk_idx: 0,
+            total_chunks: 1,
+            result_data: vec![1, 2, 3],
+            result_shape: vec![1, 3],
+            processing_time_ms: 1,
+            processor_node: "local".to_string(),
+        };
+        let sent = send_result_back(&state.pool, &json_only, &result, WireFormat::Bincode).await.unwrap();
+        let json_len = WireFormat::Json.encode(&TensorMsg::Result(result)).unwrap().len();
+        assert_eq!(sent, json_len + 8);
+    }
+}
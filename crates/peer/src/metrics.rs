@@ -0,0 +1,87 @@
+//! Prometheus metrics for the peer
+//!
+//! Served on `GET /metrics` by the UI server in the text exposition format.
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use cortex_core::metrics::{MetricType, PrometheusEncoder, CONTENT_TYPE};
+use std::sync::Arc;
+
+use crate::ui::UiState;
+use crate::PeerState;
+
+pub async fn metrics(State(state): State<Arc<UiState>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], render(&state.peer_state).await)
+}
+
+/// Collect the current metrics from the peer state
+pub async fn render(state: &PeerState) -> String {
+    let peers = state.peer_store.list_active().await;
+    let queue = state.task_queue.stats().await;
+    let contributing = *state.is_active.read().await;
+    let pool = state.pool.stats().await;
+    let (tasks_received, tasks_processed, bytes_received, bytes_sent) = {
+        let stats = state.stats.read().await;
+        (stats.tasks_received, stats.tasks_processed, stats.bytes_received, stats.bytes_sent)
+    };
+
+    let mut out = PrometheusEncoder::new();
+    out.single(
+        "cortex_peer_uptime_seconds",
+        "Seconds since the peer started",
+        MetricType::Gauge,
+        state.started_at.elapsed().as_secs() as f64,
+    );
+    out.single(
+        "cortex_peer_contributing",
+        "Whether the peer is processing tasks (1) or paused (0)",
+        MetricType::Gauge,
+        if contributing { 1.0 } else { 0.0 },
+    );
+    out.single(
+        "cortex_peer_capacity_score",
+        "Advertised device capacity score (0-100), measured when the benchmark ran",
+        MetricType::Gauge,
+        state.capabilities.capacity_score as f64,
+    );
+    if let Some(benchmark) = &state.benchmark {
+        out.single(
+            "cortex_peer_bench_ms_per_layer",
+            "Benchmarked time per decoder layer in milliseconds",
+            MetricType::Gauge,
+            benchmark.ms_per_layer,
+        );
+    }
+
+    out.single("cortex_peer_peers", "Active peers", MetricType::Gauge, peers.len() as f64);
+    out.family(
+        "cortex_peer_latency_ms",
+        "Last measured round-trip latency per peer",
+        MetricType::Gauge,
+    );
+    for peer in &peers {
+        if let Some(latency) = peer.latency_ms {
+            let peer_id = peer.node_id.to_string();
+            out.sample("cortex_peer_latency_ms", &[("peer", &peer_id)], latency as f64);
+        }
+    }
+
+    out.single("cortex_peer_tasks_received_total", "Tensor chunks received", MetricType::Counter, tasks_received as f64);
+    out.single("cortex_peer_tasks_processed_total", "Tensor chunks processed", MetricType::Counter, tasks_processed as f64);
+    out.single("cortex_peer_bytes_received_total", "Tensor bytes received", MetricType::Counter, bytes_received as f64);
+    out.single("cortex_peer_bytes_sent_total", "Result bytes sent", MetricType::Counter, bytes_sent as f64);
+
+    out.single("cortex_peer_queue_depth", "Chunks waiting in the task queue", MetricType::Gauge, queue.current_queue_size as f64);
+    out.single("cortex_peer_queue_received_total", "Chunks accepted into the queue", MetricType::Counter, queue.total_received as f64);
+    out.single("cortex_peer_queue_processed_total", "Chunks completed by the queue", MetricType::Counter, queue.total_processed as f64);
+    out.single("cortex_peer_queue_dropped_total", "Chunks rejected because the queue was full", MetricType::Counter, queue.total_dropped as f64);
+    out.single(
+        "cortex_peer_queue_processing_ms",
+        "Average chunk processing time",
+        MetricType::Gauge,
+        queue.average_processing_ms as f64,
+    );
+    out.single("cortex_peer_pool_connections", "Idle pooled result connections", MetricType::Gauge, pool.open_connections as f64);
+    out.single("cortex_peer_pool_opened_total", "Result connections opened", MetricType::Counter, pool.opened as f64);
+    out.single("cortex_peer_pool_reused_total", "Result sends over a reused connection", MetricType::Counter, pool.reused as f64);
+    out.finish()
+}
@@ -0,0 +1,153 @@
+//! Reusable connections for the result path
+//!
+//! Results for a multi-chunk task all go back to the same source node, so
+//! instead of a TCP handshake per result the streams are kept open per
+//! destination address and handed out again until they sit idle for longer
+//! than the pool's idle timeout.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// How long an unused connection stays in the pool. Kept below the tensor
+/// server's idle timeout so the client side closes first.
+pub const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Idle connections currently held
+    pub open_connections: usize,
+    /// Connections established
+    pub opened: u64,
+    /// Checkouts served by an existing connection
+    pub reused: u64,
+    /// `reused / (opened + reused)`
+    pub reuse_ratio: f64,
+}
+
+pub struct ConnectionPool {
+    idle: Mutex<HashMap<String, Vec<(TcpStream, Instant)>>>,
+    idle_timeout: Duration,
+    opened: AtomicU64,
+    reused: AtomicU64,
+}
+
+impl ConnectionPool {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle: Mutex::new(HashMap::new()),
+            idle_timeout,
+            opened: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+        }
+    }
+
+    /// A stream to `addr` and whether it was reused. A reused stream may
+    /// have been closed by the other side since it was returned.
+    pub async fn checkout(&self, addr: &str) -> io::Result<(TcpStream, bool)> {
+        let reusable = {
+            let mut idle = self.idle.lock().await;
+            let streams = idle.entry(addr.to_string()).or_default();
+            streams.retain(|(_, since)| since.elapsed() < self.idle_timeout);
+            let stream = streams.pop().map(|(stream, _)| stream);
+            if streams.is_empty() {
+                idle.remove(addr);
+            }
+            stream
+        };
+        if let Some(stream) = reusable {
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            return Ok((stream, true));
+        }
+        Ok((self.connect(addr).await?, false))
+    }
+
+    /// Open a fresh stream to `addr`, bypassing the idle connections
+    pub async fn connect(&self, addr: &str) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect(addr).await?;
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        debug!("🔌 Opened pooled connection to {}", addr);
+        Ok(stream)
+    }
+
+    /// Return a stream that is still usable
+    pub async fn checkin(&self, addr: &str, stream: TcpStream) {
+        self.idle
+            .lock()
+            .await
+            .entry(addr.to_string())
+            .or_default()
+            .push((stream, Instant::now()));
+    }
+
+    /// Close connections idle for longer than the timeout, returning how many
+    pub async fn evict_idle(&self) -> usize {
+        let mut idle = self.idle.lock().await;
+        let mut evicted = 0;
+        idle.retain(|_, streams| {
+            let before = streams.len();
+            streams.retain(|(_, since)| since.elapsed() < self.idle_timeout);
+            evicted += before - streams.len();
+            !streams.is_empty()
+        });
+        evicted
+    }
+
+    pub async fn stats(&self) -> PoolStats {
+        let open_connections = self.idle.lock().await.values().map(Vec::len).sum();
+        let opened = self.opened.load(Ordering::Relaxed);
+        let reused = self.reused.load(Ordering::Relaxed);
+        let total = opened + reused;
+        PoolStats {
+            open_connections,
+            opened,
+            reused,
+            reuse_ratio: if total == 0 { 0.0 } else { reused as f64 / total as f64 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_pool_reuses_and_evicts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::Relaxed);
+                held.push(stream);
+            }
+        });
+
+        let pool = ConnectionPool::new(Duration::from_millis(100));
+        for _ in 0..3 {
+            let (stream, _) = pool.checkout(&addr).await.unwrap();
+            pool.checkin(&addr, stream).await;
+        }
+        let stats = pool.stats().await;
+        assert_eq!((stats.open_connections, stats.opened, stats.reused), (1, 1, 2));
+        assert!((stats.reuse_ratio - 2.0 / 3.0).abs() < 1e-9);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(pool.evict_idle().await, 1);
+        assert_eq!(pool.stats().await.open_connections, 0);
+        let (_, reused) = pool.checkout(&addr).await.unwrap();
+        assert!(!reused);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::Relaxed), 2);
+    }
+}
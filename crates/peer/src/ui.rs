@@ -1,14 +1,17 @@
 //! Peer Web UI
 //! 
-//! Dashboard with stats, contribute toggle, and P2P chat.
+//! Dashboard with stats, contribute toggle, and P2P chat, plus the
+//! `/control` scripting API.
 
 use axum::{
     extract::State,
+    middleware,
     response::Html,
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
@@ -20,6 +23,8 @@ use crate::PeerState;
 pub struct UiState {
     pub peer_state: Arc<PeerState>,
     pub chat_store: Arc<RwLock<ChatStore>>,
+    /// Lets remote clients use `/control`; see `control::authorize`
+    pub control_token: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -39,6 +44,8 @@ struct DeviceInfo {
     ram_available_mb: u64,
     gpu: Option<String>,
     capacity_score: u32,
+    /// Benchmarked time per layer, see `bench`
+    ms_per_layer: Option<f64>,
     max_layers: u32,
 }
 
@@ -70,6 +77,16 @@ pub struct ToggleRequest {
 
 /// Create the UI router
 pub fn create_router(state: Arc<UiState>) -> Router {
+    let control = Router::new()
+        .route("/control/pause", post(crate::control::pause))
+        .route("/control/resume", post(crate::control::resume))
+        .route("/control/stats", get(crate::control::stats))
+        .route("/control/config", post(crate::control::config))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            crate::control::authorize,
+        ));
+
     Router::new()
         .route("/", get(dashboard))
         .route("/api/status", get(get_status))
@@ -77,6 +94,8 @@ pub fn create_router(state: Arc<UiState>) -> Router {
         .route("/api/chat", get(get_chat))
         .route("/api/chat/send", post(send_chat))
         .route("/api/chat/name", post(set_name))
+        .route("/metrics", get(crate::metrics::metrics))
+        .merge(control)
         .with_state(state)
 }
 
@@ -102,6 +121,7 @@ async fn get_status(State(state): State<Arc<UiState>>) -> Json<StatusResponse> {
             ram_available_mb: caps.memory.available_mb,
             gpu: caps.gpu.as_ref().map(|g| format!("{} ({} MB)", g.model, g.vram_mb)),
             capacity_score: caps.capacity_score,
+            ms_per_layer: state.peer_state.benchmark.as_ref().map(|b| b.ms_per_layer),
             max_layers: caps.max_layers,
         },
         network: NetworkInfo {
@@ -206,7 +226,8 @@ pub async fn start_ui_server(state: Arc<UiState>, port: u16) -> Result<(), Box<d
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     info!("🌐 Peer UI available at http://localhost:{}", port);
     
-    axum::serve(listener, app).await?;
+    // The control API checks the caller's address
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
     
     Ok(())
 }
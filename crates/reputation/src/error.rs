@@ -30,6 +30,10 @@ pub enum ReputationError {
     #[error("Trust computation failed: {0}")]
     TrustComputationFailed(String),
 
+    /// Gossip message or rating signature did not verify
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
     /// Gossip protocol error
     #[error("Gossip error: {0}")]
     GossipError(String),
@@ -37,6 +41,10 @@ pub enum ReputationError {
     /// Failed to serialize or deserialize reputation data
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    /// Reading or writing a trust snapshot failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 /// Convenience Result type for reputation operations
@@ -1,14 +1,15 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use cortex_grid::NodeId;
 
 use crate::rating::RatingRecord;
 use crate::trust::TrustGraph;
-use crate::error::Result;
+use crate::error::{ReputationError, Result};
 
 /// Messages for reputation gossip protocol
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,25 +51,53 @@ pub enum GossipMessage {
     },
 }
 
-/// Gossip protocol for propagating reputation data
+/// A `GossipMessage` signed by the node that sent it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedGossip {
+    pub origin: NodeId,
+    pub message: GossipMessage,
+    pub signature: Vec<u8>,
+}
+
+impl SignedGossip {
+    pub fn sign(message: GossipMessage, key: &SigningKey) -> Self {
+        let origin = NodeId::from_pubkey(&key.verifying_key().to_bytes());
+        let signature = key.sign(&Self::signing_bytes(&origin, &message)).to_bytes().to_vec();
+        Self { origin, message, signature }
+    }
+
+    fn signing_bytes(origin: &NodeId, message: &GossipMessage) -> Vec<u8> {
+        bincode::serialize(&(origin, message)).unwrap_or_default()
+    }
+}
+
+/// Gossip protocol for propagating reputation data.
+///
+/// Every message is signed by its sender and every rating by its rater;
+/// incoming messages that fail `verify`, and ratings that fail
+/// `RatingRecord::verify`, are dropped before reaching the trust graph.
 pub struct ReputationGossip {
     my_id: NodeId,
+    signing_key: Arc<SigningKey>,
     graph: Arc<RwLock<TrustGraph>>,
-    outbound_tx: mpsc::Sender<(NodeId, GossipMessage)>,
+    outbound_tx: mpsc::Sender<(NodeId, SignedGossip)>,
     seen_ratings: Arc<RwLock<HashSet<[u8; 32]>>>,
     running: Arc<RwLock<bool>>,
 }
 
 impl ReputationGossip {
+    /// Gossip as the node owning `signing_key`; its id is derived from the key
     pub fn new(
-        my_id: NodeId,
+        signing_key: SigningKey,
         graph: Arc<RwLock<TrustGraph>>,
-    ) -> (Self, mpsc::Receiver<(NodeId, GossipMessage)>) {
+    ) -> (Self, mpsc::Receiver<(NodeId, SignedGossip)>) {
         let (tx, rx) = mpsc::channel(256);
+        let my_id = NodeId::from_pubkey(&signing_key.verifying_key().to_bytes());
 
         (
             Self {
                 my_id,
+                signing_key: Arc::new(signing_key),
                 graph,
                 outbound_tx: tx,
                 seen_ratings: Arc::new(RwLock::new(HashSet::new())),
@@ -91,8 +120,44 @@ impl ReputationGossip {
         info!("Reputation gossip stopped");
     }
 
-    /// Handle incoming gossip message
-    pub async fn handle_message(&self, from: NodeId, msg: GossipMessage) -> Result<Option<GossipMessage>> {
+    pub fn node_id(&self) -> NodeId {
+        self.my_id
+    }
+
+    /// Check that `msg` was signed by the holder of `pubkey` and that the
+    /// key is really the origin's (`NodeId::from_pubkey`)
+    pub fn verify(msg: &SignedGossip, pubkey: &[u8; 32]) -> Result<()> {
+        let invalid = |reason: &str| ReputationError::InvalidSignature(format!("{}: {}", msg.origin, reason));
+        if NodeId::from_pubkey(pubkey) != msg.origin {
+            return Err(invalid("key does not match origin"));
+        }
+        let key = VerifyingKey::from_bytes(pubkey).map_err(|_| invalid("malformed key"))?;
+        let signature = Signature::from_slice(&msg.signature).map_err(|_| invalid("malformed signature"))?;
+        key.verify(&SignedGossip::signing_bytes(&msg.origin, &msg.message), &signature)
+            .map_err(|_| invalid("bad signature"))
+    }
+
+    /// Handle incoming gossip from `from`, whose key `pubkey` is known from
+    /// the handshake. Replies are signed by us.
+    pub async fn handle_message(
+        &self,
+        from: NodeId,
+        msg: SignedGossip,
+        pubkey: &[u8; 32],
+    ) -> Result<Option<SignedGossip>> {
+        if msg.origin != from {
+            return Err(ReputationError::InvalidSignature(format!(
+                "{} relayed a message from {}",
+                from, msg.origin
+            )));
+        }
+        Self::verify(&msg, pubkey)?;
+
+        let reply = self.dispatch(from, msg.message).await?;
+        Ok(reply.map(|reply| SignedGossip::sign(reply, &self.signing_key)))
+    }
+
+    async fn dispatch(&self, from: NodeId, msg: GossipMessage) -> Result<Option<GossipMessage>> {
         match msg {
             GossipMessage::NewRating(record) => {
                 self.handle_new_rating(from, record).await
@@ -116,7 +181,11 @@ impl ReputationGossip {
         }
     }
 
-    async fn handle_new_rating(&self, _from: NodeId, record: RatingRecord) -> Result<Option<GossipMessage>> {
+    async fn handle_new_rating(&self, from: NodeId, record: RatingRecord) -> Result<Option<GossipMessage>> {
+        if !record.verify() {
+            warn!("Discarding unverifiable rating of {} relayed by {}", record.ratee, from);
+            return Ok(None);
+        }
         let hash = record.hash();
 
         // Check if already seen
@@ -180,6 +249,10 @@ impl ReputationGossip {
     async fn handle_ratings_response(&self, ratings: Vec<RatingRecord>) -> Result<Option<GossipMessage>> {
         let graph = self.graph.write().await;
         for record in ratings {
+            if !record.verify() {
+                warn!("Discarding unverifiable rating of {} by {}", record.ratee, record.rater);
+                continue;
+            }
             let _ = graph.record_rating(record);
         }
         Ok(None)
@@ -189,9 +262,15 @@ impl ReputationGossip {
         self.handle_ratings_response(ratings).await
     }
 
-    /// Broadcast a new rating to the network
+    /// Broadcast a new rating to the network. Our own unsigned ratings
+    /// are signed first; others are relayed as they are.
     pub async fn broadcast_rating(&self, record: RatingRecord, targets: Vec<NodeId>) -> Result<()> {
-        let msg = GossipMessage::NewRating(record);
+        let record = if record.rater == self.my_id && record.signature.is_none() {
+            record.signed(&self.signing_key)
+        } else {
+            record
+        };
+        let msg = self.sign(GossipMessage::NewRating(record));
         for target in targets {
             if target != self.my_id {
                 let _ = self.outbound_tx.send((target, msg.clone())).await;
@@ -202,26 +281,31 @@ impl ReputationGossip {
 
     /// Request sync from a peer
     pub async fn request_sync(&self, peer: NodeId, since_timestamp: u64) -> Result<()> {
-        let msg = GossipMessage::SyncRequest { since_timestamp };
+        let msg = self.sign(GossipMessage::SyncRequest { since_timestamp });
         let _ = self.outbound_tx.send((peer, msg)).await;
         Ok(())
     }
 
     /// Query top nodes for a skill
     pub async fn query_top_nodes(&self, peer: NodeId, skill: &str, limit: usize) -> Result<()> {
-        let msg = GossipMessage::RequestTopNodes {
+        let msg = self.sign(GossipMessage::RequestTopNodes {
             skill: skill.to_string(),
             limit,
-        };
+        });
         let _ = self.outbound_tx.send((peer, msg)).await;
         Ok(())
     }
+
+    fn sign(&self, message: GossipMessage) -> SignedGossip {
+        SignedGossip::sign(message, &self.signing_key)
+    }
 }
 
 impl Clone for ReputationGossip {
     fn clone(&self) -> Self {
         Self {
             my_id: self.my_id,
+            signing_key: Arc::clone(&self.signing_key),
             graph: Arc::clone(&self.graph),
             outbound_tx: self.outbound_tx.clone(),
             seen_ratings: Arc::clone(&self.seen_ratings),
@@ -229,3 +313,70 @@ impl Clone for ReputationGossip {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rating::{Rating, SkillId};
+    use rand::rngs::OsRng;
+
+    fn node() -> (SigningKey, NodeId, ReputationGossip, mpsc::Receiver<(NodeId, SignedGossip)>) {
+        let key = SigningKey::generate(&mut OsRng);
+        let id = NodeId::from_pubkey(&key.verifying_key().to_bytes());
+        let graph = Arc::new(RwLock::new(TrustGraph::new(id)));
+        let (gossip, rx) = ReputationGossip::new(key.clone(), graph);
+        (key, id, gossip, rx)
+    }
+
+    #[tokio::test]
+    async fn test_signed_rating_is_merged() {
+        let (alice_key, alice, alice_gossip, mut outbound) = node();
+        let (_, bob, bob_gossip, _) = node();
+        let carol = NodeId::random();
+        let skill = SkillId::new("llm");
+
+        let record = RatingRecord::new(alice, carol, skill.clone(), Rating::positive());
+        alice_gossip.broadcast_rating(record, vec![bob]).await.unwrap();
+        let (to, msg) = outbound.recv().await.unwrap();
+        assert_eq!(to, bob);
+
+        let pubkey = alice_key.verifying_key().to_bytes();
+        bob_gossip.handle_message(alice, msg, &pubkey).await.unwrap();
+        assert!(bob_gossip.graph.read().await.get_skill_rating(&carol, &skill).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_gossip_rejected() {
+        let (alice_key, alice, _, _) = node();
+        let (mallory_key, mallory, _, _) = node();
+        let (_, _, bob_gossip, _) = node();
+        let victim = NodeId::random();
+        let skill = SkillId::new("llm");
+        let alice_pubkey = alice_key.verifying_key().to_bytes();
+        let mallory_pubkey = mallory_key.verifying_key().to_bytes();
+
+        // Alice rates the victim negatively; Mallory flips it in transit
+        let record = RatingRecord::new(alice, victim, skill.clone(), Rating::negative()).signed(&alice_key);
+        let mut msg = SignedGossip::sign(GossipMessage::NewRating(record.clone()), &alice_key);
+        if let GossipMessage::NewRating(r) = &mut msg.message {
+            r.rating = Rating::positive();
+        }
+        assert!(ReputationGossip::verify(&msg, &alice_pubkey).is_err());
+        assert!(bob_gossip.handle_message(alice, msg, &alice_pubkey).await.is_err());
+
+        // A key that doesn't hash to the claimed origin is refused
+        let msg = SignedGossip::sign(GossipMessage::NewRating(record), &alice_key);
+        assert!(ReputationGossip::verify(&msg, &mallory_pubkey).is_err());
+
+        // Mallory signs the envelope herself, but can't sign as Alice
+        let mut forged = RatingRecord::new(alice, victim, skill.clone(), Rating::positive()).signed(&alice_key);
+        forged.timestamp += 1;
+        let unsigned = RatingRecord::new(alice, victim, skill.clone(), Rating::positive());
+        let msg = SignedGossip::sign(
+            GossipMessage::SyncResponse { ratings: vec![forged, unsigned] },
+            &mallory_key,
+        );
+        bob_gossip.handle_message(mallory, msg, &mallory_pubkey).await.unwrap();
+        assert!(bob_gossip.graph.read().await.get_skill_rating(&victim, &skill).is_none());
+    }
+}
@@ -1,9 +1,11 @@
 pub mod rating;
 pub mod trust;
 pub mod gossip;
+pub mod routing;
 pub mod error;
 
 pub use rating::{Rating, RatingRecord, SkillRating, SkillId};
-pub use trust::{TrustScore, TrustGraph, EigenTrust};
-pub use gossip::{ReputationGossip, GossipMessage};
+pub use trust::{TrustScore, TrustGraph, TrustSnapshot, EigenTrust};
+pub use gossip::{ReputationGossip, GossipMessage, SignedGossip};
+pub use routing::{apply_outcome, record_outcome, routing_trust, DELEGATION_SKILL};
 pub use error::{ReputationError, Result};
@@ -1,3 +1,4 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -96,6 +97,9 @@ pub struct RatingRecord {
     pub context: Option<String>,
     /// Signature from rater (for verification)
     pub signature: Option<Vec<u8>>,
+    /// Rater's Ed25519 key, so relayed ratings can be checked against
+    /// `rater` without knowing the rater
+    pub rater_pubkey: Option<[u8; 32]>,
 }
 
 impl RatingRecord {
@@ -111,6 +115,7 @@ impl RatingRecord {
                 .as_secs(),
             context: None,
             signature: None,
+            rater_pubkey: None,
         }
     }
 
@@ -119,6 +124,36 @@ impl RatingRecord {
         self
     }
 
+    /// Sign as the rater. `key` must be the rater's, or `verify` fails.
+    pub fn signed(mut self, key: &SigningKey) -> Self {
+        let signature = key.sign(&self.signing_bytes());
+        self.signature = Some(signature.to_bytes().to_vec());
+        self.rater_pubkey = Some(key.verifying_key().to_bytes());
+        self
+    }
+
+    /// Whether the rater really issued this rating: the embedded key must
+    /// hash to `rater` and have signed the record
+    pub fn verify(&self) -> bool {
+        let (Some(signature), Some(pubkey)) = (&self.signature, &self.rater_pubkey) else {
+            return false;
+        };
+        if NodeId::from_pubkey(pubkey) != self.rater {
+            return false;
+        }
+        let Ok(signature) = Signature::from_slice(signature) else {
+            return false;
+        };
+        VerifyingKey::from_bytes(pubkey)
+            .map(|key| key.verify(&self.signing_bytes(), &signature).is_ok())
+            .unwrap_or(false)
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let fields = (&self.rater, &self.ratee, &self.skill, &self.rating, self.timestamp, &self.context);
+        bincode::serialize(&fields).unwrap_or_default()
+    }
+
     /// Compute hash for deduplication
     pub fn hash(&self) -> [u8; 32] {
         let data = format!(
@@ -0,0 +1,70 @@
+use cortex_grid::{NodeId, PeerStore, TaskOutcome};
+use tracing::debug;
+
+use crate::error::Result;
+use crate::rating::{Rating, SkillId};
+use crate::trust::{TrustGraph, TrustScore};
+
+/// Skill under which delegated-task outcomes are rated
+pub const DELEGATION_SKILL: &str = "grid.delegation";
+
+/// Trust used to route delegated tasks to `node`.
+///
+/// Averages the network-wide trust with our own record of the node's
+/// delegations, so a peer that keeps failing our tasks drops quickly even
+/// if others vouch for it. Both halves are neutral (0.5) without history.
+pub fn routing_trust(graph: &TrustGraph, node: &NodeId) -> TrustScore {
+    let approval = graph
+        .get_skill_rating(node, &SkillId::new(DELEGATION_SKILL))
+        .map(|rating| rating.approval_ratio())
+        .unwrap_or(0.5);
+    TrustScore::new((graph.get_trust(node).value() + approval) / 2.0)
+}
+
+/// Rate the peer behind a finished delegation and return its new routing trust
+pub fn record_outcome(graph: &TrustGraph, outcome: &TaskOutcome) -> Result<TrustScore> {
+    let rating = if outcome.success {
+        Rating::positive()
+    } else {
+        Rating::negative()
+    };
+    graph.rate(outcome.peer, SkillId::new(DELEGATION_SKILL), rating)?;
+    Ok(routing_trust(graph, &outcome.peer))
+}
+
+/// Record `outcome` and push the peer's new trust into `peer_store`, where
+/// the orchestrator reads it when choosing delegation targets
+pub async fn apply_outcome(graph: &TrustGraph, peer_store: &PeerStore, outcome: &TaskOutcome) -> Result<TrustScore> {
+    let trust = record_outcome(graph, outcome)?;
+    peer_store.update_trust(&outcome.peer, trust.value()).await;
+    debug!(peer = %outcome.peer.short(), success = outcome.success, trust = trust.value(), "Updated peer trust");
+    Ok(trust)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cortex_grid::PeerInfo;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_failures_lower_peer_trust() {
+        let graph = TrustGraph::new(NodeId::random());
+        let peer_store = PeerStore::new(Duration::from_secs(60));
+        let peer = NodeId::random();
+        peer_store.insert(PeerInfo::new(peer, [0u8; 32])).await;
+
+        assert_eq!(routing_trust(&graph, &peer).value(), 0.5);
+
+        for _ in 0..3 {
+            let outcome = TaskOutcome { task_id: [0u8; 32], peer, success: false };
+            apply_outcome(&graph, &peer_store, &outcome).await.unwrap();
+        }
+        let stored = peer_store.get(&peer).await.unwrap().trust_score;
+        assert!(stored < 0.5);
+
+        let outcome = TaskOutcome { task_id: [1u8; 32], peer, success: true };
+        let recovered = apply_outcome(&graph, &peer_store, &outcome).await.unwrap();
+        assert!(recovered.value() > stored);
+    }
+}
@@ -2,7 +2,9 @@ use dashmap::DashMap;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use cortex_grid::NodeId;
 
@@ -37,6 +39,69 @@ impl Default for TrustScore {
     }
 }
 
+/// Format version written by `TrustSnapshot::save`
+const SNAPSHOT_VERSION: u32 = 1;
+/// Restored global trust scores drift halfway back to neutral per week of
+/// snapshot age, since the network may have changed while we were away
+const SNAPSHOT_TRUST_HALF_LIFE_SECS: f32 = 7.0 * 24.0 * 3600.0;
+
+/// Serializable copy of a `TrustGraph`, so a restarting node keeps its view
+/// of who is trustworthy. Entries are sorted, so equal graphs produce equal
+/// bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustSnapshot {
+    pub version: u32,
+    pub my_id: NodeId,
+    /// Unix seconds when taken; restored global scores decay from here
+    pub taken_at: u64,
+    pub pre_trusted: Vec<NodeId>,
+    pub direct_trust: Vec<(NodeId, SkillId, TrustScore)>,
+    pub skill_ratings: Vec<SkillRating>,
+    /// EigenTrust scores as last computed
+    pub global_trust: Vec<(NodeId, TrustScore)>,
+    pub history: Vec<RatingRecord>,
+}
+
+impl TrustSnapshot {
+    /// Write atomically (temp file + rename) so a crash mid-save keeps the
+    /// previous snapshot
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let bytes = bincode::serialize(self).map_err(|e| ReputationError::Serialization(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// `None` if there is no snapshot at `path` yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let snapshot: Self =
+            bincode::deserialize(&bytes).map_err(|e| ReputationError::Serialization(e.to_string()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(ReputationError::Serialization(format!(
+                "trust snapshot version {} (expected {})",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(Some(snapshot))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 /// Local view of trust relationships
 #[derive(Debug, Clone)]
 pub struct TrustGraph {
@@ -66,6 +131,63 @@ impl TrustGraph {
         }
     }
 
+    /// Capture everything needed to rebuild this graph with `restore`
+    pub fn snapshot(&self) -> TrustSnapshot {
+        let mut pre_trusted: Vec<_> = self.pre_trusted.iter().copied().collect();
+        pre_trusted.sort();
+
+        let mut direct_trust: Vec<_> = self
+            .direct_trust
+            .iter()
+            .map(|entry| (entry.key().0, entry.key().1.clone(), *entry.value()))
+            .collect();
+        direct_trust.sort_by(|a, b| (a.0, a.1.as_str()).cmp(&(b.0, b.1.as_str())));
+
+        let mut skill_ratings: Vec<_> = self.skill_ratings.iter().map(|entry| entry.value().clone()).collect();
+        skill_ratings.sort_by(|a, b| (a.node, a.skill.as_str()).cmp(&(b.node, b.skill.as_str())));
+
+        let mut global_trust: Vec<_> = self.global_trust.iter().map(|entry| (*entry.key(), *entry.value())).collect();
+        global_trust.sort_by_key(|(node, _)| *node);
+
+        TrustSnapshot {
+            version: SNAPSHOT_VERSION,
+            my_id: self.my_id,
+            taken_at: unix_now(),
+            pre_trusted,
+            direct_trust,
+            skill_ratings,
+            global_trust,
+            history: self.history(),
+        }
+    }
+
+    /// Rebuild a graph from `snapshot`. Global trust scores are decayed
+    /// toward neutral by the snapshot's age; ratings are kept as they were.
+    pub fn restore(snapshot: TrustSnapshot) -> Self {
+        let age = unix_now().saturating_sub(snapshot.taken_at) as f32;
+        let retained = 0.5f32.powf(age / SNAPSHOT_TRUST_HALF_LIFE_SECS);
+        let neutral = TrustScore::default().value();
+
+        let graph = Self::new(snapshot.my_id);
+        for (node, skill, score) in snapshot.direct_trust {
+            graph.direct_trust.insert((node, skill), score);
+        }
+        for rating in snapshot.skill_ratings {
+            graph.skill_ratings.insert((rating.node, rating.skill.clone()), rating);
+        }
+        for (node, score) in snapshot.global_trust {
+            let decayed = neutral + (score.value() - neutral) * retained;
+            graph.global_trust.insert(node, TrustScore::new(decayed));
+        }
+        *graph.rating_history.write() = snapshot.history;
+
+        let mut graph = graph;
+        for node in snapshot.pre_trusted {
+            graph.add_pre_trusted(node);
+        }
+        graph
+    }
+
     /// Add a pre-trusted node (e.g., known good actors)
     pub fn add_pre_trusted(&mut self, node: NodeId) {
         self.pre_trusted.insert(node);
@@ -136,10 +258,13 @@ impl TrustGraph {
             .map(|entry| (entry.key().0, entry.value().clone()))
             .collect();
 
+        // Ties go to the lower node id so the order doesn't depend on map
+        // iteration
         results.sort_by(|a, b| {
             b.1.normalized_score()
                 .partial_cmp(&a.1.normalized_score())
                 .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
         });
 
         results.truncate(limit);
@@ -338,4 +463,61 @@ mod tests {
         assert_eq!(top.len(), 2);
         assert_eq!(top[0].0, node_a); // A should be first
     }
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip() {
+        let my_id = NodeId::random();
+        let mut graph = TrustGraph::new(my_id);
+        let seed = NodeId::random();
+        graph.add_pre_trusted(seed);
+
+        let nodes: Vec<NodeId> = (0..6).map(|_| NodeId::random()).collect();
+        for (i, node) in nodes.iter().enumerate() {
+            for _ in 0..i {
+                graph.rate(*node, "rust".into(), Rating::positive()).unwrap();
+            }
+            // Pairs of nodes tie on score
+            for _ in 0..(i % 2) {
+                graph.rate(*node, "rust".into(), Rating::negative()).unwrap();
+            }
+            graph.rate(*node, "python".into(), Rating::positive()).unwrap();
+        }
+        EigenTrust::new().update_graph(&graph);
+
+        let dir = std::env::temp_dir().join(format!("cortex-trust-{}", my_id));
+        let path = dir.join("trust.snapshot");
+        let snapshot = graph.snapshot();
+        snapshot.save(&path).unwrap();
+        let loaded = TrustSnapshot::load(&path).unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(bincode::serialize(&loaded).unwrap(), bincode::serialize(&snapshot).unwrap());
+
+        let restored = TrustGraph::restore(loaded);
+        for skill in ["rust", "python"] {
+            let top = |g: &TrustGraph| -> Vec<NodeId> {
+                g.top_nodes_for_skill(&skill.into(), 10).into_iter().map(|(n, _)| n).collect()
+            };
+            assert_eq!(top(&restored), top(&graph));
+        }
+        assert_eq!(restored.history().len(), graph.history().len());
+        assert_eq!(restored.get_trust(&seed).value(), 0.9);
+        for node in &nodes {
+            let (before, after) = (graph.get_trust(node).value(), restored.get_trust(node).value());
+            assert!((before - after).abs() < 1e-3, "{} vs {}", before, after);
+        }
+
+        assert!(TrustSnapshot::load(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_restore_decays_stale_trust() {
+        let graph = TrustGraph::new(NodeId::random());
+        let node = NodeId::random();
+        graph.global_trust.insert(node, TrustScore::new(0.9));
+
+        let mut snapshot = graph.snapshot();
+        snapshot.taken_at -= SNAPSHOT_TRUST_HALF_LIFE_SECS as u64;
+        let restored = TrustGraph::restore(snapshot);
+        assert!((restored.get_trust(&node).value() - 0.7).abs() < 1e-3);
+    }
 }
@@ -0,0 +1,164 @@
+//! Upper Confidence Bound (UCB1) pattern selection
+//!
+//! Each candidate pattern for a symbol is an arm. Selection picks the arm
+//! with the highest upper confidence bound on its success rate, so arms
+//! that have been tried little are explored until their estimate is tight
+//! enough to rule them out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::signal::SignalPattern;
+
+/// A candidate pattern and how it has performed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanditArm {
+    pub pattern: SignalPattern,
+    pub pulls: u32,
+    pub successes: u32,
+}
+
+impl BanditArm {
+    pub fn new(pattern: SignalPattern) -> Self {
+        Self {
+            pattern,
+            pulls: 0,
+            successes: 0,
+        }
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.pulls == 0 {
+            0.0
+        } else {
+            self.successes as f32 / self.pulls as f32
+        }
+    }
+
+    /// Success-rate estimate plus an exploration bonus that shrinks as the
+    /// arm is pulled; unpulled arms are always tried first
+    pub fn upper_bound(&self, total_pulls: u32, exploration: f32) -> f32 {
+        if self.pulls == 0 {
+            return f32::INFINITY;
+        }
+        let total = (total_pulls.max(1) as f32).ln();
+        self.mean() + exploration * (total / self.pulls as f32).sqrt()
+    }
+}
+
+/// UCB1 over the candidate patterns of one symbol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UcbBandit {
+    arms: Vec<BanditArm>,
+    exploration: f32,
+}
+
+impl UcbBandit {
+    /// `exploration` scales the confidence bonus; sqrt(2) is the textbook UCB1 value
+    pub fn new(exploration: f32) -> Self {
+        Self {
+            arms: Vec::new(),
+            exploration,
+        }
+    }
+
+    /// Add a candidate; returns false if it is already an arm
+    pub fn add_arm(&mut self, pattern: SignalPattern) -> bool {
+        if self.arms.iter().any(|arm| arm.pattern == pattern) {
+            return false;
+        }
+        self.arms.push(BanditArm::new(pattern));
+        true
+    }
+
+    pub fn arms(&self) -> &[BanditArm] {
+        &self.arms
+    }
+
+    pub fn total_pulls(&self) -> u32 {
+        self.arms.iter().map(|arm| arm.pulls).sum()
+    }
+
+    /// Arm to try next. Ties go to the earliest-added arm.
+    pub fn select(&self) -> Option<&SignalPattern> {
+        let total = self.total_pulls();
+        let mut best: Option<(&BanditArm, f32)> = None;
+        for arm in &self.arms {
+            let bound = arm.upper_bound(total, self.exploration);
+            if best.is_none_or(|(_, best_bound)| bound > best_bound) {
+                best = Some((arm, bound));
+            }
+        }
+        best.map(|(arm, _)| &arm.pattern)
+    }
+
+    /// Arm with the best observed success rate, without exploration
+    pub fn best(&self) -> Option<&SignalPattern> {
+        let mut best: Option<&BanditArm> = None;
+        for arm in self.arms.iter().filter(|arm| arm.pulls > 0) {
+            if best.is_none_or(|b| arm.mean() > b.mean()) {
+                best = Some(arm);
+            }
+        }
+        best.map(|arm| &arm.pattern)
+    }
+
+    /// Record an outcome, adding the pattern as an arm if it is new
+    pub fn record(&mut self, pattern: &SignalPattern, success: bool) {
+        let index = match self.arms.iter().position(|arm| &arm.pattern == pattern) {
+            Some(index) => index,
+            None => {
+                self.arms.push(BanditArm::new(pattern.clone()));
+                self.arms.len() - 1
+            }
+        };
+        let arm = &mut self.arms[index];
+        arm.pulls += 1;
+        if success {
+            arm.successes += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::Pulse;
+
+    fn pattern(n: u32) -> SignalPattern {
+        SignalPattern::new(vec![Pulse::on(100 * n), Pulse::off(100)])
+    }
+
+    #[test]
+    fn test_unpulled_arms_first() {
+        let mut bandit = UcbBandit::new(std::f32::consts::SQRT_2);
+        assert!(bandit.select().is_none());
+
+        bandit.add_arm(pattern(1));
+        bandit.add_arm(pattern(2));
+        assert!(!bandit.add_arm(pattern(1)));
+
+        bandit.record(&pattern(1), true);
+        assert_eq!(bandit.select(), Some(&pattern(2)));
+        bandit.record(&pattern(2), false);
+
+        // Both tried: the successful arm leads
+        assert_eq!(bandit.select(), Some(&pattern(1)));
+        assert_eq!(bandit.best(), Some(&pattern(1)));
+        assert_eq!(bandit.total_pulls(), 2);
+    }
+
+    #[test]
+    fn test_exploration_revisits_unlucky_arm() {
+        let mut bandit = UcbBandit::new(std::f32::consts::SQRT_2);
+        bandit.add_arm(pattern(1));
+        bandit.add_arm(pattern(2));
+
+        // Arm 2 failed once early, arm 1 keeps succeeding at 60%
+        bandit.record(&pattern(2), false);
+        for i in 0..20 {
+            bandit.record(&pattern(1), i % 5 < 3);
+        }
+        // The bonus for a single pull eventually outweighs arm 1's lead
+        assert_eq!(bandit.select(), Some(&pattern(2)));
+    }
+}
@@ -4,7 +4,18 @@ use cortex_core::SymbolId;
 use serde::{Deserialize, Serialize};
 
 use crate::error::SignalError;
-use crate::signal::{Pulse, SignalPattern};
+use crate::recognition::{match_confidence, MatchConfidence};
+use crate::signal::{CompressedPattern, Pulse, SignalPattern};
+
+/// Duration drift, as a fraction of the reference pulse, that still counts
+/// as an exact pulse when decoding with confidence
+const DECODE_DURATION_TOLERANCE: f32 = 0.1;
+
+/// Sub-sequence lengths considered when learning the dictionary
+const DICTIONARY_MIN_LEN: usize = 2;
+const DICTIONARY_MAX_LEN: usize = 16;
+/// Dictionary references are u16, but a small dictionary keeps lookups cheap
+const DICTIONARY_MAX_ENTRIES: usize = 64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StandardSymbol {
@@ -70,6 +81,9 @@ pub struct Codebook {
     entries: HashMap<SymbolId, CodebookEntry>,
     reverse: HashMap<Vec<u8>, SymbolId>,
     version: u32,
+    /// Frequent pulse sub-sequences used by `compress`
+    #[serde(default)]
+    dictionary: Vec<Vec<Pulse>>,
 }
 
 impl Default for Codebook {
@@ -84,6 +98,7 @@ impl Codebook {
             entries: HashMap::new(),
             reverse: HashMap::new(),
             version: 1,
+            dictionary: Vec::new(),
         };
         codebook.register_standard_symbols();
         codebook
@@ -190,6 +205,31 @@ impl Codebook {
             .ok_or_else(|| SignalError::InvalidPattern("unknown pattern".into()))
     }
 
+    /// Closest symbol to `pattern` and how confident the match is. Exact
+    /// patterns score 1.0; anything else is compared against every entry.
+    pub fn decode_with_confidence(
+        &self,
+        pattern: &SignalPattern,
+    ) -> Result<(SymbolId, MatchConfidence), SignalError> {
+        if let Ok(symbol) = self.decode(pattern) {
+            return Ok((symbol, MatchConfidence::new(1.0, 0.0)));
+        }
+
+        self.entries
+            .values()
+            .map(|entry| {
+                let confidence = match_confidence(pattern, &entry.pattern, Some(DECODE_DURATION_TOLERANCE));
+                (entry, confidence)
+            })
+            .max_by(|(a, ca), (b, cb)| {
+                ca.score
+                    .total_cmp(&cb.score)
+                    .then_with(|| b.symbol.as_bytes().cmp(a.symbol.as_bytes()))
+            })
+            .map(|(entry, confidence)| (entry.symbol, confidence))
+            .ok_or_else(|| SignalError::InvalidPattern("empty codebook".into()))
+    }
+
     pub fn get_entry(&self, symbol: SymbolId) -> Option<&CodebookEntry> {
         self.entries.get(&symbol)
     }
@@ -202,6 +242,64 @@ impl Codebook {
         self.entries.len()
     }
 
+    /// Learn the pulse sub-sequences that save the most when replaced by a
+    /// dictionary reference across `samples`. Both ends of a link must share
+    /// the same dictionary, so this bumps the codebook version.
+    pub fn build_dictionary(&mut self, samples: &[SignalPattern]) {
+        let mut counts: HashMap<&[Pulse], usize> = HashMap::new();
+        for sample in samples {
+            for len in DICTIONARY_MIN_LEN..=DICTIONARY_MAX_LEN.min(sample.pulses.len()) {
+                for window in sample.pulses.windows(len) {
+                    *counts.entry(window).or_default() += 1;
+                }
+            }
+        }
+
+        // A reference replaces `len` pulses with one token
+        let mut candidates: Vec<(&[Pulse], usize)> = counts
+            .into_iter()
+            .filter(|&(_, count)| count >= 2)
+            .map(|(window, count)| (window, count * (window.len() - 1)))
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| b.0.len().cmp(&a.0.len()))
+                .then_with(|| a.0.cmp(b.0))
+        });
+
+        let mut dictionary: Vec<Vec<Pulse>> = Vec::new();
+        for (window, _) in candidates {
+            if dictionary.len() >= DICTIONARY_MAX_ENTRIES {
+                break;
+            }
+            // Skip pieces of entries already chosen
+            if dictionary.iter().any(|entry| entry.windows(window.len()).any(|w| w == window)) {
+                continue;
+            }
+            dictionary.push(window.to_vec());
+        }
+
+        self.dictionary = dictionary;
+        self.version += 1;
+    }
+
+    pub fn dictionary_len(&self) -> usize {
+        self.dictionary.len()
+    }
+
+    /// Compress with run-length encoding and the learned dictionary
+    pub fn compress(&self, pattern: &SignalPattern) -> CompressedPattern {
+        pattern.compress_with(&self.dictionary)
+    }
+
+    pub fn decompress(&self, compressed: &CompressedPattern) -> Result<SignalPattern, SignalError> {
+        compressed.expand_with(&self.dictionary)
+    }
+
+    pub fn decode_compressed(&self, compressed: &CompressedPattern) -> Result<SymbolId, SignalError> {
+        self.decode(&self.decompress(compressed)?)
+    }
+
     fn pattern_to_key(&self, pattern: &SignalPattern) -> Vec<u8> {
         // Pre-calculate size: 1 byte (on/off) + 4 bytes (u32) per pulse
         let capacity = pattern.pulses.len() * 5;
@@ -257,4 +355,62 @@ mod tests {
         let encoded = codebook.encode(custom_symbol).unwrap();
         assert_eq!(encoded, &custom_pattern);
     }
+
+    /// A preamble followed by a run of standard symbols separated by gaps,
+    /// the shape of a long status report
+    fn report(codebook: &Codebook, symbols: &[StandardSymbol]) -> SignalPattern {
+        let mut pulses = [Pulse::on(100), Pulse::off(100)].repeat(8);
+        for symbol in symbols {
+            pulses.extend(codebook.encode(symbol.to_symbol_id()).unwrap().pulses.iter().copied());
+            pulses.push(Pulse::off(400));
+        }
+        SignalPattern::new(pulses)
+    }
+
+    #[test]
+    fn test_dictionary_compression() {
+        use StandardSymbol::*;
+
+        let mut codebook = Codebook::new();
+        let samples = [
+            report(&codebook, &[Ready, TaskRequest, Ack, Busy, TaskRequest, Ack]),
+            report(&codebook, &[Ping, Pong, TaskRequest, Ack, Ready]),
+            report(&codebook, &[Busy, Error, Nak, TaskRequest, Ack]),
+        ];
+        codebook.build_dictionary(&samples);
+        assert!(codebook.dictionary_len() > 0);
+
+        let message = report(
+            &codebook,
+            &[Ready, TaskRequest, Ack, TaskRequest, Ack, Busy, Error, Nak, TaskRequest, Ack, Ping, Pong],
+        );
+        let rle_only = message.compressed();
+        let compressed = codebook.compress(&message);
+
+        assert_eq!(codebook.decompress(&compressed).unwrap(), message);
+        assert!(compressed.encoded_len() < rle_only.encoded_len());
+        // Well under half the bytes of the raw pattern
+        assert!(compressed.encoded_len() * 2 < message.encoded_len());
+
+        // A single symbol round-trips through the compressed path
+        let ack = codebook.compress(codebook.encode(Ack.to_symbol_id()).unwrap());
+        assert_eq!(codebook.decode_compressed(&ack).unwrap(), Ack.to_symbol_id());
+    }
+
+    #[test]
+    fn test_namespaced_symbols_coexist() {
+        let mut codebook = Codebook::new();
+        let grid_ack = SymbolId::namespaced("grid", "ACK");
+        let sensor_ack = SymbolId::namespaced("sensor", "ACK");
+
+        codebook
+            .propose_symbol(grid_ack, SignalPattern::new(vec![Pulse::on(300), Pulse::off(700)]), None)
+            .unwrap();
+        codebook
+            .propose_symbol(sensor_ack, SignalPattern::new(vec![Pulse::on(700), Pulse::off(300)]), None)
+            .unwrap();
+
+        assert_ne!(codebook.encode(grid_ack).unwrap(), codebook.encode(sensor_ack).unwrap());
+        assert_ne!(codebook.encode(grid_ack).unwrap(), codebook.encode(StandardSymbol::Ack.to_symbol_id()).unwrap());
+    }
 }
@@ -1,11 +1,14 @@
 use async_trait::async_trait;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 use tracing::{debug, info};
 
 use crate::codebook::Codebook;
 use crate::error::EmitError;
+use crate::learning::CommunicationOutcome;
 use crate::signal::{Channel, Signal, SignalPattern};
 
 #[async_trait]
@@ -13,6 +16,98 @@ pub trait Emitter: Send + Sync {
     fn channel(&self) -> Channel;
     async fn emit(&self, pattern: &SignalPattern) -> Result<(), EmitError>;
     async fn emit_signal(&self, signal: &Signal, codebook: &Codebook) -> Result<(), EmitError>;
+
+    /// Minimum gap between consecutive emissions; zero for unpaced emitters
+    fn emit_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn set_emit_interval(&self, _interval: Duration) {}
+
+    /// Feedback on whether an emitted pattern got through, used to adapt
+    /// the emit interval
+    fn record_outcome(&self, _outcome: &CommunicationOutcome) {}
+}
+
+/// Additive-increase/multiplicative-decrease pacing for an emitter: each
+/// clean outcome shortens the interval by a fixed step, each failure
+/// multiplies it by the backoff factor
+#[derive(Debug, Clone)]
+pub struct EmitRateController {
+    interval: Duration,
+    min_interval: Duration,
+    max_interval: Duration,
+    step: Duration,
+    backoff: f64,
+    last_emit: Option<Instant>,
+}
+
+impl Default for EmitRateController {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl EmitRateController {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            min_interval: Duration::ZERO,
+            max_interval: Duration::from_secs(1),
+            step: Duration::from_millis(5),
+            backoff: 2.0,
+            last_emit: None,
+        }
+    }
+
+    pub fn with_bounds(mut self, min_interval: Duration, max_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self.max_interval = max_interval.max(min_interval);
+        self.interval = self.interval.clamp(self.min_interval, self.max_interval);
+        self
+    }
+
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: f64) -> Self {
+        self.backoff = backoff.max(1.0);
+        self
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval.clamp(self.min_interval, self.max_interval);
+    }
+
+    /// Adjust the interval for one outcome and return the new value
+    pub fn record(&mut self, success: bool) -> Duration {
+        let next = if success {
+            self.interval.saturating_sub(self.step)
+        } else {
+            // Start backing off from one step so a zero interval can grow
+            let nanos = self.interval.max(self.step).as_nanos() as f64 * self.backoff;
+            Duration::from_nanos(nanos.round().min(u64::MAX as f64) as u64)
+        };
+        self.set_interval(next);
+        self.interval
+    }
+
+    /// How long to wait before emitting now; marks the emission as started
+    pub fn next_delay(&mut self) -> Duration {
+        let now = Instant::now();
+        let delay = self
+            .last_emit
+            .map(|last| (last + self.interval).saturating_duration_since(now))
+            .unwrap_or_default();
+        self.last_emit = Some(now + delay);
+        delay
+    }
 }
 
 pub struct MockEmitter {
@@ -20,6 +115,7 @@ pub struct MockEmitter {
     emit_count: AtomicUsize,
     emitted_patterns: Arc<Mutex<Vec<SignalPattern>>>,
     should_fail: bool,
+    rate: std::sync::Mutex<EmitRateController>,
 }
 
 impl MockEmitter {
@@ -29,18 +125,22 @@ impl MockEmitter {
             emit_count: AtomicUsize::new(0),
             emitted_patterns: Arc::new(Mutex::new(Vec::new())),
             should_fail: false,
+            rate: std::sync::Mutex::new(EmitRateController::default()),
         }
     }
 
     pub fn failing(channel: Channel) -> Self {
         Self {
-            channel,
-            emit_count: AtomicUsize::new(0),
-            emitted_patterns: Arc::new(Mutex::new(Vec::new())),
             should_fail: true,
+            ..Self::new(channel)
         }
     }
 
+    pub fn with_rate_controller(self, controller: EmitRateController) -> Self {
+        *self.rate.lock().unwrap_or_else(|e| e.into_inner()) = controller;
+        self
+    }
+
     pub fn emit_count(&self) -> usize {
         self.emit_count.load(Ordering::SeqCst)
     }
@@ -61,6 +161,9 @@ impl Emitter for MockEmitter {
             return Err(EmitError::HardwareError("mock failure".into()));
         }
 
+        let delay = self.rate.lock().unwrap_or_else(|e| e.into_inner()).next_delay();
+        tokio::time::sleep(delay).await;
+
         self.emit_count.fetch_add(1, Ordering::SeqCst);
         self.emitted_patterns.lock().await.push(pattern.clone());
         debug!(
@@ -75,11 +178,24 @@ impl Emitter for MockEmitter {
         let pattern = codebook.encode(signal.symbol)?;
         self.emit(pattern).await
     }
+
+    fn emit_interval(&self) -> Duration {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).interval()
+    }
+
+    fn set_emit_interval(&self, interval: Duration) {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).set_interval(interval);
+    }
+
+    fn record_outcome(&self, outcome: &CommunicationOutcome) {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).record(outcome.success);
+    }
 }
 
 pub struct ConsoleEmitter {
     channel: Channel,
     name: String,
+    rate: std::sync::Mutex<EmitRateController>,
 }
 
 impl ConsoleEmitter {
@@ -87,8 +203,14 @@ impl ConsoleEmitter {
         Self {
             channel,
             name: name.into(),
+            rate: std::sync::Mutex::new(EmitRateController::default()),
         }
     }
+
+    pub fn with_rate_controller(self, controller: EmitRateController) -> Self {
+        *self.rate.lock().unwrap_or_else(|e| e.into_inner()) = controller;
+        self
+    }
 }
 
 #[async_trait]
@@ -98,6 +220,9 @@ impl Emitter for ConsoleEmitter {
     }
 
     async fn emit(&self, pattern: &SignalPattern) -> Result<(), EmitError> {
+        let delay = self.rate.lock().unwrap_or_else(|e| e.into_inner()).next_delay();
+        tokio::time::sleep(delay).await;
+
         let visual: String = pattern
             .pulses
             .iter()
@@ -136,6 +261,18 @@ impl Emitter for ConsoleEmitter {
         );
         self.emit(pattern).await
     }
+
+    fn emit_interval(&self) -> Duration {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).interval()
+    }
+
+    fn set_emit_interval(&self, interval: Duration) {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).set_interval(interval);
+    }
+
+    fn record_outcome(&self, outcome: &CommunicationOutcome) {
+        self.rate.lock().unwrap_or_else(|e| e.into_inner()).record(outcome.success);
+    }
 }
 
 #[cfg(test)]
@@ -164,4 +301,44 @@ mod tests {
         let result = emitter.emit(&pattern).await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_rate_controller_aimd() {
+        let mut rate = EmitRateController::new(Duration::ZERO)
+            .with_bounds(Duration::ZERO, Duration::from_millis(100))
+            .with_step(Duration::from_millis(10));
+
+        assert_eq!(rate.record(false), Duration::from_millis(20));
+        assert_eq!(rate.record(false), Duration::from_millis(40));
+        assert_eq!(rate.record(false), Duration::from_millis(80));
+        assert_eq!(rate.record(false), Duration::from_millis(100));
+
+        assert_eq!(rate.record(true), Duration::from_millis(90));
+        assert_eq!(rate.record(true), Duration::from_millis(80));
+        for _ in 0..20 {
+            rate.record(true);
+        }
+        assert_eq!(rate.interval(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_emitter_paces_by_interval() {
+        let emitter = MockEmitter::new(Channel::Light);
+        emitter.set_emit_interval(Duration::from_millis(50));
+        let pattern = SignalPattern::new(vec![Pulse::on(100)]);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            emitter.emit(&pattern).await.unwrap();
+        }
+        // Two gaps; the first emission goes out immediately
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100) && elapsed < Duration::from_millis(110));
+
+        let symbol = cortex_core::SymbolId::from_bytes(b"ACK");
+        emitter.record_outcome(&CommunicationOutcome::failure(symbol, pattern.clone()));
+        assert_eq!(emitter.emit_interval(), Duration::from_millis(100));
+        emitter.record_outcome(&CommunicationOutcome::success(symbol, pattern));
+        assert_eq!(emitter.emit_interval(), Duration::from_millis(95));
+    }
 }
@@ -1,3 +1,4 @@
+use cortex_core::SymbolId;
 use thiserror::Error;
 
 use crate::signal::Channel;
@@ -93,6 +94,11 @@ pub enum DecodeError {
     #[error("corrupted signal")]
     CorruptedSignal,
 
+    /// Closest symbol matched below the receiver's confidence threshold;
+    /// the caller may ask for a retransmission
+    #[error("low confidence decode: best {best_symbol} scored {score:.2}")]
+    LowConfidence { best_symbol: SymbolId, score: f32 },
+
     /// Error during signal reception
     #[error("receive error: {0}")]
     Receive(#[from] ReceiveError),
@@ -137,6 +143,32 @@ pub enum NegotiationError {
 /// Convenience Result type for negotiation operations
 pub type NegotiationResult<T> = std::result::Result<T, NegotiationError>;
 
+/// Errors in forward error correction of fragmented payloads.
+///
+/// Payloads sent over lossy channels are split into data and parity
+/// fragments so the receiver can rebuild them without retransmission.
+#[derive(Debug, Error)]
+pub enum FecError {
+    /// Redundancy must be a finite, non-negative ratio
+    #[error("invalid redundancy: {0}")]
+    InvalidRedundancy(f32),
+
+    /// Payload would need more fragments than the code supports
+    #[error("too many fragments: {0}")]
+    TooManyFragments(usize),
+
+    /// More fragments were lost than parity can make up for
+    #[error("too many fragments lost: need {needed}, received {received}")]
+    TooManyLost { needed: usize, received: usize },
+
+    /// Fragments disagree about the message they belong to
+    #[error("inconsistent fragments: {0}")]
+    Inconsistent(String),
+}
+
+/// Convenience Result type for FEC operations
+pub type FecResult<T> = std::result::Result<T, FecError>;
+
 /// Errors in multi-hop routing through the signal mesh.
 ///
 /// The routing layer enables messages to hop through intermediate
@@ -177,6 +177,30 @@ impl EvolutionEngine {
         Ok(())
     }
 
+    /// Add a known pattern to the population for a symbol, creating the
+    /// population if needed. Returns whether the pattern was new.
+    pub async fn seed_pattern(&self, symbol: SymbolId, pattern: SignalPattern) -> bool {
+        let mut population = self.population.write().await;
+        let generation = *self.generation.read().await;
+
+        let patterns = population.entry(symbol).or_default();
+        if patterns.iter().any(|p| p.pattern == pattern) {
+            return false;
+        }
+        patterns.push(EvolvedPattern::new(pattern, generation));
+        true
+    }
+
+    /// Current population patterns for a symbol
+    pub async fn patterns(&self, symbol: SymbolId) -> Vec<SignalPattern> {
+        self.population
+            .read()
+            .await
+            .get(&symbol)
+            .map(|patterns| patterns.iter().map(|p| p.pattern.clone()).collect())
+            .unwrap_or_default()
+    }
+
     /// Reset/reinitialize a population for a symbol
     pub async fn reset_population(&self, symbol: SymbolId) -> Result<(), SignalError> {
         let mut population = self.population.write().await;
@@ -0,0 +1,407 @@
+//! Forward error correction for lossy signal channels
+//!
+//! A payload is cut into `k` equal data fragments and `m` parity fragments
+//! are added with a systematic Cauchy Reed-Solomon code over GF(2^8). Any
+//! `k` of the `k + m` fragments are enough to rebuild the payload, so a
+//! receiver survives losing up to `m` fragments without a retransmission.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::error::FecError;
+
+/// Default bytes per fragment, sized for a single light/BLE burst
+pub const DEFAULT_FRAGMENT_SIZE: usize = 16;
+/// GF(2^8) limits the code to 255 distinct fragment indices
+pub const MAX_FRAGMENTS: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FecFragment {
+    pub index: u8,
+    pub data_count: u8,
+    pub parity_count: u8,
+    pub payload_len: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl FecFragment {
+    pub fn is_parity(&self) -> bool {
+        self.index >= self.data_count
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FecEncoder {
+    redundancy: f32,
+    fragment_size: usize,
+}
+
+impl FecEncoder {
+    /// `redundancy` is the parity-to-data fragment ratio, e.g. 0.5 adds one
+    /// parity fragment for every two data fragments
+    pub fn new(redundancy: f32) -> Self {
+        Self {
+            redundancy,
+            fragment_size: DEFAULT_FRAGMENT_SIZE,
+        }
+    }
+
+    pub fn with_fragment_size(mut self, fragment_size: usize) -> Self {
+        self.fragment_size = fragment_size;
+        self
+    }
+
+    pub fn redundancy(&self) -> f32 {
+        self.redundancy
+    }
+
+    pub fn encode(&self, payload: &[u8]) -> Result<Vec<FecFragment>, FecError> {
+        if !self.redundancy.is_finite() || self.redundancy < 0.0 {
+            return Err(FecError::InvalidRedundancy(self.redundancy));
+        }
+        if self.fragment_size == 0 {
+            return Err(FecError::TooManyFragments(usize::MAX));
+        }
+
+        let data_count = payload.len().div_ceil(self.fragment_size).max(1);
+        // A huge ratio saturates the cast, so the sum must not overflow
+        let parity_count = (data_count as f32 * self.redundancy).ceil() as usize;
+        match data_count.checked_add(parity_count) {
+            Some(total) if total <= MAX_FRAGMENTS => {}
+            total => return Err(FecError::TooManyFragments(total.unwrap_or(usize::MAX))),
+        }
+
+        let data: Vec<Vec<u8>> = (0..data_count)
+            .map(|i| {
+                let start = (i * self.fragment_size).min(payload.len());
+                let end = ((i + 1) * self.fragment_size).min(payload.len());
+                let mut shard = payload[start..end].to_vec();
+                shard.resize(self.fragment_size, 0);
+                shard
+            })
+            .collect();
+
+        let fragment = |index: usize, bytes: Vec<u8>| FecFragment {
+            index: index as u8,
+            data_count: data_count as u8,
+            parity_count: parity_count as u8,
+            payload_len: payload.len() as u32,
+            bytes,
+        };
+
+        let mut fragments: Vec<FecFragment> = data
+            .iter()
+            .enumerate()
+            .map(|(i, shard)| fragment(i, shard.clone()))
+            .collect();
+
+        for p in 0..parity_count {
+            let row = matrix_row(data_count + p, data_count);
+            let mut parity = vec![0u8; self.fragment_size];
+            for (coefficient, shard) in row.iter().zip(&data) {
+                mul_add(&mut parity, shard, *coefficient);
+            }
+            fragments.push(fragment(data_count + p, parity));
+        }
+
+        Ok(fragments)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FecStats {
+    pub data_fragments: usize,
+    pub parity_fragments: usize,
+    pub received: usize,
+    /// Data fragments that never arrived
+    pub lost: usize,
+    /// Lost data fragments rebuilt from parity
+    pub recovered: usize,
+}
+
+/// Collects the fragments of one message and rebuilds its payload
+#[derive(Debug, Default)]
+pub struct FecReassembler {
+    fragments: Vec<Option<Vec<u8>>>,
+    data_count: usize,
+    parity_count: usize,
+    payload_len: usize,
+    fragment_size: usize,
+    stats: FecStats,
+}
+
+impl FecReassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, fragment: FecFragment) -> Result<(), FecError> {
+        let data_count = fragment.data_count as usize;
+        let parity_count = fragment.parity_count as usize;
+        let index = fragment.index as usize;
+
+        if self.fragments.is_empty() {
+            if data_count == 0 || data_count + parity_count > MAX_FRAGMENTS {
+                return Err(FecError::Inconsistent("bad fragment counts".to_string()));
+            }
+            self.fragments = vec![None; data_count + parity_count];
+            self.data_count = data_count;
+            self.parity_count = parity_count;
+            self.payload_len = fragment.payload_len as usize;
+            self.fragment_size = fragment.bytes.len();
+            self.stats.data_fragments = data_count;
+            self.stats.parity_fragments = parity_count;
+        } else if data_count != self.data_count
+            || parity_count != self.parity_count
+            || fragment.payload_len as usize != self.payload_len
+            || fragment.bytes.len() != self.fragment_size
+        {
+            return Err(FecError::Inconsistent(format!(
+                "fragment {} does not belong to this message",
+                index
+            )));
+        }
+
+        if index >= self.fragments.len() {
+            return Err(FecError::Inconsistent(format!("fragment index {} out of range", index)));
+        }
+        if self.fragments[index].is_none() {
+            self.fragments[index] = Some(fragment.bytes);
+            self.stats.received += 1;
+        }
+        Ok(())
+    }
+
+    /// Whether enough fragments have arrived to rebuild the payload
+    pub fn is_complete(&self) -> bool {
+        !self.fragments.is_empty() && self.stats.received >= self.data_count
+    }
+
+    pub fn stats(&self) -> FecStats {
+        self.stats
+    }
+
+    pub fn reassemble(&mut self) -> Result<Vec<u8>, FecError> {
+        if !self.is_complete() {
+            return Err(FecError::TooManyLost {
+                needed: self.data_count.max(1),
+                received: self.stats.received,
+            });
+        }
+
+        let k = self.data_count;
+        let missing: Vec<usize> = (0..k).filter(|&i| self.fragments[i].is_none()).collect();
+        self.stats.lost = missing.len();
+
+        if !missing.is_empty() {
+            // Any k received fragments determine the data; solve for it
+            let rows: Vec<usize> = (0..self.fragments.len())
+                .filter(|&i| self.fragments[i].is_some())
+                .take(k)
+                .collect();
+            let matrix: Vec<Vec<u8>> = rows.iter().map(|&r| matrix_row(r, k)).collect();
+            let inverse = invert(matrix).ok_or_else(|| {
+                FecError::Inconsistent("fragment matrix is singular".to_string())
+            })?;
+
+            for &lost in &missing {
+                let mut shard = vec![0u8; self.fragment_size];
+                for (coefficient, &row) in inverse[lost].iter().zip(&rows) {
+                    if let Some(bytes) = &self.fragments[row] {
+                        mul_add(&mut shard, bytes, *coefficient);
+                    }
+                }
+                self.fragments[lost] = Some(shard);
+            }
+            self.stats.recovered = missing.len();
+        }
+
+        let mut payload: Vec<u8> = self.fragments[..k].iter().flatten().flatten().copied().collect();
+        payload.truncate(self.payload_len);
+        Ok(payload)
+    }
+}
+
+/// Row `r` of the systematic encoding matrix: identity for data fragments,
+/// Cauchy `1 / (x_r + y_j)` for parity fragments
+fn matrix_row(r: usize, k: usize) -> Vec<u8> {
+    if r < k {
+        let mut row = vec![0u8; k];
+        row[r] = 1;
+        return row;
+    }
+    (0..k).map(|j| gf_inv(r as u8 ^ j as u8)).collect()
+}
+
+fn mul_add(dst: &mut [u8], src: &[u8], coefficient: u8) {
+    if coefficient == 0 {
+        return;
+    }
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= gf_mul(*s, coefficient);
+    }
+}
+
+/// Gauss-Jordan inversion over GF(2^8)
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<u8>> = (0..n).map(|i| matrix_row(i, n)).collect();
+
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| matrix[r][col] != 0)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = gf_inv(matrix[col][col]);
+        for j in 0..n {
+            matrix[col][j] = gf_mul(matrix[col][j], scale);
+            inverse[col][j] = gf_mul(inverse[col][j], scale);
+        }
+
+        for r in 0..n {
+            let factor = matrix[r][col];
+            if r == col || factor == 0 {
+                continue;
+            }
+            for j in 0..n {
+                matrix[r][j] ^= gf_mul(factor, matrix[col][j]);
+                inverse[r][j] ^= gf_mul(factor, inverse[col][j]);
+            }
+        }
+    }
+    Some(inverse)
+}
+
+struct GfTables {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+fn tables() -> &'static GfTables {
+    static TABLES: OnceLock<GfTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x: u16 = 1;
+        for (i, e) in exp.iter_mut().take(255).enumerate() {
+            *e = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        let (low, high) = exp.split_at_mut(255);
+        high[..255].copy_from_slice(low);
+        GfTables { exp, log }
+    })
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let t = tables();
+    t.exp[t.log[a as usize] as usize + t.log[b as usize] as usize]
+}
+
+fn gf_inv(a: u8) -> u8 {
+    debug_assert!(a != 0, "zero has no inverse in GF(2^8)");
+    let t = tables();
+    t.exp[255 - t.log[a as usize] as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    #[test]
+    fn test_roundtrip_without_loss() {
+        let data = payload(100);
+        let fragments = FecEncoder::new(0.5).encode(&data).unwrap();
+        assert_eq!(fragments.iter().filter(|f| !f.is_parity()).count(), 7);
+        assert_eq!(fragments.iter().filter(|f| f.is_parity()).count(), 4);
+
+        let mut reassembler = FecReassembler::new();
+        for fragment in fragments {
+            reassembler.insert(fragment).unwrap();
+        }
+        assert_eq!(reassembler.reassemble().unwrap(), data);
+        assert_eq!(reassembler.stats().recovered, 0);
+    }
+
+    #[test]
+    fn test_recovers_any_losses_up_to_parity_count() {
+        let data = payload(100);
+        let fragments = FecEncoder::new(0.5).encode(&data).unwrap();
+        let total = fragments.len();
+
+        // Every way of dropping 4 of 11 fragments, stepping the start point
+        for start in 0..total {
+            for stride in 1..total {
+                let dropped: Vec<usize> = (0..4).map(|i| (start + i * stride) % total).collect();
+                let mut unique = dropped.clone();
+                unique.sort_unstable();
+                unique.dedup();
+                if unique.len() < 4 {
+                    continue;
+                }
+
+                let mut reassembler = FecReassembler::new();
+                for fragment in fragments.iter().filter(|f| !dropped.contains(&(f.index as usize))) {
+                    reassembler.insert(fragment.clone()).unwrap();
+                }
+                assert_eq!(reassembler.reassemble().unwrap(), data);
+
+                let lost_data = unique.iter().filter(|&&i| i < 7).count();
+                let stats = reassembler.stats();
+                assert_eq!(stats.received, total - 4);
+                assert_eq!(stats.lost, lost_data);
+                assert_eq!(stats.recovered, lost_data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fails_beyond_redundancy() {
+        let data = payload(64);
+        let fragments = FecEncoder::new(0.25).encode(&data).unwrap();
+        assert_eq!(fragments.len(), 5);
+
+        let mut reassembler = FecReassembler::new();
+        for fragment in fragments.into_iter().skip(2) {
+            reassembler.insert(fragment).unwrap();
+        }
+        assert!(!reassembler.is_complete());
+        assert!(matches!(
+            reassembler.reassemble(),
+            Err(FecError::TooManyLost { needed: 4, received: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(matches!(
+            FecEncoder::new(-1.0).encode(b"x"),
+            Err(FecError::InvalidRedundancy(_))
+        ));
+        assert!(matches!(
+            FecEncoder::new(1.0).with_fragment_size(1).encode(&payload(200)),
+            Err(FecError::TooManyFragments(400))
+        ));
+        assert!(matches!(
+            FecEncoder::new(f32::MAX).encode(b"x"),
+            Err(FecError::TooManyFragments(usize::MAX))
+        ));
+
+        let a = FecEncoder::new(0.5).encode(&payload(40)).unwrap();
+        let b = FecEncoder::new(0.5).encode(&payload(80)).unwrap();
+        let mut reassembler = FecReassembler::new();
+        reassembler.insert(a[0].clone()).unwrap();
+        assert!(matches!(reassembler.insert(b[1].clone()), Err(FecError::Inconsistent(_))));
+    }
+}
@@ -7,7 +7,10 @@ use cortex_core::NodeId;
 
 use crate::codebook::{Codebook, StandardSymbol};
 use crate::emitter::Emitter;
-use crate::error::{EmitError, RoutingError};
+use crate::error::{EmitError, FecError, RoutingError};
+use crate::fec::{FecEncoder, FecFragment};
+use crate::learning::CommunicationOutcome;
+use crate::negotiation::ChannelNegotiator;
 use crate::receiver::Receiver;
 use crate::routing::{MultiHopRouter, Route, RouteHop};
 use crate::signal::Channel;
@@ -19,6 +22,8 @@ pub struct ForwardedMessage {
     pub payload: Vec<u8>,
     pub hop_count: u8,
     pub max_hops: u8,
+    /// Set when the payload should be sent as FEC fragments
+    pub fec: Option<FecEncoder>,
 }
 
 impl ForwardedMessage {
@@ -29,6 +34,25 @@ impl ForwardedMessage {
             payload,
             hop_count: 0,
             max_hops,
+            fec: None,
+        }
+    }
+
+    /// Send the payload as data plus parity fragments so the receiver can
+    /// rebuild it after losing up to `redundancy` times the data fragment count
+    pub fn with_fec(mut self, redundancy: f32) -> Self {
+        self.fec = Some(FecEncoder::new(redundancy));
+        self
+    }
+
+    /// Fragments to put on the wire; a single unprotected fragment without FEC.
+    /// Feed them to a `FecReassembler` on the receiving side.
+    pub fn fragments(&self) -> Result<Vec<FecFragment>, FecError> {
+        match &self.fec {
+            Some(encoder) => encoder.encode(&self.payload),
+            None => FecEncoder::new(0.0)
+                .with_fragment_size(self.payload.len().max(1))
+                .encode(&self.payload),
         }
     }
 
@@ -46,6 +70,8 @@ pub struct SignalForwarder {
     router: Arc<MultiHopRouter>,
     codebook: Arc<RwLock<Codebook>>,
     emitters: Arc<RwLock<HashMap<Channel, Arc<dyn Emitter>>>>,
+    negotiator: Arc<ChannelNegotiator>,
+    peer_channels: Arc<RwLock<HashMap<NodeId, Vec<Channel>>>>,
     pending_forwards: Arc<RwLock<Vec<ForwardedMessage>>>,
 }
 
@@ -56,10 +82,50 @@ impl SignalForwarder {
             router,
             codebook: Arc::new(RwLock::new(Codebook::new())),
             emitters: Arc::new(RwLock::new(HashMap::new())),
+            negotiator: Arc::new(ChannelNegotiator::new()),
+            peer_channels: Arc::new(RwLock::new(HashMap::new())),
             pending_forwards: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
+    /// Share a negotiator that tracks measured channel quality
+    pub fn with_negotiator(mut self, negotiator: Arc<ChannelNegotiator>) -> Self {
+        self.negotiator = negotiator;
+        self
+    }
+
+    /// Record which physical channels a neighbour can receive on
+    pub async fn register_peer_channels(&self, peer: NodeId, channels: Vec<Channel>) {
+        debug!(peer = %peer, channels = ?channels, "Registered peer channels");
+        self.peer_channels.write().await.insert(peer, channels);
+    }
+
+    /// Channel to reach `next_hop` on: the preferred one if both ends have
+    /// it, otherwise whatever the negotiator picks from the overlap. A peer
+    /// that never announced its channels is assumed to listen only on the
+    /// channel its route hop names.
+    async fn negotiate_channel(
+        &self,
+        next_hop: &RouteHop,
+        preferred: Option<&Channel>,
+    ) -> Option<Channel> {
+        let local: Vec<Channel> = self.emitters.read().await.keys().cloned().collect();
+        let remote = self
+            .peer_channels
+            .read()
+            .await
+            .get(&next_hop.node_id)
+            .cloned()
+            .unwrap_or_else(|| vec![next_hop.channel.clone()]);
+
+        if let Some(preferred) = preferred {
+            if local.contains(preferred) && remote.contains(preferred) {
+                return Some(preferred.clone());
+            }
+        }
+        self.negotiator.negotiate(&local, &remote).await
+    }
+
     pub async fn register_emitter(&self, channel: Channel, emitter: Arc<dyn Emitter>) {
         let mut emitters = self.emitters.write().await;
         info!(channel = ?channel, "Registered emitter for multi-hop forwarding");
@@ -133,7 +199,7 @@ impl SignalForwarder {
         let pattern = codebook_guard.encode(beacon_symbol)?.clone();
         drop(codebook_guard);
         
-        let channel = preferred_channel.unwrap_or(Channel::Ble);
+        let channel = preferred_channel.clone().unwrap_or(Channel::Ble);
         let signal = crate::signal::Signal::new(beacon_symbol, pattern.clone(), channel.clone());
         
         let message = crate::routing::MultiHopMessage::new(self.local_node, destination, signal);
@@ -148,12 +214,20 @@ impl SignalForwarder {
         // Try to route the message
         match self.router.route_message(&message).await {
             Ok(Some(next_hop)) => {
+                let negotiated = self
+                    .negotiate_channel(&next_hop, preferred_channel.as_ref())
+                    .await
+                    .ok_or_else(|| EmitError::ChannelUnavailable(next_hop.channel.clone()))?;
+
                 let emitters = self.emitters.read().await;
                 let emitter = emitters
-                    .get(&next_hop.channel)
-                    .ok_or_else(|| EmitError::ChannelUnavailable(next_hop.channel.clone()))?;
+                    .get(&negotiated)
+                    .ok_or_else(|| EmitError::ChannelUnavailable(negotiated.clone()))?;
                 
-                emitter.emit(&pattern).await?;
+                if let Err(e) = emitter.emit(&pattern).await {
+                    emitter.record_outcome(&CommunicationOutcome::failure(beacon_symbol, pattern));
+                    return Err(e);
+                }
                 Ok(())
             }
             Ok(None) => {
@@ -221,6 +295,20 @@ impl SignalForwarder {
         self.router.add_route(route).await;
     }
 
+    /// Feed a delivery outcome back to the emitter on `channel` so it can
+    /// slow down on rising failures and speed up when links are clean
+    pub async fn record_outcome(&self, channel: &Channel, outcome: &CommunicationOutcome) {
+        if let Some(emitter) = self.emitters.read().await.get(channel) {
+            emitter.record_outcome(outcome);
+            debug!(
+                channel = ?channel,
+                success = outcome.success,
+                interval_ms = emitter.emit_interval().as_millis() as u64,
+                "Adjusted emit interval"
+            );
+        }
+    }
+
     pub async fn pending_forward_count(&self) -> usize {
         self.pending_forwards.read().await.len()
     }
@@ -265,6 +353,29 @@ mod tests {
         assert!(!msg.can_forward());
     }
 
+    #[test]
+    fn test_forwarded_message_fec_recovery() {
+        use crate::fec::FecReassembler;
+
+        let payload: Vec<u8> = (0..=99).collect();
+        let msg = ForwardedMessage::new(test_node_id(1), test_node_id(3), payload.clone(), 5)
+            .with_fec(0.5);
+        let fragments = msg.fragments().unwrap();
+        assert_eq!(fragments.len(), 11);
+
+        // Lose three data fragments and one parity fragment in transit
+        let mut reassembler = FecReassembler::new();
+        for fragment in fragments.into_iter().filter(|f| ![0, 3, 6, 9].contains(&f.index)) {
+            reassembler.insert(fragment).unwrap();
+        }
+        assert_eq!(reassembler.reassemble().unwrap(), payload);
+        assert_eq!(reassembler.stats().recovered, 3);
+
+        let plain = ForwardedMessage::new(test_node_id(1), test_node_id(3), payload.clone(), 5);
+        let fragments = plain.fragments().unwrap();
+        assert_eq!(fragments.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_signal_forwarder_creation() {
         let local = test_node_id(1);
@@ -274,6 +385,75 @@ mod tests {
         assert_eq!(forwarder.pending_forward_count().await, 0);
     }
 
+    #[tokio::test]
+    async fn test_send_negotiates_channel_with_next_hop() {
+        use crate::emitter::MockEmitter;
+
+        let local = test_node_id(1);
+        let relay = test_node_id(2);
+        let router = Arc::new(MultiHopRouter::new(local));
+        let forwarder = SignalForwarder::new(local, router);
+        forwarder
+            .update_route_from_signal(
+                local,
+                test_node_id(3),
+                vec![RouteHop::new(relay, Channel::Light)],
+            )
+            .await;
+
+        let ble = Arc::new(MockEmitter::new(Channel::Ble));
+        forwarder.register_emitter(Channel::Ble, ble.clone()).await;
+
+        // The route names Light, which we can't emit on and the relay hasn't
+        // said it has anything else
+        let result = forwarder.send_via_signal(test_node_id(3), vec![1], None).await;
+        assert!(matches!(result, Err(EmitError::ChannelUnavailable(Channel::Light))));
+        assert_eq!(ble.emit_count(), 0);
+
+        // Once the relay announces BLE the link is usable
+        forwarder
+            .register_peer_channels(relay, vec![Channel::Light, Channel::Ble])
+            .await;
+        forwarder
+            .send_via_signal(test_node_id(3), vec![1], Some(Channel::Light))
+            .await
+            .unwrap();
+        assert_eq!(ble.emit_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_back_off_emit_interval() {
+        use crate::emitter::{EmitRateController, MockEmitter};
+        use std::time::Duration;
+
+        let local = test_node_id(1);
+        let relay = test_node_id(2);
+        let router = Arc::new(MultiHopRouter::new(local));
+        let forwarder = SignalForwarder::new(local, router);
+        forwarder
+            .update_route_from_signal(local, relay, vec![RouteHop::new(relay, Channel::Light)])
+            .await;
+
+        let controller = EmitRateController::new(Duration::from_millis(10))
+            .with_step(Duration::from_millis(5));
+        let emitter = Arc::new(MockEmitter::failing(Channel::Light).with_rate_controller(controller));
+        forwarder.register_emitter(Channel::Light, emitter.clone()).await;
+
+        let mut previous = emitter.emit_interval();
+        for _ in 0..3 {
+            assert!(forwarder.send_via_signal(relay, vec![1], None).await.is_err());
+            assert!(emitter.emit_interval() > previous);
+            previous = emitter.emit_interval();
+        }
+        assert_eq!(previous, Duration::from_millis(80));
+
+        // Clean deliveries speed it back up one step at a time
+        let symbol = StandardSymbol::Beacon.to_symbol_id();
+        let outcome = CommunicationOutcome::success(symbol, crate::signal::SignalPattern::empty());
+        forwarder.record_outcome(&Channel::Light, &outcome).await;
+        assert_eq!(emitter.emit_interval(), Duration::from_millis(75));
+    }
+
     #[tokio::test]
     async fn test_forward_message_max_hops() {
         let local = test_node_id(2);
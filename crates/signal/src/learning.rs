@@ -3,7 +3,7 @@
 /// Coordinates evolution and recognition to create an adaptive learning system
 /// for signal communication protocols.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
@@ -12,10 +12,21 @@ use tracing::{debug, info, warn};
 
 use cortex_core::SymbolId;
 
+use crate::bandit::UcbBandit;
 use crate::error::SignalError;
 use crate::evolution::{EvolutionConfig, EvolutionEngine, FitnessMetrics};
 use crate::recognition::{RecognitionConfig, RecognitionEngine};
-use crate::signal::SignalPattern;
+use crate::signal::{Channel, SignalPattern};
+
+/// Outcomes kept for `export_history`; older ones are dropped first
+const MAX_HISTORY: usize = 10_000;
+
+/// Leads `LearningHistory::to_bytes` output. Unversioned histories start
+/// with their outcome count instead, which is never this large.
+const HISTORY_MAGIC: &[u8; 4] = b"CXLH";
+
+/// Version 1, written without a header, had no outcome channel
+const HISTORY_FORMAT_VERSION: u32 = 2;
 
 /// Learning strategy for signal adaptation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -26,6 +37,13 @@ pub enum LearningStrategy {
     Recognition,
     /// Combine evolution and recognition
     Hybrid,
+    /// Choose among candidate patterns by Upper Confidence Bound on their
+    /// success rates, seeded from an evolved population and learned patterns
+    UcbBandit,
+}
+
+fn default_ucb_exploration() -> f32 {
+    std::f32::consts::SQRT_2
 }
 
 /// Configuration for the learning system
@@ -40,6 +58,9 @@ pub struct LearningConfig {
     pub auto_evolve: bool,
     /// Minimum improvement required to adopt new patterns
     pub min_improvement: f32,
+    /// Weight of the confidence bonus for `LearningStrategy::UcbBandit`
+    #[serde(default = "default_ucb_exploration")]
+    pub ucb_exploration: f32,
 }
 
 impl Default for LearningConfig {
@@ -51,6 +72,7 @@ impl Default for LearningConfig {
             evaluation_interval: 10,
             auto_evolve: true,
             min_improvement: 0.05,
+            ucb_exploration: default_ucb_exploration(),
         }
     }
 }
@@ -66,6 +88,29 @@ pub struct LearningStats {
     pub best_fitness: f32,
     pub current_generation: u32,
     pub patterns_learned: u32,
+    /// Outcomes broken down by the channel they were reported on
+    #[serde(default)]
+    pub per_channel: HashMap<Channel, ChannelStats>,
+}
+
+/// Outcome counts for one symbol on one channel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelStats {
+    pub successful_communications: u32,
+    pub failed_communications: u32,
+    /// Evolution generations triggered by outcomes on this channel
+    pub current_generation: u32,
+}
+
+impl ChannelStats {
+    pub fn success_rate(&self) -> f32 {
+        let total = self.successful_communications + self.failed_communications;
+        if total == 0 {
+            0.0
+        } else {
+            self.successful_communications as f32 / total as f32
+        }
+    }
 }
 
 impl Default for LearningStats {
@@ -79,6 +124,7 @@ impl Default for LearningStats {
             best_fitness: 0.0,
             current_generation: 0,
             patterns_learned: 0,
+            per_channel: HashMap::new(),
         }
     }
 }
@@ -92,6 +138,26 @@ impl LearningStats {
             self.successful_communications as f32 / total as f32
         }
     }
+
+    pub fn channel(&self, channel: &Channel) -> ChannelStats {
+        self.per_channel.get(channel).cloned().unwrap_or_default()
+    }
+
+    fn count(&mut self, outcome: &CommunicationOutcome) {
+        if outcome.success {
+            self.successful_communications += 1;
+        } else {
+            self.failed_communications += 1;
+        }
+        if let Some(channel) = &outcome.channel {
+            let per_channel = self.per_channel.entry(channel.clone()).or_default();
+            if outcome.success {
+                per_channel.successful_communications += 1;
+            } else {
+                per_channel.failed_communications += 1;
+            }
+        }
+    }
 }
 
 /// Communication outcome for learning feedback
@@ -103,6 +169,33 @@ pub struct CommunicationOutcome {
     pub snr: Option<f32>,
     pub latency_us: Option<u32>,
     pub energy_cost: Option<f32>,
+    /// Channel the pattern was sent on, when known
+    pub channel: Option<Channel>,
+}
+
+/// `CommunicationOutcome` as exported before it named its channel
+#[derive(Deserialize)]
+struct CommunicationOutcomeV1 {
+    symbol: SymbolId,
+    pattern: SignalPattern,
+    success: bool,
+    snr: Option<f32>,
+    latency_us: Option<u32>,
+    energy_cost: Option<f32>,
+}
+
+impl From<CommunicationOutcomeV1> for CommunicationOutcome {
+    fn from(old: CommunicationOutcomeV1) -> Self {
+        Self {
+            symbol: old.symbol,
+            pattern: old.pattern,
+            success: old.success,
+            snr: old.snr,
+            latency_us: old.latency_us,
+            energy_cost: old.energy_cost,
+            channel: None,
+        }
+    }
 }
 
 impl CommunicationOutcome {
@@ -114,6 +207,7 @@ impl CommunicationOutcome {
             snr: None,
             latency_us: None,
             energy_cost: None,
+            channel: None,
         }
     }
 
@@ -125,6 +219,7 @@ impl CommunicationOutcome {
             snr: None,
             latency_us: None,
             energy_cost: None,
+            channel: None,
         }
     }
 
@@ -142,6 +237,50 @@ impl CommunicationOutcome {
         self.energy_cost = Some(energy_cost);
         self
     }
+
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+}
+
+/// Exported training history, for offline analysis or to warm-start
+/// another node with `LearningSystem::import_history`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LearningHistory {
+    pub outcomes: Vec<CommunicationOutcome>,
+}
+
+impl LearningHistory {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SignalError> {
+        let mut bytes = HISTORY_MAGIC.to_vec();
+        bytes.extend_from_slice(&HISTORY_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)
+            .map_err(|e| SignalError::CodecError(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decode `to_bytes` output, including unversioned version 1 histories
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignalError> {
+        let codec_error = |e: bincode::Error| SignalError::CodecError(e.to_string());
+        let Some(rest) = bytes.strip_prefix(HISTORY_MAGIC.as_slice()) else {
+            let outcomes: Vec<CommunicationOutcomeV1> =
+                bincode::deserialize(bytes).map_err(codec_error)?;
+            return Ok(Self {
+                outcomes: outcomes.into_iter().map(Into::into).collect(),
+            });
+        };
+        let (version, body) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| SignalError::CodecError("truncated history header".into()))?;
+        match u32::from_le_bytes(*version) {
+            HISTORY_FORMAT_VERSION => bincode::deserialize(body).map_err(codec_error),
+            version => Err(SignalError::CodecError(format!(
+                "history format {} is not supported",
+                version
+            ))),
+        }
+    }
 }
 
 /// The main learning system
@@ -150,6 +289,10 @@ pub struct LearningSystem {
     evolution_engine: EvolutionEngine,
     recognition_engine: RecognitionEngine,
     stats: Arc<RwLock<HashMap<SymbolId, LearningStats>>>,
+    history: Arc<RwLock<VecDeque<CommunicationOutcome>>>,
+    bandits: Arc<RwLock<HashMap<SymbolId, UcbBandit>>>,
+    /// Per-channel pattern performance, fed by outcomes that name a channel
+    channel_arms: Arc<RwLock<HashMap<(SymbolId, Channel), UcbBandit>>>,
 }
 
 impl LearningSystem {
@@ -162,6 +305,9 @@ impl LearningSystem {
             evolution_engine,
             recognition_engine,
             stats: Arc::new(RwLock::new(HashMap::new())),
+            history: Arc::new(RwLock::new(VecDeque::new())),
+            bandits: Arc::new(RwLock::new(HashMap::new())),
+            channel_arms: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -178,6 +324,10 @@ impl LearningSystem {
             LearningStrategy::Recognition => {
                 // Recognition engine doesn't need initialization
             }
+            LearningStrategy::UcbBandit => {
+                self.evolution_engine.initialize_population(symbol).await?;
+                self.seed_bandit(symbol).await;
+            }
         }
 
         let mut stats = self.stats.write().await;
@@ -211,12 +361,39 @@ impl LearningSystem {
                 // Evolution doesn't learn from external patterns directly
                 warn!("Evolution strategy doesn't support direct pattern learning");
             }
+            LearningStrategy::UcbBandit => {
+                let added = self
+                    .bandits
+                    .write()
+                    .await
+                    .entry(symbol)
+                    .or_insert_with(|| UcbBandit::new(self.config.ucb_exploration))
+                    .add_arm(pattern);
+
+                if added {
+                    let mut stats = self.stats.write().await;
+                    if let Some(s) = stats.get_mut(&symbol) {
+                        s.patterns_learned += 1;
+                    }
+                    debug!(symbol = ?symbol, "Added bandit arm");
+                }
+            }
         }
 
         Ok(())
     }
 
-    /// Get the best pattern for a symbol
+    /// Start a fresh bandit for `symbol` with the evolved population as arms
+    async fn seed_bandit(&self, symbol: SymbolId) {
+        let mut bandit = UcbBandit::new(self.config.ucb_exploration);
+        for pattern in self.evolution_engine.patterns(symbol).await {
+            bandit.add_arm(pattern);
+        }
+        self.bandits.write().await.insert(symbol, bandit);
+    }
+
+    /// Get the best pattern for a symbol. Under `UcbBandit` this is the
+    /// pattern to try next, which may be an under-explored one.
     pub async fn get_best_pattern(&self, symbol: SymbolId) -> Result<SignalPattern, SignalError> {
         match self.config.strategy {
             LearningStrategy::Evolution => {
@@ -233,6 +410,37 @@ impl LearningSystem {
                     _ => self.evolution_engine.get_best_pattern(symbol).await,
                 }
             }
+            LearningStrategy::UcbBandit => self
+                .bandits
+                .read()
+                .await
+                .get(&symbol)
+                .and_then(|bandit| bandit.select().cloned())
+                .ok_or_else(|| SignalError::UnknownSymbol(format!("{:?}", symbol))),
+        }
+    }
+
+    /// Best pattern observed on one physical channel. Under `UcbBandit` this
+    /// is the next pattern to try on that channel. Falls back to
+    /// `get_best_pattern` until outcomes have been reported for the channel.
+    pub async fn get_best_pattern_for_channel(
+        &self,
+        symbol: SymbolId,
+        channel: &Channel,
+    ) -> Result<SignalPattern, SignalError> {
+        let specialized = self
+            .channel_arms
+            .read()
+            .await
+            .get(&(symbol, channel.clone()))
+            .and_then(|arms| match self.config.strategy {
+                LearningStrategy::UcbBandit => arms.select().cloned(),
+                _ => arms.best().cloned(),
+            });
+
+        match specialized {
+            Some(pattern) => Ok(pattern),
+            None => self.get_best_pattern(symbol).await,
         }
     }
 
@@ -243,14 +451,104 @@ impl LearningSystem {
         // Update statistics
         let mut stats = self.stats.write().await;
         if let Some(s) = stats.get_mut(&symbol) {
-            if outcome.success {
-                s.successful_communications += 1;
-            } else {
-                s.failed_communications += 1;
-            }
+            s.count(&outcome);
         }
         drop(stats);
 
+        self.apply_outcome(&outcome).await;
+        let success = outcome.success;
+        let channel = outcome.channel.clone();
+        self.push_history(outcome).await;
+
+        // Auto-evolve if enabled
+        if self.config.auto_evolve {
+            self.maybe_evolve(symbol, channel.as_ref()).await?;
+        }
+
+        debug!(
+            symbol = ?symbol,
+            success = success,
+            "Recorded communication outcome"
+        );
+
+        Ok(())
+    }
+
+    /// Outcomes recorded so far, oldest first
+    pub async fn export_history(&self) -> Vec<CommunicationOutcome> {
+        self.history.read().await.iter().cloned().collect()
+    }
+
+    /// Replay another node's outcomes to rebuild per-symbol stats, templates
+    /// and pattern fitness. Patterns the history mentions are learned as
+    /// templates and seeded into the evolution population so the outcomes
+    /// have something to attach to. Replay never triggers evolution.
+    pub async fn import_history(&self, outcomes: Vec<CommunicationOutcome>) -> Result<usize, SignalError> {
+        let count = outcomes.len();
+        for outcome in outcomes {
+            let symbol = outcome.symbol;
+            {
+                let mut stats = self.stats.write().await;
+                stats.entry(symbol).or_default().count(&outcome);
+            }
+
+            if matches!(
+                self.config.strategy,
+                LearningStrategy::Recognition | LearningStrategy::Hybrid
+            ) && self.recognition_engine.ensure_template(symbol, &outcome.pattern).await?
+            {
+                let mut stats = self.stats.write().await;
+                if let Some(s) = stats.get_mut(&symbol) {
+                    s.patterns_learned += 1;
+                }
+            }
+            if matches!(
+                self.config.strategy,
+                LearningStrategy::Evolution | LearningStrategy::Hybrid
+            ) {
+                self.evolution_engine
+                    .seed_pattern(symbol, outcome.pattern.clone())
+                    .await;
+            }
+
+            self.apply_outcome(&outcome).await;
+            self.push_history(outcome).await;
+        }
+
+        info!(outcomes = count, "Imported learning history");
+        Ok(count)
+    }
+
+    async fn push_history(&self, outcome: CommunicationOutcome) {
+        let mut history = self.history.write().await;
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(outcome);
+    }
+
+    /// Feed an outcome to the recognition and evolution engines
+    async fn apply_outcome(&self, outcome: &CommunicationOutcome) {
+        let symbol = outcome.symbol;
+
+        if let Some(channel) = &outcome.channel {
+            self.channel_arms
+                .write()
+                .await
+                .entry((symbol, channel.clone()))
+                .or_insert_with(|| UcbBandit::new(self.config.ucb_exploration))
+                .record(&outcome.pattern, outcome.success);
+        }
+
+        if self.config.strategy == LearningStrategy::UcbBandit {
+            self.bandits
+                .write()
+                .await
+                .entry(symbol)
+                .or_insert_with(|| UcbBandit::new(self.config.ucb_exploration))
+                .record(&outcome.pattern, outcome.success);
+        }
+
         // Update recognition engine
         if matches!(
             self.config.strategy,
@@ -280,23 +578,10 @@ impl LearningSystem {
                 .record_fitness(symbol, &outcome.pattern, metrics)
                 .await;
         }
-
-        // Auto-evolve if enabled
-        if self.config.auto_evolve {
-            self.maybe_evolve(symbol).await?;
-        }
-
-        debug!(
-            symbol = ?symbol,
-            success = outcome.success,
-            "Recorded communication outcome"
-        );
-
-        Ok(())
     }
 
     /// Evolve patterns if it's time
-    async fn maybe_evolve(&self, symbol: SymbolId) -> Result<(), SignalError> {
+    async fn maybe_evolve(&self, symbol: SymbolId, channel: Option<&Channel>) -> Result<(), SignalError> {
         if !matches!(
             self.config.strategy,
             LearningStrategy::Evolution | LearningStrategy::Hybrid
@@ -325,6 +610,9 @@ impl LearningSystem {
             let mut stats = self.stats.write().await;
             if let Some(s) = stats.get_mut(&symbol) {
                 s.current_generation += 1;
+                if let Some(channel) = channel {
+                    s.per_channel.entry(channel.clone()).or_default().current_generation += 1;
+                }
             }
         }
 
@@ -369,6 +657,10 @@ impl LearningSystem {
             LearningStrategy::Recognition => {
                 // Would need to clear templates if we had that API
             }
+            LearningStrategy::UcbBandit => {
+                self.evolution_engine.reset_population(symbol).await?;
+                self.seed_bandit(symbol).await;
+            }
         }
 
         let mut stats = self.stats.write().await;
@@ -521,6 +813,249 @@ mod tests {
         assert_eq!(stats.success_rate(), 0.7);
     }
 
+    #[tokio::test]
+    async fn test_history_roundtrip() {
+        let symbol = SymbolId::from_bytes(b"TEST");
+        let strong = SignalPattern::new(vec![Pulse::on(100), Pulse::off(100)]);
+        let weak = SignalPattern::new(vec![Pulse::on(900), Pulse::off(50), Pulse::on(900)]);
+
+        for strategy in [LearningStrategy::Recognition, LearningStrategy::Evolution, LearningStrategy::Hybrid] {
+            let config = LearningConfig {
+                strategy,
+                auto_evolve: false,
+                ..LearningConfig::default()
+            };
+
+            let trained = LearningSystem::new(config.clone());
+            trained.initialize_symbol(symbol).await.unwrap();
+            if strategy != LearningStrategy::Evolution {
+                trained.learn_pattern(symbol, weak.clone()).await.unwrap();
+                trained.learn_pattern(symbol, strong.clone()).await.unwrap();
+            }
+            let strong = match strategy {
+                // Evolution can only score patterns in its own population
+                LearningStrategy::Evolution => trained.get_best_pattern(symbol).await.unwrap(),
+                _ => strong.clone(),
+            };
+            for i in 0..6 {
+                let pattern = if i % 2 == 0 { &strong } else { &weak };
+                let outcome = if i % 2 == 0 {
+                    CommunicationOutcome::success(symbol, pattern.clone())
+                } else {
+                    CommunicationOutcome::failure(symbol, pattern.clone())
+                };
+                trained
+                    .record_outcome(outcome.with_snr(60.0).with_latency(2_000))
+                    .await
+                    .unwrap();
+            }
+
+            let history = LearningHistory {
+                outcomes: trained.export_history().await,
+            };
+            assert_eq!(history.outcomes.len(), 6);
+            let history = LearningHistory::from_bytes(&history.to_bytes().unwrap()).unwrap();
+
+            let fresh = LearningSystem::new(config);
+            assert_eq!(fresh.import_history(history.outcomes).await.unwrap(), 6);
+
+            assert_eq!(
+                fresh.get_best_pattern(symbol).await.unwrap(),
+                trained.get_best_pattern(symbol).await.unwrap(),
+                "{:?}",
+                strategy
+            );
+            let (a, b) = (fresh.get_stats(symbol).await, trained.get_stats(symbol).await);
+            assert_eq!(a.successful_communications, b.successful_communications);
+            assert_eq!(a.failed_communications, b.failed_communications);
+            assert_eq!(fresh.export_history().await.len(), 6);
+        }
+    }
+
+    #[test]
+    fn test_history_decodes_unversioned_exports() {
+        #[derive(Serialize)]
+        struct OutcomeV1 {
+            symbol: SymbolId,
+            pattern: SignalPattern,
+            success: bool,
+            snr: Option<f32>,
+            latency_us: Option<u32>,
+            energy_cost: Option<f32>,
+        }
+
+        let symbol = SymbolId::from_bytes(b"TEST");
+        let pattern = SignalPattern::new(vec![Pulse::on(100), Pulse::off(50)]);
+        let legacy = bincode::serialize(&vec![OutcomeV1 {
+            symbol,
+            pattern: pattern.clone(),
+            success: true,
+            snr: Some(40.0),
+            latency_us: None,
+            energy_cost: None,
+        }])
+        .unwrap();
+
+        let history = LearningHistory::from_bytes(&legacy).unwrap();
+        assert_eq!(history.outcomes.len(), 1);
+        assert_eq!(history.outcomes[0].pattern, pattern);
+        assert_eq!(history.outcomes[0].snr, Some(40.0));
+        assert!(history.outcomes[0].channel.is_none());
+
+        let mut future = LearningHistory::default().to_bytes().unwrap();
+        future[4] = 3;
+        assert!(LearningHistory::from_bytes(&future).is_err());
+    }
+
+    /// Candidates with hidden delivery rates; the last one is best
+    fn bandit_environment() -> Vec<(SignalPattern, f64)> {
+        [0.2, 0.35, 0.3, 0.5, 0.8]
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| {
+                let pattern = SignalPattern::new(vec![Pulse::on(100 * (i as u32 + 1)), Pulse::off(100)]);
+                (pattern, p)
+            })
+            .collect()
+    }
+
+    /// Run `rounds` of pick/send/record, with delivery drawn from `seed`, and
+    /// return how many of the last `window` picks were the best candidate
+    async fn best_picks(
+        system: &LearningSystem,
+        symbol: SymbolId,
+        rounds: usize,
+        window: usize,
+        seed: u64,
+    ) -> usize {
+        use rand::{Rng, SeedableRng};
+
+        let environment = bandit_environment();
+        let best = &environment.last().unwrap().0;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut hits = 0;
+        for round in 0..rounds {
+            let pattern = system.get_best_pattern(symbol).await.unwrap();
+            // Patterns outside the candidate set (evolved ones) rarely get through
+            let rate = environment
+                .iter()
+                .find(|(p, _)| *p == pattern)
+                .map_or(0.05, |(_, rate)| *rate);
+            let outcome = if rng.gen_bool(rate) {
+                CommunicationOutcome::success(symbol, pattern.clone())
+            } else {
+                CommunicationOutcome::failure(symbol, pattern.clone())
+            };
+            system.record_outcome(outcome).await.unwrap();
+            if round >= rounds - window && &pattern == best {
+                hits += 1;
+            }
+        }
+        hits
+    }
+
+    #[tokio::test]
+    async fn test_ucb_bandit_converges_faster_than_hybrid() {
+        let symbol = SymbolId::from_bytes(b"TEST");
+        let (rounds, window, trials) = (300, 100, 10);
+
+        // Hybrid seeds its evolved population randomly, so a single run can
+        // get lucky; compare totals over several seeded trials instead
+        let mut totals = Vec::new();
+        for strategy in [LearningStrategy::UcbBandit, LearningStrategy::Hybrid] {
+            let mut total = 0;
+            for trial in 0..trials {
+                let system = LearningSystem::new(LearningConfig {
+                    strategy,
+                    auto_evolve: false,
+                    ..LearningConfig::default()
+                });
+                if strategy == LearningStrategy::Hybrid {
+                    system.initialize_symbol(symbol).await.unwrap();
+                }
+                for (pattern, _) in bandit_environment() {
+                    system.learn_pattern(symbol, pattern).await.unwrap();
+                }
+                total += best_picks(&system, symbol, rounds, window, trial).await;
+            }
+            totals.push(total);
+        }
+
+        let (ucb, hybrid) = (totals[0], totals[1]);
+        let runs = window * trials as usize;
+        assert!(ucb > runs * 3 / 4, "bandit picked the best arm {} of {} times", ucb, runs);
+        assert!(ucb > hybrid, "bandit {} vs hybrid {}", ucb, hybrid);
+    }
+
+    #[tokio::test]
+    async fn test_per_channel_breakdown() {
+        let symbol = SymbolId::from_bytes(b"TEST");
+        let flash = SignalPattern::new(vec![Pulse::on(50), Pulse::off(50)]);
+        let long = SignalPattern::new(vec![Pulse::on(800), Pulse::off(400)]);
+
+        let system = LearningSystem::new(LearningConfig {
+            strategy: LearningStrategy::Recognition,
+            auto_evolve: false,
+            ..LearningConfig::default()
+        });
+        system.initialize_symbol(symbol).await.unwrap();
+        system.learn_pattern(symbol, flash.clone()).await.unwrap();
+        system.learn_pattern(symbol, long.clone()).await.unwrap();
+
+        // Short flashes work on Light but get lost on BLE, long pulses the reverse
+        for (pattern, channel, success) in [
+            (&flash, Channel::Light, true),
+            (&flash, Channel::Light, true),
+            (&flash, Channel::Ble, false),
+            (&long, Channel::Light, false),
+            (&long, Channel::Ble, true),
+            (&long, Channel::Ble, true),
+        ] {
+            let outcome = if success {
+                CommunicationOutcome::success(symbol, pattern.clone())
+            } else {
+                CommunicationOutcome::failure(symbol, pattern.clone())
+            };
+            system.record_outcome(outcome.with_channel(channel)).await.unwrap();
+        }
+
+        assert_eq!(system.get_best_pattern_for_channel(symbol, &Channel::Light).await.unwrap(), flash);
+        assert_eq!(system.get_best_pattern_for_channel(symbol, &Channel::Ble).await.unwrap(), long);
+        // Nothing seen on Audio yet: the overall best
+        assert_eq!(
+            system.get_best_pattern_for_channel(symbol, &Channel::Audio).await.unwrap(),
+            system.get_best_pattern(symbol).await.unwrap()
+        );
+
+        let stats = system.get_stats(symbol).await;
+        assert_eq!(stats.successful_communications, 4);
+        assert!((stats.channel(&Channel::Light).success_rate() - 2.0 / 3.0).abs() < 1e-6);
+        assert!((stats.channel(&Channel::Ble).success_rate() - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(stats.channel(&Channel::Audio), ChannelStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_per_channel_generations() {
+        let symbol = SymbolId::from_bytes(b"TEST");
+        let system = LearningSystem::new(LearningConfig {
+            strategy: LearningStrategy::Evolution,
+            evaluation_interval: 2,
+            ..LearningConfig::default()
+        });
+        system.initialize_symbol(symbol).await.unwrap();
+
+        let pattern = system.get_best_pattern(symbol).await.unwrap();
+        for channel in [Channel::Ble, Channel::Light, Channel::Ble, Channel::Light] {
+            let outcome = CommunicationOutcome::success(symbol, pattern.clone()).with_channel(channel);
+            system.record_outcome(outcome).await.unwrap();
+        }
+
+        let stats = system.get_stats(symbol).await;
+        assert_eq!(stats.current_generation, 2);
+        assert_eq!(stats.channel(&Channel::Light).current_generation, 2);
+        assert_eq!(stats.channel(&Channel::Ble).current_generation, 0);
+    }
+
     #[tokio::test]
     async fn test_reset_symbol() {
         let system = LearningSystem::with_default_config();
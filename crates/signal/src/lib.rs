@@ -7,12 +7,16 @@
 /// - Signal evolution framework
 /// - Pattern recognition
 /// - Adaptive learning
+/// - Bandit-based pattern selection
 /// - Multi-hop routing and forwarding
+/// - Forward error correction for lossy channels
 
+pub mod bandit;
 pub mod codebook;
 pub mod emitter;
 pub mod error;
 pub mod evolution;
+pub mod fec;
 pub mod forwarder;
 pub mod learning;
 pub mod negotiation;
@@ -22,12 +26,19 @@ pub mod routing;
 pub mod signal;
 
 // Re-export commonly used types
+pub use bandit::{BanditArm, UcbBandit};
 pub use codebook::{Codebook, CodebookEntry, StandardSymbol};
-pub use emitter::{ConsoleEmitter, Emitter, MockEmitter};
-pub use error::{DecodeError, EmitError, NegotiationError, ReceiveError, RoutingError, SignalError};
+pub use emitter::{ConsoleEmitter, EmitRateController, Emitter, MockEmitter};
+pub use error::{
+    DecodeError, EmitError, FecError, NegotiationError, ReceiveError, RoutingError, SignalError,
+};
 pub use evolution::{EvolutionConfig, EvolutionEngine, EvolvedPattern, FitnessMetrics};
+pub use fec::{FecEncoder, FecFragment, FecReassembler, FecStats};
 pub use forwarder::{ForwardedMessage, SignalForwarder};
-pub use learning::{CommunicationOutcome, LearningConfig, LearningStats, LearningStrategy, LearningSystem};
+pub use learning::{
+    ChannelStats, CommunicationOutcome, LearningConfig, LearningHistory, LearningStats, LearningStrategy,
+    LearningSystem,
+};
 pub use negotiation::{ChannelNegotiator, ChannelQuality};
 pub use receiver::{MockReceiver, Receiver};
 pub use recognition::{MatchConfidence, RecognitionConfig, RecognitionEngine, RecognizedSignal, SignalTemplate};
@@ -35,4 +46,6 @@ pub use routing::{
     MultiHopMessage, MultiHopRouter, Route, RouteDiscoveryReply, RouteDiscoveryRequest, RouteHop,
     RouteId, RoutingTable,
 };
-pub use signal::{Channel, Pulse, Signal, SignalPattern};
+pub use signal::{
+    Channel, CompressedPattern, PatternToken, Pulse, Signal, SignalPattern, MAX_EXPANDED_PULSES,
+};
@@ -177,6 +177,39 @@ impl ChannelNegotiator {
         }
     }
 
+    /// Pick the channel both ends support with the best measured quality.
+    /// Channels known to be unavailable are skipped; unmeasured ones rank
+    /// below measured ones, and ties go to the priority order. Returns `None`
+    /// when the two sets are disjoint.
+    pub async fn negotiate(&self, local: &[Channel], remote: &[Channel]) -> Option<Channel> {
+        let qualities = self.qualities.read().await;
+        let rank = |channel: &Channel| {
+            self.priority
+                .iter()
+                .position(|c| c == channel)
+                .unwrap_or(self.priority.len())
+        };
+
+        let mut best: Option<(&Channel, f32)> = None;
+        for channel in local.iter().filter(|c| remote.contains(c)) {
+            let score = match qualities.get(channel) {
+                Some(quality) if !quality.available => continue,
+                Some(quality) => quality.score(),
+                None => 0.0,
+            };
+            let better = best.is_none_or(|(current, current_score)| {
+                score > current_score || (score == current_score && rank(channel) < rank(current))
+            });
+            if better {
+                best = Some((channel, score));
+            }
+        }
+
+        let chosen = best.map(|(channel, _)| channel.clone());
+        debug!(local = ?local, remote = ?remote, chosen = ?chosen, "Negotiated channel");
+        chosen
+    }
+
     pub async fn available_channels(&self) -> Vec<Channel> {
         let qualities = self.qualities.read().await;
         qualities
@@ -255,6 +288,54 @@ mod tests {
         assert_eq!(result, Channel::Light);
     }
 
+    #[tokio::test]
+    async fn test_negotiate_common_channel() {
+        let negotiator = ChannelNegotiator::new();
+        negotiator
+            .update_quality(
+                Channel::Light,
+                ChannelQuality {
+                    snr: 80.0,
+                    latency_us: 1000,
+                    packet_loss: 0.0,
+                    available: true,
+                },
+            )
+            .await;
+        negotiator
+            .update_quality(
+                Channel::Ble,
+                ChannelQuality {
+                    snr: 20.0,
+                    latency_us: 20_000,
+                    packet_loss: 0.2,
+                    available: true,
+                },
+            )
+            .await;
+
+        let local = [Channel::Light, Channel::Ble, Channel::Audio];
+
+        // Best measured channel both sides have
+        let both = [Channel::Ble, Channel::Light];
+        assert_eq!(negotiator.negotiate(&local, &both).await, Some(Channel::Light));
+
+        // Remote only has BLE
+        assert_eq!(negotiator.negotiate(&local, &[Channel::Ble]).await, Some(Channel::Ble));
+
+        // Unmeasured overlap still works, measured channels win over it
+        let remote = [Channel::Audio, Channel::Ble];
+        assert_eq!(negotiator.negotiate(&local, &remote).await, Some(Channel::Ble));
+        assert_eq!(negotiator.negotiate(&local, &[Channel::Audio]).await, Some(Channel::Audio));
+
+        // Disjoint
+        assert_eq!(negotiator.negotiate(&local, &[Channel::Radio]).await, None);
+
+        // Known-unavailable channels are never chosen
+        negotiator.mark_unavailable(Channel::Light).await;
+        assert_eq!(negotiator.negotiate(&local, &[Channel::Light]).await, None);
+    }
+
     #[tokio::test]
     async fn test_no_channels_available() {
         let negotiator = ChannelNegotiator::new();
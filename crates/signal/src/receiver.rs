@@ -5,19 +5,56 @@ use tracing::debug;
 
 use crate::codebook::Codebook;
 use crate::error::{DecodeError, ReceiveError};
-use crate::signal::{Channel, Signal, SignalPattern};
+use crate::recognition::MatchConfidence;
+use crate::signal::{Channel, CompressedPattern, Signal, SignalPattern};
 
 #[async_trait]
 pub trait Receiver: Send + Sync {
     fn channel(&self) -> Channel;
     async fn receive(&self) -> Result<SignalPattern, ReceiveError>;
     async fn decode(&self, codebook: &Codebook) -> Result<Signal, DecodeError>;
+
+    /// Lowest match score accepted as a clean symbol. The default of 1.0
+    /// only accepts patterns whose pulses all fall within the codebook's
+    /// duration tolerance.
+    fn min_confidence(&self) -> f32 {
+        1.0
+    }
+
+    /// Receive and decode to the closest symbol, failing with
+    /// `DecodeError::LowConfidence` below `min_confidence`
+    async fn decode_with_confidence(
+        &self,
+        codebook: &Codebook,
+    ) -> Result<(Signal, MatchConfidence), DecodeError> {
+        let pattern = self.receive().await?;
+        let (symbol, confidence) = codebook.decode_with_confidence(&pattern)?;
+        if confidence.score < self.min_confidence() {
+            return Err(DecodeError::LowConfidence {
+                best_symbol: symbol,
+                score: confidence.score,
+            });
+        }
+        Ok((Signal::new(symbol, pattern, self.channel()), confidence))
+    }
+
+    /// Decode a pattern that was compressed against `codebook` before emission
+    async fn decode_compressed(
+        &self,
+        compressed: &CompressedPattern,
+        codebook: &Codebook,
+    ) -> Result<Signal, DecodeError> {
+        let pattern = codebook.decompress(compressed)?;
+        let symbol = codebook.decode(&pattern)?;
+        Ok(Signal::new(symbol, pattern, self.channel()))
+    }
 }
 
 pub struct MockReceiver {
     channel: Channel,
     patterns: Arc<Mutex<Vec<SignalPattern>>>,
     should_fail: bool,
+    min_confidence: f32,
 }
 
 impl MockReceiver {
@@ -26,17 +63,23 @@ impl MockReceiver {
             channel,
             patterns: Arc::new(Mutex::new(Vec::new())),
             should_fail: false,
+            min_confidence: 1.0,
         }
     }
 
     pub fn failing(channel: Channel) -> Self {
         Self {
-            channel,
-            patterns: Arc::new(Mutex::new(Vec::new())),
             should_fail: true,
+            ..Self::new(channel)
         }
     }
 
+    /// Accept near-matches scoring at least `min_confidence` in `decode`
+    pub fn with_min_confidence(mut self, min_confidence: f32) -> Self {
+        self.min_confidence = min_confidence.clamp(0.0, 1.0);
+        self
+    }
+
     pub async fn queue_pattern(&self, pattern: SignalPattern) {
         self.patterns.lock().await.push(pattern);
     }
@@ -79,10 +122,12 @@ impl Receiver for MockReceiver {
     }
 
     async fn decode(&self, codebook: &Codebook) -> Result<Signal, DecodeError> {
-        let pattern = self.receive().await?;
-        let symbol = codebook.decode(&pattern)?;
+        let (signal, _) = self.decode_with_confidence(codebook).await?;
+        Ok(signal)
+    }
 
-        Ok(Signal::new(symbol, pattern, self.channel.clone()))
+    fn min_confidence(&self) -> f32 {
+        self.min_confidence
     }
 }
 
@@ -124,4 +169,36 @@ mod tests {
         assert_eq!(signal.symbol, StandardSymbol::Ack.to_symbol_id());
         assert_eq!(signal.channel, Channel::Ble);
     }
+
+    #[tokio::test]
+    async fn test_low_confidence_rejected() {
+        let codebook = Codebook::new();
+        let receiver = MockReceiver::new(Channel::Light).with_min_confidence(0.8);
+        let ready = StandardSymbol::Ready.to_symbol_id();
+
+        // Ready is on(300) off(100) on(100); small drift is still Ready
+        let drifted = SignalPattern::new(vec![Pulse::on(320), Pulse::off(95), Pulse::on(105)]);
+        receiver.queue_pattern(drifted).await;
+        let (signal, confidence) = receiver.decode_with_confidence(&codebook).await.unwrap();
+        assert_eq!(signal.symbol, ready);
+        assert!(confidence.score > 0.8);
+
+        // Badly degraded: the receiver caught it inverted
+        let degraded = SignalPattern::new(vec![Pulse::off(300), Pulse::on(100), Pulse::off(100)]);
+        receiver.queue_pattern(degraded).await;
+        match receiver.decode(&codebook).await {
+            Err(DecodeError::LowConfidence { score, .. }) => assert!(score < 0.8),
+            other => panic!("expected low confidence, got {:?}", other),
+        }
+
+        // The default threshold only takes pulses within the codebook tolerance
+        let strict = MockReceiver::new(Channel::Light);
+        strict
+            .queue_pattern(SignalPattern::new(vec![Pulse::on(360), Pulse::off(95), Pulse::on(105)]))
+            .await;
+        assert!(matches!(
+            strict.decode(&codebook).await,
+            Err(DecodeError::LowConfidence { best_symbol, .. }) if best_symbol == ready
+        ));
+    }
 }
@@ -153,6 +153,26 @@ impl RecognitionEngine {
         Ok(())
     }
 
+    /// Register `pattern` for `symbol` unless a matching template exists.
+    /// Returns whether a template was added.
+    pub async fn ensure_template(
+        &self,
+        symbol: SymbolId,
+        pattern: &SignalPattern,
+    ) -> Result<bool, SignalError> {
+        let known = self
+            .templates
+            .read()
+            .await
+            .get(&symbol)
+            .is_some_and(|list| list.iter().any(|t| self.patterns_match(&t.pattern, pattern)));
+        if known {
+            return Ok(false);
+        }
+        self.register_template(symbol, pattern.clone()).await?;
+        Ok(true)
+    }
+
     /// Recognize a signal pattern
     pub async fn recognize(
         &self,
@@ -204,48 +224,8 @@ impl RecognitionEngine {
 
     /// Calculate normalized distance between two patterns
     fn pattern_distance(&self, p1: &SignalPattern, p2: &SignalPattern) -> f32 {
-        // Length difference component
-        let len_diff = (p1.pulses.len() as i32 - p2.pulses.len() as i32).abs() as f32;
-        let max_len = p1.pulses.len().max(p2.pulses.len()).max(1) as f32;
-        let len_distance = len_diff / max_len;
-
-        // Pulse-wise comparison
-        let mut pulse_distance = 0.0;
-        let min_len = p1.pulses.len().min(p2.pulses.len());
-
-        for i in 0..min_len {
-            let pulse1 = &p1.pulses[i];
-            let pulse2 = &p2.pulses[i];
-
-            // On/off state difference
-            let state_diff = if pulse1.on != pulse2.on { 1.0 } else { 0.0 };
-
-            // Duration difference with tolerance
-            let duration_diff = if self.config.fuzzy_matching {
-                let tolerance = pulse2.duration_us as f32 * self.config.duration_tolerance;
-                let diff = (pulse1.duration_us as i32 - pulse2.duration_us as i32).abs() as f32;
-                if diff <= tolerance {
-                    0.0
-                } else {
-                    (diff - tolerance) / pulse2.duration_us as f32
-                }
-            } else {
-                (pulse1.duration_us as f32 - pulse2.duration_us as f32).abs()
-                    / pulse2.duration_us.max(1) as f32
-            };
-
-            pulse_distance += (state_diff + duration_diff.min(1.0)) / 2.0;
-        }
-
-        // Normalize pulse distance
-        let avg_pulse_distance = if min_len > 0 {
-            pulse_distance / min_len as f32
-        } else {
-            1.0
-        };
-
-        // Combine length and pulse distances
-        (len_distance * 0.3 + avg_pulse_distance * 0.7).clamp(0.0, 1.0)
+        let tolerance = self.config.fuzzy_matching.then_some(self.config.duration_tolerance);
+        pattern_distance(p1, p2, tolerance)
     }
 
     /// Update template statistics after usage
@@ -329,6 +309,68 @@ impl RecognitionEngine {
     }
 }
 
+/// Normalized distance between an observed pattern `p1` and a reference
+/// `p2`, from 0.0 (identical) to 1.0. With a `duration_tolerance`, duration
+/// differences within that fraction of the reference count as exact.
+pub fn pattern_distance(
+    p1: &SignalPattern,
+    p2: &SignalPattern,
+    duration_tolerance: Option<f32>,
+) -> f32 {
+    // Length difference component
+    let len_diff = (p1.pulses.len() as i32 - p2.pulses.len() as i32).abs() as f32;
+    let max_len = p1.pulses.len().max(p2.pulses.len()).max(1) as f32;
+    let len_distance = len_diff / max_len;
+
+    // Pulse-wise comparison
+    let mut pulse_distance = 0.0;
+    let min_len = p1.pulses.len().min(p2.pulses.len());
+
+    for i in 0..min_len {
+        let pulse1 = &p1.pulses[i];
+        let pulse2 = &p2.pulses[i];
+
+        // On/off state difference
+        let state_diff = if pulse1.on != pulse2.on { 1.0 } else { 0.0 };
+
+        // Duration difference with tolerance
+        let duration_diff = if let Some(duration_tolerance) = duration_tolerance {
+            let tolerance = pulse2.duration_us as f32 * duration_tolerance;
+            let diff = (pulse1.duration_us as i32 - pulse2.duration_us as i32).abs() as f32;
+            if diff <= tolerance {
+                0.0
+            } else {
+                (diff - tolerance) / pulse2.duration_us as f32
+            }
+        } else {
+            (pulse1.duration_us as f32 - pulse2.duration_us as f32).abs()
+                / pulse2.duration_us.max(1) as f32
+        };
+
+        pulse_distance += (state_diff + duration_diff.min(1.0)) / 2.0;
+    }
+
+    // Normalize pulse distance
+    let avg_pulse_distance = if min_len > 0 {
+        pulse_distance / min_len as f32
+    } else {
+        1.0
+    };
+
+    // Combine length and pulse distances
+    (len_distance * 0.3 + avg_pulse_distance * 0.7).clamp(0.0, 1.0)
+}
+
+/// Confidence that observed pattern `p1` is reference pattern `p2`
+pub fn match_confidence(
+    p1: &SignalPattern,
+    p2: &SignalPattern,
+    duration_tolerance: Option<f32>,
+) -> MatchConfidence {
+    let distance = pattern_distance(p1, p2, duration_tolerance);
+    MatchConfidence::new(1.0 - distance, distance)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,17 @@
 use cortex_core::SymbolId;
 use serde::{Deserialize, Serialize};
 
+use bincode::Options;
+
+use crate::error::SignalError;
+
+/// Longest repeating unit run-length encoding looks for, in pulses
+const MAX_RUN_UNIT: usize = 4;
+
+/// Most pulses a compressed pattern may expand to, so a tiny run token
+/// cannot make the receiver allocate without bound
+pub const MAX_EXPANDED_PULSES: usize = 1 << 16;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Channel {
     Light,
@@ -10,7 +21,7 @@ pub enum Channel {
     Radio,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Pulse {
     pub on: bool,
     pub duration_us: u32,
@@ -51,6 +62,137 @@ impl SignalPattern {
     pub fn pulse_count(&self) -> usize {
         self.pulses.len()
     }
+
+    /// Run-length encode repeated pulses and short repeated sub-patterns
+    pub fn compressed(&self) -> CompressedPattern {
+        self.compress_with(&[])
+    }
+
+    /// Expand a pattern produced by `compressed`. Patterns compressed against
+    /// a codebook dictionary need `Codebook::decompress` instead.
+    pub fn decompressed(compressed: &CompressedPattern) -> Result<Self, SignalError> {
+        compressed.expand_with(&[])
+    }
+
+    /// Greedy left-to-right compression: at each position take whichever of
+    /// a run or the longest matching dictionary entry covers more pulses
+    pub(crate) fn compress_with(&self, dictionary: &[Vec<Pulse>]) -> CompressedPattern {
+        let pulses = &self.pulses;
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < pulses.len() {
+            let run = (1..=MAX_RUN_UNIT)
+                .filter(|&unit| i + unit * 2 <= pulses.len())
+                .map(|unit| {
+                    let repeats = pulses[i..]
+                        .chunks_exact(unit)
+                        .take_while(|chunk| *chunk == &pulses[i..i + unit])
+                        .count();
+                    (unit, repeats)
+                })
+                .filter(|&(_, repeats)| repeats >= 2)
+                .max_by_key(|&(unit, repeats)| (unit * repeats, std::cmp::Reverse(unit)));
+
+            let reference = dictionary
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| !entry.is_empty() && pulses[i..].starts_with(entry))
+                .max_by_key(|(_, entry)| entry.len());
+
+            let run_len = run.map_or(0, |(unit, repeats)| unit * repeats);
+            let ref_len = reference.map_or(0, |(_, entry)| entry.len());
+
+            if run_len >= 2 && run_len >= ref_len {
+                let (unit, repeats) = run.unwrap_or_default();
+                tokens.push(PatternToken::Run {
+                    unit: pulses[i..i + unit].to_vec(),
+                    count: repeats as u32,
+                });
+                i += run_len;
+            } else if let Some((index, entry)) = reference {
+                tokens.push(PatternToken::Ref(index as u16));
+                i += entry.len();
+            } else {
+                tokens.push(PatternToken::Pulse(pulses[i]));
+                i += 1;
+            }
+        }
+
+        CompressedPattern { tokens }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatternToken {
+    Pulse(Pulse),
+    /// `unit` repeated `count` times
+    Run { unit: Vec<Pulse>, count: u32 },
+    /// Entry of the codebook dictionary
+    Ref(u16),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressedPattern {
+    pub tokens: Vec<PatternToken>,
+}
+
+impl CompressedPattern {
+    /// Varint-encoded size in bytes, comparable with `SignalPattern::encoded_len`
+    pub fn encoded_len(&self) -> usize {
+        bincode::DefaultOptions::new()
+            .serialized_size(self)
+            .map_or(0, |size| size as usize)
+    }
+
+    pub(crate) fn expand_with(&self, dictionary: &[Vec<Pulse>]) -> Result<SignalPattern, SignalError> {
+        let mut pulses = Vec::with_capacity(self.expanded_len(dictionary)?);
+        for token in &self.tokens {
+            match token {
+                PatternToken::Pulse(pulse) => pulses.push(*pulse),
+                PatternToken::Run { unit, count } => {
+                    for _ in 0..*count {
+                        pulses.extend_from_slice(unit);
+                    }
+                }
+                PatternToken::Ref(index) => {
+                    let entry = dictionary.get(*index as usize).ok_or_else(|| {
+                        SignalError::CodecError(format!("unknown dictionary entry {}", index))
+                    })?;
+                    pulses.extend_from_slice(entry);
+                }
+            }
+        }
+        Ok(SignalPattern::new(pulses))
+    }
+
+    fn expanded_len(&self, dictionary: &[Vec<Pulse>]) -> Result<usize, SignalError> {
+        let mut total = 0usize;
+        for token in &self.tokens {
+            let len = match token {
+                PatternToken::Pulse(_) => 1,
+                PatternToken::Run { unit, count } => unit.len().saturating_mul(*count as usize),
+                PatternToken::Ref(index) => dictionary.get(*index as usize).map_or(0, Vec::len),
+            };
+            total = total.saturating_add(len);
+            if total > MAX_EXPANDED_PULSES {
+                return Err(SignalError::CodecError(format!(
+                    "pattern expands to more than {} pulses",
+                    MAX_EXPANDED_PULSES
+                )));
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl SignalPattern {
+    /// Varint-encoded size in bytes of the uncompressed pattern
+    pub fn encoded_len(&self) -> usize {
+        bincode::DefaultOptions::new()
+            .serialized_size(self)
+            .map_or(0, |size| size as usize)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -95,4 +237,36 @@ mod tests {
         assert_eq!(pattern.total_duration_us(), 2500);
         assert_eq!(pattern.pulse_count(), 3);
     }
+
+    #[test]
+    fn test_run_length_roundtrip() {
+        let mut pulses = [Pulse::on(100), Pulse::off(100)].repeat(20);
+        pulses.push(Pulse::on(900));
+        pulses.extend(std::iter::repeat_n(Pulse::off(50), 6));
+        let pattern = SignalPattern::new(pulses);
+
+        let compressed = pattern.compressed();
+        assert_eq!(compressed.tokens.len(), 3);
+        assert_eq!(SignalPattern::decompressed(&compressed).unwrap(), pattern);
+        assert!(compressed.encoded_len() < pattern.encoded_len() / 4);
+
+        let unrepeated = SignalPattern::new(vec![Pulse::on(1), Pulse::off(2), Pulse::on(3)]);
+        assert_eq!(SignalPattern::decompressed(&unrepeated.compressed()).unwrap(), unrepeated);
+
+        let dangling = CompressedPattern { tokens: vec![PatternToken::Ref(0)] };
+        assert!(matches!(SignalPattern::decompressed(&dangling), Err(SignalError::CodecError(_))));
+    }
+
+    #[test]
+    fn test_oversized_run_is_rejected() {
+        let bomb = CompressedPattern {
+            tokens: vec![PatternToken::Run { unit: vec![Pulse::on(1), Pulse::off(1)], count: u32::MAX }],
+        };
+        assert!(matches!(SignalPattern::decompressed(&bomb), Err(SignalError::CodecError(_))));
+
+        let limit = CompressedPattern {
+            tokens: vec![PatternToken::Run { unit: vec![Pulse::on(1)], count: MAX_EXPANDED_PULSES as u32 }],
+        };
+        assert_eq!(SignalPattern::decompressed(&limit).unwrap().pulse_count(), MAX_EXPANDED_PULSES);
+    }
 }
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 
 use cortex_reputation::SkillId;
 
+use crate::executor::CancellationToken;
+
 /// Metadata about a skill
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillMetadata {
@@ -56,9 +58,13 @@ pub struct CostEstimate {
     pub memory_mb: u32,
 }
 
-/// Capability requirements for a skill
+/// Capability requirements for a skill, and what it provides
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillCapability {
+    /// Generic capabilities offered (translation, code, ...), so a request
+    /// can match a skill without naming its exact id
+    #[serde(default)]
+    pub provides: Vec<String>,
     /// Required hardware (gpu, tpu, etc.)
     pub hardware: Vec<String>,
     /// Required models (llama-7b, whisper, etc.)
@@ -74,6 +80,7 @@ pub struct SkillCapability {
 impl Default for SkillCapability {
     fn default() -> Self {
         Self {
+            provides: Vec::new(),
             hardware: Vec::new(),
             models: Vec::new(),
             min_memory_mb: 0,
@@ -83,6 +90,24 @@ impl Default for SkillCapability {
     }
 }
 
+impl SkillCapability {
+    /// Request for any skill providing `capability`
+    pub fn providing(capability: &str) -> Self {
+        Self {
+            provides: vec![capability.to_lowercase()],
+            ..Self::default()
+        }
+    }
+
+    /// Whether a skill declaring `self` serves a request for `wanted`: it
+    /// provides every wanted capability and uses every wanted model
+    pub fn satisfies(&self, wanted: &SkillCapability) -> bool {
+        let has = |offered: &[String], name: &String| offered.iter().any(|o| o.eq_ignore_ascii_case(name));
+        wanted.provides.iter().all(|c| has(&self.provides, c))
+            && wanted.models.iter().all(|m| has(&self.models, m))
+    }
+}
+
 /// A skill that can be executed
 #[async_trait]
 pub trait Skill: Send + Sync {
@@ -102,6 +127,31 @@ pub trait Skill: Send + Sync {
     /// Execute the skill with given input
     async fn execute(&self, input: SkillInput) -> crate::Result<SkillOutput>;
 
+    /// Execute, observing `cancel`. The executor stops awaiting a skill
+    /// when it times out or is cancelled; skills that spawn work or hold
+    /// remote calls open should override this and stop them when the
+    /// token fires.
+    async fn execute_cancellable(
+        &self,
+        input: SkillInput,
+        cancel: &CancellationToken,
+    ) -> crate::Result<SkillOutput> {
+        let _ = cancel;
+        self.execute(input).await
+    }
+
+    /// Execute several inputs at once, returning one output per input in
+    /// the same order. Skills that can amortize per-call overhead (one
+    /// forward pass over N prompts) should override this; the default runs
+    /// them one at a time.
+    async fn execute_batch(&self, inputs: Vec<SkillInput>) -> crate::Result<Vec<SkillOutput>> {
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            outputs.push(self.execute(input).await?);
+        }
+        Ok(outputs)
+    }
+
     /// Estimate execution cost
     fn estimate_cost(&self, _input: &SkillInput) -> Option<CostEstimate> {
         self.metadata().cost_estimate.clone()
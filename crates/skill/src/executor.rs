@@ -1,16 +1,84 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::RwLock;
-use tracing::{debug, info, error};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, Mutex, Notify, RwLock};
+use tracing::{debug, info, error, warn};
 
 use cortex_grid::NodeId;
 use cortex_reputation::{Rating, SkillId, TrustGraph};
 
-use crate::definition::{SkillInput, SkillOutput};
+use crate::definition::{Skill, SkillInput, SkillOutput};
 use crate::registry::LocalSkillRegistry;
 use crate::task::{SkillTask, TaskResult};
 use crate::error::{SkillError, Result};
 
+/// Cooperative cancellation signal shared between the executor and a
+/// running skill. Clones observe the same signal.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancelState>,
+}
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// How `SkillExecutor::submit` groups single requests into batches
+#[derive(Debug, Clone, Copy)]
+pub struct BatchWindow {
+    /// Most inputs handed to `Skill::execute_batch` at once
+    pub max_batch_size: usize,
+    /// How long the first request of a batch waits for others to join
+    pub max_wait: Duration,
+}
+
+impl Default for BatchWindow {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 8,
+            max_wait: Duration::from_millis(5),
+        }
+    }
+}
+
 /// Context for skill execution
 pub struct ExecutionContext {
     /// Who requested this
@@ -19,6 +87,40 @@ pub struct ExecutionContext {
     pub executor: NodeId,
     /// Trust graph for looking up other nodes
     pub trust_graph: Arc<RwLock<TrustGraph>>,
+    /// Upper bound on the skill's run time, unbounded if `None`
+    pub timeout: Option<Duration>,
+    /// Fired on timeout, or by the caller to abort the execution
+    pub cancel: CancellationToken,
+    /// Batching window for batched execution
+    pub batch: BatchWindow,
+}
+
+impl ExecutionContext {
+    pub fn new(requester: NodeId, executor: NodeId, trust_graph: Arc<RwLock<TrustGraph>>) -> Self {
+        Self {
+            requester,
+            executor,
+            trust_graph,
+            timeout: None,
+            cancel: CancellationToken::new(),
+            batch: BatchWindow::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_cancel_token(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn with_batch_window(mut self, batch: BatchWindow) -> Self {
+        self.batch = batch;
+        self
+    }
 }
 
 /// Result of execution with metadata
@@ -33,7 +135,19 @@ pub struct ExecutionResult {
 pub struct SkillExecutor {
     my_id: NodeId,
     local_skills: Arc<RwLock<LocalSkillRegistry>>,
-    _trust_graph: Arc<RwLock<TrustGraph>>,
+    trust_graph: Arc<RwLock<TrustGraph>>,
+    /// Applied by `execute` when the caller gives no context
+    default_timeout: Option<Duration>,
+    /// Batching window handed out with `context`
+    batch_window: BatchWindow,
+    /// Per-skill queues feeding `submit` batches
+    batchers: Mutex<HashMap<SkillId, mpsc::UnboundedSender<PendingInput>>>,
+}
+
+/// A single request waiting in a `submit` batch
+struct PendingInput {
+    input: SkillInput,
+    reply: oneshot::Sender<Result<ExecutionResult>>,
 }
 
 impl SkillExecutor {
@@ -45,29 +159,63 @@ impl SkillExecutor {
         Self {
             my_id,
             local_skills,
-            _trust_graph: trust_graph,
+            trust_graph,
+            default_timeout: None,
+            batch_window: BatchWindow::default(),
+            batchers: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Execute a skill locally
-    pub async fn execute(&self, skill_id: &SkillId, input: SkillInput) -> Result<ExecutionResult> {
-        let start = Instant::now();
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
 
-        let skills = self.local_skills.read().await;
-        let skill = skills
-            .get(skill_id)
-            .ok_or_else(|| SkillError::SkillNotFound(skill_id.to_string()))?;
+    pub fn with_batch_window(mut self, batch_window: BatchWindow) -> Self {
+        self.batch_window = batch_window;
+        self
+    }
 
-        if !skill.can_execute() {
-            return Err(SkillError::ExecutionFailed(format!(
-                "Skill {} cannot execute on this node",
-                skill_id
-            )));
+    /// Context for running a skill on behalf of `requester` with this
+    /// executor's default timeout
+    pub fn context(&self, requester: NodeId) -> ExecutionContext {
+        let ctx = ExecutionContext::new(requester, self.my_id, Arc::clone(&self.trust_graph))
+            .with_batch_window(self.batch_window);
+        match self.default_timeout {
+            Some(timeout) => ctx.with_timeout(timeout),
+            None => ctx,
         }
+    }
+
+    /// Execute a skill locally
+    pub async fn execute(&self, skill_id: &SkillId, input: SkillInput) -> Result<ExecutionResult> {
+        self.execute_with_context(skill_id, input, &self.context(self.my_id)).await
+    }
+
+    /// Execute a skill locally, bounded by `ctx.timeout`. On timeout the
+    /// context's token is cancelled so work the skill spawned can stop, and
+    /// `SkillError::Timeout` is returned. Cancelling the token from outside
+    /// returns `SkillError::Cancelled`.
+    pub async fn execute_with_context(
+        &self,
+        skill_id: &SkillId,
+        input: SkillInput,
+        ctx: &ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        let start = Instant::now();
+        let skill = self.runnable(skill_id).await?;
 
         debug!("Executing skill: {}", skill_id);
 
-        match skill.execute(input).await {
+        let outcome = bounded(
+            skill_id,
+            ctx.timeout,
+            &ctx.cancel,
+            skill.execute_cancellable(input, &ctx.cancel),
+        )
+        .await;
+
+        match outcome {
             Ok(output) => {
                 let duration_ms = start.elapsed().as_millis() as u64;
                 info!(
@@ -87,17 +235,90 @@ impl SkillExecutor {
         }
     }
 
+    /// Execute `inputs` through `Skill::execute_batch` in batches of at
+    /// most `ctx.batch.max_batch_size`, each bounded by `ctx.timeout`.
+    /// Results are in input order.
+    pub async fn execute_batch(
+        &self,
+        skill_id: &SkillId,
+        inputs: Vec<SkillInput>,
+        ctx: &ExecutionContext,
+    ) -> Result<Vec<ExecutionResult>> {
+        let skill = self.runnable(skill_id).await?;
+        let max = ctx.batch.max_batch_size.max(1);
+
+        let mut results = Vec::with_capacity(inputs.len());
+        let mut inputs = inputs.into_iter().peekable();
+        while inputs.peek().is_some() {
+            let chunk: Vec<_> = inputs.by_ref().take(max).collect();
+            results.extend(run_batch(skill_id, skill.as_ref(), chunk, ctx.timeout, &ctx.cancel).await?);
+        }
+        Ok(results)
+    }
+
+    /// Execute a single input through the skill's batch queue. Requests
+    /// arriving within the executor's batching window are handed to
+    /// `Skill::execute_batch` together, so skills with per-call overhead
+    /// can amortize it.
+    pub async fn submit(&self, skill_id: &SkillId, input: SkillInput) -> Result<ExecutionResult> {
+        let queue = {
+            let mut batchers = self.batchers.lock().await;
+            match batchers.get(skill_id) {
+                Some(queue) if !queue.is_closed() => queue.clone(),
+                _ => {
+                    let skill = self.runnable(skill_id).await?;
+                    let queue = spawn_batcher(skill_id.clone(), skill, self.batch_window, self.default_timeout);
+                    batchers.insert(skill_id.clone(), queue.clone());
+                    queue
+                }
+            }
+        };
+
+        let (reply, result) = oneshot::channel();
+        queue
+            .send(PendingInput { input, reply })
+            .map_err(|_| SkillError::ExecutionFailed(format!("Batch queue for {} stopped", skill_id)))?;
+        result
+            .await
+            .map_err(|_| SkillError::ExecutionFailed(format!("Batch for {} dropped the request", skill_id)))?
+    }
+
+    /// Look up a local skill that can run on this node
+    async fn runnable(&self, skill_id: &SkillId) -> Result<Arc<dyn Skill>> {
+        let skill = self
+            .local_skills
+            .read()
+            .await
+            .get(skill_id)
+            .ok_or_else(|| SkillError::SkillNotFound(skill_id.to_string()))?;
+
+        if !skill.can_execute() {
+            return Err(SkillError::ExecutionFailed(format!(
+                "Skill {} cannot execute on this node",
+                skill_id
+            )));
+        }
+        Ok(skill)
+    }
+
     /// Execute a task and report result
     pub async fn execute_task(&self, mut task: SkillTask) -> TaskResult {
         let _start = Instant::now();
 
         task.start(self.my_id);
 
-        match self.execute(&task.skill, task.input.clone()).await {
+        let ctx = self
+            .context(task.requester)
+            .with_timeout(Duration::from_secs(task.timeout_secs as u64));
+        match self.execute_with_context(&task.skill, task.input.clone(), &ctx).await {
             Ok(result) => {
                 task.complete();
                 TaskResult::success(task.id, result.output, self.my_id, result.duration_ms)
             }
+            Err(SkillError::Timeout) => {
+                task.time_out();
+                TaskResult::failure(task.id, &SkillError::Timeout.to_string(), self.my_id)
+            }
             Err(e) => {
                 task.fail(&e.to_string());
                 TaskResult::failure(task.id, &e.to_string(), self.my_id)
@@ -133,6 +354,110 @@ impl SkillExecutor {
     }
 }
 
+/// Run `fut` until it finishes, `cancel` fires or `timeout` passes. On
+/// timeout the token is cancelled so work the skill spawned can stop.
+async fn bounded<T>(
+    skill_id: &SkillId,
+    timeout: Option<Duration>,
+    cancel: &CancellationToken,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        outcome = fut => outcome,
+        _ = cancel.cancelled() => Err(SkillError::Cancelled),
+        _ = deadline => {
+            warn!("Skill {} timed out after {:?}", skill_id, timeout.unwrap_or_default());
+            cancel.cancel();
+            Err(SkillError::Timeout)
+        }
+    }
+}
+
+/// One `Skill::execute_batch` call. Every result carries the duration of
+/// the whole batch.
+async fn run_batch(
+    skill_id: &SkillId,
+    skill: &dyn Skill,
+    inputs: Vec<SkillInput>,
+    timeout: Option<Duration>,
+    cancel: &CancellationToken,
+) -> Result<Vec<ExecutionResult>> {
+    let start = Instant::now();
+    let count = inputs.len();
+    let outputs = bounded(skill_id, timeout, cancel, skill.execute_batch(inputs)).await?;
+    if outputs.len() != count {
+        return Err(SkillError::ExecutionFailed(format!(
+            "Skill {} returned {} outputs for {} inputs",
+            skill_id,
+            outputs.len(),
+            count
+        )));
+    }
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+    debug!("Skill {} ran a batch of {} in {}ms", skill_id, count, duration_ms);
+    Ok(outputs
+        .into_iter()
+        .map(|output| ExecutionResult {
+            output,
+            duration_ms,
+            success: true,
+        })
+        .collect())
+}
+
+/// Background task collecting `submit` requests for one skill. The first
+/// request opens a batch that closes when it is full or `window.max_wait`
+/// has passed. Exits once the executor drops its queue.
+fn spawn_batcher(
+    skill_id: SkillId,
+    skill: Arc<dyn Skill>,
+    window: BatchWindow,
+    timeout: Option<Duration>,
+) -> mpsc::UnboundedSender<PendingInput> {
+    let (queue, mut pending) = mpsc::unbounded_channel::<PendingInput>();
+    tokio::spawn(async move {
+        while let Some(first) = pending.recv().await {
+            let mut batch = vec![first];
+            let closes_at = tokio::time::Instant::now() + window.max_wait;
+            while batch.len() < window.max_batch_size.max(1) {
+                match tokio::time::timeout_at(closes_at, pending.recv()).await {
+                    Ok(Some(next)) => batch.push(next),
+                    _ => break,
+                }
+            }
+
+            let (inputs, replies): (Vec<_>, Vec<_>) = batch.into_iter().map(|p| (p.input, p.reply)).unzip();
+            // A fresh token per batch, so one timeout doesn't cancel the next
+            let cancel = CancellationToken::new();
+            match run_batch(&skill_id, skill.as_ref(), inputs, timeout, &cancel).await {
+                Ok(results) => {
+                    for (reply, result) in replies.into_iter().zip(results) {
+                        let _ = reply.send(Ok(result));
+                    }
+                }
+                Err(e) => {
+                    error!("Batch for skill {} failed: {}", skill_id, e);
+                    for reply in replies {
+                        let _ = reply.send(Err(match e {
+                            SkillError::Timeout => SkillError::Timeout,
+                            SkillError::Cancelled => SkillError::Cancelled,
+                            ref other => SkillError::ExecutionFailed(other.to_string()),
+                        }));
+                    }
+                }
+            }
+        }
+    });
+    queue
+}
+
 /// Remote executor - sends tasks to other nodes
 pub struct RemoteExecutor {
     _my_id: NodeId,
@@ -170,3 +495,172 @@ impl RemoteExecutor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::{Skill, SkillMetadata};
+    use async_trait::async_trait;
+
+    /// Sleeps far longer than any test timeout. Its background work stops
+    /// when the token fires and records that it saw the cancellation.
+    struct SlowSkill {
+        metadata: SkillMetadata,
+        aborted: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl Skill for SlowSkill {
+        fn metadata(&self) -> &SkillMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, _input: SkillInput) -> Result<SkillOutput> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(SkillOutput::new())
+        }
+
+        async fn execute_cancellable(
+            &self,
+            input: SkillInput,
+            cancel: &CancellationToken,
+        ) -> Result<SkillOutput> {
+            let (cancel, aborted) = (cancel.clone(), Arc::clone(&self.aborted));
+            tokio::spawn(async move {
+                cancel.cancelled().await;
+                aborted.store(true, Ordering::SeqCst);
+            });
+            self.execute(input).await
+        }
+    }
+
+    fn executor() -> (SkillExecutor, Arc<AtomicBool>) {
+        let me = NodeId::random();
+        let aborted = Arc::new(AtomicBool::new(false));
+        let mut skills = LocalSkillRegistry::new();
+        skills.register(Arc::new(SlowSkill {
+            metadata: SkillMetadata::new("slow", "Slow", "Never finishes in time"),
+            aborted: Arc::clone(&aborted),
+        }));
+        let executor = SkillExecutor::new(
+            me,
+            Arc::new(RwLock::new(skills)),
+            Arc::new(RwLock::new(TrustGraph::new(me))),
+        );
+        (executor, aborted)
+    }
+
+    #[tokio::test]
+    async fn test_slow_skill_times_out_and_is_cancelled() {
+        let (executor, aborted) = executor();
+        let executor = executor.with_default_timeout(Duration::from_millis(50));
+
+        let start = Instant::now();
+        let result = executor.execute(&SkillId::new("slow"), SkillInput::new()).await;
+        assert!(matches!(result, Err(SkillError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(aborted.load(Ordering::SeqCst), "the skill should see the cancellation");
+    }
+
+    #[tokio::test]
+    async fn test_caller_can_cancel_execution() {
+        let (executor, aborted) = executor();
+        let ctx = executor.context(NodeId::random());
+        let cancel = ctx.cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+
+        let result = executor.execute_with_context(&SkillId::new("slow"), SkillInput::new(), &ctx).await;
+        assert!(matches!(result, Err(SkillError::Cancelled)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(aborted.load(Ordering::SeqCst));
+    }
+
+    /// Upper-cases text and records the size of every batch it runs
+    struct UpperSkill {
+        metadata: SkillMetadata,
+        batches: Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait]
+    impl Skill for UpperSkill {
+        fn metadata(&self) -> &SkillMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, input: SkillInput) -> Result<SkillOutput> {
+            Ok(SkillOutput::new().with_text(&input.get_text().unwrap_or_default().to_uppercase()))
+        }
+
+        async fn execute_batch(&self, inputs: Vec<SkillInput>) -> Result<Vec<SkillOutput>> {
+            self.batches.lock().unwrap().push(inputs.len());
+            let mut outputs = Vec::new();
+            for input in inputs {
+                outputs.push(self.execute(input).await?);
+            }
+            Ok(outputs)
+        }
+    }
+
+    fn upper_executor(window: BatchWindow) -> (SkillExecutor, Arc<std::sync::Mutex<Vec<usize>>>) {
+        let me = NodeId::random();
+        let batches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut skills = LocalSkillRegistry::new();
+        skills.register(Arc::new(UpperSkill {
+            metadata: SkillMetadata::new("upper", "Upper", "Upper-cases text"),
+            batches: Arc::clone(&batches),
+        }));
+        let executor = SkillExecutor::new(
+            me,
+            Arc::new(RwLock::new(skills)),
+            Arc::new(RwLock::new(TrustGraph::new(me))),
+        )
+        .with_batch_window(window);
+        (executor, batches)
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_splits_by_window() {
+        let (executor, batches) = upper_executor(BatchWindow {
+            max_batch_size: 2,
+            max_wait: Duration::from_millis(5),
+        });
+        let ctx = executor.context(NodeId::random());
+        let inputs = ["a", "b", "c", "d", "e"].map(|t| SkillInput::new().with_text(t)).to_vec();
+
+        let results = executor.execute_batch(&SkillId::new("upper"), inputs, &ctx).await.unwrap();
+        let texts: Vec<_> = results.iter().map(|r| r.output.get_text().unwrap()).collect();
+        assert_eq!(texts, ["A", "B", "C", "D", "E"]);
+        assert_eq!(*batches.lock().unwrap(), vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_submitted_requests_share_a_batch() {
+        let (executor, batches) = upper_executor(BatchWindow {
+            max_batch_size: 4,
+            max_wait: Duration::from_millis(200),
+        });
+        let executor = Arc::new(executor);
+
+        let handles: Vec<_> = ["w", "x", "y", "z"]
+            .into_iter()
+            .map(|text| {
+                let executor = Arc::clone(&executor);
+                tokio::spawn(async move {
+                    executor.submit(&SkillId::new("upper"), SkillInput::new().with_text(text)).await
+                })
+            })
+            .collect();
+        let mut texts = Vec::new();
+        for handle in handles {
+            texts.push(handle.await.unwrap().unwrap().output.get_text().unwrap());
+        }
+
+        assert_eq!(texts, ["W", "X", "Y", "Z"]);
+        assert_eq!(*batches.lock().unwrap(), vec![4], "requests arriving together run as one batch");
+    }
+}
@@ -6,7 +6,7 @@ pub mod task;
 pub mod error;
 
 pub use definition::{Skill, SkillCapability, SkillMetadata, SkillInput, SkillOutput};
-pub use executor::{SkillExecutor, ExecutionResult, ExecutionContext};
+pub use executor::{SkillExecutor, ExecutionResult, ExecutionContext, CancellationToken, BatchWindow};
 pub use router::{SkillRouter, RouteDecision};
 pub use registry::{LocalSkillRegistry, NetworkSkillRegistry};
 pub use task::{SkillTask, TaskStatus, TaskResult};
@@ -6,7 +6,7 @@ use tracing::{debug, info};
 use cortex_grid::NodeId;
 use cortex_reputation::SkillId;
 
-use crate::definition::{Skill, SkillMetadata};
+use crate::definition::{Skill, SkillCapability, SkillMetadata};
 
 /// Registry of locally available skills
 pub struct LocalSkillRegistry {
@@ -46,6 +46,18 @@ impl LocalSkillRegistry {
     pub fn has_skill(&self, id: &SkillId) -> bool {
         self.skills.contains_key(id)
     }
+
+    /// Skills whose declared capabilities satisfy `wanted`, ordered by id
+    pub fn find_by_capability(&self, wanted: &SkillCapability) -> Vec<Arc<dyn Skill>> {
+        let mut found: Vec<_> = self
+            .skills
+            .values()
+            .filter(|s| s.capabilities().satisfies(wanted))
+            .cloned()
+            .collect();
+        found.sort_by(|a, b| a.metadata().id.as_str().cmp(b.metadata().id.as_str()));
+        found
+    }
 }
 
 impl Default for LocalSkillRegistry {
@@ -60,6 +72,8 @@ pub struct NetworkSkillRegistry {
     node_skills: DashMap<NodeId, HashSet<SkillId>>,
     /// skill -> nodes that have it
     skill_nodes: DashMap<SkillId, HashSet<NodeId>>,
+    /// skill -> what it declares it provides, for capability matching
+    skill_capabilities: DashMap<SkillId, SkillCapability>,
     /// My skills
     my_id: NodeId,
     my_skills: HashSet<SkillId>,
@@ -70,6 +84,7 @@ impl NetworkSkillRegistry {
         Self {
             node_skills: DashMap::new(),
             skill_nodes: DashMap::new(),
+            skill_capabilities: DashMap::new(),
             my_id,
             my_skills: HashSet::new(),
         }
@@ -99,6 +114,33 @@ impl NetworkSkillRegistry {
         }
     }
 
+    /// Record the capabilities a skill declares
+    pub fn register_skill_capability(&self, skill: SkillId, capability: SkillCapability) {
+        self.skill_capabilities.insert(skill, capability);
+    }
+
+    /// Register my own skills from the local registry, including their
+    /// declared capabilities
+    pub fn register_local_skills(&mut self, local: &LocalSkillRegistry) {
+        for skill in local.skills.values() {
+            let id = skill.metadata().id.clone();
+            self.register_skill_capability(id.clone(), skill.capabilities());
+            self.register_my_skill(id);
+        }
+    }
+
+    /// Known skills whose declared capabilities satisfy `wanted`
+    pub fn skills_with_capability(&self, wanted: &SkillCapability) -> Vec<SkillId> {
+        let mut skills: Vec<SkillId> = self
+            .skill_capabilities
+            .iter()
+            .filter(|e| e.value().satisfies(wanted))
+            .map(|e| e.key().clone())
+            .collect();
+        skills.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        skills
+    }
+
     /// Register my own skill
     pub fn register_my_skill(&mut self, skill: SkillId) {
         self.my_skills.insert(skill.clone());
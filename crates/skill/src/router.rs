@@ -1,10 +1,12 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
 use cortex_grid::NodeId;
-use cortex_reputation::{TrustGraph, TrustScore};
+use cortex_reputation::{SkillId, TrustGraph, TrustScore};
 
+use crate::definition::SkillCapability;
 use crate::registry::NetworkSkillRegistry;
 use crate::task::SkillTask;
 use crate::error::{SkillError, Result};
@@ -14,6 +16,9 @@ use crate::error::{SkillError, Result};
 pub struct RouteDecision {
     /// Selected node
     pub node: NodeId,
+    /// Skill the node will run. Differs from the task's skill when the
+    /// route was found by capability rather than exact id.
+    pub skill: SkillId,
     /// Node's trust score for this skill
     pub trust_score: TrustScore,
     /// Node's skill rating (normalized)
@@ -24,6 +29,10 @@ pub struct RouteDecision {
     pub alternatives: Vec<(NodeId, f32)>,
 }
 
+/// A scored candidate: node, the skill it would run, combined score,
+/// trust and skill rating
+type Ranked = (NodeId, SkillId, f32, TrustScore, f32);
+
 /// Routes tasks to the best node based on skill + reputation
 pub struct SkillRouter {
     my_id: NodeId,
@@ -54,25 +63,67 @@ impl SkillRouter {
 
     /// Find the best node to execute a task
     pub async fn route(&self, task: &SkillTask) -> Result<RouteDecision> {
+        let ranked = self.rank(task).await?;
+        Ok(Self::decide(task, &ranked))
+    }
+
+    /// Decision for the best of `ranked`, which must not be empty
+    fn decide(task: &SkillTask, ranked: &[Ranked]) -> RouteDecision {
+        let best = &ranked[0];
+        let alternatives: Vec<_> = ranked.iter().skip(1).map(|(n, _, s, _, _)| (*n, *s)).collect();
+
+        info!(
+            "Routed task {} to node {} as {} (trust: {:.2}, skill: {:.2}, combined: {:.2})",
+            task.id, best.0, best.1, best.3.value(), best.4, best.2
+        );
+
+        RouteDecision {
+            node: best.0,
+            skill: best.1.clone(),
+            trust_score: best.3,
+            skill_score: best.4,
+            route_score: best.2,
+            alternatives,
+        }
+    }
+
+    /// Nodes able to run the task, best first, one entry per node. When no
+    /// node has the exact skill, skills declaring the task's skill id as a
+    /// capability are used instead, so "translate" can reach
+    /// "translate.spanish".
+    async fn rank(&self, task: &SkillTask) -> Result<Vec<Ranked>> {
         let skill = &task.skill;
         let min_trust = task.min_trust;
 
         // Get all nodes that can execute this skill
-        let candidates = self.skill_registry.read().await.nodes_with_skill(skill);
+        let candidates: Vec<(NodeId, SkillId)> = {
+            let registry = self.skill_registry.read().await;
+            let exact: Vec<_> = registry
+                .nodes_with_skill(skill)
+                .into_iter()
+                .map(|node| (node, skill.clone()))
+                .collect();
+            if exact.is_empty() {
+                let wanted = SkillCapability::providing(skill.as_str());
+                let matched = registry.skills_with_capability(&wanted);
+                debug!("No node has skill {}, matching by capability: {:?}", skill, matched);
+                matched
+                    .into_iter()
+                    .flat_map(|m| registry.nodes_with_skill(&m).into_iter().map(move |node| (node, m.clone())))
+                    .collect()
+            } else {
+                exact
+            }
+        };
 
         if candidates.is_empty() {
             return Err(SkillError::NoCapableNode(skill.to_string()));
         }
 
         let trust_graph = self.trust_graph.read().await;
-        let mut scored: Vec<(NodeId, f32, TrustScore, f32)> = Vec::new();
-
-        for node in candidates {
-            // Don't route to self (unless no other option)
-            if node == self.my_id && scored.len() > 0 {
-                continue;
-            }
+        let mut scored: Vec<Ranked> = Vec::new();
 
+        for (node, node_skill) in candidates {
             let trust = trust_graph.get_trust(&node);
 
             // Filter by minimum trust
@@ -85,7 +136,7 @@ impl SkillRouter {
             }
 
             let skill_rating = trust_graph
-                .get_skill_rating(&node, skill)
+                .get_skill_rating(&node, &node_skill)
                 .map(|sr| sr.normalized_score())
                 .unwrap_or(0.0);
 
@@ -93,7 +144,12 @@ impl SkillRouter {
             let combined = self.trust_weight * trust.value()
                 + (1.0 - self.trust_weight) * (skill_rating + 1.0) / 2.0;
 
-            scored.push((node, combined, trust, skill_rating));
+            scored.push((node, node_skill, combined, trust, skill_rating));
+        }
+
+        // Don't route to self unless no other node meets min_trust
+        if scored.iter().any(|(node, ..)| *node != self.my_id) {
+            scored.retain(|(node, ..)| *node != self.my_id);
         }
 
         if scored.is_empty() {
@@ -103,24 +159,19 @@ impl SkillRouter {
             )));
         }
 
-        // Sort by combined score (descending)
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        let best = &scored[0];
-        let alternatives: Vec<_> = scored.iter().skip(1).map(|(n, s, _, _)| (*n, *s)).collect();
+        // Sort by combined score (descending), breaking ties by node id and
+        // then skill id so equal candidates always come out in the same
+        // order, and keep each node's best skill
+        scored.sort_by(|a, b| {
+            b.2.partial_cmp(&a.2)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+                .then_with(|| a.1.as_str().cmp(b.1.as_str()))
+        });
+        let mut seen = HashSet::new();
+        scored.retain(|(node, ..)| seen.insert(*node));
 
-        info!(
-            "Routed task {} to node {} (trust: {:.2}, skill: {:.2}, combined: {:.2})",
-            task.id, best.0, best.2.value(), best.3, best.1
-        );
-
-        Ok(RouteDecision {
-            node: best.0,
-            trust_score: best.2,
-            skill_score: best.3,
-            route_score: best.1,
-            alternatives,
-        })
+        Ok(scored)
     }
 
     /// Route with fallback: try alternatives if primary fails
@@ -155,23 +206,19 @@ impl SkillRouter {
 
     /// Find multiple nodes for parallel/redundant execution
     pub async fn route_multi(&self, task: &SkillTask, count: usize) -> Result<Vec<RouteDecision>> {
-        let decision = self.route(task).await?;
-
-        let mut results = vec![decision.clone()];
+        let ranked = self.rank(task).await?;
 
-        for (alt_node, alt_score) in decision.alternatives.into_iter().take(count - 1) {
-            let trust_graph = self.trust_graph.read().await;
-            let trust = trust_graph.get_trust(&alt_node);
-            let skill_score = trust_graph
-                .get_skill_rating(&alt_node, &task.skill)
-                .map(|sr| sr.normalized_score())
-                .unwrap_or(0.0);
+        let mut results = vec![Self::decide(task, &ranked)];
 
+        for (node, skill, route_score, trust_score, skill_score) in
+            ranked.into_iter().skip(1).take(count.saturating_sub(1))
+        {
             results.push(RouteDecision {
-                node: alt_node,
-                trust_score: trust,
+                node,
+                skill,
+                trust_score,
                 skill_score,
-                route_score: alt_score,
+                route_score,
                 alternatives: Vec::new(),
             });
         }
@@ -179,3 +226,121 @@ impl SkillRouter {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::{Skill, SkillInput, SkillMetadata, SkillOutput};
+    use crate::registry::LocalSkillRegistry;
+    use async_trait::async_trait;
+
+    struct TranslateSkill {
+        metadata: SkillMetadata,
+    }
+
+    impl TranslateSkill {
+        fn shared(language: &str) -> Arc<dyn Skill> {
+            let id = format!("translate.{}", language);
+            Arc::new(Self {
+                metadata: SkillMetadata::new(&id, &id, "Translate text"),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Skill for TranslateSkill {
+        fn metadata(&self) -> &SkillMetadata {
+            &self.metadata
+        }
+
+        fn capabilities(&self) -> SkillCapability {
+            SkillCapability::providing("translate")
+        }
+
+        async fn execute(&self, input: SkillInput) -> Result<SkillOutput> {
+            Ok(SkillOutput::new().with_text(&input.get_text().unwrap_or_default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generic_request_routes_by_capability() {
+        let me = NodeId::random();
+        let mut local = LocalSkillRegistry::new();
+        local.register(TranslateSkill::shared("spanish"));
+
+        let found = local.find_by_capability(&SkillCapability::providing("translate"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].metadata().id, SkillId::new("translate.spanish"));
+        assert!(local.find_by_capability(&SkillCapability::providing("summarize")).is_empty());
+
+        let mut network = NetworkSkillRegistry::new(me);
+        network.register_local_skills(&local);
+        let router = SkillRouter::new(
+            me,
+            Arc::new(RwLock::new(TrustGraph::new(me))),
+            Arc::new(RwLock::new(network)),
+        );
+
+        let task = SkillTask::new(SkillId::new("translate"), SkillInput::new().with_text("hola"), me);
+        let decision = router.route(&task).await.unwrap();
+        assert_eq!(decision.node, me);
+        assert_eq!(decision.skill, SkillId::new("translate.spanish"));
+
+        // An exact id still wins over capability matching
+        let exact = SkillTask::new(SkillId::new("translate.spanish"), SkillInput::new(), me);
+        assert_eq!(router.route(&exact).await.unwrap().skill, exact.skill);
+
+        let unknown = SkillTask::new(SkillId::new("summarize"), SkillInput::new(), me);
+        assert!(matches!(router.route(&unknown).await, Err(SkillError::NoCapableNode(_))));
+    }
+
+    #[tokio::test]
+    async fn test_equal_scores_route_deterministically() {
+        let me = NodeId::random();
+        let nodes: Vec<NodeId> = (1..=4u8).rev().map(|b| NodeId::new([b; 32])).collect();
+        let skill = SkillId::new("summarize");
+
+        for _ in 0..10 {
+            let registry = NetworkSkillRegistry::new(me);
+            for node in &nodes {
+                registry.register_node_skill(*node, skill.clone());
+            }
+            // Local node has the skill too but must only be used as a last resort
+            registry.register_node_skill(me, skill.clone());
+            let router = SkillRouter::new(
+                me,
+                Arc::new(RwLock::new(TrustGraph::new(me))),
+                Arc::new(RwLock::new(registry)),
+            );
+
+            let task = SkillTask::new(skill.clone(), SkillInput::new(), me);
+            let decision = router.route(&task).await.unwrap();
+            assert_eq!(decision.node, NodeId::new([1; 32]));
+            let alternatives: Vec<NodeId> = decision.alternatives.iter().map(|(n, _)| *n).collect();
+            assert_eq!(alternatives, vec![NodeId::new([2; 32]), NodeId::new([3; 32]), NodeId::new([4; 32])]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_self_when_others_lack_trust() {
+        let me = NodeId::random();
+        let other = NodeId::random();
+        let skill = SkillId::new("summarize");
+
+        let registry = NetworkSkillRegistry::new(me);
+        registry.register_node_skill(other, skill.clone());
+        registry.register_node_skill(me, skill.clone());
+        let mut graph = TrustGraph::new(me);
+        graph.add_pre_trusted(me);
+        let router = SkillRouter::new(me, Arc::new(RwLock::new(graph)), Arc::new(RwLock::new(registry)));
+
+        // `other` has neutral trust, so only this node is trusted enough
+        let task = SkillTask::new(skill.clone(), SkillInput::new(), me).with_min_trust(0.8);
+        let decision = router.route(&task).await.unwrap();
+        assert_eq!(decision.node, me);
+        assert!(decision.alternatives.is_empty());
+
+        let task = SkillTask::new(skill, SkillInput::new(), me);
+        assert_eq!(router.route(&task).await.unwrap().node, other);
+    }
+}
@@ -160,6 +160,11 @@ impl SkillTask {
         self.update_timestamp();
     }
 
+    pub fn time_out(&mut self) {
+        self.status = TaskStatus::TimedOut;
+        self.update_timestamp();
+    }
+
     fn update_timestamp(&mut self) {
         self.updated_at = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -0,0 +1,166 @@
+//! Content-addressed storage for large payloads
+//!
+//! Events too large to carry inline hold `Payload::Reference { hash, size }`
+//! instead; the bytes live in a `BlobStore` under their BLAKE3 hash
+//! (`ContentHash::of_bytes`). Since the key is derived from the content, the
+//! same blob stored twice is kept once and a blob fetched from a peer can be
+//! checked against the hash it was requested by.
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+use crate::error::StoreError;
+use crate::sync::ContentHash;
+
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, bytes: &[u8]) -> Result<ContentHash, StoreError>;
+    async fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, StoreError>;
+
+    /// Returns whether the blob was present
+    async fn remove(&self, _hash: &ContentHash) -> Result<bool, StoreError> {
+        Err(StoreError::Unsupported("removing blobs".into()))
+    }
+
+    async fn contains(&self, hash: &ContentHash) -> Result<bool, StoreError> {
+        Ok(self.get(hash).await?.is_some())
+    }
+
+    /// Fetch the bytes behind a `Payload::Reference { hash, size }`
+    async fn resolve(&self, hash: &[u8; 32], size: u64) -> Result<Vec<u8>, StoreError> {
+        let hash = ContentHash(*hash);
+        let bytes = self
+            .get(&hash)
+            .await?
+            .ok_or_else(|| StoreError::NotFound(format!("blob {}", hash.as_hex())))?;
+        if bytes.len() as u64 != size {
+            return Err(StoreError::Integrity(format!(
+                "blob {} is {} bytes, reference says {}",
+                hash.as_hex(),
+                bytes.len(),
+                size
+            )));
+        }
+        Ok(bytes)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryBlobStore {
+    blobs: RwLock<HashMap<ContentHash, Vec<u8>>>,
+}
+
+impl MemoryBlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blobs.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blobs.read().is_empty()
+    }
+}
+
+#[async_trait]
+impl BlobStore for MemoryBlobStore {
+    async fn put(&self, bytes: &[u8]) -> Result<ContentHash, StoreError> {
+        let hash = ContentHash::of_bytes(bytes);
+        self.blobs
+            .write()
+            .entry(hash.clone())
+            .or_insert_with(|| bytes.to_vec());
+        Ok(hash)
+    }
+
+    async fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self.blobs.read().get(hash).cloned())
+    }
+
+    async fn remove(&self, hash: &ContentHash) -> Result<bool, StoreError> {
+        Ok(self.blobs.write().remove(hash).is_some())
+    }
+
+    async fn contains(&self, hash: &ContentHash) -> Result<bool, StoreError> {
+        Ok(self.blobs.read().contains_key(hash))
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+pub mod rocks {
+    use super::*;
+    use rocksdb::DB;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    pub struct RocksBlobStore {
+        db: Arc<DB>,
+    }
+
+    impl RocksBlobStore {
+        pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+            let mut opts = rocksdb::Options::default();
+            opts.create_if_missing(true);
+
+            let db = DB::open(&opts, path).map_err(|e| StoreError::Backend(e.to_string()))?;
+            Ok(Self { db: Arc::new(db) })
+        }
+    }
+
+    #[async_trait]
+    impl BlobStore for RocksBlobStore {
+        async fn put(&self, bytes: &[u8]) -> Result<ContentHash, StoreError> {
+            let hash = ContentHash::of_bytes(bytes);
+            self.db
+                .put(hash.0, bytes)
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            Ok(hash)
+        }
+
+        async fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, StoreError> {
+            self.db
+                .get(hash.0)
+                .map_err(|e| StoreError::Backend(e.to_string()))
+        }
+
+        async fn remove(&self, hash: &ContentHash) -> Result<bool, StoreError> {
+            let present = self.contains(hash).await?;
+            self.db
+                .delete(hash.0)
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            Ok(present)
+        }
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+pub use rocks::RocksBlobStore;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_blob_store_resolves_references() {
+        let store = MemoryBlobStore::new();
+        let blob = vec![7u8; 4096];
+
+        let hash = store.put(&blob).await.unwrap();
+        assert_eq!(hash, ContentHash::of_bytes(&blob));
+        assert_eq!(store.put(&blob).await.unwrap(), hash);
+        assert_eq!(store.len(), 1);
+
+        assert_eq!(store.resolve(&hash.0, blob.len() as u64).await.unwrap(), blob);
+        assert!(matches!(
+            store.resolve(&hash.0, 10).await,
+            Err(StoreError::Integrity(_))
+        ));
+        assert!(matches!(
+            store.resolve(&[0u8; 32], 10).await,
+            Err(StoreError::NotFound(_))
+        ));
+    }
+}
@@ -26,6 +26,10 @@ pub enum StoreError {
     #[error("Integrity error: {0}")]
     Integrity(String),
 
+    /// Received data does not hash to the hash it was declared under
+    #[error("Hash mismatch: expected {expected}, computed {computed}")]
+    HashMismatch { expected: String, computed: String },
+
     /// File system I/O operation failed
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -34,6 +38,14 @@ pub enum StoreError {
     #[error("Lock poisoned")]
     LockPoisoned,
 
+    /// A peer's timestamp is further ahead of our clock than `MAX_CLOCK_DRIFT_MS`
+    #[error("Clock drift: remote time {remote} ms is too far ahead of local {local} ms")]
+    ClockDrift { remote: u64, local: u64 },
+
+    /// The backend does not implement this operation
+    #[error("Unsupported by this store: {0}")]
+    Unsupported(String),
+
     /// Attempted operation violates privacy rules
     #[error("Privacy violation: {0}")]
     PrivacyViolation(String),
@@ -1,89 +1,395 @@
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::warn;
 
+use crate::blob::BlobStore;
 use crate::error::StoreError;
+use crate::sync::ContentHash;
 use crate::types::{Event, EventId, Timestamp};
 
 #[async_trait]
 pub trait EventStore: Send + Sync {
     async fn append(&self, event: &Event) -> Result<EventId, StoreError>;
     async fn get(&self, id: &EventId) -> Result<Option<Event>, StoreError>;
+    /// Events whose wall-clock time is within `from..=to`, in logical order
     async fn range(&self, from: Timestamp, to: Timestamp) -> Result<Vec<Event>, StoreError>;
     async fn by_kind(&self, kind: &str) -> Result<Vec<Event>, StoreError>;
     async fn by_source(&self, source: &str) -> Result<Vec<Event>, StoreError>;
+
+    async fn remove(&self, _id: &EventId) -> Result<Option<Event>, StoreError> {
+        Err(StoreError::Unsupported("removing events".into()))
+    }
+
+    async fn dedup_stats(&self) -> Result<DedupStats, StoreError> {
+        Err(StoreError::Unsupported("payload deduplication stats".into()))
+    }
+
+    /// Every stored event, then each new one as it is appended. The stream
+    /// ends only if the backend fails.
+    fn tail(self: Arc<Self>) -> EventStream;
+}
+
+pub type EventStream = BoxStream<'static, Event>;
+
+/// How much payload sharing saves in an event store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupStats {
+    /// Distinct payloads held; equals `total_events` without deduplication
+    pub unique_payloads: usize,
+    pub total_events: usize,
+    /// Payload bytes not stored because another event has the same payload
+    pub bytes_saved: u64,
+}
+
+struct PayloadRef {
+    refs: usize,
+    size: u64,
+    /// Put into the blob store by us, so ours to delete when unreferenced
+    owned: bool,
+}
+
+/// Payloads stored once per distinct content, refcounted by event
+struct PayloadTable {
+    blobs: Arc<dyn BlobStore>,
+    /// Held across the blob store calls, so a refcount and its blob
+    /// change together
+    payloads: tokio::sync::Mutex<HashMap<ContentHash, PayloadRef>>,
+    by_event: RwLock<HashMap<EventId, ContentHash>>,
+}
+
+impl PayloadTable {
+    async fn acquire(&self, payload: &serde_json::Value) -> Result<ContentHash, StoreError> {
+        let bytes =
+            serde_json::to_vec(payload).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        let hash = ContentHash::of_bytes(&bytes);
+        let mut payloads = self.payloads.lock().await;
+        if let Some(entry) = payloads.get_mut(&hash) {
+            entry.refs += 1;
+            return Ok(hash);
+        }
+
+        let owned = !self.blobs.contains(&hash).await?;
+        self.blobs.put(&bytes).await?;
+        payloads.insert(
+            hash.clone(),
+            PayloadRef {
+                refs: 1,
+                size: bytes.len() as u64,
+                owned,
+            },
+        );
+        Ok(hash)
+    }
+
+    async fn release(&self, hash: &ContentHash) -> Result<(), StoreError> {
+        let mut payloads = self.payloads.lock().await;
+        let Some(entry) = payloads.get_mut(hash) else {
+            return Ok(());
+        };
+        if entry.refs > 1 {
+            entry.refs -= 1;
+            return Ok(());
+        }
+        if payloads.remove(hash).is_some_and(|entry| entry.owned) {
+            self.blobs.remove(hash).await?;
+        }
+        Ok(())
+    }
+
+    async fn load(&self, hash: &ContentHash) -> Result<serde_json::Value, StoreError> {
+        let bytes = self
+            .blobs
+            .get(hash)
+            .await?
+            .ok_or_else(|| StoreError::NotFound(format!("payload {}", hash.as_hex())))?;
+        serde_json::from_slice(&bytes).map_err(|e| StoreError::Deserialization(e.to_string()))
+    }
+
+    async fn stats(&self, total_events: usize) -> DedupStats {
+        let payloads = self.payloads.lock().await;
+        DedupStats {
+            unique_payloads: payloads.len(),
+            total_events,
+            bytes_saved: payloads
+                .values()
+                .map(|p| (p.refs as u64).saturating_sub(1) * p.size)
+                .sum(),
+        }
+    }
+}
+
+/// Append order of the events in a `MemoryEventStore`
+#[derive(Default)]
+struct Timeline {
+    next_seq: u64,
+    ids: BTreeMap<u64, EventId>,
+    seqs: HashMap<EventId, u64>,
+}
+
+impl Timeline {
+    fn push(&mut self, id: EventId) {
+        self.remove(&id);
+        self.ids.insert(self.next_seq, id);
+        self.seqs.insert(id, self.next_seq);
+        self.next_seq += 1;
+    }
+
+    fn remove(&mut self, id: &EventId) {
+        if let Some(seq) = self.seqs.remove(id) {
+            self.ids.remove(&seq);
+        }
+    }
+
+    /// First entry at or after `seq`
+    fn next_from(&self, seq: u64) -> Option<(u64, EventId)> {
+        self.ids.range(seq..).next().map(|(seq, id)| (*seq, *id))
+    }
 }
 
 #[derive(Default)]
 pub struct MemoryEventStore {
     events: RwLock<HashMap<EventId, Event>>,
-    timeline: RwLock<Vec<EventId>>,
+    /// Sequence numbers are never reused, so tail cursors survive removals
+    timeline: RwLock<Timeline>,
+    appended: Notify,
+    dedup: Option<PayloadTable>,
 }
 
 impl MemoryEventStore {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Store each distinct payload once in `blobs`, keyed by its BLAKE3
+    /// hash, and drop it when the last event using it is removed. Blobs
+    /// that were already in `blobs` are never deleted.
+    pub fn with_dedup(mut self, blobs: Arc<dyn BlobStore>) -> Self {
+        self.dedup = Some(PayloadTable {
+            blobs,
+            payloads: tokio::sync::Mutex::new(HashMap::new()),
+            by_event: RwLock::new(HashMap::new()),
+        });
+        self
+    }
+
+    /// Put back payloads held in the payload table
+    async fn hydrate(&self, mut events: Vec<Event>) -> Result<Vec<Event>, StoreError> {
+        let Some(dedup) = &self.dedup else {
+            return Ok(events);
+        };
+        for event in &mut events {
+            let hash = dedup.by_event.read().get(&event.id).cloned();
+            if let Some(hash) = hash {
+                event.payload = dedup.load(&hash).await?;
+            }
+        }
+        Ok(events)
+    }
+
+    fn select(&self, filter: impl Fn(&Event) -> bool) -> Vec<Event> {
+        self.events.read().values().filter(|e| filter(e)).cloned().collect()
+    }
 }
 
 #[async_trait]
 impl EventStore for MemoryEventStore {
     async fn append(&self, event: &Event) -> Result<EventId, StoreError> {
         let id = event.id;
-        self.events.write().insert(id, event.clone());
+        let stored = match &self.dedup {
+            Some(dedup) => {
+                let hash = dedup.acquire(&event.payload).await?;
+                let previous = dedup.by_event.write().insert(id, hash);
+                if let Some(previous) = previous {
+                    dedup.release(&previous).await?;
+                }
+                Event {
+                    payload: serde_json::Value::Null,
+                    ..event.clone()
+                }
+            }
+            None => event.clone(),
+        };
+        self.events.write().insert(id, stored);
         self.timeline.write().push(id);
+        self.appended.notify_waiters();
         Ok(id)
     }
 
     async fn get(&self, id: &EventId) -> Result<Option<Event>, StoreError> {
-        Ok(self.events.read().get(id).cloned())
+        let event = self.events.read().get(id).cloned();
+        match event {
+            Some(event) => Ok(self.hydrate(vec![event]).await?.pop()),
+            None => Ok(None),
+        }
     }
 
     async fn range(&self, from: Timestamp, to: Timestamp) -> Result<Vec<Event>, StoreError> {
-        let events = self.events.read();
-        let result: Vec<Event> = events
-            .values()
-            .filter(|e| e.timestamp >= from && e.timestamp <= to)
-            .cloned()
-            .collect();
-        Ok(result)
+        let mut result =
+            self.select(|e| e.timestamp.wall >= from.wall && e.timestamp.wall <= to.wall);
+        result.sort_by(|a, b| a.timestamp.logical_cmp(&b.timestamp));
+        self.hydrate(result).await
     }
 
     async fn by_kind(&self, kind: &str) -> Result<Vec<Event>, StoreError> {
-        let events = self.events.read();
-        let result: Vec<Event> = events
-            .values()
-            .filter(|e| e.kind == kind)
-            .cloned()
-            .collect();
-        Ok(result)
+        self.hydrate(self.select(|e| e.kind == kind)).await
     }
 
     async fn by_source(&self, source: &str) -> Result<Vec<Event>, StoreError> {
-        let events = self.events.read();
-        let result: Vec<Event> = events
-            .values()
-            .filter(|e| e.source == source)
-            .cloned()
-            .collect();
-        Ok(result)
+        self.hydrate(self.select(|e| e.source == source)).await
+    }
+
+    async fn remove(&self, id: &EventId) -> Result<Option<Event>, StoreError> {
+        let Some(event) = self.events.write().remove(id) else {
+            return Ok(None);
+        };
+        self.timeline.write().remove(id);
+
+        let Some(dedup) = &self.dedup else {
+            return Ok(Some(event));
+        };
+        let mut event = self.hydrate(vec![event]).await?.remove(0);
+        let hash = dedup.by_event.write().remove(id);
+        if let Some(hash) = hash {
+            if event.payload.is_null() {
+                event.payload = dedup.load(&hash).await?;
+            }
+            dedup.release(&hash).await?;
+        }
+        Ok(Some(event))
+    }
+
+    async fn dedup_stats(&self) -> Result<DedupStats, StoreError> {
+        let total_events = self.events.read().len();
+        Ok(match &self.dedup {
+            Some(dedup) => dedup.stats(total_events).await,
+            None => DedupStats {
+                unique_payloads: total_events,
+                total_events,
+                bytes_saved: 0,
+            },
+        })
+    }
+
+    fn tail(self: Arc<Self>) -> EventStream {
+        stream::unfold((self, 0u64), |(store, mut cursor)| async move {
+            loop {
+                let id = {
+                    // Registered before looking so an append in between still wakes us
+                    let appended = store.appended.notified();
+                    tokio::pin!(appended);
+                    appended.as_mut().enable();
+
+                    let Some((seq, id)) = store.timeline.read().next_from(cursor) else {
+                        appended.await;
+                        continue;
+                    };
+                    cursor = seq + 1;
+                    id
+                };
+                match store.get(&id).await {
+                    Ok(Some(event)) => return Some((event, (store, cursor))),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!("event tail stopped: {}", e);
+                        return None;
+                    }
+                }
+            }
+        })
+        .boxed()
     }
 }
 
 #[cfg(feature = "rocksdb")]
 pub mod rocks {
     use super::*;
+    use crate::schema::{self, CF_META};
+    use crate::types::{PrivacyLevel, VectorClock};
     use rocksdb::{ColumnFamilyDescriptor, Options, DB};
     use std::path::Path;
 
+    /// Version 1 split timestamps into wall and logical parts and stored
+    /// payloads inline as JSON text. Version 2 stores each distinct payload
+    /// once in `payloads`, refcounted in `payload_refs`. Records of both
+    /// versions carry the event's vector clock.
+    const FORMAT_VERSION: u32 = 2;
+
     const CF_EVENTS: &str = "events";
     const CF_BY_TIME: &str = "by_time";
     const CF_BY_KIND: &str = "by_kind";
     const CF_BY_SOURCE: &str = "by_source";
+    /// Big-endian append sequence number to event id, the order `tail` follows
+    const CF_BY_SEQ: &str = "by_seq";
+    /// Event id to its append sequence number
+    const CF_SEQ_OF: &str = "seq_of";
+    /// Payload JSON by its BLAKE3 hash
+    const CF_PAYLOADS: &str = "payloads";
+    /// Payload hash to its `PayloadCount`
+    const CF_PAYLOAD_REFS: &str = "payload_refs";
 
     pub struct RocksEventStore {
         db: Arc<DB>,
+        appended: Notify,
+        /// Next append sequence number. Held for every write, so sequence
+        /// order is the order appends become visible and payload refcounts
+        /// are read and updated together.
+        next_seq: parking_lot::Mutex<u64>,
+    }
+
+    /// An `Event` as stored in `CF_EVENTS`, with its payload in `CF_PAYLOADS`
+    #[derive(Serialize, Deserialize)]
+    struct EventRecord {
+        id: EventId,
+        kind: String,
+        source: String,
+        timestamp: Timestamp,
+        payload: ContentHash,
+        privacy: PrivacyLevel,
+        clock: Option<VectorClock>,
+    }
+
+    /// Format version 1 record, with the payload JSON inline
+    #[derive(Deserialize)]
+    struct EventRecordV1 {
+        id: EventId,
+        kind: String,
+        source: String,
+        timestamp: Timestamp,
+        payload: String,
+        privacy: PrivacyLevel,
+        clock: Option<VectorClock>,
+    }
+
+    #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+    struct PayloadCount {
+        refs: u64,
+        size: u64,
+    }
+
+    /// Reference changes to apply to payloads in one write batch
+    #[derive(Default)]
+    struct PayloadChanges {
+        deltas: HashMap<ContentHash, i64>,
+        /// JSON of payloads gaining a reference, in case they are new
+        added: HashMap<ContentHash, Vec<u8>>,
+    }
+
+    impl PayloadChanges {
+        fn acquire(&mut self, json: Vec<u8>) -> ContentHash {
+            let hash = ContentHash::of_bytes(&json);
+            *self.deltas.entry(hash.clone()).or_default() += 1;
+            self.added.insert(hash.clone(), json);
+            hash
+        }
+
+        fn release(&mut self, hash: &ContentHash) {
+            *self.deltas.entry(hash.clone()).or_default() -= 1;
+        }
     }
 
     impl RocksEventStore {
@@ -97,21 +403,277 @@ pub mod rocks {
                 ColumnFamilyDescriptor::new(CF_BY_TIME, Options::default()),
                 ColumnFamilyDescriptor::new(CF_BY_KIND, Options::default()),
                 ColumnFamilyDescriptor::new(CF_BY_SOURCE, Options::default()),
+                ColumnFamilyDescriptor::new(CF_BY_SEQ, Options::default()),
+                ColumnFamilyDescriptor::new(CF_SEQ_OF, Options::default()),
+                ColumnFamilyDescriptor::new(CF_PAYLOADS, Options::default()),
+                ColumnFamilyDescriptor::new(CF_PAYLOAD_REFS, Options::default()),
+                ColumnFamilyDescriptor::new(CF_META, Options::default()),
             ];
 
             let db = DB::open_cf_descriptors(&opts, path, cfs)
                 .map_err(|e| StoreError::Backend(e.to_string()))?;
 
-            Ok(Self { db: Arc::new(db) })
+            let store = Self {
+                db: Arc::new(db),
+                appended: Notify::new(),
+                next_seq: parking_lot::Mutex::new(0),
+            };
+            store.check_format()?;
+            store.backfill_sequence()?;
+            *store.next_seq.lock() = store.last_seq()?.map_or(0, |seq| seq + 1);
+            Ok(store)
         }
 
-        fn serialize_event(event: &Event) -> Result<Vec<u8>, StoreError> {
-            bincode::serialize(event).map_err(|e| StoreError::Serialization(e.to_string()))
+        fn cf(&self, name: &str) -> Result<&rocksdb::ColumnFamily, StoreError> {
+            self.db
+                .cf_handle(name)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))
         }
 
-        fn deserialize_event(bytes: &[u8]) -> Result<Event, StoreError> {
+        /// Databases written before the append sequence existed have events
+        /// but an empty `by_seq`; number them in time order once on open
+        fn backfill_sequence(&self) -> Result<(), StoreError> {
+            let cf_time = self.cf(CF_BY_TIME)?;
+            let cf_seq = self.cf(CF_BY_SEQ)?;
+            let cf_seq_of = self.cf(CF_SEQ_OF)?;
+            if self
+                .db
+                .iterator_cf(&cf_seq, rocksdb::IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                return Ok(());
+            }
+
+            let mut batch = rocksdb::WriteBatch::default();
+            let by_time = self.db.iterator_cf(&cf_time, rocksdb::IteratorMode::Start);
+            for (seq, item) in (0u64..).zip(by_time) {
+                let (_, id_bytes) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                batch.put_cf(&cf_seq, seq.to_be_bytes(), &id_bytes);
+                batch.put_cf(&cf_seq_of, &id_bytes, seq.to_be_bytes());
+            }
+            if batch.is_empty() {
+                return Ok(());
+            }
+            self.db
+                .write(batch)
+                .map_err(|e| StoreError::Backend(e.to_string()))
+        }
+
+        fn last_seq(&self) -> Result<Option<u64>, StoreError> {
+            let cf_seq = self.cf(CF_BY_SEQ)?;
+            let last = self.db.iterator_cf(&cf_seq, rocksdb::IteratorMode::End).next();
+            match last {
+                Some(item) => {
+                    let (key, _) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                    Ok(Some(Self::decode_seq(&key)?))
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn decode_seq(bytes: &[u8]) -> Result<u64, StoreError> {
+            let bytes: [u8; 8] = bytes
+                .try_into()
+                .map_err(|_| StoreError::Integrity("malformed sequence number".into()))?;
+            Ok(u64::from_be_bytes(bytes))
+        }
+
+        /// Sequence number `id` was appended under, if it is stored
+        fn seq_of(&self, id: &EventId) -> Result<Option<u64>, StoreError> {
+            let cf_seq_of = self.cf(CF_SEQ_OF)?;
+            self.db
+                .get_cf(&cf_seq_of, id.0.as_bytes())
+                .map_err(|e| StoreError::Backend(e.to_string()))?
+                .map(|bytes| Self::decode_seq(&bytes))
+                .transpose()
+        }
+
+        /// Unversioned stores wrote payloads as bincode `serde_json::Value`s,
+        /// which cannot be decoded, so there is nothing to migrate them from
+        fn check_format(&self) -> Result<(), StoreError> {
+            match schema::stored_version(&self.db)? {
+                Some(1) => return self.migrate_inline_payloads(),
+                Some(version) => return schema::check_not_newer(version, FORMAT_VERSION),
+                None => {}
+            }
+
+            let cf_events = self.cf(CF_EVENTS)?;
+            if self
+                .db
+                .iterator_cf(&cf_events, rocksdb::IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                return Err(StoreError::Integrity(
+                    "event store predates format versioning and its records cannot be decoded"
+                        .into(),
+                ));
+            }
+            schema::set_version(&self.db, FORMAT_VERSION)
+        }
+
+        /// Move version 1 inline payloads into the payload table
+        fn migrate_inline_payloads(&self) -> Result<(), StoreError> {
+            let cf_events = self.cf(CF_EVENTS)?;
+            let mut batch = rocksdb::WriteBatch::default();
+            let mut payloads = PayloadChanges::default();
+            for item in self.db.iterator_cf(&cf_events, rocksdb::IteratorMode::Start) {
+                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                let old: EventRecordV1 = bincode::deserialize(&value)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
+                let record = EventRecord {
+                    id: old.id,
+                    kind: old.kind,
+                    source: old.source,
+                    timestamp: old.timestamp,
+                    payload: payloads.acquire(old.payload.into_bytes()),
+                    privacy: old.privacy,
+                    clock: old.clock,
+                };
+                batch.put_cf(&cf_events, key, Self::encode_record(&record)?);
+            }
+            self.apply_payloads(&mut batch, payloads)?;
+            self.db
+                .write(batch)
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            schema::set_version(&self.db, FORMAT_VERSION)
+        }
+
+        fn encode_record(record: &EventRecord) -> Result<Vec<u8>, StoreError> {
+            bincode::serialize(record).map_err(|e| StoreError::Serialization(e.to_string()))
+        }
+
+        fn decode_record(bytes: &[u8]) -> Result<EventRecord, StoreError> {
             bincode::deserialize(bytes).map_err(|e| StoreError::Deserialization(e.to_string()))
         }
+
+        fn record(&self, id: &EventId) -> Result<Option<EventRecord>, StoreError> {
+            let cf_events = self.cf(CF_EVENTS)?;
+            self.db
+                .get_cf(&cf_events, id.0.as_bytes())
+                .map_err(|e| StoreError::Backend(e.to_string()))?
+                .map(|bytes| Self::decode_record(&bytes))
+                .transpose()
+        }
+
+        /// Decode a stored record and load its payload
+        fn deserialize_event(&self, bytes: &[u8]) -> Result<Event, StoreError> {
+            let record = Self::decode_record(bytes)?;
+            let cf_payloads = self.cf(CF_PAYLOADS)?;
+            let json = self
+                .db
+                .get_cf(&cf_payloads, record.payload.0)
+                .map_err(|e| StoreError::Backend(e.to_string()))?
+                .ok_or_else(|| {
+                    StoreError::NotFound(format!("payload {}", record.payload.as_hex()))
+                })?;
+            Ok(Event {
+                id: record.id,
+                kind: record.kind,
+                source: record.source,
+                timestamp: record.timestamp,
+                payload: serde_json::from_slice(&json)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?,
+                privacy: record.privacy,
+                clock: record.clock,
+            })
+        }
+
+        fn payload_count(&self, hash: &ContentHash) -> Result<PayloadCount, StoreError> {
+            let cf_refs = self.cf(CF_PAYLOAD_REFS)?;
+            match self
+                .db
+                .get_cf(&cf_refs, hash.0)
+                .map_err(|e| StoreError::Backend(e.to_string()))?
+            {
+                Some(bytes) => bincode::deserialize(&bytes)
+                    .map_err(|e| StoreError::Deserialization(e.to_string())),
+                None => Ok(PayloadCount::default()),
+            }
+        }
+
+        /// Add the refcount changes to `batch`, storing payloads that gain
+        /// their first reference and deleting those that lose their last.
+        /// Callers hold `next_seq` so the counts read here stay current.
+        fn apply_payloads(
+            &self,
+            batch: &mut rocksdb::WriteBatch,
+            mut changes: PayloadChanges,
+        ) -> Result<(), StoreError> {
+            let cf_payloads = self.cf(CF_PAYLOADS)?;
+            let cf_refs = self.cf(CF_PAYLOAD_REFS)?;
+            for (hash, delta) in changes.deltas {
+                if delta == 0 {
+                    continue;
+                }
+                let mut count = self.payload_count(&hash)?;
+                let refs = (count.refs as i64 + delta).max(0) as u64;
+                if refs == 0 {
+                    batch.delete_cf(&cf_payloads, hash.0);
+                    batch.delete_cf(&cf_refs, hash.0);
+                    continue;
+                }
+                if count.refs == 0 {
+                    let json = changes.added.remove(&hash).ok_or_else(|| {
+                        StoreError::Integrity(format!("payload {} has no content", hash.as_hex()))
+                    })?;
+                    count.size = json.len() as u64;
+                    batch.put_cf(&cf_payloads, hash.0, json);
+                }
+                count.refs = refs;
+                let count = bincode::serialize(&count)
+                    .map_err(|e| StoreError::Serialization(e.to_string()))?;
+                batch.put_cf(&cf_refs, hash.0, count);
+            }
+            Ok(())
+        }
+
+        /// Delete `record` and its index entries in `batch`
+        fn batch_remove(
+            &self,
+            batch: &mut rocksdb::WriteBatch,
+            record: &EventRecord,
+            payloads: &mut PayloadChanges,
+        ) -> Result<(), StoreError> {
+            let id = record.id;
+            let mut keys = vec![
+                (CF_EVENTS, id.0.as_bytes().to_vec()),
+                (CF_BY_TIME, format!("{:016x}:{}", record.timestamp.wall, id.0).into_bytes()),
+                (CF_BY_KIND, format!("{}:{}", record.kind, id.0).into_bytes()),
+                (CF_BY_SOURCE, format!("{}:{}", record.source, id.0).into_bytes()),
+                (CF_SEQ_OF, id.0.as_bytes().to_vec()),
+            ];
+            if let Some(seq) = self.seq_of(&id)? {
+                keys.push((CF_BY_SEQ, seq.to_be_bytes().to_vec()));
+            }
+            for (cf_name, key) in keys {
+                batch.delete_cf(&self.cf(cf_name)?, key);
+            }
+            payloads.release(&record.payload);
+            Ok(())
+        }
+
+        /// First event appended at or after sequence number `from`
+        fn next_from(&self, from: u64) -> Result<Option<(u64, Event)>, StoreError> {
+            let (cf_seq, cf_events) = (self.cf(CF_BY_SEQ)?, self.cf(CF_EVENTS)?);
+
+            let start = from.to_be_bytes();
+            let mode = rocksdb::IteratorMode::From(&start, rocksdb::Direction::Forward);
+            for item in self.db.iterator_cf(&cf_seq, mode) {
+                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                if let Some(bytes) = self
+                    .db
+                    .get_cf(&cf_events, &value)
+                    .map_err(|e| StoreError::Backend(e.to_string()))?
+                {
+                    let event = self.deserialize_event(&bytes)?;
+                    return Ok(Some((Self::decode_seq(&key)?, event)));
+                }
+            }
+
+            Ok(None)
+        }
     }
 
     #[async_trait]
@@ -119,44 +681,57 @@ pub mod rocks {
         async fn append(&self, event: &Event) -> Result<EventId, StoreError> {
             let id = event.id;
             let id_bytes = id.0.as_bytes();
-            let event_bytes = Self::serialize_event(event)?;
+            let json = serde_json::to_vec(&event.payload)
+                .map_err(|e| StoreError::Serialization(e.to_string()))?;
 
-            let cf_events = self
-                .db
-                .cf_handle(CF_EVENTS)
-                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
-            let cf_time = self
-                .db
-                .cf_handle(CF_BY_TIME)
-                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
-            let cf_kind = self
-                .db
-                .cf_handle(CF_BY_KIND)
-                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
-            let cf_source = self
-                .db
-                .cf_handle(CF_BY_SOURCE)
-                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+            let cf_events = self.cf(CF_EVENTS)?;
+            let cf_time = self.cf(CF_BY_TIME)?;
+            let cf_kind = self.cf(CF_BY_KIND)?;
+            let cf_source = self.cf(CF_BY_SOURCE)?;
+            let cf_seq = self.cf(CF_BY_SEQ)?;
+            let cf_seq_of = self.cf(CF_SEQ_OF)?;
 
-            self.db
-                .put_cf(&cf_events, id_bytes, &event_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            {
+                let mut next_seq = self.next_seq.lock();
+                let mut batch = rocksdb::WriteBatch::default();
+                let mut payloads = PayloadChanges::default();
 
-            let time_key = format!("{:016x}:{}", event.timestamp.0, id.0);
-            self.db
-                .put_cf(&cf_time, time_key.as_bytes(), id_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+                // Re-appending an id replaces it and moves it to the end of the sequence
+                if let Some(previous) = self.record(&id)? {
+                    self.batch_remove(&mut batch, &previous, &mut payloads)?;
+                }
 
-            let kind_key = format!("{}:{}", event.kind, id.0);
-            self.db
-                .put_cf(&cf_kind, kind_key.as_bytes(), id_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+                let record = EventRecord {
+                    id,
+                    kind: event.kind.clone(),
+                    source: event.source.clone(),
+                    timestamp: event.timestamp,
+                    payload: payloads.acquire(json),
+                    privacy: event.privacy,
+                    clock: event.clock.clone(),
+                };
+                batch.put_cf(&cf_events, id_bytes, Self::encode_record(&record)?);
 
-            let source_key = format!("{}:{}", event.source, id.0);
-            self.db
-                .put_cf(&cf_source, source_key.as_bytes(), id_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+                let time_key = format!("{:016x}:{}", event.timestamp.wall, id.0);
+                batch.put_cf(&cf_time, time_key.as_bytes(), id_bytes);
+
+                let kind_key = format!("{}:{}", event.kind, id.0);
+                batch.put_cf(&cf_kind, kind_key.as_bytes(), id_bytes);
+
+                let source_key = format!("{}:{}", event.source, id.0);
+                batch.put_cf(&cf_source, source_key.as_bytes(), id_bytes);
+
+                batch.put_cf(&cf_seq, next_seq.to_be_bytes(), id_bytes);
+                batch.put_cf(&cf_seq_of, id_bytes, next_seq.to_be_bytes());
 
+                self.apply_payloads(&mut batch, payloads)?;
+                self.db
+                    .write(batch)
+                    .map_err(|e| StoreError::Backend(e.to_string()))?;
+                *next_seq += 1;
+            }
+
+            self.appended.notify_waiters();
             Ok(id)
         }
 
@@ -167,7 +742,7 @@ pub mod rocks {
                 .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
 
             match self.db.get_cf(&cf, id.0.as_bytes()) {
-                Ok(Some(bytes)) => Ok(Some(Self::deserialize_event(&bytes)?)),
+                Ok(Some(bytes)) => Ok(Some(self.deserialize_event(&bytes)?)),
                 Ok(None) => Ok(None),
                 Err(e) => Err(StoreError::Backend(e.to_string())),
             }
@@ -183,8 +758,8 @@ pub mod rocks {
                 .cf_handle(CF_EVENTS)
                 .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
 
-            let start_key = format!("{:016x}:", from.0);
-            let end_key = format!("{:016x}:{}", to.0, "\u{00ff}");
+            let start_key = format!("{:016x}:", from.wall);
+            let end_key = format!("{:016x}:{}", to.wall, "\u{00ff}");
 
             let mut results = Vec::new();
             let iter = self.db.iterator_cf(
@@ -203,10 +778,12 @@ pub mod rocks {
                     .get_cf(&cf_events, &value)
                     .map_err(|e| StoreError::Backend(e.to_string()))?
                 {
-                    results.push(Self::deserialize_event(&bytes)?);
+                    results.push(self.deserialize_event(&bytes)?);
                 }
             }
 
+            // Keys are in wall-clock order, which a clock jump can scramble
+            results.sort_by(|a, b| a.timestamp.logical_cmp(&b.timestamp));
             Ok(results)
         }
 
@@ -237,7 +814,7 @@ pub mod rocks {
                     .get_cf(&cf_events, &value)
                     .map_err(|e| StoreError::Backend(e.to_string()))?
                 {
-                    results.push(Self::deserialize_event(&bytes)?);
+                    results.push(self.deserialize_event(&bytes)?);
                 }
             }
 
@@ -271,14 +848,231 @@ pub mod rocks {
                     .get_cf(&cf_events, &value)
                     .map_err(|e| StoreError::Backend(e.to_string()))?
                 {
-                    results.push(Self::deserialize_event(&bytes)?);
+                    results.push(self.deserialize_event(&bytes)?);
                 }
             }
 
             Ok(results)
         }
+
+        async fn remove(&self, id: &EventId) -> Result<Option<Event>, StoreError> {
+            let cf_events = self.cf(CF_EVENTS)?;
+            let _writing = self.next_seq.lock();
+            let Some(bytes) = self
+                .db
+                .get_cf(&cf_events, id.0.as_bytes())
+                .map_err(|e| StoreError::Backend(e.to_string()))?
+            else {
+                return Ok(None);
+            };
+            let record = Self::decode_record(&bytes)?;
+            let event = self.deserialize_event(&bytes)?;
+
+            let mut batch = rocksdb::WriteBatch::default();
+            let mut payloads = PayloadChanges::default();
+            self.batch_remove(&mut batch, &record, &mut payloads)?;
+            self.apply_payloads(&mut batch, payloads)?;
+            self.db
+                .write(batch)
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+
+            Ok(Some(event))
+        }
+
+        async fn dedup_stats(&self) -> Result<DedupStats, StoreError> {
+            let cf_refs = self.cf(CF_PAYLOAD_REFS)?;
+
+            let mut stats = DedupStats::default();
+            for item in self.db.iterator_cf(&cf_refs, rocksdb::IteratorMode::Start) {
+                let (_, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                let count: PayloadCount = bincode::deserialize(&value)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
+                stats.unique_payloads += 1;
+                stats.total_events += count.refs as usize;
+                stats.bytes_saved += count.refs.saturating_sub(1) * count.size;
+            }
+            Ok(stats)
+        }
+
+        /// Follows the append sequence, so events are seen in the order they
+        /// were stored whatever their timestamps
+        fn tail(self: Arc<Self>) -> EventStream {
+            stream::unfold((self, 0u64), |(store, cursor)| async move {
+                loop {
+                    let next = {
+                        let appended = store.appended.notified();
+                        tokio::pin!(appended);
+                        appended.as_mut().enable();
+
+                        let next = store.next_from(cursor);
+                        if matches!(next, Ok(None)) {
+                            appended.await;
+                            continue;
+                        }
+                        next
+                    };
+
+                    match next {
+                        Ok(Some((seq, event))) => return Some((event, (store, seq + 1))),
+                        Ok(None) => continue,
+                        Err(e) => {
+                            warn!("event tail stopped: {}", e);
+                            return None;
+                        }
+                    }
+                }
+            })
+            .boxed()
+        }
     }
 }
 
 #[cfg(feature = "rocksdb")]
 pub use rocks::RocksEventStore;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+
+    #[tokio::test]
+    async fn test_dedup_shares_payloads_and_collects_unreferenced() {
+        let blobs = Arc::new(MemoryBlobStore::new());
+        let store = MemoryEventStore::new().with_dedup(blobs.clone());
+
+        let heartbeat = serde_json::json!({ "status": "alive", "load": 0.25 });
+        let size = serde_json::to_vec(&heartbeat).unwrap().len() as u64;
+        let mut ids = Vec::new();
+        for _ in 0..10 {
+            let event = Event::new("heartbeat", "node-a", heartbeat.clone());
+            ids.push(store.append(&event).await.unwrap());
+        }
+        let other = Event::new("task.done", "node-a", serde_json::json!({ "task": 7 }));
+        store.append(&other).await.unwrap();
+
+        let stats = store.dedup_stats().await.unwrap();
+        assert_eq!(stats.unique_payloads, 2);
+        assert_eq!(stats.total_events, 11);
+        assert_eq!(stats.bytes_saved, 9 * size);
+        assert_eq!(blobs.len(), 2);
+
+        let fetched = store.get(&ids[3]).await.unwrap().unwrap();
+        assert_eq!(fetched.payload, heartbeat);
+        assert!(store
+            .by_kind("heartbeat")
+            .await
+            .unwrap()
+            .iter()
+            .all(|e| e.payload == heartbeat));
+
+        for id in &ids {
+            let removed = store.remove(id).await.unwrap().unwrap();
+            assert_eq!(removed.payload, heartbeat);
+        }
+        assert_eq!(blobs.len(), 1);
+        let stats = store.dedup_stats().await.unwrap();
+        assert_eq!((stats.unique_payloads, stats.total_events, stats.bytes_saved), (1, 1, 0));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_dedup_keeps_payloads_of_live_events() {
+        let blobs = Arc::new(MemoryBlobStore::new());
+        let store = Arc::new(MemoryEventStore::new().with_dedup(blobs.clone()));
+        let payload = serde_json::json!({ "status": "alive" });
+
+        // Removing the last reference races with new events taking one
+        for _ in 0..50 {
+            let first = Event::new("heartbeat", "node-a", payload.clone());
+            store.append(&first).await.unwrap();
+            let second = Event::new("heartbeat", "node-b", payload.clone());
+            let (removed, appended) = tokio::join!(
+                tokio::spawn({
+                    let store = store.clone();
+                    async move { store.remove(&first.id).await }
+                }),
+                tokio::spawn({
+                    let store = store.clone();
+                    let second = second.clone();
+                    async move { store.append(&second).await }
+                }),
+            );
+            removed.unwrap().unwrap();
+            appended.unwrap().unwrap();
+
+            assert_eq!(store.get(&second.id).await.unwrap().unwrap().payload, payload);
+            store.remove(&second.id).await.unwrap();
+            assert!(blobs.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tail_yields_existing_then_new_events() {
+        let store = Arc::new(MemoryEventStore::new());
+        let first = Event::new("boot", "node-a", serde_json::json!({}));
+        store.append(&first).await.unwrap();
+
+        let mut tail = store.clone().tail();
+        assert_eq!(tail.next().await.unwrap().id, first.id);
+
+        let second = Event::new("heartbeat", "node-a", serde_json::json!({ "n": 1 }));
+        let writer = {
+            let store = store.clone();
+            let second = second.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                store.append(&second).await.unwrap();
+            })
+        };
+
+        let next = tokio::time::timeout(std::time::Duration::from_secs(1), tail.next())
+            .await
+            .expect("tail should wake on append")
+            .unwrap();
+        assert_eq!(next.id, second.id);
+        assert_eq!(next.payload, second.payload);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remove_prunes_timeline_without_disturbing_tail() {
+        let store = Arc::new(MemoryEventStore::new());
+        let events: Vec<Event> = (0..3)
+            .map(|n| Event::new("tick", "node-a", serde_json::json!({ "n": n })))
+            .collect();
+        for event in &events {
+            store.append(event).await.unwrap();
+        }
+
+        let mut tail = store.clone().tail();
+        assert_eq!(tail.next().await.unwrap().id, events[0].id);
+
+        store.remove(&events[0].id).await.unwrap();
+        store.remove(&events[1].id).await.unwrap();
+        assert_eq!(store.timeline.read().ids.len(), 1);
+        assert_eq!(store.timeline.read().seqs.len(), 1);
+
+        let later = Event::new("tick", "node-a", serde_json::json!({ "n": 3 }));
+        store.append(&later).await.unwrap();
+        assert_eq!(tail.next().await.unwrap().id, events[2].id);
+        assert_eq!(tail.next().await.unwrap().id, later.id);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[tokio::test]
+    async fn test_rocks_store_keeps_vector_clocks() {
+        let dir = std::env::temp_dir().join(format!("cortex-events-{}", uuid::Uuid::new_v4()));
+        let mut clock = crate::types::VectorClock::new();
+        clock.tick("node-a");
+        let event = Event::new("note", "node-a", serde_json::json!({})).with_clock(clock.clone());
+        {
+            let store = RocksEventStore::open(&dir).unwrap();
+            store.append(&event).await.unwrap();
+        }
+
+        let store = RocksEventStore::open(&dir).unwrap();
+        let fetched = store.get(&event.id).await.unwrap().unwrap();
+        assert_eq!(fetched.clock, Some(clock));
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
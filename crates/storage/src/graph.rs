@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use crate::types::{EventId, NodeId, PrivacyLevel, Tag, Timestamp};
 
@@ -76,6 +77,49 @@ impl Outcome {
     }
 }
 
+/// Aggregated outcomes of `Action` nodes, see `GraphStore::outcome_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeStats {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Mean time from node creation to its recorded outcome
+    pub mean_duration: Duration,
+}
+
+impl OutcomeStats {
+    /// Aggregate the actions among `nodes` that have a recorded outcome
+    pub fn from_nodes<'a>(nodes: impl IntoIterator<Item = &'a ThoughtNode>) -> Self {
+        let mut stats = Self::default();
+        let mut total_ms = 0u64;
+        for node in nodes {
+            let ThoughtContent::Action { outcome: Some(outcome), .. } = &node.content else {
+                continue;
+            };
+            stats.total += 1;
+            if outcome.success {
+                stats.succeeded += 1;
+            } else {
+                stats.failed += 1;
+            }
+            total_ms += outcome.timestamp.wall.saturating_sub(node.created_at.wall);
+        }
+        if stats.total > 0 {
+            stats.mean_duration = Duration::from_millis(total_ms / stats.total as u64);
+        }
+        stats
+    }
+
+    /// Fraction of outcomes that succeeded, 0.0 when there are none
+    pub fn success_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.succeeded as f64 / self.total as f64
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThoughtEdge {
     pub from: NodeId,
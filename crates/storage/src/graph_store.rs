@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::error::StoreError;
-use crate::graph::{ThoughtContent, ThoughtEdge, ThoughtNode};
+use crate::graph::{OutcomeStats, ThoughtContent, ThoughtEdge, ThoughtNode};
 use crate::types::{NodeId, Tag, Timestamp};
 
 #[derive(Debug, Clone, Default)]
@@ -41,19 +41,124 @@ impl GraphQuery {
     }
 }
 
+/// Writes buffered by `GraphStore::transaction` and applied all-or-nothing
+#[derive(Debug, Default)]
+pub struct GraphTransaction {
+    nodes: Vec<ThoughtNode>,
+    edges: Vec<ThoughtEdge>,
+}
+
+impl GraphTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: ThoughtNode) -> NodeId {
+        let id = node.id;
+        self.nodes.push(node);
+        id
+    }
+
+    pub fn add_edge(&mut self, edge: ThoughtEdge) {
+        self.edges.push(edge);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+
+    /// Edge endpoints not added by this transaction, which must already
+    /// exist in the store for the commit to succeed
+    fn external_endpoints(&self) -> std::collections::HashSet<NodeId> {
+        let own: std::collections::HashSet<NodeId> = self.nodes.iter().map(|n| n.id).collect();
+        self.edges
+            .iter()
+            .flat_map(|e| [e.from, e.to])
+            .filter(|id| !own.contains(id))
+            .collect()
+    }
+}
+
+fn missing_endpoint(id: &NodeId) -> StoreError {
+    StoreError::NotFound(format!("edge endpoint {}", id.0))
+}
+
 #[async_trait]
 pub trait GraphStore: Send + Sync {
     async fn add_node(&self, node: ThoughtNode) -> Result<NodeId, StoreError>;
+
+    /// Fails with `StoreError::NotFound` unless both endpoints are stored
     async fn add_edge(&self, edge: ThoughtEdge) -> Result<(), StoreError>;
+
     async fn get_node(&self, id: &NodeId) -> Result<Option<ThoughtNode>, StoreError>;
     async fn get_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError>;
     async fn query(&self, query: GraphQuery) -> Result<Vec<ThoughtNode>, StoreError>;
+
+    /// Edges pointing at `node_id`, read from the reverse index
+    async fn incoming_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError>;
+
+    /// Nodes with an edge pointing at `node_id`, e.g. what caused an outcome
+    async fn predecessors(&self, node_id: &NodeId) -> Result<Vec<ThoughtNode>, StoreError> {
+        let mut nodes = Vec::new();
+        for edge in self.incoming_edges(node_id).await? {
+            if let Some(node) = self.get_node(&edge.from).await? {
+                nodes.push(node);
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Success rate and mean duration of actions tagged `tag` that have a
+    /// recorded outcome
+    async fn outcome_stats(&self, tag: &Tag) -> Result<OutcomeStats, StoreError> {
+        let query = GraphQuery::new().with_kind("action").with_tag(tag.clone());
+        let nodes = self.query(query).await?;
+        Ok(OutcomeStats::from_nodes(&nodes))
+    }
+
+    /// Apply every write in `tx` or none of them. Fails with
+    /// `StoreError::NotFound` if an edge points at a node that is neither
+    /// in the store nor in the transaction.
+    async fn commit(&self, tx: GraphTransaction) -> Result<(), StoreError>;
+
+    /// Build a transaction with `build` and commit it. Nothing is written
+    /// if `build` returns an error or the commit fails.
+    async fn transaction<F>(&self, build: F) -> Result<(), StoreError>
+    where
+        Self: Sized,
+        F: FnOnce(&mut GraphTransaction) -> Result<(), StoreError> + Send,
+    {
+        let mut tx = GraphTransaction::new();
+        build(&mut tx)?;
+        self.commit(tx).await
+    }
 }
 
 #[derive(Default)]
 pub struct MemoryGraphStore {
     nodes: RwLock<HashMap<NodeId, ThoughtNode>>,
-    edges: RwLock<Vec<ThoughtEdge>>,
+    edges: RwLock<EdgeIndex>,
+}
+
+#[derive(Default)]
+struct EdgeIndex {
+    edges: Vec<ThoughtEdge>,
+    /// Positions in `edges` by destination node
+    incoming: HashMap<NodeId, Vec<usize>>,
+}
+
+impl EdgeIndex {
+    fn push(&mut self, edge: ThoughtEdge) {
+        self.incoming.entry(edge.to).or_default().push(self.edges.len());
+        self.edges.push(edge);
+    }
+
+    fn incoming(&self, node_id: &NodeId) -> Vec<ThoughtEdge> {
+        self.incoming
+            .get(node_id)
+            .map(|positions| positions.iter().map(|&i| self.edges[i].clone()).collect())
+            .unwrap_or_default()
+    }
 }
 
 impl MemoryGraphStore {
@@ -87,7 +192,7 @@ impl MemoryGraphStore {
         }
 
         if let Some((from, to)) = query.time_range {
-            if node.created_at < from || node.created_at > to {
+            if node.created_at.wall < from.wall || node.created_at.wall > to.wall {
                 return false;
             }
         }
@@ -130,6 +235,10 @@ impl GraphStore for MemoryGraphStore {
     }
 
     async fn add_edge(&self, edge: ThoughtEdge) -> Result<(), StoreError> {
+        let nodes = self.nodes.read();
+        if let Some(id) = [&edge.from, &edge.to].into_iter().find(|id| !nodes.contains_key(id)) {
+            return Err(missing_endpoint(id));
+        }
         self.edges.write().push(edge);
         Ok(())
     }
@@ -141,6 +250,7 @@ impl GraphStore for MemoryGraphStore {
     async fn get_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError> {
         let edges = self.edges.read();
         let result: Vec<ThoughtEdge> = edges
+            .edges
             .iter()
             .filter(|e| &e.from == node_id || &e.to == node_id)
             .cloned()
@@ -157,21 +267,52 @@ impl GraphStore for MemoryGraphStore {
             .collect();
         Ok(result)
     }
+
+    async fn incoming_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError> {
+        Ok(self.edges.read().incoming(node_id))
+    }
+
+    async fn commit(&self, tx: GraphTransaction) -> Result<(), StoreError> {
+        let mut nodes = self.nodes.write();
+        let mut edges = self.edges.write();
+
+        // Check every endpoint before writing, so a failed commit leaves
+        // the graph as it was
+        if let Some(id) = tx.external_endpoints().iter().find(|id| !nodes.contains_key(id)) {
+            return Err(missing_endpoint(id));
+        }
+        for node in tx.nodes {
+            nodes.insert(node.id, node);
+        }
+        for edge in tx.edges {
+            edges.push(edge);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "rocksdb")]
 pub mod rocks {
     use super::*;
-    use rocksdb::{ColumnFamilyDescriptor, Options, DB};
+    use crate::schema::{self, CF_META};
+    use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, DB};
     use std::path::Path;
 
+    /// Version 1 split node timestamps into wall and logical parts
+    const FORMAT_VERSION: u32 = 1;
+
     const CF_NODES: &str = "nodes";
     const CF_EDGES: &str = "edges";
     const CF_BY_KIND: &str = "nodes_by_kind";
     const CF_BY_TIME: &str = "nodes_by_time";
+    /// Edges keyed `to:from`, so incoming edges are a prefix scan
+    const CF_EDGES_BY_DEST: &str = "edges_by_dest";
 
     pub struct RocksGraphStore {
         db: Arc<DB>,
+        /// Held for every write, so the endpoint checks of an edge write
+        /// and its batch see the same graph
+        write_lock: parking_lot::Mutex<()>,
     }
 
     impl RocksGraphStore {
@@ -185,12 +326,133 @@ pub mod rocks {
                 ColumnFamilyDescriptor::new(CF_EDGES, Options::default()),
                 ColumnFamilyDescriptor::new(CF_BY_KIND, Options::default()),
                 ColumnFamilyDescriptor::new(CF_BY_TIME, Options::default()),
+                ColumnFamilyDescriptor::new(CF_EDGES_BY_DEST, Options::default()),
+                ColumnFamilyDescriptor::new(CF_META, Options::default()),
             ];
 
             let db = DB::open_cf_descriptors(&opts, path, cfs)
                 .map_err(|e| StoreError::Backend(e.to_string()))?;
 
-            Ok(Self { db: Arc::new(db) })
+            let store = Self {
+                db: Arc::new(db),
+                write_lock: parking_lot::Mutex::new(()),
+            };
+            store.migrate()?;
+            store.backfill_reverse_index()?;
+            Ok(store)
+        }
+
+        /// Unversioned databases store timestamps as bare milliseconds;
+        /// rewrite their nodes in the current layout once on open
+        fn migrate(&self) -> Result<(), StoreError> {
+            if let Some(version) = schema::stored_version(&self.db)? {
+                return schema::check_not_newer(version, FORMAT_VERSION);
+            }
+
+            let cf_nodes = self
+                .db
+                .cf_handle(CF_NODES)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+            let mut batch = WriteBatch::default();
+            for item in self.db.iterator_cf(&cf_nodes, rocksdb::IteratorMode::Start) {
+                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                let node: legacy::ThoughtNode = bincode::deserialize(&value)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
+                let node_bytes = bincode::serialize(&ThoughtNode::from(node))
+                    .map_err(|e| StoreError::Serialization(e.to_string()))?;
+                batch.put_cf(&cf_nodes, key, node_bytes);
+            }
+            if !batch.is_empty() {
+                self.db
+                    .write(batch)
+                    .map_err(|e| StoreError::Backend(e.to_string()))?;
+            }
+            schema::set_version(&self.db, FORMAT_VERSION)
+        }
+
+        /// Databases written before the reverse index existed have edges
+        /// but an empty `edges_by_dest`; rebuild it once on open
+        fn backfill_reverse_index(&self) -> Result<(), StoreError> {
+            let cf_edges = self
+                .db
+                .cf_handle(CF_EDGES)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+            let cf_dest = self
+                .db
+                .cf_handle(CF_EDGES_BY_DEST)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+
+            if self
+                .db
+                .iterator_cf(&cf_dest, rocksdb::IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                return Ok(());
+            }
+
+            let mut batch = WriteBatch::default();
+            for item in self.db.iterator_cf(&cf_edges, rocksdb::IteratorMode::Start) {
+                let (_, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                let edge: ThoughtEdge = bincode::deserialize(&value)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
+                let key = format!("{}:{}", edge.to.0, edge.from.0);
+                batch.put_cf(&cf_dest, key.as_bytes(), &value);
+            }
+            if batch.is_empty() {
+                return Ok(());
+            }
+            self.db
+                .write(batch)
+                .map_err(|e| StoreError::Backend(e.to_string()))
+        }
+
+        /// Edges stored in `cf_name` under keys starting with `node_id:`
+        fn edges_with_prefix(
+            &self,
+            cf_name: &str,
+            node_id: &NodeId,
+        ) -> Result<Vec<ThoughtEdge>, StoreError> {
+            let cf = self
+                .db
+                .cf_handle(cf_name)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+
+            let prefix = format!("{}:", node_id.0);
+            let mut results = Vec::new();
+
+            let iter = self.db.prefix_iterator_cf(&cf, prefix.as_bytes());
+            for item in iter {
+                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
+                let key_str = String::from_utf8_lossy(&key);
+                if !key_str.starts_with(&prefix) {
+                    break;
+                }
+
+                let edge: ThoughtEdge = bincode::deserialize(&value)
+                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
+                results.push(edge);
+            }
+
+            Ok(results)
+        }
+
+        fn require_node(&self, id: &NodeId) -> Result<(), StoreError> {
+            let cf = self
+                .db
+                .cf_handle(CF_NODES)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+            match self.db.get_cf(&cf, id.0.as_bytes()) {
+                Ok(Some(_)) => Ok(()),
+                Ok(None) => Err(missing_endpoint(id)),
+                Err(e) => Err(StoreError::Backend(e.to_string())),
+            }
+        }
+
+        fn write(&self, batch: WriteBatch) -> Result<(), StoreError> {
+            self.db
+                .write(batch)
+                .map_err(|e| StoreError::Backend(e.to_string()))
         }
 
         fn get_content_kind(content: &ThoughtContent) -> &'static str {
@@ -202,15 +464,11 @@ pub mod rocks {
                 ThoughtContent::Concept { .. } => "concept",
             }
         }
-    }
 
-    #[async_trait]
-    impl GraphStore for RocksGraphStore {
-        async fn add_node(&self, node: ThoughtNode) -> Result<NodeId, StoreError> {
-            let id = node.id;
-            let id_bytes = id.0.as_bytes();
+        fn batch_node(&self, batch: &mut WriteBatch, node: &ThoughtNode) -> Result<(), StoreError> {
+            let id_bytes = node.id.0.as_bytes();
             let node_bytes =
-                bincode::serialize(&node).map_err(|e| StoreError::Serialization(e.to_string()))?;
+                bincode::serialize(node).map_err(|e| StoreError::Serialization(e.to_string()))?;
 
             let cf_nodes = self
                 .db
@@ -225,40 +483,59 @@ pub mod rocks {
                 .cf_handle(CF_BY_TIME)
                 .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
 
-            self.db
-                .put_cf(&cf_nodes, id_bytes, &node_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            batch.put_cf(&cf_nodes, id_bytes, &node_bytes);
 
             let kind = Self::get_content_kind(&node.content);
-            let kind_key = format!("{}:{}", kind, id.0);
-            self.db
-                .put_cf(&cf_kind, kind_key.as_bytes(), id_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            let kind_key = format!("{}:{}", kind, node.id.0);
+            batch.put_cf(&cf_kind, kind_key.as_bytes(), id_bytes);
 
-            let time_key = format!("{:016x}:{}", node.created_at.0, id.0);
-            self.db
-                .put_cf(&cf_time, time_key.as_bytes(), id_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            let time_key = format!("{:016x}:{}", node.created_at.wall, node.id.0);
+            batch.put_cf(&cf_time, time_key.as_bytes(), id_bytes);
 
-            Ok(id)
+            Ok(())
         }
 
-        async fn add_edge(&self, edge: ThoughtEdge) -> Result<(), StoreError> {
+        fn batch_edge(&self, batch: &mut WriteBatch, edge: &ThoughtEdge) -> Result<(), StoreError> {
             let cf = self
                 .db
                 .cf_handle(CF_EDGES)
                 .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+            let cf_dest = self
+                .db
+                .cf_handle(CF_EDGES_BY_DEST)
+                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
 
             let key = format!("{}:{}", edge.from.0, edge.to.0);
             let edge_bytes =
-                bincode::serialize(&edge).map_err(|e| StoreError::Serialization(e.to_string()))?;
+                bincode::serialize(edge).map_err(|e| StoreError::Serialization(e.to_string()))?;
+            batch.put_cf(&cf, key.as_bytes(), &edge_bytes);
 
-            self.db
-                .put_cf(&cf, key.as_bytes(), &edge_bytes)
-                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            let dest_key = format!("{}:{}", edge.to.0, edge.from.0);
+            batch.put_cf(&cf_dest, dest_key.as_bytes(), &edge_bytes);
 
             Ok(())
         }
+    }
+
+    #[async_trait]
+    impl GraphStore for RocksGraphStore {
+        async fn add_node(&self, node: ThoughtNode) -> Result<NodeId, StoreError> {
+            let id = node.id;
+            let mut batch = WriteBatch::default();
+            self.batch_node(&mut batch, &node)?;
+            let _guard = self.write_lock.lock();
+            self.write(batch)?;
+            Ok(id)
+        }
+
+        async fn add_edge(&self, edge: ThoughtEdge) -> Result<(), StoreError> {
+            let mut batch = WriteBatch::default();
+            self.batch_edge(&mut batch, &edge)?;
+            let _guard = self.write_lock.lock();
+            self.require_node(&edge.from)?;
+            self.require_node(&edge.to)?;
+            self.write(batch)
+        }
 
         async fn get_node(&self, id: &NodeId) -> Result<Option<ThoughtNode>, StoreError> {
             let cf = self
@@ -278,38 +555,8 @@ pub mod rocks {
         }
 
         async fn get_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError> {
-            let cf = self
-                .db
-                .cf_handle(CF_EDGES)
-                .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
-
-            let prefix = format!("{}:", node_id.0);
-            let mut results = Vec::new();
-
-            let iter = self.db.prefix_iterator_cf(&cf, prefix.as_bytes());
-            for item in iter {
-                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
-                let key_str = String::from_utf8_lossy(&key);
-                if !key_str.starts_with(&prefix) {
-                    break;
-                }
-
-                let edge: ThoughtEdge = bincode::deserialize(&value)
-                    .map_err(|e| StoreError::Deserialization(e.to_string()))?;
-                results.push(edge);
-            }
-
-            let iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start);
-            for item in iter {
-                let (key, value) = item.map_err(|e| StoreError::Backend(e.to_string()))?;
-                let key_str = String::from_utf8_lossy(&key);
-                if key_str.ends_with(&format!(":{}", node_id.0)) {
-                    let edge: ThoughtEdge = bincode::deserialize(&value)
-                        .map_err(|e| StoreError::Deserialization(e.to_string()))?;
-                    results.push(edge);
-                }
-            }
-
+            let mut results = self.edges_with_prefix(CF_EDGES, node_id)?;
+            results.extend(self.incoming_edges(node_id).await?);
             Ok(results)
         }
 
@@ -334,8 +581,307 @@ pub mod rocks {
 
             Ok(results)
         }
+
+        async fn incoming_edges(&self, node_id: &NodeId) -> Result<Vec<ThoughtEdge>, StoreError> {
+            self.edges_with_prefix(CF_EDGES_BY_DEST, node_id)
+        }
+
+        async fn commit(&self, tx: GraphTransaction) -> Result<(), StoreError> {
+            // One write batch, so RocksDB applies all of it or none
+            let mut batch = WriteBatch::default();
+            for node in &tx.nodes {
+                self.batch_node(&mut batch, node)?;
+            }
+            for edge in &tx.edges {
+                self.batch_edge(&mut batch, edge)?;
+            }
+
+            let _guard = self.write_lock.lock();
+            for id in tx.external_endpoints() {
+                self.require_node(&id)?;
+            }
+            self.write(batch)
+        }
     }
 }
 
 #[cfg(feature = "rocksdb")]
 pub use rocks::RocksGraphStore;
+
+/// Node layout of unversioned RocksDB graph stores
+#[cfg(feature = "rocksdb")]
+mod legacy {
+    use serde::Deserialize;
+
+    use crate::graph::{self, IntentionStatus};
+    use crate::types::{EventId, NodeId, PrivacyLevel, Tag, Timestamp};
+
+    #[derive(Deserialize)]
+    pub(super) struct ThoughtNode {
+        id: NodeId,
+        content: ThoughtContent,
+        created_at: u64,
+        tags: Vec<Tag>,
+        privacy: PrivacyLevel,
+    }
+
+    /// Same variants, in the same order, as `graph::ThoughtContent`
+    #[derive(Deserialize)]
+    enum ThoughtContent {
+        Perception { event_id: EventId, summary: String },
+        Intention { goal: String, status: IntentionStatus },
+        Action { description: String, outcome: Option<Outcome> },
+        Memory { text: String },
+        Concept { name: String, definition: String },
+    }
+
+    #[derive(Deserialize)]
+    struct Outcome {
+        success: bool,
+        description: String,
+        timestamp: u64,
+    }
+
+    impl From<ThoughtNode> for graph::ThoughtNode {
+        fn from(node: ThoughtNode) -> Self {
+            let content = match node.content {
+                ThoughtContent::Perception { event_id, summary } => {
+                    graph::ThoughtContent::Perception { event_id, summary }
+                }
+                ThoughtContent::Intention { goal, status } => {
+                    graph::ThoughtContent::Intention { goal, status }
+                }
+                ThoughtContent::Action { description, outcome } => graph::ThoughtContent::Action {
+                    description,
+                    outcome: outcome.map(|o| graph::Outcome {
+                        success: o.success,
+                        description: o.description,
+                        timestamp: Timestamp::from_millis(o.timestamp),
+                    }),
+                },
+                ThoughtContent::Memory { text } => graph::ThoughtContent::Memory { text },
+                ThoughtContent::Concept { name, definition } => {
+                    graph::ThoughtContent::Concept { name, definition }
+                }
+            };
+            Self {
+                id: node.id,
+                content,
+                created_at: Timestamp::from_millis(node.created_at),
+                tags: node.tags,
+                privacy: node.privacy,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Relation;
+
+    fn memory(text: &str) -> ThoughtNode {
+        ThoughtNode::new(ThoughtContent::Memory { text: text.into() })
+    }
+
+    #[tokio::test]
+    async fn test_transaction_commits_all_writes() {
+        let store = MemoryGraphStore::new();
+        let (a, b) = (memory("a"), memory("b"));
+        let (a_id, b_id) = (a.id, b.id);
+
+        store
+            .transaction(|tx| {
+                tx.add_node(a);
+                tx.add_node(b);
+                tx.add_edge(ThoughtEdge::new(a_id, b_id, Relation::LeadsTo));
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert!(store.get_node(&a_id).await.unwrap().is_some());
+        assert!(store.get_node(&b_id).await.unwrap().is_some());
+        assert_eq!(store.get_edges(&b_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_incoming_edges_and_predecessors() {
+        let store = MemoryGraphStore::new();
+        let cause = store.add_node(memory("cause")).await.unwrap();
+        let other = store.add_node(memory("other")).await.unwrap();
+        let outcome = store.add_node(memory("outcome")).await.unwrap();
+
+        store.add_edge(ThoughtEdge::new(cause, outcome, Relation::Causes)).await.unwrap();
+        store.add_edge(ThoughtEdge::new(outcome, other, Relation::LeadsTo)).await.unwrap();
+        store
+            .transaction(|tx| {
+                tx.add_edge(ThoughtEdge::new(other, outcome, Relation::Supports));
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let incoming = store.incoming_edges(&outcome).await.unwrap();
+        assert_eq!(incoming.len(), 2);
+        assert!(incoming.iter().all(|e| e.to == outcome));
+
+        let mut predecessors: Vec<NodeId> = store
+            .predecessors(&outcome)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        predecessors.sort_by_key(|id| id.0);
+        let mut expected = vec![cause, other];
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(predecessors, expected);
+        assert!(store.predecessors(&cause).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_outcome_stats_by_tag() {
+        use crate::graph::Outcome;
+        use crate::types::Timestamp;
+
+        let store = MemoryGraphStore::new();
+        let step = Tag::new("step", "fetch");
+        let action = |outcome: Option<Outcome>, took_ms: u64, tag: &Tag| {
+            let mut node = ThoughtNode::new(ThoughtContent::Action {
+                description: "fetch".into(),
+                outcome: outcome.map(|mut o| {
+                    o.timestamp = Timestamp::from_millis(1_000 + took_ms);
+                    o
+                }),
+            })
+            .with_tags(vec![tag.clone()]);
+            node.created_at = Timestamp::from_millis(1_000);
+            node
+        };
+
+        for (outcome, took_ms) in [
+            (Some(Outcome::success("ok")), 100),
+            (Some(Outcome::success("ok")), 200),
+            (Some(Outcome::failure("timeout")), 600),
+            (None, 0),
+        ] {
+            store.add_node(action(outcome, took_ms, &step)).await.unwrap();
+        }
+        let other = Tag::new("step", "parse");
+        store
+            .add_node(action(Some(Outcome::failure("bad input")), 50, &other))
+            .await
+            .unwrap();
+        store.add_node(memory("fetch").with_tags(vec![step.clone()])).await.unwrap();
+
+        let stats = store.outcome_stats(&step).await.unwrap();
+        assert_eq!((stats.total, stats.succeeded, stats.failed), (3, 2, 1));
+        assert_eq!(stats.mean_duration, std::time::Duration::from_millis(300));
+        assert!((stats.success_rate() - 2.0 / 3.0).abs() < 1e-9);
+
+        let stats = store.outcome_stats(&Tag::new("step", "unknown")).await.unwrap();
+        assert_eq!(stats, OutcomeStats::default());
+        assert_eq!(stats.success_rate(), 0.0);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_legacy_node_layout_converts() {
+        use crate::types::PrivacyLevel;
+
+        // `ThoughtContent::Memory` is variant 3; timestamps were bare millis
+        let id = NodeId::new();
+        let bytes = bincode::serialize(&(
+            id,
+            3u32,
+            "remembered".to_string(),
+            1_234u64,
+            vec![Tag::priority("high")],
+            PrivacyLevel::Shareable,
+        ))
+        .unwrap();
+
+        let legacy: legacy::ThoughtNode = bincode::deserialize(&bytes).unwrap();
+        let node = ThoughtNode::from(legacy);
+        assert_eq!(node.id, id);
+        assert!(matches!(node.content, ThoughtContent::Memory { ref text } if text == "remembered"));
+        assert_eq!(node.created_at, Timestamp::from_millis(1_234));
+        assert_eq!(node.tags, vec![Tag::priority("high")]);
+        assert_eq!(node.privacy, PrivacyLevel::Shareable);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_leaves_graph_unchanged() {
+        let store = MemoryGraphStore::new();
+        let existing = store.add_node(memory("existing")).await.unwrap();
+
+        // Edge to a node that was never added
+        let orphan = memory("orphan");
+        let orphan_id = orphan.id;
+        let result = store
+            .transaction(|tx| {
+                tx.add_node(orphan);
+                tx.add_edge(ThoughtEdge::new(existing, orphan_id, Relation::Causes));
+                tx.add_edge(ThoughtEdge::new(existing, NodeId::new(), Relation::Causes));
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(StoreError::NotFound(_))));
+
+        // Error raised while building
+        let result = store
+            .transaction(|tx| {
+                tx.add_node(memory("never written"));
+                Err(StoreError::Integrity("abort".into()))
+            })
+            .await;
+        assert!(matches!(result, Err(StoreError::Integrity(_))));
+
+        assert!(store.get_node(&orphan_id).await.unwrap().is_none());
+        assert!(store.get_edges(&existing).await.unwrap().is_empty());
+        assert_eq!(store.query(GraphQuery::new()).await.unwrap().len(), 1);
+
+        let dangling = store
+            .add_edge(ThoughtEdge::new(existing, orphan_id, Relation::Causes))
+            .await;
+        assert!(matches!(dangling, Err(StoreError::NotFound(_))));
+        assert!(store.incoming_edges(&orphan_id).await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[tokio::test]
+    async fn test_rocks_edges_need_stored_endpoints() {
+        let dir = std::env::temp_dir().join(format!("cortex-graph-{}", uuid::Uuid::new_v4()));
+        let store = RocksGraphStore::open(&dir).unwrap();
+        let existing = store.add_node(memory("existing")).await.unwrap();
+        let missing = NodeId::new();
+
+        let dangling = store
+            .add_edge(ThoughtEdge::new(existing, missing, Relation::Causes))
+            .await;
+        assert!(matches!(dangling, Err(StoreError::NotFound(_))));
+
+        let added = memory("added");
+        let added_id = added.id;
+        let result = store
+            .transaction(|tx| {
+                tx.add_node(added);
+                tx.add_edge(ThoughtEdge::new(existing, added_id, Relation::LeadsTo));
+                tx.add_edge(ThoughtEdge::new(added_id, missing, Relation::LeadsTo));
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(StoreError::NotFound(_))));
+        assert!(store.get_node(&added_id).await.unwrap().is_none());
+        assert!(store.get_edges(&existing).await.unwrap().is_empty());
+
+        store
+            .add_edge(ThoughtEdge::new(existing, existing, Relation::Supports))
+            .await
+            .unwrap();
+        assert_eq!(store.incoming_edges(&existing).await.unwrap().len(), 1);
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,18 +1,36 @@
+pub mod blob;
 pub mod error;
 pub mod types;
 pub mod event_store;
 pub mod graph;
 pub mod graph_store;
 pub mod privacy;
+pub mod replication;
+#[cfg(feature = "rocksdb")]
+mod schema;
 pub mod sync;
+pub mod throttle;
 
+pub use blob::{BlobStore, MemoryBlobStore};
 pub use error::StoreError;
-pub use types::{Event, EventId, NodeId, PrivacyLevel, Tag, Timestamp};
-pub use event_store::{EventStore, MemoryEventStore};
-pub use graph::{IntentionStatus, Outcome, Relation, ThoughtContent, ThoughtEdge, ThoughtNode};
-pub use graph_store::{GraphQuery, GraphStore, MemoryGraphStore};
+pub use types::{Event, EventId, NodeId, PrivacyLevel, Tag, Timestamp, VectorClock};
+pub use event_store::{DedupStats, EventStore, EventStream, MemoryEventStore};
+pub use graph::{IntentionStatus, Outcome, OutcomeStats, Relation, ThoughtContent, ThoughtEdge, ThoughtNode};
+pub use graph_store::{GraphQuery, GraphStore, GraphTransaction, MemoryGraphStore};
 pub use privacy::{PrivacyAware, PrivacyFilter};
-pub use sync::{ContentHash, DiffRequest, DiffResponse, ExportChunk, SyncManager, SyncManifest};
+pub use replication::{
+    last_writer_wins, ConflictHandler, ReplicaPeer, ReplicationDirection, ReplicationReport,
+    ReplicationStatus, Replicator, ReplicatorConfig, StorePeer, Winner,
+};
+pub use sync::{
+    causal_order, BlobRequest, BlobResponse, ChunkFrame, ContentHash, DiffRequest, DiffResponse,
+    EventDigest, ExportChunk, FrameAssembler, ImportedEvents, SyncManager, SyncManifest,
+    SyncProgress,
+};
+pub use throttle::{BandwidthLimiter, SessionAllocation, SessionThrottle};
+
+#[cfg(feature = "rocksdb")]
+pub use blob::RocksBlobStore;
 
 #[cfg(feature = "rocksdb")]
 pub use event_store::RocksEventStore;
@@ -31,6 +31,62 @@ impl PrivacyFilter {
         Self::default()
     }
 
+    /// Allow every level that is at least as open as `min`.
+    pub fn at_least(min: PrivacyLevel) -> Self {
+        Self {
+            allowed_levels: [PrivacyLevel::Private, PrivacyLevel::Shareable, PrivacyLevel::Public]
+                .into_iter()
+                .filter(|level| *level >= min)
+                .collect(),
+        }
+    }
+
+    /// Most restrictive of `sources`, the level a value derived from all of
+    /// them must carry. With no sources nothing is known about provenance,
+    /// so this falls back to `Private`.
+    pub fn derive_level(sources: &[PrivacyLevel]) -> PrivacyLevel {
+        sources.iter().copied().min().unwrap_or(PrivacyLevel::Private)
+    }
+
+    /// Stamp `derived` with a level no more open than any of `sources` or
+    /// the level it already has, so a pipeline stage cannot downgrade
+    /// privacy by accident.
+    pub fn stamp_derived<'a, S: PrivacyAware + 'a>(
+        derived: Event,
+        sources: impl IntoIterator<Item = &'a S>,
+    ) -> Event {
+        let mut levels: Vec<PrivacyLevel> = sources.into_iter().map(|s| s.privacy_level()).collect();
+        levels.push(derived.privacy);
+        let level = Self::derive_level(&levels);
+        derived.with_privacy(level)
+    }
+
+    /// Replace PII-like substrings with stable hashed placeholders.
+    ///
+    /// Email addresses become `<email:xxxxxxxx>`, phone numbers written with
+    /// separators (`+1 555 123 4567`, `(555) 123-4567`) become
+    /// `<phone:xxxxxxxx>` and other runs of four or more digits (account ids)
+    /// become `<number:xxxxxxxx>`, where the suffix is a BLAKE3 prefix of the
+    /// original value. The same input always maps to the same placeholder, so
+    /// redacted datasets keep their co-occurrence structure without exposing
+    /// the value itself.
+    pub fn redact_pii(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end) in phone_spans(text) {
+            out.push_str(&redact_words(&text[last..start]));
+            // Hash the bare number so differently punctuated copies match
+            let number: String = text[start..end]
+                .chars()
+                .filter(|c| c.is_ascii_digit() || *c == '+')
+                .collect();
+            out.push_str(&placeholder("phone", &number));
+            last = end;
+        }
+        out.push_str(&redact_words(&text[last..]));
+        out
+    }
+
     pub fn allows(&self, level: &PrivacyLevel) -> bool {
         self.allowed_levels.contains(level)
     }
@@ -72,6 +128,145 @@ impl PrivacyFilter {
     }
 }
 
+/// Redact emails and digit runs word by word
+fn redact_words(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for token in text.split_inclusive(char::is_whitespace) {
+        let word = token.trim_end_matches(char::is_whitespace);
+        let trailing = &token[word.len()..];
+
+        let lead = word.len() - word.trim_start_matches(is_wrapping_punct).len();
+        let core_end = word
+            .trim_end_matches(|c: char| is_wrapping_punct(c) || c == '.')
+            .len();
+        if lead >= core_end {
+            out.push_str(token);
+            continue;
+        }
+
+        let core = &word[lead..core_end];
+        out.push_str(&word[..lead]);
+        if is_email(core) {
+            out.push_str(&placeholder("email", core));
+        } else {
+            out.push_str(&redact_digit_runs(core));
+        }
+        out.push_str(&word[core_end..]);
+        out.push_str(trailing);
+    }
+    out
+}
+
+/// Fewest digits a separated number needs to be taken for a phone number
+const MIN_PHONE_DIGITS: usize = 7;
+/// E.164 numbers have at most 15 digits
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// Byte ranges of phone numbers that span separators (spaces, `-`, `.`,
+/// parentheses), which word-by-word redaction would only partly catch.
+/// Unseparated digit runs are left to `redact_digit_runs`.
+fn phone_spans(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let is_separator = |c: char| matches!(c, ' ' | '-' | '.' | '(' | ')');
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let at_boundary = i == 0
+            || !(chars[i - 1].1.is_alphanumeric() || matches!(chars[i - 1].1, '@' | '.' | '_' | '-' | '+'));
+        if !at_boundary || !(c.is_ascii_digit() || c == '+' || c == '(') {
+            i += 1;
+            continue;
+        }
+
+        let mut j = i + usize::from(c == '+');
+        let (mut digits, mut separated, mut last_digit) = (0, false, None);
+        let mut gap = 0;
+        while j < chars.len() && gap <= 3 {
+            let c = chars[j].1;
+            if c.is_ascii_digit() {
+                separated |= digits > 0 && gap > 0;
+                digits += 1;
+                last_digit = Some(j);
+                gap = 0;
+            } else if is_separator(c) {
+                gap += 1;
+            } else {
+                break;
+            }
+            j += 1;
+        }
+
+        let Some(last) = last_digit else {
+            i = j.max(i + 1);
+            continue;
+        };
+        let followed_by_word = chars
+            .get(last + 1)
+            .is_some_and(|&(_, c)| c.is_alphanumeric() || c == '@');
+        if separated && !followed_by_word && (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits) {
+            // Leave an unmatched opening parenthesis outside the placeholder
+            let opens = c == '(' && !chars[i..=last].iter().any(|&(_, c)| c == ')');
+            let start = chars[i + usize::from(opens)].0;
+            let end = chars[last].0 + 1;
+            spans.push((start, end));
+        }
+        i = last + 1;
+    }
+    spans
+}
+
+fn is_wrapping_punct(c: char) -> bool {
+    matches!(c, ',' | ';' | ':' | '!' | '?' | '(' | ')' | '[' | ']' | '<' | '>' | '"' | '\'')
+}
+
+fn is_email(word: &str) -> bool {
+    let Some((local, domain)) = word.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain
+            .split('.')
+            .filter(|part| !part.is_empty())
+            .count()
+            >= 2
+}
+
+fn placeholder(kind: &str, value: &str) -> String {
+    let hash = blake3::hash(value.as_bytes());
+    format!("<{}:{}>", kind, &hash.to_hex()[..8])
+}
+
+const MIN_REDACTED_DIGITS: usize = 4;
+
+fn redact_digit_runs(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut run = String::new();
+
+    let flush = |run: &mut String, out: &mut String| {
+        let digits = run.chars().filter(char::is_ascii_digit).count();
+        if digits >= MIN_REDACTED_DIGITS {
+            out.push_str(&placeholder("number", run));
+        } else {
+            out.push_str(run);
+        }
+        run.clear();
+    };
+
+    for c in word.chars() {
+        let continues_run = !run.is_empty() && matches!(c, '-' | '.' | '+');
+        if c.is_ascii_digit() || continues_run {
+            run.push(c);
+        } else {
+            flush(&mut run, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut run, &mut out);
+    out
+}
+
 pub trait PrivacyAware {
     fn privacy_level(&self) -> PrivacyLevel;
 
@@ -103,3 +298,55 @@ pub fn redact_for_sharing<T: PrivacyAware + Clone>(items: &[T], filter: &Privacy
         .cloned()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_events_never_downgrade() {
+        use PrivacyLevel::*;
+        assert_eq!(PrivacyFilter::derive_level(&[Public, Shareable]), Shareable);
+        assert_eq!(PrivacyFilter::derive_level(&[Public, Private, Shareable]), Private);
+        assert_eq!(PrivacyFilter::derive_level(&[Public]), Public);
+        assert_eq!(PrivacyFilter::derive_level(&[]), Private);
+
+        let private = Event::new("audio.transcript", "mic", serde_json::json!({}));
+        let public = Event::new("weather", "web", serde_json::json!({})).with_privacy(Public);
+
+        let summary = Event::new("summary", "agent", serde_json::json!({})).with_privacy(Public);
+        let summary = PrivacyFilter::stamp_derived(summary, [&private, &public]);
+        assert_eq!(summary.privacy, Private);
+        assert!(PrivacyFilter::shareable().check_event(&summary).is_err());
+
+        // Already stricter than its sources: kept as is
+        let note = Event::new("note", "agent", serde_json::json!({})).with_privacy(Shareable);
+        let note = PrivacyFilter::stamp_derived(note, [&public]);
+        assert_eq!(note.privacy, Shareable);
+    }
+
+    #[test]
+    fn test_redact_pii() {
+        let email = PrivacyFilter::redact_pii("mail ada@example.com, thanks");
+        assert!(email.starts_with("mail <email:"));
+        assert!(email.ends_with(">, thanks"));
+        assert!(!email.contains("ada@"));
+
+        // Each formatting of the same number becomes the same placeholder
+        let spaced = PrivacyFilter::redact_pii("call +1 555 123 4567 today");
+        let dashed = PrivacyFilter::redact_pii("call +1-555-123-4567 today");
+        assert_eq!(spaced, dashed);
+        assert!(spaced.starts_with("call <phone:"));
+        assert!(spaced.ends_with("> today"));
+        assert!(!spaced.contains("555"));
+
+        let local = PrivacyFilter::redact_pii("office (555) 123-4567.");
+        assert!(local.starts_with("office <phone:"));
+        assert!(local.ends_with(">."));
+
+        assert!(PrivacyFilter::redact_pii("account 12345678").starts_with("account <number:"));
+
+        let clean = "Meet at 3 pm, room 12 (bring 2 laptops).";
+        assert_eq!(PrivacyFilter::redact_pii(clean), clean);
+    }
+}
@@ -0,0 +1,447 @@
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::error::StoreError;
+use crate::event_store::EventStore;
+use crate::sync::{EventDigest, SyncManager};
+use crate::types::{Event, EventId, Timestamp};
+
+/// Which way events flow between this node and the peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplicationDirection {
+    /// Copy the peer's events here
+    Pull,
+    /// Copy our events to the peer
+    Push,
+    Bidirectional,
+}
+
+impl ReplicationDirection {
+    fn pulls(self) -> bool {
+        matches!(self, Self::Pull | Self::Bidirectional)
+    }
+
+    fn pushes(self) -> bool {
+        matches!(self, Self::Push | Self::Bidirectional)
+    }
+}
+
+/// Which copy of a conflicting event to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winner {
+    Local,
+    Remote,
+}
+
+/// Decides between the local and remote versions of an event
+pub type ConflictHandler = Arc<dyn Fn(&Event, &Event) -> Winner + Send + Sync>;
+
+/// Keep whichever version was written last: the causally later one when
+/// the vector clocks are ordered, otherwise the later timestamp. Ties keep
+/// the local copy.
+pub fn last_writer_wins(local: &Event, remote: &Event) -> Winner {
+    match local.happens_before(remote) {
+        Some(true) => Winner::Remote,
+        Some(false) => Winner::Local,
+        None if local.timestamp.logical_cmp(&remote.timestamp).is_lt() => Winner::Remote,
+        None => Winner::Local,
+    }
+}
+
+/// The far end of a replication link. `StorePeer` serves an `EventStore`
+/// directly; a networked implementation forwards these calls to the
+/// `StorePeer` on the remote node.
+#[async_trait]
+pub trait ReplicaPeer: Send + Sync {
+    async fn digests(&self) -> Result<Vec<EventDigest>, StoreError>;
+    async fn fetch(&self, ids: &[EventId]) -> Result<Vec<Event>, StoreError>;
+    /// Store `events`, replacing any with the same id
+    async fn push(&self, events: Vec<Event>) -> Result<(), StoreError>;
+}
+
+/// Exposes an event store to replicating peers, subject to the sync
+/// manager's privacy filter
+pub struct StorePeer {
+    store: Arc<dyn EventStore>,
+    sync: Arc<SyncManager>,
+}
+
+impl StorePeer {
+    pub fn new(store: Arc<dyn EventStore>, sync: Arc<SyncManager>) -> Self {
+        Self { store, sync }
+    }
+}
+
+#[async_trait]
+impl ReplicaPeer for StorePeer {
+    async fn digests(&self) -> Result<Vec<EventDigest>, StoreError> {
+        self.sync
+            .event_digests(&all_events(self.store.as_ref()).await?)
+    }
+
+    async fn fetch(&self, ids: &[EventId]) -> Result<Vec<Event>, StoreError> {
+        let mut events = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(event) = self.store.get(id).await? {
+                events.push(event);
+            }
+        }
+        Ok(self.sync.shareable_events(events))
+    }
+
+    async fn push(&self, events: Vec<Event>) -> Result<(), StoreError> {
+        let imported = self.sync.import_events(events);
+        if !imported.quarantined.is_empty() {
+            warn!("quarantined {} pushed events stamped too far ahead", imported.quarantined.len());
+        }
+        for event in imported.events {
+            replace(self.store.as_ref(), &event).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct ReplicatorConfig {
+    pub direction: ReplicationDirection,
+    /// Time between rounds when spawned
+    pub interval: Duration,
+    pub on_conflict: ConflictHandler,
+}
+
+impl Default for ReplicatorConfig {
+    fn default() -> Self {
+        Self {
+            direction: ReplicationDirection::Bidirectional,
+            interval: Duration::from_secs(30),
+            on_conflict: Arc::new(last_writer_wins),
+        }
+    }
+}
+
+/// What one replication round did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationReport {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub conflicts: usize,
+    /// Pulled events skipped for being more than `MAX_CLOCK_DRIFT_MS`
+    /// ahead of our clock. They are fetched again next round.
+    #[serde(default)]
+    pub quarantined: usize,
+}
+
+/// Replication progress so far. `behind` and `ahead` are the lag measured
+/// at the start of the last round: events only the peer had, and events
+/// only we had.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationStatus {
+    pub rounds: u64,
+    /// Wall-clock millis when the last round succeeded
+    pub last_success: Option<u64>,
+    pub behind: usize,
+    pub ahead: usize,
+    pub pulled: u64,
+    pub pushed: u64,
+    pub conflicts: u64,
+    #[serde(default)]
+    pub quarantined: u64,
+    pub last_error: Option<String>,
+}
+
+/// Keeps a local event store replicated with one peer
+pub struct Replicator {
+    store: Arc<dyn EventStore>,
+    sync: Arc<SyncManager>,
+    peer: Arc<dyn ReplicaPeer>,
+    config: ReplicatorConfig,
+    status: RwLock<ReplicationStatus>,
+}
+
+impl Replicator {
+    pub fn new(
+        store: Arc<dyn EventStore>,
+        sync: Arc<SyncManager>,
+        peer: Arc<dyn ReplicaPeer>,
+    ) -> Self {
+        Self::with_config(store, sync, peer, ReplicatorConfig::default())
+    }
+
+    pub fn with_config(
+        store: Arc<dyn EventStore>,
+        sync: Arc<SyncManager>,
+        peer: Arc<dyn ReplicaPeer>,
+        config: ReplicatorConfig,
+    ) -> Self {
+        Self {
+            store,
+            sync,
+            peer,
+            config,
+            status: RwLock::new(ReplicationStatus::default()),
+        }
+    }
+
+    pub fn status(&self) -> ReplicationStatus {
+        self.status.read().clone()
+    }
+
+    /// Run one round: diff digests with the peer, then pull, push and
+    /// resolve conflicts as the direction allows
+    pub async fn sync_once(&self) -> Result<ReplicationReport, StoreError> {
+        let result = self.round().await;
+        let mut status = self.status.write();
+        status.rounds += 1;
+        match &result {
+            Ok(report) => {
+                status.last_success = Some(Timestamp::now().wall);
+                status.pulled += report.pulled as u64;
+                status.pushed += report.pushed as u64;
+                status.conflicts += report.conflicts as u64;
+                status.quarantined += report.quarantined as u64;
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e.to_string()),
+        }
+        result
+    }
+
+    /// Replicate every `config.interval` until the handle is aborted
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sync_once().await {
+                    warn!("replication round failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn round(&self) -> Result<ReplicationReport, StoreError> {
+        let local_events = all_events(self.store.as_ref()).await?;
+        let local: HashMap<EventId, _> = self
+            .sync
+            .event_digests(&local_events)?
+            .into_iter()
+            .map(|d| (d.id, d.hash))
+            .collect();
+        let remote: HashMap<EventId, _> = self
+            .peer
+            .digests()
+            .await?
+            .into_iter()
+            .map(|d| (d.id, d.hash))
+            .collect();
+
+        let missing: Vec<EventId> = remote
+            .keys()
+            .filter(|id| !local.contains_key(id))
+            .copied()
+            .collect();
+        let unknown: Vec<EventId> = local
+            .keys()
+            .filter(|id| !remote.contains_key(id))
+            .copied()
+            .collect();
+        let diverged: Vec<EventId> = remote
+            .iter()
+            .filter(|(id, hash)| local.get(id).is_some_and(|h| h != *hash))
+            .map(|(id, _)| *id)
+            .collect();
+        {
+            let mut status = self.status.write();
+            status.behind = missing.len();
+            status.ahead = unknown.len();
+        }
+
+        let mut by_id: HashMap<EventId, Event> =
+            local_events.into_iter().map(|e| (e.id, e)).collect();
+        let mut to_pull = Vec::new();
+        let mut to_push: Vec<Event> = if self.config.direction.pushes() {
+            unknown.iter().filter_map(|id| by_id.remove(id)).collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut report = ReplicationReport {
+            conflicts: diverged.len(),
+            ..Default::default()
+        };
+        if !diverged.is_empty() {
+            for remote_event in self.peer.fetch(&diverged).await? {
+                let Some(local_event) = by_id.remove(&remote_event.id) else {
+                    continue;
+                };
+                match (self.config.on_conflict)(&local_event, &remote_event) {
+                    Winner::Remote if self.config.direction.pulls() => to_pull.push(remote_event),
+                    Winner::Local if self.config.direction.pushes() => to_push.push(local_event),
+                    _ => {}
+                }
+            }
+        }
+
+        if self.config.direction.pulls() && !missing.is_empty() {
+            to_pull.extend(self.peer.fetch(&missing).await?);
+        }
+        let imported = self.sync.import_events(to_pull);
+        report.pulled = imported.events.len();
+        report.quarantined = imported.quarantined.len();
+        if report.quarantined > 0 {
+            warn!("quarantined {} pulled events stamped too far ahead", report.quarantined);
+        }
+        for event in imported.events {
+            replace(self.store.as_ref(), &event).await?;
+        }
+
+        report.pushed = to_push.len();
+        if !to_push.is_empty() {
+            self.peer.push(to_push).await?;
+        }
+
+        Ok(report)
+    }
+}
+
+async fn all_events(store: &dyn EventStore) -> Result<Vec<Event>, StoreError> {
+    store
+        .range(Timestamp::from_millis(0), Timestamp::from_millis(u64::MAX))
+        .await
+}
+
+/// Append `event`, dropping any stored event with the same id first so
+/// backend indexes don't keep the old version
+async fn replace(store: &dyn EventStore, event: &Event) -> Result<(), StoreError> {
+    store.remove(&event.id).await?;
+    store.append(event).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::MemoryEventStore;
+    use crate::types::PrivacyLevel;
+
+    fn shareable(sync: &SyncManager, kind: &str) -> Event {
+        sync.stamp(
+            Event::new(kind, "test", serde_json::json!({})).with_privacy(PrivacyLevel::Shareable),
+        )
+    }
+
+    struct Node {
+        store: Arc<MemoryEventStore>,
+        sync: Arc<SyncManager>,
+    }
+
+    impl Node {
+        fn new(name: &str) -> Self {
+            Self {
+                store: Arc::new(MemoryEventStore::new()),
+                sync: Arc::new(SyncManager::default().with_node(name)),
+            }
+        }
+
+        fn peer(&self) -> Arc<dyn ReplicaPeer> {
+            Arc::new(StorePeer::new(self.store.clone(), self.sync.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bidirectional_replication_converges() {
+        let (a, b) = (Node::new("a"), Node::new("b"));
+        a.store.append(&shareable(&a.sync, "a.one")).await.unwrap();
+        b.store.append(&shareable(&b.sync, "b.one")).await.unwrap();
+        b.store.append(&shareable(&b.sync, "b.two")).await.unwrap();
+        let private = Event::new("b.secret", "test", serde_json::json!({}));
+        b.store.append(&private).await.unwrap();
+
+        let replicator = Replicator::new(a.store.clone(), a.sync.clone(), b.peer());
+        let report = replicator.sync_once().await.unwrap();
+        assert_eq!((report.pulled, report.pushed, report.conflicts), (2, 1, 0));
+
+        let status = replicator.status();
+        assert_eq!((status.behind, status.ahead, status.rounds), (2, 1, 1));
+        assert!(status.last_success.is_some());
+
+        assert_eq!(a.store.dedup_stats().await.unwrap().total_events, 3);
+        assert_eq!(b.store.dedup_stats().await.unwrap().total_events, 4);
+        assert!(a.store.get(&private.id).await.unwrap().is_none());
+
+        let again = replicator.sync_once().await.unwrap();
+        assert_eq!(again, ReplicationReport::default());
+        assert_eq!(
+            (replicator.status().behind, replicator.status().ahead),
+            (0, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_resolve_by_vector_clock() {
+        let (a, b) = (Node::new("a"), Node::new("b"));
+        let original = shareable(&a.sync, "doc");
+        a.store.append(&original).await.unwrap();
+        let pull = Replicator::with_config(
+            b.store.clone(),
+            b.sync.clone(),
+            a.peer(),
+            ReplicatorConfig {
+                direction: ReplicationDirection::Pull,
+                ..Default::default()
+            },
+        );
+        pull.sync_once().await.unwrap();
+
+        // b saw a's version before rewriting it, so b's version wins even
+        // though its timestamp is older
+        let mut edited = b.sync.stamp(original.clone());
+        edited.payload = serde_json::json!({ "rev": 2 });
+        edited.timestamp = Timestamp::from_millis(1);
+        b.store.append(&edited).await.unwrap();
+
+        let seen = Arc::new(RwLock::new(Vec::new()));
+        let log = seen.clone();
+        let config = ReplicatorConfig {
+            on_conflict: Arc::new(move |local: &Event, remote: &Event| {
+                log.write().push(local.id);
+                last_writer_wins(local, remote)
+            }),
+            ..Default::default()
+        };
+        let replicator = Replicator::with_config(a.store.clone(), a.sync.clone(), b.peer(), config);
+        let report = replicator.sync_once().await.unwrap();
+        assert_eq!((report.pulled, report.pushed, report.conflicts), (1, 0, 1));
+        assert_eq!(*seen.read(), vec![original.id]);
+
+        let stored = a.store.get(&original.id).await.unwrap().unwrap();
+        assert_eq!(stored.payload, serde_json::json!({ "rev": 2 }));
+        assert_eq!(a.store.dedup_stats().await.unwrap().total_events, 1);
+    }
+
+    #[tokio::test]
+    async fn test_future_events_are_quarantined() {
+        use crate::types::MAX_CLOCK_DRIFT_MS;
+
+        let (a, b) = (Node::new("a"), Node::new("b"));
+        b.store.append(&shareable(&b.sync, "b.now")).await.unwrap();
+        let mut future = shareable(&b.sync, "b.future");
+        future.timestamp = Timestamp::from_millis(Timestamp::now().wall + MAX_CLOCK_DRIFT_MS + 3_600_000);
+        b.store.append(&future).await.unwrap();
+
+        let replicator = Replicator::new(a.store.clone(), a.sync.clone(), b.peer());
+        let report = replicator.sync_once().await.unwrap();
+        assert_eq!((report.pulled, report.quarantined), (1, 1));
+        assert!(a.store.get(&future.id).await.unwrap().is_none());
+        assert_eq!(replicator.status().quarantined, 1);
+        assert!(replicator.status().last_error.is_none());
+        // The rejected timestamp did not drag our clock forward
+        assert!(Timestamp::now().logical < future.timestamp.logical);
+    }
+}
@@ -0,0 +1,51 @@
+//! On-disk format versions of the RocksDB stores
+//!
+//! Each store keeps its format version in a `meta` column family and checks
+//! it on open. A database without one predates versioning and is migrated by
+//! the store that owns it; one from a newer build is refused rather than
+//! misread.
+
+use rocksdb::DB;
+
+use crate::error::StoreError;
+
+pub(crate) const CF_META: &str = "meta";
+
+const FORMAT_VERSION_KEY: &[u8] = b"format_version";
+
+/// Version recorded in `db`, or `None` if it was never written
+pub(crate) fn stored_version(db: &DB) -> Result<Option<u32>, StoreError> {
+    let cf = db
+        .cf_handle(CF_META)
+        .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+    let Some(bytes) = db
+        .get_cf(&cf, FORMAT_VERSION_KEY)
+        .map_err(|e| StoreError::Backend(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+    let bytes: [u8; 4] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| StoreError::Integrity("malformed format version".into()))?;
+    Ok(Some(u32::from_le_bytes(bytes)))
+}
+
+pub(crate) fn set_version(db: &DB, version: u32) -> Result<(), StoreError> {
+    let cf = db
+        .cf_handle(CF_META)
+        .ok_or_else(|| StoreError::Backend("CF not found".into()))?;
+    db.put_cf(&cf, FORMAT_VERSION_KEY, version.to_le_bytes())
+        .map_err(|e| StoreError::Backend(e.to_string()))
+}
+
+/// Refuse databases written by a newer build
+pub(crate) fn check_not_newer(found: u32, supported: u32) -> Result<(), StoreError> {
+    if found > supported {
+        return Err(StoreError::Integrity(format!(
+            "database format {} is newer than supported format {}",
+            found, supported
+        )));
+    }
+    Ok(())
+}
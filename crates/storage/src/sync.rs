@@ -1,12 +1,16 @@
 use blake3::Hasher;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
+use crate::blob::BlobStore;
 use crate::error::StoreError;
 use crate::graph::ThoughtNode;
 use crate::privacy::{PrivacyFilter, PrivacyAware};
-use crate::types::{Event, NodeId, PrivacyLevel};
+use crate::throttle::{BandwidthLimiter, SessionThrottle};
+use crate::types::{Event, EventId, NodeId, PrivacyLevel, Timestamp, VectorClock};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ContentHash(pub [u8; 32]);
 
 impl ContentHash {
@@ -18,6 +22,11 @@ impl ContentHash {
         Ok(Self(hasher.finalize().into()))
     }
 
+    /// Hash raw bytes as received off the wire.
+    pub fn of_bytes(data: &[u8]) -> Self {
+        Self(*blake3::hash(data).as_bytes())
+    }
+
     pub fn as_hex(&self) -> String {
         hex::encode(&self.0)
     }
@@ -62,6 +71,26 @@ pub struct DiffResponse {
     pub new_hashes: Vec<ContentHash>,
 }
 
+/// One shareable event in a replication manifest; events with the same
+/// id but different hashes conflict
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventDigest {
+    pub id: EventId,
+    pub hash: ContentHash,
+}
+
+/// Ask a peer for blobs referenced by synced data that we don't hold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobRequest {
+    pub hashes: Vec<ContentHash>,
+}
+
+/// Blobs the peer had; hashes it lacked are absent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobResponse {
+    pub blobs: Vec<(ContentHash, Vec<u8>)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportChunk {
     pub id: u32,
@@ -84,16 +113,202 @@ impl ExportChunk {
     }
 
     pub fn verify(&self) -> Result<bool, StoreError> {
+        Ok(self.computed_hash()? == self.hash)
+    }
+
+    fn computed_hash(&self) -> Result<ContentHash, StoreError> {
         let combined: (Vec<&ThoughtNode>, Vec<&Event>) =
             (self.nodes.iter().collect(), self.events.iter().collect());
-        let computed = ContentHash::compute(&combined)?;
-        Ok(computed.0 == self.hash.0)
+        ContentHash::compute(&combined)
+    }
+
+    /// Serialize the chunk for a put; the returned hash is what the
+    /// receiver checks the bytes against in `SyncManager::handle_chunk_put`.
+    ///
+    /// Uses JSON because event payloads are `serde_json::Value`, which
+    /// bincode cannot decode.
+    pub fn to_bytes(&self) -> Result<(ContentHash, Vec<u8>), StoreError> {
+        let bytes =
+            serde_json::to_vec(self).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        Ok((ContentHash::of_bytes(&bytes), bytes))
+    }
+}
+
+/// Result of `SyncManager::import_events`
+#[derive(Debug, Clone, Default)]
+pub struct ImportedEvents {
+    /// Accepted events, in causal order
+    pub events: Vec<Event>,
+    /// Events too far in the future to accept; they did not touch the
+    /// local clocks
+    pub quarantined: Vec<Event>,
+}
+
+/// Default size of a transfer frame (64 KiB).
+pub const DEFAULT_FRAME_SIZE: usize = 64 * 1024;
+
+/// Most frames one chunk may be split into. The count comes from the peer,
+/// so `FrameAssembler::new` refuses anything larger before allocating.
+pub const MAX_FRAMES: u32 = 1 << 16;
+
+/// A fixed-size slice of a serialized chunk, sent independently so an
+/// interrupted transfer can resume from the first missing frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkFrame {
+    /// Hash of the complete serialized chunk this frame belongs to
+    pub chunk_hash: ContentHash,
+    pub index: u32,
+    pub total: u32,
+    /// Hash of `data` alone
+    pub hash: ContentHash,
+    pub data: Vec<u8>,
+}
+
+/// Progress of a framed chunk transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncProgress {
+    pub frames_received: u32,
+    pub frames_total: u32,
+    pub bytes_received: u64,
+    /// First frame still missing, i.e. where a reconnecting sender resumes
+    pub resume_from: Option<u32>,
+}
+
+impl SyncProgress {
+    pub fn is_complete(&self) -> bool {
+        self.resume_from.is_none()
+    }
+}
+
+/// Collects the frames of one chunk across connections.
+///
+/// Keep the assembler alive over a reconnect and ask the sender to restart
+/// at `next_missing()`; frames that already arrived are not re-sent.
+pub struct FrameAssembler {
+    chunk_hash: ContentHash,
+    frames: Vec<Option<Vec<u8>>>,
+    bytes_received: u64,
+}
+
+impl FrameAssembler {
+    /// Fails if `total_frames` is zero or above `MAX_FRAMES`.
+    pub fn new(chunk_hash: ContentHash, total_frames: u32) -> Result<Self, StoreError> {
+        if total_frames == 0 || total_frames > MAX_FRAMES {
+            return Err(StoreError::Integrity(format!(
+                "Chunk {} declares {} frames, expected 1 to {}",
+                chunk_hash.as_hex(),
+                total_frames,
+                MAX_FRAMES
+            )));
+        }
+        Ok(Self {
+            chunk_hash,
+            frames: vec![None; total_frames as usize],
+            bytes_received: 0,
+        })
+    }
+
+    pub fn chunk_hash(&self) -> &ContentHash {
+        &self.chunk_hash
+    }
+
+    /// Verify and store a frame. Duplicates are accepted and ignored.
+    pub fn accept(&mut self, frame: ChunkFrame) -> Result<SyncProgress, StoreError> {
+        if frame.chunk_hash != self.chunk_hash {
+            return Err(StoreError::Integrity(format!(
+                "Frame belongs to chunk {}, expected {}",
+                frame.chunk_hash.as_hex(),
+                self.chunk_hash.as_hex()
+            )));
+        }
+        if frame.total as usize != self.frames.len() || frame.index >= frame.total {
+            return Err(StoreError::Integrity(format!(
+                "Frame {}/{} does not fit a {}-frame transfer",
+                frame.index,
+                frame.total,
+                self.frames.len()
+            )));
+        }
+
+        let computed = ContentHash::of_bytes(&frame.data);
+        if computed != frame.hash {
+            return Err(StoreError::HashMismatch {
+                expected: frame.hash.as_hex(),
+                computed: computed.as_hex(),
+            });
+        }
+
+        let slot = &mut self.frames[frame.index as usize];
+        if slot.is_none() {
+            self.bytes_received += frame.data.len() as u64;
+            *slot = Some(frame.data);
+        }
+        Ok(self.progress())
+    }
+
+    pub fn next_missing(&self) -> Option<u32> {
+        self.frames.iter().position(Option::is_none).map(|i| i as u32)
     }
+
+    pub fn progress(&self) -> SyncProgress {
+        SyncProgress {
+            frames_received: self.frames.iter().filter(|f| f.is_some()).count() as u32,
+            frames_total: self.frames.len() as u32,
+            bytes_received: self.bytes_received,
+            resume_from: self.next_missing(),
+        }
+    }
+
+    /// Join the frames back into the serialized chunk.
+    pub fn finish(self) -> Result<Vec<u8>, StoreError> {
+        if let Some(missing) = self.next_missing() {
+            return Err(StoreError::NotFound(format!(
+                "Frame {} of chunk {} not received",
+                missing,
+                self.chunk_hash.as_hex()
+            )));
+        }
+        Ok(self.frames.into_iter().flatten().flatten().collect())
+    }
+}
+
+/// Sort `events` so every event comes after those that happen before it.
+/// A clock's total grows along every happens-before edge, so clocked events
+/// sort by total and then by `Timestamp::logical_cmp`. Events without
+/// clocks are merged in by timestamp alone.
+pub fn causal_order(events: Vec<Event>) -> Vec<Event> {
+    let (clocked, mut unclocked): (Vec<Event>, Vec<Event>) =
+        events.into_iter().partition(|e| e.clock.is_some());
+
+    let mut clocked: Vec<(u64, Event)> = clocked
+        .into_iter()
+        .map(|e| (e.clock.as_ref().map_or(0, VectorClock::total), e))
+        .collect();
+    clocked.sort_by(|(ta, a), (tb, b)| ta.cmp(tb).then(a.timestamp.logical_cmp(&b.timestamp)));
+    unclocked.sort_by(|a, b| a.timestamp.logical_cmp(&b.timestamp));
+
+    let mut order = Vec::with_capacity(clocked.len() + unclocked.len());
+    let mut unclocked = unclocked.into_iter().peekable();
+    for (_, event) in clocked {
+        while let Some(next) =
+            unclocked.next_if(|u| u.timestamp.logical_cmp(&event.timestamp).is_lt())
+        {
+            order.push(next);
+        }
+        order.push(event);
+    }
+    order.extend(unclocked);
+    order
 }
 
 pub struct SyncManager {
     privacy_filter: PrivacyFilter,
     chunk_size: usize,
+    frame_size: usize,
+    bandwidth: Option<Arc<BandwidthLimiter>>,
+    /// This node's name in vector clocks; `stamp` needs it
+    node: Option<String>,
+    clock: Mutex<VectorClock>,
 }
 
 impl Default for SyncManager {
@@ -101,6 +316,10 @@ impl Default for SyncManager {
         Self {
             privacy_filter: PrivacyFilter::shareable(),
             chunk_size: 100,
+            frame_size: DEFAULT_FRAME_SIZE,
+            bandwidth: None,
+            node: None,
+            clock: Mutex::new(VectorClock::new()),
         }
     }
 }
@@ -110,9 +329,98 @@ impl SyncManager {
         Self {
             privacy_filter,
             chunk_size,
+            ..Self::default()
         }
     }
 
+    /// Track causality with vector clocks, counting this node's events
+    /// under `node`
+    pub fn with_node(mut self, node: impl Into<String>) -> Self {
+        self.node = Some(node.into());
+        self
+    }
+
+    /// Record a locally created event: tick this node's counter and attach
+    /// the resulting clock. Events pass through unchanged without
+    /// `with_node`.
+    pub fn stamp(&self, event: Event) -> Event {
+        let Some(node) = &self.node else {
+            return event;
+        };
+        let mut clock = self.clock.lock();
+        clock.tick(node);
+        event.with_clock(clock.clone())
+    }
+
+    /// Everything this node has seen, as a vector clock
+    pub fn clock(&self) -> VectorClock {
+        self.clock.lock().clone()
+    }
+
+    pub fn with_frame_size(mut self, frame_size: usize) -> Self {
+        self.frame_size = frame_size.max(1);
+        self
+    }
+
+    /// Draw transfer bandwidth from a limiter shared with other managers,
+    /// so concurrent syncs with several peers respect one global cap.
+    pub fn with_bandwidth_limiter(mut self, limiter: Arc<BandwidthLimiter>) -> Self {
+        self.bandwidth = Some(limiter);
+        self
+    }
+
+    /// Open a throttled session for one transfer. Call
+    /// `SessionThrottle::consume` before sending each frame. Returns `None`
+    /// when no limiter is configured.
+    pub fn bandwidth_session(&self, weight: u32) -> Option<SessionThrottle> {
+        self.bandwidth.as_ref().map(|limiter| limiter.register(weight))
+    }
+
+    /// Split a chunk into transfer frames. Chunks no larger than the frame
+    /// size produce a single frame; chunks needing more than `MAX_FRAMES`
+    /// are refused, as the receiver would reject them.
+    pub fn frame_chunk(&self, chunk: &ExportChunk) -> Result<Vec<ChunkFrame>, StoreError> {
+        let (chunk_hash, bytes) = chunk.to_bytes()?;
+        let total = bytes.len().div_ceil(self.frame_size).max(1);
+        if total > MAX_FRAMES as usize {
+            return Err(StoreError::Integrity(format!(
+                "Chunk {} needs {} frames, more than {}; raise the frame size",
+                chunk_hash.as_hex(),
+                total,
+                MAX_FRAMES
+            )));
+        }
+        let total = total as u32;
+
+        let frames = if bytes.is_empty() {
+            vec![Vec::new()]
+        } else {
+            bytes.chunks(self.frame_size).map(<[u8]>::to_vec).collect()
+        };
+
+        Ok(frames
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| ChunkFrame {
+                chunk_hash: chunk_hash.clone(),
+                index: index as u32,
+                total,
+                hash: ContentHash::of_bytes(&data),
+                data,
+            })
+            .collect())
+    }
+
+    /// Import a chunk once all of its frames have arrived.
+    pub fn import_frames(
+        &self,
+        assembler: FrameAssembler,
+    ) -> Result<(Vec<ThoughtNode>, Vec<Event>), StoreError> {
+        let chunk_hash = assembler.chunk_hash().clone();
+        let bytes = assembler.finish()?;
+        self.handle_chunk_put(&chunk_hash, &bytes)
+    }
+
     pub fn create_manifest(
         &self,
         nodes: &[ThoughtNode],
@@ -204,13 +512,325 @@ impl SyncManager {
     }
 
     pub fn import_chunk(&self, chunk: &ExportChunk) -> Result<(Vec<ThoughtNode>, Vec<Event>), StoreError> {
-        if !chunk.verify()? {
-            return Err(StoreError::Integrity("Chunk hash mismatch".into()));
+        let computed = chunk.computed_hash()?;
+        if computed != chunk.hash {
+            return Err(StoreError::HashMismatch {
+                expected: chunk.hash.as_hex(),
+                computed: computed.as_hex(),
+            });
         }
 
+        // A chunk is taken whole or not at all, so check every timestamp
+        // before witnessing any
         let nodes = chunk.nodes.clone();
-        let events = chunk.events.clone();
+        for timestamp in nodes.iter().map(|n| &n.created_at).chain(chunk.events.iter().map(|e| &e.timestamp)) {
+            Timestamp::check_drift(timestamp)?;
+        }
+        for node in &nodes {
+            Timestamp::observe(&node.created_at);
+        }
+
+        Ok((nodes, self.import_events(chunk.events.clone()).events))
+    }
+
+    /// Digests of the events this manager may share, for diffing event
+    /// stores during replication
+    pub fn event_digests(&self, events: &[Event]) -> Result<Vec<EventDigest>, StoreError> {
+        events
+            .iter()
+            .filter(|e| self.privacy_filter.allows(&e.privacy_level()))
+            .map(|e| {
+                Ok(EventDigest {
+                    id: e.id,
+                    hash: ContentHash::compute(e)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The events this manager may share with a peer
+    pub fn shareable_events(&self, events: Vec<Event>) -> Vec<Event> {
+        events
+            .into_iter()
+            .filter(|e| self.privacy_filter.allows(&e.privacy_level()))
+            .collect()
+    }
+
+    /// Take in events received from a peer: advance the local clocks past
+    /// them and return them in causal order.
+    ///
+    /// Events stamped more than `MAX_CLOCK_DRIFT_MS` ahead are set aside in
+    /// `quarantined` instead. Every event is checked before any is
+    /// witnessed, so a quarantined one never moves the local clocks.
+    pub fn import_events(&self, events: Vec<Event>) -> ImportedEvents {
+        let (accepted, quarantined): (Vec<Event>, Vec<Event>) = events
+            .into_iter()
+            .partition(|e| Timestamp::check_drift(&e.timestamp).is_ok());
+        {
+            let mut clock = self.clock.lock();
+            for event in &accepted {
+                Timestamp::observe(&event.timestamp);
+                if let Some(event_clock) = &event.clock {
+                    clock.merge(event_clock);
+                }
+            }
+        }
+
+        ImportedEvents {
+            events: causal_order(accepted),
+            quarantined,
+        }
+    }
+
+    /// Build a request for the `referenced` blobs missing from `store`, or
+    /// `None` if we already have them all
+    pub async fn blob_request(
+        &self,
+        store: &dyn BlobStore,
+        referenced: &[ContentHash],
+    ) -> Result<Option<BlobRequest>, StoreError> {
+        let mut hashes = Vec::new();
+        for hash in referenced {
+            if !store.contains(hash).await? && !hashes.contains(hash) {
+                hashes.push(hash.clone());
+            }
+        }
+        Ok((!hashes.is_empty()).then_some(BlobRequest { hashes }))
+    }
+
+    /// Answer a peer's `BlobRequest` from `store`. Blobs are addressed by
+    /// content, so only a peer that already knows a hash can ask for it.
+    pub async fn handle_blob_request(
+        &self,
+        store: &dyn BlobStore,
+        request: &BlobRequest,
+    ) -> Result<BlobResponse, StoreError> {
+        let mut blobs = Vec::new();
+        for hash in &request.hashes {
+            if let Some(bytes) = store.get(hash).await? {
+                blobs.push((hash.clone(), bytes));
+            }
+        }
+        Ok(BlobResponse { blobs })
+    }
+
+    /// Store the blobs a peer sent, checking each against its hash first.
+    /// Returns how many were stored; a blob whose bytes don't match its
+    /// hash fails the whole response before anything is written.
+    pub async fn accept_blobs(
+        &self,
+        store: &dyn BlobStore,
+        response: BlobResponse,
+    ) -> Result<usize, StoreError> {
+        for (hash, bytes) in &response.blobs {
+            let computed = ContentHash::of_bytes(bytes);
+            if computed != *hash {
+                return Err(StoreError::HashMismatch {
+                    expected: hash.as_hex(),
+                    computed: computed.as_hex(),
+                });
+            }
+        }
+        for (_, bytes) in &response.blobs {
+            store.put(bytes).await?;
+        }
+        Ok(response.blobs.len())
+    }
+
+    /// Accept a chunk pushed by a peer under a declared hash.
+    ///
+    /// The raw bytes are re-hashed before anything is decoded, so a peer
+    /// cannot get data cached under a hash it does not match. The decoded
+    /// chunk's own content hash is then checked as in `import_chunk`.
+    pub fn handle_chunk_put(
+        &self,
+        hash: &ContentHash,
+        data: &[u8],
+    ) -> Result<(Vec<ThoughtNode>, Vec<Event>), StoreError> {
+        let computed = ContentHash::of_bytes(data);
+        if &computed != hash {
+            return Err(StoreError::HashMismatch {
+                expected: hash.as_hex(),
+                computed: computed.as_hex(),
+            });
+        }
+
+        let chunk: ExportChunk =
+            serde_json::from_slice(data).map_err(|e| StoreError::Deserialization(e.to_string()))?;
+        self.import_chunk(&chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_chunk() -> ExportChunk {
+        let mut event = Event::new("test.event", "tester", serde_json::json!({"n": 1}));
+        event.privacy = PrivacyLevel::Shareable;
+        ExportChunk::new(0, Vec::new(), vec![event]).unwrap()
+    }
+
+    #[test]
+    fn test_import_orders_events_causally() {
+        let shareable = |kind: &str, source: &str| {
+            Event::new(kind, source, serde_json::json!({})).with_privacy(PrivacyLevel::Shareable)
+        };
+        let alice = SyncManager::default().with_node("alice");
+        let bob = SyncManager::default().with_node("bob");
+        let carol = SyncManager::default().with_node("carol");
+
+        // alice's event reaches bob before bob writes his; carol writes on
+        // her own
+        let mut first = alice.stamp(shareable("alice.note", "alice"));
+        let chunk = ExportChunk::new(0, Vec::new(), vec![first.clone()]).unwrap();
+        bob.import_chunk(&chunk).unwrap();
+        let mut reply = bob.stamp(shareable("bob.reply", "bob"));
+        let mut aside = carol.stamp(shareable("carol.aside", "carol"));
+
+        assert_eq!(first.happens_before(&reply), Some(true));
+        assert_eq!(reply.happens_before(&first), Some(false));
+        assert_eq!(aside.happens_before(&reply), None);
+        assert_eq!(aside.happens_before(&first), None);
+
+        // bob's clock ran behind, so timestamps alone would put the reply
+        // first; carol's concurrent event falls back to timestamp order
+        first.timestamp = Timestamp::from_millis(3_000);
+        reply.timestamp = Timestamp::from_millis(1_000);
+        aside.timestamp = Timestamp::from_millis(2_000);
+
+        let receiver = SyncManager::default().with_node("dave");
+        let chunk = ExportChunk::new(0, Vec::new(), vec![reply, aside, first]).unwrap();
+        let (_, events) = receiver.import_chunk(&chunk).unwrap();
+        let kinds: Vec<&str> = events.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds, ["carol.aside", "alice.note", "bob.reply"]);
+
+        let clock = receiver.clock();
+        assert_eq!((clock.get("alice"), clock.get("bob"), clock.get("carol")), (1, 1, 1));
+        let next = receiver.stamp(shareable("dave.ack", "dave"));
+        assert!(events.iter().all(|e| e.happens_before(&next) == Some(true)));
+    }
+
+    #[test]
+    fn test_causal_order_merges_unclocked_events_by_timestamp() {
+        let at = |kind: &str, millis: u64, ticks: &[&str]| {
+            let mut event = Event::new(kind, "test", serde_json::json!({}));
+            event.timestamp = Timestamp::from_millis(millis);
+            if !ticks.is_empty() {
+                let mut clock = VectorClock::new();
+                ticks.iter().for_each(|node| clock.tick(node));
+                event = event.with_clock(clock);
+            }
+            event
+        };
+        let events = vec![
+            at("cause", 5_000, &["a"]),
+            at("effect", 1_000, &["a", "a"]),
+            at("plain.early", 500, &[]),
+            at("plain.late", 9_000, &[]),
+        ];
+        let kinds: Vec<String> = causal_order(events).into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, ["plain.early", "cause", "effect", "plain.late"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_missing_blobs_from_peer() {
+        use crate::blob::MemoryBlobStore;
+
+        let manager = SyncManager::default();
+        let peer = MemoryBlobStore::new();
+        let local = MemoryBlobStore::new();
+        let shared = local.put(b"already here").await.unwrap();
+        let wanted = peer.put(&vec![3u8; 10_000]).await.unwrap();
+        let unknown = ContentHash::of_bytes(b"nobody has this");
+
+        let referenced = [shared.clone(), wanted.clone(), unknown.clone()];
+        let request = manager.blob_request(&local, &referenced).await.unwrap().unwrap();
+        assert_eq!(request.hashes, vec![wanted.clone(), unknown]);
+
+        let response = manager.handle_blob_request(&peer, &request).await.unwrap();
+        let mut tampered = response.clone();
+        tampered.blobs[0].1[0] ^= 0xff;
+        assert!(matches!(
+            manager.accept_blobs(&local, tampered).await,
+            Err(StoreError::HashMismatch { .. })
+        ));
+        assert!(!local.contains(&wanted).await.unwrap());
+
+        assert_eq!(manager.accept_blobs(&local, response).await.unwrap(), 1);
+        assert_eq!(local.resolve(&wanted.0, 10_000).await.unwrap(), vec![3u8; 10_000]);
+        assert!(manager.blob_request(&local, &referenced[..2]).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chunk_put_accepts_matching_hash() {
+        let manager = SyncManager::default();
+        let (hash, bytes) = sample_chunk().to_bytes().unwrap();
+
+        let (nodes, events) = manager.handle_chunk_put(&hash, &bytes).unwrap();
+        assert!(nodes.is_empty());
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_chunk_put_rejects_tampered_data() {
+        let manager = SyncManager::default();
+        let (hash, mut bytes) = sample_chunk().to_bytes().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        match manager.handle_chunk_put(&hash, &bytes) {
+            Err(StoreError::HashMismatch { expected, computed }) => {
+                assert_eq!(expected, hash.as_hex());
+                assert_eq!(computed, ContentHash::of_bytes(&bytes).as_hex());
+            }
+            other => panic!("expected HashMismatch, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_frame_transfer_resumes_after_interruption() {
+        let manager = SyncManager::default().with_frame_size(32);
+        let frames = manager.frame_chunk(&sample_chunk()).unwrap();
+        assert!(frames.len() > 3);
+
+        let mut assembler = FrameAssembler::new(frames[0].chunk_hash.clone(), frames[0].total).unwrap();
+
+        // Connection drops after two frames.
+        for frame in frames.iter().take(2).cloned() {
+            assembler.accept(frame).unwrap();
+        }
+        let progress = assembler.progress();
+        assert_eq!(progress.frames_received, 2);
+        assert_eq!(progress.resume_from, Some(2));
+
+        // Sender resumes from the first missing frame.
+        let resume = progress.resume_from.unwrap() as usize;
+        for frame in frames[resume..].iter().cloned() {
+            assembler.accept(frame).unwrap();
+        }
+        assert!(assembler.progress().is_complete());
+
+        let (_, events) = manager.import_frames(assembler).unwrap();
+        assert_eq!(events.len(), 1);
+    }
 
-        Ok((nodes, events))
+    #[test]
+    fn test_frame_with_bad_hash_is_rejected() {
+        let manager = SyncManager::default().with_frame_size(32);
+        let mut frames = manager.frame_chunk(&sample_chunk()).unwrap();
+        let mut assembler = FrameAssembler::new(frames[0].chunk_hash.clone(), frames[0].total).unwrap();
+
+        frames[1].data[0] ^= 0xff;
+        assert!(matches!(
+            assembler.accept(frames[1].clone()),
+            Err(StoreError::HashMismatch { .. })
+        ));
+        assert_eq!(assembler.progress().frames_received, 0);
+
+        // A peer cannot make the assembler allocate for an absurd count
+        let hash = frames[0].chunk_hash.clone();
+        assert!(matches!(FrameAssembler::new(hash.clone(), u32::MAX), Err(StoreError::Integrity(_))));
+        assert!(matches!(FrameAssembler::new(hash, 0), Err(StoreError::Integrity(_))));
     }
 }
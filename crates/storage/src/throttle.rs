@@ -0,0 +1,205 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A global bandwidth budget shared by every sync session on a node.
+///
+/// Each registered session gets a share of `max_bytes_per_sec` proportional
+/// to its weight, so the sum of all sessions never exceeds the cap no matter
+/// how many peers are syncing. When a session ends its share is handed back
+/// to the remaining ones.
+pub struct BandwidthLimiter {
+    max_bytes_per_sec: u64,
+    next_id: AtomicU64,
+    sessions: Mutex<HashMap<u64, SessionState>>,
+}
+
+struct SessionState {
+    weight: u32,
+    /// Bytes the session may still send; negative while it is in debt
+    tokens: f64,
+    last_refill: Instant,
+    bytes_sent: u64,
+}
+
+/// Current share of the budget held by one session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionAllocation {
+    pub session_id: u64,
+    pub weight: u32,
+    pub bytes_per_sec: u64,
+    pub bytes_sent: u64,
+}
+
+impl BandwidthLimiter {
+    pub fn new(max_bytes_per_sec: u64) -> Arc<Self> {
+        Arc::new(Self {
+            max_bytes_per_sec: max_bytes_per_sec.max(1),
+            next_id: AtomicU64::new(0),
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn max_bytes_per_sec(&self) -> u64 {
+        self.max_bytes_per_sec
+    }
+
+    /// Register a transfer. The session is removed when the handle drops.
+    pub fn register(self: &Arc<Self>, weight: u32) -> SessionThrottle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.sessions.lock().insert(
+            id,
+            SessionState {
+                weight: weight.max(1),
+                tokens: 0.0,
+                last_refill: Instant::now(),
+                bytes_sent: 0,
+            },
+        );
+        SessionThrottle {
+            id,
+            limiter: Arc::clone(self),
+        }
+    }
+
+    pub fn active_sessions(&self) -> usize {
+        self.sessions.lock().len()
+    }
+
+    /// Per-session share of the budget, for observability.
+    pub fn allocations(&self) -> Vec<SessionAllocation> {
+        let sessions = self.sessions.lock();
+        let total_weight = total_weight(&sessions);
+        let mut allocations: Vec<_> = sessions
+            .iter()
+            .map(|(id, s)| SessionAllocation {
+                session_id: *id,
+                weight: s.weight,
+                bytes_per_sec: self.rate_for(s.weight, total_weight) as u64,
+                bytes_sent: s.bytes_sent,
+            })
+            .collect();
+        allocations.sort_by_key(|a| a.session_id);
+        allocations
+    }
+
+    /// Charge `bytes` to a session and return how long it must wait before
+    /// sending them.
+    fn reserve(&self, id: u64, bytes: usize) -> Duration {
+        let mut sessions = self.sessions.lock();
+        let total_weight = total_weight(&sessions);
+        let Some(session) = sessions.get_mut(&id) else {
+            return Duration::ZERO;
+        };
+
+        let rate = self.rate_for(session.weight, total_weight);
+        let now = Instant::now();
+        let elapsed = now.duration_since(session.last_refill).as_secs_f64();
+        // Allow at most one second of burst.
+        session.tokens = (session.tokens + elapsed * rate).min(rate);
+        session.last_refill = now;
+
+        session.tokens -= bytes as f64;
+        session.bytes_sent += bytes as u64;
+
+        if session.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-session.tokens / rate)
+        }
+    }
+
+    fn rate_for(&self, weight: u32, total_weight: u64) -> f64 {
+        self.max_bytes_per_sec as f64 * weight as f64 / total_weight.max(1) as f64
+    }
+
+    fn unregister(&self, id: u64) {
+        self.sessions.lock().remove(&id);
+    }
+}
+
+fn total_weight(sessions: &HashMap<u64, SessionState>) -> u64 {
+    sessions.values().map(|s| s.weight as u64).sum()
+}
+
+/// One transfer's handle on a shared `BandwidthLimiter`.
+pub struct SessionThrottle {
+    id: u64,
+    limiter: Arc<BandwidthLimiter>,
+}
+
+impl SessionThrottle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Wait until `bytes` may be sent under this session's share.
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self.limiter.reserve(self.id, bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Like `consume`, but return the required delay instead of sleeping.
+    pub fn reserve(&self, bytes: usize) -> Duration {
+        self.limiter.reserve(self.id, bytes)
+    }
+}
+
+impl Drop for SessionThrottle {
+    fn drop(&mut self) {
+        self.limiter.unregister(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_split_by_weight() {
+        let limiter = BandwidthLimiter::new(1000);
+        let a = limiter.register(1);
+        let b = limiter.register(3);
+
+        let allocations = limiter.allocations();
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(allocations[0].session_id, a.id());
+        assert_eq!(allocations[0].bytes_per_sec, 250);
+        assert_eq!(allocations[1].session_id, b.id());
+        assert_eq!(allocations[1].bytes_per_sec, 750);
+
+        let total: u64 = allocations.iter().map(|a| a.bytes_per_sec).sum();
+        assert!(total <= limiter.max_bytes_per_sec());
+    }
+
+    #[test]
+    fn test_share_returned_when_session_ends() {
+        let limiter = BandwidthLimiter::new(1000);
+        let a = limiter.register(1);
+        let b = limiter.register(1);
+        assert_eq!(limiter.allocations()[0].bytes_per_sec, 500);
+
+        drop(b);
+        assert_eq!(limiter.active_sessions(), 1);
+        assert_eq!(limiter.allocations()[0].bytes_per_sec, 1000);
+        drop(a);
+        assert_eq!(limiter.active_sessions(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_sessions_wait_for_their_share() {
+        let limiter = BandwidthLimiter::new(1000);
+        let a = limiter.register(1);
+        let _b = limiter.register(1);
+
+        // 500 B/s each: sending 1000 bytes at once costs about two seconds.
+        let wait = a.reserve(1000);
+        assert!(wait > Duration::from_millis(1900));
+        assert!(wait <= Duration::from_secs(2));
+    }
+}
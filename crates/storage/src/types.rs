@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
+use crate::error::StoreError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EventId(pub Uuid);
 
@@ -31,16 +36,154 @@ impl Default for NodeId {
     }
 }
 
+/// Last logical time handed out by `Timestamp::now` in this process
+static LOGICAL_CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// Furthest ahead of the local wall clock a witnessed timestamp may be
+pub const MAX_CLOCK_DRIFT_MS: u64 = 60_000;
+
+/// When something happened, as two clocks.
+///
+/// `wall` is milliseconds since the Unix epoch from the system clock. Use it
+/// for display, TTLs and time-range queries, but not for ordering: NTP or a
+/// manual clock change can move it backwards or forwards.
+///
+/// `logical` is a hybrid logical clock. It starts at the wall time but never
+/// repeats or goes back: each `now()` returns at least one more than the
+/// previous call in this process. It is therefore a stable creation order
+/// for events made on this node, even across wall-clock jumps. Because it is
+/// seeded from the wall clock, the order also survives restarts, unless the
+/// clock was set back while the node was down. `witness` advances it past
+/// timestamps received from peers, so events made after a sync order after
+/// what was synced, but refuses ones more than `MAX_CLOCK_DRIFT_MS` ahead of
+/// the local wall clock. Compare it with `logical_cmp`.
+///
+/// The derived `Ord` compares `wall` first, then `logical`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Timestamp(pub u64);
+pub struct Timestamp {
+    pub wall: u64,
+    pub logical: u64,
+}
 
 impl Timestamp {
     pub fn now() -> Self {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let duration = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
+        let wall = wall_millis();
+        let previous = LOGICAL_CLOCK
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(last.saturating_add(1).max(wall)))
             .unwrap_or_default();
-        Self(duration.as_millis() as u64)
+        Self {
+            wall,
+            logical: previous.saturating_add(1).max(wall),
+        }
+    }
+
+    /// A timestamp at `millis` wall time, for queries and fixtures; it does
+    /// not touch the logical clock
+    pub fn from_millis(millis: u64) -> Self {
+        Self {
+            wall: millis,
+            logical: millis,
+        }
+    }
+
+    /// Order by the logical clock, falling back to wall time on a tie
+    pub fn logical_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.logical
+            .cmp(&other.logical)
+            .then(self.wall.cmp(&other.wall))
+    }
+
+    /// Record a timestamp from elsewhere so later `now()` calls order after
+    /// it. A peer with a clock far in the future would otherwise drag every
+    /// later local timestamp there with it, so those are rejected.
+    pub fn witness(remote: &Timestamp) -> Result<(), StoreError> {
+        Self::check_drift(remote)?;
+        Self::observe(remote);
+        Ok(())
+    }
+
+    /// The drift check of `witness`, without advancing the clock
+    pub fn check_drift(remote: &Timestamp) -> Result<(), StoreError> {
+        let local = wall_millis();
+        if remote.logical > local.saturating_add(MAX_CLOCK_DRIFT_MS) {
+            return Err(StoreError::ClockDrift {
+                remote: remote.logical,
+                local,
+            });
+        }
+        Ok(())
+    }
+
+    /// Advance the clock past a timestamp that passed `check_drift`
+    pub(crate) fn observe(remote: &Timestamp) {
+        LOGICAL_CLOCK.fetch_max(remote.logical, Ordering::SeqCst);
+    }
+}
+
+fn wall_millis() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Per-node event counters for causal ordering across nodes.
+///
+/// A node ticks its own entry when it creates an event and merges the
+/// clocks of events it receives, so `a < b` exactly when `a`'s event could
+/// have influenced `b`'s. Clocks where neither is `<=` the other belong to
+/// concurrent events and `partial_cmp` returns `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorClock {
+    counters: BTreeMap<String, u64>,
+}
+
+impl VectorClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, node: &str) -> u64 {
+        self.counters.get(node).copied().unwrap_or(0)
+    }
+
+    /// Sum of every node's counter; strictly larger for any later clock
+    pub fn total(&self) -> u64 {
+        self.counters.values().sum()
+    }
+
+    /// Count a new event on `node`
+    pub fn tick(&mut self, node: &str) {
+        *self.counters.entry(node.to_string()).or_default() += 1;
+    }
+
+    /// Take the element-wise maximum with `other`
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (node, count) in &other.counters {
+            let entry = self.counters.entry(node.clone()).or_default();
+            *entry = (*entry).max(*count);
+        }
+    }
+}
+
+impl PartialOrd for VectorClock {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        let nodes = self.counters.keys().chain(other.counters.keys());
+        let (mut less, mut greater) = (false, false);
+        for node in nodes {
+            match self.get(node).cmp(&other.get(node)) {
+                CmpOrdering::Less => less = true,
+                CmpOrdering::Greater => greater = true,
+                CmpOrdering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(CmpOrdering::Equal),
+            (true, false) => Some(CmpOrdering::Less),
+            (false, true) => Some(CmpOrdering::Greater),
+            (true, true) => None,
+        }
     }
 }
 
@@ -52,6 +195,9 @@ pub struct Event {
     pub timestamp: Timestamp,
     pub payload: serde_json::Value,
     pub privacy: PrivacyLevel,
+    /// Causal history, when the creating node tracks one
+    #[serde(default)]
+    pub clock: Option<VectorClock>,
 }
 
 impl Event {
@@ -63,6 +209,28 @@ impl Event {
             timestamp: Timestamp::now(),
             payload,
             privacy: PrivacyLevel::Private,
+            clock: None,
+        }
+    }
+
+    pub fn with_privacy(mut self, privacy: PrivacyLevel) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: VectorClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// `Some(true)` if this event causally precedes `other`, `Some(false)`
+    /// if `other` precedes this one, `None` if they are concurrent or
+    /// either lacks a vector clock
+    pub fn happens_before(&self, other: &Event) -> Option<bool> {
+        match self.clock.as_ref()?.partial_cmp(other.clock.as_ref()?)? {
+            CmpOrdering::Less => Some(true),
+            CmpOrdering::Greater => Some(false),
+            CmpOrdering::Equal => None,
         }
     }
 }
@@ -94,7 +262,11 @@ impl Tag {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+/// How widely a piece of data may be shared.
+///
+/// Variants are ordered from most restrictive to most open, so
+/// `PrivacyLevel::Private < PrivacyLevel::Public`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
 pub enum PrivacyLevel {
     #[default]
     Private,
@@ -111,3 +283,31 @@ impl PrivacyLevel {
         matches!(self, PrivacyLevel::Public)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logical_order_survives_clock_jump() {
+        let first = Timestamp::now();
+        let mut second = Timestamp::now();
+        assert_eq!(second.logical_cmp(&first), std::cmp::Ordering::Greater);
+
+        // As if the wall clock was set back an hour between the two events
+        second.wall = first.wall - 3_600_000;
+        assert!(second < first);
+        assert_eq!(second.logical_cmp(&first), std::cmp::Ordering::Greater);
+
+        let remote = Timestamp::from_millis(Timestamp::now().logical + 1_000);
+        Timestamp::witness(&remote).unwrap();
+        assert_eq!(Timestamp::now().logical_cmp(&remote), std::cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn test_witness_rejects_timestamps_beyond_drift() {
+        let far = Timestamp::from_millis(wall_millis() + MAX_CLOCK_DRIFT_MS + 3_600_000);
+        assert!(matches!(Timestamp::witness(&far), Err(StoreError::ClockDrift { .. })));
+        assert!(Timestamp::now().logical < far.logical);
+    }
+}
@@ -15,7 +15,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tracing::info;
 
-use cortex_grid::PeerStore;
+use cortex_grid::{PeerAddress, PeerStore};
 
 /// Distributed task - truly parallel processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -161,15 +161,15 @@ pub async fn execute_distributed(
         let peer = &peers[i % num_nodes];
         
         if let Some(addr) = peer.addresses.first() {
-            let addr_str = addr.to_string();
-            if let Some(task_addr) = get_task_address(&addr_str) {
+            // The task server listens 1000 above the discovery port
+            if let Some(task_addr) = PeerAddress::from(*addr).offset_port(1000).map(|a| a.to_string()) {
                 let task_id = format!("{}-part{}", task_id, i);
                 let part_name = part_name.to_string();
                 let sub_question = sub_question.clone();
                 let from_node = from_node.to_string();
                 let peer_id = peer.node_id.to_string();
                 
-                info!("📤 Part '{}' → Node {} ({})", part_name, &peer_id[..8], task_addr);
+                info!("📤 Part '{}' → Node {} ({})", part_name, peer.node_id.short(), task_addr);
                 
                 let handle = tokio::spawn(async move {
                     let start = std::time::Instant::now();
@@ -285,13 +285,3 @@ async fn send_part(
         Err(response.error.unwrap_or_else(|| "Unknown error".to_string()))
     }
 }
-
-fn get_task_address(addr: &str) -> Option<String> {
-    if let Some((ip, port_str)) = addr.rsplit_once(':') {
-        if let Ok(port) = port_str.parse::<u16>() {
-            return Some(format!("{}:{}", ip, port + 1000));
-        }
-    }
-    None
-}
-
@@ -2,15 +2,25 @@
 //! 
 //! Tracks all node communications and exposes them via API
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::Serialize;
 use chrono::{DateTime, Utc};
+use cortex_grid::NodeId;
 
 /// Maximum number of logs to keep in memory
 const MAX_LOGS: usize = 500;
 
+/// Label for a node or task id in log messages: `NodeId::short` for a full
+/// node id, otherwise at most the first 8 characters
+pub fn short(id: &str) -> String {
+    match id.parse::<NodeId>() {
+        Ok(node_id) => node_id.short(),
+        Err(_) => id.chars().take(8).collect(),
+    }
+}
+
 /// Type of log entry
 #[derive(Debug, Clone, Serialize)]
 pub enum LogType {
@@ -42,16 +52,25 @@ pub struct LogEntry {
 /// Global log store
 pub struct LogStore {
     logs: RwLock<VecDeque<LogEntry>>,
+    /// Entries ever recorded per type, unaffected by eviction or `clear`
+    totals: RwLock<HashMap<String, u64>>,
 }
 
 impl LogStore {
     pub fn new() -> Self {
         Self {
             logs: RwLock::new(VecDeque::with_capacity(MAX_LOGS)),
+            totals: RwLock::new(HashMap::new()),
         }
     }
 
     pub async fn add(&self, entry: LogEntry) {
+        *self
+            .totals
+            .write()
+            .await
+            .entry(format!("{:?}", entry.log_type))
+            .or_insert(0) += 1;
         let mut logs = self.logs.write().await;
         if logs.len() >= MAX_LOGS {
             logs.pop_front();
@@ -59,6 +78,11 @@ impl LogStore {
         logs.push_back(entry);
     }
 
+    /// Number of entries ever recorded, by log type
+    pub async fn totals(&self) -> HashMap<String, u64> {
+        self.totals.read().await.clone()
+    }
+
     pub async fn get_recent(&self, count: usize) -> Vec<LogEntry> {
         let logs = self.logs.read().await;
         logs.iter()
@@ -90,7 +114,7 @@ impl LogStore {
             log_type: LogType::Discovery,
             source: source.to_string(),
             target: Some(peer_id.to_string()),
-            message: format!("Discovered peer {} at {:?}", &peer_id[..8], addresses),
+            message: format!("Discovered peer {} at {:?}", short(peer_id), addresses),
             details: Some(serde_json::json!({
                 "peer_id": peer_id,
                 "addresses": addresses,
@@ -107,7 +131,7 @@ impl LogStore {
             source: from.to_string(),
             target: Some(to.to_string()),
             message: format!("Task {} sent to {} (skill: {}, {} bytes)", 
-                &task_id[..8], &to[..8.min(to.len())], skill, payload_len),
+                short(task_id), short(to), skill, payload_len),
             details: Some(serde_json::json!({
                 "task_id": task_id,
                 "skill": skill,
@@ -124,7 +148,7 @@ impl LogStore {
             log_type: LogType::TaskReceived,
             source: node.to_string(),
             target: Some(from.to_string()),
-            message: format!("Task {} received from {}", &task_id[..8], &from[..8.min(from.len())]),
+            message: format!("Task {} received from {}", short(task_id), short(from)),
             details: Some(serde_json::json!({
                 "task_id": task_id,
                 "from_node": from,
@@ -141,7 +165,7 @@ impl LogStore {
             source: node.to_string(),
             target: None,
             message: format!("Task {} completed in {}ms ({} bytes result)", 
-                &task_id[..8], duration_ms, result_len),
+                short(task_id), duration_ms, result_len),
             details: Some(serde_json::json!({
                 "task_id": task_id,
                 "result_bytes": result_len,
@@ -157,7 +181,7 @@ impl LogStore {
             log_type: LogType::TaskFailed,
             source: node.to_string(),
             target: None,
-            message: format!("Task {} FAILED: {}", &task_id[..8], error),
+            message: format!("Task {} FAILED: {}", short(task_id), error),
             details: Some(serde_json::json!({
                 "task_id": task_id,
                 "error": error,
@@ -206,7 +230,7 @@ impl LogStore {
             log_type: LogType::NetworkError,
             source: source.to_string(),
             target: Some(target.to_string()),
-            message: format!("Network error to {}: {}", &target[..8.min(target.len())], error),
+            message: format!("Network error to {}: {}", short(target), error),
             details: Some(serde_json::json!({
                 "error": error,
             })),
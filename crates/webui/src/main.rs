@@ -1,6 +1,7 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use clap::Parser;
 use axum::{
     extract::State,
     http::StatusCode,
@@ -11,21 +12,47 @@ use axum::{
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
 
-use cortex_grid::{NodeId, PeerStore, PeerInfo, Capabilities, GridOrchestrator, LanDiscovery, KademliaDiscovery, Discovery};
+use cortex_grid::{NodeId, PeerStore, PeerInfo, Capabilities, GridOrchestrator, LanDiscovery, KademliaDiscovery, Discovery, PipelineCoordinator};
 use cortex_skill::NetworkSkillRegistry;
 use cortex_reputation::TrustGraph;
+use cortex_core::logging::{self, LogFormat};
 use cortex_core::runtime::EventBus;
+use cortex_inference::{DistributedConfig, DistributedExecutor};
 
 mod api;
 mod dashboard;
 mod distributed;
 mod logs;
+mod metrics;
 mod swarm;
 
 pub use logs::LOGS;
 
 use api::*;
 
+#[derive(Parser)]
+#[command(name = "cortex-webui")]
+#[command(about = "Web-based management interface for CortexOS nodes")]
+struct Args {
+    /// Log output format: text or json
+    #[arg(long, env = "CORTEX_LOG_FORMAT", default_value = "text")]
+    log_format: LogFormat,
+
+    /// Model to size pipelines for: a GGUF file or a directory with a
+    /// config.json. Without one, pipelines assume a default layer count.
+    #[arg(long, env = "CORTEX_MODEL")]
+    model: Option<std::path::PathBuf>,
+
+    /// Where this node's tensor server listens when it heads a tensor
+    /// pipeline
+    #[arg(long, env = "CORTEX_TENSOR_ADDR", default_value = "127.0.0.1:9000")]
+    tensor_addr: String,
+
+    /// Tensor port of the compute peers in a tensor pipeline
+    #[arg(long, env = "CORTEX_PEER_TENSOR_PORT", default_value = "9000")]
+    peer_tensor_port: u16,
+}
+
 #[derive(Clone)]
 struct AppState {
     node_id: NodeId,
@@ -34,13 +61,26 @@ struct AppState {
     trust_graph: Arc<RwLock<TrustGraph>>,
     event_bus: Arc<EventBus>,
     orchestrator: Option<Arc<RwLock<GridOrchestrator>>>,
+    /// Pipeline over the compute peers, resharded as they come and go
+    pipeline: Arc<PipelineCoordinator>,
+    /// Model passed with `--model`
+    model_path: Option<std::path::PathBuf>,
+    /// Transformer layers in that model
+    model_layers: Option<u32>,
+    /// Executor for `/api/tasks/tensor`, built once for `--model`. Its
+    /// shard is only reloaded when the local layer range changes.
+    tensor_executor: Option<Arc<DistributedExecutor>>,
+    /// `--tensor-addr`, this node's place in tensor pipelines
+    tensor_addr: String,
+    /// `--peer-tensor-port`
+    peer_tensor_port: u16,
+    started_at: Instant,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    let args = Args::parse();
+    logging::init(args.log_format, tracing::Level::INFO, true);
 
     // Initialize node components
     let node_id = NodeId::random();
@@ -50,6 +90,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let trust_graph = Arc::new(RwLock::new(TrustGraph::new(node_id)));
     let event_bus = Arc::new(EventBus::default());
 
+    let model_layers = args.model.as_ref().and_then(|path| {
+        match cortex_inference::model_layer_count(path) {
+            Ok(layers) => {
+                tracing::info!("🧠 Model {} has {} layers", path.display(), layers);
+                Some(layers)
+            }
+            Err(e) => {
+                tracing::warn!("Could not read layer count from {}: {}", path.display(), e);
+                None
+            }
+        }
+    });
+
     // Create orchestrator
     let mut orchestrator = GridOrchestrator::new(
         node_id,
@@ -104,6 +157,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
+    let tensor_executor = args.model.as_ref().map(|path| {
+        let total_layers = model_layers.unwrap_or(FALLBACK_TENSOR_LAYERS);
+        Arc::new(DistributedExecutor::new(DistributedConfig {
+            node_id: node_id.to_string(),
+            listen_addr: args.tensor_addr.clone(),
+            model_name: path.display().to_string(),
+            total_layers,
+            layers_per_node: total_layers,
+        }))
+    });
+
+    let pipeline = Arc::new(
+        PipelineCoordinator::new(node_id, Arc::clone(&peer_store), pipeline_config(model_layers))
+            .with_event_bus(Arc::clone(&event_bus)),
+    );
+    Arc::clone(&pipeline).rebuild_on_change();
+
     let app_state = AppState {
         node_id,
         peer_store,
@@ -111,6 +181,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         trust_graph,
         event_bus,
         orchestrator: Some(orchestrator),
+        pipeline,
+        model_path: args.model.clone(),
+        model_layers,
+        tensor_executor,
+        tensor_addr: args.tensor_addr.clone(),
+        peer_tensor_port: args.peer_tensor_port,
+        started_at: Instant::now(),
     };
 
     // Build router
@@ -134,6 +211,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/tasks/tensor", post(distributed_tensor_inference))
         .route("/api/pipeline/status", get(pipeline_status))
         .route("/api/stats", get(get_stats))
+        .route("/metrics", get(metrics::metrics))
         // Static files are embedded in the binary
         .layer(CorsLayer::permissive())
         .with_state(app_state);
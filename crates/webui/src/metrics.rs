@@ -0,0 +1,69 @@
+//! Prometheus metrics for the web UI node
+//!
+//! Served on `GET /metrics` in the text exposition format.
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use cortex_core::metrics::{MetricType, PrometheusEncoder, CONTENT_TYPE};
+
+use crate::api::pending_tasks;
+use crate::AppState;
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], render(&state).await)
+}
+
+/// Collect the current metrics from the node state
+pub async fn render(state: &AppState) -> String {
+    let peers = state.peer_store.list_active().await;
+    let compute_peers = peers.iter().filter(|p| p.capabilities.can_compute).count();
+    let relay_peers = peers.iter().filter(|p| p.capabilities.can_relay).count();
+    let skills = state.skill_registry.read().await.skill_distribution().len();
+    let log_totals = crate::LOGS.totals().await;
+
+    let mut out = PrometheusEncoder::new();
+    out.single(
+        "cortex_uptime_seconds",
+        "Seconds since the node started",
+        MetricType::Gauge,
+        state.started_at.elapsed().as_secs() as f64,
+    );
+
+    out.family("cortex_peers", "Active peers by capability", MetricType::Gauge)
+        .sample("cortex_peers", &[("capability", "any")], peers.len() as f64)
+        .sample("cortex_peers", &[("capability", "compute")], compute_peers as f64)
+        .sample("cortex_peers", &[("capability", "relay")], relay_peers as f64);
+
+    out.family(
+        "cortex_peer_latency_ms",
+        "Last measured round-trip latency per peer",
+        MetricType::Gauge,
+    );
+    for peer in &peers {
+        if let Some(latency) = peer.latency_ms {
+            let peer_id = peer.node_id.to_string();
+            out.sample("cortex_peer_latency_ms", &[("peer", &peer_id)], latency as f64);
+        }
+    }
+
+    out.single("cortex_skills", "Skills known in the network", MetricType::Gauge, skills as f64);
+    out.single(
+        "cortex_pending_tasks",
+        "Delegated tasks awaiting a result",
+        MetricType::Gauge,
+        pending_tasks(state).await as f64,
+    );
+
+    out.family(
+        "cortex_log_entries_total",
+        "Communication log entries recorded, by type",
+        MetricType::Counter,
+    );
+    let mut log_totals: Vec<_> = log_totals.into_iter().collect();
+    log_totals.sort();
+    for (kind, count) in &log_totals {
+        out.sample("cortex_log_entries_total", &[("type", kind)], *count as f64);
+    }
+
+    out.event_bus("cortex", &state.event_bus.metrics().snapshot());
+    out.finish()
+}
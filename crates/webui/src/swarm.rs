@@ -9,7 +9,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tracing::{info, warn};
 
-use cortex_grid::PeerStore;
+use cortex_grid::{PeerAddress, PeerStore};
 
 /// Swarm task request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,8 +152,8 @@ pub async fn execute_swarm_task(
     
     for (i, peer) in peers.iter().enumerate() {
         if let Some(addr) = peer.addresses.first() {
-            let addr_str = addr.to_string();
-            if let Some(task_addr) = get_task_address(&addr_str) {
+            // The task server listens 1000 above the discovery port
+            if let Some(task_addr) = PeerAddress::from(*addr).offset_port(1000).map(|a| a.to_string()) {
                 let task_id = format!("{}-{}", task_id, i);
                 let payload = payload.to_string();
                 let skill = skill.to_string();
@@ -281,39 +281,3 @@ async fn send_subtask(
         success: response.success,
     })
 }
-
-/// Extract task port address from peer address
-fn get_task_address(addr: &str) -> Option<String> {
-    // Handle different address formats
-    if let Some((ip, port_str)) = addr.rsplit_once(':') {
-        if let Ok(port) = port_str.parse::<u16>() {
-            let task_port = port + 1000;
-            return Some(format!("{}:{}", ip, task_port));
-        }
-    }
-    
-    // Try multiaddr format
-    let parts: Vec<&str> = addr.split('/').collect();
-    let mut ip = None;
-    let mut port = None;
-    
-    for (i, part) in parts.iter().enumerate() {
-        if *part == "ip4" || *part == "ip6" {
-            if i + 1 < parts.len() {
-                ip = Some(parts[i + 1]);
-            }
-        }
-        if *part == "tcp" || *part == "udp" {
-            if i + 1 < parts.len() {
-                port = parts[i + 1].parse::<u16>().ok();
-            }
-        }
-    }
-    
-    if let (Some(ip), Some(port)) = (ip, port) {
-        return Some(format!("{}:{}", ip, port + 1000));
-    }
-    
-    None
-}
-
@@ -133,7 +133,7 @@ async fn main() {
     info!("   Demonstrating event-driven agent communication");
     info!("");
 
-    let runtime = Runtime::new();
+    let runtime = Arc::new(Runtime::new().with_shutdown_grace(Duration::from_secs(2)));
     let event_bus = runtime.event_bus();
 
     let heartbeat1 = HeartbeatAgent::new("heart-1", 1000, Arc::clone(&event_bus));
@@ -148,9 +148,10 @@ async fn main() {
 
     info!("Agents spawned. Press Ctrl+C to stop.\n");
 
+    let forwarder = Arc::clone(&runtime);
     tokio::spawn(async move {
         while let Some(event) = subscription.recv().await {
-            if let Some(agent) = runtime.get_agent("listener-1") {
+            if let Some(agent) = forwarder.get_agent("listener-1") {
                 let _ = agent.send(event).await;
             }
         }
@@ -158,4 +159,13 @@ async fn main() {
 
     tokio::signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
     info!("\n🛑 Shutting down...");
+
+    match runtime.shutdown().await {
+        Ok(report) => info!(
+            "Stopped {} agent(s) cleanly, aborted {:?}",
+            report.clean.len(),
+            report.aborted
+        ),
+        Err(e) => info!("Shutdown error: {}", e),
+    }
 }
@@ -26,10 +26,25 @@ char* cortex_start_logger_agent(const char* name);
 // Returns JSON with agent info (must free with cortex_free_string)
 char* cortex_start_inference_agent(const char* name);
 
+// Start an Offline Agent running a local GGUF model (tokenizer.json beside it)
+// Returns JSON with agent info (must free with cortex_free_string)
+char* cortex_start_local_gguf_agent(const char* name, const char* model_path);
+
+// Same as above with GenerationParams JSON (max_tokens, temperature, top_p, seed, ...)
+// Pass NULL for the defaults
+char* cortex_start_local_gguf_agent_with_params(const char* name, const char* model_path, const char* params_json);
+
 // Start a Remote Inference Agent (Ollama/HTTP)
 // Returns JSON with agent info (must free with cortex_free_string)
 char* cortex_start_remote_inference_agent(const char* name, const char* url, const char* model);
 
+// Start an agent for an OpenAI-compatible server (LM Studio, vLLM, hosted APIs).
+// api_key may be NULL or empty when the server needs no authentication.
+char* cortex_start_openai_inference_agent(const char* name, const char* url, const char* model, const char* api_key);
+
+// Forget an inference agent's conversation history (multi-turn context)
+bool cortex_clear_agent_context(const char* agent_id);
+
 // Get number of running agents
 int cortex_agent_count(void);
 
@@ -55,6 +70,13 @@ bool cortex_remove_agent(const char* agent_id);
 // Returns JSONL string (must free with cortex_free_string)
 char* cortex_export_dataset(const char* agent_id);
 
+// Export history at or above a privacy level (0 = private, 1 = shareable, 2 = public)
+// Non-public entries have emails and long numbers redacted (must free with cortex_free_string)
+char* cortex_export_dataset_filtered(const char* agent_id, int min_privacy_level);
+
+// Set the privacy level tagged on an agent's future history entries
+bool cortex_set_agent_privacy(const char* agent_id, int privacy_level);
+
 // ============ Discovery API ============
 
 // Start continuous multi-protocol discovery (auto-called by cortex_init)
@@ -1,9 +1,11 @@
 use cortex_core::NodeId;
 use cortex_signal::{
-    Channel, Codebook, ConsoleEmitter, Emitter, MultiHopMessage, MultiHopRouter, Pulse, Route,
-    RouteHop, Signal, SignalPattern, StandardSymbol,
+    Channel, Codebook, CommunicationOutcome, ConsoleEmitter, Emitter, LearningConfig,
+    LearningStrategy, LearningSystem, MultiHopMessage, MultiHopRouter, Pulse, Route, RouteHop,
+    Signal, SignalPattern, StandardSymbol,
 };
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{info, Level};
 
 #[tokio::main]
@@ -90,6 +92,7 @@ async fn main() {
 
     // Simulate routing the message through the network
     info!("\n--- Simulating Multi-Hop Routing ---");
+    let mut hops_taken = Vec::new();
 
     // At Node A
     info!("\n[Node A] Processing message");
@@ -98,6 +101,7 @@ async fn main() {
         message.forward(node_a).unwrap();
         info!("[Node A] Forwarding to Node B via {:?}", hop.channel);
         info!("[Node A] Updated hop count: {}, TTL: {}", message.hop_count, message.ttl);
+        hops_taken.push(hop);
     }
 
     // At Node B
@@ -107,6 +111,7 @@ async fn main() {
         message.forward(node_b).unwrap();
         info!("[Node B] Forwarding to Node C via {:?}", hop.channel);
         info!("[Node B] Updated hop count: {}, TTL: {}", message.hop_count, message.ttl);
+        hops_taken.push(hop);
     }
 
     // At Node C
@@ -116,6 +121,7 @@ async fn main() {
         message.forward(node_c).unwrap();
         info!("[Node C] Forwarding to Node D via {:?}", hop.channel);
         info!("[Node C] Updated hop count: {}, TTL: {}", message.hop_count, message.ttl);
+        hops_taken.push(hop);
     }
 
     // At Node D (destination)
@@ -152,6 +158,56 @@ async fn main() {
     info!("\nEmitting on Audio (Node D):");
     audio_emitter.emit_signal(&audio_signal, &codebook).await.unwrap();
 
+    // Learn which pattern each medium of the routed message's hops prefers
+    info!("\n--- Per-Channel Pattern Learning ---");
+    let learning = LearningSystem::new(LearningConfig {
+        strategy: LearningStrategy::Recognition,
+        auto_evolve: false,
+        ..LearningConfig::default()
+    });
+    learning.initialize_symbol(symbol).await.unwrap();
+
+    let short_flashes = SignalPattern::new(vec![Pulse::on(50), Pulse::off(50), Pulse::on(50)]);
+    let long_pulses = test_signal.pattern.clone();
+    for pattern in [&short_flashes, &long_pulses] {
+        learning.learn_pattern(symbol, pattern.clone()).await.unwrap();
+    }
+
+    // Send both patterns over every hop the message took and learn from how
+    // each emission went
+    let emitters: [&dyn Emitter; 3] = [&ble_emitter, &light_emitter, &audio_emitter];
+    for hop in &hops_taken {
+        let Some(emitter) = emitters.iter().find(|e| e.channel() == hop.channel) else {
+            continue;
+        };
+        for pattern in [&short_flashes, &long_pulses] {
+            let started = Instant::now();
+            let outcome = match emitter.emit(pattern).await {
+                Ok(()) => CommunicationOutcome::success(symbol, pattern.clone()),
+                Err(e) => {
+                    info!("{:?} hop failed: {}", hop.channel, e);
+                    CommunicationOutcome::failure(symbol, pattern.clone())
+                }
+            };
+            let outcome = outcome
+                .with_latency(started.elapsed().as_micros() as u32)
+                .with_channel(hop.channel.clone());
+            learning.record_outcome(outcome).await.unwrap();
+        }
+    }
+
+    let stats = learning.get_stats(symbol).await;
+    for channel in hops_taken.iter().map(|hop| &hop.channel) {
+        let best = learning.get_best_pattern_for_channel(symbol, channel).await.unwrap();
+        info!(
+            "{:?}: success rate {:.0}%, best pattern {} pulses ({}µs)",
+            channel,
+            stats.channel(channel).success_rate() * 100.0,
+            best.pulses.len(),
+            best.total_duration_us()
+        );
+    }
+
     // Display route statistics
     info!("\n--- Route Statistics ---");
     info!("Total latency for A->D route: {:?}µs", route_a_to_d.total_latency_us());
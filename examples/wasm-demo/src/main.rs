@@ -141,6 +141,18 @@ fn main() {
     println!("  Has TCP network capability: {}", agent.capabilities().check_network("any", true));
     println!("  Has Microphone sensor: {}", agent.capabilities().check_sensor(&SensorType::Microphone));
     
+    // Test 10: cortex-lang scripts through the C ABI a browser host uses
+    println!("\n✓ Testing cortex-lang eval...");
+    let script = "let xs = [3, 1, 2]; let n = len(xs); n * 10;";
+    let json = unsafe {
+        let out = cortex_lang::wasm::lang_eval(script.as_ptr(), script.len());
+        let json = std::ffi::CStr::from_ptr(out).to_string_lossy().into_owned();
+        cortex_lang::wasm::lang_free_result(out);
+        json
+    };
+    println!("  {} => {}", script, json);
+    println!("  Runaway loop => {}", cortex_lang::wasm::eval("while true {}"));
+    
     println!("\n=== All Tests Passed ===");
     println!("WASM demo completed successfully!");
     println!("\n📊 Size Optimizations Applied:");